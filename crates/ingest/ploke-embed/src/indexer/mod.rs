@@ -10,9 +10,10 @@ use crate::{config::CozoConfig, error::truncate_string};
 use cozo::{CallbackOp, DataValue, NamedRows};
 use ploke_core::EmbeddingData;
 use ploke_core::embeddings::EmbeddingSet;
+use ploke_db::multi_embedding::cache::snippet_hash;
 use ploke_db::{CallbackManager, Database, NodeType, TypedEmbedData, bm25_index};
 use ploke_io::IoManagerHandle;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
@@ -485,6 +486,7 @@ impl IndexerTask {
         self.db.put_embedding_set(&active_embedding_set)?;
         self.db
             .ensure_vector_embedding_relation(&active_embedding_set)?;
+        self.db.ensure_embedding_cache_relation()?;
 
         let num_not_proc = self.db.count_unembedded_nonfiles()?;
         tracing::info!("Starting indexing with {} unembedded nodes", num_not_proc);
@@ -851,30 +853,66 @@ impl IndexerTask {
 
         let active_set = self.embedding_runtime.current_active_set().ok();
         let runtime_dims = self.embedding_runtime.dimensions().ok();
-        let embed_snippets = valid_snippets.clone();
-        let embeddings = match self
-            .embedding_runtime
-            .generate_embeddings_with_cancel(
-                embed_snippets,
-                Some(&self.cancellation_token.listener()),
-            )
-            .await
-        {
-            Ok(embeddings) => embeddings,
-            Err(err) => {
-                record_embed_span_metadata(&embed_span, active_set.as_ref(), runtime_dims);
-                log_embedding_failure_context(&err, &valid_data, &valid_nodes, &valid_snippets);
-                return Err(err);
+        let dims = self.embedding_runtime.dimensions()?;
+
+        // Snippets already embedded with the active set (e.g. before a re-parse changed their
+        // node ids) are taken from the cache; only the rest go to the provider, once each.
+        let hashes: Vec<Uuid> = valid_snippets.iter().map(|s| snippet_hash(s)).collect();
+        let mut cached = match &active_set {
+            Some(set) => self.db.cached_embeddings(set, &hashes).unwrap_or_else(|e| {
+                tracing::warn!("Embedding cache lookup failed, embedding every snippet: {e}");
+                HashMap::new()
+            }),
+            None => HashMap::new(),
+        };
+        cached.retain(|_, embedding| embedding.len() == dims);
+        let mut queued = HashSet::new();
+        let (miss_hashes, embed_snippets): (Vec<Uuid>, Vec<String>) = hashes
+            .iter()
+            .zip(valid_snippets.iter())
+            .filter(|(hash, _)| !cached.contains_key(*hash) && queued.insert(**hash))
+            .map(|(hash, snippet)| (*hash, snippet.clone()))
+            .unzip();
+        tracing::debug!(
+            target: "embed-pipeline",
+            "embedding cache | reused: {}, to_embed: {}",
+            valid_snippets.len() - embed_snippets.len(),
+            embed_snippets.len()
+        );
+
+        let fresh = if embed_snippets.is_empty() {
+            Vec::new()
+        } else {
+            match self
+                .embedding_runtime
+                .generate_embeddings_with_cancel(
+                    embed_snippets,
+                    Some(&self.cancellation_token.listener()),
+                )
+                .await
+            {
+                Ok(embeddings) => embeddings,
+                Err(err) => {
+                    record_embed_span_metadata(&embed_span, active_set.as_ref(), runtime_dims);
+                    log_embedding_failure_context(&err, &valid_data, &valid_nodes, &valid_snippets);
+                    return Err(err);
+                }
             }
         };
         tracing::trace!(
             "Processed embeddings {} with dimension {:?}",
-            embeddings.len(),
-            embeddings.first().map(|v| v.len())
+            fresh.len(),
+            fresh.first().map(|v| v.len())
         );
 
-        let dims = self.embedding_runtime.dimensions()?;
-        for embedding in &embeddings {
+        if fresh.len() != miss_hashes.len() {
+            return Err(EmbedError::Embedding(format!(
+                "provider returned {} embeddings for {} snippets",
+                fresh.len(),
+                miss_hashes.len()
+            )));
+        }
+        for embedding in &fresh {
             if embedding.len() != dims {
                 return Err(EmbedError::DimensionMismatch {
                     expected: dims,
@@ -883,6 +921,22 @@ impl IndexerTask {
             }
         }
 
+        let fresh: Vec<(Uuid, Vec<f32>)> = miss_hashes.into_iter().zip(fresh).collect();
+        if let Some(set) = &active_set
+            && let Err(e) = self.db.put_cached_embeddings(set, fresh.clone())
+        {
+            tracing::warn!("Failed to cache {} embeddings: {e}", fresh.len());
+        }
+        cached.extend(fresh);
+        let embeddings = hashes
+            .iter()
+            .map(|hash| {
+                cached.get(hash).cloned().ok_or_else(|| {
+                    EmbedError::Embedding(format!("no embedding for snippet hash {hash}"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
         let updates = valid_data
             .into_iter()
             .zip(embeddings)
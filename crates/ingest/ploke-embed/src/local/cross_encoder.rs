@@ -0,0 +1,203 @@
+//! Local cross-encoder scoring of (query, passage) pairs, for reranking retrieved snippets.
+//!
+//! A cross-encoder reads the query and a passage together and outputs a single relevance logit,
+//! which is more accurate than comparing independently embedded vectors but needs one forward
+//! pass per candidate. Models are BERT sequence classifiers with a single output (e.g. the
+//! `cross-encoder/ms-marco-*` family), run on the CPU.
+use candle_core::{DType, Device, IndexOp, Tensor};
+use candle_nn::{Linear, Module, VarBuilder, linear};
+use candle_transformers::models::bert::{BertModel, Config};
+use hf_hub::{Repo, RepoType, api::sync::Api};
+use std::fmt;
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+use tracing::{instrument, trace};
+
+use super::{EmbeddingError, LocalEmbedder};
+use crate::utils::trace_consts::EMBEDDING_TRACE;
+
+#[derive(Debug, Clone)]
+pub struct CrossEncoderConfig {
+    pub model_id: String,
+    pub revision: Option<String>,
+    /// Number of pairs per forward pass.
+    pub model_batch_size: usize,
+    /// Maximum tokens of a (query, passage) pair; passages are truncated first.
+    pub max_length: usize,
+}
+
+impl Default for CrossEncoderConfig {
+    fn default() -> Self {
+        Self {
+            model_id: "cross-encoder/ms-marco-MiniLM-L-6-v2".to_string(),
+            revision: None,
+            model_batch_size: 8,
+            max_length: 512,
+        }
+    }
+}
+
+pub struct LocalCrossEncoder {
+    model: BertModel,
+    pooler: Linear,
+    classifier: Linear,
+    tokenizer: Tokenizer,
+    device: Device,
+    config: CrossEncoderConfig,
+}
+
+impl fmt::Debug for LocalCrossEncoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalCrossEncoder")
+            .field("device", &self.device)
+            .field("config", &self.config)
+            .field("model", &"BertModel { ... }")
+            .finish()
+    }
+}
+
+impl LocalCrossEncoder {
+    pub fn new(config: CrossEncoderConfig) -> Result<Self, EmbeddingError> {
+        let device = Device::Cpu;
+        let api = Api::new().map_err(EmbeddingError::ModelDownload)?;
+        let repo = match &config.revision {
+            Some(revision) => {
+                Repo::with_revision(config.model_id.clone(), RepoType::Model, revision.clone())
+            }
+            None => Repo::new(config.model_id.to_owned(), RepoType::Model),
+        };
+        let repo_api = api.repo(repo);
+
+        let config_path = repo_api.get("config.json")?;
+        let config_str = std::fs::read_to_string(&config_path)?;
+        let model_config: Config =
+            serde_json::from_str(&config_str).map_err(|e| EmbeddingError::Config(e.to_string()))?;
+
+        let tokenizer_path = repo_api.get("tokenizer.json")?;
+        LocalEmbedder::validate_file_size(&tokenizer_path, 1024)?;
+        let mut tokenizer =
+            Tokenizer::from_file(tokenizer_path).map_err(EmbeddingError::Tokenizer)?;
+        let max_length = config.max_length.min(model_config.max_position_embeddings);
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length,
+                stride: 0,
+                strategy: tokenizers::TruncationStrategy::OnlySecond,
+                direction: tokenizers::TruncationDirection::Right,
+            }))
+            .map_err(EmbeddingError::Tokenizer)?
+            .with_padding(Some(PaddingParams {
+                strategy: tokenizers::PaddingStrategy::BatchLongest,
+                ..Default::default()
+            }));
+
+        let weights_path = repo_api
+            .get("model.safetensors")
+            .or_else(|_| repo_api.get("pytorch_model.bin"))?;
+        LocalEmbedder::validate_file_size(&weights_path, 1024)?;
+        let vb = if weights_path.extension().and_then(|e| e.to_str()) == Some("safetensors") {
+            LocalEmbedder::load_safetensors(&weights_path, &device)?
+        } else {
+            VarBuilder::from_pth(&weights_path, DType::F32, &device)?
+        };
+
+        let model = BertModel::load(vb.clone(), &model_config)
+            .map_err(|e| EmbeddingError::ModelConfig(e.to_string()))?;
+        let hidden = model_config.hidden_size;
+        let pooler = linear(hidden, hidden, vb.pp("bert.pooler.dense"))
+            .map_err(|e| EmbeddingError::ModelConfig(format!("missing pooler: {e}")))?;
+        let classifier = linear(hidden, 1, vb.pp("classifier")).map_err(|e| {
+            EmbeddingError::ModelConfig(format!("not a single-output classifier: {e}"))
+        })?;
+
+        Ok(Self {
+            model,
+            pooler,
+            classifier,
+            tokenizer,
+            device,
+            config,
+        })
+    }
+
+    /// Relevance of each of `passages` to `query`, in `(0, 1)` (sigmoid of the model logit), in
+    /// the order of `passages`.
+    pub fn score(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>, EmbeddingError> {
+        if passages.is_empty() {
+            return Err(EmbeddingError::EmptyBatch);
+        }
+        let mut scores = Vec::with_capacity(passages.len());
+        for chunk in passages.chunks(self.config.model_batch_size.max(1)) {
+            scores.extend(self.score_batch(query, chunk)?);
+        }
+        Ok(scores)
+    }
+
+    #[instrument(skip(self, query, passages), fields(pairs = passages.len()), level = "DEBUG")]
+    fn score_batch(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>, EmbeddingError> {
+        let pairs: Vec<(&str, &str)> = passages.iter().map(|p| (query, *p)).collect();
+        let tokens = self.tokenizer.encode_batch(pairs, true)?;
+
+        let token_ids = self.stack(&tokens, |t| t.get_ids())?;
+        let token_type_ids = self.stack(&tokens, |t| t.get_type_ids())?;
+        let attention_mask = self.stack(&tokens, |t| t.get_attention_mask())?;
+
+        let outputs = self
+            .model
+            .forward(&token_ids, &token_type_ids, Some(&attention_mask))?;
+        // Classify the [CLS] token through the BERT pooler, as `BertForSequenceClassification`.
+        let cls = outputs.i((.., 0, ..))?;
+        let pooled = self.pooler.forward(&cls)?.tanh()?;
+        let logits = self.classifier.forward(&pooled)?.squeeze(1)?;
+        let scores = candle_nn::ops::sigmoid(&logits)?.to_vec1::<f32>()?;
+        trace!(target: EMBEDDING_TRACE, "cross-encoder scores: {:?}", scores);
+
+        if scores.len() != passages.len() {
+            return Err(EmbeddingError::Dimension(format!(
+                "expected {} scores, got {}",
+                passages.len(),
+                scores.len()
+            )));
+        }
+        Ok(scores)
+    }
+
+    /// Stacks one row per encoding, as selected by `field`, into a `(batch, seq)` tensor.
+    fn stack(
+        &self,
+        tokens: &[tokenizers::Encoding],
+        field: fn(&tokenizers::Encoding) -> &[u32],
+    ) -> Result<Tensor, EmbeddingError> {
+        let rows = tokens
+            .iter()
+            .map(|t| Tensor::new(field(t), &self.device))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Tensor::stack(&rows, 0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore = "downloads cross-encoder/ms-marco-MiniLM-L-6-v2 from Hugging Face"]
+    fn scores_relevant_passages_above_unrelated_ones() -> Result<(), EmbeddingError> {
+        let encoder = LocalCrossEncoder::new(CrossEncoderConfig {
+            model_batch_size: 2,
+            ..CrossEncoderConfig::default()
+        })?;
+        let passages = [
+            "fn parse_config(path: &Path) -> Result<Config, Error> { toml::from_str(&read(path)?) }",
+            "The weather in Lisbon is sunny for most of the year.",
+            "impl Drop for Connection { fn drop(&mut self) { self.close(); } }",
+        ];
+
+        // Three passages over batches of two go through the pooler and classifier twice.
+        let scores = encoder.score("how is the config file parsed", &passages)?;
+        assert_eq!(scores.len(), passages.len());
+        assert!(scores.iter().all(|s| *s > 0.0 && *s < 1.0), "{scores:?}");
+        assert!(scores[0] > scores[1], "{scores:?}");
+        assert!(scores[0] > scores[2], "{scores:?}");
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+pub mod cross_encoder;
+
 use candle_core::{DType, Device, Error as CandleError, IndexOp, Tensor, safetensors};
 use candle_nn::VarBuilder;
 use candle_transformers::models::bert::{BertModel, Config, HiddenAct};
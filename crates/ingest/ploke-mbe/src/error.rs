@@ -20,6 +20,12 @@ pub enum MbeError {
     #[error("invalid fragment specifier `{fragment}`")]
     InvalidFragment { fragment: String },
 
+    #[error("no rule of macro `{name}` matches the invocation")]
+    NoMatchingRule { name: String },
+
+    #[error("failed to transcribe macro `{name}`: {message}")]
+    Transcription { name: String, message: String },
+
     #[error("failed to parse expanded items: {message}")]
     StructuralParse { message: String },
 }
@@ -0,0 +1,347 @@
+use std::collections::{BTreeSet, HashMap};
+
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::parse::{ParseStream, Parser};
+
+use crate::error::MbeError;
+use crate::ir::{DeclarativeMacro, MetaTemplate, MetaVarKind, Op, RepeatKind};
+
+/// Fragments captured by a matcher, keyed by metavariable name.
+type Bindings = HashMap<String, Binding>;
+
+#[derive(Debug, Clone)]
+enum Binding {
+    /// Tokens captured by a metavariable outside of any repetition.
+    Fragment(TokenStream),
+    /// One binding per iteration of the enclosing repetition.
+    Repeated(Vec<Binding>),
+}
+
+/// Expands a `macro_rules!` invocation by trying each rule of `mac` in order.
+///
+/// `input` is the invocation body without its outer delimiters. Tokens written in the macro
+/// transcriber are given the `call_site` span, while captured fragments keep the spans they had in
+/// the invocation.
+///
+/// This is a best-effort expander: it does not implement hygiene, and fragment specifiers other
+/// than `ident`, `lifetime`, `literal`, `tt` and `block` are matched by parsing the remaining input
+/// with `syn`.
+pub fn expand_macro(
+    mac: &DeclarativeMacro,
+    input: TokenStream,
+    call_site: Span,
+) -> Result<TokenStream, MbeError> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+
+    for rule in &mac.rules {
+        let mut bindings = Bindings::new();
+        let mut pos = 0;
+        let matcher = strip_outer_subtree(&rule.matcher);
+        if !match_ops(matcher, &tokens, &mut pos, &mut bindings) || pos != tokens.len() {
+            continue;
+        }
+
+        let mut out = Vec::new();
+        transcribe(
+            strip_outer_subtree(&rule.transcriber),
+            &bindings,
+            call_site,
+            &mut out,
+        )
+        .map_err(|message| MbeError::Transcription {
+            name: mac.name.clone(),
+            message,
+        })?;
+        return Ok(out.into_iter().collect());
+    }
+
+    Err(MbeError::NoMatchingRule {
+        name: mac.name.clone(),
+    })
+}
+
+/// Rules are written as `(matcher) => { transcriber }`; the outer delimiters are not part of
+/// either side.
+fn strip_outer_subtree(template: &MetaTemplate) -> &[Op] {
+    match template.ops.as_slice() {
+        [Op::Subtree { tokens, .. }] => &tokens.ops,
+        ops => ops,
+    }
+}
+
+fn match_ops(ops: &[Op], tokens: &[TokenTree], pos: &mut usize, bindings: &mut Bindings) -> bool {
+    ops.iter().all(|op| match_op(op, tokens, pos, bindings))
+}
+
+fn match_op(op: &Op, tokens: &[TokenTree], pos: &mut usize, bindings: &mut Bindings) -> bool {
+    match op {
+        Op::Var { name, kind } => {
+            let kind = kind.as_ref().unwrap_or(&MetaVarKind::Tt);
+            let Some(len) = match_fragment(kind, &tokens[*pos..]) else {
+                return false;
+            };
+            let mut captured: TokenStream = tokens[*pos..*pos + len].iter().cloned().collect();
+            if matches!(kind, MetaVarKind::Expr | MetaVarKind::Expr2021) {
+                // Keep the captured expression together when it is substituted next to operators.
+                captured = TokenTree::Group(Group::new(Delimiter::None, captured)).into();
+            }
+            bindings.insert(name.clone(), Binding::Fragment(captured));
+            *pos += len;
+            true
+        }
+        Op::Repeat {
+            tokens: repeated,
+            separator,
+            kind,
+        } => {
+            let mut iterations: Vec<Bindings> = Vec::new();
+            loop {
+                let start = *pos;
+                if !iterations.is_empty()
+                    && let Some(separator) = separator
+                {
+                    match tokens.get(*pos) {
+                        Some(tt) if tt.to_string() == separator.text => *pos += 1,
+                        _ => break,
+                    }
+                }
+                let before_body = *pos;
+                let mut inner = Bindings::new();
+                if !match_ops(&repeated.ops, tokens, pos, &mut inner) || *pos == before_body {
+                    *pos = start;
+                    break;
+                }
+                iterations.push(inner);
+                if *kind == RepeatKind::ZeroOrOne {
+                    break;
+                }
+            }
+            if *kind == RepeatKind::OneOrMore && iterations.is_empty() {
+                return false;
+            }
+
+            for name in metavars(&repeated.ops) {
+                let per_iteration = iterations
+                    .iter()
+                    .filter_map(|iteration| iteration.get(&name).cloned())
+                    .collect();
+                bindings.insert(name, Binding::Repeated(per_iteration));
+            }
+            true
+        }
+        Op::Subtree {
+            delimiter,
+            tokens: inner,
+        } => match tokens.get(*pos) {
+            Some(TokenTree::Group(group)) if group.delimiter() == *delimiter => {
+                let group_tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+                let mut inner_pos = 0;
+                if match_ops(&inner.ops, &group_tokens, &mut inner_pos, bindings)
+                    && inner_pos == group_tokens.len()
+                {
+                    *pos += 1;
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        },
+        Op::Literal(text) => match tokens.get(*pos) {
+            Some(TokenTree::Literal(lit)) if lit.to_string() == *text => {
+                *pos += 1;
+                true
+            }
+            _ => false,
+        },
+        Op::Punct(ch, _) => match tokens.get(*pos) {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == *ch => {
+                *pos += 1;
+                true
+            }
+            _ => false,
+        },
+        Op::Ident(text) => match tokens.get(*pos) {
+            Some(TokenTree::Ident(ident)) if *ident == *text => {
+                *pos += 1;
+                true
+            }
+            _ => false,
+        },
+    }
+}
+
+/// Returns how many tokens at the start of `tokens` form a fragment of the given kind.
+fn match_fragment(kind: &MetaVarKind, tokens: &[TokenTree]) -> Option<usize> {
+    match kind {
+        MetaVarKind::Tt => (!tokens.is_empty()).then_some(1),
+        MetaVarKind::Ident => matches!(tokens.first(), Some(TokenTree::Ident(_))).then_some(1),
+        MetaVarKind::Lifetime => match tokens {
+            [TokenTree::Punct(tick), TokenTree::Ident(_), ..]
+                if tick.as_char() == '\'' && tick.spacing() == Spacing::Joint =>
+            {
+                Some(2)
+            }
+            _ => None,
+        },
+        MetaVarKind::Literal => match tokens {
+            [TokenTree::Literal(_), ..] => Some(1),
+            [TokenTree::Punct(minus), TokenTree::Literal(_), ..] if minus.as_char() == '-' => {
+                Some(2)
+            }
+            [TokenTree::Ident(ident), ..] if ident == "true" || ident == "false" => Some(1),
+            _ => None,
+        },
+        MetaVarKind::Block => match tokens.first() {
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => Some(1),
+            _ => None,
+        },
+        MetaVarKind::Expr | MetaVarKind::Expr2021 => parse_prefix::<syn::Expr>(tokens),
+        MetaVarKind::Item => parse_prefix::<syn::Item>(tokens),
+        MetaVarKind::Meta => parse_prefix::<syn::Meta>(tokens),
+        MetaVarKind::Path => parse_prefix::<syn::Path>(tokens),
+        MetaVarKind::Stmt => parse_prefix::<syn::Stmt>(tokens),
+        MetaVarKind::Ty => parse_prefix::<syn::Type>(tokens),
+        MetaVarKind::Vis => parse_prefix::<syn::Visibility>(tokens),
+        MetaVarKind::Pat => parse_prefix_with(tokens, syn::Pat::parse_multi_with_leading_vert),
+        MetaVarKind::PatParam => parse_prefix_with(tokens, syn::Pat::parse_single),
+    }
+}
+
+fn parse_prefix<T: syn::parse::Parse + ToTokens>(tokens: &[TokenTree]) -> Option<usize> {
+    parse_prefix_with(tokens, T::parse)
+}
+
+/// Parses a `T` from the front of `tokens` and returns the number of token trees it consumed.
+fn parse_prefix_with<T>(
+    tokens: &[TokenTree],
+    parse: fn(ParseStream) -> syn::Result<T>,
+) -> Option<usize> {
+    if tokens.is_empty() {
+        return None;
+    }
+    let rest = (|input: ParseStream| {
+        parse(input)?;
+        input.parse::<TokenStream>()
+    })
+    .parse2(tokens.iter().cloned().collect())
+    .ok()?;
+    Some(tokens.len() - rest.into_iter().count())
+}
+
+/// Collects the names of all metavariables bound within `ops`, including nested repetitions.
+fn metavars(ops: &[Op]) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    collect_metavars(ops, &mut names);
+    names
+}
+
+fn collect_metavars(ops: &[Op], names: &mut BTreeSet<String>) {
+    for op in ops {
+        match op {
+            Op::Var { name, .. } => {
+                names.insert(name.clone());
+            }
+            Op::Repeat { tokens, .. } | Op::Subtree { tokens, .. } => {
+                collect_metavars(&tokens.ops, names)
+            }
+            Op::Literal(_) | Op::Punct(..) | Op::Ident(_) => {}
+        }
+    }
+}
+
+fn transcribe(
+    ops: &[Op],
+    bindings: &Bindings,
+    call_site: Span,
+    out: &mut Vec<TokenTree>,
+) -> Result<(), String> {
+    for op in ops {
+        match op {
+            Op::Var { name, .. } => match bindings.get(name) {
+                Some(Binding::Fragment(tokens)) => out.extend(tokens.clone()),
+                Some(Binding::Repeated(_)) => {
+                    return Err(format!(
+                        "variable `${name}` is still repeating at this depth"
+                    ));
+                }
+                None if name == "crate" => out.push(Ident::new("crate", call_site).into()),
+                None => {
+                    // Not a metavariable of this rule, so `$name` is emitted verbatim.
+                    let mut dollar = Punct::new('$', Spacing::Alone);
+                    dollar.set_span(call_site);
+                    out.push(dollar.into());
+                    out.push(new_ident(name, call_site).into());
+                }
+            },
+            Op::Repeat {
+                tokens, separator, ..
+            } => {
+                let names = metavars(&tokens.ops);
+                let count = names
+                    .iter()
+                    .filter_map(|name| match bindings.get(name) {
+                        Some(Binding::Repeated(iterations)) => Some(iterations.len()),
+                        _ => None,
+                    })
+                    .max()
+                    .ok_or_else(|| {
+                        "repetition does not contain a repeating metavariable".to_string()
+                    })?;
+
+                for i in 0..count {
+                    if i > 0
+                        && let Some(separator) = separator
+                    {
+                        out.extend(respan(parse_text(&separator.text)?, call_site));
+                    }
+                    let mut frame = bindings.clone();
+                    for name in &names {
+                        if let Some(Binding::Repeated(iterations)) = bindings.get(name) {
+                            match iterations.get(i) {
+                                Some(binding) => frame.insert(name.clone(), binding.clone()),
+                                None => frame.remove(name),
+                            };
+                        }
+                    }
+                    transcribe(&tokens.ops, &frame, call_site, out)?;
+                }
+            }
+            Op::Subtree { delimiter, tokens } => {
+                let mut inner = Vec::new();
+                transcribe(&tokens.ops, bindings, call_site, &mut inner)?;
+                let mut group = Group::new(*delimiter, inner.into_iter().collect());
+                group.set_span(call_site);
+                out.push(group.into());
+            }
+            Op::Literal(text) => out.extend(respan(parse_text(text)?, call_site)),
+            Op::Punct(ch, spacing) => {
+                let mut punct = Punct::new(*ch, *spacing);
+                punct.set_span(call_site);
+                out.push(punct.into());
+            }
+            Op::Ident(text) => out.push(new_ident(text, call_site).into()),
+        }
+    }
+    Ok(())
+}
+
+fn new_ident(text: &str, span: Span) -> Ident {
+    match text.strip_prefix("r#") {
+        Some(raw) => Ident::new_raw(raw, span),
+        None => Ident::new(text, span),
+    }
+}
+
+fn parse_text(text: &str) -> Result<TokenStream, String> {
+    text.parse::<TokenStream>()
+        .map_err(|err| format!("invalid token `{text}`: {err}"))
+}
+
+fn respan(tokens: TokenStream, span: Span) -> impl Iterator<Item = TokenTree> {
+    tokens.into_iter().map(move |mut tt| {
+        tt.set_span(span);
+        tt
+    })
+}
@@ -1,4 +1,4 @@
-use proc_macro2::{Delimiter, TokenStream};
+use proc_macro2::{Delimiter, Spacing, TokenStream};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DeclarativeMacro {
@@ -33,7 +33,7 @@ pub enum Op {
         tokens: MetaTemplate,
     },
     Literal(String),
-    Punct(char),
+    Punct(char, Spacing),
     Ident(String),
 }
 
@@ -1,9 +1,11 @@
 mod error;
+mod expand;
 mod ir;
 mod parse;
 mod structural;
 
 pub use error::MbeError;
+pub use expand::expand_macro;
 pub use ir::{
     DeclarativeMacro, MacroInvocation, MetaTemplate, MetaVarKind, Op, RepeatKind, Rule, Separator,
 };
@@ -12,13 +14,21 @@ pub use structural::{StructuralItem, collect_structural_items, parse_expanded_it
 
 #[cfg(test)]
 mod tests {
-    use quote::quote;
+    use quote::{ToTokens, quote};
 
     use crate::{
-        MbeError, Op, RepeatKind, StructuralItem, collect_structural_items, parse_expanded_items,
-        parse_macro_rules_item,
+        MbeError, Op, RepeatKind, StructuralItem, collect_structural_items, expand_macro,
+        parse_expanded_items, parse_macro_rules_item,
     };
 
+    fn expand(definition: proc_macro2::TokenStream, input: proc_macro2::TokenStream) -> String {
+        let item: syn::ItemMacro = syn::parse2(definition).expect("macro_rules item should parse");
+        let mac = parse_macro_rules_item(&item).expect("macro should parse");
+        expand_macro(&mac, input, proc_macro2::Span::call_site())
+            .expect("invocation should expand")
+            .to_string()
+    }
+
     #[test]
     fn parses_simple_macro_rules_definition() {
         let item: syn::ItemMacro = syn::parse2(quote! {
@@ -103,4 +113,87 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn expands_first_matching_rule() {
+        let definition = quote! {
+            macro_rules! make_item {
+                (struct $name:ident) => { pub struct $name; };
+                (fn $name:ident) => { pub fn $name() {} };
+            }
+        };
+
+        assert_eq!(
+            expand(definition.clone(), quote! { struct Foo }),
+            quote! { pub struct Foo; }.to_string()
+        );
+        assert_eq!(
+            expand(definition, quote! { fn bar }),
+            quote! { pub fn bar() {} }.to_string()
+        );
+    }
+
+    #[test]
+    fn expands_repetitions_with_separators() {
+        let expanded = expand(
+            quote! {
+                macro_rules! make_consts {
+                    ($($name:ident = $value:expr),* $(,)?) => { $(pub const $name: u32 = $value;)* };
+                }
+            },
+            quote! { A = 1, B = 2 + 3, },
+        );
+
+        let items =
+            parse_expanded_items(expanded.parse().unwrap()).expect("expansion should parse");
+        let names: Vec<_> = items
+            .iter()
+            .map(|item| match item {
+                syn::Item::Const(item) => item.ident.to_string(),
+                other => panic!("expected const item, found `{}`", other.to_token_stream()),
+            })
+            .collect();
+        assert_eq!(names, ["A", "B"]);
+    }
+
+    #[test]
+    fn expands_nested_repetitions_and_dollar_crate() {
+        let expanded = expand(
+            quote! {
+                macro_rules! impl_all {
+                    ($($ty:ty => [$($method:ident),*]);*) => {
+                        $(impl $ty { $(fn $method(&self) -> $crate::Out { todo!() })* })*
+                    };
+                }
+            },
+            quote! { Foo => [a, b]; Bar<u8> => [c] },
+        );
+
+        let items =
+            parse_expanded_items(expanded.parse().unwrap()).expect("expansion should parse");
+        let method_counts: Vec<_> = items
+            .iter()
+            .map(|item| match item {
+                syn::Item::Impl(item) => item.items.len(),
+                other => panic!("expected impl item, found `{}`", other.to_token_stream()),
+            })
+            .collect();
+        assert_eq!(method_counts, [2, 1]);
+        assert!(expanded.contains("crate :: Out"));
+    }
+
+    #[test]
+    fn reports_invocations_without_matching_rule() {
+        let item: syn::ItemMacro = syn::parse2(quote! {
+            macro_rules! only_idents {
+                ($name:ident) => { struct $name; };
+            }
+        })
+        .expect("macro_rules item should parse");
+        let mac = parse_macro_rules_item(&item).expect("macro should parse");
+
+        let err = expand_macro(&mac, quote! { 1 + 2 }, proc_macro2::Span::call_site())
+            .expect_err("literal input should not match");
+        assert!(matches!(err, MbeError::NoMatchingRule { .. }));
+    }
 }
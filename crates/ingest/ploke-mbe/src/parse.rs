@@ -154,7 +154,7 @@ fn parse_op(
         }),
         TokenTree::Ident(ident) => Ok(Op::Ident(ident.to_string())),
         TokenTree::Literal(literal) => Ok(Op::Literal(literal.to_string())),
-        TokenTree::Punct(punct) => Ok(Op::Punct(punct.as_char())),
+        TokenTree::Punct(punct) => Ok(Op::Punct(punct.as_char(), punct.spacing())),
     }
 }
 
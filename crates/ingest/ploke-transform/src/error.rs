@@ -20,6 +20,9 @@ pub enum TransformError {
 
     #[error("Data transformation failed: {0}")]
     Transformation(String),
+
+    #[error("Namespace {0} holds an indexed dependency and is read-only")]
+    ReadOnlyNamespace(uuid::Uuid),
 }
 
 impl From<cozo::Error> for TransformError {
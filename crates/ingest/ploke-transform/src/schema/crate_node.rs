@@ -24,3 +24,16 @@ define_schema!(WorkspaceMetadataSchema {
     exclude: "[String]?",
     package_version: "String?",
 });
+
+// Marks a namespace as the read-only, public API of a dependency indexed from its sources (see
+// `syn_parser::parse_dependency_sources`). `id` is the namespace of the dependency's
+// `crate_context` row.
+define_schema!(DependencyNamespaceSchema {
+    "dependency_namespace",
+    id: "Uuid",
+    name: "String",
+    version: "String",
+    source: "String",
+    origin: "String",
+    root_path: "String",
+});
@@ -5,9 +5,10 @@
 //! 2. Semantic relations - higher-level logical relationships derived from syntactic ones
 
 use crate::define_schema;
+use crate::transform::ScriptRunner;
 
 use super::*;
-use cozo::{Db, MemStorage};
+use cozo::Db;
 use itertools::Itertools;
 use std::collections::BTreeMap;
 use syn_parser::parser::nodes::ToCozoUuid;
@@ -24,6 +25,114 @@ define_schema!(SyntacticRelationSchema {
     target_kind: "String"
 });
 
+// Call sites recorded in function and method bodies, resolved or not.
+//
+// Resolved call sites also produce a `Calls` row in `syntax_edge`; this relation keeps the
+// per-site detail, including the written path of calls that could not be resolved.
+//
+// Fields:
+// - id: UUID v5 of the caller id and the byte span of the call expression.
+// - owner_id: UUID of the calling function or method.
+// - kind: Syntactic form of the call (`Function`, `AssociatedFunction`, `SelfMethod`, `Method`,
+//   `Macro`).
+// - path: Path segments as written, e.g. `["Self", "new"]` or `["len"]` for method calls.
+// - span: Byte span of the call expression in the caller's file.
+// - resolved_id: UUID of the crate-local callee, if the call was resolved.
+define_schema!(CallSiteSchema {
+    "call_site",
+    id: "Uuid",
+    owner_id: "Uuid",
+    kind: "String",
+    path: "[String]",
+    span: "[Int; 2]",
+    resolved_id: "Uuid?",
+});
+
+// Item-position invocations of crate-local `macro_rules!` macros that were expanded.
+//
+// Each generated item that survived pruning also has an `ExpandsTo` row in `syntax_edge` when the
+// defining macro is known; this relation keeps the invocation itself.
+//
+// Fields:
+// - id: UUID v5 of the module id, macro name and byte span of the invocation.
+// - owner_id: UUID of the module containing the invocation.
+// - macro_name: Name of the expanded macro.
+// - invocation_path: Path of the invocation as written, e.g. `["crate", "make_newtype"]`.
+// - span: Byte span of the invocation in the module's file.
+// - macro_id: UUID of the defining `macro_rules!` node, if known.
+// - generated_ids: UUIDs of the primary items generated by the expansion.
+define_schema!(MacroExpansionSchema {
+    "macro_expansion",
+    id: "Uuid",
+    owner_id: "Uuid",
+    macro_name: "String",
+    invocation_path: "[String]",
+    span: "[Int; 2]",
+    macro_id: "Uuid?",
+    generated_ids: "[Uuid]",
+});
+
+// Test functions and doc-tests of the crate.
+//
+// Test functions keep their `function` row; this relation records what makes them a test. The
+// items a test exercises are in `test_target`.
+//
+// Fields:
+// - id: UUID v5 of the owner id and the position of the doc-test, if any.
+// - owner_id: UUID of the test function, or of the documented item for doc-tests.
+// - name: Name of the test function, or `<item> (doctest <n>)`.
+// - kind: How the test is run (`Unit`, `Integration`, `Doc`, `Bench`).
+// - module_id: UUID of the module containing the owner.
+// - file_path: File containing the owner.
+// - span: Byte span of the owner in its file.
+// - doctest_index: Position of a doc-test among the code blocks of its item's documentation.
+// - code: Code of a doc-test, without the fence lines.
+// - ignored: Whether the test is `#[ignore]`d or its doc-test fence is marked `ignore`.
+define_schema!(TestSchema {
+    "test",
+    id: "Uuid",
+    owner_id: "Uuid",
+    name: "String",
+    kind: "String",
+    module_id: "Uuid",
+    file_path: "String",
+    span: "[Int; 2]",
+    doctest_index: "Int?",
+    code: "String?",
+    ignored: "Bool",
+});
+
+// Items exercised by a test.
+//
+// Fields:
+// - source_id: UUID of the `test` row.
+// - target_id: UUID of the exercised item.
+// - coverage: Why the test is linked to the item (`Call`, `EnclosingModule`, `Documented`).
+define_schema!(TestTargetSchema {
+    "test_target",
+    source_id: "Uuid",
+    target_id: "Uuid",
+    coverage: "String",
+});
+
+// Impls synthesized from `#[derive(...)]` attributes.
+//
+// The impl itself has an `impl` row, with the derive path as written for its trait type; this
+// relation records which derive produced it.
+//
+// Fields:
+// - id: UUID of the `impl` row.
+// - macro_path: Path of the derive macro as written, e.g. `["Serialize"]`.
+// - resolved_path: `macro_path` resolved through the imports of the containing module.
+// - trait_path: Path of the implemented trait, e.g. `["core", "hash", "Hash"]`.
+define_schema!(DerivedImplSchema {
+    "derived_impl",
+    id: "Uuid",
+    macro_path: "[String]",
+    resolved_path: "[String]?",
+    trait_path: "[String]?",
+});
+
 impl SyntacticRelationSchema {
     /// Transforms a SyntacticRelation into parameters for database insertion
     pub fn relation_to_params(
@@ -51,7 +160,15 @@ impl SyntacticRelationSchema {
             SyntacticRelation::EnumVariant { .. } => ("Enum", "Variant"),
             SyntacticRelation::ImplAssociatedItem { .. } => ("Impl", "AssociatedItem"),
             SyntacticRelation::TraitAssociatedItem { .. } => ("Trait", "AssociatedItem"),
+            SyntacticRelation::ImplementsTrait { .. } => ("Impl", "Trait"),
+            SyntacticRelation::ImplFor { .. } => ("Impl", "Primary"),
+            SyntacticRelation::ImplementsTraitMethod { .. } => ("Method", "Method"),
             SyntacticRelation::ImportedBy { .. } => ("Primary", "Import"),
+            SyntacticRelation::Calls { .. } => ("Callable", "Callable"),
+            SyntacticRelation::ExpandsTo { .. } => ("Macro", "Primary"),
+            SyntacticRelation::UsesType { .. } => ("TypeConsumer", "Primary"),
+            SyntacticRelation::RequiresTrait { .. } => ("GenericOwner", "Trait"),
+            SyntacticRelation::SuperTrait { .. } => ("Trait", "Trait"),
         };
         // ANCHOR_END: impl_trait_associated_edges
 
@@ -76,7 +193,7 @@ impl SyntacticRelationSchema {
     /// Inserts a syntactic relation into the database
     pub fn insert_relation(
         &self,
-        db: &Db<MemStorage>,
+        db: &impl ScriptRunner,
         relation: &SyntacticRelation,
     ) -> Result<(), TransformError> {
         let params = self.relation_to_params(relation);
@@ -99,7 +216,7 @@ impl SemanticRelationSchema {
     /// Derives a semantic "contains" relation from syntactic relations
     pub fn derive_contains_relation<T, S>(
         &self,
-        db: &Db<MemStorage>,
+        db: &Db<impl CozoStorage>,
         source_id: T,
         target_id: S,
         // TODO: Consider seriously whether each edge should have its own hashed ID. This seems
@@ -156,7 +273,7 @@ impl SemanticRelationSchema {
     /// Derives a semantic "defines" relation from syntactic relations
     pub fn derive_defines_relation(
         &self,
-        db: &Db<MemStorage>,
+        db: &Db<impl CozoStorage>,
         source_id: uuid::Uuid,
         target_id: uuid::Uuid,
         syntactic_ids: Vec<uuid::Uuid>,
@@ -196,7 +313,7 @@ impl SemanticRelationSchema {
     /// Derives a semantic "uses" relation from syntactic relations
     pub fn derive_uses_relation(
         &self,
-        db: &Db<MemStorage>,
+        db: &Db<impl CozoStorage>,
         source_id: uuid::Uuid,
         target_id: uuid::Uuid,
         syntactic_ids: Vec<uuid::Uuid>,
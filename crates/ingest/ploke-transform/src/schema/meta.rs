@@ -15,3 +15,88 @@ define_schema!(Bm25MetaSchema {
     tokenizer_version: "String",
     token_length: "Int",
 });
+
+// Sparse BM25 vector of each indexed document, so the index can be restored without a rebuild.
+// The vector of a document depends on the `avgdl` it was embedded with, see `bm25_index_stats`.
+//
+// Fields:
+// - id: UUID of the node/document.
+// - namespace: UUID of the namespace (crate) holding the node.
+// - token_ids: Token ids of the vector, as produced by the tokenizer's embedder.
+// - token_weights: BM25 weight of each token id, in the same order.
+define_schema!(Bm25PostingsSchema {
+    "bm25_doc_postings",
+    id: "Uuid",
+    namespace: "Uuid",
+    token_ids: "[Int]",
+    token_weights: "[Float]",
+});
+
+// Parameters of the BM25 index, per namespace.
+//
+// Fields:
+// - id: UUID of the namespace.
+// - tokenizer_version: Tokenizer/version tag the postings of the namespace were produced with.
+//   Postings of another version are stale and the index is rebuilt instead of restored.
+// - avgdl: Average document length the postings were embedded with.
+// - doc_count: Number of persisted documents of the namespace, as of its last upsert.
+// - total_len: Sum of the token lengths of those documents.
+define_schema!(Bm25IndexStatsSchema {
+    "bm25_index_stats",
+    id: "Uuid",
+    tokenizer_version: "String",
+    avgdl: "Float",
+    doc_count: "Int",
+    total_len: "Float",
+});
+
+// A snapshot of the code graph, tagged with a git commit or an index run (see
+// `transform::snapshot`). `started_at` and `recorded_at` are validity timestamps in microseconds:
+// the graph as of the snapshot is read with `@ recorded_at`, and the rows asserted or retracted by
+// the run that produced it have validities in `(started_at, recorded_at]`.
+//
+// Fields:
+// - id: UUID v5 of `kind` and `tag`, so recording the same tag again supersedes the older row.
+// - kind: "commit" or "run".
+// - tag: The commit hash or the run id.
+define_schema!(GraphSnapshotSchema {
+    "graph_snapshot",
+    id: "Uuid",
+    kind: "String",
+    tag: "String",
+    started_at: "Int",
+    recorded_at: "Int",
+});
+
+// Content-addressed embedding vectors, so a snippet embedded again with the same embedding set is
+// not sent to the provider twice. Unlike the vector relations it is not keyed by node id, and
+// survives re-parses that change node ids.
+//
+// Fields:
+// - id: UUID v5 of `embedding_set_id` and `snippet_hash`.
+// - embedding_set_id: Hash id of the embedding set (provider, model and shape) of the vector.
+// - snippet_hash: UUID v5 of the embedded snippet text.
+// - vector: The embedding of the snippet.
+define_schema!(EmbeddingCacheSchema {
+    "embedding_cache",
+    id: "Uuid",
+    embedding_set_id: "Int",
+    snippet_hash: "Uuid",
+    vector: "[Float]",
+});
+
+// Schema migrations applied to the database, see `ploke_db::migration`. The schema version of the
+// database is the highest `version`; databases without the relation are at version 0.
+//
+// Fields:
+// - id: UUID v5 of `name`.
+// - version: Schema version the migration upgrades the database to.
+// - name: Name of the migration.
+// - applied_at: When the migration was applied, in microseconds since the Unix epoch.
+define_schema!(SchemaMigrationSchema {
+    "schema_migration",
+    id: "Uuid",
+    version: "Int",
+    name: "String",
+    applied_at: "Int",
+});
@@ -51,11 +51,17 @@ use compilation_unit::{
     CompilationUnitEnabledEdgeSchema, CompilationUnitEnabledFileSchema,
     CompilationUnitEnabledNodeSchema, CompilationUnitMetaSchema, CompilationUnitSchema,
 };
-use cozo::{Db, MemStorage, ScriptMutability};
-use crate_node::{CrateContextSchema, WorkspaceMetadataSchema};
-use edges::SyntacticRelationSchema;
+use cozo::{DataValue, Db, ScriptMutability, Storage};
+use crate_node::{CrateContextSchema, DependencyNamespaceSchema, WorkspaceMetadataSchema};
+use edges::{
+    CallSiteSchema, DerivedImplSchema, MacroExpansionSchema, SyntacticRelationSchema, TestSchema,
+    TestTargetSchema,
+};
 use itertools::Itertools;
-use meta::Bm25MetaSchema;
+use meta::{
+    Bm25IndexStatsSchema, Bm25MetaSchema, Bm25PostingsSchema, EmbeddingCacheSchema,
+    GraphSnapshotSchema, SchemaMigrationSchema,
+};
 use secondary_nodes::*;
 use std::collections::BTreeMap;
 use subnode_variants::FileModuleNodeSchema;
@@ -86,9 +92,18 @@ impl CozoField {
     }
 }
 
+/// A Cozo storage engine, such as the in-memory [`cozo::MemStorage`] or the persistent
+/// [`cozo::SqliteStorage`], that can back a [`Db`] borrowed for any lifetime.
+///
+/// Lets the schema functions run against any engine without spelling out the higher-ranked
+/// bound on `Storage<'s>`.
+pub trait CozoStorage: for<'s> Storage<'s> {}
+
+impl<S> CozoStorage for S where S: for<'s> Storage<'s> {}
+
 /// Create schema for all nodes and enter them into the database.
 /// This step must only be run once to avoid errors from the database.
-pub fn create_schema_all(db: &Db<MemStorage>) -> Result<(), crate::error::TransformError> {
+pub fn create_schema_all(db: &Db<impl CozoStorage>) -> Result<(), crate::error::TransformError> {
     // -- secondary nodes --
     ParamNodeSchema::create_and_insert_schema(db)?;
     AttributeNodeSchema::create_and_insert_schema(db)?;
@@ -119,6 +134,11 @@ pub fn create_schema_all(db: &Db<MemStorage>) -> Result<(), crate::error::Transf
 
     // -- edges --
     SyntacticRelationSchema::create_and_insert_schema(db)?;
+    CallSiteSchema::create_and_insert_schema(db)?;
+    MacroExpansionSchema::create_and_insert_schema(db)?;
+    TestSchema::create_and_insert_schema(db)?;
+    TestTargetSchema::create_and_insert_schema(db)?;
+    DerivedImplSchema::create_and_insert_schema(db)?;
 
     // -- crate_context --
     CrateContextSchema::create_and_insert_schema(db)?;
@@ -133,10 +153,12 @@ pub fn create_schema_all(db: &Db<MemStorage>) -> Result<(), crate::error::Transf
 
     // -- bm25_doc_meta --
     Bm25MetaSchema::create_and_insert_schema(db)?;
+    Bm25PostingsSchema::create_and_insert_schema(db)?;
+    Bm25IndexStatsSchema::create_and_insert_schema(db)?;
     Ok(())
 }
 
-fn list_stored_relations(db: &Db<MemStorage>) -> Result<Vec<String>, TransformError> {
+fn list_stored_relations(db: &Db<impl CozoStorage>) -> Result<Vec<String>, TransformError> {
     let output = db.run_script("::relations", BTreeMap::new(), ScriptMutability::Immutable)?;
     Ok(Vec::from_iter(output.rows.into_iter().filter_map(|r| {
         r.first()
@@ -147,7 +169,7 @@ fn list_stored_relations(db: &Db<MemStorage>) -> Result<Vec<String>, TransformEr
 }
 
 /// Create `compilation_unit*` relations if missing (e.g. after loading a backup that predates them).
-pub fn ensure_compilation_unit_relations(db: &Db<MemStorage>) -> Result<(), TransformError> {
+pub fn ensure_compilation_unit_relations(db: &Db<impl CozoStorage>) -> Result<(), TransformError> {
     let rels = list_stored_relations(db)?;
     let has = |name: &str| rels.iter().any(|r| r == name);
     if !has(CompilationUnitSchema::SCHEMA.relation) {
@@ -168,6 +190,121 @@ pub fn ensure_compilation_unit_relations(db: &Db<MemStorage>) -> Result<(), Tran
     Ok(())
 }
 
+/// Create the `call_site`, `macro_expansion`, `test`, `test_target` and `derived_impl` relations
+/// if missing (e.g. after loading a backup that predates them).
+pub fn ensure_parse_detail_relations(db: &Db<impl CozoStorage>) -> Result<(), TransformError> {
+    let rels = list_stored_relations(db)?;
+    let has = |name: &str| rels.iter().any(|r| r == name);
+    if !has(CallSiteSchema::SCHEMA.relation) {
+        CallSiteSchema::create_and_insert_schema(db)?;
+    }
+    if !has(MacroExpansionSchema::SCHEMA.relation) {
+        MacroExpansionSchema::create_and_insert_schema(db)?;
+    }
+    if !has(TestSchema::SCHEMA.relation) {
+        TestSchema::create_and_insert_schema(db)?;
+    }
+    if !has(TestTargetSchema::SCHEMA.relation) {
+        TestTargetSchema::create_and_insert_schema(db)?;
+    }
+    if !has(DerivedImplSchema::SCHEMA.relation) {
+        DerivedImplSchema::create_and_insert_schema(db)?;
+    }
+    Ok(())
+}
+
+/// Create the `dependency_namespace` relation if missing. It is only created once a dependency
+/// is indexed, so databases and backups without dependencies never carry it.
+pub fn ensure_dependency_namespace_relation(
+    db: &Db<impl CozoStorage>,
+) -> Result<(), TransformError> {
+    let rels = list_stored_relations(db)?;
+    if !rels
+        .iter()
+        .any(|r| r == DependencyNamespaceSchema::SCHEMA.relation)
+    {
+        DependencyNamespaceSchema::create_and_insert_schema(db)?;
+    }
+    Ok(())
+}
+
+/// Whether `namespace` holds an indexed dependency. Such namespaces are read-only: only
+/// [`transform_parsed_dependency`](crate::transform::transform_parsed_dependency) writes them.
+pub fn is_dependency_namespace(
+    db: &Db<impl CozoStorage>,
+    namespace: uuid::Uuid,
+) -> Result<bool, TransformError> {
+    let rels = list_stored_relations(db)?;
+    if !rels
+        .iter()
+        .any(|r| r == DependencyNamespaceSchema::SCHEMA.relation)
+    {
+        return Ok(false);
+    }
+    let rows = db.run_script(
+        "?[id] := *dependency_namespace { id @ 'NOW' }, id = $namespace",
+        BTreeMap::from([(
+            "namespace".to_string(),
+            DataValue::Uuid(cozo::UuidWrapper(namespace)),
+        )]),
+        ScriptMutability::Immutable,
+    )?;
+    Ok(!rows.rows.is_empty())
+}
+
+/// Create the `bm25_doc_postings` and `bm25_index_stats` relations if missing (e.g. after loading
+/// a backup that predates them).
+pub fn ensure_bm25_index_relations(db: &Db<impl CozoStorage>) -> Result<(), TransformError> {
+    let rels = list_stored_relations(db)?;
+    let has = |name: &str| rels.iter().any(|r| r == name);
+    if !has(Bm25PostingsSchema::SCHEMA.relation) {
+        Bm25PostingsSchema::create_and_insert_schema(db)?;
+    }
+    if !has(Bm25IndexStatsSchema::SCHEMA.relation) {
+        Bm25IndexStatsSchema::create_and_insert_schema(db)?;
+    }
+    Ok(())
+}
+
+/// Create the `graph_snapshot` relation if missing. It is only created once a snapshot is
+/// recorded, so databases and backups without snapshots never carry it.
+pub fn ensure_graph_snapshot_relation(db: &Db<impl CozoStorage>) -> Result<(), TransformError> {
+    let rels = list_stored_relations(db)?;
+    if !rels
+        .iter()
+        .any(|r| r == GraphSnapshotSchema::SCHEMA.relation)
+    {
+        GraphSnapshotSchema::create_and_insert_schema(db)?;
+    }
+    Ok(())
+}
+
+/// Create the `embedding_cache` relation if missing. Like the vector relations it is only created
+/// once something is embedded.
+pub fn ensure_embedding_cache_relation(db: &Db<impl CozoStorage>) -> Result<(), TransformError> {
+    let rels = list_stored_relations(db)?;
+    if !rels
+        .iter()
+        .any(|r| r == EmbeddingCacheSchema::SCHEMA.relation)
+    {
+        EmbeddingCacheSchema::create_and_insert_schema(db)?;
+    }
+    Ok(())
+}
+
+/// Create the `schema_migration` relation if missing. It is created by the first migration run, so
+/// older backups do not carry it.
+pub fn ensure_schema_migration_relation(db: &Db<impl CozoStorage>) -> Result<(), TransformError> {
+    let rels = list_stored_relations(db)?;
+    if !rels
+        .iter()
+        .any(|r| r == SchemaMigrationSchema::SCHEMA.relation)
+    {
+        SchemaMigrationSchema::create_and_insert_schema(db)?;
+    }
+    Ok(())
+}
+
 pub const ID_KEYWORDS: [&str; 8] = [
     "id",
     "function_id",
@@ -283,6 +420,23 @@ macro_rules! define_schema {
                 script
             }
 
+            /// Like [`Self::script_put`], for many rows at once bound to `$rows`. Each row lists
+            /// its values in the order of the keys of `params`, the params of any one of the rows.
+            pub fn script_put_rows(&self, params: &BTreeMap<String, cozo::DataValue>) -> String {
+                let columns = params.keys().join(", ");
+                let lhs_keys = params.keys()
+                        .filter(|k| ID_KEYWORDS.contains(&k.as_str()))
+                        .join(", ");
+                let lhs_entries = params.keys()
+                        .filter(|k| !ID_KEYWORDS.contains(&k.as_str()))
+                        .join(", ");
+                format!(
+                    "input[{columns}] <- $rows\n\
+                    ?[{lhs_keys}, at, {lhs_entries}] := input[{columns}], at = 'ASSERT' :put {}",
+                    self.script_identity()
+                )
+            }
+
             pub fn log_create_script(&self) {
                 tracing::trace!(target: "db",
                     "{} {}: {:?}",
@@ -294,7 +448,7 @@ macro_rules! define_schema {
 
             pub(crate) fn create_and_insert(
                 &self,
-                db: &Db<MemStorage>,
+                db: &Db<impl $crate::schema::CozoStorage>,
             ) -> Result<(), TransformError> {
                 let const_schema = Self::SCHEMA;
                 let db_result = db.run_script(
@@ -307,7 +461,7 @@ macro_rules! define_schema {
                 Ok(())
             }
 
-            pub(crate) fn create_and_insert_schema(db: &Db<MemStorage>) -> Result<(), TransformError> {
+            pub(crate) fn create_and_insert_schema(db: &Db<impl $crate::schema::CozoStorage>) -> Result<(), TransformError> {
                 let schema = &Self::SCHEMA;
                 let script_create = &schema.script_create();
                 schema.log_create_script();
@@ -324,7 +478,9 @@ macro_rules! define_schema {
 }
 
 /// Helper function to create and insert the three types of generics at once.
-pub(crate) fn create_and_insert_generic_schema(db: &Db<MemStorage>) -> Result<(), TransformError> {
+pub(crate) fn create_and_insert_generic_schema(
+    db: &Db<impl CozoStorage>,
+) -> Result<(), TransformError> {
     GenericTypeNodeSchema::create_and_insert_schema(db)?;
     GenericConstNodeSchema::create_and_insert_schema(db)?;
     GenericLifetimeNodeSchema::create_and_insert_schema(db)?;
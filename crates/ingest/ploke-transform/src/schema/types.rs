@@ -4,7 +4,7 @@ use crate::define_schema;
 /// Helper function to insert all currnetly handled types' relations into the cozo database at
 /// once. Logs the created script inside the `create_and_insert` function using target: "db" on
 /// "info"
-pub fn create_and_insert_types(db: &Db<MemStorage>) -> Result<(), TransformError> {
+pub fn create_and_insert_types(db: &Db<impl CozoStorage>) -> Result<(), TransformError> {
     NamedTypeSchema::SCHEMA.create_and_insert(db)?;
     ReferenceTypeSchema::SCHEMA.create_and_insert(db)?;
     SliceTypeSchema::SCHEMA.create_and_insert(db)?;
@@ -0,0 +1,171 @@
+//! Collecting the writes of a transform to apply them in one transaction.
+//!
+//! The transforms write through a [`ScriptRunner`]: a [`Db`] runs each script right away, while a
+//! [`WriteBatch`] collects them, so retractions and insertions can be chained into one Cozo script
+//! that either takes effect as a whole or not at all.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use cozo::{DataValue, Db, NamedRows, ScriptMutability};
+
+use crate::schema::CozoStorage;
+
+/// Runs the mutating scripts of the transforms.
+pub trait ScriptRunner {
+    fn run_script(
+        &self,
+        script: &str,
+        params: BTreeMap<String, DataValue>,
+        mutability: ScriptMutability,
+    ) -> Result<NamedRows, cozo::Error>;
+}
+
+impl<S: CozoStorage> ScriptRunner for Db<S> {
+    fn run_script(
+        &self,
+        script: &str,
+        params: BTreeMap<String, DataValue>,
+        mutability: ScriptMutability,
+    ) -> Result<NamedRows, cozo::Error> {
+        Db::run_script(self, script, params, mutability)
+    }
+}
+
+/// Mutating scripts collected to run as one chained Cozo script, which Cozo applies in a single
+/// transaction: either every script takes effect or none does.
+#[derive(Debug, Default)]
+pub struct WriteBatch {
+    scripts: RefCell<Vec<(String, BTreeMap<String, DataValue>)>>,
+}
+
+impl WriteBatch {
+    /// Adds `script`, to run after the scripts already in the batch.
+    pub fn push(&self, script: &str, params: BTreeMap<String, DataValue>) {
+        self.scripts.borrow_mut().push((script.to_string(), params));
+    }
+
+    /// Adds the scripts of `other`, to run after the scripts already in the batch.
+    pub fn append(&self, other: WriteBatch) {
+        self.scripts.borrow_mut().extend(other.scripts.into_inner());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scripts.borrow().is_empty()
+    }
+
+    /// Runs the batch as one transaction. The parameters of each script are renamed so they
+    /// cannot clash with those of other scripts.
+    pub fn run(self, db: &Db<impl CozoStorage>) -> Result<(), cozo::Error> {
+        let scripts = self.scripts.into_inner();
+        if scripts.is_empty() {
+            return Ok(());
+        }
+        let mut blocks = Vec::with_capacity(scripts.len());
+        let mut all_params = BTreeMap::new();
+        for (i, (script, params)) in scripts.into_iter().enumerate() {
+            let prefix = format!("b{i}_");
+            let mut block = String::with_capacity(script.len());
+            let mut rest = script.as_str();
+            while let Some(pos) = rest.find('$') {
+                block.push_str(&rest[..=pos]);
+                rest = &rest[pos + 1..];
+                let end = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                if params.contains_key(&rest[..end]) {
+                    block.push_str(&prefix);
+                }
+            }
+            block.push_str(rest);
+            all_params.extend(
+                params
+                    .into_iter()
+                    .map(|(name, value)| (format!("{prefix}{name}"), value)),
+            );
+            blocks.push(format!("{{\n{block}\n}}"));
+        }
+        db.run_script(&blocks.join("\n"), all_params, ScriptMutability::Mutable)
+            .map(|_| ())
+    }
+}
+
+impl ScriptRunner for WriteBatch {
+    /// Adds `script` to the batch. Only mutating scripts can be batched; their rows are not
+    /// returned.
+    fn run_script(
+        &self,
+        script: &str,
+        params: BTreeMap<String, DataValue>,
+        mutability: ScriptMutability,
+    ) -> Result<NamedRows, cozo::Error> {
+        debug_assert!(
+            matches!(mutability, ScriptMutability::Mutable),
+            "only mutating scripts can be batched"
+        );
+        self.push(script, params);
+        Ok(NamedRows {
+            headers: Vec::new(),
+            rows: Vec::new(),
+            next: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use cozo::{DataValue, Db, MemStorage, ScriptMutability};
+
+    use super::WriteBatch;
+
+    fn names(db: &Db<MemStorage>) -> Vec<DataValue> {
+        db.run_script(
+            "?[name] := *item { name }",
+            BTreeMap::new(),
+            ScriptMutability::Immutable,
+        )
+        .expect("query names")
+        .rows
+        .into_iter()
+        .map(|mut row| row.remove(0))
+        .collect()
+    }
+
+    #[test]
+    fn failing_batch_writes_nothing() {
+        let db = Db::new(MemStorage::default()).expect("create database");
+        db.initialize().expect("initialize database");
+        db.run_script(
+            ":create item { name: String }",
+            BTreeMap::new(),
+            ScriptMutability::Mutable,
+        )
+        .expect("create relation");
+
+        let batch = WriteBatch::default();
+        for name in ["a", "b"] {
+            batch.push(
+                "?[name] <- [[$name]]\n:put item { name }",
+                BTreeMap::from([("name".to_string(), DataValue::from(name))]),
+            );
+        }
+        batch.push(
+            "?[name] := *missing { name }\n:put item { name }",
+            BTreeMap::new(),
+        );
+        assert!(batch.run(&db).is_err());
+        assert!(names(&db).is_empty());
+
+        let batch = WriteBatch::default();
+        for name in ["a", "b"] {
+            batch.push(
+                "?[name] <- [[$name]]\n:put item { name }",
+                BTreeMap::from([("name".to_string(), DataValue::from(name))]),
+            );
+        }
+        batch.run(&db).expect("run batch");
+        assert_eq!(names(&db), vec![DataValue::from("a"), DataValue::from("b")]);
+    }
+}
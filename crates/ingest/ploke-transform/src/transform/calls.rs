@@ -0,0 +1,108 @@
+use cozo::UuidWrapper;
+use syn_parser::parser::calls::CallSite;
+use tracing::instrument;
+
+use super::*;
+use crate::schema::edges::CallSiteSchema;
+
+#[instrument(skip_all)]
+pub(super) fn transform_call_sites(
+    db: &impl ScriptRunner,
+    call_sites: Vec<CallSite>,
+) -> Result<(), TransformError> {
+    let schema = &CallSiteSchema::SCHEMA;
+    let mut script = None;
+    let mut rows = Vec::with_capacity(call_sites.len());
+    for site in &call_sites {
+        let params = call_site_params(schema, site);
+        script.get_or_insert_with(|| schema.script_put_rows(&params));
+        rows.push(DataValue::List(params.into_values().collect()));
+    }
+    let Some(script) = script else {
+        return Ok(());
+    };
+    let params = BTreeMap::from([("rows".to_string(), DataValue::List(rows))]);
+    db.run_script(&script, params, ScriptMutability::Mutable)?;
+    Ok(())
+}
+
+fn call_site_params(schema: &CallSiteSchema, site: &CallSite) -> BTreeMap<String, DataValue> {
+    let owner_id = site.caller.to_cozo_uuid();
+    // A caller can only have one call expression starting and ending at the same offsets, so the
+    // span is enough to tell call sites of the same caller apart.
+    let id = match &owner_id {
+        DataValue::Uuid(UuidWrapper(caller)) => DataValue::Uuid(UuidWrapper(uuid::Uuid::new_v5(
+            caller,
+            format!("{}:{}", site.span.0, site.span.1).as_bytes(),
+        ))),
+        _ => unreachable!("to_cozo_uuid always returns DataValue::Uuid"),
+    };
+    let path = DataValue::List(
+        site.path
+            .iter()
+            .map(|s| DataValue::from(s.as_str()))
+            .collect(),
+    );
+    let span = DataValue::List(Vec::from([
+        DataValue::Num(Num::Int(site.span.0 as i64)),
+        DataValue::Num(Num::Int(site.span.1 as i64)),
+    ]));
+    let resolved_id = site
+        .resolved
+        .map_or(DataValue::Null, |callee| callee.to_cozo_uuid());
+
+    BTreeMap::from([
+        (schema.id().to_string(), id),
+        (schema.owner_id().to_string(), owner_id),
+        (
+            schema.kind().to_string(),
+            DataValue::from(site.kind.as_str()),
+        ),
+        (schema.path().to_string(), path),
+        (schema.span().to_string(), span),
+        (schema.resolved_id().to_string(), resolved_id),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use cozo::{Db, MemStorage, ScriptMutability};
+    use ploke_test_utils::test_run_phases_and_collect;
+    use std::collections::BTreeMap;
+    use syn_parser::parser::ParsedCodeGraph;
+
+    use crate::{error::TransformError, schema::edges::CallSiteSchema};
+
+    use super::transform_call_sites;
+
+    #[test]
+    fn test_transform_call_sites() -> Result<(), Box<TransformError>> {
+        let successful_graphs = test_run_phases_and_collect("fixture_nodes");
+        let mut merged =
+            ParsedCodeGraph::merge_new(successful_graphs).expect("Failed to merge graph");
+        let _tree = merged
+            .build_tree_and_prune()
+            .expect("Failed to build module tree");
+        let call_sites = std::mem::take(&mut merged.graph.call_sites);
+        let expected = call_sites.len();
+        assert!(expected > 0, "fixture_nodes should contain call sites");
+
+        let db = Db::new(MemStorage::default()).expect("Failed to create database");
+        db.initialize().expect("Failed to initialize database");
+        CallSiteSchema::SCHEMA.create_and_insert(&db)?;
+
+        transform_call_sites(&db, call_sites)?;
+
+        let rows = db
+            .run_script(
+                "?[count(id)] := *call_site{ id }",
+                BTreeMap::new(),
+                ScriptMutability::Immutable,
+            )
+            .map_err(|e| Box::new(TransformError::from(e)))?;
+        let stored = rows.rows[0][0].get_int().expect("count is an int");
+        assert_eq!(stored as usize, expected);
+
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@
 use std::collections::{BTreeMap, HashSet};
 use std::path::PathBuf;
 
-use cozo::{DataValue, Db, MemStorage, Num, ScriptMutability, UuidWrapper};
+use cozo::{DataValue, Db, Num, ScriptMutability, UuidWrapper};
 use ploke_core::CompilationUnitTargetKind;
 use syn_parser::compilation_unit::{
     COMPILATION_UNIT_ID_NAMESPACE, CompilationUnitKey, StructuralCompilationUnitSlice,
@@ -11,6 +11,8 @@ use syn_parser::compilation_unit::{
 use tracing::instrument;
 use uuid::Uuid;
 
+use super::ScriptRunner;
+use crate::schema::CozoStorage;
 use crate::{
     error::TransformError,
     schema::compilation_unit::{
@@ -22,7 +24,14 @@ use crate::{
 /// Persists one compilation unit row plus membership rows for a structural slice.
 #[instrument(skip_all, fields(cu_id = %slice.cu_id))]
 pub fn insert_structural_compilation_unit_slice(
-    db: &Db<MemStorage>,
+    db: &Db<impl CozoStorage>,
+    slice: &StructuralCompilationUnitSlice,
+) -> Result<(), TransformError> {
+    write_structural_compilation_unit_slice(db, slice)
+}
+
+pub(super) fn write_structural_compilation_unit_slice(
+    db: &impl ScriptRunner,
     slice: &StructuralCompilationUnitSlice,
 ) -> Result<(), TransformError> {
     insert_compilation_unit_row(db, &slice.key)?;
@@ -34,7 +43,7 @@ pub fn insert_structural_compilation_unit_slice(
 }
 
 fn insert_compilation_unit_row(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     key: &CompilationUnitKey,
 ) -> Result<(), TransformError> {
     let schema = &CompilationUnitSchema::SCHEMA;
@@ -95,7 +104,7 @@ fn insert_compilation_unit_row(
 }
 
 fn insert_enabled_nodes(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     cu_id: Uuid,
     nodes: &HashSet<Uuid>,
 ) -> Result<(), TransformError> {
@@ -118,7 +127,7 @@ fn insert_enabled_nodes(
 }
 
 fn insert_enabled_edges(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     cu_id: Uuid,
     edges: &[syn_parser::compilation_unit::EnabledSyntacticEdge],
 ) -> Result<(), TransformError> {
@@ -161,7 +170,7 @@ fn insert_enabled_edges(
 }
 
 fn insert_enabled_files(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     cu_id: Uuid,
     paths: &HashSet<PathBuf>,
 ) -> Result<(), TransformError> {
@@ -190,7 +199,7 @@ fn insert_enabled_files(
 }
 
 fn insert_meta(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     slice: &StructuralCompilationUnitSlice,
 ) -> Result<(), TransformError> {
     let schema = &CompilationUnitMetaSchema::SCHEMA;
@@ -8,7 +8,7 @@ use super::*;
 /// Transforms value nodes into the values relation
 #[instrument(skip_all)]
 pub(super) fn transform_consts(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     consts: Vec<ConstNode>,
 ) -> Result<(), TransformError> {
     for consta in consts.into_iter() {
@@ -48,7 +48,7 @@ pub trait LogScript {
     ) -> Result<(), TransformError>;
 }
 
-impl LogScript for &Db<MemStorage> {
+impl<T: ScriptRunner> LogScript for &T {
     fn run_script_log(
         &self,
         script: &str,
@@ -10,7 +10,7 @@ use super::*;
 /// Transforms a CrateContext into a node in the database using CrateContextSchema
 #[instrument(skip_all)]
 pub(super) fn transform_crate_context(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     crate_context: CrateContext,
 ) -> Result<(), TransformError> {
     let schema = &CrateContextSchema::SCHEMA;
@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+
+use cozo::{DataValue, Db, ScriptMutability};
+use syn_parser::{DependencySource, ParsedDependency, discovery::CrateContext};
+use tracing::instrument;
+
+use crate::error::TransformError;
+use crate::schema::crate_node::DependencyNamespaceSchema;
+use crate::schema::ensure_dependency_namespace_relation;
+
+use super::transform_parsed_graph;
+use crate::schema::CozoStorage;
+
+/// Transforms the graph of a parsed dependency into its own namespace, and marks that namespace
+/// as a read-only dependency namespace.
+#[instrument(skip_all, fields(name = %parsed_dependency.source.name, version = %parsed_dependency.source.version))]
+pub fn transform_parsed_dependency(
+    db: &Db<impl CozoStorage>,
+    parsed_dependency: ParsedDependency,
+) -> Result<(), TransformError> {
+    let ParsedDependency { source, parsed } = parsed_dependency;
+    let mut parser_output = parsed.parser_output;
+    let merged_graph = parser_output.extract_merged_graph().ok_or_else(|| {
+        TransformError::Transformation("ParsedDependency was missing its merged graph".to_string())
+    })?;
+    let module_tree = parser_output.extract_module_tree().ok_or_else(|| {
+        TransformError::Transformation("ParsedDependency was missing its module tree".to_string())
+    })?;
+
+    transform_parsed_graph(db, merged_graph, &module_tree)?;
+    transform_dependency_namespace(db, &source, &parsed.crate_context)
+}
+
+fn transform_dependency_namespace(
+    db: &Db<impl CozoStorage>,
+    source: &DependencySource,
+    crate_context: &CrateContext,
+) -> Result<(), TransformError> {
+    ensure_dependency_namespace_relation(db)?;
+    let schema = &DependencyNamespaceSchema::SCHEMA;
+    let params = process_dependency_namespace(source, crate_context, schema)?;
+
+    let script = schema.script_put(&params);
+    db.run_script(&script, params, ScriptMutability::Mutable)
+        .inspect_err(|e| {
+            tracing::error!(target: "transform_dependency",
+                "DependencySource: {}\n\tcreate schema: {}\n\tput script: {}",
+                e,
+                schema.script_create(),
+                script
+            );
+        })?;
+
+    Ok(())
+}
+
+fn process_dependency_namespace(
+    source: &DependencySource,
+    crate_context: &CrateContext,
+    schema: &DependencyNamespaceSchema,
+) -> Result<BTreeMap<String, DataValue>, TransformError> {
+    let root_path = source.root_path.to_str().ok_or_else(|| {
+        TransformError::Transformation("Could not parse dependency root path".to_string())
+    })?;
+
+    Ok(BTreeMap::from([
+        (
+            schema.id().to_string(),
+            DataValue::Uuid(cozo::UuidWrapper(crate_context.namespace)),
+        ),
+        (
+            schema.name().to_string(),
+            DataValue::from(source.name.as_str()),
+        ),
+        (
+            schema.version().to_string(),
+            DataValue::from(source.version.as_str()),
+        ),
+        (
+            schema.source().to_string(),
+            DataValue::from(source.source.as_str()),
+        ),
+        (
+            schema.origin().to_string(),
+            DataValue::from(source.origin.as_str()),
+        ),
+        (schema.root_path().to_string(), DataValue::from(root_path)),
+    ]))
+}
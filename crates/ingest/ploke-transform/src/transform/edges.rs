@@ -78,7 +78,6 @@
 //  experience with queries.
 //
 
-use cozo::{Db, MemStorage};
 use syn_parser::parser::relations::SyntacticRelation;
 use tracing::instrument;
 
@@ -87,7 +86,7 @@ use crate::schema::edges::SyntacticRelationSchema;
 
 #[instrument(skip_all)]
 pub(super) fn transform_relations(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     relations: Vec<SyntacticRelation>,
 ) -> Result<(), TransformError> {
     let schema = &SyntacticRelationSchema::SCHEMA;
@@ -10,7 +10,7 @@ use super::{secondary_nodes::process_fields, *};
 
 #[instrument(skip_all)]
 pub(super) fn transform_enums(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     enums: Vec<EnumNode>,
 ) -> Result<(), TransformError> {
     for enm in enums.into_iter() {
@@ -12,7 +12,7 @@ use super::*;
 /// Transforms function nodes into the functions relation
 #[instrument(skip_all)]
 pub(super) fn transform_functions(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     functions: Vec<FunctionNode>,
     tree: &ModuleTree,
 ) -> Result<(), TransformError> {
@@ -1,4 +1,6 @@
+use crate::schema::edges::DerivedImplSchema;
 use crate::schema::primary_nodes_multi::ImplNodeSchema;
+use syn_parser::parser::nodes::DerivedImpl;
 use tracing::instrument;
 
 use crate::{macro_traits::CommonFields, schema::assoc_nodes::MethodNodeSchema};
@@ -8,7 +10,7 @@ use super::*;
 /// Transforms impl nodes into the impls relation
 #[instrument(skip_all)]
 pub(super) fn transform_impls(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     impls: Vec<ImplNode>,
 ) -> Result<(), TransformError> {
     // ANCHOR: transform_impls_methods
@@ -47,6 +49,19 @@ pub(super) fn transform_impls(
         );
         db.run_script(&script, imple_params, ScriptMutability::Mutable)?;
 
+        if let Some(derived) = &imple.derived {
+            let derived_schema = &DerivedImplSchema::SCHEMA;
+            let derived_params = process_derived_impl(imple_any_id, derived);
+            let script = derived_schema.script_put(&derived_params);
+            tracing::trace!(
+                "  {} {} {:?}",
+                "derived_impl put:".log_step(),
+                script,
+                derived_params
+            );
+            db.run_script(&script, derived_params, ScriptMutability::Mutable)?;
+        }
+
         // Add generic parameters
         for (i, generic_param) in imple.generic_params.into_iter().enumerate() {
             let (params, script) = process_generic_params(imple_any_id, i as i64, generic_param);
@@ -102,6 +117,38 @@ fn process_impl(imple: &ImplNode) -> BTreeMap<String, DataValue> {
     ])
 }
 
+fn process_derived_impl(
+    imple_any_id: AnyNodeId,
+    derived: &DerivedImpl,
+) -> BTreeMap<String, DataValue> {
+    fn path_value(path: &[String]) -> DataValue {
+        DataValue::List(path.iter().map(|s| DataValue::from(s.as_str())).collect())
+    }
+
+    let schema = &DerivedImplSchema::SCHEMA;
+    BTreeMap::from([
+        (schema.id().to_string(), imple_any_id.to_cozo_uuid()),
+        (
+            schema.macro_path().to_string(),
+            path_value(&derived.macro_path),
+        ),
+        (
+            schema.resolved_path().to_string(),
+            derived
+                .resolved_path
+                .as_deref()
+                .map_or(DataValue::Null, path_value),
+        ),
+        (
+            schema.trait_path().to_string(),
+            derived
+                .trait_path
+                .as_deref()
+                .map_or(DataValue::Null, path_value),
+        ),
+    ])
+}
+
 pub(super) fn process_methods(
     imple_any_id: AnyNodeId,
     method: MethodNode,
@@ -123,7 +170,8 @@ mod tests {
     use crate::{
         schema::{
             assoc_nodes::MethodNodeSchema, create_and_insert_generic_schema,
-            primary_nodes::ImplNodeSchema, secondary_nodes::AttributeNodeSchema,
+            edges::DerivedImplSchema, primary_nodes::ImplNodeSchema,
+            secondary_nodes::AttributeNodeSchema,
         },
         transform::impls::transform_impls,
     };
@@ -153,6 +201,8 @@ mod tests {
         MethodNodeSchema::create_and_insert_schema(&db)?;
         // create and insert impl schema
         ImplNodeSchema::create_and_insert_schema(&db)?;
+        // create and insert derived impl schema
+        DerivedImplSchema::create_and_insert_schema(&db)?;
 
         // transform and insert impls into cozo
         transform_impls(&db, merged.graph.impls)?;
@@ -10,7 +10,7 @@ use super::*;
 
 #[instrument(skip_all)]
 pub(super) fn transform_imports(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     imports: Vec<ImportNode>,
 ) -> Result<(), TransformError> {
     for import in imports.into_iter() {
@@ -0,0 +1,365 @@
+//! Applies a [`GraphDiff`] between two parses of the same crate to a database that holds the
+//! older one, without dropping the crate's namespace.
+//!
+//! Rows of removed and modified nodes are retracted, together with the rows owned by them (params,
+//! fields, variants, generic params, attributes, methods, `file_mod`, `derived_impl`), the
+//! `syntax_edge` rows of removed relations and the parse details of the callers, modules and test
+//! owners whose call sites, macro expansions or tests changed. The newer graph is then filtered
+//! down to the added and modified nodes, added relations, added types and changed parse details,
+//! and inserted as by [`transform_parsed_graph`]. Retractions and insertions are collected in one
+//! [`WriteBatch`] and applied in a single transaction.
+//!
+//! Type rows are never retracted, since type nodes are shared by every item naming the type.
+
+use std::collections::BTreeSet;
+
+use itertools::Itertools;
+use syn_parser::parser::graph::GraphDiff;
+use syn_parser::parser::relations::SyntacticRelation;
+use uuid::Uuid;
+
+use super::*;
+use crate::schema::assoc_nodes::MethodNodeSchema;
+use crate::schema::edges::{
+    CallSiteSchema, DerivedImplSchema, MacroExpansionSchema, TestSchema, TestTargetSchema,
+};
+use crate::schema::primary_nodes::*;
+use crate::schema::secondary_nodes::*;
+use crate::schema::subnode_variants::FileModuleNodeSchema;
+
+/// The name, key fields and value fields of each relation holding node rows.
+macro_rules! node_relations {
+    ($($schema:ident),* $(,)?) => {
+        [$(
+            (
+                $schema::SCHEMA.relation,
+                $schema::SCHEMA.keys().copied().collect::<Vec<_>>(),
+                $schema::SCHEMA.vals().copied().collect::<Vec<_>>(),
+            )
+        ),*]
+    };
+}
+
+/// Updates the rows of the crate in `db` from the older graph the diff was computed against to
+/// `parsed_graph`, the newer one.
+///
+/// `tree` must be the module tree of `parsed_graph`. Relations that are not part of the parsed
+/// graph itself, such as compilation-unit masks and embeddings, are left untouched; use
+/// `ploke_db::Database::apply_graph_diff` to also retract the embeddings and BM25 metadata of
+/// the stale nodes. All rows are retracted and inserted in one transaction, so a failing update
+/// leaves the crate as it was.
+///
+/// Returns [`TransformError::ReadOnlyNamespace`] for the namespace of an indexed dependency.
+#[instrument(skip_all, fields(added = diff.added.len(), removed = diff.removed.len(), modified = diff.modified.len()))]
+pub fn apply_graph_diff(
+    db: &Db<impl CozoStorage>,
+    parsed_graph: ParsedCodeGraph,
+    tree: &ModuleTree,
+    diff: &GraphDiff,
+) -> Result<(), TransformError> {
+    let batch = WriteBatch::default();
+    push_graph_diff(db, &batch, parsed_graph, tree, diff)?;
+    batch.run(db)?;
+    Ok(())
+}
+
+/// Like [`apply_graph_diff`], but adds the writes to `batch` instead of running them. `db` is
+/// only read.
+pub fn push_graph_diff(
+    db: &Db<impl CozoStorage>,
+    batch: &WriteBatch,
+    parsed_graph: ParsedCodeGraph,
+    tree: &ModuleTree,
+    diff: &GraphDiff,
+) -> Result<(), TransformError> {
+    if crate::schema::is_dependency_namespace(db, parsed_graph.crate_namespace)? {
+        return Err(TransformError::ReadOnlyNamespace(
+            parsed_graph.crate_namespace,
+        ));
+    }
+    let stale: BTreeSet<Uuid> = diff.stale_ids().map(AnyNodeId::uuid).collect();
+    for (relation, keys, vals) in node_relations!(
+        ParamNodeSchema,
+        AttributeNodeSchema,
+        VariantNodeSchema,
+        FieldNodeSchema,
+        GenericTypeNodeSchema,
+        GenericLifetimeNodeSchema,
+        GenericConstNodeSchema,
+        ConstNodeSchema,
+        EnumNodeSchema,
+        FunctionNodeSchema,
+        ImplNodeSchema,
+        ImportNodeSchema,
+        MacroNodeSchema,
+        ModuleNodeSchema,
+        StaticNodeSchema,
+        StructNodeSchema,
+        TraitNodeSchema,
+        TypeAliasNodeSchema,
+        UnionNodeSchema,
+        FileModuleNodeSchema,
+        MethodNodeSchema,
+        DerivedImplSchema,
+    ) {
+        push_rows_retraction(batch, relation, &keys, &vals, &stale);
+    }
+
+    let call_site_owners: BTreeSet<Uuid> = diff
+        .call_site_owners
+        .iter()
+        .map(|id| id.uuid())
+        .chain(stale.iter().copied())
+        .collect();
+    let expansion_owners: BTreeSet<Uuid> = diff
+        .macro_expansion_owners
+        .iter()
+        .map(|id| id.as_any().uuid())
+        .chain(stale.iter().copied())
+        .collect();
+    for (relation, keys, vals, ids) in [
+        (
+            CallSiteSchema::SCHEMA.relation,
+            CallSiteSchema::SCHEMA.keys().copied().collect::<Vec<_>>(),
+            CallSiteSchema::SCHEMA.vals().copied().collect::<Vec<_>>(),
+            &call_site_owners,
+        ),
+        (
+            MacroExpansionSchema::SCHEMA.relation,
+            MacroExpansionSchema::SCHEMA.keys().copied().collect(),
+            MacroExpansionSchema::SCHEMA.vals().copied().collect(),
+            &expansion_owners,
+        ),
+    ] {
+        push_rows_retraction(batch, relation, &keys, &vals, ids);
+    }
+    push_tests_retraction(db, batch, diff, &stale)?;
+    push_syntax_edges_retraction(batch, &diff.removed_relations);
+
+    let changed_graph = filter_changed(parsed_graph, diff);
+    push_parsed_graph(db, batch, changed_graph, tree)
+}
+
+/// Keeps only the parts of `parsed_graph` that are not in the database yet.
+fn filter_changed(mut parsed_graph: ParsedCodeGraph, diff: &GraphDiff) -> ParsedCodeGraph {
+    let changed: BTreeSet<AnyNodeId> = diff.changed_ids().collect();
+    let graph = &mut parsed_graph.graph;
+    graph.functions.retain(|n| changed.contains(&n.id.as_any()));
+    graph
+        .defined_types
+        .retain(|n| changed.contains(&n.any_id()));
+    graph.traits.retain(|n| changed.contains(&n.id.as_any()));
+    graph.impls.retain(|n| changed.contains(&n.id.as_any()));
+    graph.modules.retain(|n| changed.contains(&n.id.as_any()));
+    graph.consts.retain(|n| changed.contains(&n.id.as_any()));
+    graph.statics.retain(|n| changed.contains(&n.id.as_any()));
+    graph.macros.retain(|n| changed.contains(&n.id.as_any()));
+    graph
+        .use_statements
+        .retain(|n| changed.contains(&n.id.as_any()));
+    graph
+        .type_graph
+        .retain(|t| diff.added_types.contains(&t.id));
+    graph
+        .relations
+        .retain(|rel| diff.added_relations.contains(rel));
+    graph.call_sites.retain(|site| {
+        let caller = site.caller.as_any();
+        diff.call_site_owners.contains(&caller) || changed.contains(&caller)
+    });
+    graph.macro_expansions.retain(|expansion| {
+        diff.macro_expansion_owners.contains(&expansion.module_id)
+            || changed.contains(&expansion.module_id.as_any())
+    });
+    graph
+        .tests
+        .retain(|test| diff.test_owners.contains(&test.owner) || changed.contains(&test.owner));
+    parsed_graph
+}
+
+/// Adds the retraction of the rows of `relation` in which any key field holds one of `ids`.
+fn push_rows_retraction(
+    batch: &WriteBatch,
+    relation: &str,
+    key_fields: &[&str],
+    val_fields: &[&str],
+    ids: &BTreeSet<Uuid>,
+) {
+    if ids.is_empty() {
+        return;
+    }
+    let input_rows = ids.iter().map(|id| format!("[\"{id}\"]")).join(", ");
+    let fields = key_fields.iter().chain(val_fields).join(", ");
+    let key_match = key_fields
+        .iter()
+        .map(|field| format!("target[{field}]"))
+        .join(" or ");
+    let put_keys = key_fields.join(", ");
+    let put_vals = val_fields.join(", ");
+    let script = format!(
+        r#"
+input[id_str] <- [{input_rows}]
+target[id] := input[id_str], id = to_uuid(id_str)
+?[{fields}, at] := *{relation} {{ {fields} }}, ({key_match}), at = 'RETRACT'
+
+:put {relation} {{ {put_keys}, at => {put_vals} }}
+"#
+    );
+    batch.push(&script, BTreeMap::new());
+}
+
+/// Adds the retraction of the `test` and `test_target` rows of the tests owned by stale nodes or
+/// by owners whose tests changed. The targets are found through the ids of the stored tests.
+fn push_tests_retraction(
+    db: &Db<impl CozoStorage>,
+    batch: &WriteBatch,
+    diff: &GraphDiff,
+    stale: &BTreeSet<Uuid>,
+) -> Result<(), TransformError> {
+    let owners: BTreeSet<Uuid> = diff
+        .test_owners
+        .iter()
+        .map(|id| id.uuid())
+        .chain(stale.iter().copied())
+        .collect();
+    if owners.is_empty() {
+        return Ok(());
+    }
+    let input_rows = owners.iter().map(|id| format!("[\"{id}\"]")).join(", ");
+    let script = format!(
+        r#"
+input[id_str] <- [{input_rows}]
+owner[owner_id] := input[id_str], owner_id = to_uuid(id_str)
+?[id] := *test {{ id, owner_id @ 'NOW' }}, owner[owner_id]
+"#
+    );
+    let rows = db.run_script(&script, BTreeMap::new(), ScriptMutability::Immutable)?;
+    let test_ids: BTreeSet<Uuid> = rows
+        .rows
+        .into_iter()
+        .filter_map(|row| match row.first() {
+            Some(DataValue::Uuid(cozo::UuidWrapper(id))) => Some(*id),
+            _ => None,
+        })
+        .collect();
+    push_rows_retraction(
+        batch,
+        TestTargetSchema::SCHEMA.relation,
+        &TestTargetSchema::SCHEMA.keys().copied().collect::<Vec<_>>(),
+        &TestTargetSchema::SCHEMA.vals().copied().collect::<Vec<_>>(),
+        &test_ids,
+    );
+    push_rows_retraction(
+        batch,
+        TestSchema::SCHEMA.relation,
+        &TestSchema::SCHEMA.keys().copied().collect::<Vec<_>>(),
+        &TestSchema::SCHEMA.vals().copied().collect::<Vec<_>>(),
+        &owners,
+    );
+    Ok(())
+}
+
+/// Adds the retraction of the `syntax_edge` rows of `relations`, which are keyed by their source
+/// and target.
+fn push_syntax_edges_retraction(batch: &WriteBatch, relations: &BTreeSet<SyntacticRelation>) {
+    if relations.is_empty() {
+        return;
+    }
+    let input_rows = relations
+        .iter()
+        .map(|rel| format!("[\"{}\", \"{}\"]", rel.source().uuid(), rel.target().uuid()))
+        .join(", ");
+    let script = format!(
+        r#"
+input[source_str, target_str] <- [{input_rows}]
+target[source_id, target_id] := input[source_str, target_str],
+    source_id = to_uuid(source_str),
+    target_id = to_uuid(target_str)
+?[source_id, target_id, relation_kind, source_kind, target_kind, at] :=
+    *syntax_edge {{ source_id, target_id, relation_kind, source_kind, target_kind }},
+    target[source_id, target_id],
+    at = 'RETRACT'
+
+:put syntax_edge {{ source_id, target_id, at => relation_kind, source_kind, target_kind }}
+"#
+    );
+    batch.push(&script, BTreeMap::new());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    use cozo::{DataValue, Db, MemStorage, ScriptMutability};
+    use syn_parser::incremental::IncrementalParser;
+    use tempfile::tempdir;
+
+    use super::apply_graph_diff;
+    use crate::schema::create_schema_all;
+    use crate::transform::transform_parsed_graph;
+
+    fn current_names(db: &Db<MemStorage>, relation: &str) -> Vec<String> {
+        let rows = db
+            .run_script(
+                &format!("?[name] := *{relation} {{ name @ 'NOW' }}"),
+                BTreeMap::new(),
+                ScriptMutability::Immutable,
+            )
+            .expect("query current names");
+        let mut names: Vec<String> = rows
+            .rows
+            .into_iter()
+            .map(|row| match &row[0] {
+                DataValue::Str(name) => name.to_string(),
+                other => panic!("unexpected name {other:?}"),
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_apply_graph_diff_updates_changed_rows_only() {
+        let tmp = tempdir().unwrap();
+        let crate_root = tmp.path();
+        fs::create_dir_all(crate_root.join("src")).unwrap();
+        fs::write(
+            crate_root.join("Cargo.toml"),
+            "[package]\nname = \"incremental_fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        fs::write(crate_root.join("src/lib.rs"), "pub mod a;\n").unwrap();
+        fs::write(
+            crate_root.join("src/a.rs"),
+            "pub struct Point {\n    pub x: i32,\n}\n\npub fn keep() {}\n\npub fn gone() {}\n",
+        )
+        .unwrap();
+
+        let db = Db::new(MemStorage::default()).expect("create database");
+        db.initialize().expect("initialize database");
+        create_schema_all(&db).expect("create schema");
+        let mut parser = IncrementalParser::new(crate_root).expect("initial parse");
+        transform_parsed_graph(&db, parser.merged_graph().clone(), parser.module_tree())
+            .expect("initial transform");
+        assert_eq!(current_names(&db, "function"), vec!["gone", "keep"]);
+
+        fs::write(
+            crate_root.join("src/a.rs"),
+            "pub struct Point {\n    pub x: i32,\n    pub y: i32,\n}\n\npub fn keep() {}\n\npub fn added() {}\n",
+        )
+        .unwrap();
+        let diff = parser.update().expect("incremental update");
+        apply_graph_diff(
+            &db,
+            parser.merged_graph().clone(),
+            parser.module_tree(),
+            &diff,
+        )
+        .expect("apply diff");
+
+        assert_eq!(current_names(&db, "function"), vec!["added", "keep"]);
+        assert_eq!(current_names(&db, "struct"), vec!["Point"]);
+        assert_eq!(current_names(&db, "field"), vec!["x", "y"]);
+    }
+}
@@ -0,0 +1,120 @@
+use cozo::UuidWrapper;
+use syn_parser::parser::macro_expansion::MacroExpansion;
+use tracing::instrument;
+
+use super::*;
+use crate::schema::edges::MacroExpansionSchema;
+
+#[instrument(skip_all)]
+pub(super) fn transform_macro_expansions(
+    db: &impl ScriptRunner,
+    expansions: Vec<MacroExpansion>,
+) -> Result<(), TransformError> {
+    let schema = &MacroExpansionSchema::SCHEMA;
+    for expansion in expansions {
+        let params = macro_expansion_params(schema, &expansion);
+        let script = schema.script_put(&params);
+        db.run_script(&script, params, ScriptMutability::Mutable)?;
+    }
+    Ok(())
+}
+
+fn macro_expansion_params(
+    schema: &MacroExpansionSchema,
+    expansion: &MacroExpansion,
+) -> BTreeMap<String, DataValue> {
+    let owner_id = expansion.module_id.as_any().to_cozo_uuid();
+    // Nested invocations share the span of the outermost one, so the macro name is needed to
+    // tell them apart.
+    let id = match &owner_id {
+        DataValue::Uuid(UuidWrapper(module)) => DataValue::Uuid(UuidWrapper(uuid::Uuid::new_v5(
+            module,
+            format!(
+                "{}:{}:{}",
+                expansion.macro_name, expansion.span.0, expansion.span.1
+            )
+            .as_bytes(),
+        ))),
+        _ => unreachable!("to_cozo_uuid always returns DataValue::Uuid"),
+    };
+    let invocation_path = DataValue::List(
+        expansion
+            .invocation_path
+            .iter()
+            .map(|s| DataValue::from(s.as_str()))
+            .collect(),
+    );
+    let span = DataValue::List(Vec::from([
+        DataValue::Num(Num::Int(expansion.span.0 as i64)),
+        DataValue::Num(Num::Int(expansion.span.1 as i64)),
+    ]));
+    let macro_id = expansion
+        .macro_id
+        .map_or(DataValue::Null, |id| id.as_any().to_cozo_uuid());
+    let generated_ids = DataValue::List(
+        expansion
+            .generated
+            .iter()
+            .map(|id| id.to_cozo_uuid())
+            .collect(),
+    );
+
+    BTreeMap::from([
+        (schema.id().to_string(), id),
+        (schema.owner_id().to_string(), owner_id),
+        (
+            schema.macro_name().to_string(),
+            DataValue::from(expansion.macro_name.as_str()),
+        ),
+        (schema.invocation_path().to_string(), invocation_path),
+        (schema.span().to_string(), span),
+        (schema.macro_id().to_string(), macro_id),
+        (schema.generated_ids().to_string(), generated_ids),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use cozo::{Db, MemStorage, ScriptMutability};
+    use ploke_test_utils::test_run_phases_and_collect;
+    use std::collections::BTreeMap;
+    use syn_parser::parser::ParsedCodeGraph;
+
+    use crate::{error::TransformError, schema::edges::MacroExpansionSchema};
+
+    use super::transform_macro_expansions;
+
+    #[test]
+    fn test_transform_macro_expansions() -> Result<(), Box<TransformError>> {
+        let successful_graphs = test_run_phases_and_collect("fixture_macro_expansion");
+        let mut merged =
+            ParsedCodeGraph::merge_new(successful_graphs).expect("Failed to merge graph");
+        let _tree = merged
+            .build_tree_and_prune()
+            .expect("Failed to build module tree");
+        let expansions = std::mem::take(&mut merged.graph.macro_expansions);
+        let expected = expansions.len();
+        assert!(
+            expected > 0,
+            "fixture_macro_expansion should contain macro expansions"
+        );
+
+        let db = Db::new(MemStorage::default()).expect("Failed to create database");
+        db.initialize().expect("Failed to initialize database");
+        MacroExpansionSchema::SCHEMA.create_and_insert(&db)?;
+
+        transform_macro_expansions(&db, expansions)?;
+
+        let rows = db
+            .run_script(
+                "?[count(id)] := *macro_expansion{ id }",
+                BTreeMap::new(),
+                ScriptMutability::Immutable,
+            )
+            .map_err(|e| Box::new(TransformError::from(e)))?;
+        let stored = rows.rows[0][0].get_int().expect("count is an int");
+        assert_eq!(stored as usize, expected);
+
+        Ok(())
+    }
+}
@@ -7,7 +7,7 @@ use super::*;
 
 #[instrument(skip_all)]
 pub(super) fn transform_macros(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     macros: Vec<MacroNode>,
 ) -> Result<(), TransformError> {
     for macro_node in macros.into_iter() {
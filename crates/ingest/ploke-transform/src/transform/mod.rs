@@ -1,10 +1,14 @@
 //! Transforms CodeGraph into CozoDB relations
 
 // -- external
-use cozo::{DataValue, Db, MemStorage, Num, ScriptMutability};
+use cozo::{DataValue, Db, Num, ScriptMutability};
 
+pub use batch::{ScriptRunner, WriteBatch};
 use crate_context::transform_crate_context;
-pub use workspace::transform_parsed_workspace;
+pub use dependency::transform_parsed_dependency;
+pub use incremental::{apply_graph_diff, push_graph_diff};
+pub use snapshot::{GraphSnapshot, SnapshotRun, SnapshotTag};
+pub use workspace::{push_parsed_workspace, transform_parsed_workspace};
 // -- from workspace
 use syn_parser::ParsedCodeGraph;
 use syn_parser::parser::nodes::*;
@@ -22,42 +26,54 @@ use crate::error::TransformError;
 // use crate::schema::*;
 
 // -- transforms
+use calls::transform_call_sites;
 use consts::transform_consts;
 use edges::transform_relations;
 use enums::transform_enums;
 use impls::transform_impls;
 use imports::transform_imports;
+use macro_expansions::transform_macro_expansions;
 use macros::transform_macros;
 use module::transform_modules;
 use statics::transform_statics;
 use std::collections::BTreeMap;
 use structs::transform_structs;
+use test_nodes::transform_tests;
 use tracing::instrument;
 use traits::transform_traits;
 use type_alias::transform_type_aliases;
 use type_node::transform_types;
 use unions::transform_unions;
 
+mod batch;
 mod fields;
 mod secondary_nodes;
 // -- special case nodes --
 pub mod compilation_unit;
 pub mod union_crate_masks;
 pub use compilation_unit::insert_structural_compilation_unit_slice;
-pub use union_crate_masks::transform_union_crate_and_structural_masks;
+pub use union_crate_masks::{
+    push_union_crate_and_structural_masks, transform_union_crate_and_structural_masks,
+};
 mod crate_context;
+mod dependency;
+mod incremental;
+pub mod snapshot;
 mod workspace;
 // -- primary nodes --
+mod calls;
 mod consts;
 mod edges;
 mod enums;
 mod functions;
 mod impls;
 mod imports;
+mod macro_expansions;
 mod macros;
 mod module;
 mod statics;
 mod structs;
+mod test_nodes;
 mod traits;
 mod type_alias;
 mod unions;
@@ -72,6 +88,7 @@ use functions::transform_functions;
 use secondary_nodes::{process_attributes, process_generic_params, process_params};
 
 // -- schema
+use crate::schema::CozoStorage;
 use crate::schema::secondary_nodes::AttributeNodeSchema;
 
 // -- edges
@@ -79,7 +96,7 @@ use crate::schema::secondary_nodes::AttributeNodeSchema;
 #[deprecated = "Use transform_parsed_graph instead"]
 /// Transforms a CodeGraph into CozoDB relations
 pub fn transform_code_graph(
-    db: &Db<MemStorage>,
+    db: &Db<impl CozoStorage>,
     code_graph: CodeGraph,
     tree: &ModuleTree,
     namespace: uuid::Uuid,
@@ -122,9 +139,34 @@ pub fn transform_code_graph(
 }
 
 /// Transforms a CodeGraph into CozoDB relations, inserts into the cozo database
+///
+/// Returns [`TransformError::ReadOnlyNamespace`] when the crate's namespace already holds an
+/// indexed dependency.
 #[instrument(skip_all)]
 pub fn transform_parsed_graph(
-    db: &Db<MemStorage>,
+    db: &Db<impl CozoStorage>,
+    parsed_graph: ParsedCodeGraph,
+    tree: &ModuleTree,
+) -> Result<(), TransformError> {
+    write_parsed_graph(db, db, parsed_graph, tree)
+}
+
+/// Like [`transform_parsed_graph`], but adds the writes to `batch` instead of running them.
+#[instrument(skip_all)]
+pub fn push_parsed_graph(
+    db: &Db<impl CozoStorage>,
+    batch: &WriteBatch,
+    parsed_graph: ParsedCodeGraph,
+    tree: &ModuleTree,
+) -> Result<(), TransformError> {
+    write_parsed_graph(db, batch, parsed_graph, tree)
+}
+
+/// Checks the crate's namespace against `db` and writes the rows of `parsed_graph` through
+/// `out`.
+fn write_parsed_graph(
+    db: &Db<impl CozoStorage>,
+    out: &impl ScriptRunner,
     parsed_graph: ParsedCodeGraph,
     tree: &ModuleTree,
 ) -> Result<(), TransformError> {
@@ -133,35 +175,44 @@ pub fn transform_parsed_graph(
     let crate_context = parsed_graph
         .crate_context
         .expect("Invariant: All Code Graphs must have a Crate Context");
+    if crate::schema::is_dependency_namespace(db, crate_context.namespace)? {
+        return Err(TransformError::ReadOnlyNamespace(crate_context.namespace));
+    }
 
     tracing::trace!("{}: Starting", "types".log_step());
-    transform_types(db, code_graph.type_graph)?;
+    transform_types(out, code_graph.type_graph)?;
     tracing::trace!("{}: Starting", "functions".log_step());
-    transform_functions(db, code_graph.functions, tree)?;
+    transform_functions(out, code_graph.functions, tree)?;
 
     //  TODO: Refactor CodeGraph to split these nodes into their own collections.
     tracing::trace!("{}: Starting", "defined_types".log_step());
-    transform_defined_types(db, code_graph.defined_types)?;
+    transform_defined_types(out, code_graph.defined_types)?;
 
     tracing::trace!("{}: Starting", "traits".log_step());
-    transform_traits(db, code_graph.traits)?;
+    transform_traits(out, code_graph.traits)?;
     tracing::trace!("{}: Starting", "impls".log_step());
-    transform_impls(db, code_graph.impls)?;
+    transform_impls(out, code_graph.impls)?;
     tracing::trace!("{}: Starting", "modules".log_step());
-    transform_modules(db, code_graph.modules, crate_context.namespace)?;
+    transform_modules(out, code_graph.modules, crate_context.namespace)?;
     tracing::trace!("{}: Starting", "consts".log_step());
-    transform_consts(db, code_graph.consts)?;
+    transform_consts(out, code_graph.consts)?;
     tracing::trace!("{}: Starting", "statics".log_step());
-    transform_statics(db, code_graph.statics)?;
+    transform_statics(out, code_graph.statics)?;
     tracing::trace!("{}: Starting", "macros".log_step());
-    transform_macros(db, code_graph.macros)?;
+    transform_macros(out, code_graph.macros)?;
     tracing::trace!("{}: Starting", "imports".log_step());
-    transform_imports(db, code_graph.use_statements)?;
+    transform_imports(out, code_graph.use_statements)?;
     tracing::trace!("{}: Starting", "relations".log_step());
-    transform_relations(db, code_graph.relations)?;
+    transform_relations(out, code_graph.relations)?;
+    tracing::trace!("{}: Starting", "call_sites".log_step());
+    transform_call_sites(out, code_graph.call_sites)?;
+    tracing::trace!("{}: Starting", "macro_expansions".log_step());
+    transform_macro_expansions(out, code_graph.macro_expansions)?;
+    tracing::trace!("{}: Starting", "tests".log_step());
+    transform_tests(out, code_graph.tests)?;
 
     tracing::trace!("{}: Starting", "crate_context".log_step());
-    transform_crate_context(db, crate_context)?;
+    transform_crate_context(out, crate_context)?;
 
     Ok(())
     // ANCHOR_END: transform_parsed_graph_methods
@@ -169,7 +220,7 @@ pub fn transform_parsed_graph(
 
 #[instrument(skip_all)]
 fn transform_defined_types(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     defined_types: Vec<TypeDefNode>,
 ) -> Result<(), TransformError> {
     let mut structs = Vec::new();
@@ -30,7 +30,7 @@ pub struct FileModuleNode {
 
 #[instrument(skip_all)]
 pub(super) fn transform_modules(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     modules: Vec<ModuleNode>,
     namespace: Uuid,
 ) -> Result<(), TransformError> {
@@ -83,7 +83,7 @@ pub(super) fn transform_modules(
 }
 
 fn process_module_def(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     module_def: ModuleKind,
     module_id: ModuleNodeId,
     namespace: Uuid,
@@ -0,0 +1,161 @@
+//! Snapshots of the code graph, keyed by a git commit or an index run.
+//!
+//! Every row written by the transforms is a validity-stamped assertion (`'ASSERT'`) or retraction
+//! (`'RETRACT'`), so the graph at any past moment can be read back with Cozo's time travel. A
+//! snapshot names such a moment: a [`SnapshotRun`] is begun before a run writes to the database
+//! and recorded once it is done, which stores a `graph_snapshot` row holding the validity window
+//! of the run.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cozo::{DataValue, Db, ScriptMutability};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::error::TransformError;
+use crate::schema::meta::GraphSnapshotSchema;
+use crate::schema::{CozoStorage, ensure_graph_snapshot_relation};
+
+/// What a snapshot of the code graph is keyed by.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SnapshotTag {
+    /// A git commit hash.
+    Commit(String),
+    /// The id of an index run, e.g. one per LLM session.
+    Run(String),
+}
+
+impl SnapshotTag {
+    /// A run tag with a fresh random id.
+    pub fn new_run() -> Self {
+        Self::Run(Uuid::new_v4().to_string())
+    }
+
+    /// Rebuilds a tag from the `kind` and `tag` columns of a `graph_snapshot` row.
+    pub fn from_parts(kind: &str, tag: String) -> Option<Self> {
+        match kind {
+            "commit" => Some(Self::Commit(tag)),
+            "run" => Some(Self::Run(tag)),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Commit(_) => "commit",
+            Self::Run(_) => "run",
+        }
+    }
+
+    pub fn tag(&self) -> &str {
+        match self {
+            Self::Commit(tag) | Self::Run(tag) => tag,
+        }
+    }
+
+    /// The id of the `graph_snapshot` row of this tag.
+    pub fn snapshot_id(&self) -> Uuid {
+        Uuid::new_v5(&Uuid::NAMESPACE_OID, self.to_string().as_bytes())
+    }
+}
+
+impl fmt::Display for SnapshotTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.kind(), self.tag())
+    }
+}
+
+/// A recorded snapshot of the code graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphSnapshot {
+    pub tag: SnapshotTag,
+    /// Validity timestamp, in microseconds, at which the run producing the snapshot began.
+    pub started_at: i64,
+    /// Validity timestamp, in microseconds, of the graph as of the snapshot.
+    pub recorded_at: i64,
+}
+
+/// The current validity timestamp, in microseconds since the Unix epoch, on the clock Cozo uses
+/// for `'ASSERT'` and `'RETRACT'`.
+pub fn validity_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_micros() as i64)
+        .unwrap_or_default()
+}
+
+/// A run of writes to the code graph, recorded as a [`GraphSnapshot`] once it is done.
+#[derive(Debug, Clone)]
+pub struct SnapshotRun {
+    tag: SnapshotTag,
+    started_at: i64,
+}
+
+impl SnapshotRun {
+    /// Begins a run, before its first write to the database.
+    pub fn begin(tag: SnapshotTag) -> Self {
+        Self {
+            tag,
+            started_at: validity_now(),
+        }
+    }
+
+    pub fn tag(&self) -> &SnapshotTag {
+        &self.tag
+    }
+
+    /// Records the graph as it is now as the snapshot of the run. Must be called after the last
+    /// write of the run. Recording a tag again supersedes the older snapshot of that tag.
+    #[instrument(skip_all, fields(tag = %self.tag))]
+    pub fn record(self, db: &Db<impl CozoStorage>) -> Result<GraphSnapshot, TransformError> {
+        ensure_graph_snapshot_relation(db)?;
+        let snapshot = GraphSnapshot {
+            tag: self.tag,
+            started_at: self.started_at,
+            recorded_at: validity_now(),
+        };
+        let schema = &GraphSnapshotSchema::SCHEMA;
+        let params = process_snapshot(&snapshot, schema);
+
+        let script = schema.script_put(&params);
+        db.run_script(&script, params, ScriptMutability::Mutable)
+            .inspect_err(|e| {
+                tracing::error!(target: "transform_snapshot",
+                    "GraphSnapshot: {}\n\tput script: {}",
+                    e,
+                    script
+                );
+            })?;
+        Ok(snapshot)
+    }
+}
+
+fn process_snapshot(
+    snapshot: &GraphSnapshot,
+    schema: &GraphSnapshotSchema,
+) -> BTreeMap<String, DataValue> {
+    BTreeMap::from([
+        (
+            schema.id().to_string(),
+            DataValue::Uuid(cozo::UuidWrapper(snapshot.tag.snapshot_id())),
+        ),
+        (
+            schema.kind().to_string(),
+            DataValue::from(snapshot.tag.kind()),
+        ),
+        (
+            schema.tag().to_string(),
+            DataValue::from(snapshot.tag.tag()),
+        ),
+        (
+            schema.started_at().to_string(),
+            DataValue::from(snapshot.started_at),
+        ),
+        (
+            schema.recorded_at().to_string(),
+            DataValue::from(snapshot.recorded_at),
+        ),
+    ])
+}
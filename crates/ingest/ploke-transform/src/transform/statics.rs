@@ -7,7 +7,7 @@ use super::*;
 
 #[instrument(skip_all)]
 pub(super) fn transform_statics(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     statics: Vec<StaticNode>,
 ) -> Result<(), TransformError> {
     for stat in statics.into_iter() {
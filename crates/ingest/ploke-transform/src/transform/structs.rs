@@ -7,7 +7,7 @@ use super::{secondary_nodes::process_fields, *};
 
 #[instrument(skip_all)]
 pub(super) fn transform_structs(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     structs: Vec<StructNode>,
 ) -> Result<(), TransformError> {
     for strukt in structs.into_iter() {
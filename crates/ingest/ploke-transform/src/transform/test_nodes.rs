@@ -0,0 +1,130 @@
+use cozo::UuidWrapper;
+use syn_parser::parser::test_items::{TestNode, TestTarget};
+use tracing::instrument;
+
+use super::*;
+use crate::schema::edges::{TestSchema, TestTargetSchema};
+
+#[instrument(skip_all)]
+pub(super) fn transform_tests(
+    db: &impl ScriptRunner,
+    tests: Vec<TestNode>,
+) -> Result<(), TransformError> {
+    let schema = &TestSchema::SCHEMA;
+    let target_schema = &TestTargetSchema::SCHEMA;
+    for test in tests {
+        let id = DataValue::Uuid(UuidWrapper(test.id()));
+        for target in &test.targets {
+            let params = test_target_params(target_schema, id.clone(), target);
+            let script = target_schema.script_put(&params);
+            db.run_script(&script, params, ScriptMutability::Mutable)?;
+        }
+        let params = test_params(schema, id, test);
+        let script = schema.script_put(&params);
+        db.run_script(&script, params, ScriptMutability::Mutable)?;
+    }
+    Ok(())
+}
+
+fn test_params(schema: &TestSchema, id: DataValue, test: TestNode) -> BTreeMap<String, DataValue> {
+    let span = DataValue::List(Vec::from([
+        DataValue::Num(Num::Int(test.span.0 as i64)),
+        DataValue::Num(Num::Int(test.span.1 as i64)),
+    ]));
+    let doctest_index = test.doctest_index.map_or(DataValue::Null, |index| {
+        DataValue::Num(Num::Int(index as i64))
+    });
+    let code = test
+        .code
+        .map_or(DataValue::Null, |code| DataValue::from(code.as_str()));
+
+    BTreeMap::from([
+        (schema.id().to_string(), id),
+        (schema.owner_id().to_string(), test.owner.to_cozo_uuid()),
+        (
+            schema.name().to_string(),
+            DataValue::from(test.name.as_str()),
+        ),
+        (
+            schema.kind().to_string(),
+            DataValue::from(test.kind.as_str()),
+        ),
+        (
+            schema.module_id().to_string(),
+            test.module_id.as_any().to_cozo_uuid(),
+        ),
+        (
+            schema.file_path().to_string(),
+            DataValue::from(test.file_path.to_string_lossy().as_ref()),
+        ),
+        (schema.span().to_string(), span),
+        (schema.doctest_index().to_string(), doctest_index),
+        (schema.code().to_string(), code),
+        (schema.ignored().to_string(), DataValue::Bool(test.ignored)),
+    ])
+}
+
+fn test_target_params(
+    schema: &TestTargetSchema,
+    test_id: DataValue,
+    target: &TestTarget,
+) -> BTreeMap<String, DataValue> {
+    BTreeMap::from([
+        (schema.source_id().to_string(), test_id),
+        (schema.target_id().to_string(), target.id.to_cozo_uuid()),
+        (
+            schema.coverage().to_string(),
+            DataValue::from(target.coverage.as_str()),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use cozo::{Db, MemStorage, ScriptMutability};
+    use ploke_test_utils::test_run_phases_and_collect;
+    use std::collections::BTreeMap;
+    use syn_parser::parser::ParsedCodeGraph;
+
+    use crate::{
+        error::TransformError,
+        schema::edges::{TestSchema, TestTargetSchema},
+    };
+
+    use super::transform_tests;
+
+    #[test]
+    fn test_transform_tests() -> Result<(), Box<TransformError>> {
+        let successful_graphs = test_run_phases_and_collect("fixture_tests");
+        let mut merged =
+            ParsedCodeGraph::merge_new(successful_graphs).expect("Failed to merge graph");
+        let _tree = merged
+            .build_tree_and_prune()
+            .expect("Failed to build module tree");
+        let tests = std::mem::take(&mut merged.graph.tests);
+        let expected = tests.len();
+        let expected_targets: usize = tests.iter().map(|t| t.targets.len()).sum();
+        assert!(expected > 0, "fixture_tests should contain tests");
+
+        let db = Db::new(MemStorage::default()).expect("Failed to create database");
+        db.initialize().expect("Failed to initialize database");
+        TestSchema::SCHEMA.create_and_insert(&db)?;
+        TestTargetSchema::SCHEMA.create_and_insert(&db)?;
+
+        transform_tests(&db, tests)?;
+
+        let count = |script: &str| -> Result<usize, Box<TransformError>> {
+            let rows = db
+                .run_script(script, BTreeMap::new(), ScriptMutability::Immutable)
+                .map_err(|e| Box::new(TransformError::from(e)))?;
+            Ok(rows.rows[0][0].get_int().expect("count is an int") as usize)
+        };
+        assert_eq!(count("?[count(id)] := *test{ id }")?, expected);
+        assert_eq!(
+            count("?[count(source_id)] := *test_target{ source_id }")?,
+            expected_targets
+        );
+
+        Ok(())
+    }
+}
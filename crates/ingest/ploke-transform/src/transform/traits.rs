@@ -10,7 +10,7 @@ use super::*;
 
 #[instrument(skip_all)]
 pub(super) fn transform_traits(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     traits: Vec<TraitNode>,
 ) -> Result<(), TransformError> {
     // ANCHOR: transform_traits_methods
@@ -7,7 +7,7 @@ use super::*;
 
 #[instrument(skip_all)]
 pub(super) fn transform_type_aliases(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     type_aliases: Vec<TypeAliasNode>,
 ) -> Result<(), TransformError> {
     for type_alias in type_aliases.into_iter() {
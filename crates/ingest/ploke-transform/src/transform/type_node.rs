@@ -8,7 +8,7 @@ use super::*;
 
 #[instrument(skip_all)]
 pub(super) fn transform_types(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     type_nodes: Vec<TypeNode>,
 ) -> Result<(), TransformError> {
     fn process_trait_bounds(type_node: &TypeNode) -> DataValue {
@@ -1,6 +1,6 @@
 //! Union crate ingest: one merged graph + transform, then structural CU masks per target.
 
-use cozo::{Db, MemStorage};
+use cozo::Db;
 use ploke_core::CompilationUnitKey;
 use syn_parser::ParsedCodeGraph;
 use syn_parser::compilation_unit::{
@@ -10,15 +10,37 @@ use syn_parser::compilation_unit::{
 use syn_parser::parser::visitor::ActiveCfg;
 
 use crate::error::TransformError;
-use crate::transform::transform_parsed_graph;
 
-use super::insert_structural_compilation_unit_slice;
+use super::compilation_unit::write_structural_compilation_unit_slice;
+use super::{ScriptRunner, WriteBatch, write_parsed_graph};
+use crate::schema::CozoStorage;
 
-/// Merge all Cargo target roots, [`ParsedCodeGraph::build_tree_and_prune`], [`transform_parsed_graph`]
+/// Merge all Cargo target roots, [`ParsedCodeGraph::build_tree_and_prune`],
+/// [`transform_parsed_graph`](super::transform_parsed_graph)
 /// once, then persist one structural [`syn_parser::compilation_unit::StructuralCompilationUnitSlice`]
 /// per discovered target (same triple/profile/features baseline).
 pub fn transform_union_crate_and_structural_masks(
-    db: &Db<MemStorage>,
+    db: &Db<impl CozoStorage>,
+    parsed_graphs: Vec<ParsedCodeGraph>,
+    compilation_units: Option<Vec<CompilationUnitKey>>,
+) -> Result<(), TransformError> {
+    write_union_crate_and_structural_masks(db, db, parsed_graphs, compilation_units)
+}
+
+/// Like [`transform_union_crate_and_structural_masks`], but adds the writes to `batch` instead of
+/// running them.
+pub fn push_union_crate_and_structural_masks(
+    db: &Db<impl CozoStorage>,
+    batch: &WriteBatch,
+    parsed_graphs: Vec<ParsedCodeGraph>,
+    compilation_units: Option<Vec<CompilationUnitKey>>,
+) -> Result<(), TransformError> {
+    write_union_crate_and_structural_masks(db, batch, parsed_graphs, compilation_units)
+}
+
+fn write_union_crate_and_structural_masks(
+    db: &Db<impl CozoStorage>,
+    out: &impl ScriptRunner,
     parsed_graphs: Vec<ParsedCodeGraph>,
     compilation_units: Option<Vec<CompilationUnitKey>>,
 ) -> Result<(), TransformError> {
@@ -40,7 +62,7 @@ pub fn transform_union_crate_and_structural_masks(
     let parsed_for_masks = parsed_graphs.clone();
     let (merged, tree) = ParsedCodeGraph::merge_union_graph_and_prune_tree(parsed_graphs)
         .map_err(|e| TransformError::Transformation(e.to_string()))?;
-    transform_parsed_graph(db, merged, &tree)?;
+    write_parsed_graph(db, out, merged, &tree)?;
 
     let keys = compilation_units.unwrap_or_else(|| {
         let dims = CompilationUnitDimensionRequest::from_env_or_default();
@@ -82,7 +104,7 @@ pub fn transform_union_crate_and_structural_masks(
             slice,
             &active_cfg,
         );
-        insert_structural_compilation_unit_slice(db, &cfg_refined)?;
+        write_structural_compilation_unit_slice(out, &cfg_refined)?;
     }
     Ok(())
 }
@@ -7,7 +7,7 @@ use super::{secondary_nodes::process_fields, *};
 
 #[instrument(skip_all)]
 pub(super) fn transform_unions(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     unions: Vec<UnionNode>,
 ) -> Result<(), TransformError> {
     // union->onion (rust keywords)
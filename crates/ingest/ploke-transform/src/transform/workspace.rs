@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
-use cozo::{DataValue, Db, MemStorage, ScriptMutability};
+use cozo::{DataValue, Db, ScriptMutability};
 use ploke_core::WorkspaceId;
 use syn_parser::{ParsedWorkspace, discovery::workspace::WorkspaceMetadataSection};
 
@@ -9,15 +9,34 @@ use crate::error::TransformError;
 use crate::schema::crate_node::WorkspaceMetadataSchema;
 use tracing::instrument;
 
-use super::transform_parsed_graph;
+use super::{ScriptRunner, WriteBatch, write_parsed_graph};
+use crate::schema::CozoStorage;
 
 /// Transforms workspace metadata into a database row and then transforms each parsed crate graph.
 #[instrument(skip_all, fields(crate_count = parsed_workspace.crates.len()))]
 pub fn transform_parsed_workspace(
-    db: &Db<MemStorage>,
+    db: &Db<impl CozoStorage>,
     parsed_workspace: ParsedWorkspace,
 ) -> Result<(), TransformError> {
-    transform_workspace_metadata(db, &parsed_workspace.workspace)?;
+    write_parsed_workspace(db, db, parsed_workspace)
+}
+
+/// Like [`transform_parsed_workspace`], but adds the writes to `batch` instead of running them.
+#[instrument(skip_all, fields(crate_count = parsed_workspace.crates.len()))]
+pub fn push_parsed_workspace(
+    db: &Db<impl CozoStorage>,
+    batch: &WriteBatch,
+    parsed_workspace: ParsedWorkspace,
+) -> Result<(), TransformError> {
+    write_parsed_workspace(db, batch, parsed_workspace)
+}
+
+fn write_parsed_workspace(
+    db: &Db<impl CozoStorage>,
+    out: &impl ScriptRunner,
+    parsed_workspace: ParsedWorkspace,
+) -> Result<(), TransformError> {
+    transform_workspace_metadata(out, &parsed_workspace.workspace)?;
 
     for parsed_crate in parsed_workspace.crates {
         let mut parser_output = parsed_crate.parser_output;
@@ -32,14 +51,14 @@ pub fn transform_parsed_workspace(
             )
         })?;
 
-        transform_parsed_graph(db, merged_graph, &module_tree)?;
+        write_parsed_graph(db, out, merged_graph, &module_tree)?;
     }
 
     Ok(())
 }
 
 pub(super) fn transform_workspace_metadata(
-    db: &Db<MemStorage>,
+    db: &impl ScriptRunner,
     workspace: &WorkspaceMetadataSection,
 ) -> Result<(), TransformError> {
     let schema = &WorkspaceMetadataSchema::SCHEMA;
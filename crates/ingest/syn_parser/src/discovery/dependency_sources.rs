@@ -0,0 +1,357 @@
+//! Locates the sources of a workspace's resolved dependencies on the local filesystem.
+//!
+//! `Cargo.lock` lists every package cargo resolved for the workspace. Packages with a registry
+//! `source` are looked up in the workspace's `vendor/` directory first (as written by
+//! `cargo vendor`, with or without `--versioned-dirs`), then in cargo's registry source cache
+//! (`$CARGO_HOME/registry/src/<index>/<name>-<version>`). Nothing is downloaded: packages whose
+//! sources are not on disk are reported as missing.
+//!
+//! Workspace members, path dependencies and git dependencies are skipped.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::discovery::DiscoveryError;
+use crate::discovery::ManifestCtx;
+use crate::discovery::ManifestKind;
+use crate::discovery::WithDiscoveryManifestRead;
+use crate::discovery::WithDiscoveryManifestToml;
+
+/// Options for [`locate_dependency_sources`].
+#[derive(Debug, Clone, Default)]
+pub struct DependencySourceConfig {
+    /// Cargo's registry source cache. Defaults to `$CARGO_HOME/registry/src`, falling back to
+    /// `$HOME/.cargo/registry/src`.
+    pub registry_src: Option<PathBuf>,
+    /// Directory written by `cargo vendor`. Defaults to `<workspace_root>/vendor`.
+    pub vendor_dir: Option<PathBuf>,
+    /// Only locate these packages (by name). `None` locates every selected package.
+    pub packages: Option<Vec<String>>,
+    /// Also locate transitive dependencies. By default only packages that a workspace member
+    /// depends on directly are located.
+    pub include_transitive: bool,
+}
+
+/// A `[[package]]` entry of `Cargo.lock`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// e.g. `registry+https://github.com/rust-lang/crates.io-index`. `None` for workspace members
+    /// and path dependencies.
+    pub source: Option<String>,
+    /// Dependencies as written in the lockfile: `name`, `name version` or
+    /// `name version (source)`.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+impl LockedPackage {
+    /// Whether the package was resolved from a crates.io-style registry (including sparse
+    /// registries), as opposed to a git repository or a path.
+    pub fn is_registry(&self) -> bool {
+        self.source
+            .as_deref()
+            .is_some_and(|s| s.starts_with("registry+") || s.starts_with("sparse+"))
+    }
+
+    /// Whether `dependency`, an entry of some package's `dependencies`, refers to this package.
+    fn matches_dependency(&self, dependency: &str) -> bool {
+        let mut parts = dependency.split_whitespace();
+        parts.next() == Some(self.name.as_str())
+            && parts.next().is_none_or(|version| version == self.version)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoLock {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+/// Where the source of a dependency was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DependencyOrigin {
+    /// The workspace's `vendor/` directory.
+    Vendor,
+    /// Cargo's registry source cache.
+    Registry,
+}
+
+impl DependencyOrigin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DependencyOrigin::Vendor => "vendor",
+            DependencyOrigin::Registry => "registry",
+        }
+    }
+}
+
+/// A dependency whose source directory was found on disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencySource {
+    pub name: String,
+    pub version: String,
+    /// The lockfile `source` of the package.
+    pub source: String,
+    pub origin: DependencyOrigin,
+    /// The crate root, containing the package's `Cargo.toml`.
+    pub root_path: PathBuf,
+}
+
+/// Output of [`locate_dependency_sources`].
+#[derive(Debug, Clone, Default)]
+pub struct LocatedDependencies {
+    pub sources: Vec<DependencySource>,
+    /// Selected packages whose sources are neither vendored nor in the registry cache, e.g.
+    /// because they have not been fetched yet.
+    pub missing: Vec<LockedPackage>,
+}
+
+/// Reads the `[[package]]` entries of `<workspace_root>/Cargo.lock`.
+pub fn read_cargo_lock(workspace_root: &Path) -> Result<Vec<LockedPackage>, DiscoveryError> {
+    let lock_path = workspace_root.join("Cargo.lock");
+    let ctx = ManifestCtx {
+        kind: ManifestKind::Lockfile,
+        manifest_path: lock_path.clone(),
+        crate_path: None,
+        content: None,
+    };
+    let content = fs::read_to_string(&lock_path)
+        .with_discovery_err(ctx.clone())
+        .for_read()?;
+    let lock: CargoLock = toml::from_str(&content)
+        .with_discovery_err(ctx.with_content(&content))
+        .for_toml()?;
+    Ok(lock.package)
+}
+
+/// Cargo's registry source cache, from `$CARGO_HOME` or `$HOME/.cargo`.
+pub fn default_registry_src() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")))
+        .map(|cargo_home| cargo_home.join("registry").join("src"))
+}
+
+/// Locates the sources of the registry dependencies resolved in `<workspace_root>/Cargo.lock`.
+///
+/// # Errors
+/// [`DiscoveryError::ManifestRead`] / [`DiscoveryError::ManifestParse`] (with
+/// [`ManifestKind::Lockfile`]) when `Cargo.lock` cannot be read or parsed. Dependencies that
+/// cannot be found are not errors, see [`LocatedDependencies::missing`].
+pub fn locate_dependency_sources(
+    workspace_root: &Path,
+    config: &DependencySourceConfig,
+) -> Result<LocatedDependencies, DiscoveryError> {
+    let packages = read_cargo_lock(workspace_root)?;
+    let vendor_dir = config
+        .vendor_dir
+        .clone()
+        .unwrap_or_else(|| workspace_root.join("vendor"));
+    let registry_src = config.registry_src.clone().or_else(default_registry_src);
+    // Registry index directories, e.g. `index.crates.io-6f17d22bba15001f`.
+    let mut index_dirs: Vec<PathBuf> = registry_src
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    index_dirs.sort();
+
+    let local_packages: Vec<&LockedPackage> =
+        packages.iter().filter(|pkg| pkg.source.is_none()).collect();
+    let is_direct = |pkg: &LockedPackage| {
+        local_packages
+            .iter()
+            .flat_map(|local| local.dependencies.iter())
+            .any(|dep| pkg.matches_dependency(dep))
+    };
+
+    let mut located = LocatedDependencies::default();
+    for pkg in packages.iter().filter(|pkg| pkg.is_registry()) {
+        if config
+            .packages
+            .as_ref()
+            .is_some_and(|names| !names.contains(&pkg.name))
+        {
+            continue;
+        }
+        if !config.include_transitive && !is_direct(pkg) {
+            continue;
+        }
+        let versioned = format!("{}-{}", pkg.name, pkg.version);
+        let vendored = [vendor_dir.join(&versioned), vendor_dir.join(&pkg.name)]
+            .into_iter()
+            .find(|dir| is_package_dir(dir, pkg))
+            .map(|dir| (dir, DependencyOrigin::Vendor));
+        let found = vendored.or_else(|| {
+            index_dirs
+                .iter()
+                .map(|index| index.join(&versioned))
+                .find(|dir| dir.join("Cargo.toml").is_file())
+                .map(|dir| (dir, DependencyOrigin::Registry))
+        });
+        match found {
+            Some((root_path, origin)) => located.sources.push(DependencySource {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                source: pkg.source.clone().unwrap_or_default(),
+                origin,
+                root_path,
+            }),
+            None => located.missing.push(pkg.clone()),
+        }
+    }
+    tracing::debug!(
+        located = located.sources.len(),
+        missing = located.missing.len(),
+        "located dependency sources"
+    );
+    Ok(located)
+}
+
+/// Whether `dir` holds the vendored sources of `pkg`. Unversioned vendor directories are only
+/// accepted when their manifest declares the locked version.
+fn is_package_dir(dir: &Path, pkg: &LockedPackage) -> bool {
+    let Ok(manifest) = cargo_toml::Manifest::from_path(dir.join("Cargo.toml")) else {
+        return false;
+    };
+    manifest
+        .package
+        .as_ref()
+        .is_some_and(|package| package.name() == pkg.name && package.version() == pkg.version)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use tempfile::tempdir;
+
+    const CRATES_IO: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+    fn write_package(dir: &Path, name: &str, version: &str) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join("Cargo.toml"),
+            format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\nedition = \"2021\"\n"),
+        )
+        .unwrap();
+        fs::write(dir.join("src/lib.rs"), "pub fn api() {}\n").unwrap();
+    }
+
+    fn write_lockfile(workspace_root: &Path) {
+        fs::write(
+            workspace_root.join("Cargo.lock"),
+            format!(
+                r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "direct",
+ "vendored",
+ "absent",
+ "local_path",
+]
+
+[[package]]
+name = "direct"
+version = "1.2.3"
+source = "{CRATES_IO}"
+dependencies = [
+ "transitive",
+]
+
+[[package]]
+name = "transitive"
+version = "0.4.0"
+source = "{CRATES_IO}"
+
+[[package]]
+name = "vendored"
+version = "2.0.0"
+source = "{CRATES_IO}"
+
+[[package]]
+name = "absent"
+version = "0.1.0"
+source = "{CRATES_IO}"
+
+[[package]]
+name = "local_path"
+version = "0.1.0"
+"#
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn locates_direct_dependencies_in_vendor_then_registry() {
+        let tmp = tempdir().unwrap();
+        let workspace_root = tmp.path().join("ws");
+        let registry_src = tmp.path().join("registry/src");
+        let index = registry_src.join("index.crates.io-0000000000000000");
+        fs::create_dir_all(&workspace_root).unwrap();
+        write_lockfile(&workspace_root);
+        write_package(&index.join("direct-1.2.3"), "direct", "1.2.3");
+        write_package(&index.join("transitive-0.4.0"), "transitive", "0.4.0");
+        write_package(&index.join("vendored-2.0.0"), "vendored", "2.0.0");
+        write_package(&workspace_root.join("vendor/vendored"), "vendored", "2.0.0");
+
+        let config = DependencySourceConfig {
+            registry_src: Some(registry_src),
+            ..Default::default()
+        };
+        let located = locate_dependency_sources(&workspace_root, &config).unwrap();
+
+        let found: Vec<(&str, DependencyOrigin)> = located
+            .sources
+            .iter()
+            .map(|dep| (dep.name.as_str(), dep.origin))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("direct", DependencyOrigin::Registry),
+                ("vendored", DependencyOrigin::Vendor),
+            ]
+        );
+        assert_eq!(located.sources[0].root_path, index.join("direct-1.2.3"));
+        assert_eq!(located.sources[0].source, CRATES_IO);
+        let missing: Vec<&str> = located.missing.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(missing, vec!["absent"]);
+
+        let config = DependencySourceConfig {
+            include_transitive: true,
+            packages: Some(vec!["transitive".to_string()]),
+            ..config
+        };
+        let located = locate_dependency_sources(&workspace_root, &config).unwrap();
+        assert_eq!(located.sources.len(), 1);
+        assert_eq!(located.sources[0].name, "transitive");
+        assert!(located.missing.is_empty());
+    }
+
+    #[test]
+    fn missing_lockfile_is_a_lockfile_read_error() {
+        let tmp = tempdir().unwrap();
+        let err =
+            locate_dependency_sources(tmp.path(), &DependencySourceConfig::default()).unwrap_err();
+        assert!(matches!(
+            err,
+            DiscoveryError::ManifestRead {
+                manifest_kind: ManifestKind::Lockfile,
+                ..
+            }
+        ));
+    }
+}
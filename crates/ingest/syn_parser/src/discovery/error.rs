@@ -20,6 +20,8 @@ pub enum ManifestKind {
     WorkspaceRoot,
     /// A manifest inspected while walking ancestors from a crate (workspace discovery).
     AncestorWorkspace,
+    /// The workspace `Cargo.lock`, read when locating dependency sources.
+    Lockfile,
 }
 
 impl fmt::Display for ManifestKind {
@@ -28,6 +30,7 @@ impl fmt::Display for ManifestKind {
             ManifestKind::Crate => "crate",
             ManifestKind::WorkspaceRoot => "workspace_root",
             ManifestKind::AncestorWorkspace => "ancestor_workspace",
+            ManifestKind::Lockfile => "lockfile",
         })
     }
 }
@@ -1,3 +1,4 @@
+pub mod dependency_sources;
 pub mod error;
 pub mod single_crate;
 pub mod workspace;
@@ -8,6 +9,10 @@ use std::{
 };
 
 use cargo_toml::Manifest;
+pub use dependency_sources::{
+    DependencyOrigin, DependencySource, DependencySourceConfig, LocatedDependencies, LockedPackage,
+    locate_dependency_sources,
+};
 pub use error::*;
 use serde::Serialize;
 pub use single_crate::*;
@@ -101,7 +101,8 @@ pub enum DependencySpec {
         /// Whether to use the default features of the dependency.
         #[serde(rename = "default-features")]
         default_features: Option<bool>,
-        // Add other fields like 'package' if needed
+        /// The package name, when the dependency is renamed (`foo = { package = "bar" }`).
+        package: Option<String>,
     },
 }
 
@@ -207,6 +208,14 @@ impl DependencySpec {
         }
     }
 
+    /// Returns the package name if this is a detailed spec of a renamed dependency.
+    pub fn package(&self) -> Option<&str> {
+        match self {
+            DependencySpec::Detailed { package, .. } => package.as_deref(),
+            DependencySpec::Version(_) => None,
+        }
+    }
+
     /// Returns whether default features are enabled if this is a detailed spec.
     pub fn has_default_features(&self) -> Option<bool> {
         match self {
@@ -268,6 +277,7 @@ pub trait DependencyMap {
     ///         features: None,
     ///         optional: Some(false),
     ///         default_features: Some(true),
+    ///         package: None,
     ///     },
     /// )]));
     ///
@@ -300,6 +310,7 @@ pub trait DependencyMap {
     ///             features: Some(vec!["rt".into(), "macros".into()]),
     ///             optional: None,
     ///             default_features: Some(false),
+    ///             package: None,
     ///         },
     ///     ),
     /// ]));
@@ -333,6 +344,7 @@ pub trait DependencyMap {
     ///             features: Some(vec!["derive".into()]),
     ///             optional: Some(false),
     ///             default_features: Some(true),
+    ///             package: None,
     ///         },
     ///     ),
     ///     (
@@ -347,6 +359,7 @@ pub trait DependencyMap {
     ///             features: Some(vec!["macros".into()]),
     ///             optional: None,
     ///             default_features: Some(false),
+    ///             package: None,
     ///         },
     ///     ),
     /// ]));
@@ -387,6 +400,7 @@ pub trait DependencyMap {
     ///             features: None,
     ///             optional: None,
     ///             default_features: None,
+    ///             package: None,
     ///         },
     ///     ),
     /// ]));
@@ -435,6 +449,7 @@ pub trait DependencyMap {
     ///             features: None,
     ///             optional: Some(true),
     ///             default_features: None,
+    ///             package: None,
     ///         },
     ///     ),
     ///     (
@@ -473,6 +488,7 @@ pub trait DependencyMap {
     ///             features: None,
     ///             optional: None,
     ///             default_features: Some(true),
+    ///             package: None,
     ///         },
     ///     ),
     ///     (
@@ -670,6 +686,7 @@ fn dependency_spec_from_cargo(dep: &cargo_toml::Dependency) -> DependencySpec {
             },
             optional: Some(d.optional),
             default_features: Some(d.default_features),
+            package: d.package.clone(),
         },
         cargo_toml::Dependency::Inherited(i) => DependencySpec::Detailed {
             version: None,
@@ -685,6 +702,7 @@ fn dependency_spec_from_cargo(dep: &cargo_toml::Dependency) -> DependencySpec {
             },
             optional: Some(i.optional),
             default_features: None,
+            package: None,
         },
     }
 }
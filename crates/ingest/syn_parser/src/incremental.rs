@@ -0,0 +1,223 @@
+//! Incremental re-parsing of a crate whose graph has already been built.
+//!
+//! [`IncrementalParser`] keeps the per-file graphs of the last parse together with a hash of each
+//! file's contents. On [`IncrementalParser::update`] it re-runs discovery, parses only the files
+//! that were added or whose hash changed, and reuses the cached graphs of the others. The merged
+//! graph and module tree are then rebuilt from all per-file graphs, so that module structure,
+//! imports and the other resolved edges reflect the change everywhere in the crate, and the result
+//! is compared with the previous merged graph to produce a [`GraphDiff`].
+//!
+//! Re-parsing is not incremental for macros: when an added, removed or changed file contains
+//! `macro_rules!`, every file is parsed again, since expansions in unchanged files may differ.
+//!
+//! Callers keeping a database in sync apply the diff with `ploke_db::Database::apply_graph_diff`,
+//! which also retracts the embeddings and BM25 metadata of the changed nodes. The TUI keeps one
+//! parser per indexed crate this way, for `/index` on a crate and for `/workspace update`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ploke_core::file_hash::FileHash;
+use tracing::instrument;
+
+use crate::discovery::{TargetSelector, run_discovery_phase_with_target};
+use crate::error::SynParserError;
+use crate::parser::derives::DeriveTable;
+use crate::parser::graph::{FileChanges, GraphDiff};
+use crate::parser::{analyze_files_parallel, analyze_files_parallel_reusing};
+use crate::{ModuleTree, ParsedCodeGraph, collect_parse_results, merge_for_default_root};
+
+/// The last parsed state of one source file.
+#[derive(Debug, Clone)]
+struct CachedFile {
+    hash: FileHash,
+    /// Whether the file contains a `macro_rules!` definition.
+    defines_macros: bool,
+    graph: ParsedCodeGraph,
+}
+
+/// The contents hash of a file as it is on disk now.
+struct FileState {
+    hash: FileHash,
+    defines_macros: bool,
+}
+
+impl FileState {
+    fn read(path: &Path) -> Result<Self, SynParserError> {
+        let bytes = std::fs::read(path)?;
+        Ok(Self {
+            hash: FileHash::from_bytes(&bytes),
+            defines_macros: String::from_utf8_lossy(&bytes).contains("macro_rules!"),
+        })
+    }
+}
+
+/// Parses a crate once and keeps its graph up to date by re-parsing only the files that changed.
+#[derive(Debug)]
+pub struct IncrementalParser {
+    crate_root: PathBuf,
+    selected_target: Option<TargetSelector>,
+    derives: DeriveTable,
+    files: HashMap<PathBuf, CachedFile>,
+    merged: ParsedCodeGraph,
+    tree: ModuleTree,
+}
+
+impl IncrementalParser {
+    /// Parses the crate at `target_crate`, like [`try_run_phases_and_merge`].
+    ///
+    /// [`try_run_phases_and_merge`]: crate::try_run_phases_and_merge
+    pub fn new(target_crate: &Path) -> Result<Self, SynParserError> {
+        Self::with_target(target_crate, None)
+    }
+
+    /// Like [`IncrementalParser::new`], but passes `selected_target` through to discovery.
+    pub fn with_target(
+        target_crate: &Path,
+        selected_target: Option<&TargetSelector>,
+    ) -> Result<Self, SynParserError> {
+        Self::with_derives(target_crate, selected_target, DeriveTable::default())
+    }
+
+    /// Like [`IncrementalParser::with_target`], but resolves derive macros against `derives`,
+    /// on this parse and on every update.
+    pub fn with_derives(
+        target_crate: &Path,
+        selected_target: Option<&TargetSelector>,
+        derives: DeriveTable,
+    ) -> Result<Self, SynParserError> {
+        let crate_root = target_crate.to_path_buf();
+        let discovery_output = run_discovery_phase_with_target(
+            None,
+            std::slice::from_ref(&crate_root),
+            selected_target,
+        )?;
+        let mut states = HashMap::new();
+        for crate_context in discovery_output.crate_contexts.values() {
+            for file in &crate_context.files {
+                states.insert(file.clone(), FileState::read(file)?);
+            }
+        }
+        let graphs = collect_parse_results(analyze_files_parallel(&discovery_output, 0))?;
+        let files = cache_files(&graphs, states);
+        let (merged, tree) = merge_for_default_root(graphs, &derives)?;
+        Ok(Self {
+            crate_root,
+            selected_target: selected_target.cloned(),
+            derives,
+            files,
+            merged,
+            tree,
+        })
+    }
+
+    /// The merged graph of the crate as of the last parse.
+    pub fn merged_graph(&self) -> &ParsedCodeGraph {
+        &self.merged
+    }
+
+    /// The module tree of the crate as of the last parse.
+    pub fn module_tree(&self) -> &ModuleTree {
+        &self.tree
+    }
+
+    /// The table derive macros are resolved against.
+    pub fn derives(&self) -> &DeriveTable {
+        &self.derives
+    }
+
+    /// Re-parses the files of the crate that were added or changed since the last parse, rebuilds
+    /// the merged graph and module tree, and returns the changes to the merged graph.
+    ///
+    /// Returns an empty diff without re-merging when no file was added, removed or changed. On
+    /// error the previous state is kept.
+    #[instrument(skip(self), fields(crate_root = %self.crate_root.display()))]
+    pub fn update(&mut self) -> Result<GraphDiff, SynParserError> {
+        let discovery_output = run_discovery_phase_with_target(
+            None,
+            std::slice::from_ref(&self.crate_root),
+            self.selected_target.as_ref(),
+        )?;
+
+        let mut states = HashMap::new();
+        let mut changes = FileChanges::default();
+        let mut macros_changed = false;
+        for crate_context in discovery_output.crate_contexts.values() {
+            for file in &crate_context.files {
+                let state = FileState::read(file)?;
+                match self.files.get(file) {
+                    None => {
+                        macros_changed |= state.defines_macros;
+                        changes.added.push(file.clone());
+                    }
+                    Some(cached) if cached.hash != state.hash => {
+                        macros_changed |= state.defines_macros || cached.defines_macros;
+                        changes.modified.push(file.clone());
+                    }
+                    Some(_) => {}
+                }
+                states.insert(file.clone(), state);
+            }
+        }
+        for (file, cached) in &self.files {
+            if !states.contains_key(file) {
+                macros_changed |= cached.defines_macros;
+                changes.removed.push(file.clone());
+            }
+        }
+        if changes.is_empty() {
+            return Ok(GraphDiff::default());
+        }
+        changes.added.sort();
+        changes.modified.sort();
+        changes.removed.sort();
+
+        tracing::debug!(
+            added = changes.added.len(),
+            modified = changes.modified.len(),
+            removed = changes.removed.len(),
+            macros_changed,
+            "re-parsing changed files"
+        );
+        let reuse = |path: &Path| -> Option<ParsedCodeGraph> {
+            if macros_changed {
+                return None;
+            }
+            let cached = self.files.get(path)?;
+            let state = states.get(path)?;
+            (cached.hash == state.hash).then(|| cached.graph.clone())
+        };
+        let graphs =
+            collect_parse_results(analyze_files_parallel_reusing(&discovery_output, &reuse))?;
+        let files = cache_files(&graphs, states);
+        let (merged, tree) = merge_for_default_root(graphs, &self.derives)?;
+
+        let mut diff = self.merged.diff(&merged);
+        diff.files = changes;
+        self.files = files;
+        self.merged = merged;
+        self.tree = tree;
+        Ok(diff)
+    }
+}
+
+/// Pairs each per-file graph with the state of its file.
+fn cache_files(
+    graphs: &[ParsedCodeGraph],
+    mut states: HashMap<PathBuf, FileState>,
+) -> HashMap<PathBuf, CachedFile> {
+    graphs
+        .iter()
+        .filter_map(|graph| {
+            let state = states.remove(&graph.file_path)?;
+            Some((
+                graph.file_path.clone(),
+                CachedFile {
+                    hash: state.hash,
+                    defines_macros: state.defines_macros,
+                    graph: graph.clone(),
+                },
+            ))
+        })
+        .collect()
+}
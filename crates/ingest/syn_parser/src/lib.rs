@@ -36,12 +36,14 @@
 pub mod compilation_unit;
 pub mod discovery;
 pub mod error;
+pub mod incremental;
 pub mod parser;
 pub mod resolve;
 pub mod utils; // Don't re-export `LogStyle` to keep it clear its a utility trait.
 
 use std::path::{Path, PathBuf};
 
+use discovery::locate_dependency_sources;
 use discovery::run_discovery_phase;
 use discovery::run_discovery_phase_with_target;
 use error::SynParserError;
@@ -51,6 +53,9 @@ use itertools::Itertools;
 use parser::analyze_files_parallel;
 // Re-export key items for easier access
 pub use discovery::CrateContext;
+pub use discovery::DependencySource;
+pub use discovery::DependencySourceConfig;
+pub use discovery::LockedPackage;
 pub use discovery::ManifestCtx;
 pub use discovery::ManifestKind;
 pub use discovery::TargetSelector;
@@ -72,6 +77,7 @@ pub use resolve::module_tree::ModuleTree;
 
 use crate::compilation_unit::{CompilationUnitDimensionRequest, enumerate_compilation_unit_keys};
 use crate::discovery::workspace::WorkspaceMetadataSection;
+use crate::parser::derives::DeriveTable;
 use tracing::{info_span, instrument};
 
 /// Configuration for [`parse_workspace_with_config`].
@@ -85,6 +91,8 @@ pub struct ParseWorkspaceConfig<'a> {
     pub selected_crates: Option<&'a [&'a Path]>,
     /// When set, discovery uses this Cargo target selector for every member crate.
     pub target_selector: Option<&'a TargetSelector>,
+    /// The table derive macros are resolved against; `None` means [`DeriveTable::default`].
+    pub derives: Option<&'a DeriveTable>,
 }
 
 #[instrument(skip_all, fields(workspace = %target_workspace_dir.file_name()
@@ -139,6 +147,8 @@ pub fn parse_workspace_with_config(
         }
     }
 
+    let default_derives = DeriveTable::default();
+    let derives = config.derives.unwrap_or(&default_derives);
     let (successes, errors): (Vec<ParserOutput>, Vec<SynParserError>) = workspace_data
         .members
         .iter()
@@ -159,7 +169,7 @@ pub fn parse_workspace_with_config(
                 rel_path = %rel_path
             )
             .entered();
-            try_run_phases_and_merge_with_target(member, config.target_selector)
+            try_run_phases_and_merge_with_derives(member, config.target_selector, derives)
         })
         .partition_result();
 
@@ -167,11 +177,17 @@ pub fn parse_workspace_with_config(
         return Err(SynParserError::MultipleErrors(errors));
     }
 
+    let mut crates = successes
+        .into_iter()
+        .map(ParsedCrate::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    {
+        let _span = info_span!("resolve_workspace_paths").entered();
+        resolve::resolve_workspace_paths(&mut crates);
+    }
+
     Ok(ParsedWorkspace {
-        crates: successes
-            .into_iter()
-            .map(ParsedCrate::try_from)
-            .collect::<Result<Vec<_>, _>>()?,
+        crates,
         workspace: workspace_data,
     })
 }
@@ -185,11 +201,59 @@ pub fn parse_workspace(
         target_workspace_dir,
         &ParseWorkspaceConfig {
             selected_crates,
-            target_selector: None,
+            ..Default::default()
         },
     )
 }
 
+/// Parses the public API of the registry dependencies of the workspace at `workspace_root`.
+///
+/// Dependency sources are located from `Cargo.lock` with
+/// [`locate_dependency_sources`](crate::discovery::locate_dependency_sources); nothing is
+/// fetched. Each located crate is parsed like a workspace member and then reduced with
+/// [`ParsedCodeGraph::retain_public_api`].
+///
+/// A dependency that fails to parse does not fail the whole run, and is reported in
+/// [`ParsedDependencies::failures`] instead.
+#[instrument(skip(config), fields(workspace = %workspace_root.display()))]
+pub fn parse_dependency_sources(
+    workspace_root: &Path,
+    config: &DependencySourceConfig,
+) -> Result<ParsedDependencies, SynParserError> {
+    let located = locate_dependency_sources(workspace_root, config)?;
+
+    let mut crates = Vec::new();
+    let mut failures = Vec::new();
+    for source in located.sources {
+        let _span = info_span!(
+            "parse_dependency_source",
+            name = %source.name,
+            version = %source.version
+        )
+        .entered();
+        let parsed = try_run_phases_and_merge(&source.root_path).and_then(|mut output| {
+            if let Some(graph) = output.merged_graph.as_mut() {
+                graph.retain_public_api();
+            }
+            ParsedCrate::try_from(output)
+        });
+        match parsed {
+            Ok(parsed) => crates.push(ParsedDependency { source, parsed }),
+            Err(err) => {
+                tracing::warn!(name = %source.name, version = %source.version, %err,
+                    "failed to parse dependency source");
+                failures.push((source, err));
+            }
+        }
+    }
+
+    Ok(ParsedDependencies {
+        crates,
+        missing: located.missing,
+        failures,
+    })
+}
+
 fn normalize_selected_crates(workspace_root: &Path, selected_crates: &[&Path]) -> Vec<PathBuf> {
     selected_crates
         .iter()
@@ -240,7 +304,14 @@ pub fn try_run_phases_and_resolve_with_target(
 
     let results: Vec<Result<ParsedCodeGraph, SynParserError>> =
         analyze_files_parallel(&discovery_output, 0); // num_workers ignored by rayon bridge
+    collect_parse_results(results)
+}
 
+/// Splits the per-file results of the parsing phase into the parsed graphs, or the error to
+/// return when some or all files failed to parse.
+fn collect_parse_results(
+    results: Vec<Result<ParsedCodeGraph, SynParserError>>,
+) -> Result<Vec<ParsedCodeGraph>, SynParserError> {
     // Separate successes and errors
     let (successes_res, errors_res): (Vec<_>, Vec<_>) =
         results.into_iter().partition(Result::is_ok);
@@ -305,36 +376,7 @@ pub fn run_phases_and_collect(fixture_name: &str) -> Result<Vec<ParsedCodeGraph>
 
     let results: Vec<Result<ParsedCodeGraph, SynParserError>> =
         analyze_files_parallel(&discovery_output, 0); // num_workers ignored by rayon bridge
-
-    // Separate successes and errors
-    let (successes_res, errors_res): (Vec<_>, Vec<_>) =
-        results.into_iter().partition(Result::is_ok);
-
-    let successes: Vec<ParsedCodeGraph> = successes_res.into_iter().map(Result::unwrap).collect();
-    let error_list: Vec<SynParserError> = errors_res.into_iter().map(Result::unwrap_err).collect();
-
-    // Check if at least one crate carries the context (critical for merging)
-    if !successes.is_empty() && !successes.iter().any(|pr| pr.crate_context.is_some()) {
-        return Err(SynParserError::ParsedGraphError(
-            crate::parser::graph::ParsedGraphError::MissingCrateContext,
-        ));
-    }
-
-    if !error_list.is_empty() {
-        if successes.is_empty() {
-            // All failed - return combined errors
-            return Err(SynParserError::MultipleErrors(error_list));
-        } else {
-            // Partial success - return successes and errors
-            // The caller can match on this error to retrieve the partial successes if needed.
-            return Err(SynParserError::PartialParsing {
-                successes: PartialSuccess(successes),
-                errors: error_list,
-            });
-        }
-    }
-
-    Ok(successes)
+    collect_parse_results(results)
 }
 
 /// Runs the full parsing pipeline and returns a `ParserOutput`.
@@ -410,13 +452,36 @@ pub fn try_run_phases_and_merge(target_crate: &Path) -> Result<ParserOutput, Syn
 
 /// Like [`try_run_phases_and_merge`], but passes `selected_target` through to discovery (see
 /// [`try_run_phases_and_resolve_with_target`]).
-#[instrument()]
 pub fn try_run_phases_and_merge_with_target(
     target_crate: &Path,
     selected_target: Option<&TargetSelector>,
+) -> Result<ParserOutput, SynParserError> {
+    try_run_phases_and_merge_with_derives(target_crate, selected_target, &DeriveTable::default())
+}
+
+/// Like [`try_run_phases_and_merge_with_target`], but resolves derive macros against `derives`.
+#[instrument(skip(derives))]
+pub fn try_run_phases_and_merge_with_derives(
+    target_crate: &Path,
+    selected_target: Option<&TargetSelector>,
+    derives: &DeriveTable,
 ) -> Result<ParserOutput, SynParserError> {
     let parsed_graphs = try_run_phases_and_resolve_with_target(target_crate, selected_target)?;
-    let _parsed_file_count = parsed_graphs.len();
+    let (merged, tree) = merge_for_default_root(parsed_graphs, derives)?;
+    Ok(ParserOutput {
+        merged_graph: Some(merged),
+        module_tree: Some(tree),
+        parsed_graphs_for_masks: None,
+        compilation_units: None,
+    })
+}
+
+/// Merges the per-file graphs of the crate's default target root and builds its module tree,
+/// running the resolution passes.
+fn merge_for_default_root(
+    parsed_graphs: Vec<ParsedCodeGraph>,
+    derives: &DeriveTable,
+) -> Result<(ParsedCodeGraph, ModuleTree), SynParserError> {
     let partition = ParsedCodeGraph::partition_by_selected_roots(parsed_graphs)?;
     let selected_root = partition.select_default_root_path()?.to_path_buf();
     let mut merged = partition.merge_for_root(&selected_root)?;
@@ -429,18 +494,16 @@ pub fn try_run_phases_and_merge_with_target(
             graph_module_count
         )
         .entered();
-        merged.build_tree_and_prune_for_root_path(&selected_root)
+        merged.build_tree_and_prune_for_root_path_with_derives(&selected_root, derives)
     }
     .map_err(|err| SynParserError::InternalState(format!("Failed to build module tree: {err}")))?;
-    Ok(ParserOutput {
-        merged_graph: Some(merged),
-        module_tree: Some(tree),
-        parsed_graphs_for_masks: None,
-        compilation_units: None,
-    })
+    Ok((merged, tree))
 }
 
 /// Output of parsing a workspace.
+///
+/// Paths from one parsed member into another are already resolved, and their edges are in the
+/// graph of the crate containing the path.
 pub struct ParsedWorkspace {
     pub workspace: WorkspaceMetadataSection,
     pub crates: Vec<ParsedCrate>,
@@ -452,6 +515,21 @@ pub struct ParsedCrate {
     pub parser_output: ParserOutput,
 }
 
+/// A dependency parsed by [`parse_dependency_sources`].
+pub struct ParsedDependency {
+    pub source: DependencySource,
+    pub parsed: ParsedCrate,
+}
+
+/// Output of [`parse_dependency_sources`].
+pub struct ParsedDependencies {
+    pub crates: Vec<ParsedDependency>,
+    /// Dependencies listed in `Cargo.lock` whose sources were not found locally.
+    pub missing: Vec<LockedPackage>,
+    /// Dependencies whose sources were found but could not be parsed.
+    pub failures: Vec<(DependencySource, SynParserError)>,
+}
+
 /// The output of the parser, containing the merged `ParsedCodeGraph` and `ModuleTree`.
 pub struct ParserOutput {
     pub merged_graph: Option<ParsedCodeGraph>,
@@ -556,6 +634,10 @@ mod tests {
     //   Fixture: `create_workspace_fixture()`.
     //   Verifies that the returned `ParsedWorkspace` / `ParsedCrate` DTOs expose usable workspace
     //   identity, crate roots, merged graphs, module trees, and graph crate context.
+    // - `parse_workspace_resolves_paths_into_renamed_member_dependency`
+    //   Fixture: ad hoc two-member workspace where `crate_b` depends on `crate_a` renamed to `geo`.
+    //   Verifies that imports, impl headers, field types and calls in `crate_b` are linked to the
+    //   definitions in `crate_a`.
     use std::fs;
     use std::path::Path;
 
@@ -960,6 +1042,162 @@ edition = "2021"
         );
     }
 
+    #[test]
+    fn parse_workspace_resolves_paths_into_renamed_member_dependency() {
+        use crate::parser::graph::GraphNode as _;
+        use crate::parser::nodes::{AsAnyNodeId as _, CallableNodeId, PrimaryNodeId, TypeDefNode};
+        use crate::parser::relations::SyntacticRelation;
+
+        let tmp = create_workspace_fixture_with_members(&[
+            (
+                "crate_a",
+                r#"pub mod shapes {
+    pub struct Circle;
+
+    impl Circle {
+        pub fn new() -> Self {
+            Circle
+        }
+    }
+}
+
+pub trait Area {
+    fn area(&self) -> f64;
+}
+
+pub fn unit() {}
+"#,
+            ),
+            (
+                "crate_b",
+                r#"use geo::shapes::Circle;
+use geo::Area;
+
+pub struct Ring {
+    pub inner: Circle,
+}
+
+impl Area for Ring {
+    fn area(&self) -> f64 {
+        0.0
+    }
+}
+
+pub fn make() -> Ring {
+    geo::unit();
+    Ring { inner: Circle::new() }
+}
+"#,
+            ),
+        ]);
+        fs::write(
+            tmp.path().join("crate_b/Cargo.toml"),
+            r#"[package]
+name = "crate_b"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+geo = { package = "crate_a", path = "../crate_a" }
+"#,
+        )
+        .unwrap();
+
+        let parsed_workspace = parse_workspace(tmp.path(), None).unwrap();
+        let graph_of = |name: &str| {
+            parsed_workspace
+                .crates
+                .iter()
+                .find(|krate| krate.crate_context.name == name)
+                .and_then(|krate| krate.parser_output.merged_graph.as_ref())
+                .unwrap()
+        };
+        let graph_a = graph_of("crate_a");
+        let graph_b = graph_of("crate_b");
+
+        let circle = graph_a
+            .defined_types()
+            .iter()
+            .find(|def| def.name() == "Circle")
+            .map(|def| PrimaryNodeId::try_from(def.any_id()).unwrap())
+            .unwrap();
+        let area = graph_a
+            .traits()
+            .iter()
+            .find(|tr| tr.name == "Area")
+            .unwrap();
+        let unit = graph_a
+            .functions()
+            .iter()
+            .find(|f| f.name == "unit")
+            .unwrap();
+        let circle_new = graph_a
+            .impls()
+            .iter()
+            .flat_map(|imp| imp.methods.iter())
+            .find(|m| m.name == "new")
+            .unwrap();
+        let circle_import = graph_b
+            .use_statements()
+            .iter()
+            .find(|imp| imp.visible_name == "Circle")
+            .unwrap();
+        let make = graph_b
+            .functions()
+            .iter()
+            .find(|f| f.name == "make")
+            .unwrap();
+        let Some(TypeDefNode::Struct(ring)) = graph_b
+            .defined_types()
+            .iter()
+            .find(|def| def.name() == "Ring")
+        else {
+            panic!("`Ring` should be a struct");
+        };
+        let area_impl = graph_b.impls().first().unwrap();
+
+        let relations = graph_b.relations();
+        assert!(relations.contains(&SyntacticRelation::ImportedBy {
+            source: circle,
+            target: circle_import.id,
+        }));
+        assert!(relations.contains(&SyntacticRelation::ImplementsTrait {
+            source: area_impl.id,
+            target: area.id,
+        }));
+        assert!(
+            relations.contains(&SyntacticRelation::ImplementsTraitMethod {
+                source: area_impl.methods[0].id,
+                target: area.methods[0].id,
+            })
+        );
+        assert!(relations.contains(&SyntacticRelation::UsesType {
+            source: ring.fields[0].id.into(),
+            target: circle,
+        }));
+        for callee in [
+            CallableNodeId::from(unit.id),
+            CallableNodeId::from(circle_new.id),
+        ] {
+            assert!(
+                relations.contains(&SyntacticRelation::Calls {
+                    source: make.id.into(),
+                    target: callee,
+                }),
+                "missing call from `make` to {}",
+                callee.as_any()
+            );
+        }
+        assert!(
+            graph_b
+                .graph
+                .call_sites
+                .iter()
+                .all(|site| site.is_resolved()),
+            "every call site in `make` leads into `crate_a`"
+        );
+    }
+
     #[test]
     fn parse_workspace_committed_fixture_uses_multi_member_workspace() {
         let fixture_root = committed_workspace_fixture_root("ws_fixture_01");
@@ -1003,6 +1241,7 @@ edition = "2021"
             &ParseWorkspaceConfig {
                 selected_crates: Some(&[selected_member]),
                 target_selector: None,
+                ..Default::default()
             },
         )
         .expect("workspace-config parse should succeed for serde_derive_internals");
@@ -1061,6 +1300,7 @@ edition = "2021"
             &ParseWorkspaceConfig {
                 selected_crates: None,
                 target_selector: Some(&lib_sel),
+                ..Default::default()
             },
         )
         .expect("parse workspace with lib target selector");
@@ -1166,4 +1406,173 @@ edition = "2021"
             "legacy-safe parse mode should skip benches when lib/bin exists"
         );
     }
+
+    #[test]
+    fn parse_dependency_sources_keeps_only_public_api() {
+        use crate::parser::graph::GraphNode as _;
+        use crate::parser::nodes::TypeDefNode;
+
+        let tmp = tempdir().unwrap();
+        let workspace_root = tmp.path().join("ws");
+        let dep_root = tmp
+            .path()
+            .join("registry/src/index.crates.io-0000000000000000/dep_api-0.3.1");
+        fs::create_dir_all(&workspace_root).unwrap();
+        fs::create_dir_all(dep_root.join("src")).unwrap();
+        fs::write(
+            workspace_root.join("Cargo.lock"),
+            r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "dep_api",
+]
+
+[[package]]
+name = "dep_api"
+version = "0.3.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dep_root.join("Cargo.toml"),
+            "[package]\nname = \"dep_api\"\nversion = \"0.3.1\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dep_root.join("src/lib.rs"),
+            r#"pub struct Client {
+    pub name: String,
+    secret: u64,
+}
+
+impl Client {
+    pub fn connect(name: String) -> Self {
+        Self { name, secret: helper() }
+    }
+
+    fn reconnect(&self) {}
+}
+
+struct Hidden;
+
+impl Hidden {
+    pub fn unreachable(&self) {}
+}
+
+fn helper() -> u64 {
+    0
+}
+"#,
+        )
+        .unwrap();
+
+        let config = DependencySourceConfig {
+            registry_src: Some(tmp.path().join("registry/src")),
+            ..Default::default()
+        };
+        let parsed = parse_dependency_sources(&workspace_root, &config).unwrap();
+        assert!(parsed.failures.is_empty() && parsed.missing.is_empty());
+        assert_eq!(parsed.crates.len(), 1);
+
+        let dependency = &parsed.crates[0];
+        assert_eq!(dependency.source.name, "dep_api");
+        assert_eq!(dependency.parsed.crate_context.version, "0.3.1");
+        let graph = dependency
+            .parsed
+            .parser_output
+            .merged_graph
+            .as_ref()
+            .unwrap();
+
+        assert!(graph.functions().is_empty(), "`helper` is private");
+        let type_names = graph
+            .defined_types()
+            .iter()
+            .map(|def| def.name())
+            .collect_vec();
+        assert_eq!(type_names, vec!["Client"]);
+        let method_names = graph
+            .impls()
+            .iter()
+            .flat_map(|imp| imp.methods.iter())
+            .map(|m| m.name.as_str())
+            .collect_vec();
+        assert_eq!(method_names, vec!["connect"]);
+        let TypeDefNode::Struct(client) = &graph.defined_types()[0] else {
+            panic!("`Client` should be a struct");
+        };
+        assert_eq!(client.fields.len(), 1, "`secret` is private");
+    }
+
+    #[test]
+    fn incremental_update_reports_changes_of_edited_files_only() {
+        use crate::incremental::IncrementalParser;
+        use crate::parser::graph::GraphNode as _;
+        use crate::parser::nodes::AsAnyNodeId as _;
+
+        let tmp = tempdir().unwrap();
+        let crate_root = tmp.path();
+        fs::create_dir_all(crate_root.join("src")).unwrap();
+        fs::write(
+            crate_root.join("Cargo.toml"),
+            "[package]\nname = \"incremental_fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        fs::write(crate_root.join("src/lib.rs"), "pub mod a;\npub mod b;\n").unwrap();
+        fs::write(
+            crate_root.join("src/a.rs"),
+            "pub fn keep() {}\npub fn edit() -> u8 {\n    0\n}\n",
+        )
+        .unwrap();
+        fs::write(crate_root.join("src/b.rs"), "pub struct Untouched;\n").unwrap();
+
+        let mut parser = IncrementalParser::new(crate_root).unwrap();
+        assert!(parser.update().unwrap().is_empty(), "nothing changed yet");
+
+        let function_id = |parser: &IncrementalParser, name: &str| {
+            parser
+                .merged_graph()
+                .functions()
+                .iter()
+                .find(|f| f.name == name)
+                .map(|f| f.id.as_any())
+        };
+        let keep = function_id(&parser, "keep").unwrap();
+        let edit = function_id(&parser, "edit").unwrap();
+        let untouched = parser
+            .merged_graph()
+            .defined_types()
+            .iter()
+            .find(|def| def.name() == "Untouched")
+            .unwrap()
+            .any_id();
+
+        fs::write(
+            crate_root.join("src/a.rs"),
+            "pub fn keep() {}\npub fn edit() -> u16 {\n    0\n}\npub fn added() {}\n",
+        )
+        .unwrap();
+        let diff = parser.update().unwrap();
+        assert_eq!(diff.files.modified.len(), 1);
+        assert!(diff.files.modified[0].ends_with("src/a.rs"));
+        assert!(diff.files.added.is_empty() && diff.files.removed.is_empty());
+        let added = function_id(&parser, "added").unwrap();
+        assert!(diff.added.contains(&added));
+        assert!(diff.modified.contains(&edit), "return type changed");
+        assert!(!diff.modified.contains(&keep) && !diff.added.contains(&keep));
+        assert!(diff.stale_ids().all(|id| id != untouched));
+        assert!(diff.removed.is_empty());
+
+        fs::write(crate_root.join("src/lib.rs"), "pub mod a;\n").unwrap();
+        fs::remove_file(crate_root.join("src/b.rs")).unwrap();
+        let diff = parser.update().unwrap();
+        assert_eq!(diff.files.removed.len(), 1);
+        assert!(diff.files.removed[0].ends_with("src/b.rs"));
+        assert!(diff.removed.contains(&untouched));
+        assert!(function_id(&parser, "keep").is_some());
+    }
 }
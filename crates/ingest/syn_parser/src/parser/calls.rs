@@ -0,0 +1,76 @@
+//! Call sites recorded from function and method bodies.
+//!
+//! The `CodeVisitor` walks each body it processes and records one [`CallSite`] per syntactic
+//! call: free-function paths (`foo()`, `crate::a::foo()`), associated-function paths
+//! (`Type::new()`, `Self::helper()`), method calls (`x.len()`, `self.step()`), and macro
+//! invocations (`println!(..)`).
+//!
+//! Call sites are collected during parallel parsing, when no cross-file information is
+//! available, so they only store the path as written. After the `ModuleTree` is built,
+//! `resolve::call_resolver` fills in [`CallSite::resolved`] where the path can be resolved to a
+//! crate-local callable and adds a matching [`SyntacticRelation::Calls`] edge. Call sites that
+//! cannot be resolved (external crates, trait methods on unknown receivers, etc.) are kept as-is
+//! so the path is still available downstream.
+//!
+//! [`SyntacticRelation::Calls`]: crate::parser::relations::SyntacticRelation::Calls
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::nodes::{CallableNodeId, ModuleNodeId};
+
+/// The syntactic form of a call site.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CallKind {
+    /// A call through a path that names a free function, e.g. `foo()` or `crate::a::foo()`.
+    Function,
+    /// A call through a path whose parent segment names a type or trait, e.g. `Type::new()`,
+    /// `Self::helper()` or `<T as Trait>::method()`.
+    AssociatedFunction,
+    /// A method call on `self`, e.g. `self.step()`.
+    SelfMethod,
+    /// A method call on any other receiver, e.g. `items.len()`.
+    Method,
+    /// A macro invocation, e.g. `println!(..)` or `my_macro!(..)`.
+    Macro,
+}
+
+impl CallKind {
+    /// Name of the variant, used when storing call sites in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallKind::Function => "Function",
+            CallKind::AssociatedFunction => "AssociatedFunction",
+            CallKind::SelfMethod => "SelfMethod",
+            CallKind::Method => "Method",
+            CallKind::Macro => "Macro",
+        }
+    }
+}
+
+/// A single call expression found in the body of a function or method.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct CallSite {
+    /// The function or method whose body contains the call.
+    pub caller: CallableNodeId,
+    /// The module the caller is defined in, used as the scope for path resolution.
+    pub module_id: ModuleNodeId,
+    pub kind: CallKind,
+    /// Path segments as written, without generic arguments.
+    /// For method calls this is just the method name, e.g. `["len"]`.
+    pub path: Vec<String>,
+    /// Byte span of the call expression in the source file.
+    pub span: (usize, usize),
+    /// The crate-local callee, if the call could be resolved after the module tree was built.
+    pub resolved: Option<CallableNodeId>,
+}
+
+impl CallSite {
+    /// Returns the last segment of the call path, i.e. the name of the callee.
+    pub fn callee_name(&self) -> Option<&str> {
+        self.path.last().map(String::as_str)
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.resolved.is_some()
+    }
+}
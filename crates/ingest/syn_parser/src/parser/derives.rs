@@ -0,0 +1,231 @@
+//! The traits implemented by derive macros.
+//!
+//! The `CodeVisitor` turns each path of a `#[derive(...)]` attribute into an
+//! [`ImplNode`](crate::parser::nodes::ImplNode) marked as derived. Once the graph is merged,
+//! `resolve::derive_resolver` resolves the macro path through the imports of the containing
+//! module and looks it up in a [`DeriveTable`] to find the trait it implements:
+//!
+//! - Derives listed with a trait get that trait, e.g. `thiserror::Error` implements
+//!   `core::error::Error`.
+//! - Derives listed without a trait, such as `derive_builder::Builder`, generate other items
+//!   than a trait impl, and their impls are removed.
+//! - Other derives are assumed to implement the trait of the same path, which holds for
+//!   `serde::Serialize`, `clap::Parser` and most derives re-exported next to their trait.
+//!
+//! The built-in derives of the standard library are listed by name, and also match when written
+//! with a `std`, `core` or `alloc` path such as `core::fmt::Debug`.
+
+use std::collections::HashMap;
+
+/// Environment variable adding entries to [`DeriveTable::from_env_or_default`].
+pub const DERIVE_TABLE_ENV: &str = "PLOKE_DERIVE_TABLE";
+
+const STD_DERIVES: &[(&str, &str)] = &[
+    ("Clone", "core::clone::Clone"),
+    ("Copy", "core::marker::Copy"),
+    ("Debug", "core::fmt::Debug"),
+    ("Default", "core::default::Default"),
+    ("Eq", "core::cmp::Eq"),
+    ("Hash", "core::hash::Hash"),
+    ("Ord", "core::cmp::Ord"),
+    ("PartialEq", "core::cmp::PartialEq"),
+    ("PartialOrd", "core::cmp::PartialOrd"),
+];
+
+/// Well-known third-party derives whose trait is not at the path of the macro.
+const THIRD_PARTY_DERIVES: &[(&str, Option<&str>)] = &[
+    ("serde_derive::Serialize", Some("serde::Serialize")),
+    ("serde_derive::Deserialize", Some("serde::Deserialize")),
+    ("thiserror::Error", Some("core::error::Error")),
+    ("derive_more::Display", Some("core::fmt::Display")),
+    ("derive_more::From", Some("core::convert::From")),
+    ("derive_more::Deref", Some("core::ops::Deref")),
+    ("derive_more::DerefMut", Some("core::ops::DerefMut")),
+    ("strum::Display", Some("core::fmt::Display")),
+    ("strum::EnumString", Some("core::str::FromStr")),
+    ("strum::AsRefStr", Some("core::convert::AsRef")),
+    ("strum::EnumIter", Some("strum::IntoEnumIterator")),
+    ("strum_macros::Display", Some("core::fmt::Display")),
+    ("strum_macros::EnumString", Some("core::str::FromStr")),
+    ("strum_macros::AsRefStr", Some("core::convert::AsRef")),
+    ("strum_macros::EnumIter", Some("strum::IntoEnumIterator")),
+    (
+        "num_derive::FromPrimitive",
+        Some("num_traits::FromPrimitive"),
+    ),
+    ("num_derive::ToPrimitive", Some("num_traits::ToPrimitive")),
+    ("derive_builder::Builder", None),
+    ("derive_new::new", None),
+    ("getset::Getters", None),
+    ("getset::MutGetters", None),
+    ("getset::Setters", None),
+];
+
+fn split_path(path: &str) -> Vec<String> {
+    path.split("::")
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Maps the resolved paths of derive macros, joined with `::`, to the path of the trait they
+/// implement, or to `None` for derives that do not implement a trait.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeriveTable {
+    entries: HashMap<String, Option<Vec<String>>>,
+}
+
+impl Default for DeriveTable {
+    /// The built-in derives of the standard library and the well-known third-party derives.
+    fn default() -> Self {
+        let std_derives = STD_DERIVES
+            .iter()
+            .map(|(name, trait_path)| (*name, Some(*trait_path)));
+        let entries = std_derives
+            .chain(THIRD_PARTY_DERIVES.iter().copied())
+            .map(|(macro_path, trait_path)| (macro_path.to_string(), trait_path.map(split_path)))
+            .collect();
+        Self { entries }
+    }
+}
+
+impl DeriveTable {
+    /// A table without any entry, in which every derive implements the trait at its own path.
+    pub fn empty() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// The default table, extended with the entries of the [`DERIVE_TABLE_ENV`] variable.
+    ///
+    /// Entries are `;`-separated `macro::path=trait::path` pairs. An empty trait path marks a
+    /// derive that implements no trait, e.g.
+    /// `PLOKE_DERIVE_TABLE="my_macros::Model=my_crate::Model;my_macros::Builder="`.
+    pub fn from_env_or_default() -> Self {
+        let mut table = Self::default();
+        if let Ok(value) = std::env::var(DERIVE_TABLE_ENV) {
+            table.extend_from_str(&value);
+        }
+        table
+    }
+
+    /// Adds the `;`-separated `macro=trait` entries of `value`, see
+    /// [`DeriveTable::from_env_or_default`]. Malformed entries are skipped with a warning.
+    pub fn extend_from_str(&mut self, value: &str) {
+        for entry in value
+            .split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            match entry.split_once('=') {
+                Some((macro_path, trait_path)) if !macro_path.trim().is_empty() => {
+                    self.insert_str(macro_path, trait_path);
+                }
+                _ => tracing::warn!(
+                    entry,
+                    "skipping derive table entry, expected `macro::path=trait::path`"
+                ),
+            }
+        }
+    }
+
+    /// Records that the derive at `macro_path` implements the trait at the `::`-separated
+    /// `trait_path`, or no trait if `trait_path` is empty.
+    pub fn insert_str(&mut self, macro_path: &str, trait_path: &str) -> &mut Self {
+        let trait_path = split_path(trait_path);
+        if trait_path.is_empty() {
+            self.insert_without_trait(macro_path.trim())
+        } else {
+            self.insert(macro_path.trim(), trait_path)
+        }
+    }
+
+    /// Records that the derive at `macro_path` implements the trait at `trait_path`.
+    pub fn insert(&mut self, macro_path: &str, trait_path: Vec<String>) -> &mut Self {
+        self.entries
+            .insert(macro_path.to_string(), Some(trait_path));
+        self
+    }
+
+    /// Records that the derive at `macro_path` does not implement a trait.
+    pub fn insert_without_trait(&mut self, macro_path: &str) -> &mut Self {
+        self.entries.insert(macro_path.to_string(), None);
+        self
+    }
+
+    /// Removes the entry of `macro_path`, so that it implements the trait at its own path.
+    pub fn remove(&mut self, macro_path: &str) -> &mut Self {
+        self.entries.remove(macro_path);
+        self
+    }
+
+    /// The path of the trait implemented by the derive at the resolved `macro_path`, or `None`
+    /// if it implements no trait.
+    pub fn trait_path(&self, macro_path: &[String]) -> Option<Vec<String>> {
+        if let Some(entry) = self.entries.get(&macro_path.join("::")) {
+            return entry.clone();
+        }
+        let is_std =
+            macro_path.len() > 1 && matches!(macro_path[0].as_str(), "std" | "core" | "alloc");
+        if is_std && let Some(entry) = macro_path.last().and_then(|name| self.entries.get(name)) {
+            return entry.clone();
+        }
+        Some(macro_path.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(p: &str) -> Vec<String> {
+        split_path(p)
+    }
+
+    #[test]
+    fn test_default_table_maps_std_and_third_party_derives() {
+        let table = DeriveTable::default();
+        assert_eq!(
+            table.trait_path(&path("Hash")),
+            Some(path("core::hash::Hash"))
+        );
+        assert_eq!(
+            table.trait_path(&path("std::fmt::Debug")),
+            Some(path("core::fmt::Debug"))
+        );
+        assert_eq!(
+            table.trait_path(&path("thiserror::Error")),
+            Some(path("core::error::Error"))
+        );
+        assert_eq!(
+            table.trait_path(&path("serde::Serialize")),
+            Some(path("serde::Serialize"))
+        );
+        assert_eq!(table.trait_path(&path("derive_builder::Builder")), None);
+    }
+
+    #[test]
+    fn test_extend_from_str_overrides_entries() {
+        let mut table = DeriveTable::default();
+        table.extend_from_str(
+            "my_macros::Model = my_crate::Model; derive_builder::Builder=x::B;getset::Getters=;\
+             no_separator;=x::Y;",
+        );
+        assert_eq!(
+            table.trait_path(&path("my_macros::Model")),
+            Some(path("my_crate::Model"))
+        );
+        assert_eq!(
+            table.trait_path(&path("derive_builder::Builder")),
+            Some(path("x::B"))
+        );
+        assert_eq!(table.trait_path(&path("getset::Getters")), None);
+        assert_eq!(
+            table.trait_path(&path("no_separator")),
+            Some(path("no_separator")),
+            "malformed entries are skipped"
+        );
+    }
+}
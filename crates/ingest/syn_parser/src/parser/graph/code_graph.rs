@@ -3,12 +3,15 @@ use serde::{Deserialize, Serialize};
 use crate::error::SynParserError;
 
 use crate::parser::{
+    calls::CallSite,
+    macro_expansion::MacroExpansion,
     // Updated node types
     nodes::{
         ConstNode, FunctionNode, ImplNode, ImportNode, MacroNode, ModuleNode, StaticNode,
         TraitNode, TypeDefNode, UnresolvedNode,
     },
     relations::SyntacticRelation, // Use new relation enum
+    test_items::TestNode,
     types::TypeNode,
 };
 
@@ -41,6 +44,15 @@ pub struct CodeGraph {
     pub use_statements: Vec<ImportNode>,
     // Unresolved items (e.g., from include! macros that couldn't be expanded)
     pub unresolved_nodes: Vec<UnresolvedNode>,
+    // Call sites found in function and method bodies
+    #[serde(default)]
+    pub call_sites: Vec<CallSite>,
+    // Expanded invocations of crate-local `macro_rules!` macros
+    #[serde(default)]
+    pub macro_expansions: Vec<MacroExpansion>,
+    // Test functions and doc-tests, found once the module tree is built
+    #[serde(default)]
+    pub tests: Vec<TestNode>,
 }
 
 impl GraphAccess for CodeGraph {
@@ -176,6 +188,9 @@ impl CodeGraph {
         self.macros.append(&mut other.macros);
         self.use_statements.append(&mut other.use_statements);
         self.unresolved_nodes.append(&mut other.unresolved_nodes);
+        self.call_sites.append(&mut other.call_sites);
+        self.macro_expansions.append(&mut other.macro_expansions);
+        self.tests.append(&mut other.tests);
         Ok(())
     }
     // ANCHOR_END: code_graph_append_all
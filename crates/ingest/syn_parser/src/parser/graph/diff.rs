@@ -0,0 +1,298 @@
+//! Differences between two merged graphs of the same crate.
+//!
+//! Node ids do not depend on spans, so an item keeps its id across re-parses as long as its
+//! file, module path, name, kind and cfgs are unchanged. A node is *modified* when it has the
+//! same id in both graphs but differs in any field, span included, since the database stores
+//! spans along with the node.
+//!
+//! Nodes are compared at the level of primary items: the methods of impls and traits, the
+//! variants of enums and the fields of structs, unions and variants are reported with their
+//! owner, but only when the owner itself is added, removed or modified. Params, generic params
+//! and attributes have no id of their own and change with their owner.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+
+use ploke_core::TypeId;
+
+use crate::parser::{
+    calls::CallSite,
+    macro_expansion::MacroExpansion,
+    nodes::{
+        AnyNodeId, AsAnyNodeId, ConstNode, FieldNode, FunctionNode, ImplNode, ImportNode,
+        MacroNode, ModuleNode, ModuleNodeId, StaticNode, TraitNode, TypeDefNode,
+    },
+    relations::SyntacticRelation,
+    test_items::TestNode,
+};
+
+use super::{GraphAccess, GraphNode, ParsedCodeGraph};
+
+/// Files of the crate that were added, removed or changed since the previous parse.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileChanges {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+}
+
+impl FileChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// The changes between an older and a newer merged graph of the same crate, see
+/// [`ParsedCodeGraph::diff`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphDiff {
+    /// The files whose changes produced this diff, when known.
+    pub files: FileChanges,
+    /// Nodes only in the newer graph.
+    pub added: BTreeSet<AnyNodeId>,
+    /// Nodes only in the older graph.
+    pub removed: BTreeSet<AnyNodeId>,
+    /// Nodes in both graphs that differ.
+    pub modified: BTreeSet<AnyNodeId>,
+    /// Type nodes only in the newer graph. Types only in the older graph are not tracked, since
+    /// type nodes are shared by every item naming the type.
+    pub added_types: BTreeSet<TypeId>,
+    /// Relations only in the newer graph.
+    pub added_relations: BTreeSet<SyntacticRelation>,
+    /// Relations only in the older graph.
+    pub removed_relations: BTreeSet<SyntacticRelation>,
+    /// Callers whose call sites differ, including in how they were resolved.
+    pub call_site_owners: BTreeSet<AnyNodeId>,
+    /// Modules whose expanded macro invocations differ.
+    pub macro_expansion_owners: BTreeSet<ModuleNodeId>,
+    /// Test functions and documented items whose tests differ, including in their targets.
+    pub test_owners: BTreeSet<AnyNodeId>,
+}
+
+impl GraphDiff {
+    /// Returns `true` when the two graphs hold the same nodes, relations and parse details.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && self.added_types.is_empty()
+            && self.added_relations.is_empty()
+            && self.removed_relations.is_empty()
+            && self.call_site_owners.is_empty()
+            && self.macro_expansion_owners.is_empty()
+            && self.test_owners.is_empty()
+    }
+
+    /// Nodes whose stored rows are out of date: the removed and the modified ones.
+    pub fn stale_ids(&self) -> impl Iterator<Item = AnyNodeId> + '_ {
+        self.removed.iter().chain(&self.modified).copied()
+    }
+
+    /// Nodes whose rows must be written from the newer graph: the added and the modified ones.
+    pub fn changed_ids(&self) -> impl Iterator<Item = AnyNodeId> + '_ {
+        self.added.iter().chain(&self.modified).copied()
+    }
+}
+
+/// A primary node of either graph, compared as a whole.
+#[derive(PartialEq)]
+enum PrimaryRef<'a> {
+    Function(&'a FunctionNode),
+    TypeDef(&'a TypeDefNode),
+    Trait(&'a TraitNode),
+    Impl(&'a ImplNode),
+    Module(&'a ModuleNode),
+    Const(&'a ConstNode),
+    Static(&'a StaticNode),
+    Macro(&'a MacroNode),
+    Import(&'a ImportNode),
+}
+
+impl PrimaryRef<'_> {
+    /// The ids of the nodes stored as part of this one.
+    fn child_ids(&self) -> Vec<AnyNodeId> {
+        fn field_ids(fields: &[FieldNode]) -> impl Iterator<Item = AnyNodeId> + '_ {
+            fields.iter().map(|field| field.id.as_any())
+        }
+        match self {
+            PrimaryRef::TypeDef(TypeDefNode::Struct(s)) => field_ids(&s.fields).collect(),
+            PrimaryRef::TypeDef(TypeDefNode::Union(u)) => field_ids(&u.fields).collect(),
+            PrimaryRef::TypeDef(TypeDefNode::Enum(e)) => e
+                .variants
+                .iter()
+                .flat_map(|variant| {
+                    std::iter::once(variant.id.as_any()).chain(field_ids(&variant.fields))
+                })
+                .collect(),
+            PrimaryRef::Trait(t) => t.methods.iter().map(|m| m.id.as_any()).collect(),
+            PrimaryRef::Impl(i) => i.methods.iter().map(|m| m.id.as_any()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn primary_nodes(graph: &ParsedCodeGraph) -> HashMap<AnyNodeId, PrimaryRef<'_>> {
+    let mut nodes = HashMap::new();
+    nodes.extend(
+        graph
+            .functions()
+            .iter()
+            .map(|n| (n.id.as_any(), PrimaryRef::Function(n))),
+    );
+    nodes.extend(
+        graph
+            .defined_types()
+            .iter()
+            .map(|n| (n.any_id(), PrimaryRef::TypeDef(n))),
+    );
+    nodes.extend(
+        graph
+            .traits()
+            .iter()
+            .map(|n| (n.id.as_any(), PrimaryRef::Trait(n))),
+    );
+    nodes.extend(
+        graph
+            .impls()
+            .iter()
+            .map(|n| (n.id.as_any(), PrimaryRef::Impl(n))),
+    );
+    nodes.extend(
+        graph
+            .modules()
+            .iter()
+            .map(|n| (n.id.as_any(), PrimaryRef::Module(n))),
+    );
+    nodes.extend(
+        graph
+            .consts()
+            .iter()
+            .map(|n| (n.id.as_any(), PrimaryRef::Const(n))),
+    );
+    nodes.extend(
+        graph
+            .statics()
+            .iter()
+            .map(|n| (n.id.as_any(), PrimaryRef::Static(n))),
+    );
+    nodes.extend(
+        graph
+            .macros()
+            .iter()
+            .map(|n| (n.id.as_any(), PrimaryRef::Macro(n))),
+    );
+    nodes.extend(
+        graph
+            .use_statements()
+            .iter()
+            .map(|n| (n.id.as_any(), PrimaryRef::Import(n))),
+    );
+    nodes
+}
+
+fn call_sites_by_owner(graph: &ParsedCodeGraph) -> HashMap<AnyNodeId, HashSet<&CallSite>> {
+    let mut owners: HashMap<AnyNodeId, HashSet<&CallSite>> = HashMap::new();
+    for site in &graph.graph.call_sites {
+        owners.entry(site.caller.as_any()).or_default().insert(site);
+    }
+    owners
+}
+
+fn expansions_by_owner(graph: &ParsedCodeGraph) -> HashMap<ModuleNodeId, Vec<&MacroExpansion>> {
+    let mut owners: HashMap<ModuleNodeId, Vec<&MacroExpansion>> = HashMap::new();
+    for expansion in &graph.graph.macro_expansions {
+        owners
+            .entry(expansion.module_id)
+            .or_default()
+            .push(expansion);
+    }
+    owners
+}
+
+fn tests_by_owner(graph: &ParsedCodeGraph) -> HashMap<AnyNodeId, Vec<&TestNode>> {
+    let mut owners: HashMap<AnyNodeId, Vec<&TestNode>> = HashMap::new();
+    for test in &graph.graph.tests {
+        owners.entry(test.owner).or_default().push(test);
+    }
+    owners
+}
+
+/// Collects the keys of `old` and `new` whose values differ, including keys only in one map.
+fn differing_keys<K, V>(old: &HashMap<K, V>, new: &HashMap<K, V>) -> BTreeSet<K>
+where
+    K: Copy + Ord + std::hash::Hash,
+    V: PartialEq,
+{
+    old.iter()
+        .filter(|(key, value)| new.get(*key) != Some(*value))
+        .map(|(key, _)| *key)
+        .chain(new.keys().filter(|key| !old.contains_key(*key)).copied())
+        .collect()
+}
+
+impl ParsedCodeGraph {
+    /// Computes the changes that turn `self`, an older merged graph of a crate, into `newer`.
+    ///
+    /// Both graphs are expected to have gone through `build_tree_and_prune`, so that relations
+    /// from the resolution passes are compared too. The [`GraphDiff::files`] of the result are
+    /// left empty.
+    pub fn diff(&self, newer: &ParsedCodeGraph) -> GraphDiff {
+        let mut diff = GraphDiff::default();
+
+        let old_nodes = primary_nodes(self);
+        let new_nodes = primary_nodes(newer);
+        for (id, old) in &old_nodes {
+            match new_nodes.get(id) {
+                None => {
+                    diff.removed.insert(*id);
+                    diff.removed.extend(old.child_ids());
+                }
+                Some(new) if new != old => {
+                    diff.modified.insert(*id);
+                    let old_children: BTreeSet<_> = old.child_ids().into_iter().collect();
+                    let new_children: BTreeSet<_> = new.child_ids().into_iter().collect();
+                    diff.removed
+                        .extend(old_children.difference(&new_children).copied());
+                    diff.added
+                        .extend(new_children.difference(&old_children).copied());
+                    diff.modified
+                        .extend(old_children.intersection(&new_children).copied());
+                }
+                Some(_) => {}
+            }
+        }
+        for (id, new) in &new_nodes {
+            if !old_nodes.contains_key(id) {
+                diff.added.insert(*id);
+                diff.added.extend(new.child_ids());
+            }
+        }
+
+        let old_types: HashSet<TypeId> = self.type_graph().iter().map(|t| t.id).collect();
+        diff.added_types = newer
+            .type_graph()
+            .iter()
+            .map(|t| t.id)
+            .filter(|id| !old_types.contains(id))
+            .collect();
+
+        let old_relations: HashSet<&SyntacticRelation> = self.relations().iter().collect();
+        let new_relations: HashSet<&SyntacticRelation> = newer.relations().iter().collect();
+        diff.added_relations = new_relations
+            .difference(&old_relations)
+            .map(|rel| **rel)
+            .collect();
+        diff.removed_relations = old_relations
+            .difference(&new_relations)
+            .map(|rel| **rel)
+            .collect();
+
+        diff.call_site_owners =
+            differing_keys(&call_sites_by_owner(self), &call_sites_by_owner(newer));
+        diff.macro_expansion_owners =
+            differing_keys(&expansions_by_owner(self), &expansions_by_owner(newer));
+        diff.test_owners = differing_keys(&tests_by_owner(self), &tests_by_owner(newer));
+
+        diff
+    }
+}
@@ -1,5 +1,6 @@
 use crate::parser::nodes::AsAnyNodeId;
 mod code_graph;
+mod diff;
 mod parsed_graph;
 
 use crate::utils::logging::{LOG_TARGET_GRAPH_FIND, LOG_TARGET_NODE};
@@ -8,6 +9,7 @@ use std::collections::HashSet;
 
 pub use code_graph::CodeGraph;
 use colored::Colorize;
+pub use diff::{FileChanges, GraphDiff};
 use itertools::Itertools;
 use log::{debug, trace};
 pub use parsed_graph::ParsedCodeGraph;
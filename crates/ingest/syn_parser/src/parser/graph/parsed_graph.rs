@@ -1,8 +1,11 @@
 use crate::{
     discovery::DependencyMap as _,
+    parser::derives::DeriveTable,
     parser::diagnostics::{TRACE_TARGET_MERGE, TRACE_TARGET_PRUNE, emit_json_diagnostic},
     resolve::{
         ModuleTreeError, PruningResult, TreeRelation, UnlinkedModuleInfo, module_tree::ModuleTree,
+        resolve_call_sites, resolve_derives, resolve_generic_bounds, resolve_impls,
+        resolve_macro_expansions, resolve_tests, resolve_type_usages,
     },
 };
 use std::{
@@ -317,6 +320,11 @@ impl ParsedCodeGraph {
         self.graph
             .use_statements
             .append(&mut other.graph.use_statements);
+        self.graph.call_sites.append(&mut other.graph.call_sites);
+        self.graph
+            .macro_expansions
+            .append(&mut other.graph.macro_expansions);
+        self.graph.tests.append(&mut other.graph.tests);
 
         #[cfg(feature = "validate")]
         {
@@ -864,15 +872,28 @@ impl ParsedCodeGraph {
     /// This is the primary public-facing API for turning the flat set of parsed
     /// files into a coherent crate-level structure:
     ///
-    /// 1. Constructs a `ModuleTree` from the root module.
-    /// 2. Registers every `ModuleNode` (definitions and declarations).
-    /// 3. Copies all syntactic relations into the tree.
-    /// 4. Establishes inter-module links (`ResolvesToDefinition`,
+    /// 1. Resolves the derive macros of the impls synthesized from `#[derive(...)]`
+    ///    attributes against the default [`DeriveTable`], removing those that implement no
+    ///    trait. Use [`Self::build_tree_and_prune_with_derives`] to pass another table.
+    /// 2. Constructs a `ModuleTree` from the root module.
+    /// 3. Registers every `ModuleNode` (definitions and declarations).
+    /// 4. Copies all syntactic relations into the tree.
+    /// 5. Establishes inter-module links (`ResolvesToDefinition`,
     ///    `CustomPath`, …).
-    /// 5. Prunes file-based modules that remain unlinked after the above steps.
-    /// 6. Removes every node, module and relation listed in the `PruningResult`
+    /// 6. Prunes file-based modules that remain unlinked after the above steps.
+    /// 7. Removes every node, module and relation listed in the `PruningResult`
     ///    from the current `ParsedCodeGraph`, keeping the graph and the tree
     ///    consistent.
+    /// 8. Resolves the self type and trait of each impl, adding `ImplFor`, `ImplementsTrait`
+    ///    and `ImplementsTraitMethod` relations.
+    /// 9. Resolves the call sites recorded in function and method bodies against the
+    ///    tree, adding `Calls` relations for the ones that point to crate-local items.
+    /// 10. Resolves the types named in signatures, fields, impls and declarations, adding
+    ///     `UsesType` relations to the crate-local type definitions they refer to.
+    /// 11. Resolves the trait bounds of generic parameters, where-clauses and supertraits,
+    ///     adding `RequiresTrait` and `SuperTrait` relations.
+    /// 12. Collects the `#[test]` and `#[bench]` functions and the doc-tests of the crate
+    ///     into `tests`, linked to the items they exercise.
     ///
     /// After a successful call the `ParsedCodeGraph` contains only items that are
     /// reachable through the resulting `ModuleTree`.
@@ -881,8 +902,20 @@ impl ParsedCodeGraph {
     /// Returns any error encountered during tree construction, path resolution,
     /// or pruning (in creating the items to prune within build_module_tree).
     /// - JL Reviewed, Jul 28, 2025
-    #[instrument(target = TRACE_TARGET_PRUNE, skip(self), fields(pruned_relations, pruned_modules, pruned_items))]
     pub fn build_tree_and_prune(&mut self) -> Result<ModuleTree, ploke_error::Error> {
+        self.build_tree_and_prune_with_derives(&DeriveTable::default())
+    }
+
+    /// Like [`Self::build_tree_and_prune`], but resolves derive macros against `derives`.
+    #[instrument(target = TRACE_TARGET_PRUNE, skip(self, derives), fields(pruned_relations, pruned_modules, pruned_items))]
+    pub fn build_tree_and_prune_with_derives(
+        &mut self,
+        derives: &DeriveTable,
+    ) -> Result<ModuleTree, ploke_error::Error> {
+        {
+            let _span = info_span!("resolve_derives").entered();
+            resolve_derives(self, derives);
+        }
         let (tree, pruned_items) = {
             let _span = info_span!("build_module_tree").entered();
             self.build_module_tree()?
@@ -897,14 +930,29 @@ impl ParsedCodeGraph {
             .entered();
             self.prune(pruned_items);
         }
+        self.run_resolution_passes(&tree);
         Ok(tree)
     }
 
-    #[instrument(target = TRACE_TARGET_PRUNE, skip(self), fields(pruned_relations, pruned_modules, pruned_items, root_file = ?root_file))]
     pub fn build_tree_and_prune_for_root_path(
         &mut self,
         root_file: &Path,
     ) -> Result<ModuleTree, ploke_error::Error> {
+        self.build_tree_and_prune_for_root_path_with_derives(root_file, &DeriveTable::default())
+    }
+
+    /// Like [`Self::build_tree_and_prune_for_root_path`], but resolves derive macros against
+    /// `derives`.
+    #[instrument(target = TRACE_TARGET_PRUNE, skip(self, derives), fields(pruned_relations, pruned_modules, pruned_items, root_file = ?root_file))]
+    pub fn build_tree_and_prune_for_root_path_with_derives(
+        &mut self,
+        root_file: &Path,
+        derives: &DeriveTable,
+    ) -> Result<ModuleTree, ploke_error::Error> {
+        {
+            let _span = info_span!("resolve_derives").entered();
+            resolve_derives(self, derives);
+        }
         let (tree, pruned_items) = {
             let _span = info_span!("build_module_tree_for_root").entered();
             self.build_module_tree_for_root_path(root_file)?
@@ -919,9 +967,39 @@ impl ParsedCodeGraph {
             .entered();
             self.prune(pruned_items);
         }
+        self.run_resolution_passes(&tree);
         Ok(tree)
     }
 
+    /// Runs the resolution passes that need the pruned module tree, steps 8 to 12 of
+    /// [`build_tree_and_prune`](Self::build_tree_and_prune).
+    fn run_resolution_passes(&mut self, tree: &ModuleTree) {
+        {
+            let _span = info_span!("resolve_impls").entered();
+            resolve_impls(self, tree);
+        }
+        {
+            let _span = info_span!("resolve_call_sites").entered();
+            resolve_call_sites(self, tree);
+        }
+        {
+            let _span = info_span!("resolve_type_usages").entered();
+            resolve_type_usages(self, tree);
+        }
+        {
+            let _span = info_span!("resolve_generic_bounds").entered();
+            resolve_generic_bounds(self, tree);
+        }
+        {
+            let _span = info_span!("resolve_macro_expansions").entered();
+            resolve_macro_expansions(self);
+        }
+        {
+            let _span = info_span!("resolve_tests").entered();
+            resolve_tests(self, tree);
+        }
+    }
+
     #[allow(clippy::boxed_local, clippy::box_collection)]
     fn handle_unlinked_modules(&self, unlinked_infos: Vec<UnlinkedModuleInfo>) {
         if !unlinked_infos.is_empty() {
@@ -1011,6 +1089,119 @@ impl ParsedCodeGraph {
         self.graph.modules.retain(|n| set.contains(&n.any_id()));
         self.graph.statics.retain(|n| set.contains(&n.any_id()));
     }
+
+    /// Reduces the graph to the crate's public API surface, used for dependency sources where
+    /// only the signatures callers can use are of interest.
+    ///
+    /// Removes every function, type definition, trait, const, static, macro and import that is
+    /// not declared `pub`, the private fields of structs and unions, the non-`pub` methods of
+    /// inherent impls, and impls whose self type was removed. Modules are kept so that the paths
+    /// of the remaining items stay intact. Relations, call sites and macro expansions that
+    /// refer to a removed node are dropped.
+    ///
+    /// Must run after the module tree is built and the resolution passes have run, since impls
+    /// are matched to their self type through [`SyntacticRelation::ImplFor`].
+    ///
+    /// **Limitations:** `pub` items in private modules are kept even when they are not
+    /// re-exported, and type nodes referenced only by removed items are not removed.
+    pub fn retain_public_api(&mut self) {
+        fn retain_public<T: GraphNode>(items: &mut Vec<T>, removed: &mut HashSet<AnyNodeId>) {
+            items.retain(|item| {
+                let keep = matches!(item.visibility(), VisibilityKind::Public);
+                if !keep {
+                    removed.insert(item.any_id());
+                }
+                keep
+            });
+        }
+        fn retain_public_fields(fields: &mut Vec<FieldNode>, removed: &mut HashSet<AnyNodeId>) {
+            fields.retain(|field| {
+                let keep = matches!(field.visibility, VisibilityKind::Public);
+                if !keep {
+                    removed.insert(field.id.as_any());
+                }
+                keep
+            });
+        }
+
+        let mut removed: HashSet<AnyNodeId> = HashSet::new();
+        retain_public(&mut self.graph.functions, &mut removed);
+        retain_public(&mut self.graph.defined_types, &mut removed);
+        retain_public(&mut self.graph.traits, &mut removed);
+        retain_public(&mut self.graph.consts, &mut removed);
+        retain_public(&mut self.graph.statics, &mut removed);
+        retain_public(&mut self.graph.macros, &mut removed);
+        retain_public(&mut self.graph.use_statements, &mut removed);
+        for def in self.graph.defined_types.iter_mut() {
+            match def {
+                TypeDefNode::Struct(s) => retain_public_fields(&mut s.fields, &mut removed),
+                TypeDefNode::Union(u) => retain_public_fields(&mut u.fields, &mut removed),
+                TypeDefNode::Enum(_) | TypeDefNode::TypeAlias(_) => {}
+            }
+        }
+
+        let removed_impls: HashSet<ImplNodeId> = self
+            .graph
+            .relations
+            .iter()
+            .filter_map(|rel| match rel {
+                SyntacticRelation::ImplFor { source, target }
+                    if removed.contains(&target.as_any()) =>
+                {
+                    Some(*source)
+                }
+                _ => None,
+            })
+            .collect();
+        self.graph.impls.retain(|imp| {
+            let keep = !removed_impls.contains(&imp.id);
+            if !keep {
+                removed.insert(imp.id.as_any());
+                removed.extend(imp.methods.iter().map(|m| m.id.as_any()));
+            }
+            keep
+        });
+        for imp in self.graph.impls.iter_mut() {
+            // Methods of trait impls are as visible as the trait itself.
+            if imp.trait_type.is_none() {
+                retain_public(&mut imp.methods, &mut removed);
+            }
+        }
+        for module in self.graph.modules.iter_mut() {
+            if let ModuleKind::FileBased { items, .. } | ModuleKind::Inline { items, .. } =
+                &mut module.module_def
+            {
+                items.retain(|id| !removed.contains(&id.as_any()));
+            }
+        }
+
+        self.graph
+            .relations
+            .retain(|rel| !removed.contains(&rel.source()) && !removed.contains(&rel.target()));
+        self.graph
+            .call_sites
+            .retain(|site| !removed.contains(&site.caller.into()));
+        for site in self.graph.call_sites.iter_mut() {
+            site.resolved = site
+                .resolved
+                .filter(|callee| !removed.contains(&(*callee).into()));
+        }
+        for expansion in self.graph.macro_expansions.iter_mut() {
+            expansion
+                .generated
+                .retain(|id| !removed.contains(&id.as_any()));
+            expansion.macro_id = expansion
+                .macro_id
+                .filter(|id| !removed.contains(&id.as_any()));
+        }
+        self.graph
+            .tests
+            .retain(|test| !removed.contains(&test.owner));
+        for test in self.graph.tests.iter_mut() {
+            test.targets.retain(|target| !removed.contains(&target.id));
+        }
+        tracing::debug!(removed = removed.len(), "retained public API");
+    }
 }
 
 impl GraphAccess for ParsedCodeGraph {
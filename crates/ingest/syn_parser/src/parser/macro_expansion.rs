@@ -0,0 +1,150 @@
+//! Best-effort expansion of crate-local `macro_rules!` macros invoked at item position.
+//!
+//! When the `CodeVisitor` meets an item-position invocation such as `make_newtype!(UserId);`, it
+//! looks the macro up (preferring a definition earlier in the same file), expands it with
+//! `ploke_mbe`, and visits the generated items as if they had been written at the invocation site.
+//! Each successful expansion is recorded as a [`MacroExpansion`].
+//!
+//! Every file of a crate is first visited with only its own definitions. The crate's
+//! definitions are then rebuilt from the [`MacroNode`]s of the parsed graphs by
+//! [`MacroDefinitions::from_graphs`], and only the files that invoked a macro defined elsewhere in
+//! the crate are visited again.
+//!
+//! Generated items get the span of the invocation, except for tokens captured from the invocation
+//! input, which keep their original position.
+//!
+//! The defining macro of an expansion is only known by id when it is defined in the same file as
+//! the invocation. After the `ModuleTree` is built, `resolve::macro_resolver` fills in
+//! [`MacroExpansion::macro_id`] for definitions in other files and adds one
+//! [`SyntacticRelation::ExpandsTo`] edge per generated item that survived pruning.
+//!
+//! Expansion is skipped (and the invocation left as-is) when the macro is not defined in the
+//! crate, when the name is ambiguous, when no rule matches, or when the output does not parse as
+//! items.
+//!
+//! [`SyntacticRelation::ExpandsTo`]: crate::parser::relations::SyntacticRelation::ExpandsTo
+//! [`MacroNode`]: crate::parser::nodes::MacroNode
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::parser::ExtractSpan;
+use crate::parser::graph::ParsedCodeGraph;
+use crate::parser::nodes::{MacroKind, MacroNode, MacroNodeId, ModuleNodeId, PrimaryNodeId};
+use ploke_mbe::DeclarativeMacro;
+use serde::{Deserialize, Serialize};
+
+/// Maximum nesting of macro invocations expanded from within other expansions.
+pub(crate) const MAX_EXPANSION_DEPTH: usize = 8;
+
+/// Provenance of the items generated by one expanded macro invocation.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct MacroExpansion {
+    /// Name of the expanded macro, i.e. the last segment of the invocation path.
+    pub macro_name: String,
+    /// Path of the invocation as written, e.g. `["make_newtype"]` or `["crate", "make_newtype"]`.
+    pub invocation_path: Vec<String>,
+    /// The module containing the invocation.
+    pub module_id: ModuleNodeId,
+    /// Byte span of the invocation in its source file.
+    pub span: (usize, usize),
+    /// File containing the `macro_rules!` definition that was used.
+    pub definition_file: PathBuf,
+    /// Byte span of the `macro_rules!` definition in `definition_file`.
+    pub definition_span: (usize, usize),
+    /// The defining macro node, once known.
+    pub macro_id: Option<MacroNodeId>,
+    /// Primary items generated directly by the expansion, in source order.
+    pub generated: Vec<PrimaryNodeId>,
+}
+
+/// A `macro_rules!` definition found while scanning a crate.
+#[derive(Debug)]
+pub(crate) struct MacroDefinition {
+    pub(crate) mac: DeclarativeMacro,
+    pub(crate) file_path: PathBuf,
+    pub(crate) span: (usize, usize),
+    pub(crate) exported: bool,
+}
+
+/// The `macro_rules!` definitions of a crate, keyed by macro name.
+#[derive(Debug, Default)]
+pub(crate) struct MacroDefinitions {
+    by_name: HashMap<String, Vec<Arc<MacroDefinition>>>,
+}
+
+impl MacroDefinitions {
+    /// Rebuilds the `macro_rules!` definitions from the macro nodes of `graphs`, skipping those
+    /// `ploke_mbe` cannot parse. Definitions nested in function bodies never become nodes, and
+    /// are not visible at item position elsewhere anyway.
+    pub(crate) fn from_graphs<'a>(graphs: impl IntoIterator<Item = &'a ParsedCodeGraph>) -> Self {
+        let mut definitions = Self::default();
+        for graph in graphs {
+            for node in &graph.graph.macros {
+                if let Some(definition) = Self::from_node(node, &graph.file_path) {
+                    definitions.insert(definition);
+                }
+            }
+        }
+        definitions
+    }
+
+    fn from_node(node: &MacroNode, file_path: &Path) -> Option<MacroDefinition> {
+        if node.kind != MacroKind::DeclarativeMacro {
+            return None;
+        }
+        let source = format!("macro_rules! {} {{ {} }}", node.name, node.body.as_ref()?);
+        let item: syn::ItemMacro = syn::parse_str(&source).ok()?;
+        let mac = ploke_mbe::parse_macro_rules_item(&item).ok()?;
+        Some(MacroDefinition {
+            mac,
+            file_path: file_path.to_path_buf(),
+            span: node.span,
+            exported: node
+                .attributes
+                .iter()
+                .any(|attr| attr.name == "macro_export"),
+        })
+    }
+
+    /// Parses `item` as a `macro_rules!` definition, returning `None` for other item macros and
+    /// for definitions `ploke_mbe` cannot parse.
+    pub(crate) fn parse_definition(
+        item: &syn::ItemMacro,
+        file_path: &Path,
+    ) -> Option<MacroDefinition> {
+        let mac = ploke_mbe::parse_macro_rules_item(item).ok()?;
+        Some(MacroDefinition {
+            mac,
+            file_path: file_path.to_path_buf(),
+            span: item.extract_span_bytes(),
+            exported: item
+                .attrs
+                .iter()
+                .any(|attr| attr.path().is_ident("macro_export")),
+        })
+    }
+
+    fn insert(&mut self, definition: MacroDefinition) {
+        self.by_name
+            .entry(definition.mac.name.clone())
+            .or_default()
+            .push(Arc::new(definition));
+    }
+
+    /// Returns the definition of `name` if it is unambiguous, preferring the single
+    /// `#[macro_export]` definition when there are several.
+    pub(crate) fn lookup(&self, name: &str) -> Option<&Arc<MacroDefinition>> {
+        match self.by_name.get(name)?.as_slice() {
+            [only] => Some(only),
+            defs => {
+                let mut exported = defs.iter().filter(|def| def.exported);
+                match (exported.next(), exported.next()) {
+                    (Some(def), None) => Some(def),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
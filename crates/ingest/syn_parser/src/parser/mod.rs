@@ -1,8 +1,12 @@
+pub mod calls;
 pub mod channel;
+pub mod derives;
 pub mod diagnostics;
 pub mod graph; // Make these public
+pub mod macro_expansion;
 pub mod nodes;
 pub mod relations;
+pub mod test_items;
 pub mod types;
 pub mod utils;
 pub mod visibility;
@@ -12,4 +16,4 @@ pub mod visitor;
 pub use self::channel::{ParserMessage, create_parser_channel};
 pub use self::graph::{CodeGraph, ParsedCodeGraph};
 pub use self::utils::ExtractSpan;
-pub use self::visitor::analyze_files_parallel;
+pub use self::visitor::{analyze_files_parallel, analyze_files_parallel_reusing};
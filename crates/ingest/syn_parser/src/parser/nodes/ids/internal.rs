@@ -811,6 +811,57 @@ impl Default for TryFromAssociatedItemError {
     }
 }
 
+/// Error type for failed TryFrom<CallableNodeId> conversions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TryFromCallableError;
+
+impl std::fmt::Display for TryFromCallableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CallableNodeId variant mismatch")
+    }
+}
+impl std::error::Error for TryFromCallableError {}
+
+impl Default for TryFromCallableError {
+    fn default() -> Self {
+        TryFromCallableError
+    }
+}
+
+/// Error type for failed TryFrom<TypeConsumerNodeId> conversions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TryFromTypeConsumerError;
+
+impl std::fmt::Display for TryFromTypeConsumerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TypeConsumerNodeId variant mismatch")
+    }
+}
+impl std::error::Error for TryFromTypeConsumerError {}
+
+impl Default for TryFromTypeConsumerError {
+    fn default() -> Self {
+        TryFromTypeConsumerError
+    }
+}
+
+/// Error type for failed TryFrom<GenericOwnerNodeId> conversions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TryFromGenericOwnerError;
+
+impl std::fmt::Display for TryFromGenericOwnerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "GenericOwnerNodeId variant mismatch")
+    }
+}
+impl std::error::Error for TryFromGenericOwnerError {}
+
+impl Default for TryFromGenericOwnerError {
+    fn default() -> Self {
+        TryFromGenericOwnerError
+    }
+}
+
 pub trait PrimaryNodeMarker {}
 
 impl PrimaryNodeMarker for FunctionNode {}
@@ -952,6 +1003,51 @@ define_category_enum!(
     ]
 );
 
+define_category_enum!(
+    #[doc = "Represents the ID of any node type that can appear at either end of a call edge: free functions, methods, and macros."]
+    CallableNodeId,
+    TryFromCallableError, // Pass the specific error type
+    ItemKind,
+    [
+        (Function, FunctionNodeId, ItemKind::Function),
+        (Method, MethodNodeId, ItemKind::Method),
+        (Macro, MacroNodeId, ItemKind::Macro),
+    ]
+);
+
+define_category_enum!(
+    #[doc = "Represents the ID of any node type whose signature or declared type can name another type: functions and methods (parameters and return type), fields, impls (self type and trait), type aliases, consts, and statics."]
+    TypeConsumerNodeId,
+    TryFromTypeConsumerError, // Pass the specific error type
+    ItemKind,
+    [
+        (Function, FunctionNodeId, ItemKind::Function),
+        (Method, MethodNodeId, ItemKind::Method),
+        (Field, FieldNodeId, ItemKind::Field),
+        (Impl, ImplNodeId, ItemKind::Impl),
+        (TypeAlias, TypeAliasNodeId, ItemKind::TypeAlias),
+        (Const, ConstNodeId, ItemKind::Const),
+        (Static, StaticNodeId, ItemKind::Static),
+    ]
+);
+
+define_category_enum!(
+    #[doc = "Represents the ID of any node type that can declare generic parameters and where-clauses: functions, methods, structs, enums, unions, type aliases, traits, and impls."]
+    GenericOwnerNodeId,
+    TryFromGenericOwnerError, // Pass the specific error type
+    ItemKind,
+    [
+        (Function, FunctionNodeId, ItemKind::Function),
+        (Method, MethodNodeId, ItemKind::Method),
+        (Struct, StructNodeId, ItemKind::Struct),
+        (Enum, EnumNodeId, ItemKind::Enum),
+        (Union, UnionNodeId, ItemKind::Union),
+        (TypeAlias, TypeAliasNodeId, ItemKind::TypeAlias),
+        (Trait, TraitNodeId, ItemKind::Trait),
+        (Impl, ImplNodeId, ItemKind::Impl),
+    ]
+);
+
 // --- Manually Defined AnyNodeId ---
 
 /// Represents the ID of *any* node type in the graph. Used as a key for heterogeneous storage.
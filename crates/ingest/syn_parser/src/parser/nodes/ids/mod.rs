@@ -36,12 +36,15 @@ pub use internal::{ToCozoUuid, ToUuidString};
 // pub(crate) use internal::TypedNodeIdGet;
 // --- enums ---
 // Re-export category enums
-pub use internal::{AnyNodeId, AssociatedItemNodeId, PrimaryNodeId, SecondaryNodeId};
+pub use internal::{
+    AnyNodeId, AssociatedItemNodeId, CallableNodeId, GenericOwnerNodeId, PrimaryNodeId,
+    SecondaryNodeId, TypeConsumerNodeId,
+};
 // --- macro rules ---
 // --- error types ---
 pub use internal::{
-    AnyNodeIdConversionError, TryFromAssociatedItemError, TryFromPrimaryError,
-    TryFromSecondaryError,
+    AnyNodeIdConversionError, TryFromAssociatedItemError, TryFromCallableError,
+    TryFromGenericOwnerError, TryFromPrimaryError, TryFromSecondaryError, TryFromTypeConsumerError,
 };
 
 // --- semi-private ---
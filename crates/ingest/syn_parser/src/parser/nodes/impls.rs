@@ -19,11 +19,41 @@ pub struct ImplNode {
     pub methods: Vec<MethodNode>, // Changed from FunctionNode
     pub generic_params: Vec<GenericParamNode>,
     pub cfgs: Vec<String>,
+    /// Set for impls synthesized from a `#[derive(...)]` attribute rather than written out.
+    #[serde(default)]
+    pub derived: Option<DerivedImpl>,
     // TODO: Add fields for associated consts and types once we are processing them.
     // pub associated_consts: Vec<ConstNodeId>,
     // pub associated_types: Vec<TypeAliasNodeId>,
 }
 
+/// The derive macro an impl was synthesized from.
+///
+/// The `CodeVisitor` creates one [`ImplNode`] without methods for each path in the `#[derive]`
+/// attributes of a struct, enum or union. Its `trait_type` is the derive path as written, and
+/// `resolve_derives` fills in the paths below once the graph is merged.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct DerivedImpl {
+    /// Path of the derive macro as written, e.g. `["Serialize"]` or `["serde", "Serialize"]`.
+    pub macro_path: Vec<String>,
+    /// `macro_path` resolved through the imports of the containing module, e.g.
+    /// `["serde", "Serialize"]` for `Serialize` under `use serde::Serialize;`.
+    pub resolved_path: Option<Vec<String>>,
+    /// Path of the implemented trait, looked up in the
+    /// [`DeriveTable`](crate::parser::derives::DeriveTable), e.g. `["core", "hash", "Hash"]`.
+    pub trait_path: Option<Vec<String>>,
+}
+
+impl DerivedImpl {
+    pub fn new(macro_path: Vec<String>) -> Self {
+        Self {
+            macro_path,
+            resolved_path: None,
+            trait_path: None,
+        }
+    }
+}
+
 impl ImplNode {
     /// Returns the typed ID for this impl node.
     pub fn impl_id(&self) -> ImplNodeId {
@@ -58,6 +88,14 @@ impl ImplNode {
         &self.cfgs
     }
 
+    pub fn derived(&self) -> Option<&DerivedImpl> {
+        self.derived.as_ref()
+    }
+
+    pub fn is_derived(&self) -> bool {
+        self.derived.is_some()
+    }
+
     pub fn cfgs_str_iter(&self) -> impl Iterator<Item = &str> {
         self.cfgs().iter().map(|c| c.as_str())
     }
@@ -33,7 +33,7 @@ use serde::{Deserialize, Serialize};
 pub use consts::ConstNode;
 pub use enums::{EnumNode, VariantNode};
 pub use function::{FunctionNode, ParamData};
-pub use impls::ImplNode;
+pub use impls::{DerivedImpl, ImplNode};
 pub use import::{ImportKind, ImportNode};
 pub use macros::{MacroKind, MacroNode, ProcMacroKind};
 pub use method::{ExpectedMethodNode, MethodNode};
@@ -1,8 +1,9 @@
 // Import specific typed IDs AND the new category enums
 use super::nodes::{AnyNodeId, PrimaryNodeIdTrait};
 use crate::parser::nodes::{
-    AssociatedItemNodeId, EnumNodeId, FieldNodeId, ImplNodeId, ImportNodeId, ModuleNodeId,
-    PrimaryNodeId, StructNodeId, TraitNodeId, UnionNodeId, VariantNodeId,
+    AssociatedItemNodeId, CallableNodeId, EnumNodeId, FieldNodeId, GenericOwnerNodeId, ImplNodeId,
+    ImportNodeId, MacroNodeId, MethodNodeId, ModuleNodeId, PrimaryNodeId, StructNodeId,
+    TraitNodeId, TypeConsumerNodeId, UnionNodeId, VariantNodeId,
 };
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -17,6 +18,10 @@ pub enum RelationConversionError {
 // ANCHOR: syntactic_relation
 /// Represents a type-safe structural or semantic relation between two nodes in the code graph.
 /// Each variant enforces the correct NodeId types for its source and target where possible.
+///
+/// Relations created after the ModuleTree is built may link into another member of the
+/// workspace, in which case they are stored with the graph of the crate containing the path
+/// (see `resolve::workspace_resolver`).
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum SyntacticRelation {
     //-----------------------------------------------------------------------
@@ -83,6 +88,7 @@ pub enum SyntacticRelation {
         target: PrimaryNodeId,
     },
     /// A definition is brought into scope by a `use`/`pub use` site.
+    /// The definition may be in another workspace member, see `resolve::workspace_resolver`.
     /// Source: PrimaryNodeId (definition)
     /// Target: ImportNodeId (the import/re-export site)
     ImportedBy {
@@ -128,8 +134,8 @@ pub enum SyntacticRelation {
     /// Impl block contains an associated item (method, type, const).
     /// Source: ImplNodeId
     /// Target: AssociatedItemNodeId (Restricts target to valid associated item types)
-    // NOTE: Note yet implemented (2025-05-02)
-    //
+    /// Created in CodeVisitor
+    // NOTE: Only methods are linked for now, associated consts and types are not yet collected.
     ImplAssociatedItem {
         source: ImplNodeId,
         target: AssociatedItemNodeId,
@@ -138,11 +144,102 @@ pub enum SyntacticRelation {
     /// Trait definition contains an associated item (method, type, const).
     /// Source: TraitNodeId
     /// Target: AssociatedItemNodeId (Restricts target to valid associated item types)
-    // NOTE: Note yet implemented (2025-05-02)
+    /// Created in CodeVisitor
+    // NOTE: Only methods are linked for now, associated consts and types are not yet collected.
     TraitAssociatedItem {
         source: TraitNodeId,
         target: AssociatedItemNodeId,
     },
+
+    //-----------------------------------------------------------------------
+    //                    Trait Implementation Relations
+    //-----------------------------------------------------------------------
+    // All created after the ModuleTree is built (see `resolve::impl_resolver`), by resolving the
+    // paths written in the impl header in the scope of the module containing the impl.
+    /// Impl block implements a crate-local trait, e.g. `impl Router for AppRouter`.
+    /// Source: ImplNodeId
+    /// Target: TraitNodeId
+    ImplementsTrait {
+        source: ImplNodeId,
+        target: TraitNodeId,
+    },
+
+    /// Impl block (inherent or trait) is implemented for a crate-local type definition.
+    /// Source: ImplNodeId
+    /// Target: PrimaryNodeId (a struct, enum, union, or type alias)
+    ImplFor {
+        source: ImplNodeId,
+        target: PrimaryNodeId,
+    },
+
+    /// Method in a trait impl provides the trait method of the same name.
+    /// Source: MethodNodeId (in the impl)
+    /// Target: MethodNodeId (in the trait)
+    ImplementsTraitMethod {
+        source: MethodNodeId,
+        target: MethodNodeId,
+    },
+
+    //-----------------------------------------------------------------------
+    //                          Call Graph Relations
+    //-----------------------------------------------------------------------
+    /// A function or method body contains a call site that resolved to a crate-local callable.
+    /// Created after the ModuleTree is built (see `resolve::call_resolver`), from the
+    /// `CallSite`s recorded by the `CodeVisitor`.
+    /// Source: CallableNodeId (the caller, a function or method)
+    /// Target: CallableNodeId (the callee, a function, method, or macro)
+    Calls {
+        source: CallableNodeId,
+        target: CallableNodeId,
+    },
+
+    //-----------------------------------------------------------------------
+    //                        Macro Expansion Relations
+    //-----------------------------------------------------------------------
+    /// A `macro_rules!` macro was expanded at item position and generated the target item.
+    /// Created after the ModuleTree is built (see `resolve::macro_resolver`), from the
+    /// `MacroExpansion`s recorded by the `CodeVisitor`.
+    /// Source: MacroNodeId (the defining macro)
+    /// Target: PrimaryNodeId (an item generated by the expansion)
+    ExpandsTo {
+        source: MacroNodeId,
+        target: PrimaryNodeId,
+    },
+
+    //-----------------------------------------------------------------------
+    //                          Type Usage Relations
+    //-----------------------------------------------------------------------
+    /// A type written in the source's signature or declared type names a crate-local type
+    /// definition, possibly nested, e.g. `fn f(graphs: Vec<ParsedCodeGraph>)`.
+    /// Created after the ModuleTree is built (see `resolve::type_usage_resolver`).
+    /// Source: TypeConsumerNodeId (a function, method, field, impl, type alias, const, or static)
+    /// Target: PrimaryNodeId (a struct, enum, union, or type alias)
+    UsesType {
+        source: TypeConsumerNodeId,
+        target: PrimaryNodeId,
+    },
+
+    //-----------------------------------------------------------------------
+    //                         Generic Bound Relations
+    //-----------------------------------------------------------------------
+    // Both created after the ModuleTree is built (see `resolve::bound_resolver`).
+    /// A generic parameter of the source is bound by a crate-local trait, either inline
+    /// (`fn f<R: Router>`) or in a where-clause (`where R: Router`).
+    /// Source: GenericOwnerNodeId (a function, method, struct, enum, union, type alias, trait, or
+    /// impl)
+    /// Target: TraitNodeId
+    RequiresTrait {
+        source: GenericOwnerNodeId,
+        target: TraitNodeId,
+    },
+
+    /// A trait declares a crate-local supertrait, either as `trait A: B` or as `where Self: B`.
+    /// Source: TraitNodeId (the subtrait)
+    /// Target: TraitNodeId (the supertrait)
+    SuperTrait {
+        source: TraitNodeId,
+        target: TraitNodeId,
+    },
 }
 // ANCHOR_END: syntactic_relation
 
@@ -233,6 +330,14 @@ impl SyntacticRelation {
             SyntacticRelation::EnumVariant { source, .. } => source.into(),
             SyntacticRelation::ImplAssociatedItem { source, .. } => source.into(),
             SyntacticRelation::TraitAssociatedItem { source, .. } => source.into(),
+            SyntacticRelation::ImplementsTrait { source, .. } => source.into(),
+            SyntacticRelation::ImplFor { source, .. } => source.into(),
+            SyntacticRelation::ImplementsTraitMethod { source, .. } => source.into(),
+            SyntacticRelation::Calls { source, .. } => source.into(),
+            SyntacticRelation::ExpandsTo { source, .. } => source.into(),
+            SyntacticRelation::UsesType { source, .. } => source.into(),
+            SyntacticRelation::RequiresTrait { source, .. } => source.into(),
+            SyntacticRelation::SuperTrait { source, .. } => source.into(),
         }
     }
 
@@ -252,6 +357,14 @@ impl SyntacticRelation {
             SyntacticRelation::EnumVariant { target, .. } => target.into(),
             SyntacticRelation::ImplAssociatedItem { target, .. } => target.into(),
             SyntacticRelation::TraitAssociatedItem { target, .. } => target.into(),
+            SyntacticRelation::ImplementsTrait { target, .. } => target.into(),
+            SyntacticRelation::ImplFor { target, .. } => target.into(),
+            SyntacticRelation::ImplementsTraitMethod { target, .. } => target.into(),
+            SyntacticRelation::Calls { target, .. } => target.into(),
+            SyntacticRelation::ExpandsTo { target, .. } => target.into(),
+            SyntacticRelation::UsesType { target, .. } => target.into(),
+            SyntacticRelation::RequiresTrait { target, .. } => target.into(),
+            SyntacticRelation::SuperTrait { target, .. } => target.into(),
         }
     }
 
@@ -287,6 +400,14 @@ impl SyntacticRelation {
             SyntacticRelation::EnumVariant { .. } => "EnumVariant",
             SyntacticRelation::ImplAssociatedItem { .. } => "ImplAssociatedItem",
             SyntacticRelation::TraitAssociatedItem { .. } => "TraitAssociatedItem",
+            SyntacticRelation::ImplementsTrait { .. } => "ImplementsTrait",
+            SyntacticRelation::ImplFor { .. } => "ImplFor",
+            SyntacticRelation::ImplementsTraitMethod { .. } => "ImplementsTraitMethod",
+            SyntacticRelation::Calls { .. } => "Calls",
+            SyntacticRelation::ExpandsTo { .. } => "ExpandsTo",
+            SyntacticRelation::UsesType { .. } => "UsesType",
+            SyntacticRelation::RequiresTrait { .. } => "RequiresTrait",
+            SyntacticRelation::SuperTrait { .. } => "SuperTrait",
         }
     }
 
@@ -312,6 +433,70 @@ impl SyntacticRelation {
     pub fn is_contains(&self) -> bool {
         matches!(self, Self::Contains { .. })
     }
+
+    /// Returns `true` if the syntactic relation is [`ImplementsTrait`].
+    ///
+    /// [`ImplementsTrait`]: SyntacticRelation::ImplementsTrait
+    #[must_use]
+    pub fn is_implements_trait(&self) -> bool {
+        matches!(self, Self::ImplementsTrait { .. })
+    }
+
+    /// Returns `true` if the syntactic relation is [`ImplFor`].
+    ///
+    /// [`ImplFor`]: SyntacticRelation::ImplFor
+    #[must_use]
+    pub fn is_impl_for(&self) -> bool {
+        matches!(self, Self::ImplFor { .. })
+    }
+
+    /// Returns `true` if the syntactic relation is [`ImplementsTraitMethod`].
+    ///
+    /// [`ImplementsTraitMethod`]: SyntacticRelation::ImplementsTraitMethod
+    #[must_use]
+    pub fn is_implements_trait_method(&self) -> bool {
+        matches!(self, Self::ImplementsTraitMethod { .. })
+    }
+
+    /// Returns `true` if the syntactic relation is [`Calls`].
+    ///
+    /// [`Calls`]: SyntacticRelation::Calls
+    #[must_use]
+    pub fn is_calls(&self) -> bool {
+        matches!(self, Self::Calls { .. })
+    }
+
+    /// Returns `true` if the syntactic relation is [`ExpandsTo`].
+    ///
+    /// [`ExpandsTo`]: SyntacticRelation::ExpandsTo
+    #[must_use]
+    pub fn is_expands_to(&self) -> bool {
+        matches!(self, Self::ExpandsTo { .. })
+    }
+
+    /// Returns `true` if the syntactic relation is [`UsesType`].
+    ///
+    /// [`UsesType`]: SyntacticRelation::UsesType
+    #[must_use]
+    pub fn is_uses_type(&self) -> bool {
+        matches!(self, Self::UsesType { .. })
+    }
+
+    /// Returns `true` if the syntactic relation is [`RequiresTrait`].
+    ///
+    /// [`RequiresTrait`]: SyntacticRelation::RequiresTrait
+    #[must_use]
+    pub fn is_requires_trait(&self) -> bool {
+        matches!(self, Self::RequiresTrait { .. })
+    }
+
+    /// Returns `true` if the syntactic relation is [`SuperTrait`].
+    ///
+    /// [`SuperTrait`]: SyntacticRelation::SuperTrait
+    #[must_use]
+    pub fn is_super_trait(&self) -> bool {
+        matches!(self, Self::SuperTrait { .. })
+    }
 }
 
 impl std::fmt::Display for SyntacticRelation {
@@ -356,6 +541,30 @@ impl std::fmt::Display for SyntacticRelation {
             SyntacticRelation::TraitAssociatedItem { source, target } => {
                 write!(f, "TraitAssociatedItem({} → {})", source, target)
             }
+            SyntacticRelation::ImplementsTrait { source, target } => {
+                write!(f, "ImplementsTrait({} → {})", source, target)
+            }
+            SyntacticRelation::ImplFor { source, target } => {
+                write!(f, "ImplFor({} → {})", source, target)
+            }
+            SyntacticRelation::ImplementsTraitMethod { source, target } => {
+                write!(f, "ImplementsTraitMethod({} → {})", source, target)
+            }
+            SyntacticRelation::Calls { source, target } => {
+                write!(f, "Calls({} → {})", source, target)
+            }
+            SyntacticRelation::ExpandsTo { source, target } => {
+                write!(f, "ExpandsTo({} → {})", source, target)
+            }
+            SyntacticRelation::UsesType { source, target } => {
+                write!(f, "UsesType({} → {})", source, target)
+            }
+            SyntacticRelation::RequiresTrait { source, target } => {
+                write!(f, "RequiresTrait({} → {})", source, target)
+            }
+            SyntacticRelation::SuperTrait { source, target } => {
+                write!(f, "SuperTrait({} → {})", source, target)
+            }
         }
     }
 }
@@ -394,6 +603,14 @@ impl TryInto<ScopeKind> for SyntacticRelation {
             // Relations that don't fit the ScopeKind model
             Self::ResolvesToDefinition { .. } => Err(RelationConversionError::NotApplicable(self)),
             Self::Sibling { .. } => Err(RelationConversionError::NotApplicable(self)),
+            Self::ImplementsTrait { .. } => Err(RelationConversionError::NotApplicable(self)),
+            Self::ImplFor { .. } => Err(RelationConversionError::NotApplicable(self)),
+            Self::ImplementsTraitMethod { .. } => Err(RelationConversionError::NotApplicable(self)),
+            Self::Calls { .. } => Err(RelationConversionError::NotApplicable(self)),
+            Self::ExpandsTo { .. } => Err(RelationConversionError::NotApplicable(self)),
+            Self::UsesType { .. } => Err(RelationConversionError::NotApplicable(self)),
+            Self::RequiresTrait { .. } => Err(RelationConversionError::NotApplicable(self)),
+            Self::SuperTrait { .. } => Err(RelationConversionError::NotApplicable(self)),
         }
     }
 }
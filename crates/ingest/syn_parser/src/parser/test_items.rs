@@ -0,0 +1,214 @@
+//! Tests found in a crate: `#[test]` and `#[bench]` functions and the doc-tests in doc comments.
+//!
+//! Tests are not recorded by the `CodeVisitor`, which does not know which module a test ends up
+//! in or what it calls. After the `ModuleTree` is built and call sites are resolved,
+//! `resolve::test_resolver` turns each test function and each Rust code block of a doc comment
+//! into a [`TestNode`], linked to the items it exercises through [`TestTarget`]s:
+//!
+//! - the crate-local functions and methods a test function calls,
+//! - for a test inside a `#[cfg(test)]` module, the module under test enclosing it,
+//! - for a doc-test, the documented item.
+//!
+//! Test functions keep their [`FunctionNode`](crate::parser::nodes::FunctionNode) as well; the
+//! [`TestNode`] only records what makes them a test.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::parser::nodes::{AnyNodeId, ModuleNodeId};
+
+/// How a test is run.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TestKind {
+    /// A `#[test]` function compiled into a library or binary target.
+    Unit,
+    /// A `#[test]` function of an integration test target under `tests/`.
+    Integration,
+    /// A Rust code block in a doc comment.
+    Doc,
+    /// A `#[bench]` function, or a test function of a bench target under `benches/`.
+    Bench,
+}
+
+impl TestKind {
+    /// Name of the variant, used when storing tests in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TestKind::Unit => "Unit",
+            TestKind::Integration => "Integration",
+            TestKind::Doc => "Doc",
+            TestKind::Bench => "Bench",
+        }
+    }
+}
+
+/// Why a test is considered to exercise an item.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TestCoverage {
+    /// The test function calls the item.
+    Call,
+    /// The item is the module under test enclosing the test's `#[cfg(test)]` module.
+    EnclosingModule,
+    /// The doc-test is part of the item's documentation.
+    Documented,
+}
+
+impl TestCoverage {
+    /// Name of the variant, used when storing test targets in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TestCoverage::Call => "Call",
+            TestCoverage::EnclosingModule => "EnclosingModule",
+            TestCoverage::Documented => "Documented",
+        }
+    }
+}
+
+/// An item exercised by a test.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TestTarget {
+    pub id: AnyNodeId,
+    pub coverage: TestCoverage,
+}
+
+/// A test function or doc-test.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TestNode {
+    /// Name of the test function, or `<item> (doctest <n>)` for the `n`th doc-test of an item.
+    pub name: String,
+    pub kind: TestKind,
+    /// The test function, or the documented item of a doc-test.
+    pub owner: AnyNodeId,
+    /// The module containing the owner.
+    pub module_id: ModuleNodeId,
+    /// The file containing the owner.
+    pub file_path: PathBuf,
+    /// Byte span of the owner in its file.
+    pub span: (usize, usize),
+    /// Position of a doc-test among the Rust code blocks of its item's documentation.
+    pub doctest_index: Option<usize>,
+    /// The code of a doc-test, without the fence lines.
+    pub code: Option<String>,
+    /// Whether the test is `#[ignore]`d or its doc-test fence is marked `ignore`.
+    pub ignored: bool,
+    pub targets: Vec<TestTarget>,
+}
+
+impl TestNode {
+    /// Stable id of the test, derived from its owner and its position among the owner's
+    /// doc-tests.
+    pub fn id(&self) -> Uuid {
+        let discriminator = match self.doctest_index {
+            Some(index) => format!("doctest:{index}"),
+            None => "test".to_string(),
+        };
+        Uuid::new_v5(&self.owner.uuid(), discriminator.as_bytes())
+    }
+}
+
+/// A Rust code block found in a doc comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocTestBlock {
+    pub code: String,
+    pub ignored: bool,
+}
+
+/// Returns `true` if a code block with the fence info string `info` is compiled as a doc-test,
+/// following rustdoc: blocks without a language and blocks whose attributes are all rustdoc's own
+/// are Rust.
+fn is_rust_fence(info: &str) -> bool {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .map(|attr| attr.trim_matches(|c| c == '{' || c == '}' || c == '.'))
+        .filter(|attr| !attr.is_empty())
+        .all(|attr| {
+            matches!(
+                attr,
+                "rust"
+                    | "ignore"
+                    | "should_panic"
+                    | "no_run"
+                    | "compile_fail"
+                    | "test_harness"
+                    | "standalone_crate"
+            ) || attr.starts_with("edition")
+                || attr.starts_with("ignore-")
+                || attr.starts_with("E0")
+        })
+}
+
+/// Extracts the Rust code blocks of `docs`, in order. Lines hidden from the rendered docs with a
+/// leading `# ` are kept, without the marker, since they are part of the compiled test.
+pub fn extract_doctests(docs: &str) -> Vec<DocTestBlock> {
+    let mut blocks = Vec::new();
+    // Fence, whether the block is Rust, whether it is ignored, and its lines so far.
+    let mut open: Option<(&str, bool, bool, Vec<&str>)> = None;
+    for line in docs.lines() {
+        let trimmed = line.trim_start();
+        match open.as_mut() {
+            None => {
+                let fence = if trimmed.starts_with("```") {
+                    "```"
+                } else if trimmed.starts_with("~~~") {
+                    "~~~"
+                } else {
+                    continue;
+                };
+                let info = trimmed.trim_start_matches(fence.chars().next().unwrap_or('`'));
+                let is_rust = is_rust_fence(info);
+                let ignored = info
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .any(|attr| attr == "ignore" || attr.starts_with("ignore-"));
+                open = Some((fence, is_rust, ignored, Vec::new()));
+            }
+            Some((fence, is_rust, ignored, lines)) => {
+                if trimmed.starts_with(*fence) {
+                    if *is_rust {
+                        blocks.push(DocTestBlock {
+                            code: lines.join("\n"),
+                            ignored: *ignored,
+                        });
+                    }
+                    open = None;
+                } else if trimmed == "#" {
+                    lines.push("");
+                } else if let Some(hidden) = trimmed.strip_prefix("# ") {
+                    lines.push(hidden);
+                } else {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_doctests_keeps_rust_blocks_only() {
+        let docs = "Adds one.\n\n```\nlet x = add_one(1);\n# assert_eq!(x, 2);\n```\n\n```text\nnot rust\n```\n\n```rust,ignore\nbroken(\n```";
+        let blocks = extract_doctests(docs);
+        assert_eq!(
+            blocks,
+            vec![
+                DocTestBlock {
+                    code: "let x = add_one(1);\nassert_eq!(x, 2);".to_string(),
+                    ignored: false,
+                },
+                DocTestBlock {
+                    code: "broken(".to_string(),
+                    ignored: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_doctests_ignores_unclosed_block() {
+        assert!(extract_doctests("```\nlet x = 1;").is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+//! Body walker that records the call sites of a single function or method.
+//!
+//! Used by the `CodeVisitor` when it processes a function, an impl method, or a trait method with
+//! a default body. The walker only looks at the body itself: nested items (e.g. a `fn` defined
+//! inside another `fn`) are skipped, while closures and blocks are visited and their calls are
+//! attributed to the enclosing function.
+
+use quote::ToTokens;
+use syn::{
+    Expr, ExprCall, ExprMethodCall, ExprPath, Token,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit::{self, Visit},
+};
+
+use crate::parser::calls::{CallKind, CallSite};
+use crate::parser::nodes::{CallableNodeId, ModuleNodeId};
+
+pub(crate) struct CallCollector {
+    caller: CallableNodeId,
+    module_id: ModuleNodeId,
+    sites: Vec<CallSite>,
+}
+
+impl CallCollector {
+    pub(crate) fn new(caller: CallableNodeId, module_id: ModuleNodeId) -> Self {
+        Self {
+            caller,
+            module_id,
+            sites: Vec::new(),
+        }
+    }
+
+    /// Walks `block` and returns every call site found in it, in source order.
+    pub(crate) fn collect_block(mut self, block: &syn::Block) -> Vec<CallSite> {
+        self.visit_block(block);
+        self.sites
+    }
+
+    fn push(&mut self, kind: CallKind, path: Vec<String>, span: std::ops::Range<usize>) {
+        self.sites.push(CallSite {
+            caller: self.caller,
+            module_id: self.module_id,
+            kind,
+            path,
+            span: (span.start, span.end),
+            resolved: None,
+        });
+    }
+}
+
+fn path_segments(path: &syn::Path) -> Vec<String> {
+    path.segments
+        .iter()
+        .map(|seg| seg.ident.to_string())
+        .collect()
+}
+
+fn starts_uppercase(segment: &str) -> bool {
+    segment.chars().next().is_some_and(char::is_uppercase)
+}
+
+fn is_self_receiver(expr: &Expr) -> bool {
+    matches!(expr, Expr::Path(ExprPath { qself: None, path, .. }) if path.is_ident("self"))
+}
+
+impl<'ast> Visit<'ast> for CallCollector {
+    fn visit_expr_call(&mut self, call: &'ast ExprCall) {
+        if let Expr::Path(ExprPath { qself, path, .. }) = &*call.func {
+            let mut segments = path_segments(path);
+            // Tuple struct constructors and enum variants (`Wrapper(x)`, `Some(x)`,
+            // `Shape::Circle(r)`) share call syntax but are not calls.
+            let is_constructor = segments.last().is_some_and(|last| starts_uppercase(last));
+            if !is_constructor {
+                let has_type_parent =
+                    segments.len() >= 2 && starts_uppercase(&segments[segments.len() - 2]);
+                let kind = if qself.is_some() || has_type_parent {
+                    CallKind::AssociatedFunction
+                } else {
+                    CallKind::Function
+                };
+                // `<T>::f()` has no path segment for `T`, keep it as written.
+                if let Some(qself) = qself.as_ref().filter(|q| q.position == 0) {
+                    segments.insert(0, qself.ty.to_token_stream().to_string());
+                }
+                self.push(kind, segments, call.span().byte_range());
+            }
+        }
+        visit::visit_expr_call(self, call);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast ExprMethodCall) {
+        let kind = if is_self_receiver(&call.receiver) {
+            CallKind::SelfMethod
+        } else {
+            CallKind::Method
+        };
+        self.push(
+            kind,
+            vec![call.method.to_string()],
+            call.span().byte_range(),
+        );
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        self.push(
+            CallKind::Macro,
+            path_segments(&mac.path),
+            mac.span().byte_range(),
+        );
+        // Most macros called from function bodies (`format!`, `assert_eq!`, `vec!`, ...) take
+        // comma-separated expressions. Visit them when the body parses that way so calls made in
+        // macro arguments are not lost; anything else is left opaque.
+        if let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+    }
+
+    fn visit_item(&mut self, _item: &'ast syn::Item) {
+        // Nested items are not nodes in the graph, and their calls do not belong to the
+        // enclosing function.
+    }
+}
@@ -9,6 +9,7 @@
 // fn visit_impl_item_const(&mut self, i: &'ast syn::ImplItemConst)
 
 use super::attribute_processing::{extract_attributes, extract_cfg_strings, extract_docstring};
+use super::call_collector::CallCollector;
 use super::state::VisitorState;
 use super::type_processing::get_or_create_type;
 use crate::parser::graph::GraphAccess;
@@ -22,9 +23,9 @@ use crate::parser::nodes::{
 use crate::parser::nodes::{AnyNodeId, AssociatedItemNodeId, PrimaryNodeId, SecondaryNodeId};
 // Nodes
 use crate::parser::nodes::{
-    ConstNode, EnumNode, FieldNode, FunctionNode, ImplNode, ImportNode, MacroNode, MethodNode,
-    ModuleNode, StaticNode, StructNode, TraitNode, TypeAliasNode, TypeDefNode, UnionNode,
-    VariantNode,
+    ConstNode, DerivedImpl, EnumNode, FieldNode, FunctionNode, ImplNode, ImportNode, MacroNode,
+    MethodNode, ModuleNode, StaticNode, StructNode, TraitNode, TypeAliasNode, TypeDefNode,
+    UnionNode, VariantNode,
 };
 // Kinds of nodes
 use crate::parser::nodes::{ImportKind, MacroKind, ModuleKind, ProcMacroKind};
@@ -32,6 +33,7 @@ use crate::parser::nodes::{ImportKind, MacroKind, ModuleKind, ProcMacroKind};
 use ploke_core::ItemKind;
 
 use crate::parser::ExtractSpan;
+use crate::parser::macro_expansion::{MAX_EXPANSION_DEPTH, MacroDefinitions, MacroExpansion};
 use crate::parser::relations::*;
 use crate::parser::types::*;
 use crate::parser::visitor::calculate_cfg_hash_bytes;
@@ -44,10 +46,12 @@ use ploke_core::TypeId;
 
 use colored::*;
 use quote::ToTokens;
+use std::sync::Arc;
 use syn::TypePath;
+use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::{
-    ItemEnum, ItemFn, ItemImpl, ItemStruct, ItemTrait, ReturnType, Type,
+    ItemEnum, ItemFn, ItemImpl, ItemStruct, ItemTrait, ReturnType, Token, Type,
     visit::{self, Visit},
 };
 use tracing::{error, instrument, trace}; // Import error macro
@@ -479,6 +483,199 @@ impl<'a> CodeVisitor<'a> {
         parent_mod_id_opt.map(|parent_mod_id| (node_id, parent_mod_id))
     }
 
+    /// Expands an item-position invocation of a crate-local `macro_rules!` macro and visits the
+    /// generated items as if they were written in place of the invocation.
+    ///
+    /// Invocations that cannot be expanded are skipped, as they were before expansion was
+    /// supported. See [`crate::parser::macro_expansion`] for details.
+    fn expand_item_macro(&mut self, item_macro: &syn::ItemMacro) {
+        #[cfg(feature = "cfg_eval")]
+        {
+            use crate::parser::visitor::attribute_processing::should_include_item;
+            if !should_include_item(&item_macro.attrs, &self.state.active_cfg) {
+                return; // Skip this item due to cfg
+            }
+        }
+        let invocation_path: Vec<String> = item_macro
+            .mac
+            .path
+            .segments
+            .iter()
+            .map(|seg| seg.ident.to_string())
+            .collect();
+        // Only bare and `crate::` paths can name a macro defined in this crate.
+        let macro_name = match invocation_path.as_slice() {
+            _ if item_macro.mac.path.leading_colon.is_some() => return,
+            [name] => name.clone(),
+            [krate, name] if krate == "crate" => name.clone(),
+            _ => return,
+        };
+        if self.state.macro_expansion_depth >= MAX_EXPANSION_DEPTH {
+            tracing::warn!(
+                target: VISITOR_TARGET_TRACE,
+                "Not expanding `{}!` in {}: expansion depth limit of {} reached",
+                macro_name, self.state.current_file_path.display(), MAX_EXPANSION_DEPTH
+            );
+            return;
+        }
+
+        let (macro_id, definition) = match self.state.local_macros.get(&macro_name) {
+            Some((id, definition)) => (Some(*id), Arc::clone(definition)),
+            None => match self.state.crate_macros.lookup(&macro_name) {
+                Some(definition) => (None, Arc::clone(definition)),
+                None => {
+                    self.state.unresolved_macros.push(macro_name);
+                    return;
+                }
+            },
+        };
+        let expanded = ploke_mbe::expand_macro(
+            &definition.mac,
+            item_macro.mac.tokens.clone(),
+            item_macro.span(),
+        )
+        .and_then(ploke_mbe::parse_expanded_items);
+        let items = match expanded {
+            Ok(items) => items,
+            Err(e) => {
+                trace!(target: VISITOR_TARGET_TRACE, "Not expanding `{}!`: {}", macro_name, e);
+                return;
+            }
+        };
+
+        let Some((module_id, items_before)) = self.current_module_items() else {
+            return;
+        };
+        // Generated items inherit the cfgs of the invocation.
+        let saved_cfgs = self.state.current_scope_cfgs.clone();
+        self.state
+            .current_scope_cfgs
+            .extend(extract_cfg_strings(&item_macro.attrs));
+        self.state.macro_expansion_depth += 1;
+        for item in &items {
+            self.visit_item(item);
+        }
+        self.state.macro_expansion_depth -= 1;
+        self.state.current_scope_cfgs = saved_cfgs;
+
+        let generated = self
+            .current_module_items()
+            .map(|(_, items)| items.into_iter().skip(items_before.len()).collect())
+            .unwrap_or_default();
+        self.state.code_graph.macro_expansions.push(MacroExpansion {
+            macro_name,
+            invocation_path,
+            module_id,
+            span: item_macro.extract_span_bytes(),
+            definition_file: definition.file_path.clone(),
+            definition_span: definition.span,
+            macro_id,
+            generated,
+        });
+    }
+
+    /// Returns the module at `current_module_path` and a copy of its item list.
+    fn current_module_items(&self) -> Option<(ModuleNodeId, Vec<PrimaryNodeId>)> {
+        self.state
+            .code_graph
+            .modules
+            .iter()
+            .find(|m| m.path == self.state.current_module_path)
+            .map(|m| (m.id, m.items().map(<[_]>::to_vec).unwrap_or_default()))
+    }
+
+    /// Adds an [`ImplNode`] for each path in the `#[derive(...)]` attributes of a struct, enum
+    /// or union named `ident`, as if the impl had been written next to the type.
+    ///
+    /// Derived impls have no methods and keep the type's generics. Their trait is the derive path
+    /// as written; `resolve_derives` resolves it through the module's imports after merging.
+    /// Must be called after the type's own scope has been popped, so that the impls are scoped
+    /// like the type itself. Derives behind `cfg_attr` are not seen.
+    fn add_derived_impls(
+        &mut self,
+        attrs: &[syn::Attribute],
+        ident: &syn::Ident,
+        generics: &syn::Generics,
+    ) {
+        let derive_paths: Vec<syn::Path> = attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("derive"))
+            .filter_map(|attr| {
+                attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+                    .ok()
+            })
+            .flatten()
+            .collect();
+        if derive_paths.is_empty() {
+            return;
+        }
+
+        // --- CFG Handling (Raw Strings) ---
+        let item_cfgs = extract_cfg_strings(attrs);
+        let provisional_effective_cfgs: Vec<String> = self
+            .state
+            .current_scope_cfgs
+            .iter()
+            .cloned()
+            .chain(item_cfgs.iter().cloned())
+            .collect();
+        let cfg_bytes = calculate_cfg_hash_bytes(&provisional_effective_cfgs);
+        // --- End CFG Handling ---
+
+        let (_, ty_generics, _) = generics.split_for_impl();
+        let self_ty: Type = syn::parse_quote!(#ident #ty_generics);
+        for path in derive_paths {
+            let macro_path: Vec<String> = path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect();
+            let impl_name = format!(
+                "impl derive({}) for {}",
+                macro_path.join("::"),
+                type_to_string(&self_ty)
+            );
+            let Some((impl_any_id, parent_mod_id)) =
+                self.register_new_node_id(&impl_name, ItemKind::Impl, cfg_bytes.as_deref())
+            else {
+                return;
+            };
+            self.debug_new_id(&impl_name, impl_any_id);
+
+            let impl_node_id: ImplNodeId = impl_any_id.try_into().unwrap();
+            self.push_primary_scope(&impl_name, impl_node_id.into(), &provisional_effective_cfgs);
+            let self_type = get_or_create_type(self.state, &self_ty);
+            let trait_type = get_or_create_type(
+                self.state,
+                &Type::Path(TypePath {
+                    qself: None,
+                    path: path.clone(),
+                }),
+            );
+            let generic_params = self.state.process_generics(generics);
+            self.pop_primary_scope(&impl_name);
+
+            let byte_range = path.span().byte_range();
+            self.state.code_graph.impls.push(ImplNode {
+                id: impl_node_id,
+                span: (byte_range.start, byte_range.end),
+                self_type,
+                trait_type: Some(trait_type),
+                methods: Vec::new(),
+                generic_params,
+                cfgs: item_cfgs.clone(),
+                derived: Some(DerivedImpl::new(macro_path)),
+            });
+            self.state
+                .code_graph
+                .relations
+                .push(SyntacticRelation::Contains {
+                    source: parent_mod_id,
+                    target: PrimaryNodeId::from(impl_node_id),
+                });
+        }
+    }
+
     /// Helper to push primary scope and log (using trace!)
     /// 'primary scope' here means a primary node, which may be defined directly within a module
     fn push_primary_scope(&mut self, name: &str, id: PrimaryNodeId, cfgs: &[String]) {
@@ -726,6 +923,11 @@ impl<'a, 'ast> Visit<'ast> for CodeVisitor<'a> {
             };
             self.state.code_graph.functions.push(function_node);
 
+            // Record call sites from the body, resolved after the module tree is built.
+            let call_sites =
+                CallCollector::new(fn_typed_id.into(), parent_mod_id).collect_block(&func.block);
+            self.state.code_graph.call_sites.extend(call_sites);
+
             // Create and add the Contains relation
             let relation = SyntacticRelation::Contains {
                 source: parent_mod_id,
@@ -880,6 +1082,12 @@ impl<'a, 'ast> Visit<'ast> for CodeVisitor<'a> {
 
         // Pop the struct's scope using the helper
         self.pop_primary_scope(&struct_name);
+
+        self.add_derived_impls(
+            &item_struct.attrs,
+            &item_struct.ident,
+            &item_struct.generics,
+        );
     }
 
     // Visit struct definitions
@@ -1027,6 +1235,12 @@ impl<'a, 'ast> Visit<'ast> for CodeVisitor<'a> {
 
         // Pop the struct's scope using the helper
         self.pop_primary_scope(&struct_name);
+
+        self.add_derived_impls(
+            &item_struct.attrs,
+            &item_struct.ident,
+            &item_struct.generics,
+        );
     }
 
     // Visit type alias definitions
@@ -1271,6 +1485,8 @@ impl<'a, 'ast> Visit<'ast> for CodeVisitor<'a> {
 
         // Pop the union's scope using the helper *after* visiting children
         self.pop_primary_scope(&union_name);
+
+        self.add_derived_impls(&item_union.attrs, &item_union.ident, &item_union.generics);
     }
 
     // Visit enum definitions
@@ -1536,12 +1752,19 @@ impl<'a, 'ast> Visit<'ast> for CodeVisitor<'a> {
 
         // Pop the enum's scope using the helper *after* visiting children
         self.pop_primary_scope(&enum_name);
+
+        self.add_derived_impls(&item_enum.attrs, &item_enum.ident, &item_enum.generics);
     }
 
     // Visit impl blocks
     fn visit_item_impl(&mut self, item_impl: &'ast ItemImpl) {
         let impl_span = item_impl.extract_span_bytes();
-        let impl_name = name_impl(item_impl, impl_span); // Use helper to generate a name for the impl block
+        let mut impl_name = name_impl(item_impl, impl_span); // Use helper to generate a name for the impl block
+        if self.state.macro_expansion_depth > 0 {
+            // Impls generated by one expansion all share the invocation span.
+            self.state.generated_impl_ordinal += 1;
+            impl_name.push_str(&format!(" #{}", self.state.generated_impl_ordinal));
+        }
         #[cfg(feature = "cfg_eval")]
         {
             use crate::parser::visitor::attribute_processing::should_include_item;
@@ -1710,6 +1933,11 @@ impl<'a, 'ast> Visit<'ast> for CodeVisitor<'a> {
                     cfgs: method_item_cfgs,
                 };
                 methods.push(method_node);
+
+                // Record call sites from the method body, resolved after the module tree is built.
+                let call_sites = CallCollector::new(method_typed_id.into(), parent_mod_id)
+                    .collect_block(&method.block);
+                self.state.code_graph.call_sites.extend(call_sites);
                 // ANCHOR_END: method_from_impl_node
             }
             // TODO: Handle syn::ImplItem::Const and syn::ImplItem::Type here
@@ -1735,6 +1963,7 @@ impl<'a, 'ast> Visit<'ast> for CodeVisitor<'a> {
             methods, // Pass the collected MethodNode Vec
             generic_params,
             cfgs: item_cfgs,
+            derived: None,
         };
         let typed_impl_id = impl_node.impl_id();
         trace!(target:"duplicate_impl", "{:-^40}
@@ -1921,6 +2150,13 @@ impl<'a, 'ast> Visit<'ast> for CodeVisitor<'a> {
                     cfgs: method_item_cfgs,
                 };
                 methods.push(method_node);
+
+                // Default method bodies can make calls as well.
+                if let Some(block) = &method.default {
+                    let call_sites = CallCollector::new(method_node_id.into(), parent_mod_id)
+                        .collect_block(block);
+                    self.state.code_graph.call_sites.extend(call_sites);
+                }
                 // ANCHOR_END: method_from_trait_node
             }
             // TODO: Handle syn::TraitItem::Const and syn::TraitItem::Type here
@@ -1938,7 +2174,7 @@ impl<'a, 'ast> Visit<'ast> for CodeVisitor<'a> {
         let generic_params = self.state.process_generics(&item_trait.generics);
 
         // Process super traits
-        let super_traits: Vec<TypeId> = item_trait
+        let mut super_traits: Vec<TypeId> = item_trait
             .supertraits
             .iter()
             .filter_map(|bound| {
@@ -1965,6 +2201,8 @@ impl<'a, 'ast> Visit<'ast> for CodeVisitor<'a> {
                 }
             })
             .collect();
+        // `where Self: Bound` is another way to declare a supertrait.
+        super_traits.extend(self.state.process_self_where_bounds(&item_trait.generics));
 
         // Pop the trait's ID from the scope stack AFTER processing its generics/supertraits
         // Note: This pop happens *before* visiting children, which might be incorrect
@@ -2592,6 +2830,7 @@ use statement ident: {:?}
     // Visit macro definitions (macro_rules!)
     fn visit_item_macro(&mut self, item_macro: &'ast syn::ItemMacro) {
         if item_macro.ident.as_ref().is_none() {
+            self.expand_item_macro(item_macro);
             return;
         }
         #[cfg(feature = "cfg_eval")]
@@ -2660,6 +2899,16 @@ use statement ident: {:?}
         // Convert macro ID
         let typed_macro_id: crate::parser::nodes::MacroNodeId = macro_any_id.try_into().unwrap();
 
+        // Make the definition available to invocations later in this file.
+        if let Some(definition) =
+            MacroDefinitions::parse_definition(item_macro, &self.state.current_file_path)
+        {
+            self.state.local_macros.insert(
+                definition.mac.name.clone(),
+                (typed_macro_id, Arc::new(definition)),
+            );
+        }
+
         // Construct MacroNode directly
         let macro_node = MacroNode {
             id: typed_macro_id, // Use typed ID
@@ -6,6 +6,7 @@ use std::{collections::HashMap, hash::Hasher};
 use syn::visit::Visit;
 use tracing::instrument;
 mod attribute_processing;
+mod call_collector;
 mod cfg_evaluator;
 #[cfg(feature = "cfg_eval")]
 pub use attribute_processing::parse_cfg_expr_from_inner_tokens;
@@ -23,6 +24,7 @@ use crate::{
     parser::{
         diagnostics::{TRACE_TARGET_INVARIANTS, emit_json_diagnostic},
         graph::GraphAccess,
+        macro_expansion::MacroDefinitions,
         nodes::{ModuleNodeInfo, PrimaryNodeId},
         relations::SyntacticRelation,
     },
@@ -30,6 +32,7 @@ use crate::{
 };
 
 use std::path::{Component, Path, PathBuf}; // Add Path and Component
+use std::sync::Arc;
 
 /// Derives the logical module path for a source file the same way Phase 2 parallel parsing does.
 ///
@@ -96,15 +99,40 @@ use {
 };
 
 /// Analyze a single file for Phase 2 (UUID Path) - The Worker Function
-/// Receives context from analyze_files_parallel.
+///
+/// Only `macro_rules!` macros defined in this file are expanded; [`analyze_files_parallel`] also
+/// makes the definitions from the rest of the crate available.
 #[cfg(feature = "cfg_eval")]
-#[instrument(target = TRACE_TARGET_INVARIANTS, skip(crate_context), fields(root_module_name))]
 pub fn analyze_file_phase2(
     file_path: PathBuf,
     crate_namespace: Uuid,            // Context passed from caller
     logical_module_path: Vec<String>, // NEW: The derived logical path for this file
     crate_context: &crate::discovery::CrateContext,
 ) -> Result<ParsedCodeGraph, syn::Error> {
+    analyze_file_with_macros(
+        file_path,
+        crate_namespace,
+        logical_module_path,
+        crate_context,
+        Arc::default(),
+    )
+    .map(|(pg, _)| pg)
+}
+
+/// Analyze a single file for Phase 2 (UUID Path) - The Worker Function
+/// Receives context from analyze_files_parallel.
+///
+/// Also returns the names of the item-position macro invocations that were not expanded because
+/// neither the file nor `crate_macros` defines the macro.
+#[cfg(feature = "cfg_eval")]
+#[instrument(target = TRACE_TARGET_INVARIANTS, skip(crate_context, crate_macros), fields(root_module_name))]
+fn analyze_file_with_macros(
+    file_path: PathBuf,
+    crate_namespace: Uuid,            // Context passed from caller
+    logical_module_path: Vec<String>, // NEW: The derived logical path for this file
+    crate_context: &crate::discovery::CrateContext,
+    crate_macros: Arc<MacroDefinitions>,
+) -> Result<(ParsedCodeGraph, Vec<String>), syn::Error> {
     // Consider a more specific Phase2Error later
 
     use super::nodes::ModuleKind;
@@ -133,6 +161,7 @@ pub fn analyze_file_phase2(
         state::VisitorState::new(crate_namespace, file_path.to_path_buf(), crate_context);
     // Set the correct initial module path for the visitor
     state.current_module_path = logical_module_path.clone();
+    state.crate_macros = crate_macros;
 
     // Extract raw file-level CFG strings (#![cfg(...)])
     let file_cfgs = extract_cfg_strings(&file.attrs);
@@ -254,21 +283,43 @@ validate_unique_rels = <deferred>", file_path.display());
     //     }
     // }
 
-    Ok(ParsedCodeGraph::new(
-        file_path,
-        crate_namespace,
-        state.code_graph,
+    Ok((
+        ParsedCodeGraph::new(file_path, crate_namespace, state.code_graph),
+        state.unresolved_macros,
     ))
 }
 
 /// Analyze a single file for Phase 2 (UUID Path) - The Worker Function
-/// Receives context from analyze_files_parallel.
+///
+/// Only `macro_rules!` macros defined in this file are expanded; [`analyze_files_parallel`] also
+/// makes the definitions from the rest of the crate available.
 #[cfg(not(feature = "cfg_eval"))]
 pub fn analyze_file_phase2(
     file_path: PathBuf,
     crate_namespace: Uuid,            // Context passed from caller
     logical_module_path: Vec<String>, // NEW: The derived logical path for this file
 ) -> Result<ParsedCodeGraph, syn::Error> {
+    analyze_file_with_macros(
+        file_path,
+        crate_namespace,
+        logical_module_path,
+        Arc::default(),
+    )
+    .map(|(pg, _)| pg)
+}
+
+/// Analyze a single file for Phase 2 (UUID Path) - The Worker Function
+/// Receives context from analyze_files_parallel.
+///
+/// Also returns the names of the item-position macro invocations that were not expanded because
+/// neither the file nor `crate_macros` defines the macro.
+#[cfg(not(feature = "cfg_eval"))]
+fn analyze_file_with_macros(
+    file_path: PathBuf,
+    crate_namespace: Uuid,            // Context passed from caller
+    logical_module_path: Vec<String>, // NEW: The derived logical path for this file
+    crate_macros: Arc<MacroDefinitions>,
+) -> Result<(ParsedCodeGraph, Vec<String>), syn::Error> {
     // Consider a more specific Phase2Error later
 
     use super::nodes::ModuleKind;
@@ -291,6 +342,7 @@ pub fn analyze_file_phase2(
     let mut state = state::VisitorState::new(crate_namespace, file_path.to_path_buf());
     // Set the correct initial module path for the visitor
     state.current_module_path = logical_module_path.clone();
+    state.crate_macros = crate_macros;
 
     // Extract raw file-level CFG strings (#![cfg(...)])
     let file_cfgs = extract_cfg_strings(&file.attrs);
@@ -403,10 +455,9 @@ pub fn analyze_file_phase2(
     //     }
     // }
 
-    Ok(ParsedCodeGraph::new(
-        file_path,
-        crate_namespace,
-        state.code_graph,
+    Ok((
+        ParsedCodeGraph::new(file_path, crate_namespace, state.code_graph),
+        state.unresolved_macros,
     ))
 }
 
@@ -507,10 +558,23 @@ fn debug_relationships(visitor: &CodeVisitor<'_>) {
 /// Process multiple files in parallel using rayon
 ///
 /// Takes DiscoveryOutput and distributes work to analyze_file_phase2.
-#[instrument(skip_all, fields(crate_count = discovery_output.crate_contexts.len()))]
 pub fn analyze_files_parallel(
     discovery_output: &DiscoveryOutput, // Takes output from Phase 1
     _num_workers: usize, // May not be directly used if relying on rayon's default pool size
+) -> Vec<Result<ParsedCodeGraph, SynParserError>> {
+    analyze_files_parallel_reusing(discovery_output, &|_| None)
+}
+
+/// Like [`analyze_files_parallel`], but takes the graph of a file from `reuse` when it returns
+/// one, and only parses the files for which it returns `None`.
+///
+/// The `macro_rules!` definitions of the crate are still collected from every graph, reused or
+/// not, but expansions in a reused graph are not redone: callers must not reuse the graphs of files
+/// whose expansions may have changed.
+#[instrument(skip_all, fields(crate_count = discovery_output.crate_contexts.len()))]
+pub fn analyze_files_parallel_reusing(
+    discovery_output: &DiscoveryOutput,
+    reuse: &(dyn Fn(&Path) -> Option<ParsedCodeGraph> + Sync),
 ) -> Vec<Result<ParsedCodeGraph, SynParserError>> {
     // Adjust error type if needed
 
@@ -527,35 +591,64 @@ pub fn analyze_files_parallel(
         .flat_map(|crate_context| {
             let parse_inputs = build_parse_inputs(crate_context);
             let selected_roots = selected_target_roots(crate_context);
-
-            parse_inputs.into_par_iter().map(move |input| {
+            let analyze = move |input: &ParseInput, crate_macros: Arc<MacroDefinitions>| {
                 // Call the single-file worker function with its specific context + logical path
                 #[cfg(not(feature = "cfg_eval"))]
-                let parsed = analyze_file_phase2(
+                let parsed = analyze_file_with_macros(
                     input.file_path.clone(),
                     crate_context.namespace,
-                    input.logical_path, // Pass the derived path
-
+                    input.logical_path.clone(), // Pass the derived path
+                    crate_macros,
                 )
-                .map(|pg| set_root_context(crate_context, &selected_roots, pg))
-                .map_err(|e| SynParserError::syn_parse_in_file(input.file_path.clone(), e))
-                .inspect(|pg| { log::debug!(target: "crate_context", "{}", info_crate_context(crate_context, pg)) });
+                .map_err(|e| SynParserError::syn_parse_in_file(input.file_path.clone(), e));
 
-                log::debug!(target: "debug_dup", "file path in par_iter: {}", input.file_path.display());
                 #[cfg(feature = "cfg_eval")]
-                let parsed = analyze_file_phase2(
+                let parsed = analyze_file_with_macros(
                     input.file_path.clone(),
                     crate_context.namespace,
-                    input.logical_path, // Pass the derived path
-                    crate_context
+                    input.logical_path.clone(), // Pass the derived path
+                    crate_context,
+                    crate_macros,
                 )
-                .map(|pg| set_root_context(crate_context, &selected_roots, pg))
                 .map_err(|e| {
                     tracing::trace!("Error found: {} (file: {})", e, input.file_path.display());
                     SynParserError::syn_parse_in_file(input.file_path.clone(), e)
+                });
+                log::debug!(target: "debug_dup", "file path in par_iter: {}", input.file_path.display());
+                parsed
+            };
+
+            // First pass: every file is parsed once, expanding only the macros it defines itself.
+            let first_pass: Vec<_> = parse_inputs
+                .into_par_iter()
+                .map(|input| {
+                    let parsed = match reuse(&input.file_path) {
+                        Some(pg) => Ok((pg, Vec::new())),
+                        None => analyze(&input, Arc::default()),
+                    };
+                    (input, parsed)
                 })
-                .inspect(|pg| { log::debug!(target: "crate_context", "{}", info_crate_context(crate_context, pg)) });
+                .collect();
+
+            // The crate's definitions come from the macro nodes of the parsed graphs. Only files
+            // invoking a macro defined in another file are visited again, with all definitions.
+            let crate_macros = Arc::new(MacroDefinitions::from_graphs(
+                first_pass
+                    .iter()
+                    .filter_map(|(_, parsed)| parsed.as_ref().ok().map(|(pg, _)| pg)),
+            ));
+            first_pass.into_par_iter().map(move |(input, parsed)| {
+                let parsed = match parsed {
+                    Ok((_, unresolved))
+                        if unresolved.iter().any(|name| crate_macros.lookup(name).is_some()) =>
+                    {
+                        analyze(&input, Arc::clone(&crate_macros))
+                    }
+                    parsed => parsed,
+                };
                 parsed
+                    .map(|(pg, _)| set_root_context(crate_context, &selected_roots, pg))
+                    .inspect(|pg| { log::debug!(target: "crate_context", "{}", info_crate_context(crate_context, pg)) })
             })
         })
         .collect(); // Collect all results (Result<ParsedCodeGraph, Error>) into a Vec
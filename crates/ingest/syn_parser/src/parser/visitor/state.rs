@@ -2,16 +2,17 @@
 
 use crate::discovery::CrateContext;
 use crate::parser::graph::CodeGraph;
+use crate::parser::macro_expansion::{MacroDefinition, MacroDefinitions};
 use crate::parser::nodes::{
     AnyNodeIdConversionError, GeneratesAnyNodeId, GenericParamNodeId, SecondaryNodeId,
 }; // Import AnyNodeIdConversionError, GenericParamNodeId
-use crate::parser::nodes::{AssociatedItemNodeId, PrimaryNodeId};
+use crate::parser::nodes::{AssociatedItemNodeId, MacroNodeId, PrimaryNodeId};
 use crate::parser::types::{GenericParamKind, GenericParamNode, VisibilityKind};
 use crate::utils::logging::LogErrorConversion; // Import the new logging trait
 use log::error;
 use ploke_core::ItemKind;
 use quote::ToTokens;
-use syn::{FnArg, Generics, Pat, PatIdent, PatType, TypeParam, Visibility};
+use syn::{FnArg, Generics, Pat, PatIdent, PatType, PredicateType, TypeParam, Visibility};
 
 use super::calculate_cfg_hash_bytes;
 use super::type_processing::get_or_create_type;
@@ -19,7 +20,9 @@ use super::type_processing::get_or_create_type;
 use {
     crate::parser::nodes::ParamData,
     ploke_core::{TrackingHash, TypeId},
+    std::collections::HashMap,
     std::path::PathBuf,
+    std::sync::Arc,
     uuid::Uuid,
 };
 
@@ -53,6 +56,17 @@ pub struct VisitorState {
 
     /// Ordinal for disambiguating anonymous items like `const _` within this file.
     pub(crate) anon_const_ordinal: usize,
+
+    /// `macro_rules!` definitions of the whole crate, used to expand item-position invocations.
+    pub(crate) crate_macros: Arc<MacroDefinitions>,
+    /// `macro_rules!` definitions visited so far in this file, with their node ids.
+    pub(crate) local_macros: HashMap<String, (MacroNodeId, Arc<MacroDefinition>)>,
+    /// How many macro expansions are currently being visited, see `MAX_EXPANSION_DEPTH`.
+    pub(crate) macro_expansion_depth: usize,
+    /// Ordinal for disambiguating impls generated by macro expansions within this file.
+    pub(crate) generated_impl_ordinal: usize,
+    /// Names of invoked macros defined neither in this file nor in `crate_macros`.
+    pub(crate) unresolved_macros: Vec<String>,
 }
 
 impl VisitorState {
@@ -75,6 +89,9 @@ impl VisitorState {
                 macros: Vec::new(),
                 use_statements: Vec::new(),
                 unresolved_nodes: Vec::new(),
+                call_sites: Vec::new(),
+                macro_expansions: Vec::new(),
+                tests: Vec::new(),
             },
             // New values needed for Uuid generation of Synthetic NodeId/TypeId variants
             crate_namespace,
@@ -94,6 +111,11 @@ impl VisitorState {
                 crate_context,
             ),
             anon_const_ordinal: 0,
+            crate_macros: Arc::default(),
+            local_macros: HashMap::new(),
+            macro_expansion_depth: 0,
+            generated_impl_ordinal: 0,
+            unresolved_macros: Vec::new(),
         }
     }
 
@@ -310,9 +332,63 @@ impl VisitorState {
             }
         }
 
+        // Fold `where T: Bound` predicates into the bounds of the parameter `T`.
+        for (name, bounds) in self.process_where_predicates(generics) {
+            if let Some(GenericParamKind::Type {
+                bounds: param_bounds,
+                ..
+            }) = params
+                .iter_mut()
+                .map(|param| &mut param.kind)
+                .find(|kind| kind.name() == Some(name.as_str()))
+            {
+                param_bounds.extend(bounds);
+            }
+        }
+
         params
     }
 
+    /// Returns the bounds of the `where Self: Bound` predicates in `generics`.
+    ///
+    /// Only meaningful on traits, where these predicates declare supertraits.
+    pub(crate) fn process_self_where_bounds(&mut self, generics: &Generics) -> Vec<TypeId> {
+        self.process_where_predicates(generics)
+            .into_iter()
+            .filter(|(name, _)| name == "Self")
+            .flat_map(|(_, bounds)| bounds)
+            .collect()
+    }
+
+    /// Processes the where-clause predicates whose bounded type is a single identifier, such as
+    /// a generic parameter or `Self`, returning that identifier with the trait bounds.
+    ///
+    /// Predicates on other types (`Vec<T>: Debug`, `T::Item: Display`) are skipped.
+    fn process_where_predicates(&mut self, generics: &Generics) -> Vec<(String, Vec<TypeId>)> {
+        let Some(where_clause) = &generics.where_clause else {
+            return Vec::new();
+        };
+        where_clause
+            .predicates
+            .iter()
+            .filter_map(|predicate| match predicate {
+                syn::WherePredicate::Type(PredicateType {
+                    bounded_ty: syn::Type::Path(type_path),
+                    bounds,
+                    ..
+                }) if type_path.qself.is_none() => {
+                    let ident = type_path.path.get_ident()?;
+                    let bounds = bounds
+                        .iter()
+                        .filter_map(|bound| self.process_type_bound(bound))
+                        .collect();
+                    Some((ident.to_string(), bounds))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Process type bounds for generics
     // Only handles trait bounds for now
     fn process_type_bound(&mut self, bound: &syn::TypeParamBound) -> Option<TypeId> {
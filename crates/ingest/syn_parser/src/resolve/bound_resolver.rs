@@ -0,0 +1,213 @@
+//! Resolution of the trait bounds of generic items into [`SyntacticRelation::RequiresTrait`]
+//! and [`SyntacticRelation::SuperTrait`] edges.
+//!
+//! Runs once the `ModuleTree` is built and the graph has been pruned. The bounds recorded by the
+//! `CodeVisitor` already include the where-clause predicates on a generic parameter
+//! (`where T: Router` is stored like `<T: Router>`), and `where Self: Trait` on a trait is stored
+//! with its supertraits. Each bound is resolved in the scope of the module containing the item
+//! through [`ModuleTree::resolve_path_in_scope`].
+//!
+//! Only the bound itself is linked: in `T: Into<Config>` the trait `Into` is the requirement,
+//! while `Config` is a type argument and is not followed. Bounds that do not resolve to a
+//! crate-local trait (`Clone`, `?Sized`, `Fn(u8)`) produce no edge; traits of other workspace
+//! members are linked later by `workspace_resolver`.
+//!
+//! **Limitations:** where-clause predicates on anything other than a bare generic parameter or
+//! `Self` (e.g. `Vec<T>: Debug` or `T::Item: Display`) are not recorded by the visitor.
+
+use std::collections::{HashMap, HashSet};
+
+use ploke_core::{TypeId, TypeKind};
+
+use crate::parser::{
+    ParsedCodeGraph,
+    graph::GraphAccess,
+    nodes::{GenericOwnerNodeId, ModuleNodeId, PrimaryNodeId, TraitNodeId, TypeDefNode},
+    relations::SyntacticRelation,
+    types::{GenericParamNode, TypeNode},
+};
+
+use super::module_tree::ModuleTree;
+use super::workspace_resolver::{CrateLocal, ExternalScope, resolve_path_or_external};
+
+const LOG_TARGET_BOUNDS: &str = "bound_resolver";
+
+/// The trait bounds written by one generic item, with the scope needed to resolve them.
+struct BoundUsage {
+    owner: GenericOwnerNodeId,
+    module_id: ModuleNodeId,
+    bounds: Vec<TypeId>,
+}
+
+fn param_bounds<'a>(params: impl IntoIterator<Item = &'a GenericParamNode>) -> Vec<TypeId> {
+    params
+        .into_iter()
+        .filter_map(|param| param.kind.bounds())
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// Collects the bounds of every live generic item in the graph that has at least one.
+fn collect_bounds(
+    graph: &ParsedCodeGraph,
+    modules: &HashMap<PrimaryNodeId, ModuleNodeId>,
+) -> Vec<BoundUsage> {
+    let mut usages = Vec::new();
+    let mut push = |owner: GenericOwnerNodeId, module_id: ModuleNodeId, bounds: Vec<TypeId>| {
+        if !bounds.is_empty() {
+            usages.push(BoundUsage {
+                owner,
+                module_id,
+                bounds,
+            });
+        }
+    };
+
+    for def in graph.defined_types() {
+        let (id, owner, generic_params) = match def {
+            TypeDefNode::Struct(s) => (PrimaryNodeId::from(s.id), s.id.into(), &s.generic_params),
+            TypeDefNode::Enum(e) => (PrimaryNodeId::from(e.id), e.id.into(), &e.generic_params),
+            TypeDefNode::Union(u) => (PrimaryNodeId::from(u.id), u.id.into(), &u.generic_params),
+            TypeDefNode::TypeAlias(t) => {
+                (PrimaryNodeId::from(t.id), t.id.into(), &t.generic_params)
+            }
+        };
+        if let Some(&module_id) = modules.get(&id) {
+            push(owner, module_id, param_bounds(generic_params));
+        }
+    }
+
+    for func in graph.functions() {
+        if let Some(&module_id) = modules.get(&PrimaryNodeId::from(func.id)) {
+            push(
+                func.id.into(),
+                module_id,
+                param_bounds(&func.generic_params),
+            );
+        }
+    }
+
+    let impls = graph.impls().iter().map(|imp| {
+        (
+            PrimaryNodeId::from(imp.id),
+            GenericOwnerNodeId::from(imp.id),
+            &imp.generic_params,
+            &imp.methods,
+        )
+    });
+    let traits = graph.traits().iter().map(|tr| {
+        (
+            PrimaryNodeId::from(tr.id),
+            GenericOwnerNodeId::from(tr.id),
+            &tr.generic_params,
+            &tr.methods,
+        )
+    });
+    for (id, owner, generic_params, methods) in impls.chain(traits) {
+        let Some(&module_id) = modules.get(&id) else {
+            continue;
+        };
+        push(owner, module_id, param_bounds(generic_params));
+        for method in methods {
+            push(
+                method.id.into(),
+                module_id,
+                param_bounds(&method.generic_params),
+            );
+        }
+    }
+
+    usages
+}
+
+/// Resolves the bound `type_id` to a trait, if it names one.
+fn resolve_bound(
+    types: &HashMap<TypeId, &TypeNode>,
+    tree: &ModuleTree,
+    external: &dyn ExternalScope,
+    module_id: ModuleNodeId,
+    type_id: TypeId,
+) -> Option<TraitNodeId> {
+    let TypeKind::Named { path, .. } = &types.get(&type_id)?.kind else {
+        return None;
+    };
+    match resolve_path_or_external(tree, external, module_id, path)? {
+        PrimaryNodeId::Trait(trait_id) => Some(trait_id),
+        _ => None,
+    }
+}
+
+/// Resolves the trait bounds of generic parameters, where-clauses and supertraits, and adds
+/// one [`SyntacticRelation::RequiresTrait`] or [`SyntacticRelation::SuperTrait`] edge per
+/// distinct (item, trait) pair.
+///
+/// Must run after the graph has been pruned against `tree`.
+pub(crate) fn resolve_generic_bounds(graph: &mut ParsedCodeGraph, tree: &ModuleTree) {
+    let new_relations = bound_relations(graph, tree, &CrateLocal);
+    graph.graph.relations.extend(new_relations);
+}
+
+/// Collects the [`SyntacticRelation::RequiresTrait`] and [`SyntacticRelation::SuperTrait`]
+/// edges of `graph` that it does not contain yet, resolving the paths that leave the crate
+/// through `external`.
+pub(super) fn bound_relations(
+    graph: &ParsedCodeGraph,
+    tree: &ModuleTree,
+    external: &dyn ExternalScope,
+) -> Vec<SyntacticRelation> {
+    let mut seen: HashSet<SyntacticRelation> = graph
+        .relations()
+        .iter()
+        .filter(|rel| rel.is_requires_trait() || rel.is_super_trait())
+        .copied()
+        .collect();
+    let modules: HashMap<PrimaryNodeId, ModuleNodeId> = graph
+        .relations()
+        .iter()
+        .filter_map(|rel| match rel {
+            SyntacticRelation::Contains { source, target } => Some((*target, *source)),
+            _ => None,
+        })
+        .collect();
+    let types: HashMap<TypeId, &TypeNode> = graph.type_graph().iter().map(|t| (t.id, t)).collect();
+
+    let mut new_relations = Vec::new();
+    let mut push = |relation: SyntacticRelation| {
+        if seen.insert(relation) {
+            new_relations.push(relation);
+        }
+    };
+    for usage in collect_bounds(graph, &modules) {
+        for &type_id in &usage.bounds {
+            if let Some(target) = resolve_bound(&types, tree, external, usage.module_id, type_id) {
+                push(SyntacticRelation::RequiresTrait {
+                    source: usage.owner,
+                    target,
+                });
+            }
+        }
+    }
+    for tr in graph.traits() {
+        let Some(&module_id) = modules.get(&PrimaryNodeId::from(tr.id)) else {
+            continue;
+        };
+        for &type_id in &tr.super_traits {
+            if let Some(target) = resolve_bound(&types, tree, external, module_id, type_id)
+                && target != tr.id
+            {
+                push(SyntacticRelation::SuperTrait {
+                    source: tr.id,
+                    target,
+                });
+            }
+        }
+    }
+
+    tracing::debug!(
+        target: LOG_TARGET_BOUNDS,
+        bound_edges = new_relations.len(),
+        "resolved generic bounds"
+    );
+    new_relations
+}
@@ -0,0 +1,262 @@
+//! Resolution of recorded [`CallSite`]s into [`SyntacticRelation::Calls`] edges.
+//!
+//! Runs once the `ModuleTree` is built and the graph has been pruned, so that paths written in
+//! function bodies can be resolved through the same definition index and import links used for
+//! `use` statements (see [`ModuleTree::resolve_path_in_scope`]).
+//!
+//! What is resolved:
+//! - `CallKind::Function`: the path is resolved in the caller's module scope.
+//! - `CallKind::AssociatedFunction`: the parent path is resolved to a type or trait, then the
+//!   method is looked up in that trait or in the impls of that type. `Self::f()` uses the impl
+//!   (or trait) the caller is defined in.
+//! - `CallKind::SelfMethod`: looked up like `Self::f()`.
+//! - `CallKind::Macro`: resolved by path, falling back to a uniquely named local `macro_rules!`
+//!   (textual macro scope is not tracked by the module tree).
+//!
+//! Method calls on other receivers (`CallKind::Method`) need type inference and are left
+//! unresolved, as is anything that points outside the crate. Calls into other workspace members
+//! are resolved later by `workspace_resolver`, through the same [`CallIndex`].
+
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::{
+    ParsedCodeGraph,
+    calls::{CallKind, CallSite},
+    graph::GraphAccess,
+    nodes::{
+        CallableNodeId, FunctionNodeId, ImplNode, ImplNodeId, MacroKind, MacroNodeId, MethodNodeId,
+        PrimaryNodeId, TraitNode, TraitNodeId,
+    },
+    relations::SyntacticRelation,
+};
+
+use super::module_tree::ModuleTree;
+use super::workspace_resolver::{CrateLocal, ExternalScope, resolve_path_or_external};
+
+const LOG_TARGET_CALLS: &str = "call_resolver";
+
+/// Where a method is defined.
+#[derive(Debug, Clone, Copy)]
+enum MethodOwner {
+    Impl(ImplNodeId),
+    Trait(TraitNodeId),
+}
+
+/// Lookup tables over the pruned graph, built once per resolution pass.
+pub(super) struct CallIndex<'a> {
+    tree: &'a ModuleTree,
+    /// Consulted for paths that do not resolve in `tree`.
+    external: &'a dyn ExternalScope,
+    live_callers: HashSet<CallableNodeId>,
+    method_owner: HashMap<MethodNodeId, MethodOwner>,
+    impls: HashMap<ImplNodeId, &'a ImplNode>,
+    traits: HashMap<TraitNodeId, &'a TraitNode>,
+    /// The definition each impl's self type resolves to, from the `ImplFor` edges.
+    impl_target: HashMap<ImplNodeId, PrimaryNodeId>,
+    impls_by_target: HashMap<PrimaryNodeId, Vec<ImplNodeId>>,
+    declarative_macros: HashMap<&'a str, Vec<MacroNodeId>>,
+}
+
+impl<'a> CallIndex<'a> {
+    pub(super) fn new(
+        graph: &'a ParsedCodeGraph,
+        tree: &'a ModuleTree,
+        external: &'a dyn ExternalScope,
+    ) -> Self {
+        let mut live_callers: HashSet<CallableNodeId> = graph
+            .functions()
+            .iter()
+            .map(|f| CallableNodeId::from(f.id))
+            .collect();
+        let mut method_owner = HashMap::new();
+        for imp in graph.impls() {
+            for m in &imp.methods {
+                live_callers.insert(m.id.into());
+                method_owner.insert(m.id, MethodOwner::Impl(imp.id));
+            }
+        }
+        for tr in graph.traits() {
+            for m in &tr.methods {
+                live_callers.insert(m.id.into());
+                method_owner.insert(m.id, MethodOwner::Trait(tr.id));
+            }
+        }
+
+        let mut impl_target = HashMap::new();
+        let mut impls_by_target: HashMap<PrimaryNodeId, Vec<ImplNodeId>> = HashMap::new();
+        for rel in graph.relations() {
+            if let SyntacticRelation::ImplFor { source, target } = rel {
+                impl_target.insert(*source, *target);
+                impls_by_target.entry(*target).or_default().push(*source);
+            }
+        }
+
+        let mut declarative_macros: HashMap<&str, Vec<MacroNodeId>> = HashMap::new();
+        for mac in graph.macros() {
+            if matches!(mac.kind, MacroKind::DeclarativeMacro) {
+                declarative_macros
+                    .entry(mac.name.as_str())
+                    .or_default()
+                    .push(mac.id);
+            }
+        }
+
+        Self {
+            tree,
+            external,
+            live_callers,
+            method_owner,
+            impls: graph.impls().iter().map(|imp| (imp.id, imp)).collect(),
+            traits: graph.traits().iter().map(|tr| (tr.id, tr)).collect(),
+            impl_target,
+            impls_by_target,
+            declarative_macros,
+        }
+    }
+
+    pub(super) fn resolve(&self, site: &CallSite) -> Option<CallableNodeId> {
+        match site.kind {
+            CallKind::Function => self
+                .resolve_path(site, &site.path)
+                .and_then(|found| FunctionNodeId::try_from(found).ok())
+                .map(CallableNodeId::from),
+            CallKind::AssociatedFunction => {
+                let (name, parent) = site.path.split_last()?;
+                if parent.len() == 1 && parent[0] == "Self" {
+                    return self.method_on_caller_owner(site.caller, name);
+                }
+                self.method_of(self.resolve_path(site, parent)?, name)
+            }
+            CallKind::SelfMethod => self.method_on_caller_owner(site.caller, site.callee_name()?),
+            CallKind::Macro => self.resolve_macro(site),
+            CallKind::Method => None,
+        }
+    }
+
+    /// Resolves `path` in the scope of `site`, falling back to `external` for paths that leave
+    /// the crate.
+    fn resolve_path(&self, site: &CallSite, path: &[String]) -> Option<PrimaryNodeId> {
+        resolve_path_or_external(self.tree, self.external, site.module_id, path)
+    }
+
+    /// Looks up `name` in the trait `owner`, or in the impls of the type definition `owner`.
+    pub(super) fn method_of(&self, owner: PrimaryNodeId, name: &str) -> Option<CallableNodeId> {
+        match owner {
+            PrimaryNodeId::Trait(trait_id) => self
+                .trait_method(trait_id, name)
+                .or_else(|| self.external.method(owner, name)),
+            type_def => self.method_on_type(type_def, name),
+        }
+    }
+
+    /// Resolves `Self::name` / `self.name()` relative to the impl or trait defining `caller`.
+    fn method_on_caller_owner(&self, caller: CallableNodeId, name: &str) -> Option<CallableNodeId> {
+        let CallableNodeId::Method(method_id) = caller else {
+            return None;
+        };
+        match *self.method_owner.get(&method_id)? {
+            MethodOwner::Impl(impl_id) => self.impl_method(impl_id, name).or_else(|| {
+                self.impl_target
+                    .get(&impl_id)
+                    .and_then(|target| self.method_on_type(*target, name))
+            }),
+            MethodOwner::Trait(trait_id) => self.trait_method(trait_id, name),
+        }
+    }
+
+    /// Looks up `name` in the impls of `type_def`, preferring inherent impls over trait impls.
+    /// The impls of a type from another crate are looked up in this crate, then through
+    /// `external`.
+    fn method_on_type(&self, type_def: PrimaryNodeId, name: &str) -> Option<CallableNodeId> {
+        let impl_ids = self
+            .impls_by_target
+            .get(&type_def)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let (inherent, trait_impls): (Vec<&ImplNodeId>, Vec<&ImplNodeId>) =
+            impl_ids.iter().partition(|id| {
+                self.impls
+                    .get(id)
+                    .is_some_and(|imp| imp.trait_type.is_none())
+            });
+        inherent
+            .into_iter()
+            .chain(trait_impls)
+            .find_map(|impl_id| self.impl_method(*impl_id, name))
+            .or_else(|| self.external.method(type_def, name))
+    }
+
+    fn impl_method(&self, impl_id: ImplNodeId, name: &str) -> Option<CallableNodeId> {
+        self.impls
+            .get(&impl_id)?
+            .methods
+            .iter()
+            .find(|m| m.name == name)
+            .map(|m| m.id.into())
+    }
+
+    fn trait_method(&self, trait_id: TraitNodeId, name: &str) -> Option<CallableNodeId> {
+        self.traits
+            .get(&trait_id)?
+            .methods
+            .iter()
+            .find(|m| m.name == name)
+            .map(|m| m.id.into())
+    }
+
+    fn resolve_macro(&self, site: &CallSite) -> Option<CallableNodeId> {
+        if let Some(PrimaryNodeId::Macro(macro_id)) = self.resolve_path(site, &site.path) {
+            return Some(macro_id.into());
+        }
+        // `macro_rules!` macros are usually invoked by bare name through textual scope, which
+        // the definition index does not model. Accept the match only if it is unambiguous.
+        let [name] = site.path.as_slice() else {
+            return None;
+        };
+        match self.declarative_macros.get(name.as_str())?.as_slice() {
+            [only] => Some((*only).into()),
+            _ => None,
+        }
+    }
+}
+
+/// Drops call sites whose caller was pruned, resolves the remaining ones, and adds one
+/// [`SyntacticRelation::Calls`] edge per distinct (caller, callee) pair.
+///
+/// Must run after the graph has been pruned against `tree`, and after `resolve_impls` so that
+/// the [`SyntacticRelation::ImplFor`] edges are available.
+pub(crate) fn resolve_call_sites(graph: &mut ParsedCodeGraph, tree: &ModuleTree) {
+    let mut call_sites = std::mem::take(&mut graph.graph.call_sites);
+    let mut seen: HashSet<SyntacticRelation> = graph
+        .relations()
+        .iter()
+        .filter(|rel| rel.is_calls())
+        .copied()
+        .collect();
+    let mut new_relations = Vec::new();
+    {
+        let index = CallIndex::new(graph, tree, &CrateLocal);
+        call_sites.retain(|site| index.live_callers.contains(&site.caller));
+        for site in call_sites.iter_mut() {
+            site.resolved = index.resolve(site);
+            if let Some(target) = site.resolved {
+                let relation = SyntacticRelation::Calls {
+                    source: site.caller,
+                    target,
+                };
+                if seen.insert(relation) {
+                    new_relations.push(relation);
+                }
+            }
+        }
+    }
+    tracing::debug!(
+        target: LOG_TARGET_CALLS,
+        call_sites = call_sites.len(),
+        resolved = call_sites.iter().filter(|site| site.is_resolved()).count(),
+        calls_edges = new_relations.len(),
+        "resolved call sites"
+    );
+    graph.graph.relations.extend(new_relations);
+    graph.graph.call_sites = call_sites;
+}
@@ -0,0 +1,112 @@
+//! Resolution of the impls synthesized from `#[derive(...)]` attributes.
+//!
+//! Runs on the merged graph before the `ModuleTree` is built, since derives that implement no
+//! trait are removed along with their `Contains` relation and their entry in the module's items.
+//! The macro path of each derived impl is resolved through the non-glob imports of the module
+//! containing it, and the trait it implements is looked up in a [`DeriveTable`].
+//!
+//! The `trait_type` of a derived impl is left as written, so that `impl_resolver` resolves it
+//! like the trait of a hand-written impl.
+//!
+//! **Limitations:** derives brought into scope by glob imports or re-exported by another module
+//! of the crate are looked up by the path as written.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::{
+    ParsedCodeGraph,
+    derives::DeriveTable,
+    graph::GraphAccess,
+    nodes::{ImplNodeId, ImportNode, ModuleKind, ModuleNodeId, PrimaryNodeId},
+    relations::SyntacticRelation,
+};
+
+const LOG_TARGET_DERIVES: &str = "derive_resolver";
+
+/// Resolves `macro_path` through the non-glob imports of a module.
+fn resolve_macro_path(imports: &[ImportNode], macro_path: &[String]) -> Vec<String> {
+    let Some(first) = macro_path.first() else {
+        return Vec::new();
+    };
+    imports
+        .iter()
+        .find(|imp| !imp.is_glob && &imp.visible_name == first)
+        .map(|imp| {
+            imp.source_path()
+                .iter()
+                .chain(&macro_path[1..])
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_else(|| macro_path.to_vec())
+}
+
+/// Fills in the resolved macro path and trait path of every derived impl from `table`, and
+/// removes the derived impls whose derive implements no trait.
+///
+/// Must run on the merged graph, before the module tree is built.
+pub(crate) fn resolve_derives(graph: &mut ParsedCodeGraph, table: &DeriveTable) {
+    let impl_module: HashMap<PrimaryNodeId, ModuleNodeId> = graph
+        .relations()
+        .iter()
+        .filter_map(|rel| match rel {
+            SyntacticRelation::Contains {
+                source,
+                target: target @ PrimaryNodeId::Impl(_),
+            } => Some((*target, *source)),
+            _ => None,
+        })
+        .collect();
+    let module_imports: HashMap<ModuleNodeId, Vec<ImportNode>> = graph
+        .modules()
+        .iter()
+        .map(|m| (m.id, m.imports.clone()))
+        .collect();
+
+    let mut removed: HashSet<ImplNodeId> = HashSet::new();
+    let mut resolved = 0;
+    for imp in graph.graph.impls.iter_mut() {
+        let Some(derived) = imp.derived.as_mut() else {
+            continue;
+        };
+        let imports = impl_module
+            .get(&PrimaryNodeId::from(imp.id))
+            .and_then(|module_id| module_imports.get(module_id))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let resolved_path = resolve_macro_path(imports, &derived.macro_path);
+        derived.trait_path = table.trait_path(&resolved_path);
+        if derived.trait_path.is_none() {
+            removed.insert(imp.id);
+        }
+        derived.resolved_path = Some(resolved_path);
+        resolved += 1;
+    }
+
+    if !removed.is_empty() {
+        graph.graph.impls.retain(|imp| !removed.contains(&imp.id));
+        graph.graph.relations.retain(|rel| match rel {
+            SyntacticRelation::Contains {
+                target: PrimaryNodeId::Impl(impl_id),
+                ..
+            } => !removed.contains(impl_id),
+            _ => true,
+        });
+        for module in graph.graph.modules.iter_mut() {
+            if let ModuleKind::Inline { items, .. } | ModuleKind::FileBased { items, .. } =
+                &mut module.module_def
+            {
+                items.retain(|item| match item {
+                    PrimaryNodeId::Impl(impl_id) => !removed.contains(impl_id),
+                    _ => true,
+                });
+            }
+        }
+    }
+    tracing::debug!(
+        target: LOG_TARGET_DERIVES,
+        derived_impls = resolved,
+        removed = removed.len(),
+        "resolved derives"
+    );
+}
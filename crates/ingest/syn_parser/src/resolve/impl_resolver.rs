@@ -0,0 +1,161 @@
+//! Resolution of impl headers into [`SyntacticRelation::ImplFor`],
+//! [`SyntacticRelation::ImplementsTrait`] and [`SyntacticRelation::ImplementsTraitMethod`] edges.
+//!
+//! Runs once the `ModuleTree` is built and the graph has been pruned, and before the other
+//! resolution passes that need to know which type an impl is for (see `call_resolver` and
+//! `type_usage_resolver`).
+//!
+//! The self type and trait of an impl are `TypeKind::Named` paths as written in the impl header,
+//! resolved in the scope of the module containing the impl through
+//! [`ModuleTree::resolve_path_in_scope`]. Impls for references, tuples and other non-path types,
+//! and impls of traits from other crates, only get the edges that can be resolved. Traits and
+//! types of other workspace members are linked later by `workspace_resolver`.
+
+use std::collections::{HashMap, HashSet};
+
+use ploke_core::{TypeId, TypeKind};
+
+use crate::parser::{
+    ParsedCodeGraph,
+    graph::GraphAccess,
+    nodes::{ImplNode, ModuleNodeId, PrimaryNodeId, TraitNode, TraitNodeId},
+    relations::SyntacticRelation,
+    types::TypeNode,
+};
+
+use super::module_tree::ModuleTree;
+use super::workspace_resolver::{CrateLocal, ExternalScope, resolve_path_or_external};
+
+const LOG_TARGET_IMPLS: &str = "impl_resolver";
+
+/// Resolves the `TypeKind::Named` path of `type_id` in the scope of `module_id`, falling back to
+/// `external` for paths that leave the crate.
+fn resolve_named_type(
+    tree: &ModuleTree,
+    external: &dyn ExternalScope,
+    types: &HashMap<TypeId, &TypeNode>,
+    module_id: ModuleNodeId,
+    type_id: TypeId,
+) -> Option<PrimaryNodeId> {
+    match &types.get(&type_id)?.kind {
+        TypeKind::Named { path, .. } => resolve_path_or_external(tree, external, module_id, path),
+        _ => None,
+    }
+}
+
+/// Collects the edges for a single impl from its resolved self type and trait.
+fn impl_relations(
+    imp: &ImplNode,
+    self_target: Option<PrimaryNodeId>,
+    trait_target: Option<&TraitNode>,
+    out: &mut Vec<SyntacticRelation>,
+) {
+    if let Some(target) = self_target {
+        out.push(SyntacticRelation::ImplFor {
+            source: imp.id,
+            target,
+        });
+    }
+    let Some(trait_node) = trait_target else {
+        return;
+    };
+    out.push(SyntacticRelation::ImplementsTrait {
+        source: imp.id,
+        target: trait_node.id,
+    });
+    for method in &imp.methods {
+        if let Some(trait_method) = trait_node.methods.iter().find(|m| m.name == method.name) {
+            out.push(SyntacticRelation::ImplementsTraitMethod {
+                source: method.id,
+                target: trait_method.id,
+            });
+        }
+    }
+}
+
+/// Resolves the self type and trait of every live impl, adding [`SyntacticRelation::ImplFor`]
+/// and [`SyntacticRelation::ImplementsTrait`] edges, and links each method of a trait impl to
+/// the trait method it provides with [`SyntacticRelation::ImplementsTraitMethod`].
+///
+/// Must run after the graph has been pruned against `tree`.
+pub(crate) fn resolve_impls(graph: &mut ParsedCodeGraph, tree: &ModuleTree) {
+    let new_relations = impl_header_relations(graph, tree, &CrateLocal);
+    tracing::debug!(
+        target: LOG_TARGET_IMPLS,
+        impl_for = new_relations.iter().filter(|rel| rel.is_impl_for()).count(),
+        implements_trait = new_relations.iter().filter(|rel| rel.is_implements_trait()).count(),
+        implements_trait_method = new_relations
+            .iter()
+            .filter(|rel| rel.is_implements_trait_method())
+            .count(),
+        "resolved impls"
+    );
+    graph.graph.relations.extend(new_relations);
+}
+
+/// Collects the impl header edges of `graph` that it does not contain yet, resolving the paths
+/// that leave the crate through `external`.
+pub(super) fn impl_header_relations(
+    graph: &ParsedCodeGraph,
+    tree: &ModuleTree,
+    external: &dyn ExternalScope,
+) -> Vec<SyntacticRelation> {
+    let mut seen: HashSet<SyntacticRelation> = graph
+        .relations()
+        .iter()
+        .filter(|rel| {
+            rel.is_impl_for() || rel.is_implements_trait() || rel.is_implements_trait_method()
+        })
+        .copied()
+        .collect();
+    let mut new_relations = Vec::new();
+    {
+        let types: HashMap<TypeId, &TypeNode> =
+            graph.type_graph().iter().map(|t| (t.id, t)).collect();
+        let impl_module: HashMap<PrimaryNodeId, ModuleNodeId> = graph
+            .relations()
+            .iter()
+            .filter_map(|rel| match rel {
+                SyntacticRelation::Contains {
+                    source,
+                    target: target @ PrimaryNodeId::Impl(_),
+                } => Some((*target, *source)),
+                _ => None,
+            })
+            .collect();
+        let traits: HashMap<TraitNodeId, &TraitNode> =
+            graph.traits().iter().map(|tr| (tr.id, tr)).collect();
+
+        let mut relations = Vec::new();
+        for imp in graph.impls() {
+            let Some(&module_id) = impl_module.get(&PrimaryNodeId::from(imp.id)) else {
+                continue;
+            };
+            let self_target = resolve_named_type(tree, external, &types, module_id, imp.self_type)
+                .filter(|target| {
+                    matches!(
+                        target,
+                        PrimaryNodeId::Struct(_)
+                            | PrimaryNodeId::Enum(_)
+                            | PrimaryNodeId::Union(_)
+                            | PrimaryNodeId::TypeAlias(_)
+                    )
+                });
+            let trait_target = imp
+                .trait_type
+                .and_then(|trait_type| {
+                    resolve_named_type(tree, external, &types, module_id, trait_type)
+                })
+                .and_then(|target| TraitNodeId::try_from(target).ok())
+                .and_then(|trait_id| {
+                    traits
+                        .get(&trait_id)
+                        .copied()
+                        .or_else(|| external.trait_node(trait_id))
+                });
+            impl_relations(imp, self_target, trait_target, &mut relations);
+        }
+        new_relations.extend(relations.into_iter().filter(|rel| seen.insert(*rel)));
+    }
+    new_relations
+}
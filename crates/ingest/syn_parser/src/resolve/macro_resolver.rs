@@ -0,0 +1,97 @@
+//! Resolution of recorded [`MacroExpansion`]s into [`SyntacticRelation::ExpandsTo`] edges.
+//!
+//! Runs once the graph has been pruned against the `ModuleTree`. Expansions whose invocation was
+//! pruned are dropped, and generated items that were pruned are removed from
+//! [`MacroExpansion::generated`].
+//!
+//! The `CodeVisitor` only knows the id of the defining macro when it is defined in the same file
+//! as the invocation. For the others, the defining `macro_rules!` node is found by name and by
+//! the span recorded for its definition.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::{
+    ParsedCodeGraph,
+    graph::GraphAccess,
+    macro_expansion::MacroExpansion,
+    nodes::{MacroKind, MacroNodeId, ModuleNodeId, PrimaryNodeId},
+    relations::SyntacticRelation,
+};
+
+const LOG_TARGET_MACROS: &str = "macro_resolver";
+
+/// Drops expansions whose invocation was pruned, fills in the defining macro of the remaining
+/// ones, and adds one [`SyntacticRelation::ExpandsTo`] edge per live generated item.
+///
+/// Must run after the graph has been pruned.
+pub(crate) fn resolve_macro_expansions(graph: &mut ParsedCodeGraph) {
+    let mut expansions = std::mem::take(&mut graph.graph.macro_expansions);
+    let mut seen: HashSet<SyntacticRelation> = graph
+        .relations()
+        .iter()
+        .filter(|rel| rel.is_expands_to())
+        .copied()
+        .collect();
+    let mut new_relations = Vec::new();
+    {
+        let live_modules: HashSet<ModuleNodeId> = graph.modules().iter().map(|m| m.id).collect();
+        let live_items: HashSet<PrimaryNodeId> = graph
+            .relations()
+            .iter()
+            .filter_map(|rel| match rel {
+                SyntacticRelation::Contains { target, .. } => Some(*target),
+                _ => None,
+            })
+            .collect();
+        let mut macros_by_def: HashMap<(&str, (usize, usize)), Vec<MacroNodeId>> = HashMap::new();
+        for mac in graph.macros() {
+            if matches!(mac.kind, MacroKind::DeclarativeMacro) {
+                macros_by_def
+                    .entry((mac.name.as_str(), mac.span))
+                    .or_default()
+                    .push(mac.id);
+            }
+        }
+        let live_macros: HashSet<MacroNodeId> = graph.macros().iter().map(|m| m.id).collect();
+
+        expansions.retain(|expansion| live_modules.contains(&expansion.module_id));
+        for expansion in expansions.iter_mut() {
+            expansion.generated.retain(|id| live_items.contains(id));
+            expansion.macro_id = expansion
+                .macro_id
+                .filter(|id| live_macros.contains(id))
+                .or_else(|| defining_macro(&macros_by_def, expansion));
+            let Some(source) = expansion.macro_id else {
+                continue;
+            };
+            for &target in &expansion.generated {
+                let relation = SyntacticRelation::ExpandsTo { source, target };
+                if seen.insert(relation) {
+                    new_relations.push(relation);
+                }
+            }
+        }
+    }
+    tracing::debug!(
+        target: LOG_TARGET_MACROS,
+        expansions = expansions.len(),
+        expands_to_edges = new_relations.len(),
+        "resolved macro expansions"
+    );
+    graph.graph.relations.extend(new_relations);
+    graph.graph.macro_expansions = expansions;
+}
+
+/// Finds the `macro_rules!` node for the definition used by `expansion`, if it is unambiguous.
+fn defining_macro(
+    macros_by_def: &HashMap<(&str, (usize, usize)), Vec<MacroNodeId>>,
+    expansion: &MacroExpansion,
+) -> Option<MacroNodeId> {
+    match macros_by_def
+        .get(&(expansion.macro_name.as_str(), expansion.definition_span))?
+        .as_slice()
+    {
+        [only] => Some(*only),
+        _ => None,
+    }
+}
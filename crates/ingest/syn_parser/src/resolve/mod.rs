@@ -1,17 +1,33 @@
+mod bound_resolver;
+mod call_resolver;
+mod derive_resolver;
 mod error;
 pub mod id_resolver;
+mod impl_resolver;
 mod logging;
+mod macro_resolver;
 pub mod module_tree;
 mod path_resolver;
 mod relation_indexer;
+mod test_resolver;
+mod type_usage_resolver;
+mod workspace_resolver;
 
 #[cfg(not(feature = "not_wip_marker"))]
 pub mod traversal;
 
 use std::{collections::HashSet, path::PathBuf};
 
+pub(crate) use bound_resolver::resolve_generic_bounds;
+pub(crate) use call_resolver::resolve_call_sites;
+pub(crate) use derive_resolver::resolve_derives;
 pub use error::ModuleTreeError;
+pub(crate) use impl_resolver::resolve_impls;
+pub(crate) use macro_resolver::resolve_macro_expansions;
 pub use relation_indexer::RelationIndexer;
+pub(crate) use test_resolver::resolve_tests;
+pub(crate) use type_usage_resolver::resolve_type_usages;
+pub(crate) use workspace_resolver::resolve_workspace_paths;
 
 // -- local re-exports for children
 use logging::LogTree;
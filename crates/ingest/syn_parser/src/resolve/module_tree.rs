@@ -1536,6 +1536,80 @@ impl ModuleTree {
         Ok(self.definition_index.get(&node_path).copied())
     }
 
+    /// Resolves a path as written inside `scope_mod_id` (e.g. in a function body) to a
+    /// crate-local definition.
+    ///
+    /// Lookup order:
+    /// 1. The path relative to the scope (handles `crate`, `self` and `super`).
+    /// 2. A non-glob `use` in the scope whose visible name matches the first segment, with the
+    ///    import's source path substituted for that segment.
+    /// 3. Each glob import of the scope, with the glob's source path prepended.
+    ///
+    /// If the path lands on an import site (e.g. a `pub use` re-export), the `ImportedBy` link
+    /// created by `link_definition_imports` is followed to the definition.
+    ///
+    /// Returns `None` for paths into external crates, for paths that do not resolve, and if the
+    /// definition index has not been built yet.
+    pub fn resolve_path_in_scope(
+        &self,
+        scope_mod_id: ModuleNodeId,
+        segments: &[String],
+    ) -> Option<PrimaryNodeId> {
+        if !self.definition_index_ready || segments.is_empty() {
+            return None;
+        }
+        let lookup = |path: &[String]| {
+            self.lookup_definition_by_segments(scope_mod_id, path)
+                .ok()
+                .flatten()
+                .map(|found| self.follow_import_to_definition(found))
+        };
+
+        if let Some(found) = lookup(segments) {
+            return Some(found);
+        }
+
+        let imports = &self.modules.get(&scope_mod_id)?.imports;
+        let first = &segments[0];
+        if let Some(import) = imports
+            .iter()
+            .find(|imp| !imp.is_glob && !imp.is_extern_crate() && &imp.visible_name == first)
+        {
+            let expanded = import
+                .source_path()
+                .iter()
+                .chain(&segments[1..])
+                .cloned()
+                .collect::<Vec<_>>();
+            return lookup(&expanded);
+        }
+
+        imports.iter().filter(|imp| imp.is_glob).find_map(|glob| {
+            let expanded = glob
+                .source_path()
+                .iter()
+                .chain(segments)
+                .cloned()
+                .collect::<Vec<_>>();
+            lookup(&expanded)
+        })
+    }
+
+    /// Maps an import site to the definition it brings into scope, if it was linked.
+    fn follow_import_to_definition(&self, found: PrimaryNodeId) -> PrimaryNodeId {
+        let PrimaryNodeId::Import(import_id) = found else {
+            return found;
+        };
+        self.get_iter_relations_to(&import_id.as_any())
+            .find_map(|tr| match tr.rel() {
+                SyntacticRelation::ImportedBy { source, target } if *target == import_id => {
+                    Some(*source)
+                }
+                _ => None,
+            })
+            .unwrap_or(found)
+    }
+
     #[allow(dead_code)]
     fn get_reexport_name(&self, module_id: ModuleNodeId, item_id: ImportNodeId) -> Option<String> {
         // Changed: item_id is ImportNodeId
@@ -0,0 +1,372 @@
+//! Collection of the tests of a crate into [`TestNode`]s.
+//!
+//! Runs once the `ModuleTree` is built, the graph has been pruned and call sites have been
+//! resolved. Two kinds of items become tests:
+//!
+//! - Functions with a `#[test]` (including `#[tokio::test]` and other `<path>::test` attributes) or
+//!   `#[bench]` attribute. Their kind depends on the file they are in: files under `tests/` are
+//!   integration tests and files under `benches/` are benches. Each resolved call site of the
+//!   function becomes a [`TestCoverage::Call`] target, and when the function sits in a
+//!   `#[cfg(test)]` module, the module enclosing the outermost `#[cfg(test)]` module becomes a
+//!   [`TestCoverage::EnclosingModule`] target.
+//! - Rust code blocks in the documentation of functions, methods, type definitions, traits,
+//!   modules, consts, statics and macros. The documented item is the only target.
+//!
+//! **Limitations:** paths in doc-tests are not resolved, so the items a doc-test calls are not
+//! linked. Test functions generated by macros such as `#[rstest]` or `#[test_case]` are not
+//! recognised.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::parser::{
+    ParsedCodeGraph,
+    graph::GraphAccess,
+    nodes::{
+        AnyNodeId, AsAnyNodeId, Attribute, CallableNodeId, FunctionNode, ModuleNode, ModuleNodeId,
+        PrimaryNodeId, PrimaryNodeIdTrait, TypeDefNode,
+    },
+    relations::SyntacticRelation,
+    test_items::{TestCoverage, TestKind, TestNode, TestTarget, extract_doctests},
+};
+
+use super::module_tree::ModuleTree;
+
+const LOG_TARGET_TESTS: &str = "test_resolver";
+
+/// The attribute that makes a function a test, if any.
+fn test_attribute(attributes: &[Attribute]) -> Option<TestKind> {
+    attributes.iter().find_map(|attr| {
+        let name: String = attr.name.split_whitespace().collect();
+        match name.rsplit("::").next() {
+            Some("test") => Some(TestKind::Unit),
+            Some("bench") if name == "bench" => Some(TestKind::Bench),
+            _ => None,
+        }
+    })
+}
+
+fn is_ignored(attributes: &[Attribute]) -> bool {
+    attributes.iter().any(|attr| attr.name == "ignore")
+}
+
+/// Lookup tables over the pruned graph.
+struct TestIndex<'a> {
+    tree: &'a ModuleTree,
+    crate_root: Option<&'a Path>,
+    modules: HashMap<ModuleNodeId, &'a ModuleNode>,
+    /// The module containing each primary item.
+    containing_module: HashMap<PrimaryNodeId, ModuleNodeId>,
+    /// The resolved callees of each caller, in call order.
+    callees: HashMap<CallableNodeId, Vec<CallableNodeId>>,
+}
+
+impl<'a> TestIndex<'a> {
+    fn new(graph: &'a ParsedCodeGraph, tree: &'a ModuleTree) -> Self {
+        let containing_module = graph
+            .relations()
+            .iter()
+            .filter_map(|rel| match rel {
+                SyntacticRelation::Contains { source, target } => Some((*target, *source)),
+                _ => None,
+            })
+            .collect();
+        let mut callees: HashMap<CallableNodeId, Vec<CallableNodeId>> = HashMap::new();
+        for site in &graph.graph.call_sites {
+            if let Some(callee) = site.resolved {
+                let entry = callees.entry(site.caller).or_default();
+                if !entry.contains(&callee) {
+                    entry.push(callee);
+                }
+            }
+        }
+        Self {
+            tree,
+            crate_root: graph.crate_context.as_ref().map(|c| c.root_path.as_path()),
+            modules: graph.modules().iter().map(|m| (m.id, m)).collect(),
+            containing_module,
+            callees,
+        }
+    }
+
+    fn file_path<T: PrimaryNodeIdTrait>(&self, id: T) -> Option<PathBuf> {
+        self.tree
+            .find_defining_file_path_ref_seq(id)
+            .ok()
+            .map(Path::to_path_buf)
+    }
+
+    fn is_cfg_test(&self, module_id: ModuleNodeId) -> bool {
+        self.modules
+            .get(&module_id)
+            .is_some_and(|m| m.cfgs.iter().any(|cfg| cfg == "test"))
+    }
+
+    /// The module enclosing the outermost `#[cfg(test)]` module around `module_id`, if any.
+    fn module_under_test(&self, module_id: ModuleNodeId) -> Option<ModuleNodeId> {
+        let mut under_test = None;
+        let mut current = Some(module_id);
+        while let Some(module_id) = current {
+            let parent = self.tree.get_parent_module_id(module_id);
+            if self.is_cfg_test(module_id) {
+                under_test = parent;
+            }
+            current = parent;
+        }
+        under_test
+    }
+
+    /// The kind of a test function in `file_path` marked by an attribute of kind `marked`.
+    fn function_test_kind(&self, file_path: &Path, marked: TestKind) -> TestKind {
+        let Some(root) = self.crate_root else {
+            return marked;
+        };
+        if file_path.starts_with(root.join("benches")) {
+            TestKind::Bench
+        } else if marked == TestKind::Unit && file_path.starts_with(root.join("tests")) {
+            TestKind::Integration
+        } else {
+            marked
+        }
+    }
+
+    fn test_function(&self, func: &FunctionNode) -> Option<TestNode> {
+        let marked = test_attribute(&func.attributes)?;
+        let module_id = *self.containing_module.get(&PrimaryNodeId::from(func.id))?;
+        let file_path = self.file_path(func.id)?;
+        let mut targets: Vec<TestTarget> = self
+            .callees
+            .get(&CallableNodeId::from(func.id))
+            .into_iter()
+            .flatten()
+            .map(|callee| TestTarget {
+                id: callee.as_any(),
+                coverage: TestCoverage::Call,
+            })
+            .collect();
+        if let Some(module_under_test) = self.module_under_test(module_id) {
+            targets.push(TestTarget {
+                id: module_under_test.as_any(),
+                coverage: TestCoverage::EnclosingModule,
+            });
+        }
+        Some(TestNode {
+            name: func.name.clone(),
+            kind: self.function_test_kind(&file_path, marked),
+            owner: func.id.as_any(),
+            module_id,
+            span: func.span,
+            file_path,
+            doctest_index: None,
+            code: None,
+            ignored: is_ignored(&func.attributes),
+            targets,
+        })
+    }
+}
+
+/// An item whose documentation may contain doc-tests.
+struct Documented<'a> {
+    owner: AnyNodeId,
+    name: &'a str,
+    /// The primary item used to find the module and file of `owner`: itself, or the impl or trait
+    /// defining a method.
+    scope: PrimaryNodeId,
+    span: (usize, usize),
+    docs: Vec<&'a str>,
+}
+
+fn documented_items(graph: &ParsedCodeGraph) -> Vec<Documented<'_>> {
+    fn item<'a>(
+        owner: AnyNodeId,
+        name: &'a str,
+        scope: PrimaryNodeId,
+        span: (usize, usize),
+        docs: impl IntoIterator<Item = &'a String>,
+    ) -> Documented<'a> {
+        Documented {
+            owner,
+            name,
+            scope,
+            span,
+            docs: docs.into_iter().map(String::as_str).collect(),
+        }
+    }
+
+    let mut items = Vec::new();
+    for func in graph.functions() {
+        items.push(item(
+            func.id.as_any(),
+            &func.name,
+            func.id.into(),
+            func.span,
+            &func.docstring,
+        ));
+    }
+    for def in graph.defined_types() {
+        items.push(match def {
+            TypeDefNode::Struct(n) => {
+                item(n.id.as_any(), &n.name, n.id.into(), n.span, &n.docstring)
+            }
+            TypeDefNode::Enum(n) => item(n.id.as_any(), &n.name, n.id.into(), n.span, &n.docstring),
+            TypeDefNode::TypeAlias(n) => {
+                item(n.id.as_any(), &n.name, n.id.into(), n.span, &n.docstring)
+            }
+            TypeDefNode::Union(n) => {
+                item(n.id.as_any(), &n.name, n.id.into(), n.span, &n.docstring)
+            }
+        });
+    }
+    for tr in graph.traits() {
+        items.push(item(
+            tr.id.as_any(),
+            &tr.name,
+            tr.id.into(),
+            tr.span,
+            &tr.docstring,
+        ));
+        for method in &tr.methods {
+            items.push(item(
+                method.id.as_any(),
+                &method.name,
+                tr.id.into(),
+                method.span,
+                &method.docstring,
+            ));
+        }
+    }
+    for imp in graph.impls() {
+        for method in &imp.methods {
+            items.push(item(
+                method.id.as_any(),
+                &method.name,
+                imp.id.into(),
+                method.span,
+                &method.docstring,
+            ));
+        }
+    }
+    for module in graph.modules() {
+        items.push(item(
+            module.id.as_any(),
+            &module.name,
+            module.id.into(),
+            module.span,
+            module.docstring.iter().chain(module.file_docs()),
+        ));
+    }
+    for konst in graph.consts() {
+        items.push(item(
+            konst.id.as_any(),
+            &konst.name,
+            konst.id.into(),
+            konst.span,
+            &konst.docstring,
+        ));
+    }
+    for stat in graph.statics() {
+        items.push(item(
+            stat.id.as_any(),
+            &stat.name,
+            stat.id.into(),
+            stat.span,
+            &stat.docstring,
+        ));
+    }
+    for mac in graph.macros() {
+        items.push(item(
+            mac.id.as_any(),
+            &mac.name,
+            mac.id.into(),
+            mac.span,
+            &mac.docstring,
+        ));
+    }
+    items.retain(|item| !item.docs.is_empty());
+    items
+}
+
+impl TestIndex<'_> {
+    fn doctests(&self, item: &Documented<'_>) -> Vec<TestNode> {
+        let module_id = match item.scope {
+            PrimaryNodeId::Module(module_id) => Some(module_id),
+            scope => self.containing_module.get(&scope).copied(),
+        };
+        let Some(module_id) = module_id else {
+            return Vec::new();
+        };
+        let Some(file_path) = self.file_path(item.scope) else {
+            return Vec::new();
+        };
+        item.docs
+            .iter()
+            .flat_map(|docs| extract_doctests(docs))
+            .enumerate()
+            .map(|(index, block)| TestNode {
+                name: format!("{} (doctest {})", item.name, index + 1),
+                kind: TestKind::Doc,
+                owner: item.owner,
+                module_id,
+                file_path: file_path.clone(),
+                span: item.span,
+                doctest_index: Some(index),
+                code: Some(block.code),
+                ignored: block.ignored,
+                targets: vec![TestTarget {
+                    id: item.owner,
+                    coverage: TestCoverage::Documented,
+                }],
+            })
+            .collect()
+    }
+}
+
+/// Collects the test functions and doc-tests of `graph` into [`CodeGraph::tests`], replacing any
+/// collected before.
+///
+/// Must run after the graph has been pruned against `tree` and after `resolve_call_sites`.
+///
+/// [`CodeGraph::tests`]: crate::parser::graph::CodeGraph::tests
+pub(crate) fn resolve_tests(graph: &mut ParsedCodeGraph, tree: &ModuleTree) {
+    let tests: Vec<TestNode> = {
+        let index = TestIndex::new(graph, tree);
+        let functions = graph
+            .functions()
+            .iter()
+            .filter_map(|func| index.test_function(func));
+        let doctests = documented_items(graph)
+            .into_iter()
+            .flat_map(|item| index.doctests(&item));
+        functions.chain(doctests).collect()
+    };
+    tracing::debug!(
+        target: LOG_TARGET_TESTS,
+        tests = tests.len(),
+        doctests = tests.iter().filter(|t| t.kind == TestKind::Doc).count(),
+        "collected tests"
+    );
+    graph.graph.tests = tests;
+}
+
+/// Adds a [`TestCoverage::Call`] target to the tests owned by `caller` for a call resolved after
+/// [`resolve_tests`] ran, e.g. into another workspace member.
+pub(super) fn add_call_target(
+    graph: &mut ParsedCodeGraph,
+    caller: CallableNodeId,
+    callee: CallableNodeId,
+) {
+    let target = TestTarget {
+        id: callee.as_any(),
+        coverage: TestCoverage::Call,
+    };
+    for test in graph
+        .graph
+        .tests
+        .iter_mut()
+        .filter(|t| t.doctest_index.is_none() && t.owner == caller.as_any())
+    {
+        if !test.targets.contains(&target) {
+            test.targets.push(target);
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Resolution of the types named in signatures and declarations into
+//! [`SyntacticRelation::UsesType`] edges.
+//!
+//! Runs once the `ModuleTree` is built and the graph has been pruned. Every [`TypeNode`]
+//! reachable from a consumer's types is walked, so `Option<Vec<Foo>>`, `&[Foo]` and
+//! `Box<dyn Fn(Foo) -> Bar>` all link to `Foo` (and `Bar`). Each `TypeKind::Named` path is
+//! resolved in the scope of the module containing the consumer through
+//! [`ModuleTree::resolve_path_in_scope`].
+//!
+//! What is linked:
+//! - Functions and methods: parameter and return types.
+//! - Struct, union, and variant fields: the field type.
+//! - Impls: the generic arguments of the self type and of the implemented trait. The self type
+//!   itself is linked by [`SyntacticRelation::ImplFor`].
+//! - Type aliases, consts, and statics: the declared type.
+//!
+//! Only targets that are crate-local structs, enums, unions or type aliases produce an edge;
+//! types of other workspace members are linked later by `workspace_resolver`. Generic
+//! parameters in scope are never resolved, and `Self` inside an impl resolves to the impl's self
+//! type.
+
+use std::collections::{HashMap, HashSet};
+
+use ploke_core::{TypeId, TypeKind};
+
+use crate::parser::{
+    ParsedCodeGraph,
+    graph::GraphAccess,
+    nodes::{
+        FieldNode, ImplNodeId, ModuleNodeId, ParamData, PrimaryNodeId, TypeConsumerNodeId,
+        TypeDefNode,
+    },
+    relations::SyntacticRelation,
+    types::{GenericParamNode, TypeNode},
+};
+
+use super::module_tree::ModuleTree;
+use super::workspace_resolver::{CrateLocal, ExternalScope, resolve_path_or_external};
+
+const LOG_TARGET_TYPE_USAGE: &str = "type_usage_resolver";
+
+/// The types written by one consumer, with the scope needed to resolve them.
+struct TypeUsage<'a> {
+    consumer: TypeConsumerNodeId,
+    module_id: ModuleNodeId,
+    type_ids: Vec<TypeId>,
+    /// Names of the generic parameters in scope, which shadow any item of the same name.
+    generics: HashSet<&'a str>,
+    /// The impl whose self type `Self` refers to, if any.
+    self_impl: Option<ImplNodeId>,
+}
+
+/// Lookup tables over the pruned graph, built once per resolution pass.
+struct TypeUsageIndex<'a> {
+    tree: &'a ModuleTree,
+    /// Consulted for paths that do not resolve in `tree`.
+    external: &'a dyn ExternalScope,
+    types: HashMap<TypeId, &'a TypeNode>,
+    /// The definition each impl's self type resolves to, from the `ImplFor` edges.
+    impl_target: HashMap<ImplNodeId, PrimaryNodeId>,
+}
+
+impl<'a> TypeUsageIndex<'a> {
+    fn new(
+        graph: &'a ParsedCodeGraph,
+        tree: &'a ModuleTree,
+        external: &'a dyn ExternalScope,
+    ) -> Self {
+        Self {
+            tree,
+            external,
+            types: graph.type_graph().iter().map(|t| (t.id, t)).collect(),
+            impl_target: graph
+                .relations()
+                .iter()
+                .filter_map(|rel| match rel {
+                    SyntacticRelation::ImplFor { source, target } => Some((*source, *target)),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the distinct type definitions named anywhere in `usage.type_ids`.
+    fn resolve(&self, usage: &TypeUsage) -> Vec<PrimaryNodeId> {
+        let mut targets = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = usage.type_ids.clone();
+        while let Some(type_id) = stack.pop() {
+            if !visited.insert(type_id) {
+                continue;
+            }
+            let Some(type_node) = self.types.get(&type_id) else {
+                continue;
+            };
+            stack.extend(type_node.related_types.iter().copied());
+            let TypeKind::Named { path, .. } = &type_node.kind else {
+                continue;
+            };
+            if let Some(target) = self.resolve_named(usage, path)
+                && !targets.contains(&target)
+            {
+                targets.push(target);
+            }
+        }
+        targets
+    }
+
+    fn resolve_named(&self, usage: &TypeUsage, path: &[String]) -> Option<PrimaryNodeId> {
+        let found = match path {
+            [name] if name == "Self" => *self.impl_target.get(&usage.self_impl?)?,
+            [name] if usage.generics.contains(name.as_str()) => return None,
+            _ => resolve_path_or_external(self.tree, self.external, usage.module_id, path)?,
+        };
+        matches!(
+            found,
+            PrimaryNodeId::Struct(_)
+                | PrimaryNodeId::Enum(_)
+                | PrimaryNodeId::Union(_)
+                | PrimaryNodeId::TypeAlias(_)
+        )
+        .then_some(found)
+    }
+}
+
+/// The module each live primary item is contained in.
+type ItemModules = HashMap<PrimaryNodeId, ModuleNodeId>;
+
+fn generic_names<'a>(
+    params: impl IntoIterator<Item = &'a GenericParamNode>,
+) -> impl Iterator<Item = &'a str> {
+    params.into_iter().filter_map(|param| param.kind.name())
+}
+
+fn signature_types(parameters: &[ParamData], return_type: Option<TypeId>) -> Vec<TypeId> {
+    parameters
+        .iter()
+        .map(|param| param.type_id)
+        .chain(return_type)
+        .collect()
+}
+
+/// Collects the types written by every live consumer in the graph.
+fn collect_usages<'a>(graph: &'a ParsedCodeGraph, modules: &ItemModules) -> Vec<TypeUsage<'a>> {
+    let mut usages = Vec::new();
+    let module_of = |id: PrimaryNodeId| modules.get(&id).copied();
+    let fields_of =
+        |fields: &'a [FieldNode], module_id: ModuleNodeId, generics: &HashSet<&'a str>| {
+            fields
+                .iter()
+                .map(move |field| TypeUsage {
+                    consumer: field.id.into(),
+                    module_id,
+                    type_ids: vec![field.type_id],
+                    generics: generics.clone(),
+                    self_impl: None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+    for def in graph.defined_types() {
+        let (id, generic_params) = match def {
+            TypeDefNode::Struct(s) => (PrimaryNodeId::from(s.id), &s.generic_params),
+            TypeDefNode::Enum(e) => (PrimaryNodeId::from(e.id), &e.generic_params),
+            TypeDefNode::Union(u) => (PrimaryNodeId::from(u.id), &u.generic_params),
+            TypeDefNode::TypeAlias(t) => (PrimaryNodeId::from(t.id), &t.generic_params),
+        };
+        let Some(module_id) = module_of(id) else {
+            continue;
+        };
+        let generics: HashSet<&str> = generic_names(generic_params).collect();
+        match def {
+            TypeDefNode::Struct(s) => usages.extend(fields_of(&s.fields, module_id, &generics)),
+            TypeDefNode::Union(u) => usages.extend(fields_of(&u.fields, module_id, &generics)),
+            TypeDefNode::Enum(e) => {
+                for variant in &e.variants {
+                    usages.extend(fields_of(&variant.fields, module_id, &generics));
+                }
+            }
+            TypeDefNode::TypeAlias(t) => usages.push(TypeUsage {
+                consumer: t.id.into(),
+                module_id,
+                type_ids: vec![t.type_id],
+                generics,
+                self_impl: None,
+            }),
+        }
+    }
+
+    for func in graph.functions() {
+        let Some(module_id) = module_of(func.id.into()) else {
+            continue;
+        };
+        usages.push(TypeUsage {
+            consumer: func.id.into(),
+            module_id,
+            type_ids: signature_types(&func.parameters, func.return_type),
+            generics: generic_names(&func.generic_params).collect(),
+            self_impl: None,
+        });
+    }
+
+    let impls = graph.impls().iter().map(|imp| {
+        (
+            PrimaryNodeId::from(imp.id),
+            Some(imp.id),
+            &imp.generic_params,
+            &imp.methods,
+        )
+    });
+    let traits = graph.traits().iter().map(|tr| {
+        (
+            PrimaryNodeId::from(tr.id),
+            None,
+            &tr.generic_params,
+            &tr.methods,
+        )
+    });
+    for (owner, self_impl, generic_params, methods) in impls.chain(traits) {
+        let Some(module_id) = module_of(owner) else {
+            continue;
+        };
+        let generics: HashSet<&str> = generic_names(generic_params).collect();
+        for method in methods {
+            usages.push(TypeUsage {
+                consumer: method.id.into(),
+                module_id,
+                type_ids: signature_types(&method.parameters, method.return_type),
+                generics: generics
+                    .iter()
+                    .copied()
+                    .chain(generic_names(&method.generic_params))
+                    .collect(),
+                self_impl,
+            });
+        }
+    }
+
+    for imp in graph.impls() {
+        if let Some(module_id) = module_of(imp.id.into()) {
+            usages.push(TypeUsage {
+                consumer: imp.id.into(),
+                module_id,
+                type_ids: std::iter::once(imp.self_type)
+                    .chain(imp.trait_type)
+                    .collect(),
+                generics: generic_names(&imp.generic_params).collect(),
+                self_impl: None,
+            });
+        }
+    }
+
+    let declared = graph
+        .consts()
+        .iter()
+        .map(|c| {
+            (
+                TypeConsumerNodeId::from(c.id),
+                PrimaryNodeId::from(c.id),
+                c.type_id,
+            )
+        })
+        .chain(graph.statics().iter().map(|s| {
+            (
+                TypeConsumerNodeId::from(s.id),
+                PrimaryNodeId::from(s.id),
+                s.type_id,
+            )
+        }));
+    for (consumer, id, type_id) in declared {
+        if let Some(module_id) = module_of(id) {
+            usages.push(TypeUsage {
+                consumer,
+                module_id,
+                type_ids: vec![type_id],
+                generics: HashSet::new(),
+                self_impl: None,
+            });
+        }
+    }
+
+    usages
+}
+
+/// Resolves the types named by functions, methods, fields, impls, type aliases, consts and
+/// statics, and adds one [`SyntacticRelation::UsesType`] edge per distinct (consumer, type
+/// definition) pair.
+///
+/// Must run after the graph has been pruned against `tree`, and after `resolve_impls` so that
+/// the [`SyntacticRelation::ImplFor`] edges are available.
+pub(crate) fn resolve_type_usages(graph: &mut ParsedCodeGraph, tree: &ModuleTree) {
+    let new_relations = type_usage_relations(graph, tree, &CrateLocal);
+    graph.graph.relations.extend(new_relations);
+}
+
+/// Collects the [`SyntacticRelation::UsesType`] edges of `graph` that it does not contain yet,
+/// resolving the paths that leave the crate through `external`.
+pub(super) fn type_usage_relations(
+    graph: &ParsedCodeGraph,
+    tree: &ModuleTree,
+    external: &dyn ExternalScope,
+) -> Vec<SyntacticRelation> {
+    let mut seen: HashSet<SyntacticRelation> = graph
+        .relations()
+        .iter()
+        .filter(|rel| rel.is_uses_type())
+        .copied()
+        .collect();
+    let mut new_relations = Vec::new();
+    let consumers;
+    {
+        let modules: ItemModules = graph
+            .relations()
+            .iter()
+            .filter_map(|rel| match rel {
+                SyntacticRelation::Contains { source, target } => Some((*target, *source)),
+                _ => None,
+            })
+            .collect();
+        let index = TypeUsageIndex::new(graph, tree, external);
+        let usages = collect_usages(graph, &modules);
+        consumers = usages.len();
+        for usage in &usages {
+            for target in index.resolve(usage) {
+                // The self type of an impl is already linked by `ImplFor`.
+                if let TypeConsumerNodeId::Impl(impl_id) = usage.consumer
+                    && index.impl_target.get(&impl_id) == Some(&target)
+                {
+                    continue;
+                }
+                let relation = SyntacticRelation::UsesType {
+                    source: usage.consumer,
+                    target,
+                };
+                if seen.insert(relation) {
+                    new_relations.push(relation);
+                }
+            }
+        }
+    }
+    tracing::debug!(
+        target: LOG_TARGET_TYPE_USAGE,
+        consumers,
+        uses_type_edges = new_relations.len(),
+        "resolved type usages"
+    );
+    new_relations
+}
@@ -0,0 +1,408 @@
+//! Resolution of paths that lead from one workspace member into another.
+//!
+//! Each member crate is parsed and resolved on its own, so `use other_member::Foo` or
+//! `other_member::Foo::new()` are left unresolved by the crate-local passes. Once the selected
+//! members of a workspace are parsed, [`resolve_workspace_paths`] resolves these paths against
+//! the module trees of the members a crate depends on, and adds the resulting edges to the graph
+//! of the crate containing the path:
+//! - `use` statements: [`SyntacticRelation::ImportedBy`], from the definition to the import.
+//! - Impl headers: `ImplFor`, `ImplementsTrait` and `ImplementsTraitMethod` (see
+//!   `impl_resolver`).
+//! - Call sites: `Calls` (see `call_resolver`), and a call target on the tests of the caller
+//!   (see `test_resolver`).
+//! - Types named in signatures and fields: `UsesType` (see `type_usage_resolver`).
+//! - Trait bounds and supertraits: `RequiresTrait` and `SuperTrait` (see `bound_resolver`).
+//!
+//! A path leads into another member when its first segment is the name the crate uses for that
+//! member in its `Cargo.toml` (`[dependencies]` or `[dev-dependencies]`, with `-` replaced by
+//! `_`), either as written or after substituting a `use` of the scope. Renamed dependencies
+//! (`alias = { package = "member" }`) are known by their alias.
+//!
+//! **Limitations:** the visibility of the target in the other member is not checked, and a
+//! re-export of a third member's item is linked to the re-exporting import rather than to the
+//! definition.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ParsedCrate,
+    discovery::DependencyMap as _,
+    parser::{
+        ParsedCodeGraph,
+        graph::GraphAccess,
+        nodes::{
+            AnyNodeId, AsAnyNodeId as _, CallableNodeId, ModuleNodeId, PrimaryNodeId, TraitNode,
+            TraitNodeId,
+        },
+        relations::SyntacticRelation,
+    },
+};
+
+use super::{
+    bound_resolver::bound_relations, call_resolver::CallIndex,
+    impl_resolver::impl_header_relations, module_tree::ModuleTree, test_resolver::add_call_target,
+    type_usage_resolver::type_usage_relations,
+};
+
+const LOG_TARGET_WORKSPACE: &str = "workspace_resolver";
+
+/// Definitions outside the crate being resolved, consulted by the resolution passes for paths
+/// that do not resolve in the crate itself.
+pub(super) trait ExternalScope {
+    /// Resolves `segments`, written in `scope_mod_id`, to a definition in another crate.
+    fn resolve_path(
+        &self,
+        scope_mod_id: ModuleNodeId,
+        segments: &[String],
+    ) -> Option<PrimaryNodeId>;
+
+    /// Looks up `name` in a trait, or in the impls of a type definition, of another crate.
+    fn method(&self, owner: PrimaryNodeId, name: &str) -> Option<CallableNodeId>;
+
+    /// Returns a trait defined in another crate.
+    fn trait_node(&self, trait_id: TraitNodeId) -> Option<&TraitNode>;
+}
+
+/// Resolves `segments`, written in `scope_mod_id`, through `tree`, then through `external` if
+/// the path does not resolve in the crate or only leads to a `use` of an item outside it.
+pub(super) fn resolve_path_or_external(
+    tree: &ModuleTree,
+    external: &dyn ExternalScope,
+    scope_mod_id: ModuleNodeId,
+    segments: &[String],
+) -> Option<PrimaryNodeId> {
+    tree.resolve_path_in_scope(scope_mod_id, segments)
+        .filter(|found| !matches!(found, PrimaryNodeId::Import(_)))
+        .or_else(|| external.resolve_path(scope_mod_id, segments))
+}
+
+/// The scope of a crate resolved on its own, where nothing outside the crate is known.
+pub(super) struct CrateLocal;
+
+impl ExternalScope for CrateLocal {
+    fn resolve_path(&self, _: ModuleNodeId, _: &[String]) -> Option<PrimaryNodeId> {
+        None
+    }
+
+    fn method(&self, _: PrimaryNodeId, _: &str) -> Option<CallableNodeId> {
+        None
+    }
+
+    fn trait_node(&self, _: TraitNodeId) -> Option<&TraitNode> {
+        None
+    }
+}
+
+/// A member crate whose graph and module tree are available.
+struct Member<'a> {
+    graph: &'a ParsedCodeGraph,
+    tree: &'a ModuleTree,
+}
+
+fn members(crates: &[ParsedCrate]) -> Vec<Option<Member<'_>>> {
+    crates
+        .iter()
+        .map(|krate| {
+            Some(Member {
+                graph: krate.parser_output.merged_graph.as_ref()?,
+                tree: krate.parser_output.module_tree.as_ref()?,
+            })
+        })
+        .collect()
+}
+
+/// Maps the names `crates[idx]` uses in paths for the other members it depends on to the index
+/// of that member.
+fn member_dependencies(crates: &[ParsedCrate], idx: usize) -> HashMap<String, usize> {
+    let context = &crates[idx].crate_context;
+    let normalize = |name: &str| name.replace('-', "_");
+    context
+        .dependencies
+        .inner_map()
+        .iter()
+        .chain(context.dev_dependencies.inner_map())
+        .filter_map(|(name, spec)| {
+            let package = normalize(spec.package().unwrap_or(name));
+            let member = (0..crates.len()).find(|&other_idx| {
+                other_idx != idx && normalize(&crates[other_idx].crate_context.name) == package
+            })?;
+            Some((normalize(name), member))
+        })
+        .collect()
+}
+
+/// The members one crate depends on, as seen from that crate.
+struct DependencyScope<'a> {
+    /// The module tree of the crate whose paths are resolved.
+    tree: &'a ModuleTree,
+    members: &'a [Option<Member<'a>>],
+    dependencies: &'a HashMap<String, usize>,
+    traits: &'a HashMap<TraitNodeId, &'a TraitNode>,
+    /// Crate-local call indexes of the members, for method lookups in the dependencies. Empty
+    /// while impl headers are resolved.
+    call_indexes: &'a [Option<CallIndex<'a>>],
+}
+
+impl DependencyScope<'_> {
+    /// Resolves a path whose first segment names a dependency on another member.
+    fn resolve_in_dependency(&self, path: &[String]) -> Option<PrimaryNodeId> {
+        // `::other_member::Foo`
+        let path = match path {
+            [leading, rest @ ..] if leading.is_empty() => rest,
+            _ => path,
+        };
+        let (first, rest) = path.split_first()?;
+        let member = self.members.get(*self.dependencies.get(first)?)?.as_ref()?;
+        let root = member.tree.root();
+        if rest.is_empty() {
+            return Some(PrimaryNodeId::from(root));
+        }
+        let segments = std::iter::once("crate".to_string())
+            .chain(rest.iter().cloned())
+            .collect::<Vec<_>>();
+        member.tree.resolve_path_in_scope(root, &segments)
+    }
+}
+
+impl ExternalScope for DependencyScope<'_> {
+    /// Tries the path as written, then with the source path of a matching non-glob `use` of the
+    /// scope substituted for its first segment, then behind each glob `use` of the scope.
+    fn resolve_path(
+        &self,
+        scope_mod_id: ModuleNodeId,
+        segments: &[String],
+    ) -> Option<PrimaryNodeId> {
+        let (first, rest) = segments.split_first()?;
+        if matches!(first.as_str(), "crate" | "self" | "super" | "Self") {
+            return None;
+        }
+        let imports = self
+            .tree
+            .modules()
+            .get(&scope_mod_id)
+            .map(|module| module.imports.as_slice())
+            .unwrap_or_default();
+        let renamed = imports
+            .iter()
+            .filter(|imp| !imp.is_glob && &imp.visible_name == first)
+            .map(|imp| imp.source_path().iter().chain(rest).cloned().collect());
+        let globbed = imports
+            .iter()
+            .filter(|imp| imp.is_glob)
+            .map(|imp| imp.source_path().iter().chain(segments).cloned().collect());
+        std::iter::once(segments.to_vec())
+            .chain(renamed)
+            .chain(globbed)
+            .find_map(|path: Vec<String>| self.resolve_in_dependency(&path))
+    }
+
+    fn method(&self, owner: PrimaryNodeId, name: &str) -> Option<CallableNodeId> {
+        self.dependencies
+            .values()
+            .filter_map(|&member_idx| self.call_indexes.get(member_idx)?.as_ref())
+            .find_map(|index| index.method_of(owner, name))
+    }
+
+    fn trait_node(&self, trait_id: TraitNodeId) -> Option<&TraitNode> {
+        self.traits.get(&trait_id).copied()
+    }
+}
+
+/// Links each `use` of the crate that brings an item of another member into scope with a
+/// [`SyntacticRelation::ImportedBy`] edge. Edges the graph already has are dropped when the
+/// links are applied.
+fn import_relations(graph: &ParsedCodeGraph, scope: &DependencyScope) -> Vec<SyntacticRelation> {
+    graph
+        .use_statements()
+        .iter()
+        .filter_map(|imp| {
+            Some(SyntacticRelation::ImportedBy {
+                source: scope.resolve_in_dependency(imp.source_path())?,
+                target: imp.id,
+            })
+        })
+        .collect()
+}
+
+/// Resolves the call sites of the crate left unresolved by `resolve_call_sites`, returning the
+/// index of each newly resolved site with its callee.
+fn external_call_resolutions(
+    graph: &ParsedCodeGraph,
+    index: &CallIndex,
+) -> Vec<(usize, CallableNodeId)> {
+    graph
+        .graph
+        .call_sites
+        .iter()
+        .enumerate()
+        .filter(|(_, site)| !site.is_resolved())
+        .filter_map(|(site_idx, site)| Some((site_idx, index.resolve(site)?)))
+        .collect()
+}
+
+/// New edges and call site resolutions for one crate.
+#[derive(Default)]
+struct CrossCrateLinks {
+    relations: Vec<SyntacticRelation>,
+    call_resolutions: Vec<(usize, CallableNodeId)>,
+}
+
+impl CrossCrateLinks {
+    /// Drops the edges and call resolutions leading into a member that the crate at `idx` does
+    /// not depend on, e.g. through a trait of a third member.
+    fn retain_dependencies(
+        mut self,
+        members: &[Option<Member>],
+        idx: usize,
+        dependencies: &HashMap<String, usize>,
+    ) -> Self {
+        let outside: Vec<&Member> = members
+            .iter()
+            .enumerate()
+            .filter(|(member_idx, _)| {
+                *member_idx != idx && !dependencies.values().any(|dep| dep == member_idx)
+            })
+            .filter_map(|(_, member)| member.as_ref())
+            .collect();
+        if outside.is_empty() {
+            return self;
+        }
+        let in_scope = |id: AnyNodeId| {
+            outside
+                .iter()
+                .all(|member| member.graph.find_any_node(id).is_none())
+        };
+        self.relations
+            .retain(|rel| in_scope(rel.source()) && in_scope(rel.target()));
+        self.call_resolutions
+            .retain(|(_, callee)| in_scope(callee.as_any()));
+        self
+    }
+}
+
+fn apply_links(crates: &mut [ParsedCrate], links: Vec<CrossCrateLinks>) {
+    for (krate, links) in crates.iter_mut().zip(links) {
+        let Some(graph) = krate.parser_output.merged_graph.as_mut() else {
+            continue;
+        };
+        let mut seen: HashSet<SyntacticRelation> = graph.relations().iter().copied().collect();
+        for (site_idx, callee) in links.call_resolutions {
+            let site = &mut graph.graph.call_sites[site_idx];
+            site.resolved = Some(callee);
+            let caller = site.caller;
+            let relation = SyntacticRelation::Calls {
+                source: caller,
+                target: callee,
+            };
+            add_call_target(graph, caller, callee);
+            if seen.insert(relation) {
+                graph.graph.relations.push(relation);
+            }
+        }
+        graph
+            .graph
+            .relations
+            .extend(links.relations.into_iter().filter(|rel| seen.insert(*rel)));
+    }
+}
+
+/// Resolves the paths of each crate in `crates` that lead into another crate of `crates`, and
+/// adds the resulting edges to the graph of the crate containing the path. Calls resolved this
+/// way also update the `resolved` callee of their [`CallSite`](crate::parser::calls::CallSite).
+///
+/// Must run after every crate has been resolved on its own (see
+/// [`ParsedCodeGraph::build_tree_and_prune`]). Crates without a merged graph or module tree are
+/// skipped.
+pub(crate) fn resolve_workspace_paths(crates: &mut [ParsedCrate]) {
+    let dependencies = (0..crates.len())
+        .map(|idx| member_dependencies(crates, idx))
+        .collect::<Vec<_>>();
+    if dependencies.iter().all(HashMap::is_empty) {
+        return;
+    }
+
+    // Impl headers go first: calls and type usages through `Self` rely on `ImplFor`.
+    let impl_links = {
+        let members = members(crates);
+        let traits = workspace_traits(&members);
+        members
+            .iter()
+            .zip(&dependencies)
+            .enumerate()
+            .map(|(idx, (member, dependencies))| {
+                let Some(member) = member else {
+                    return CrossCrateLinks::default();
+                };
+                let scope = DependencyScope {
+                    tree: member.tree,
+                    members: &members,
+                    dependencies,
+                    traits: &traits,
+                    call_indexes: &[],
+                };
+                let mut relations = import_relations(member.graph, &scope);
+                relations.extend(impl_header_relations(member.graph, member.tree, &scope));
+                CrossCrateLinks {
+                    relations,
+                    call_resolutions: Vec::new(),
+                }
+                .retain_dependencies(&members, idx, dependencies)
+            })
+            .collect::<Vec<_>>()
+    };
+    apply_links(crates, impl_links);
+
+    let links = {
+        let members = members(crates);
+        let traits = workspace_traits(&members);
+        let call_indexes = members
+            .iter()
+            .map(|member| {
+                member
+                    .as_ref()
+                    .map(|member| CallIndex::new(member.graph, member.tree, &CrateLocal))
+            })
+            .collect::<Vec<_>>();
+        members
+            .iter()
+            .zip(&dependencies)
+            .enumerate()
+            .map(|(idx, (member, dependencies))| {
+                let Some(member) = member else {
+                    return CrossCrateLinks::default();
+                };
+                let scope = DependencyScope {
+                    tree: member.tree,
+                    members: &members,
+                    dependencies,
+                    traits: &traits,
+                    call_indexes: &call_indexes,
+                };
+                let index = CallIndex::new(member.graph, member.tree, &scope);
+                let mut relations = type_usage_relations(member.graph, member.tree, &scope);
+                relations.extend(bound_relations(member.graph, member.tree, &scope));
+                CrossCrateLinks {
+                    relations,
+                    call_resolutions: external_call_resolutions(member.graph, &index),
+                }
+                .retain_dependencies(&members, idx, dependencies)
+            })
+            .collect::<Vec<_>>()
+    };
+    tracing::debug!(
+        target: LOG_TARGET_WORKSPACE,
+        calls = links.iter().map(|l| l.call_resolutions.len()).sum::<usize>(),
+        relations = links.iter().map(|l| l.relations.len()).sum::<usize>(),
+        "resolved workspace paths"
+    );
+    apply_links(crates, links);
+}
+
+fn workspace_traits<'a>(members: &[Option<Member<'a>>]) -> HashMap<TraitNodeId, &'a TraitNode> {
+    members
+        .iter()
+        .flatten()
+        .flat_map(|member| member.graph.traits())
+        .map(|tr| (tr.id, tr))
+        .collect()
+}
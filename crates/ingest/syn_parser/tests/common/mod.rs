@@ -361,6 +361,7 @@ pub fn parse_workspace_both(
         &ParseWorkspaceConfig {
             selected_crates,
             target_selector,
+            ..Default::default()
         },
     )?;
 
@@ -0,0 +1,108 @@
+//! Tests for the `RequiresTrait` and `SuperTrait` edges resolved from generic bounds,
+//! where-clauses and supertraits.
+
+use syn_parser::parser::ParsedCodeGraph;
+use syn_parser::parser::nodes::{GenericOwnerNodeId, TraitNodeId};
+use syn_parser::parser::relations::SyntacticRelation;
+use syn_parser::run_phases_and_collect;
+
+use ploke_error::Error as PlokeError;
+
+fn trait_id(merged: &ParsedCodeGraph, name: &str) -> TraitNodeId {
+    merged
+        .graph
+        .traits
+        .iter()
+        .find(|t| t.name == name)
+        .unwrap_or_else(|| panic!("Trait {name} not found"))
+        .id
+}
+
+fn required_traits(merged: &ParsedCodeGraph, source: GenericOwnerNodeId) -> Vec<TraitNodeId> {
+    merged
+        .graph
+        .relations
+        .iter()
+        .filter_map(|rel| match rel {
+            SyntacticRelation::RequiresTrait { source: s, target } if *s == source => Some(*target),
+            _ => None,
+        })
+        .collect()
+}
+
+fn super_traits(merged: &ParsedCodeGraph, source: TraitNodeId) -> Vec<TraitNodeId> {
+    merged
+        .graph
+        .relations
+        .iter()
+        .filter_map(|rel| match rel {
+            SyntacticRelation::SuperTrait { source: s, target } if *s == source => Some(*target),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_inline_and_where_clause_bounds_resolve() -> Result<(), PlokeError> {
+    let results = run_phases_and_collect("fixture_generics")?;
+    let mut merged = ParsedCodeGraph::merge_new(results)?;
+    let _tree = merged.build_tree_and_prune()?;
+
+    let describe = trait_id(&merged, "Describe");
+    let describe_twice = trait_id(&merged, "DescribeTwice");
+
+    // `pub fn describe_all<D: Describe, E>(..) where E: DescribeTwice`
+    let describe_all = merged
+        .graph
+        .functions
+        .iter()
+        .find(|f| f.name == "describe_all")
+        .expect("describe_all not found");
+    let mut targets = required_traits(&merged, describe_all.id.into());
+    targets.sort();
+    let mut expected = vec![describe, describe_twice];
+    expected.sort();
+    assert_eq!(
+        targets, expected,
+        "`describe_all` should require both the inline and the where-clause bound"
+    );
+
+    // `where Self: Describe` on a trait is a supertrait.
+    assert_eq!(super_traits(&merged, describe_twice), vec![describe]);
+
+    // Bounds on traits outside the crate produce no edges.
+    let generic_function = merged
+        .graph
+        .functions
+        .iter()
+        .find(|f| f.name == "generic_function")
+        .expect("generic_function not found");
+    assert!(
+        required_traits(&merged, generic_function.id.into()).is_empty(),
+        "`T: Default` should not resolve to a crate-local trait"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_supertraits_resolve() -> Result<(), PlokeError> {
+    let results = run_phases_and_collect("fixture_nodes")?;
+    let mut merged = ParsedCodeGraph::merge_new(results)?;
+    let _tree = merged.build_tree_and_prune()?;
+
+    let simple = trait_id(&merged, "SimpleTrait");
+    let internal = trait_id(&merged, "InternalTrait");
+
+    // `pub trait SuperTrait: SimpleTrait`
+    let super_trait = trait_id(&merged, "SuperTrait");
+    assert_eq!(super_traits(&merged, super_trait), vec![simple]);
+
+    // `pub trait MultiSuperTrait: SimpleTrait + InternalTrait + Debug`: `Debug` is external.
+    let multi = trait_id(&merged, "MultiSuperTrait");
+    let mut targets = super_traits(&merged, multi);
+    targets.sort();
+    let mut expected = vec![simple, internal];
+    expected.sort();
+    assert_eq!(targets, expected);
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! Tests for the impls synthesized from `#[derive(...)]` attributes.
+
+use syn_parser::parser::ParsedCodeGraph;
+use syn_parser::parser::nodes::{ImplNode, PrimaryNodeId, TypeDefNode};
+use syn_parser::parser::relations::SyntacticRelation;
+use syn_parser::run_phases_and_collect;
+
+use ploke_error::Error as PlokeError;
+
+fn type_def_id(merged: &ParsedCodeGraph, name: &str) -> PrimaryNodeId {
+    merged
+        .graph
+        .defined_types
+        .iter()
+        .find_map(|def| match def {
+            TypeDefNode::Struct(s) if s.name == name => Some(PrimaryNodeId::from(s.id)),
+            TypeDefNode::Enum(e) if e.name == name => Some(PrimaryNodeId::from(e.id)),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("Could not find type `{name}`"))
+}
+
+/// The impls of the type `name`, through their `ImplFor` edges.
+fn impls_for<'a>(merged: &'a ParsedCodeGraph, name: &str) -> Vec<&'a ImplNode> {
+    let self_type = type_def_id(merged, name);
+    merged
+        .graph
+        .impls
+        .iter()
+        .filter(|imp| {
+            merged
+                .graph
+                .relations
+                .contains(&SyntacticRelation::ImplFor {
+                    source: imp.id,
+                    target: self_type,
+                })
+        })
+        .collect()
+}
+
+fn derived_trait_paths(impls: &[&ImplNode]) -> Vec<String> {
+    let mut paths: Vec<String> = impls
+        .iter()
+        .filter_map(|imp| imp.derived.as_ref()?.trait_path.as_ref())
+        .map(|path| path.join("::"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_derives_become_trait_impls() -> Result<(), PlokeError> {
+    let results = run_phases_and_collect("fixture_derives")?;
+    let mut merged = ParsedCodeGraph::merge_new(results)?;
+    let _tree = merged.build_tree_and_prune()?;
+
+    // `#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]` with
+    // `use serde::{Deserialize, Serialize};`.
+    let model_id_impls = impls_for(&merged, "ModelId");
+    assert_eq!(
+        derived_trait_paths(&model_id_impls),
+        vec![
+            "core::clone::Clone",
+            "core::cmp::Eq",
+            "core::cmp::PartialEq",
+            "core::fmt::Debug",
+            "core::hash::Hash",
+            "core::marker::Copy",
+            "serde::Deserialize",
+            "serde::Serialize",
+        ]
+    );
+    let serialize = model_id_impls
+        .iter()
+        .filter_map(|imp| imp.derived.as_ref())
+        .find(|derived| derived.macro_path == ["Serialize"])
+        .expect("`Serialize` derive of `ModelId`");
+    assert_eq!(
+        serialize.resolved_path.as_deref(),
+        Some(&["serde".to_string(), "Serialize".to_string()][..])
+    );
+    assert!(
+        model_id_impls
+            .iter()
+            .all(|imp| imp.methods.is_empty() && imp.trait_type.is_some()),
+        "Derived impls have a trait and no methods"
+    );
+
+    // The hand-written `impl Describe for ModelId` is not derived and links to the trait.
+    let describe = model_id_impls
+        .iter()
+        .find(|imp| !imp.is_derived())
+        .expect("hand-written `Describe` impl");
+    assert!(merged.graph.relations.iter().any(|rel| matches!(
+        rel,
+        SyntacticRelation::ImplementsTrait { source, .. } if *source == describe.id
+    )));
+    Ok(())
+}
+
+#[test]
+fn test_derive_table_maps_and_drops_third_party_derives() -> Result<(), PlokeError> {
+    let results = run_phases_and_collect("fixture_derives")?;
+    let mut merged = ParsedCodeGraph::merge_new(results)?;
+    let _tree = merged.build_tree_and_prune()?;
+
+    // `#[derive(Debug, thiserror::Error)]`
+    let error_impls = impls_for(&merged, "ModelError");
+    assert_eq!(
+        derived_trait_paths(&error_impls),
+        vec!["core::error::Error", "core::fmt::Debug"]
+    );
+
+    // `#[derive(Clone, Builder)]` on `Model<T: Clone>`: `derive_builder::Builder` implements no
+    // trait, so only the `Clone` impl is kept, generic over `T` like the struct.
+    let model_impls = impls_for(&merged, "Model");
+    assert_eq!(
+        derived_trait_paths(&model_impls),
+        vec!["core::clone::Clone"]
+    );
+    assert_eq!(model_impls[0].generic_params.len(), 1);
+    assert!(
+        !merged
+            .graph
+            .impls
+            .iter()
+            .filter_map(|imp| imp.derived.as_ref())
+            .any(|derived| derived.macro_path == ["Builder"]),
+        "The `Builder` derive should be removed"
+    );
+    Ok(())
+}
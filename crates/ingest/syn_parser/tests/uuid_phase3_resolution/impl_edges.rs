@@ -0,0 +1,113 @@
+//! Tests for the `ImplFor`, `ImplementsTrait` and `ImplementsTraitMethod` edges resolved from
+//! impl headers.
+
+use syn_parser::parser::ParsedCodeGraph;
+use syn_parser::parser::nodes::{ImplNode, ImplNodeId, PrimaryNodeId, TraitNodeId, TypeDefNode};
+use syn_parser::parser::relations::SyntacticRelation;
+use syn_parser::run_phases_and_collect;
+
+use ploke_error::Error as PlokeError;
+
+fn impl_for(merged: &ParsedCodeGraph, impl_id: ImplNodeId) -> Option<PrimaryNodeId> {
+    merged.graph.relations.iter().find_map(|rel| match rel {
+        SyntacticRelation::ImplFor { source, target } if *source == impl_id => Some(*target),
+        _ => None,
+    })
+}
+
+fn impl_with_method<'a>(
+    merged: &'a ParsedCodeGraph,
+    implementors: &[ImplNodeId],
+    self_type: Option<PrimaryNodeId>,
+) -> &'a ImplNode {
+    merged
+        .graph
+        .impls
+        .iter()
+        .find(|imp| implementors.contains(&imp.id) && impl_for(merged, imp.id) == self_type)
+        .unwrap_or_else(|| panic!("No implementor of `SimpleTrait` for {self_type:?}"))
+}
+
+#[test]
+fn test_trait_impls_link_to_trait_and_self_type() -> Result<(), PlokeError> {
+    let results = run_phases_and_collect("fixture_nodes")?;
+    let mut merged = ParsedCodeGraph::merge_new(results)?;
+    let _tree = merged.build_tree_and_prune()?;
+
+    let simple_trait = merged
+        .graph
+        .traits
+        .iter()
+        .find(|tr| tr.name == "SimpleTrait")
+        .expect("Could not find `SimpleTrait`");
+    let simple_trait_id: TraitNodeId = simple_trait.id;
+    let trait_method = simple_trait
+        .methods
+        .iter()
+        .find(|m| m.name == "trait_method")
+        .expect("Could not find `SimpleTrait::trait_method`")
+        .id;
+
+    // "Show all implementors of `SimpleTrait`": `SimpleStruct`, `GenericStruct<T>`, `i32`,
+    // `&'a SimpleStruct`, and `inner::InnerStruct` (which imports the trait with `use super::..`).
+    let implementors: Vec<ImplNodeId> = merged
+        .graph
+        .relations
+        .iter()
+        .filter_map(|rel| match rel {
+            SyntacticRelation::ImplementsTrait { source, target } if *target == simple_trait_id => {
+                Some(*source)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(implementors.len(), 5, "implementors: {implementors:?}");
+
+    // `fixture_nodes` has two structs named `SimpleStruct`; take the one in `impls.rs` from its
+    // inherent impl, which only gets `ImplFor`.
+    let inherent = merged
+        .graph
+        .impls
+        .iter()
+        .find(|imp| imp.methods.iter().any(|m| m.name == "new"))
+        .expect("Could not find `impl SimpleStruct`");
+    let simple_struct = impl_for(&merged, inherent.id).expect("`impl SimpleStruct` has ImplFor");
+    assert!(merged.graph.defined_types.iter().any(|def| matches!(
+        def,
+        TypeDefNode::Struct(s) if s.name == "SimpleStruct" && PrimaryNodeId::from(s.id) == simple_struct
+    )));
+    assert!(
+        !merged
+            .graph
+            .relations
+            .iter()
+            .any(|rel| rel.is_implements_trait() && rel.source() == inherent.id.into())
+    );
+
+    let for_simple_struct = impl_with_method(&merged, &implementors, Some(simple_struct));
+
+    // "Which trait does this method satisfy"
+    let method = for_simple_struct
+        .methods
+        .iter()
+        .find(|m| m.name == "trait_method")
+        .expect("Could not find `<SimpleStruct as SimpleTrait>::trait_method`");
+    let implements_method = SyntacticRelation::ImplementsTraitMethod {
+        source: method.id,
+        target: trait_method,
+    };
+    assert!(
+        merged.graph.relations.contains(&implements_method),
+        "Expected {implements_method}"
+    );
+
+    // `impl SimpleTrait for i32` and `impl SimpleTrait for &'a SimpleStruct` have no local self
+    // type definition.
+    let without_self_type = implementors
+        .iter()
+        .filter(|id| impl_for(&merged, **id).is_none())
+        .count();
+    assert_eq!(without_self_type, 2);
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! Tests for items generated by expanding crate-local `macro_rules!` invocations and the
+//! `ExpandsTo` edges resolved for them.
+
+use syn_parser::parser::ParsedCodeGraph;
+use syn_parser::parser::nodes::{MacroNodeId, PrimaryNodeId, TypeDefNode};
+use syn_parser::parser::relations::SyntacticRelation;
+use syn_parser::parser::types::VisibilityKind;
+use syn_parser::run_phases_and_collect;
+
+use ploke_error::Error as PlokeError;
+
+fn struct_named<'a>(
+    merged: &'a ParsedCodeGraph,
+    name: &str,
+) -> &'a syn_parser::parser::nodes::StructNode {
+    merged
+        .graph
+        .defined_types
+        .iter()
+        .find_map(|def| match def {
+            TypeDefNode::Struct(s) if s.name == name => Some(s),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("Macro-generated struct `{name}` is missing from the graph"))
+}
+
+#[test]
+fn test_macro_generated_items_are_graph_nodes() -> Result<(), PlokeError> {
+    let results = run_phases_and_collect("fixture_macro_expansion")?;
+    let mut merged = ParsedCodeGraph::merge_new(results)?;
+    let _tree = merged.build_tree_and_prune()?;
+
+    let make_newtype: MacroNodeId = merged
+        .graph
+        .macros
+        .iter()
+        .find(|m| m.name == "make_newtype")
+        .map(|m| m.id)
+        .expect("Could not find `make_newtype` definition");
+
+    // `make_newtype!(pub UserId(u64));` in the same file as the definition.
+    let user_id = struct_named(&merged, "UserId");
+    assert_eq!(user_id.visibility, VisibilityKind::Public);
+    let user_id_pid = PrimaryNodeId::from(user_id.id);
+    let expansion = merged
+        .graph
+        .macro_expansions
+        .iter()
+        .find(|exp| exp.generated.contains(&user_id_pid))
+        .expect("Missing expansion record for `make_newtype!(pub UserId(u64))`");
+    assert_eq!(expansion.macro_name, "make_newtype");
+    assert_eq!(expansion.macro_id, Some(make_newtype));
+    assert_eq!(
+        expansion.generated.len(),
+        2,
+        "Expected the struct and its impl"
+    );
+    assert!(
+        expansion.span.0 <= user_id.span.0 && user_id.span.1 <= expansion.span.1,
+        "Generated struct span {:?} should lie within the invocation {:?}",
+        user_id.span,
+        expansion.span
+    );
+    let expands_to = SyntacticRelation::ExpandsTo {
+        source: make_newtype,
+        target: user_id_pid,
+    };
+    assert!(
+        merged.graph.relations.contains(&expands_to),
+        "Expected {expands_to} in graph relations"
+    );
+    assert!(
+        merged
+            .graph
+            .impls
+            .iter()
+            .flat_map(|imp| imp.methods.iter())
+            .any(|m| m.name == "new"),
+        "Expected the generated `impl UserId {{ fn new }}`"
+    );
+
+    // Doc comments passed through a `$(#[$meta:meta])*` repetition.
+    let order_id = struct_named(&merged, "OrderId");
+    assert_eq!(
+        order_id.docstring.as_deref().map(str::trim),
+        Some("Identifies an order.")
+    );
+
+    // Repetitions with `expr` fragments.
+    let const_names: Vec<&str> = merged
+        .graph
+        .consts
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    assert!(const_names.contains(&"ONE") && const_names.contains(&"TWO"));
+
+    // Invoked from another file, and from inside another macro's expansion.
+    for name in ["SessionId", "RetryCount"] {
+        let pid = PrimaryNodeId::from(struct_named(&merged, name).id);
+        let expands_to = SyntacticRelation::ExpandsTo {
+            source: make_newtype,
+            target: pid,
+        };
+        assert!(
+            merged.graph.relations.contains(&expands_to),
+            "Expected {expands_to} for `{name}`"
+        );
+    }
+
+    // Macros that are not defined in the crate are left unexpanded.
+    assert!(
+        merged
+            .graph
+            .macro_expansions
+            .iter()
+            .all(|exp| exp.macro_name != "thread_local")
+    );
+
+    Ok(())
+}
@@ -4,10 +4,24 @@ pub mod canon_resolver;
 
 pub mod backlink_imports;
 
+pub mod bound_edges;
+
 pub mod path_attribute;
 
 pub mod prune_unlinked_imports;
 
+pub mod call_edges;
+
+pub mod derived_impls;
+
+pub mod impl_edges;
+
+pub mod macro_expansion;
+
+pub mod test_nodes;
+
+pub mod type_usage_edges;
+
 #[cfg(not(feature = "type_bearing_ids"))]
 pub mod shortest_path;
 
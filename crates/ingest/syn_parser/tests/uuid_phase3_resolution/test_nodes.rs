@@ -0,0 +1,159 @@
+//! Tests for the test functions and doc-tests collected into `TestNode`s, and the items they are
+//! linked to.
+
+use syn_parser::parser::ParsedCodeGraph;
+use syn_parser::parser::nodes::{AnyNodeId, AsAnyNodeId};
+use syn_parser::parser::test_items::{TestCoverage, TestKind, TestNode, TestTarget};
+use syn_parser::run_phases_and_collect;
+
+use ploke_error::Error as PlokeError;
+
+fn test_named<'a>(merged: &'a ParsedCodeGraph, name: &str) -> &'a TestNode {
+    merged
+        .graph
+        .tests
+        .iter()
+        .find(|test| test.name == name)
+        .unwrap_or_else(|| panic!("Test `{name}` is missing from the graph"))
+}
+
+fn function_id(merged: &ParsedCodeGraph, name: &str) -> AnyNodeId {
+    merged
+        .graph
+        .functions
+        .iter()
+        .find(|f| f.name == name)
+        .map(|f| f.id.as_any())
+        .unwrap_or_else(|| panic!("Could not find function `{name}`"))
+}
+
+fn module_id(merged: &ParsedCodeGraph, name: &str) -> AnyNodeId {
+    merged
+        .graph
+        .modules
+        .iter()
+        .find(|m| m.name == name && !m.is_decl())
+        .map(|m| m.id.as_any())
+        .unwrap_or_else(|| panic!("Could not find module `{name}`"))
+}
+
+#[test]
+fn test_unit_tests_link_callees_and_module_under_test() -> Result<(), PlokeError> {
+    let results = run_phases_and_collect("fixture_tests")?;
+    let mut merged = ParsedCodeGraph::merge_new(results)?;
+    let _tree = merged.build_tree_and_prune()?;
+
+    let add = function_id(&merged, "add");
+    let math = module_id(&merged, "math");
+    let module_under_test = TestTarget {
+        id: math,
+        coverage: TestCoverage::EnclosingModule,
+    };
+
+    // `#[test] fn adds() { assert_eq!(super::add(1, 2), 3); }` in `math::tests`.
+    let adds = test_named(&merged, "adds");
+    assert_eq!(adds.kind, TestKind::Unit);
+    assert_eq!(adds.owner, function_id(&merged, "adds"));
+    assert!(!adds.ignored);
+    assert!(
+        adds.targets.contains(&TestTarget {
+            id: add,
+            coverage: TestCoverage::Call,
+        }),
+        "Expected `adds` to cover `add`, got {:?}",
+        adds.targets
+    );
+    assert!(adds.targets.contains(&module_under_test));
+
+    let doubles = test_named(&merged, "doubles");
+    assert!(doubles.ignored, "`doubles` is `#[ignore]`d");
+
+    // `math::tests::nested` has no `#[cfg(test)]` of its own, but is inside `math::tests`.
+    let adds_zero = test_named(&merged, "adds_zero");
+    assert!(adds_zero.targets.contains(&module_under_test));
+
+    // A `#[test]` outside any `#[cfg(test)]` module has no module under test.
+    let outside = test_named(&merged, "answer_outside_test_module");
+    assert!(
+        outside
+            .targets
+            .iter()
+            .all(|target| target.coverage != TestCoverage::EnclosingModule),
+        "Unexpected module under test: {:?}",
+        outside.targets
+    );
+
+    assert!(
+        merged
+            .graph
+            .tests
+            .iter()
+            .filter(|test| test.kind != TestKind::Doc)
+            .all(|test| test.doctest_index.is_none() && test.code.is_none())
+    );
+    Ok(())
+}
+
+#[test]
+fn test_doctests_link_documented_items() -> Result<(), PlokeError> {
+    let results = run_phases_and_collect("fixture_tests")?;
+    let mut merged = ParsedCodeGraph::merge_new(results)?;
+    let _tree = merged.build_tree_and_prune()?;
+
+    // The `text` block of `answer` is not a doc-test.
+    let answer = function_id(&merged, "answer");
+    let answer_doctests: Vec<&TestNode> = merged
+        .graph
+        .tests
+        .iter()
+        .filter(|test| test.kind == TestKind::Doc && test.owner == answer)
+        .collect();
+    assert_eq!(answer_doctests.len(), 1);
+    let doctest = answer_doctests[0];
+    assert_eq!(doctest.name, "answer (doctest 1)");
+    assert_eq!(doctest.doctest_index, Some(0));
+    assert_eq!(
+        doctest.code.as_deref(),
+        Some("assert_eq!(fixture_tests::answer(), 42);")
+    );
+    assert_eq!(
+        doctest.targets,
+        vec![TestTarget {
+            id: answer,
+            coverage: TestCoverage::Documented,
+        }]
+    );
+
+    let add_doctest = test_named(&merged, "add (doctest 1)");
+    assert!(add_doctest.ignored, "The `add` doc-test is marked `ignore`");
+
+    // Hidden `# ` lines are part of the doc-test.
+    let increment_doctest = test_named(&merged, "increment (doctest 1)");
+    assert!(
+        increment_doctest
+            .code
+            .as_deref()
+            .is_some_and(|code| code.contains("assert_eq!(counter.count, 1);")),
+        "Hidden lines should be kept, got {:?}",
+        increment_doctest.code
+    );
+    assert_eq!(
+        increment_doctest.module_id.as_any(),
+        module_id(&merged, "math")
+    );
+
+    // Crate docs (`//!`) of `lib.rs`.
+    assert!(
+        merged
+            .graph
+            .tests
+            .iter()
+            .any(|test| test.kind == TestKind::Doc
+                && test
+                    .code
+                    .as_deref()
+                    .is_some_and(|c| c.contains("math::add(1, 2)"))),
+        "Expected the crate-level doc-test"
+    );
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Tests for the `UsesType` edges resolved from signatures, fields and impls.
+
+use syn_parser::parser::ParsedCodeGraph;
+use syn_parser::parser::nodes::{ModuleNodeId, PrimaryNodeId, TypeConsumerNodeId, TypeDefNode};
+use syn_parser::parser::relations::SyntacticRelation;
+use syn_parser::run_phases_and_collect;
+
+use ploke_error::Error as PlokeError;
+
+fn containing_module(merged: &ParsedCodeGraph, item: PrimaryNodeId) -> ModuleNodeId {
+    merged
+        .graph
+        .relations
+        .iter()
+        .find_map(|rel| match rel {
+            SyntacticRelation::Contains { source, target } if *target == item => Some(*source),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("No module contains {item}"))
+}
+
+fn uses_type_targets(merged: &ParsedCodeGraph, source: TypeConsumerNodeId) -> Vec<PrimaryNodeId> {
+    merged
+        .graph
+        .relations
+        .iter()
+        .filter_map(|rel| match rel {
+            SyntacticRelation::UsesType { source: s, target } if *s == source => Some(*target),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_signature_types_resolve_in_their_module() -> Result<(), PlokeError> {
+    let results = run_phases_and_collect("fixture_types")?;
+    let mut merged = ParsedCodeGraph::merge_new(results)?;
+    let _tree = merged.build_tree_and_prune()?;
+
+    // `pub fn process_tuple(p: Point) -> i32` is defined both at the crate root and in
+    // `mod duplicate_names`, each next to its own `pub type Point = (i32, i32);`.
+    let functions: Vec<_> = merged
+        .graph
+        .functions
+        .iter()
+        .filter(|f| f.name == "process_tuple")
+        .map(|f| f.id)
+        .collect();
+    assert_eq!(functions.len(), 2);
+    for function_id in functions {
+        let module = containing_module(&merged, function_id.into());
+        let targets = uses_type_targets(&merged, function_id.into());
+        assert_eq!(
+            targets.len(),
+            1,
+            "`process_tuple` should only use `Point`, found {targets:?}"
+        );
+        let point = merged
+            .graph
+            .defined_types
+            .iter()
+            .find_map(|def| match def {
+                TypeDefNode::TypeAlias(t) if PrimaryNodeId::from(t.id) == targets[0] => Some(t),
+                _ => None,
+            })
+            .expect("`UsesType` target should be a type alias");
+        assert_eq!(point.name, "Point");
+        assert_eq!(
+            containing_module(&merged, point.id.into()),
+            module,
+            "`Point` should resolve in the scope of the function using it"
+        );
+    }
+
+    // Primitive and external types produce no edges.
+    let process_slice = merged
+        .graph
+        .functions
+        .iter()
+        .find(|f| f.name == "process_slice")
+        .expect("Could not find `process_slice`");
+    assert!(uses_type_targets(&merged, process_slice.id.into()).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_self_return_resolves_to_impl_self_type() -> Result<(), PlokeError> {
+    let results = run_phases_and_collect("fixture_nodes")?;
+    let mut merged = ParsedCodeGraph::merge_new(results)?;
+    let _tree = merged.build_tree_and_prune()?;
+
+    // `impl SimpleStruct { pub fn new(data: i32) -> Self { .. } .. }` in `impls.rs`
+    let simple_impl = merged
+        .graph
+        .impls
+        .iter()
+        .find(|imp| imp.methods.iter().any(|m| m.name == "new"))
+        .expect("Could not find `impl SimpleStruct` in fixture_nodes");
+    let simple_struct = merged
+        .graph
+        .relations
+        .iter()
+        .find_map(|rel| match rel {
+            SyntacticRelation::ImplFor { source, target } if *source == simple_impl.id => {
+                Some(*target)
+            }
+            _ => None,
+        })
+        .expect("`impl SimpleStruct` should have an ImplFor edge");
+
+    let new = simple_impl
+        .methods
+        .iter()
+        .find(|m| m.name == "new")
+        .expect("Could not find `SimpleStruct::new`");
+    let uses_type = SyntacticRelation::UsesType {
+        source: new.id.into(),
+        target: simple_struct,
+    };
+    assert!(
+        merged.graph.relations.contains(&uses_type),
+        "Expected {uses_type} for `fn new(data: i32) -> Self`"
+    );
+
+    // The self type of the impl itself is covered by `ImplFor`, not duplicated as `UsesType`.
+    assert!(uses_type_targets(&merged, simple_impl.id.into()).is_empty());
+
+    Ok(())
+}
@@ -20,12 +20,46 @@ pub struct ContextStats {
     pub parts: usize,
     pub truncated_parts: usize,
     pub dedup_removed: usize,
+    /// Parts folded into an overlapping, adjacent or enclosing part of the same file.
+    #[serde(default)]
+    pub merged_parts: usize,
+    /// Parts left out because the quota of their node type was reached.
+    #[serde(default)]
+    pub capped_parts: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssembledContext {
     pub parts: Vec<ContextPart>,
     pub stats: ContextStats,
+    /// Why each part was included, in the order of `parts`, followed by the parts folded into
+    /// them.
+    #[serde(default)]
+    pub inclusions: Vec<PartInclusion>,
+}
+
+/// Why a part was included in an assembled context.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InclusionReason {
+    /// Retrieved for the query.
+    Retrieved,
+    /// Reached through the code graph from the retrieved part `from`, e.g. the struct a retrieved
+    /// method is implemented for.
+    GraphExpansion {
+        from: Uuid,
+        /// Name of the expansion rule that reached the part, e.g. `"self_type"`.
+        rule: String,
+        hops: usize,
+    },
+    /// Folded into the part `into`, whose text covers the range of this one.
+    Folded { into: Uuid },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartInclusion {
+    pub id: Uuid,
+    pub reason: InclusionReason,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd, Ord)]
@@ -101,6 +135,9 @@ pub struct AssembledMeta {
     pub search_term: String,
     pub top_k: usize,
     pub kind: ContextPartKind,
+    /// Why each part of the context was included.
+    #[serde(default)]
+    pub inclusions: Vec<PartInclusion>,
 }
 
 impl RequestCodeContextResult {
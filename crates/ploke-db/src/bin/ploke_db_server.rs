@@ -0,0 +1,55 @@
+//! Serves a ploke database backup over HTTP, see [`ploke_db::server`].
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::Parser;
+use ploke_db::bm25_index::Bm25Indexer;
+use ploke_db::server::{ServerState, router};
+use ploke_db::{Database, create_index_primary};
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Parser)]
+#[command(about = "Read-only HTTP/JSON query server over a ploke database backup")]
+struct Args {
+    /// Backup to load, as written by the TUI.
+    backup: PathBuf,
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1:8731")]
+    addr: SocketAddr,
+    /// Crate whose active embedding set is used for dense search. Without it, the first embedding
+    /// set holding vectors is used.
+    #[arg(long, default_value = "")]
+    crate_name: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+    let args = Args::parse();
+
+    let db = Database::init_with_schema()?;
+    db.import_backup_with_embeddings(&args.backup)?;
+    match db.restore_embedding_set(&args.crate_name)? {
+        Some((set, how)) => {
+            tracing::info!("Using embedding set {} ({how:?})", set.rel_name);
+            if let Err(e) = create_index_primary(&db) {
+                tracing::warn!("Failed to create the HNSW index, dense search is unavailable: {e}");
+            }
+        }
+        None => tracing::warn!("No populated embedding set, dense search is unavailable"),
+    }
+    let bm25 = Bm25Indexer::restore_or_rebuild(&db)?;
+
+    let state = ServerState {
+        db: Arc::new(db),
+        bm25: Arc::new(bm25),
+    };
+    let listener = tokio::net::TcpListener::bind(args.addr).await?;
+    tracing::info!("Listening on {}", listener.local_addr()?);
+    axum::serve(listener, router(state)).await?;
+    Ok(())
+}
@@ -3,7 +3,7 @@ use std::{collections::HashMap, sync::Arc};
 use crate::{Database, DbError};
 use ploke_core::RetrievalScope;
 
-use super::{Bm25Indexer, DocData, DocMeta};
+use super::{Bm25Indexer, Bm25Query, DocData, DocMeta};
 use tokio::sync::{mpsc, oneshot};
 use uuid::Uuid;
 
@@ -22,7 +22,7 @@ pub enum Bm25Cmd {
     IndexBatch { docs: Vec<DocData> },
     /// Remove documents from the sparse index by id.
     Remove { ids: Vec<Uuid> },
-    /// Rebuild the index from source of truth (placeholder; no-op for now).
+    /// Rebuild the index from the database and replace its persisted state.
     Rebuild,
     /// Finalize the seed/build phase; commit metadata and return ack.
     FinalizeSeed {
@@ -35,6 +35,13 @@ pub enum Bm25Cmd {
         scope: RetrievalScope,
         resp: oneshot::Sender<Vec<(Uuid, f32)>>,
     },
+    /// Search with a parsed query, e.g. to apply non-default field boosts.
+    SearchQuery {
+        query: Bm25Query,
+        top_k: usize,
+        scope: RetrievalScope,
+        resp: oneshot::Sender<Vec<(Uuid, f32)>>,
+    },
     /// Get current lifecycle/status of the BM25 actor/index.
     Status {
         resp: oneshot::Sender<Result<Bm25Status, DbError>>,
@@ -49,6 +56,10 @@ pub enum Bm25Cmd {
         path: std::path::PathBuf,
         resp: oneshot::Sender<Result<(), DbError>>,
     },
+    /// Restore the index persisted in the database, rebuilding it when missing or stale.
+    Restore {
+        resp: oneshot::Sender<Result<(), DbError>>,
+    },
 }
 
 /// Start the BM25 actor with a given avgdl parameter.
@@ -80,20 +91,25 @@ pub fn start(db: Arc<Database>, avgdl: f32) -> Result<mpsc::Sender<Bm25Cmd>, DbE
                 }
                 Bm25Cmd::Remove { ids } => {
                     tracing::debug!("BM25 Remove: {} docs", ids.len());
-                    for id in ids {
-                        indexer.remove(&id);
+                    match indexer.remove_with_cozo(db.as_ref(), &ids) {
+                        Ok(()) => {
+                            let docs = indexer.doc_count();
+                            status = if docs == 0 {
+                                Bm25Status::Empty
+                            } else {
+                                Bm25Status::Ready { docs }
+                            };
+                        }
+                        Err(e) => {
+                            tracing::error!("Error removing docs from cozo: {e}");
+                            status = Bm25Status::Error(e.to_string());
+                        }
                     }
-                    let docs = indexer.doc_count();
-                    status = if docs == 0 {
-                        Bm25Status::Empty
-                    } else {
-                        Bm25Status::Ready { docs }
-                    };
                 }
                 Bm25Cmd::Rebuild => {
                     tracing::info!("BM25 Rebuild: starting rebuild from database");
                     // status = Bm25Status::Building;
-                    match Bm25Indexer::rebuild_from_db(db.as_ref()) {
+                    match Bm25Indexer::rebuild_and_persist(db.as_ref()) {
                         Ok(new_indexer) => {
                             let docs = new_indexer.doc_count();
                             indexer = new_indexer;
@@ -143,6 +159,23 @@ pub fn start(db: Arc<Database>, avgdl: f32) -> Result<mpsc::Sender<Bm25Cmd>, DbE
                         );
                     }
                 }
+                Bm25Cmd::SearchQuery {
+                    query,
+                    top_k,
+                    scope,
+                    resp,
+                } => {
+                    let results: Vec<(Uuid, f32)> = indexer
+                        .search_query(&query, top_k, scope)
+                        .into_iter()
+                        .map(|d| (d.id, d.score))
+                        .collect();
+                    if resp.send(results).is_err() {
+                        tracing::warn!(
+                            "BM25 search response receiver dropped before sending results"
+                        );
+                    }
+                }
                 Bm25Cmd::Status { resp } => {
                     let _ = resp.send(Ok(status.clone()));
                 }
@@ -167,9 +200,8 @@ pub fn start(db: Arc<Database>, avgdl: f32) -> Result<mpsc::Sender<Bm25Cmd>, DbE
                 }
                 Bm25Cmd::Load { path, resp } => {
                     tracing::info!("BM25 Load requested from {:?}", path);
-                    // Placeholder implementation: perform a rebuild to ensure a ready index.
-                    // status = Bm25Status::Building;
-                    let res = match Bm25Indexer::rebuild_from_db(db.as_ref()) {
+                    // The index is persisted in the database; the sidecar path is not read.
+                    let res = match Bm25Indexer::restore_or_rebuild(db.as_ref()) {
                         Ok(new_indexer) => {
                             let docs = new_indexer.doc_count();
                             indexer = new_indexer;
@@ -188,6 +220,26 @@ pub fn start(db: Arc<Database>, avgdl: f32) -> Result<mpsc::Sender<Bm25Cmd>, DbE
                     };
                     let _ = resp.send(res);
                 }
+                Bm25Cmd::Restore { resp } => {
+                    let res = match Bm25Indexer::restore_or_rebuild(db.as_ref()) {
+                        Ok(new_indexer) => {
+                            let docs = new_indexer.doc_count();
+                            indexer = new_indexer;
+                            status = if docs == 0 {
+                                Bm25Status::Empty
+                            } else {
+                                Bm25Status::Ready { docs }
+                            };
+                            Ok(())
+                        }
+                        Err(e) => {
+                            let msg = e.to_string();
+                            status = Bm25Status::Error(msg.clone());
+                            Err(DbError::Cozo(format!("bm25 restore error: {}", msg)))
+                        }
+                    };
+                    let _ = resp.send(res);
+                }
             }
         }
         tracing::info!("BM25 service actor loop ended");
@@ -201,10 +253,10 @@ pub fn start_default(db: Arc<Database>) -> Result<mpsc::Sender<Bm25Cmd>, DbError
     start(db, 10.0)
 }
 
-/// Start the BM25 actor by rebuilding the in-memory index from the Cozo database.
+/// Start the BM25 actor by restoring the index persisted in the Cozo database.
 ///
-/// This scans all primary node relations, computes a fitted avgdl, and populates the in-memory
-/// scorer so the service is immediately queryable.
+/// When no usable index was persisted, this scans all primary node relations, computes a fitted
+/// avgdl, and populates the in-memory scorer so the service is immediately queryable.
 ///
 /// Example (no_run):
 /// ```
@@ -219,7 +271,7 @@ pub fn start_default(db: Arc<Database>) -> Result<mpsc::Sender<Bm25Cmd>, DbError
 /// ```
 pub fn start_rebuilt(db: Arc<Database>) -> Result<mpsc::Sender<Bm25Cmd>, DbError> {
     let (tx, mut rx) = mpsc::channel::<Bm25Cmd>(128);
-    let indexer = Bm25Indexer::rebuild_from_db(db.as_ref())?;
+    let indexer = Bm25Indexer::restore_or_rebuild(db.as_ref())?;
 
     tokio::spawn(async move {
         let mut indexer = indexer;
@@ -252,20 +304,25 @@ pub fn start_rebuilt(db: Arc<Database>) -> Result<mpsc::Sender<Bm25Cmd>, DbError
                 }
                 Bm25Cmd::Remove { ids } => {
                     tracing::debug!("BM25 Remove: {} docs", ids.len());
-                    for id in ids {
-                        indexer.remove(&id);
+                    match indexer.remove_with_cozo(db.as_ref(), &ids) {
+                        Ok(()) => {
+                            let docs = indexer.doc_count();
+                            status = if docs == 0 {
+                                Bm25Status::Empty
+                            } else {
+                                Bm25Status::Ready { docs }
+                            };
+                        }
+                        Err(e) => {
+                            tracing::error!("Error removing docs from cozo: {e}");
+                            status = Bm25Status::Error(e.to_string());
+                        }
                     }
-                    let docs = indexer.doc_count();
-                    status = if docs == 0 {
-                        Bm25Status::Empty
-                    } else {
-                        Bm25Status::Ready { docs }
-                    };
                 }
                 Bm25Cmd::Rebuild => {
                     tracing::info!("BM25 Rebuild: starting rebuild from database");
                     // status = Bm25Status::Building;
-                    match Bm25Indexer::rebuild_from_db(db.as_ref()) {
+                    match Bm25Indexer::rebuild_and_persist(db.as_ref()) {
                         Ok(new_indexer) => {
                             let docs = new_indexer.doc_count();
                             indexer = new_indexer;
@@ -313,6 +370,23 @@ pub fn start_rebuilt(db: Arc<Database>) -> Result<mpsc::Sender<Bm25Cmd>, DbError
                         );
                     }
                 }
+                Bm25Cmd::SearchQuery {
+                    query,
+                    top_k,
+                    scope,
+                    resp,
+                } => {
+                    let results: Vec<(Uuid, f32)> = indexer
+                        .search_query(&query, top_k, scope)
+                        .into_iter()
+                        .map(|d| (d.id, d.score))
+                        .collect();
+                    if resp.send(results).is_err() {
+                        tracing::warn!(
+                            "BM25 search response receiver dropped before sending results"
+                        );
+                    }
+                }
                 Bm25Cmd::Status { resp } => {
                     let _ = resp.send(Ok(status.clone()));
                 }
@@ -337,9 +411,8 @@ pub fn start_rebuilt(db: Arc<Database>) -> Result<mpsc::Sender<Bm25Cmd>, DbError
                 }
                 Bm25Cmd::Load { path, resp } => {
                     tracing::info!("BM25 Load requested from {:?}", path);
-                    // Placeholder implementation: perform a rebuild to ensure a ready index.
-                    // status = Bm25Status::Building;
-                    let res = match Bm25Indexer::rebuild_from_db(db.as_ref()) {
+                    // The index is persisted in the database; the sidecar path is not read.
+                    let res = match Bm25Indexer::restore_or_rebuild(db.as_ref()) {
                         Ok(new_indexer) => {
                             let docs = new_indexer.doc_count();
                             indexer = new_indexer;
@@ -358,6 +431,26 @@ pub fn start_rebuilt(db: Arc<Database>) -> Result<mpsc::Sender<Bm25Cmd>, DbError
                     };
                     let _ = resp.send(res);
                 }
+                Bm25Cmd::Restore { resp } => {
+                    let res = match Bm25Indexer::restore_or_rebuild(db.as_ref()) {
+                        Ok(new_indexer) => {
+                            let docs = new_indexer.doc_count();
+                            indexer = new_indexer;
+                            status = if docs == 0 {
+                                Bm25Status::Empty
+                            } else {
+                                Bm25Status::Ready { docs }
+                            };
+                            Ok(())
+                        }
+                        Err(e) => {
+                            let msg = e.to_string();
+                            status = Bm25Status::Error(msg.clone());
+                            Err(DbError::Cozo(format!("bm25 restore error: {}", msg)))
+                        }
+                    };
+                    let _ = resp.send(res);
+                }
             }
         }
         tracing::info!("BM25 service actor loop ended");
@@ -0,0 +1,422 @@
+//! Fielded documents for the structured BM25 index.
+//!
+//! A document is split into [`Bm25Field`]s which are tokenized separately. Each token is stored
+//! under an id tagged with its field, so a query scores every field on its own and weighs the
+//! field scores with [`FieldBoosts`]. Next to the scored fields, a document also carries:
+//! - prefixes of its name, for `pars*` queries,
+//! - pairs of adjacent tokens, for `"exact phrase"` queries,
+//! - its kind and module path, for `kind:fn` and `path:crate::foo` filters.
+//!
+//! Token ids are stable hashes rather than the ids of `bm25::Embedder`, so postings persisted in
+//! Cozo stay valid across runs.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+};
+
+use bm25::{Embedding, TokenEmbedding, Tokenizer};
+use serde::{Deserialize, Serialize};
+
+use super::CodeTokenizer;
+
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+/// Shortest name prefix indexed for prefix queries.
+pub const MIN_PREFIX_LEN: usize = 2;
+/// Longest name prefix indexed for prefix queries; longer query prefixes are truncated.
+pub const MAX_PREFIX_LEN: usize = 16;
+
+const PREFIX_TAG: &str = "prefix";
+const PHRASE_TAG: &str = "phrase";
+const KIND_TAG: &str = "kind";
+const PATH_FILTER_TAG: &str = "in";
+
+/// A scored field of an indexed document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bm25Field {
+    Name,
+    Path,
+    Docs,
+    Signature,
+    Body,
+}
+
+impl Bm25Field {
+    pub const ALL: [Bm25Field; 5] = [
+        Bm25Field::Name,
+        Bm25Field::Path,
+        Bm25Field::Docs,
+        Bm25Field::Signature,
+        Bm25Field::Body,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Bm25Field::Name => "name",
+            Bm25Field::Path => "path",
+            Bm25Field::Docs => "docs",
+            Bm25Field::Signature => "signature",
+            Bm25Field::Body => "body",
+        }
+    }
+
+    /// Typical token length of the field, used to normalize term frequencies. The body uses the
+    /// average fitted by the indexer.
+    fn avgdl(self, body_avgdl: f32) -> f32 {
+        match self {
+            Bm25Field::Name => 2.0,
+            Bm25Field::Path => 3.0,
+            Bm25Field::Docs => 16.0,
+            Bm25Field::Signature => 8.0,
+            Bm25Field::Body => body_avgdl,
+        }
+    }
+}
+
+/// Query-time weight of each field's score. A zero boost leaves the field out of the search.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldBoosts {
+    pub name: f32,
+    pub path: f32,
+    pub docs: f32,
+    pub signature: f32,
+    pub body: f32,
+    /// Weight of `pars*` prefix matches against the item name.
+    pub prefix: f32,
+}
+
+impl Default for FieldBoosts {
+    fn default() -> Self {
+        Self {
+            name: 3.0,
+            path: 1.0,
+            docs: 1.0,
+            signature: 1.5,
+            body: 1.0,
+            prefix: 2.0,
+        }
+    }
+}
+
+impl FieldBoosts {
+    pub fn get(&self, field: Bm25Field) -> f32 {
+        match field {
+            Bm25Field::Name => self.name,
+            Bm25Field::Path => self.path,
+            Bm25Field::Docs => self.docs,
+            Bm25Field::Signature => self.signature,
+            Bm25Field::Body => self.body,
+        }
+    }
+}
+
+/// Stable id of `token` within the token group `tag` (32-bit FNV-1a).
+fn token_id(tag: &str, token: &str) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in tag.bytes().chain([0x1f]).chain(token.bytes()) {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+pub(crate) fn field_token_id(field: Bm25Field, token: &str) -> u32 {
+    token_id(field.as_str(), token)
+}
+
+pub(crate) fn prefix_token_id(prefix: &str) -> u32 {
+    token_id(PREFIX_TAG, prefix)
+}
+
+pub(crate) fn bigram_token_id(first: &str, second: &str) -> u32 {
+    token_id(PHRASE_TAG, &format!("{first} {second}"))
+}
+
+pub(crate) fn kind_token_id(kind: &str) -> u32 {
+    token_id(KIND_TAG, kind)
+}
+
+pub(crate) fn path_filter_token_id(path: &str) -> u32 {
+    token_id(PATH_FILTER_TAG, path)
+}
+
+/// Normalizes the prefix of a `pars*` query the way name prefixes are indexed. Returns `None` for
+/// prefixes shorter than [`MIN_PREFIX_LEN`].
+pub(crate) fn normalize_prefix(prefix: &str) -> Option<String> {
+    let prefix: String = prefix.to_lowercase().chars().take(MAX_PREFIX_LEN).collect();
+    (prefix.chars().count() >= MIN_PREFIX_LEN).then_some(prefix)
+}
+
+/// Embedding matching every document holding one of `ids`.
+pub(crate) fn query_embedding(ids: impl IntoIterator<Item = u32>) -> Embedding<u32> {
+    let ids: BTreeSet<u32> = ids.into_iter().collect();
+    Embedding(
+        ids.into_iter()
+            .map(|index| TokenEmbedding { index, value: 1.0 })
+            .collect(),
+    )
+}
+
+/// A document split into the fields of the structured BM25 index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldedDoc {
+    pub name: String,
+    /// Path of the module holding the item, e.g. `["crate", "parser"]`.
+    pub module_path: Vec<String>,
+    /// Relation of the item's node, e.g. `function` or `struct`.
+    pub kind: Option<String>,
+    pub docs: String,
+    pub signature: String,
+    pub body: String,
+}
+
+impl FieldedDoc {
+    /// A document with only a name, as rebuilt from the database without snippets.
+    pub fn named(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Splits `snippet` into its leading doc comments, its signature up to the first `{` or `;`,
+    /// and the remaining body. Attributes before the signature are dropped.
+    pub fn from_snippet(name: impl Into<String>, snippet: &str) -> Self {
+        let mut docs = Vec::new();
+        let mut in_block_doc = false;
+        let mut code_start = snippet.len();
+        let mut offset = 0;
+        for line in snippet.split_inclusive('\n') {
+            let line_start = offset;
+            offset += line.len();
+            let trimmed = line.trim();
+            if in_block_doc {
+                in_block_doc = !trimmed.contains("*/");
+                docs.push(trimmed.trim_end_matches("*/").trim_start_matches('*'));
+            } else if trimmed.is_empty() || trimmed.starts_with("#[") || trimmed.starts_with("#![")
+            {
+                continue;
+            } else if let Some(doc) = trimmed
+                .strip_prefix("///")
+                .or_else(|| trimmed.strip_prefix("//!"))
+            {
+                docs.push(doc);
+            } else if let Some(doc) = trimmed
+                .strip_prefix("/**")
+                .or_else(|| trimmed.strip_prefix("/*!"))
+            {
+                in_block_doc = !doc.contains("*/");
+                docs.push(doc.trim_end_matches("*/"));
+            } else {
+                code_start = line_start;
+                break;
+            }
+        }
+
+        let code = &snippet[code_start..];
+        let (signature, body) = match signature_end(code) {
+            Some(idx) => code.split_at(idx),
+            None => (code, ""),
+        };
+        Self {
+            name: name.into(),
+            module_path: Vec::new(),
+            kind: None,
+            docs: docs
+                .iter()
+                .map(|doc| doc.trim())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            signature: signature.trim().to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    pub fn with_module_path(mut self, module_path: Vec<String>) -> Self {
+        self.module_path = module_path;
+        self
+    }
+
+    pub fn with_kind(mut self, kind: impl Into<String>) -> Self {
+        self.kind = Some(kind.into());
+        self
+    }
+
+    fn field_text(&self, field: Bm25Field) -> Cow<'_, str> {
+        match field {
+            Bm25Field::Name => Cow::Borrowed(&self.name),
+            Bm25Field::Path => Cow::Owned(self.module_path.join(" ")),
+            Bm25Field::Docs => Cow::Borrowed(&self.docs),
+            Bm25Field::Signature => Cow::Borrowed(&self.signature),
+            Bm25Field::Body => Cow::Borrowed(&self.body),
+        }
+    }
+
+    /// Prefixes of the name's subtokens, of the lowercased name and of its subtokens joined, so
+    /// `parse_h*`, `parseh*` and `head*` all match `parse_header`.
+    fn name_prefixes(&self) -> BTreeSet<String> {
+        let subtokens = CodeTokenizer::split_identifier(&self.name);
+        let words = subtokens
+            .iter()
+            .cloned()
+            .chain([self.name.to_lowercase(), subtokens.concat()]);
+        let mut prefixes = BTreeSet::new();
+        for word in words {
+            let chars: Vec<char> = word.chars().collect();
+            for len in MIN_PREFIX_LEN..=chars.len().min(MAX_PREFIX_LEN) {
+                prefixes.insert(chars[..len].iter().collect());
+            }
+        }
+        prefixes
+    }
+
+    /// Every `path:` filter the document passes: each prefix of its module path, and its own
+    /// path including the name.
+    fn path_filters(&self) -> Vec<String> {
+        let mut filters: Vec<String> = (1..=self.module_path.len())
+            .map(|len| self.module_path[..len].join("::"))
+            .collect();
+        if !self.name.is_empty() {
+            filters.push(
+                self.module_path
+                    .iter()
+                    .map(String::as_str)
+                    .chain([self.name.as_str()])
+                    .collect::<Vec<_>>()
+                    .join("::"),
+            );
+        }
+        filters
+    }
+
+    /// Embeds the document with BM25 term-frequency weights. `body_avgdl` normalizes the length
+    /// of the body; the other fields use fixed typical lengths.
+    pub(crate) fn embed(&self, body_avgdl: f32) -> Embedding<u32> {
+        let tokenizer = CodeTokenizer;
+        let mut weights: HashMap<u32, f32> = HashMap::new();
+        for field in Bm25Field::ALL {
+            let tokens = tokenizer.tokenize(&self.field_text(field));
+            add_weights(
+                &mut weights,
+                tokens.iter().map(|token| field_token_id(field, token)),
+                Some(field.avgdl(body_avgdl)),
+            );
+            add_weights(
+                &mut weights,
+                tokens
+                    .windows(2)
+                    .map(|pair| bigram_token_id(&pair[0], &pair[1])),
+                None,
+            );
+        }
+        add_weights(
+            &mut weights,
+            self.name_prefixes()
+                .iter()
+                .map(|prefix| prefix_token_id(prefix)),
+            None,
+        );
+        add_weights(
+            &mut weights,
+            self.kind.iter().map(|kind| kind_token_id(kind)),
+            None,
+        );
+        add_weights(
+            &mut weights,
+            self.path_filters()
+                .iter()
+                .map(|path| path_filter_token_id(path)),
+            None,
+        );
+
+        let mut embedding: Vec<TokenEmbedding<u32>> = weights
+            .into_iter()
+            .map(|(index, value)| TokenEmbedding { index, value })
+            .collect();
+        embedding.sort_by_key(|token| token.index);
+        Embedding(embedding)
+    }
+}
+
+/// Byte offset of the `{` or `;` ending the signature at the start of `code`. Semicolons inside
+/// brackets, as in `[u8; 4]`, do not count.
+fn signature_end(code: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    for (idx, c) in code.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            '{' => return Some(idx),
+            ';' if depth <= 0 => return Some(idx),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Adds the BM25 term-frequency weight of each distinct id in `ids`. Without an `avgdl` the
+/// weights are not normalized by length.
+fn add_weights(
+    weights: &mut HashMap<u32, f32>,
+    ids: impl Iterator<Item = u32>,
+    avgdl: Option<f32>,
+) {
+    let mut counts: HashMap<u32, f32> = HashMap::new();
+    let mut len = 0usize;
+    for id in ids {
+        *counts.entry(id).or_default() += 1.0;
+        len += 1;
+    }
+    let norm = match avgdl {
+        Some(avgdl) if avgdl > 0.0 => 1.0 - B + B * (len as f32) / avgdl,
+        _ => 1.0,
+    };
+    for (id, tf) in counts {
+        *weights.entry(id).or_default() += tf * (K1 + 1.0) / (tf + K1 * norm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_snippet_splits_docs_signature_and_body() {
+        let snippet = "/// Parses the header.\n/** More docs. */\n#[inline]\npub fn parse_header(buf: &[u8]) -> Header {\n    todo!()\n}";
+        let doc = FieldedDoc::from_snippet("parse_header", snippet);
+        assert_eq!(doc.docs, "Parses the header.\nMore docs.");
+        assert_eq!(doc.signature, "pub fn parse_header(buf: &[u8]) -> Header");
+        assert_eq!(doc.body, "{\n    todo!()\n}");
+
+        let array = FieldedDoc::from_snippet("KEY", "const KEY: [u8; 4] = [0; 4];");
+        assert_eq!(array.signature, "const KEY: [u8; 4] = [0; 4]");
+
+        let unit = FieldedDoc::from_snippet("Marker", "struct Marker;");
+        assert_eq!(unit.signature, "struct Marker");
+        assert_eq!(unit.body, ";");
+    }
+
+    #[test]
+    fn name_prefixes_cover_subtokens_and_joined_name() {
+        let prefixes = FieldedDoc::named("parseHeader").name_prefixes();
+        for prefix in ["pa", "pars", "he", "head", "parsehe"] {
+            assert!(prefixes.contains(prefix), "missing {prefix}");
+        }
+        assert!(!prefixes.contains("p"));
+        assert_eq!(normalize_prefix("Pars"), Some("pars".to_string()));
+        assert_eq!(normalize_prefix("p"), None);
+    }
+
+    #[test]
+    fn path_filters_include_module_prefixes_and_item_path() {
+        let doc = FieldedDoc::named("parse")
+            .with_module_path(vec!["crate".to_string(), "parser".to_string()]);
+        assert_eq!(
+            doc.path_filters(),
+            vec!["crate", "crate::parser", "crate::parser::parse"]
+        );
+    }
+}
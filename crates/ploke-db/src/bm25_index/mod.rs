@@ -2,25 +2,37 @@
 // BM25 integration for Ploke using the `bm25` crate v2.3.1
 // - In-memory indexer that accepts batches of (Uuid, snippet)
 // - Uses a code-aware tokenizer (implements bm25::Tokenizer)
-// - Uses bm25::Embedder and bm25::Scorer to build sparse embeddings and score queries
+// - Builds field-tagged sparse embeddings and scores queries with bm25::Scorer
 // - Adds Cozo client trait + an index_batch_with_cozo method that upserts doc metadata into Cozo
 // - Adds `new_from_corpus` constructor that consumes a Vec<(Uuid, String)> to compute avgdl
+// - Persists sparse vectors in Cozo so the index can be restored instead of rebuilt
+// - Indexes documents by field and parses structured queries (see `fields` and `query`)
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::Arc,
 };
 
-use bm25::{EmbedderBuilder, Scorer, Tokenizer};
+use bm25::{Scorer, Tokenizer};
 use cozo::{DataValue, UuidWrapper};
 use ploke_core::{CrateId, EmbeddingData, RetrievalScope, TrackingHash};
 use uuid::Uuid;
 
-use crate::{Database, DbError, NodeType};
+use crate::{Database, DbError, NodeType, database::to_string_list};
 
 pub mod bm25_service;
+pub mod fields;
+pub mod query;
 
-pub const TOKENIZER_VERSION: &str = "code_version_v1";
+pub use fields::{Bm25Field, FieldBoosts, FieldedDoc};
+pub use query::Bm25Query;
+
+use fields::{
+    bigram_token_id, field_token_id, kind_token_id, path_filter_token_id, prefix_token_id,
+    query_embedding,
+};
+
+pub const TOKENIZER_VERSION: &str = "code_version_v2";
 
 // ------------------------- Code-aware tokenizer -------------------------
 // Implements bm25::Tokenizer by producing a Vec<String> of tokens from code.
@@ -400,9 +412,58 @@ impl DocData {
     }
 }
 
+/// Sparse BM25 vector of a document, as persisted in the `bm25_doc_postings` relation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bm25Posting {
+    pub id: Uuid,
+    pub namespace: Uuid,
+    pub token_ids: Vec<u32>,
+    pub token_weights: Vec<f32>,
+}
+
+impl Bm25Posting {
+    pub fn from_embedding(id: Uuid, namespace: Uuid, embedding: &bm25::Embedding<u32>) -> Self {
+        let (token_ids, token_weights) = embedding
+            .0
+            .iter()
+            .map(|token| (token.index, token.value))
+            .unzip();
+        Self {
+            id,
+            namespace,
+            token_ids,
+            token_weights,
+        }
+    }
+
+    pub fn to_embedding(&self) -> bm25::Embedding<u32> {
+        bm25::Embedding(
+            self.token_ids
+                .iter()
+                .zip(&self.token_weights)
+                .map(|(&index, &value)| bm25::TokenEmbedding { index, value })
+                .collect(),
+        )
+    }
+}
+
+/// Parameters of the persisted BM25 index for one namespace, from the `bm25_index_stats`
+/// relation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bm25IndexStats {
+    pub namespace: Uuid,
+    pub tokenizer_version: String,
+    pub avgdl: f32,
+    /// Number of persisted documents of the namespace. 0 for stats written before the count was
+    /// recorded.
+    pub doc_count: usize,
+    /// Sum of the token lengths of those documents.
+    pub total_len: f64,
+}
+
 // ------------------------- BM25 Indexer -------------------------
 
-/// In-memory BM25 indexer that uses bm25::Embedder + Scorer.
+/// In-memory BM25 indexer over [`FieldedDoc`]s, scored with bm25::Scorer.
 ///
 /// Example:
 /// ```
@@ -425,23 +486,22 @@ impl DocData {
 /// assert!(!res.is_empty());
 /// ```
 pub struct Bm25Indexer {
-    embedder: bm25::Embedder<u32, CodeTokenizer>,
     scorer: Scorer<Uuid, u32>,
     staged_meta: HashMap<Uuid, DocMeta>,
     docs: HashSet<Uuid>,
+    avgdl: f32,
     version: &'static str,
 }
 
 impl Bm25Indexer {
     /// Create a new indexer. `avgdl` should be an estimate or a fitted value for your corpus.
     pub fn new(avgdl: f32) -> Self {
-        let embedder = EmbedderBuilder::<u32, CodeTokenizer>::with_avgdl(avgdl).build();
         let scorer = Scorer::<Uuid, u32>::new();
         Self {
-            embedder,
             scorer,
             staged_meta: HashMap::new(),
             docs: HashSet::new(),
+            avgdl,
             version: TOKENIZER_VERSION,
         }
     }
@@ -474,32 +534,22 @@ impl Bm25Indexer {
             0.0
         };
 
-        let embedder = EmbedderBuilder::<u32, CodeTokenizer>::with_avgdl(avgdl).build();
-        let mut scorer = Scorer::<Uuid, u32>::new();
-        let mut docs_set = HashSet::new();
+        let mut indexer = Self::new(avgdl);
 
         // now embed and upsert
         for (id, _len, snippet) in doc_token_counts.into_iter() {
-            let embedding = embedder.embed(&snippet);
-            scorer.upsert(&id, embedding);
-            docs_set.insert(id);
-        }
-
-        Self {
-            embedder,
-            scorer,
-            staged_meta: HashMap::new(),
-            docs: docs_set,
-            version: TOKENIZER_VERSION,
+            let embedding = FieldedDoc::from_snippet("", &snippet).embed(avgdl);
+            indexer.scorer.upsert(&id, embedding);
+            indexer.docs.insert(id);
         }
+        indexer
     }
 
     /// Rebuild the in-memory BM25 index from the Cozo database.
     ///
     /// This scans all primary node relations for (id, name, tracking_hash), computes a corpus
-    /// average document length using the code-aware tokenizer, builds a new embedder with the
-    /// fitted avgdl, and indexes each document using a light-weight representation of the snippet
-    /// (the identifier name doubled to provide a small boost).
+    /// average document length using the code-aware tokenizer, and indexes each document using a
+    /// light-weight representation without the snippet: its name, kind and module path.
     ///
     /// The rebuild does not depend on preexisting BM25 metadata and will work on any database
     /// containing the primary node relations with `id`, `name` and `tracking_hash` attributes.
@@ -520,11 +570,97 @@ impl Bm25Indexer {
     /// # }
     /// ```
     pub fn rebuild_from_db(db: &Database) -> Result<Self, DbError> {
+        Self::rebuild_with_postings(db).map(|(indexer, _)| indexer)
+    }
+
+    /// Like [`Self::rebuild_from_db`], then replaces the persisted index with the rebuilt one.
+    pub fn rebuild_and_persist(db: &Database) -> Result<Self, DbError> {
+        let (indexer, postings) = Self::rebuild_with_postings(db)?;
+        indexer.persist(db, postings)?;
+        Ok(indexer)
+    }
+
+    /// Restore the index persisted by [`Self::upsert_batch_with_cozo`] and
+    /// [`Self::rebuild_and_persist`] without re-tokenizing any document.
+    ///
+    /// Returns `None` when nothing was persisted or the postings were produced by another
+    /// tokenizer version, in which case the index has to be rebuilt.
+    pub fn restore_from_db(db: &Database) -> Result<Option<Self>, DbError> {
+        let stats = db.bm25_index_stats()?;
+        if stats.is_empty() {
+            return Ok(None);
+        }
+        if let Some(stale) = stats
+            .iter()
+            .find(|s| s.tokenizer_version != TOKENIZER_VERSION)
+        {
+            tracing::info!(
+                "BM25 postings of namespace {} use tokenizer {}, expected {TOKENIZER_VERSION}",
+                stale.namespace,
+                stale.tokenizer_version
+            );
+            return Ok(None);
+        }
+        if let Some(uncounted) = stats.iter().find(|s| s.doc_count == 0) {
+            tracing::info!(
+                "BM25 index stats of namespace {} have no document count",
+                uncounted.namespace
+            );
+            return Ok(None);
+        }
+        let postings = db.bm25_postings()?;
+        if postings.is_empty() {
+            return Ok(None);
+        }
+
+        // Queries are embedded with the average length over the documents of every namespace, so
+        // each namespace counts by its number of documents.
+        let doc_count = stats.iter().map(|s| s.doc_count).sum::<usize>();
+        let total_len = stats.iter().map(|s| s.total_len).sum::<f64>();
+        let avgdl = (total_len / doc_count as f64) as f32;
+        let mut indexer = Self::new(avgdl);
+        for (posting, meta) in postings {
+            indexer.scorer.upsert(&posting.id, posting.to_embedding());
+            indexer.docs.insert(posting.id);
+            indexer.staged_meta.insert(posting.id, meta);
+        }
+        Ok(Some(indexer))
+    }
+
+    /// Restore the persisted index, falling back to a rebuild when it is missing, stale or
+    /// unreadable. A rebuilt index that cannot be persisted is still returned.
+    pub fn restore_or_rebuild(db: &Database) -> Result<Self, DbError> {
+        match Self::restore_from_db(db) {
+            Ok(Some(indexer)) => {
+                tracing::info!("BM25 index restored with {} docs", indexer.doc_count());
+                return Ok(indexer);
+            }
+            Ok(None) => tracing::info!("No usable persisted BM25 index, rebuilding"),
+            Err(e) => tracing::warn!("Failed to restore the persisted BM25 index, rebuilding: {e}"),
+        }
+        let (indexer, postings) = Self::rebuild_with_postings(db)?;
+        if let Err(e) = indexer.persist(db, postings) {
+            tracing::warn!("Failed to persist the rebuilt BM25 index: {e}");
+        }
+        Ok(indexer)
+    }
+
+    /// Replace the persisted index with the documents of this indexer.
+    fn persist(&self, db: &Database, postings: Vec<Bm25Posting>) -> Result<(), DbError> {
+        db.retract_bm25_index()?;
+        db.upsert_bm25_doc_meta_batch(self.staged_meta.iter().map(|(id, meta)| (*id, *meta)))?;
+        db.upsert_bm25_postings_batch(self.avgdl, postings)
+    }
+
+    fn rebuild_with_postings(db: &Database) -> Result<(Self, Vec<Bm25Posting>), DbError> {
         let items = collect_rebuild_sources(db)?;
         let n = items.len();
 
-        // Compute total token count and prepare compact (id, meta, combined) triples
-        let (sum, metas): (usize, Vec<(Uuid, DocMeta, String)>) = items
+        let ids: Vec<Uuid> = items.iter().map(|(id, ..)| *id).collect();
+        let mut locations = collect_doc_locations(db, &ids)?;
+
+        // Compute total token count and prepare compact (id, meta, doc) triples
+        let (sum, metas): (usize, Vec<(Uuid, DocMeta, FieldedDoc)>) = items
             .into_iter()
             .map(|(id, name, th, namespace)| {
                 let token_len = CodeTokenizer::count_tokens_in_code(&name);
@@ -533,15 +669,17 @@ impl Bm25Indexer {
                     tracking_hash: th,
                     namespace,
                 };
-                // Combined text: boost the identifier by doubling it
-                let combined = format!("{0} {0}", name);
-                (id, meta, combined)
+                let doc = locations
+                    .remove(&id)
+                    .unwrap_or_default()
+                    .apply(FieldedDoc::named(name));
+                (id, meta, doc)
             })
             .fold(
                 (0usize, Vec::new()),
-                |(acc_sum, mut acc_vec), (id, meta, combined)| {
+                |(acc_sum, mut acc_vec), (id, meta, doc)| {
                     let new_sum = acc_sum + meta.token_length;
-                    acc_vec.push((id, meta, combined));
+                    acc_vec.push((id, meta, doc));
                     (new_sum, acc_vec)
                 },
             );
@@ -551,24 +689,22 @@ impl Bm25Indexer {
         } else {
             (sum as f32) / (n as f32)
         };
-        let embedder = EmbedderBuilder::<u32, CodeTokenizer>::with_avgdl(avgdl).build();
-        let mut scorer = Scorer::<Uuid, u32>::new();
-        let mut staged_meta = HashMap::with_capacity(n);
-
-        for (id, meta, combined) in metas.into_iter() {
-            let emb = embedder.embed(&combined);
-            scorer.upsert(&id, emb);
-            staged_meta.insert(id, meta);
+        let mut indexer = Self::new(avgdl);
+        let mut postings = Vec::with_capacity(n);
+        for (id, meta, doc) in metas.into_iter() {
+            postings.push(indexer.upsert_doc(id, meta, &doc));
         }
+        Ok((indexer, postings))
+    }
 
-        let docs = staged_meta.keys().cloned().collect();
-        Ok(Self {
-            embedder,
-            scorer,
-            staged_meta,
-            docs,
-            version: TOKENIZER_VERSION,
-        })
+    /// Index one document, returning its sparse vector for persistence.
+    fn upsert_doc(&mut self, id: Uuid, meta: DocMeta, doc: &FieldedDoc) -> Bm25Posting {
+        let embedding = doc.embed(self.avgdl);
+        let posting = Bm25Posting::from_embedding(id, meta.namespace, &embedding);
+        self.scorer.upsert(&id, embedding);
+        self.docs.insert(id);
+        self.staged_meta.insert(id, meta);
+        posting
     }
 
     /// Index a batch of (uuid, snippet, name) items.
@@ -585,23 +721,8 @@ impl Bm25Indexer {
             name,
         } in batch
         {
-            // Boost the identifier by repeating it once before the snippet.
-            // The tokenizer will split the identifier into subtokens.
-            let combined = format!("{} {} {}", name, name, snippet);
-            let embedding = self.embedder.embed(&combined);
-            self.scorer.upsert(&id, embedding);
-            self.docs.insert(id);
-            // Stage per-doc metadata for atomic Finalize
-            let tracking_hash = meta.tracking_hash;
-            let token_len = meta.token_length;
-            self.staged_meta.insert(
-                id,
-                DocMeta {
-                    token_length: token_len,
-                    tracking_hash,
-                    namespace: meta.namespace,
-                },
-            );
+            // Stages per-doc metadata for atomic Finalize
+            self.upsert_doc(id, meta, &FieldedDoc::from_snippet(name, &snippet));
         }
         inserted
     }
@@ -617,42 +738,45 @@ impl Bm25Indexer {
             name,
         } in batch
         {
-            let combined = format!("{} {} {}", name, name, snippet);
-            let embedding = self.embedder.embed(&combined);
-            self.scorer.upsert(&id, embedding);
-            self.docs.insert(id);
-            // Stage per-doc metadata for atomic Finalize and avgdl computation
-            self.staged_meta.insert(id, meta);
+            // Stages per-doc metadata for atomic Finalize and avgdl computation
+            self.upsert_doc(id, meta, &FieldedDoc::from_snippet(name, &snippet));
             inserted += 1;
         }
         inserted
     }
 
-    /// Index a batch of (uuid, snippet) pairs.
+    /// Index a batch of (uuid, snippet) pairs, persisting their metadata and sparse vectors so
+    /// the index can be restored with [`Self::restore_from_db`]. The kind and module path of each
+    /// document are looked up in the database for `kind:` and `path:` filters.
     /// Returns the number of items indexed.
     pub fn upsert_batch_with_cozo(
         &mut self,
         cozo: &Database,
         batch: impl IntoIterator<Item = DocData>,
     ) -> Result<usize, DbError> {
-        let mut inserted = 0;
-        let new_iter = batch.into_iter().map(|d| {
-            let DocData {
-                id,
-                meta,
-                snippet,
-                name,
-            } = d;
-            let combined = format!("{} {} {}", name, name, snippet);
-            let embedding = self.embedder.embed(&combined);
-            self.scorer.upsert(&id, embedding);
+        let batch: Vec<DocData> = batch.into_iter().collect();
+        let ids: Vec<Uuid> = batch.iter().map(|doc| doc.id).collect();
+        let mut locations = collect_doc_locations(cozo, &ids)?;
+        let mut metas = Vec::new();
+        let mut postings = Vec::new();
+        for DocData {
+            id,
+            meta,
+            snippet,
+            name,
+        } in batch
+        {
+            let doc = locations
+                .remove(&id)
+                .unwrap_or_default()
+                .apply(FieldedDoc::from_snippet(name, &snippet));
             // Stage per-doc metadata (keeps parity with index_batch and supports avgdl/finalize)
-            self.staged_meta.insert(id, meta);
-            self.docs.insert(id);
-            inserted += 1;
-            (id, meta)
-        });
-        cozo.upsert_bm25_doc_meta_batch(new_iter)?;
+            postings.push(self.upsert_doc(id, meta, &doc));
+            metas.push((id, meta));
+        }
+        let inserted = metas.len();
+        cozo.upsert_bm25_doc_meta_batch(metas)?;
+        cozo.upsert_bm25_postings_batch(self.avgdl, postings)?;
         Ok(inserted)
     }
 
@@ -663,30 +787,115 @@ impl Bm25Indexer {
         self.staged_meta.remove(id);
     }
 
-    /// Search with a query string, returning top-k results as ScoredDocument<Uuid>
+    /// Remove documents by id from the index and from its persisted state.
+    pub fn remove_with_cozo(&mut self, cozo: &Database, ids: &[Uuid]) -> Result<(), DbError> {
+        for id in ids {
+            self.remove(id);
+        }
+        cozo.retract_bm25_docs(ids)
+    }
+
+    /// Search with a query string in the [`Bm25Query`] syntax, returning top-k results as
+    /// ScoredDocument<Uuid>
     pub fn search(
         &self,
         query: &str,
         top_k: usize,
         scope: RetrievalScope,
     ) -> Vec<bm25::ScoredDocument<Uuid>> {
-        let qemb = self.embedder.embed(query);
-        tracing::debug!("qemb: {qemb:?}");
-        let mut matches = self.scorer.matches(&qemb);
-        tracing::trace!("matches: {matches:?}");
-        if let Some(namespace) = scope.namespace_filter() {
-            matches.retain(|doc| {
-                self.staged_meta
-                    .get(&doc.id)
-                    .is_some_and(|meta| meta.namespace == namespace)
-            });
+        self.search_query(&Bm25Query::parse(query), top_k, scope)
+    }
+
+    /// Search with a parsed query. Each field is scored on its own and weighed with the query's
+    /// boosts; phrases, `kind:` and `path:` filters then drop documents that do not match them.
+    /// A query with only filters gives every matching document a score of 1.0.
+    pub fn search_query(
+        &self,
+        query: &Bm25Query,
+        top_k: usize,
+        scope: RetrievalScope,
+    ) -> Vec<bm25::ScoredDocument<Uuid>> {
+        tracing::debug!("query: {query:?}");
+        let mut scores: HashMap<Uuid, f32> = HashMap::new();
+        let mut add_scores = |ids: Vec<u32>, boost: f32| {
+            if ids.is_empty() || boost <= 0.0 {
+                return;
+            }
+            for doc in self.scorer.matches(&query_embedding(ids)) {
+                *scores.entry(doc.id).or_default() += boost * doc.score;
+            }
+        };
+        for field in Bm25Field::ALL {
+            let ids = query
+                .terms
+                .iter()
+                .map(|term| field_token_id(field, term))
+                .collect();
+            add_scores(ids, query.boosts.get(field));
+        }
+        let prefix_ids = query.prefixes.iter().map(|p| prefix_token_id(p)).collect();
+        add_scores(prefix_ids, query.boosts.prefix);
+
+        let mut required: Vec<HashSet<Uuid>> = Vec::new();
+        if !query.kinds.is_empty() {
+            required.push(self.matching_any(query.kinds.iter().map(|kind| kind_token_id(kind))));
         }
+        if !query.paths.is_empty() {
+            required
+                .push(self.matching_any(query.paths.iter().map(|path| path_filter_token_id(path))));
+        }
+        for phrase in &query.phrases {
+            required.push(self.matching_phrase(phrase));
+        }
+        if query.terms.is_empty()
+            && query.prefixes.is_empty()
+            && let Some(first) = required.first()
+        {
+            scores = first.iter().map(|id| (*id, 1.0)).collect();
+        }
+
+        let namespace = scope.namespace_filter();
+        let mut matches: Vec<bm25::ScoredDocument<Uuid>> = scores
+            .into_iter()
+            .filter(|(id, _)| required.iter().all(|ids| ids.contains(id)))
+            .filter(|(id, _)| {
+                namespace.is_none_or(|namespace| {
+                    self.staged_meta
+                        .get(id)
+                        .is_some_and(|meta| meta.namespace == namespace)
+                })
+            })
+            .map(|(id, score)| bm25::ScoredDocument { id, score })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        tracing::trace!("matches: {matches:?}");
         if matches.len() > top_k {
             matches.truncate(top_k);
         }
         matches
     }
 
+    /// Documents holding any of `ids`.
+    fn matching_any(&self, ids: impl IntoIterator<Item = u32>) -> HashSet<Uuid> {
+        self.scorer
+            .matches(&query_embedding(ids))
+            .into_iter()
+            .map(|doc| doc.id)
+            .collect()
+    }
+
+    /// Documents holding the tokens of `phrase` next to each other in one field.
+    fn matching_phrase(&self, phrase: &[String]) -> HashSet<Uuid> {
+        if let [token] = phrase {
+            return self.matching_any(Bm25Field::ALL.map(|field| field_token_id(field, token)));
+        }
+        let mut bigrams = phrase
+            .windows(2)
+            .map(|pair| self.matching_any([bigram_token_id(&pair[0], &pair[1])]));
+        let first = bigrams.next().unwrap_or_default();
+        bigrams.fold(first, |acc, ids| &acc & &ids)
+    }
+
     /// Compute average document length (avgdl) from staged metadata.
     /// Returns 0.0 if no documents are staged.
     pub fn compute_avgdl_from_staged(&self) -> f32 {
@@ -703,6 +912,11 @@ impl Bm25Indexer {
         self.staged_meta.drain().collect()
     }
 
+    /// Average document length the index embeds documents and queries with.
+    pub fn avgdl(&self) -> f32 {
+        self.avgdl
+    }
+
     /// Number of documents currently known to the in-memory scorer.
     pub fn doc_count(&self) -> usize {
         self.docs.len()
@@ -788,6 +1002,80 @@ is_root_module[id] := *module{{id @ 'NOW'}}, *file_mod{{owner_id: id @ 'NOW'}}
     Ok(out)
 }
 
+/// Kind and module path of an indexed node, looked up for `kind:` and `path:` filters.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DocLocation {
+    kind: Option<&'static str>,
+    module_path: Vec<String>,
+}
+
+impl DocLocation {
+    fn apply(self, doc: FieldedDoc) -> FieldedDoc {
+        let doc = doc.with_module_path(self.module_path);
+        match self.kind {
+            Some(kind) => doc.with_kind(kind),
+            None => doc,
+        }
+    }
+}
+
+/// Look up the relation and the path of the nearest enclosing module of each node in `ids`.
+/// Nodes missing from the database are left out.
+pub(crate) fn collect_doc_locations(
+    db: &Database,
+    ids: &[Uuid],
+) -> Result<HashMap<Uuid, DocLocation>, DbError> {
+    let mut out: HashMap<Uuid, DocLocation> = HashMap::new();
+    if ids.is_empty() {
+        return Ok(out);
+    }
+    let ids_param = || {
+        BTreeMap::from([(
+            "ids".to_string(),
+            DataValue::List(
+                ids.iter()
+                    .map(|id| DataValue::List(vec![DataValue::Uuid(UuidWrapper(*id))]))
+                    .collect(),
+            ),
+        )])
+    };
+
+    for node in NodeType::primary_and_assoc_nodes() {
+        let script = format!(
+            "ids[id] <- $ids\n?[id] := ids[id], *{rel}{{ id @ 'NOW' }}",
+            rel = node.relation_str()
+        );
+        for row in db.raw_query_params(&script, ids_param())?.rows {
+            if let DataValue::Uuid(UuidWrapper(id)) = &row[0] {
+                out.entry(*id).or_default().kind = Some(node.relation_str());
+            }
+        }
+    }
+
+    // Walk up Contains edges (and impl/trait membership, for methods) from the given ids only.
+    let script = r#"
+parent_of[child, parent] := *syntax_edge{source_id: parent, target_id: child, relation_kind: "Contains" @ 'NOW'}
+parent_of[child, parent] := *syntax_edge{source_id: parent, target_id: child, relation_kind: "ImplAssociatedItem" @ 'NOW'}
+parent_of[child, parent] := *syntax_edge{source_id: parent, target_id: child, relation_kind: "TraitAssociatedItem" @ 'NOW'}
+ids[id] <- $ids
+up[id, asc] := ids[id], parent_of[id, asc]
+up[id, asc] := up[id, mid], parent_of[mid, asc]
+
+?[id, path] := up[id, mod_id], *module{ id: mod_id, path @ 'NOW' }
+"#;
+    for row in db.raw_query_params(script, ids_param())?.rows {
+        let DataValue::Uuid(UuidWrapper(id)) = &row[0] else {
+            continue;
+        };
+        let path = to_string_list(&row[1])?;
+        let location = out.entry(*id).or_default();
+        if path.len() > location.module_path.len() {
+            location.module_path = path;
+        }
+    }
+    Ok(out)
+}
+
 // ------------------------- Tests -------------------------
 
 // ------------------------- Tests -------------------------
@@ -914,7 +1202,7 @@ fn compute_answer() -> i32 { 42 }",
         ];
         idx.index_batch(docs);
 
-        let qemb = idx.embedder.embed("compute");
+        let qemb = query_embedding([field_token_id(Bm25Field::Body, "compute")]);
         let score_a = idx.scorer.score(&id_a, &qemb).unwrap_or(0.0);
         let score_b = idx.scorer.score(&id_b, &qemb).unwrap_or(0.0);
         assert!(
@@ -956,6 +1244,267 @@ fn hello() { println!(\"hi\"); }",
         assert_eq!(staged.tracking_hash, meta.tracking_hash);
     }
 
+    fn doc_data(name: &str, snippet: &str, namespace: Uuid) -> DocData {
+        DocData {
+            id: Uuid::new_v4(),
+            meta: DocMeta {
+                token_length: CodeTokenizer::count_tokens_in_code(snippet),
+                tracking_hash: TrackingHash(Uuid::new_v5(&Uuid::NAMESPACE_DNS, snippet.as_bytes())),
+                namespace,
+            },
+            snippet: snippet.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn restore_from_db_matches_indexed_search_results() {
+        let db = Database::init_with_schema().expect("init db");
+        let namespace = Uuid::new_v4();
+        let docs = vec![
+            doc_data("parse_header", "fn parse_header(buf: &[u8]) {}", namespace),
+            doc_data(
+                "write_header",
+                "fn write_header(out: &mut Vec<u8>) {}",
+                namespace,
+            ),
+            doc_data(
+                "checksum",
+                "fn checksum(buf: &[u8]) -> u32 { 0 }",
+                namespace,
+            ),
+        ];
+        let total_len: usize = docs.iter().map(|doc| doc.meta.token_length).sum();
+        let avgdl = (total_len as f64 / docs.len() as f64) as f32;
+        let mut idx = Bm25Indexer::new(avgdl);
+        idx.upsert_batch_with_cozo(&db, docs)
+            .expect("upsert to cozo");
+
+        let restored = Bm25Indexer::restore_from_db(&db)
+            .expect("restore")
+            .expect("persisted index");
+        assert_eq!(restored.doc_count(), 3);
+        assert_eq!(restored.avgdl(), avgdl);
+        for query in ["header", "parse buf", "checksum"] {
+            let scope = RetrievalScope::LoadedWorkspace;
+            let expected: Vec<(Uuid, f32)> = idx
+                .search(query, 5, scope)
+                .into_iter()
+                .map(|d| (d.id, d.score))
+                .collect();
+            let got: Vec<(Uuid, f32)> = restored
+                .search(query, 5, scope)
+                .into_iter()
+                .map(|d| (d.id, d.score))
+                .collect();
+            assert_eq!(got, expected, "query {query:?}");
+        }
+    }
+
+    #[test]
+    fn restore_from_db_drops_removed_docs() {
+        let db = Database::init_with_schema().expect("init db");
+        assert!(
+            Bm25Indexer::restore_from_db(&db)
+                .expect("restore")
+                .is_none()
+        );
+
+        let namespace = Uuid::new_v4();
+        let kept = doc_data("kept_fn", "fn kept_fn() {}", namespace);
+        let removed = doc_data("removed_fn", "fn removed_fn() {}", namespace);
+        let (kept_id, removed_id) = (kept.id, removed.id);
+        let mut idx = Bm25Indexer::new(4.0);
+        idx.upsert_batch_with_cozo(&db, vec![kept, removed])
+            .expect("upsert to cozo");
+        idx.remove_with_cozo(&db, &[removed_id])
+            .expect("remove from cozo");
+
+        let restored = Bm25Indexer::restore_from_db(&db)
+            .expect("restore")
+            .expect("persisted index");
+        assert_eq!(restored.doc_count(), 1);
+        let hits = restored.search("removed_fn", 5, RetrievalScope::LoadedWorkspace);
+        assert!(hits.iter().all(|d| d.id != removed_id));
+        let hits = restored.search("kept_fn", 5, RetrievalScope::LoadedWorkspace);
+        assert_eq!(hits.first().map(|d| d.id), Some(kept_id));
+    }
+
+    #[test]
+    fn restore_from_db_weights_namespaces_by_document_count() {
+        let db = Database::init_with_schema().expect("init db");
+        let (small, large) = (Uuid::new_v4(), Uuid::new_v4());
+        let docs = vec![
+            doc_data("a", "fn a() {}", small),
+            doc_data(
+                "parse_header",
+                "fn parse_header(buf: &[u8], len: usize) -> Header { todo!() }",
+                large,
+            ),
+            doc_data(
+                "write_header",
+                "fn write_header(out: &mut Vec<u8>, header: &Header) {}",
+                large,
+            ),
+            doc_data("checksum", "fn checksum(buf: &[u8]) -> u32 { 0 }", large),
+        ];
+        let total_len: usize = docs.iter().map(|doc| doc.meta.token_length).sum();
+        // Indexed in separate sessions, with the avgdl fitted to each namespace.
+        let (small_docs, large_docs): (Vec<_>, Vec<_>) = docs
+            .into_iter()
+            .partition(|doc| doc.meta.namespace == small);
+        for batch in [small_docs, large_docs] {
+            let len: usize = batch.iter().map(|doc| doc.meta.token_length).sum();
+            let mut idx = Bm25Indexer::new(len as f32 / batch.len() as f32);
+            idx.upsert_batch_with_cozo(&db, batch)
+                .expect("upsert to cozo");
+        }
+
+        let stats = db.bm25_index_stats().expect("index stats");
+        let counts: HashMap<Uuid, usize> =
+            stats.iter().map(|s| (s.namespace, s.doc_count)).collect();
+        assert_eq!(counts, HashMap::from([(small, 1), (large, 3)]));
+        let restored = Bm25Indexer::restore_from_db(&db)
+            .expect("restore")
+            .expect("persisted index");
+        assert_eq!(restored.avgdl(), (total_len as f64 / 4.0) as f32);
+    }
+
+    fn upsert_fielded(idx: &mut Bm25Indexer, doc: FieldedDoc) -> Uuid {
+        let id = Uuid::new_v4();
+        let meta = DocMeta {
+            token_length: CodeTokenizer::count_tokens_in_code(&doc.body),
+            tracking_hash: TrackingHash(id),
+            namespace: Uuid::nil(),
+        };
+        idx.upsert_doc(id, meta, &doc);
+        id
+    }
+
+    fn hit_ids(hits: Vec<bm25::ScoredDocument<Uuid>>) -> Vec<Uuid> {
+        hits.into_iter().map(|d| d.id).collect()
+    }
+
+    #[test]
+    fn search_boosts_name_matches_over_body_matches() {
+        let mut idx = Bm25Indexer::new(10.0);
+        let in_name = upsert_fielded(
+            &mut idx,
+            FieldedDoc::from_snippet("checksum", "fn checksum(buf: &[u8]) -> u32 { 0 }"),
+        );
+        let in_body = upsert_fielded(
+            &mut idx,
+            FieldedDoc::from_snippet(
+                "verify",
+                "fn verify(buf: &[u8]) -> bool { checksum(buf) == 0 }",
+            ),
+        );
+        let scope = RetrievalScope::LoadedWorkspace;
+        assert_eq!(
+            hit_ids(idx.search("checksum", 5, scope)),
+            vec![in_name, in_body]
+        );
+
+        let body_only = Bm25Query::parse("checksum").with_boosts(FieldBoosts {
+            name: 0.0,
+            signature: 0.0,
+            ..FieldBoosts::default()
+        });
+        assert_eq!(
+            hit_ids(idx.search_query(&body_only, 5, scope)),
+            vec![in_body]
+        );
+    }
+
+    #[test]
+    fn search_matches_phrases_and_name_prefixes() {
+        let mut idx = Bm25Indexer::new(10.0);
+        let adjacent = upsert_fielded(
+            &mut idx,
+            FieldedDoc::from_snippet(
+                "advance",
+                "/// Returns the next token of the stream.\nfn advance() {}",
+            ),
+        );
+        let apart = upsert_fielded(
+            &mut idx,
+            FieldedDoc::from_snippet("peek", "/// Returns the token after next.\nfn peek() {}"),
+        );
+        let scope = RetrievalScope::LoadedWorkspace;
+        assert_eq!(idx.search("next token", 5, scope).len(), 2);
+        assert_eq!(
+            hit_ids(idx.search("\"next token\"", 5, scope)),
+            vec![adjacent]
+        );
+
+        let header = upsert_fielded(&mut idx, FieldedDoc::named("parse_header"));
+        let _ = upsert_fielded(&mut idx, FieldedDoc::named("write_header"));
+        assert_eq!(hit_ids(idx.search("pars*", 5, scope)), vec![header]);
+        assert_eq!(hit_ids(idx.search("parseH*", 5, scope)), vec![header]);
+        assert_eq!(idx.search("head*", 5, scope).len(), 2);
+        assert_eq!(hit_ids(idx.search("pee*", 5, scope)), vec![apart]);
+    }
+
+    #[test]
+    fn search_applies_kind_and_path_filters() {
+        let mut idx = Bm25Indexer::new(10.0);
+        let path = |module: &str| vec!["crate".to_string(), module.to_string()];
+        let parser_fn = upsert_fielded(
+            &mut idx,
+            FieldedDoc::named("parse")
+                .with_kind("function")
+                .with_module_path(path("parser")),
+        );
+        let parser_struct = upsert_fielded(
+            &mut idx,
+            FieldedDoc::named("Parser")
+                .with_kind("struct")
+                .with_module_path(path("parser")),
+        );
+        let util_fn = upsert_fielded(
+            &mut idx,
+            FieldedDoc::named("parse")
+                .with_kind("function")
+                .with_module_path(path("util")),
+        );
+        let scope = RetrievalScope::LoadedWorkspace;
+
+        assert_eq!(
+            hit_ids(idx.search("parse kind:fn path:crate::parser", 5, scope)),
+            vec![parser_fn]
+        );
+        let mut functions = hit_ids(idx.search("kind:fn", 5, scope));
+        functions.sort();
+        let mut expected = vec![parser_fn, util_fn];
+        expected.sort();
+        assert_eq!(functions, expected);
+        assert_eq!(
+            hit_ids(idx.search("path:crate::parser::Parser", 5, scope)),
+            vec![parser_struct]
+        );
+        assert!(idx.search("kind:enum", 5, scope).is_empty());
+    }
+
+    #[test]
+    fn collect_doc_locations_finds_kinds_and_module_paths() {
+        let db = TEST_DB_NODES.as_ref().expect("test db init").clone();
+        let ids: Vec<Uuid> = collect_rebuild_sources(db.as_ref())
+            .expect("collect names")
+            .into_iter()
+            .map(|(id, ..)| id)
+            .collect();
+        let locations = collect_doc_locations(db.as_ref(), &ids).expect("collect locations");
+        assert_eq!(locations.len(), ids.len());
+        for location in locations.values() {
+            assert!(location.kind.is_some(), "missing kind: {location:?}");
+            assert_eq!(
+                location.module_path.first().map(String::as_str),
+                Some("crate"),
+                "unexpected module path: {location:?}"
+            );
+        }
+    }
+
     #[test]
     fn new_from_corpus_consumes_vec_and_indexes() {
         let id1 = Uuid::new_v4();
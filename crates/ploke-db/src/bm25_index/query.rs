@@ -0,0 +1,120 @@
+//! Query syntax of the structured BM25 search.
+//!
+//! A query is a whitespace-separated list of:
+//! - free terms, tokenized like code and scored against every field,
+//! - `"quoted phrases"`, whose tokens must appear next to each other in one field,
+//! - `pars*` prefixes, matched against the subtokens and the whole of item names,
+//! - `kind:fn` filters on the item kind (`fn`, `mod` and `type` are accepted as aliases),
+//! - `path:crate::foo` filters keeping items in that module or with that path.
+//!
+//! Several `kind:` (or `path:`) filters keep items matching any of them.
+
+use bm25::Tokenizer;
+
+use super::{CodeTokenizer, fields::FieldBoosts};
+
+/// A parsed BM25 query, see the [module docs](self) for the syntax.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bm25Query {
+    /// Tokens of the free terms and phrases.
+    pub terms: Vec<String>,
+    /// Lowercased name prefixes.
+    pub prefixes: Vec<String>,
+    /// Tokens of each phrase.
+    pub phrases: Vec<Vec<String>>,
+    /// Node relations an item must have one of, e.g. `function`.
+    pub kinds: Vec<String>,
+    /// Paths an item must be in or have one of, e.g. `crate::parser`.
+    pub paths: Vec<String>,
+    pub boosts: FieldBoosts,
+}
+
+impl Bm25Query {
+    pub fn parse(input: &str) -> Self {
+        let tokenizer = CodeTokenizer;
+        let mut query = Self::default();
+        let mut rest = input.trim_start();
+        while !rest.is_empty() {
+            if let Some(quoted) = rest.strip_prefix('"') {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                let phrase = tokenizer.tokenize(&quoted[..end]);
+                if !phrase.is_empty() {
+                    query.terms.extend(phrase.iter().cloned());
+                    query.phrases.push(phrase);
+                }
+                rest = quoted.get(end + 1..).unwrap_or_default().trim_start();
+                continue;
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let (word, tail) = rest.split_at(end);
+            rest = tail.trim_start();
+
+            if let Some(kind) = word.strip_prefix("kind:") {
+                if !kind.is_empty() {
+                    query
+                        .kinds
+                        .push(kind_alias(&kind.to_lowercase()).to_string());
+                }
+            } else if let Some(path) = word.strip_prefix("path:") {
+                let path = path.trim_end_matches("::");
+                if !path.is_empty() {
+                    query.paths.push(path.to_string());
+                }
+            } else if let Some(prefix) = word.strip_suffix('*') {
+                if let Some(prefix) = super::fields::normalize_prefix(prefix) {
+                    query.prefixes.push(prefix);
+                }
+            } else {
+                query.terms.extend(tokenizer.tokenize(word));
+            }
+        }
+        query
+    }
+
+    pub fn with_boosts(mut self, boosts: FieldBoosts) -> Self {
+        self.boosts = boosts;
+        self
+    }
+
+    /// True if the query neither scores nor filters anything.
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+            && self.prefixes.is_empty()
+            && self.kinds.is_empty()
+            && self.paths.is_empty()
+    }
+}
+
+/// Maps the Rust keyword of a kind to the relation it is indexed under.
+fn kind_alias(kind: &str) -> &str {
+    match kind {
+        "fn" => "function",
+        "mod" => "module",
+        "type" => "type_alias",
+        "macro_rules" => "macro",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_splits_terms_phrases_prefixes_and_filters() {
+        let query = Bm25Query::parse(r#"kind:fn path:crate::parser:: "next token" lexHead* span"#);
+        assert_eq!(query.kinds, vec!["function"]);
+        assert_eq!(query.paths, vec!["crate::parser"]);
+        assert_eq!(query.phrases, vec![vec!["next", "token"]]);
+        assert_eq!(query.prefixes, vec!["lexhead"]);
+        assert_eq!(query.terms, vec!["next", "token", "span"]);
+    }
+
+    #[test]
+    fn parse_tolerates_unclosed_quotes_and_short_prefixes() {
+        let query = Bm25Query::parse(r#"p* "parse_header"#);
+        assert!(query.prefixes.is_empty());
+        assert_eq!(query.phrases, vec![vec!["parse", "header"]]);
+        assert!(Bm25Query::parse("   ").is_empty());
+    }
+}
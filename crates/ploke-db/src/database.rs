@@ -4,18 +4,23 @@ use std::{ops::Deref, panic::Location, path::Path};
 
 use crate::NodeType;
 use crate::QueryResult;
-use crate::bm25_index::{DocMeta, TOKENIZER_VERSION};
+use crate::bm25_index::{Bm25IndexStats, Bm25Posting, DocMeta, TOKENIZER_VERSION};
 use crate::error::DbError;
 use crate::multi_embedding::db_ext::EmbeddingExt;
 use crate::multi_embedding::hnsw_ext::HnswExt;
 use crate::multi_embedding::schema::{EmbeddingSetExt as _, EmbeddingVector};
-use cozo::{DataValue, Db, MemStorage, NamedRows, UuidWrapper, Vector};
+use cozo::{DataValue, Db, MemStorage, NamedRows, SqliteStorage, UuidWrapper, Vector};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use ploke_core::{EmbeddingData, FileData, TrackingHash};
 use ploke_error::Error as PlokeError;
+use ploke_transform::schema::CozoStorage;
 use ploke_transform::schema::assoc_nodes::MethodNodeSchema;
+use ploke_transform::schema::edges::{
+    CallSiteSchema, DerivedImplSchema, MacroExpansionSchema, TestSchema, TestTargetSchema,
+};
 use ploke_transform::schema::meta::Bm25MetaSchema;
+use ploke_transform::transform::{GraphSnapshot, SnapshotRun, SnapshotTag, WriteBatch};
 use serde::{Deserialize, Serialize};
 use syn_parser::parser::nodes::{AnyNodeId, ToCozoUuid};
 use tracing::{debug, info, instrument, trace, warn};
@@ -42,6 +47,30 @@ lazy_static! {
 
 pub const HNSW_SUFFIX: &str = ":hnsw_idx";
 pub const ACTIVE_EMBEDDING_SET_REL: &str = "active_embedding_set";
+pub const CALL_SITE_REL: &str = "call_site";
+pub const MACRO_EXPANSION_REL: &str = "macro_expansion";
+pub const TEST_REL: &str = "test";
+pub const TEST_TARGET_REL: &str = "test_target";
+pub const DERIVED_IMPL_REL: &str = "derived_impl";
+/// Relations keeping per-site parse details. Backups and namespace exports taken before a
+/// relation was added do not contain it, so each one is imported on its own.
+pub const PARSE_DETAIL_RELS: [&str; 5] = [
+    CALL_SITE_REL,
+    MACRO_EXPANSION_REL,
+    TEST_REL,
+    TEST_TARGET_REL,
+    DERIVED_IMPL_REL,
+];
+pub const BM25_POSTINGS_REL: &str = "bm25_doc_postings";
+pub const BM25_INDEX_STATS_REL: &str = "bm25_index_stats";
+/// Relations persisting the BM25 index. Backups taken before they were added do not contain them,
+/// so each one is imported on its own.
+pub const BM25_INDEX_RELS: [&str; 2] = [BM25_POSTINGS_REL, BM25_INDEX_STATS_REL];
+/// Marks the read-only namespaces holding the public API of indexed dependencies. Only created
+/// once a dependency is indexed.
+pub const DEPENDENCY_NAMESPACE_REL: &str = "dependency_namespace";
+/// Names the recorded snapshots of the code graph. Only created once a snapshot is recorded.
+pub const GRAPH_SNAPSHOT_REL: &str = "graph_snapshot";
 
 /// Reason an embedding set was chosen during restore.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -52,7 +81,12 @@ pub enum RestoredEmbeddingSet {
     FirstPopulated,
 }
 
-/// Main database connection and query interface
+/// Main database connection and query interface.
+///
+/// Generic over the Cozo storage engine: the default [`MemStorage`] keeps the whole graph in
+/// memory, while a [`PersistentDatabase`] reads and writes an on-disk SQLite store, see
+/// [`Database::open_persistent`]. The query, namespace and embedding-set APIs work the same on
+/// both.
 // TODO:refactor:database improve state handling for active_embedding_set
 // JL, 2025-12-06
 // - [ ] Change the active_embedding_set to an option, and when we add ways to remove embeddings,
@@ -60,12 +94,23 @@ pub enum RestoredEmbeddingSet {
 // - [ ] Add an error type for when an embedding set is not found
 // - [ ] Add convenience methods to map the option onto an error when we expect the
 // active_embedding_set to be Some but find None, so we can ergonomically handle unwraps.
-#[derive(Debug)]
-pub struct Database {
-    db: Db<MemStorage>,
+pub struct Database<S = MemStorage> {
+    db: Db<S>,
     pub active_embedding_set: Arc<RwLock<EmbeddingSet>>,
 }
 
+/// A [`Database`] backed by an on-disk SQLite store. Cozo backups written by
+/// [`Database::write_backup_to_path`] are SQLite stores, so they can be opened as one directly.
+pub type PersistentDatabase = Database<SqliteStorage>;
+
+impl<S> std::fmt::Debug for Database<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Database")
+            .field("active_embedding_set", &self.active_embedding_set)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct QueryContext {
     pub name: &'static str,
@@ -92,6 +137,47 @@ pub struct CrateContextRow {
     pub root_path: String,
 }
 
+/// A row of [`DEPENDENCY_NAMESPACE_REL`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyNamespaceRow {
+    pub namespace: Uuid,
+    pub name: String,
+    pub version: String,
+    /// The `Cargo.lock` source of the dependency.
+    pub source: String,
+    /// Where the sources were found, `"registry"` or `"vendor"`.
+    pub origin: String,
+    pub root_path: String,
+}
+
+/// A node of the code graph, as compared by [`Database::diff_graph_snapshots`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotItem {
+    pub id: Uuid,
+    pub name: String,
+    /// The relation holding the node, e.g. `"function"`.
+    pub relation: String,
+    pub vis_kind: String,
+    pub tracking_hash: Uuid,
+}
+
+/// The primary and associated nodes that differ between two snapshots of the code graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphSnapshotDiff {
+    /// Nodes present in the later snapshot only.
+    pub added: Vec<SnapshotItem>,
+    /// Nodes present in the earlier snapshot only.
+    pub removed: Vec<SnapshotItem>,
+    /// Nodes whose tracking hash or visibility changed, as of the later snapshot.
+    pub changed: Vec<SnapshotItem>,
+}
+
+impl GraphSnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NamespaceInventory {
     pub crate_context: CrateContextRow,
@@ -127,6 +213,10 @@ pub struct NamespaceExportArtifact {
     pub descendant_ids: BTreeSet<Uuid>,
     pub workspace_members: Vec<String>,
     pub relation_exports: Vec<RelationExportRows>,
+    /// Validity timestamp, in microseconds, at which the loaded version of each file was
+    /// written, keyed by file path. [`Database::merge_namespace`] keeps the newer version of a
+    /// file that differs on both sides.
+    pub file_timestamps: BTreeMap<String, i64>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -161,8 +251,280 @@ impl From<DbError> for NamespaceImportError {
     }
 }
 
-impl std::ops::Deref for Database {
-    type Target = Db<MemStorage>;
+/// Outcome of [`Database::merge_namespace`]. File lists hold file paths and are sorted.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NamespaceMergeResult {
+    pub merged_namespace: Uuid,
+    pub added_files: Vec<String>,
+    pub updated_files: Vec<String>,
+    pub removed_files: Vec<String>,
+    /// Files whose current version was kept over a different incoming one: local changes since
+    /// the base, files whose current version is newer, or, without a base, files missing from
+    /// the incoming artifact.
+    pub kept_files: Vec<String>,
+    /// Files changed on both sides since the base. The newer version wins, so each of them is
+    /// also listed as kept, or as added, updated or removed.
+    pub conflicted_files: Vec<String>,
+    pub imported_node_ids: BTreeSet<Uuid>,
+    pub retracted_node_ids: BTreeSet<Uuid>,
+    /// Replaced nodes whose tracking hash did not change, so their embeddings were kept.
+    pub preserved_embedding_ids: BTreeSet<Uuid>,
+    /// Imported edges joining an imported node to a node the merge kept.
+    pub relinked_edges: usize,
+    /// Imported edges dropped because one of their ends is no longer in the namespace.
+    pub dropped_edges: usize,
+    pub hnsw_invalidated: bool,
+    pub bm25_invalidated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NamespaceFileChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Structured difference between two exports of a namespace, see
+/// [`NamespaceExportArtifact::diff`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NamespaceArtifactDiff {
+    pub crate_context_changed: bool,
+    /// Changed files by path. A file is modified when the tracking hash of its module changed.
+    pub files: BTreeMap<String, NamespaceFileChange>,
+    pub added_nodes: BTreeSet<Uuid>,
+    pub removed_nodes: BTreeSet<Uuid>,
+    /// Nodes present in both artifacts with a different tracking hash.
+    pub changed_nodes: BTreeSet<Uuid>,
+    /// `(source_id, target_id, relation_kind)` of the syntax edges only in the newer artifact.
+    pub added_edges: BTreeSet<(Uuid, Uuid, String)>,
+    pub removed_edges: BTreeSet<(Uuid, Uuid, String)>,
+}
+
+impl NamespaceArtifactDiff {
+    pub fn is_empty(&self) -> bool {
+        !self.crate_context_changed
+            && self.files.is_empty()
+            && self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.changed_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+    }
+}
+
+impl NamespaceExportArtifact {
+    /// Compares this artifact with a `newer` export of the same namespace.
+    pub fn diff(&self, newer: &Self) -> NamespaceArtifactDiff {
+        let old = NamespaceArtifactIndex::new(self);
+        let new = NamespaceArtifactIndex::new(newer);
+
+        let old_files = old.file_states();
+        let new_files = new.file_states();
+        let mut files = BTreeMap::new();
+        for path in old_files.keys().chain(new_files.keys()) {
+            let change = match (old_files.get(path), new_files.get(path)) {
+                (None, Some(_)) => NamespaceFileChange::Added,
+                (Some(_), None) => NamespaceFileChange::Removed,
+                (Some((_, old_hash)), Some((_, new_hash))) if old_hash != new_hash => {
+                    NamespaceFileChange::Modified
+                }
+                _ => continue,
+            };
+            files.insert(path.clone(), change);
+        }
+
+        let mut diff = NamespaceArtifactDiff {
+            crate_context_changed: self.crate_context != newer.crate_context,
+            files,
+            added_edges: new.edges.difference(&old.edges).cloned().collect(),
+            removed_edges: old.edges.difference(&new.edges).cloned().collect(),
+            ..Default::default()
+        };
+        for (id, hash) in &new.node_hashes {
+            match old.node_hashes.get(id) {
+                None => {
+                    diff.added_nodes.insert(*id);
+                }
+                Some(old_hash) if old_hash != hash => {
+                    diff.changed_nodes.insert(*id);
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed_nodes = old
+            .node_hashes
+            .keys()
+            .filter(|id| !new.node_hashes.contains_key(id))
+            .copied()
+            .collect();
+        diff
+    }
+}
+
+/// Relations holding the nodes of a namespace, i.e. everything keyed by a descendant id.
+fn namespace_node_relations() -> Vec<(String, Vec<&'static str>, Vec<&'static str>)> {
+    let mut relations = NodeType::all_variants()
+        .into_iter()
+        .filter(|ty| *ty != NodeType::SyntaxEdge && !ty.is_parse_detail())
+        .map(|ty| {
+            (
+                ty.relation_str().to_string(),
+                ty.keys().collect::<Vec<_>>(),
+                ty.vals().collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    relations.push((
+        "method".to_string(),
+        MethodNodeSchema::SCHEMA.keys().copied().collect(),
+        MethodNodeSchema::SCHEMA.vals().copied().collect(),
+    ));
+    relations.sort_by(|a, b| a.0.cmp(&b.0));
+    relations.dedup_by(|a, b| a.0 == b.0);
+    relations
+}
+
+/// Per-file view of a [`NamespaceExportArtifact`], used to diff and merge artifacts.
+#[derive(Debug, Default)]
+struct NamespaceArtifactIndex {
+    /// File path of each file module, keyed by the module id.
+    file_paths: BTreeMap<Uuid, String>,
+    /// Tracking hash of each node.
+    node_hashes: BTreeMap<Uuid, Option<Uuid>>,
+    /// File module owning each node, test and parse detail row.
+    owners: BTreeMap<Uuid, Uuid>,
+    edges: BTreeSet<(Uuid, Uuid, String)>,
+}
+
+impl RelationExportRows {
+    fn field<'a>(&self, row: &'a [DataValue], field: &str) -> Option<&'a DataValue> {
+        let idx = self
+            .key_fields
+            .iter()
+            .chain(self.val_fields.iter())
+            .position(|name| name == field)?;
+        row.get(idx)
+    }
+
+    fn field_uuid(&self, row: &[DataValue], field: &str) -> Option<Uuid> {
+        self.field(row, field).and_then(|value| to_uuid(value).ok())
+    }
+
+    fn key_uuids<'a>(&'a self, row: &'a [DataValue]) -> impl Iterator<Item = Uuid> + 'a {
+        row.iter()
+            .take(self.key_fields.len())
+            .filter_map(|value| to_uuid(value).ok())
+    }
+}
+
+impl NamespaceArtifactIndex {
+    fn new(artifact: &NamespaceExportArtifact) -> Self {
+        let mut index = Self::default();
+        let export = |relation: &str| {
+            artifact
+                .relation_exports
+                .iter()
+                .find(|rows| rows.relation == relation)
+        };
+
+        if let Some(file_mods) = export("file_mod") {
+            for row in &file_mods.rows {
+                let owner = file_mods.field_uuid(row, "owner_id");
+                let path = file_mods
+                    .field(row, "file_path")
+                    .and_then(|value| to_string(value).ok());
+                if let (Some(owner), Some(path)) = (owner, path) {
+                    index.file_paths.insert(owner, path);
+                    index.owners.insert(owner, owner);
+                }
+            }
+        }
+
+        let mut children: BTreeMap<Uuid, Vec<Uuid>> = BTreeMap::new();
+        if let Some(edges) = export("syntax_edge") {
+            for row in &edges.rows {
+                let source = edges.field_uuid(row, "source_id");
+                let target = edges.field_uuid(row, "target_id");
+                let kind = edges
+                    .field(row, "relation_kind")
+                    .and_then(|value| to_string(value).ok());
+                let (Some(source), Some(target), Some(kind)) = (source, target, kind) else {
+                    continue;
+                };
+                if kind == "Contains" {
+                    children.entry(source).or_default().push(target);
+                }
+                index.edges.insert((source, target, kind));
+            }
+        }
+        let mut queue = index.file_paths.keys().copied().collect::<Vec<_>>();
+        while let Some(parent) = queue.pop() {
+            let owner = index.owners[&parent];
+            for child in children.get(&parent).into_iter().flatten() {
+                if !index.owners.contains_key(child) {
+                    index.owners.insert(*child, owner);
+                    queue.push(*child);
+                }
+            }
+        }
+
+        // Methods are owned through their `owner_id`, tests and parse details through the node
+        // they belong to, and `test_target` rows through their test.
+        let relations = namespace_node_relations()
+            .into_iter()
+            .map(|(relation, _, _)| relation)
+            .chain(PARSE_DETAIL_RELS.iter().map(|rel| rel.to_string()));
+        for relation in relations {
+            let Some(rows) = export(&relation) else {
+                continue;
+            };
+            let is_node = !PARSE_DETAIL_RELS.contains(&relation.as_str());
+            for row in &rows.rows {
+                if is_node && let Some(id) = rows.field_uuid(row, "id") {
+                    index
+                        .node_hashes
+                        .insert(id, rows.field_uuid(row, "tracking_hash"));
+                }
+                let Some(owner) = index.row_owner(rows, row) else {
+                    continue;
+                };
+                for id in rows.key_uuids(row) {
+                    index.owners.entry(id).or_insert(owner);
+                }
+            }
+        }
+        index
+    }
+
+    /// File module owning a row, through the first of its keys with a known owner.
+    fn row_owner(&self, rows: &RelationExportRows, row: &[DataValue]) -> Option<Uuid> {
+        rows.key_uuids(row)
+            .find_map(|id| self.owners.get(&id).copied())
+    }
+
+    /// File module id and module tracking hash of each file, keyed by path.
+    fn file_states(&self) -> BTreeMap<String, (Uuid, Option<Uuid>)> {
+        self.file_paths
+            .iter()
+            .map(|(owner, path)| {
+                let hash = self.node_hashes.get(owner).copied().flatten();
+                (path.clone(), (*owner, hash))
+            })
+            .collect()
+    }
+
+    /// Ids owned by any of the file modules in `files`.
+    fn ids_owned_by(&self, files: &BTreeSet<Uuid>) -> BTreeSet<Uuid> {
+        self.owners
+            .iter()
+            .filter(|(_, owner)| files.contains(owner))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+impl<S> std::ops::Deref for Database<S> {
+    type Target = Db<S>;
 
     fn deref(&self) -> &Self::Target {
         &self.db
@@ -171,13 +533,27 @@ impl std::ops::Deref for Database {
 
 pub trait ImmutQuery {
     fn raw_query(&self, query: &str) -> Result<NamedRows, DbError>;
+    fn raw_query_with_params(
+        &self,
+        query: &str,
+        params: BTreeMap<String, DataValue>,
+    ) -> Result<NamedRows, DbError>;
 }
 
-impl ImmutQuery for Database {
+impl<S: CozoStorage> ImmutQuery for Database<S> {
     fn raw_query(&self, query: &str) -> Result<NamedRows, DbError> {
         self.run_script(query, BTreeMap::new(), cozo::ScriptMutability::Immutable)
             .map_err(DbError::from)
     }
+
+    fn raw_query_with_params(
+        &self,
+        query: &str,
+        params: BTreeMap<String, DataValue>,
+    ) -> Result<NamedRows, DbError> {
+        self.run_script(query, params, cozo::ScriptMutability::Immutable)
+            .map_err(DbError::from)
+    }
 }
 
 /// Safely converts a Cozo DataValue to a Uuid.
@@ -246,6 +622,15 @@ pub fn to_u64(val: &DataValue) -> Result<u64, DbError> {
     }
 }
 
+/// Converts a Cozo DataValue to an i64.
+pub fn to_i64(val: &DataValue) -> Result<i64, DbError> {
+    if let DataValue::Num(cozo::Num::Int(n)) = val {
+        Ok(*n)
+    } else {
+        Err(DbError::Cozo(format!("Expected Integer, found {:?}", val)))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypedEmbedData {
     pub v: Vec<EmbeddingData>,
@@ -269,20 +654,20 @@ pub struct FileInfo {
     file_path: String,
 }
 
-impl Database {
+impl<S: CozoStorage> Database<S> {
     fn uuid_input_rows(ids: &BTreeSet<Uuid>) -> String {
         ids.iter().map(|id| format!("[\"{id}\"]")).join(", ")
     }
 
-    fn retract_relation_rows_by_id(
-        &self,
+    fn push_relation_rows_retraction(
+        batch: &WriteBatch,
         relation: &str,
         key_fields: &[&str],
         val_fields: &[&str],
         ids: &BTreeSet<Uuid>,
-    ) -> Result<(), DbError> {
+    ) {
         if ids.is_empty() {
-            return Ok(());
+            return;
         }
 
         let input_rows = Self::uuid_input_rows(ids);
@@ -312,9 +697,7 @@ target[id] := input[id_str], id = to_uuid(id_str)
 :put {relation} {{ {put_keys}, at => {put_vals} }}
 "#
         );
-        self.run_script(&script, BTreeMap::new(), cozo::ScriptMutability::Mutable)
-            .map(|_| ())
-            .map_err(DbError::from)
+        batch.push(&script, BTreeMap::new());
     }
 
     fn collect_relation_rows_by_id(
@@ -450,6 +833,110 @@ target[node_id] := input[id_str], node_id = to_uuid(id_str)
         )
     }
 
+    /// Extends `ids` with the ids of methods owned by any impl or trait in `ids`.
+    ///
+    /// Methods are not reached through `Contains` edges, but they own call sites and are the
+    /// endpoints of `Calls` edges, so namespace-wide operations need them alongside the
+    /// descendant ids.
+    fn with_owned_method_ids(&self, ids: &BTreeSet<Uuid>) -> Result<BTreeSet<Uuid>, DbError> {
+        let mut expanded = ids.clone();
+        if ids.is_empty() {
+            return Ok(expanded);
+        }
+
+        let input_rows = Self::uuid_input_rows(ids);
+        let script = format!(
+            r#"
+input[id_str] <- [{input_rows}]
+target[id] := input[id_str], id = to_uuid(id_str)
+?[id] := *method {{ id, owner_id @ 'NOW' }}, target[owner_id]
+"#
+        );
+        for row in self.raw_query(&script)?.rows {
+            let id = row
+                .first()
+                .ok_or_else(|| DbError::QueryExecution("missing method id".into()))
+                .and_then(to_uuid)?;
+            expanded.insert(id);
+        }
+        Ok(expanded)
+    }
+
+    /// Key and value fields of each relation in [`PARSE_DETAIL_RELS`].
+    fn parse_detail_fields() -> [(&'static str, Vec<&'static str>, Vec<&'static str>); 5] {
+        let call_site = &CallSiteSchema::SCHEMA;
+        let macro_expansion = &MacroExpansionSchema::SCHEMA;
+        let test = &TestSchema::SCHEMA;
+        let test_target = &TestTargetSchema::SCHEMA;
+        let derived_impl = &DerivedImplSchema::SCHEMA;
+        [
+            (
+                CALL_SITE_REL,
+                call_site.keys().copied().collect(),
+                call_site.vals().copied().collect(),
+            ),
+            (
+                MACRO_EXPANSION_REL,
+                macro_expansion.keys().copied().collect(),
+                macro_expansion.vals().copied().collect(),
+            ),
+            (
+                TEST_REL,
+                test.keys().copied().collect(),
+                test.vals().copied().collect(),
+            ),
+            (
+                TEST_TARGET_REL,
+                test_target.keys().copied().collect(),
+                test_target.vals().copied().collect(),
+            ),
+            (
+                DERIVED_IMPL_REL,
+                derived_impl.keys().copied().collect(),
+                derived_impl.vals().copied().collect(),
+            ),
+        ]
+    }
+
+    /// Extends `ids` with the ids of the tests owned by any node in `ids`, so that the
+    /// `test_target` rows of those tests are matched by their `source_id`.
+    fn with_owned_test_ids(&self, ids: &BTreeSet<Uuid>) -> Result<BTreeSet<Uuid>, DbError> {
+        let mut expanded = ids.clone();
+        if ids.is_empty() {
+            return Ok(expanded);
+        }
+
+        let input_rows = Self::uuid_input_rows(ids);
+        let script = format!(
+            r#"
+input[id_str] <- [{input_rows}]
+target[id] := input[id_str], id = to_uuid(id_str)
+?[id] := *{TEST_REL} {{ id, owner_id @ 'NOW' }}, target[owner_id]
+"#
+        );
+        for row in self.raw_query(&script)?.rows {
+            let id = row
+                .first()
+                .ok_or_else(|| DbError::QueryExecution("missing test id".into()))
+                .and_then(to_uuid)?;
+            expanded.insert(id);
+        }
+        Ok(expanded)
+    }
+
+    fn collect_parse_detail_rows_for_ids(
+        &self,
+        ids: &BTreeSet<Uuid>,
+    ) -> Result<Vec<RelationExportRows>, DbError> {
+        let ids = self.with_owned_test_ids(ids)?;
+        Self::parse_detail_fields()
+            .into_iter()
+            .map(|(relation, key_fields, val_fields)| {
+                self.collect_relation_rows_by_id(relation, &key_fields, &val_fields, &ids)
+            })
+            .collect()
+    }
+
     fn collect_crate_context_rows_for_namespace(
         &self,
         namespace: Uuid,
@@ -476,6 +963,32 @@ target[node_id] := input[id_str], node_id = to_uuid(id_str)
         })
     }
 
+    fn collect_dependency_namespace_rows_for_namespace(
+        &self,
+        namespace: Uuid,
+    ) -> Result<RelationExportRows, DbError> {
+        let namespace_lit = namespace.to_string();
+        let script = format!(
+            r#"
+?[id, name, version, source, origin, root_path] :=
+    *dependency_namespace {{ id, name, version, source, origin, root_path @ 'NOW' }},
+    id = to_uuid("{namespace_lit}")
+"#
+        );
+        Ok(RelationExportRows {
+            relation: DEPENDENCY_NAMESPACE_REL.to_string(),
+            key_fields: vec!["id".to_string()],
+            val_fields: vec![
+                "name".to_string(),
+                "version".to_string(),
+                "source".to_string(),
+                "origin".to_string(),
+                "root_path".to_string(),
+            ],
+            rows: self.raw_query(&script)?.rows,
+        })
+    }
+
     fn collect_file_mod_rows_for_namespace(
         &self,
         namespace: Uuid,
@@ -684,53 +1197,55 @@ target[node_id] := input[id_str], node_id = to_uuid(id_str)
     }
 
     fn put_relation_export_rows(&self, export: &RelationExportRows) -> Result<(), DbError> {
+        let batch = WriteBatch::default();
+        Self::push_relation_export_rows(&batch, export);
+        batch.run(&self.db).map_err(DbError::from)
+    }
+
+    fn push_relation_export_rows(batch: &WriteBatch, export: &RelationExportRows) {
         if export.rows.is_empty() {
-            return Ok(());
+            return;
         }
 
-        let all_fields = export
+        let lhs = export
             .key_fields
             .iter()
             .chain(export.val_fields.iter())
-            .cloned()
-            .collect::<Vec<_>>();
+            .join(", ");
         let put_keys = export.key_fields.join(", ");
         let put_vals = export.val_fields.join(", ");
-        let lhs = all_fields.join(", ");
-
-        for row in &export.rows {
-            let mut params = BTreeMap::new();
-            let rhs = row
+        let rows = DataValue::List(
+            export
+                .rows
                 .iter()
-                .enumerate()
-                .map(|(idx, value)| {
-                    let name = format!("f{idx}");
-                    params.insert(name.clone(), value.clone());
-                    format!("${name}")
-                })
-                .join(", ");
-            let script = format!(
-                r#"
-?[{lhs}, at] <- [[{rhs}, 'ASSERT']]
+                .map(|row| DataValue::List(row.clone()))
+                .collect(),
+        );
+        let script = format!(
+            r#"
+input[{lhs}] <- $rows
+?[{lhs}, at] := input[{lhs}], at = 'ASSERT'
 
 :put {relation} {{ {put_keys}, at => {put_vals} }}
 "#,
-                relation = export.relation
-            );
-            self.run_script(&script, params, cozo::ScriptMutability::Mutable)
-                .map_err(DbError::from)?;
-        }
-
-        Ok(())
+            relation = export.relation
+        );
+        batch.push(&script, BTreeMap::from([("rows".to_string(), rows)]));
     }
 
+    /// Rejects an artifact clashing with the loaded namespaces. When `merging`, the namespace of
+    /// the artifact itself is not a conflict.
     fn validate_namespace_import_conflicts(
         &self,
         artifact: &NamespaceExportArtifact,
+        merging: bool,
     ) -> Result<(), NamespaceImportError> {
-        let crate_rows = self
+        let mut crate_rows = self
             .list_crate_context_rows()
             .map_err(NamespaceImportError::from)?;
+        if merging {
+            crate_rows.retain(|row| row.namespace != artifact.namespace);
+        }
         let duplicate_namespace = crate_rows
             .iter()
             .find(|row| row.namespace == artifact.namespace)
@@ -804,6 +1319,50 @@ target[node_id] := input[id_str], node_id = to_uuid(id_str)
         Ok(())
     }
 
+    /// Registers the embedding sets of an artifact, creating their relations, and restores its
+    /// active embedding set. Returns the vector relations of the registered sets.
+    fn put_embedding_sets_from_export(
+        &self,
+        artifact: &NamespaceExportArtifact,
+    ) -> Result<BTreeSet<String>, DbError> {
+        let batch = WriteBatch::default();
+        let relations = self.push_embedding_sets_from_export(&batch, artifact)?;
+        batch.run(&self.db).map_err(DbError::from)?;
+        Ok(relations)
+    }
+
+    /// Like [`Self::put_embedding_sets_from_export`], except that only the relations are created
+    /// right away: the rows registering the sets are added to `batch`.
+    fn push_embedding_sets_from_export(
+        &self,
+        batch: &WriteBatch,
+        artifact: &NamespaceExportArtifact,
+    ) -> Result<BTreeSet<String>, DbError> {
+        let embedding_set_export = Self::relation_export(artifact, EmbeddingSet::RELATION_NAME)?;
+        Self::push_relation_export_rows(batch, embedding_set_export);
+
+        let mut relations = BTreeSet::new();
+        for row in &embedding_set_export.rows {
+            let provider = to_string(&row[1])?;
+            let model = to_string(&row[2])?;
+            let dims = to_u64(&row[3])?;
+            let dtype = Self::parse_embedding_dtype(&to_string(&row[4])?)?;
+            let set = EmbeddingSet::new(
+                EmbeddingProviderSlug::new_from_str(&provider),
+                EmbeddingModelId::new_from_str(&model),
+                EmbeddingShape::new(dims as u32, dtype),
+            );
+            self.ensure_embedding_relation(&set)?;
+            self.ensure_vector_embedding_relation(&set)?;
+            relations.insert(to_string(&row[5])?);
+        }
+
+        let active_embedding_export = Self::relation_export(artifact, ACTIVE_EMBEDDING_SET_REL)?;
+        self.ensure_active_embedding_relation()?;
+        Self::push_relation_export_rows(batch, active_embedding_export);
+        Ok(relations)
+    }
+
     fn merge_workspace_metadata_from_export(
         &self,
         artifact: &NamespaceExportArtifact,
@@ -927,9 +1486,9 @@ target[node_id] := input[id_str], node_id = to_uuid(id_str)
         Ok(true)
     }
 
-    fn retract_syntax_edges_for_ids(&self, ids: &BTreeSet<Uuid>) -> Result<(), DbError> {
+    fn push_syntax_edges_retraction(batch: &WriteBatch, ids: &BTreeSet<Uuid>) {
         if ids.is_empty() {
-            return Ok(());
+            return;
         }
 
         let input_rows = Self::uuid_input_rows(ids);
@@ -945,9 +1504,7 @@ target[id] := input[id_str], id = to_uuid(id_str)
 :put syntax_edge {{ source_id, target_id, at => relation_kind, source_kind, target_kind }}
 "#
         );
-        self.run_script(&script, BTreeMap::new(), cozo::ScriptMutability::Mutable)
-            .map(|_| ())
-            .map_err(DbError::from)
+        batch.push(&script, BTreeMap::new());
     }
 
     fn retract_vector_rows_for_ids(
@@ -955,8 +1512,14 @@ target[id] := input[id_str], id = to_uuid(id_str)
         relation: &str,
         ids: &BTreeSet<Uuid>,
     ) -> Result<(), DbError> {
+        let batch = WriteBatch::default();
+        Self::push_vector_rows_retraction(&batch, relation, ids);
+        batch.run(&self.db).map_err(DbError::from)
+    }
+
+    fn push_vector_rows_retraction(batch: &WriteBatch, relation: &str, ids: &BTreeSet<Uuid>) {
         if ids.is_empty() {
-            return Ok(());
+            return;
         }
 
         let input_rows = Self::uuid_input_rows(ids);
@@ -972,18 +1535,24 @@ target[node_id] := input[id_str], node_id = to_uuid(id_str)
 :put {relation} {{ node_id, embedding_set_id, at => vector }}
 "#
         );
-        self.run_script(&script, BTreeMap::new(), cozo::ScriptMutability::Mutable)
-            .map(|_| ())
-            .map_err(DbError::from)
+        batch.push(&script, BTreeMap::new());
     }
 
-    fn retract_bm25_doc_meta_for_ids(&self, ids: &BTreeSet<Uuid>) -> Result<(), DbError> {
+    /// Retracts the BM25 document metadata and persisted postings of `ids`, so a restored index
+    /// does not bring the documents back.
+    fn retract_bm25_rows_for_ids(&self, ids: &BTreeSet<Uuid>) -> Result<(), DbError> {
+        let batch = WriteBatch::default();
+        Self::push_bm25_rows_retraction(&batch, ids);
+        batch.run(&self.db).map_err(DbError::from)
+    }
+
+    fn push_bm25_rows_retraction(batch: &WriteBatch, ids: &BTreeSet<Uuid>) {
         if ids.is_empty() {
-            return Ok(());
+            return;
         }
 
         let input_rows = Self::uuid_input_rows(ids);
-        let script = format!(
+        let meta_script = format!(
             r#"
 input[id_str] <- [{input_rows}]
 target[id] := input[id_str], id = to_uuid(id_str)
@@ -995,11 +1564,54 @@ target[id] := input[id_str], id = to_uuid(id_str)
 :put bm25_doc_meta {{ id, at => tracking_hash, tokenizer_version, token_length }}
 "#
         );
-        self.run_script(&script, BTreeMap::new(), cozo::ScriptMutability::Mutable)
+        let postings_script = format!(
+            r#"
+input[id_str] <- [{input_rows}]
+target[id] := input[id_str], id = to_uuid(id_str)
+?[id, namespace, token_ids, token_weights, at] :=
+    *bm25_doc_postings {{ id, namespace, token_ids, token_weights }},
+    target[id],
+    at = 'RETRACT'
+
+:put bm25_doc_postings {{ id, at => namespace, token_ids, token_weights }}
+"#
+        );
+        batch.push(&meta_script, BTreeMap::new());
+        batch.push(&postings_script, BTreeMap::new());
+    }
+
+    fn retract_bm25_index_stats_for_namespace(&self, namespace: Uuid) -> Result<(), DbError> {
+        let script = r#"
+?[id, at, tokenizer_version, avgdl, doc_count, total_len] :=
+    *bm25_index_stats { id, tokenizer_version, avgdl, doc_count, total_len },
+    id = $namespace,
+    at = 'RETRACT'
+
+:put bm25_index_stats { id, at => tokenizer_version, avgdl, doc_count, total_len }
+"#;
+        let params = BTreeMap::from([(
+            "namespace".to_string(),
+            DataValue::Uuid(UuidWrapper(namespace)),
+        )]);
+        self.run_script(script, params, cozo::ScriptMutability::Mutable)
             .map(|_| ())
             .map_err(DbError::from)
     }
 
+    /// Looks up the tests owned by `ids` now, and adds the retraction of their parse details to
+    /// `batch`.
+    fn push_parse_detail_rows_retraction(
+        &self,
+        batch: &WriteBatch,
+        ids: &BTreeSet<Uuid>,
+    ) -> Result<(), DbError> {
+        let ids = self.with_owned_test_ids(ids)?;
+        for (relation, key_fields, val_fields) in Self::parse_detail_fields() {
+            Self::push_relation_rows_retraction(batch, relation, &key_fields, &val_fields, &ids);
+        }
+        Ok(())
+    }
+
     fn list_embedding_vector_relations(&self) -> Result<Vec<String>, DbError> {
         let rels = self
             .iter_relations()
@@ -1206,7 +1818,7 @@ target[id] := input[id_str], id = to_uuid(id_str)
     }
 
     pub fn rel_names_with_tracking_hash<'a>(&'a self) -> Result<Vec<String>, DbError> {
-        fn filter_is_th(db: &Database, rel_name: &str) -> Result<bool, DbError> {
+        fn filter_is_th<S: CozoStorage>(db: &Database<S>, rel_name: &str) -> Result<bool, DbError> {
             let script_th_col = format!("::columns {rel_name}");
             let is_th = db
                 .raw_query(&script_th_col)
@@ -1322,53 +1934,38 @@ target[id] := input[id_str], id = to_uuid(id_str)
     }
 
     /// Create new database connection
-    pub fn new(db: Db<MemStorage>) -> Self {
+    pub fn new(db: Db<S>) -> Self {
         Self::new_with_active_set(db, Arc::new(RwLock::new(DEFAULT_EMBEDDING_SET.clone())))
     }
 
-    pub fn new_with_active_set(db: Db<MemStorage>, active_set: Arc<RwLock<EmbeddingSet>>) -> Self {
+    pub fn new_with_active_set(db: Db<S>, active_set: Arc<RwLock<EmbeddingSet>>) -> Self {
         Self {
             db,
             active_embedding_set: active_set,
         }
     }
 
-    pub fn new_init() -> Result<Self, PlokeError> {
-        let db = Db::new(MemStorage::default()).map_err(|e| DbError::Cozo(e.to_string()))?;
-        db.initialize().map_err(|e| DbError::Cozo(e.to_string()))?;
-        Ok(Self::new_with_active_set(
-            db,
-            Arc::new(RwLock::new(DEFAULT_EMBEDDING_SET.clone())),
-        ))
-    }
-
-    pub fn init_with_schema() -> Result<Self, PlokeError> {
-        let db = Db::new(MemStorage::default()).map_err(|e| DbError::Cozo(e.to_string()))?;
-        db.initialize().map_err(|e| DbError::Cozo(e.to_string()))?;
-
-        // Create the schema
-        ploke_transform::schema::create_schema_all(&db)?;
-
-        Ok(Self::new_with_active_set(
-            db,
-            Arc::new(RwLock::new(DEFAULT_EMBEDDING_SET.clone())),
-        ))
-    }
-
-    /// Restore `compilation_unit*` relations after a backup import when the snapshot predates them.
+    /// Restore `compilation_unit*`, [`PARSE_DETAIL_RELS`] and [`BM25_INDEX_RELS`] relations after
+    /// a backup import when the snapshot predates them.
     pub fn ensure_compilation_unit_relations(&self) -> Result<(), PlokeError> {
         ploke_transform::schema::ensure_compilation_unit_relations(&self.db)?;
+        ploke_transform::schema::ensure_parse_detail_relations(&self.db)?;
+        ploke_transform::schema::ensure_bm25_index_relations(&self.db)?;
         Ok(())
     }
 
     /// Relation names for [`cozo::Db::import_from_backup`] when the `.sqlite` snapshot may omit
-    /// `compilation_unit*` tables (older fixtures). Call [`Self::ensure_compilation_unit_relations`]
-    /// after import.
+    /// `compilation_unit*`, [`PARSE_DETAIL_RELS`] or [`BM25_INDEX_RELS`] tables (older
+    /// fixtures). Call [`Self::ensure_compilation_unit_relations`] after import.
     pub fn prior_rels_for_plain_backup_import(&self) -> Result<Vec<String>, PlokeError> {
         Ok(self
             .relations_vec()?
             .into_iter()
-            .filter(|r| !r.starts_with("compilation_unit"))
+            .filter(|r| {
+                !r.starts_with("compilation_unit")
+                    && !PARSE_DETAIL_RELS.contains(&r.as_str())
+                    && !BM25_INDEX_RELS.contains(&r.as_str())
+            })
             .collect())
     }
 
@@ -2067,7 +2664,19 @@ target[id] := input[id_str], id = to_uuid(id_str)
 
     /// Import a backup in two passes so embedding-set metadata is read first, allowing per-set
     /// vector relations to be created before vector rows are imported.
+    ///
+    /// The backup keeps the layout of the ploke version that wrote it. A backup older than
+    /// [`CURRENT_SCHEMA_VERSION`](crate::migration::CURRENT_SCHEMA_VERSION) is restored into a
+    /// scratch database and migrated before its rows are copied into the current relations; a
+    /// current one is imported from the file directly.
     pub fn import_backup_with_embeddings(&self, backup: &Path) -> Result<(), DbError> {
+        let version = Database::<MemStorage>::backup_schema_version(backup)?;
+        let source = if version < crate::migration::CURRENT_SCHEMA_VERSION {
+            BackupSource::Migrated(Database::<MemStorage>::restore_migrated(backup)?)
+        } else {
+            BackupSource::Current(backup)
+        };
+
         // Ensure base relations exist in the fresh DB.
         self.ensure_embedding_set_relation()
             .map_err(|e| DbError::Cozo(e.to_string()))?;
@@ -2076,32 +2685,13 @@ target[id] := input[id_str], id = to_uuid(id_str)
             .map_err(|e| DbError::Cozo(e.to_string()));
 
         // Pass 1: import embedding_set (and active metadata if present) to discover sets.
-        let first_pass: Vec<String> = vec![EmbeddingSet::RELATION_NAME.to_string()];
-
-        self.db
-            .import_from_backup(backup, &first_pass)
-            .map_err(DbError::from)?;
+        self.import_relations_from(
+            &source,
+            &[EmbeddingSet::RELATION_NAME, ACTIVE_EMBEDDING_SET_REL],
+        )?;
 
         let sets = self.list_embedding_sets()?;
 
-        // Attempt to import active_embedding_set metadata if present in the backup; ignore missing
-        // relation errors for legacy backups.
-        if let Err(e) = self
-            .db
-            .import_from_backup(backup, &[ACTIVE_EMBEDDING_SET_REL.to_string()])
-        {
-            let msg = e.to_string();
-            if msg.contains("Cannot find requested stored relation")
-                || msg.contains("No relations specified")
-            {
-                warn!(
-                    "active_embedding_set relation not found in backup; continuing without metadata"
-                );
-            } else {
-                return Err(DbError::from(e));
-            }
-        }
-
         // Create per-set relations so vector rows can import.
         for set in &sets {
             if let Err(e) = self.ensure_embedding_relation(set) {
@@ -2120,10 +2710,19 @@ target[id] := input[id_str], id = to_uuid(id_str)
             }
         }
 
+        // Relations added to the schema after the DB was created; the migrated backup has them.
+        self.ensure_compilation_unit_relations()
+            .map_err(|e| DbError::Cozo(e.to_string()))?;
+        ploke_transform::schema::ensure_graph_snapshot_relation(&self.db)
+            .map_err(|e| DbError::Cozo(e.to_string()))?;
+        self.ensure_embedding_cache_relation()?;
+        ploke_transform::schema::ensure_schema_migration_relation(&self.db)
+            .map_err(|e| DbError::Cozo(e.to_string()))?;
+
         let mut relations: Vec<String> = self
             .relations_vec()
             .map_err(|e| DbError::Cozo(e.to_string()))?;
-        relations.retain(|r| r != ACTIVE_EMBEDDING_SET_REL);
+        relations.retain(|r| r != EmbeddingSet::RELATION_NAME && r != ACTIVE_EMBEDDING_SET_REL);
         for set in &sets {
             relations.push(set.rel_name().as_ref().to_string());
         }
@@ -2132,17 +2731,63 @@ target[id] := input[id_str], id = to_uuid(id_str)
         let mut uniq = HashSet::new();
         relations.retain(|r| uniq.insert(r.clone()));
 
-        // Snapshots may omit compilation-unit relations; importing them by name fails on legacy
-        // backups. Recreate after import via `ensure_compilation_unit_relations`.
-        relations.retain(|r| !r.starts_with("compilation_unit"));
-
-        self.db
-            .import_from_backup(backup, &relations)
-            .map_err(DbError::from)?;
+        let relations: Vec<&str> = relations.iter().map(String::as_str).collect();
+        self.import_relations_from(&source, &relations)
+    }
 
-        self.ensure_compilation_unit_relations()
+    /// Copies the rows of `relations` from `source` into this database. Relations `source` does
+    /// not have, e.g. ones added after a legacy backup was written, are skipped with a warning.
+    fn import_relations_from(
+        &self,
+        source: &BackupSource<'_>,
+        relations: &[&str],
+    ) -> Result<(), DbError> {
+        let source = match source {
+            BackupSource::Migrated(source) => source,
+            BackupSource::Current(backup) => {
+                return self.import_relations_from_file(backup, relations);
+            }
+        };
+        let present = source
+            .relations_vec()
             .map_err(|e| DbError::Cozo(e.to_string()))?;
+        let (found, missing): (Vec<&str>, Vec<&str>) = relations
+            .iter()
+            .partition(|relation| present.iter().any(|r| r == *relation));
+        for relation in missing {
+            warn!("{relation} relation not found in backup; continuing without it");
+        }
+        if found.is_empty() {
+            return Ok(());
+        }
 
+        let rows = source
+            .db
+            .export_relations(found.into_iter())
+            .map_err(DbError::from)?;
+        self.db.import_relations(rows).map_err(DbError::from)
+    }
+
+    /// Like [`Self::import_relations_from`], reading the rows from the backup file. The relations
+    /// are imported together, or one by one when the backup lacks some of them.
+    fn import_relations_from_file(&self, backup: &Path, relations: &[&str]) -> Result<(), DbError> {
+        let all: Vec<String> = relations.iter().map(|r| r.to_string()).collect();
+        match self.db.import_from_backup(backup, &all) {
+            Err(err) if is_missing_relation(&err) => {}
+            result => return result.map_err(DbError::from),
+        }
+        for relation in all {
+            match self
+                .db
+                .import_from_backup(backup, std::slice::from_ref(&relation))
+            {
+                Ok(()) => {}
+                Err(err) if is_missing_relation(&err) => {
+                    warn!("{relation} relation not found in backup; continuing without it");
+                }
+                Err(err) => return Err(DbError::from(err)),
+            }
+        }
         Ok(())
     }
 
@@ -2190,26 +2835,6 @@ target[id] := input[id_str], id = to_uuid(id_str)
         ];
         Ok(mock_nodes)
     }
-    pub async fn create_new_backup_default(path: impl AsRef<Path>) -> Result<Database, PlokeError> {
-        let new_db = cozo::new_cozo_mem().map_err(DbError::from)?;
-        new_db.restore_backup(&path).map_err(DbError::from)?;
-        Ok(Self {
-            db: new_db,
-            active_embedding_set: Arc::new(RwLock::new(DEFAULT_EMBEDDING_SET.clone())),
-        })
-    }
-    pub async fn create_new_backup(
-        active_embedding_set: EmbeddingSet,
-        path: impl AsRef<Path>,
-    ) -> Result<Database, PlokeError> {
-        let new_db = cozo::new_cozo_mem().map_err(DbError::from)?;
-        new_db.restore_backup(&path).map_err(DbError::from)?;
-        Ok(Self {
-            db: new_db,
-            active_embedding_set: Arc::new(RwLock::new(active_embedding_set)),
-        })
-    }
-
     pub fn iter_relations(&self) -> Result<impl IntoIterator<Item = String>, PlokeError> {
         let output = self.raw_query("::relations")?;
         Ok(output.rows.into_iter().filter_map(|r| {
@@ -2295,6 +2920,167 @@ target[id] := input[id_str], id = to_uuid(id_str)
             .collect()
     }
 
+    fn has_dependency_namespace_relation(&self) -> Result<bool, DbError> {
+        Ok(self
+            .relations_vec()
+            .map_err(|e| DbError::Cozo(e.to_string()))?
+            .iter()
+            .any(|r| r == DEPENDENCY_NAMESPACE_REL))
+    }
+
+    /// Lists the namespaces holding indexed dependencies.
+    pub fn list_dependency_namespaces(&self) -> Result<Vec<DependencyNamespaceRow>, DbError> {
+        if !self.has_dependency_namespace_relation()? {
+            return Ok(Vec::new());
+        }
+        let rows = self.raw_query(
+            "?[id, name, version, source, origin, root_path] := *dependency_namespace { id, name, version, source, origin, root_path @ 'NOW' }",
+        )?;
+
+        rows.rows
+            .into_iter()
+            .map(|row| {
+                let field = |idx: usize, name: &str| {
+                    row.get(idx).ok_or_else(|| {
+                        DbError::QueryExecution(format!("missing dependency_namespace.{name}"))
+                    })
+                };
+                Ok(DependencyNamespaceRow {
+                    namespace: field(0, "id").and_then(to_uuid)?,
+                    name: field(1, "name").and_then(to_string)?,
+                    version: field(2, "version").and_then(to_string)?,
+                    source: field(3, "source").and_then(to_string)?,
+                    origin: field(4, "origin").and_then(to_string)?,
+                    root_path: field(5, "root_path").and_then(to_string)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Whether `namespace` holds an indexed dependency, which [`Self::remove_namespace`],
+    /// [`Self::import_namespace`], [`Self::merge_namespace`] and the graph transforms refuse to
+    /// modify.
+    pub fn is_dependency_namespace(&self, namespace: Uuid) -> Result<bool, DbError> {
+        ploke_transform::schema::is_dependency_namespace(&self.db, namespace)
+            .map_err(|e| DbError::Cozo(e.to_string()))
+    }
+
+    /// Records the graph as it is now as the snapshot of `run`, see
+    /// [`ploke_transform::transform::snapshot`].
+    pub fn record_graph_snapshot(&self, run: SnapshotRun) -> Result<GraphSnapshot, DbError> {
+        run.record(&self.db)
+            .map_err(|e| DbError::Cozo(e.to_string()))
+    }
+
+    fn has_graph_snapshot_relation(&self) -> Result<bool, DbError> {
+        Ok(self
+            .relations_vec()
+            .map_err(|e| DbError::Cozo(e.to_string()))?
+            .iter()
+            .any(|r| r == GRAPH_SNAPSHOT_REL))
+    }
+
+    /// Lists the recorded snapshots of the code graph, oldest first.
+    pub fn list_graph_snapshots(&self) -> Result<Vec<GraphSnapshot>, DbError> {
+        if !self.has_graph_snapshot_relation()? {
+            return Ok(Vec::new());
+        }
+        let rows = self.raw_query(
+            "?[kind, tag, started_at, recorded_at] := *graph_snapshot { kind, tag, started_at, recorded_at @ 'NOW' } :order recorded_at",
+        )?;
+
+        rows.rows
+            .into_iter()
+            .map(|row| {
+                let field = |idx: usize, name: &str| {
+                    row.get(idx).ok_or_else(|| {
+                        DbError::QueryExecution(format!("missing graph_snapshot.{name}"))
+                    })
+                };
+                let kind = field(0, "kind").and_then(to_string)?;
+                let tag = SnapshotTag::from_parts(&kind, field(1, "tag").and_then(to_string)?)
+                    .ok_or_else(|| {
+                        DbError::QueryExecution(format!("unknown graph_snapshot.kind {kind:?}"))
+                    })?;
+                Ok(GraphSnapshot {
+                    tag,
+                    started_at: field(2, "started_at").and_then(to_i64)?,
+                    recorded_at: field(3, "recorded_at").and_then(to_i64)?,
+                })
+            })
+            .collect()
+    }
+
+    /// The recorded snapshot of `tag`, if any.
+    pub fn graph_snapshot(&self, tag: &SnapshotTag) -> Result<Option<GraphSnapshot>, DbError> {
+        Ok(self
+            .list_graph_snapshots()?
+            .into_iter()
+            .find(|snapshot| &snapshot.tag == tag))
+    }
+
+    /// Runs an immutable query against the graph as of `snapshot`. `script` reads relations
+    /// `@ $as_of`, which is bound to the validity timestamp of the snapshot.
+    pub fn raw_query_as_of(
+        &self,
+        script: &str,
+        snapshot: &GraphSnapshot,
+    ) -> Result<QueryResult, DbError> {
+        let params = BTreeMap::from([("as_of".to_string(), DataValue::from(snapshot.recorded_at))]);
+        self.raw_query_params(script, params)
+    }
+
+    /// The primary and associated nodes of the graph as of the validity timestamp `at`, by id.
+    fn snapshot_items(&self, at: i64) -> Result<BTreeMap<Uuid, SnapshotItem>, DbError> {
+        let mut items = BTreeMap::new();
+        for node_type in NodeType::primary_and_assoc_nodes() {
+            let relation = node_type.relation_str();
+            let rows = self.raw_query_params(
+                &format!(
+                    "?[id, name, vis_kind, tracking_hash] := *{relation} {{ id, name, vis_kind, tracking_hash @ $at }}"
+                ),
+                BTreeMap::from([("at".to_string(), DataValue::from(at))]),
+            )?;
+            for row in rows.rows {
+                let item = SnapshotItem {
+                    id: to_uuid(&row[0])?,
+                    name: to_string(&row[1])?,
+                    relation: relation.to_string(),
+                    vis_kind: to_string(&row[2])?,
+                    tracking_hash: to_uuid(&row[3])?,
+                };
+                items.insert(item.id, item);
+            }
+        }
+        Ok(items)
+    }
+
+    /// The primary and associated nodes added, removed or changed between the snapshots `from`
+    /// and `to`. A node changed if its tracking hash or visibility differs.
+    pub fn diff_graph_snapshots(
+        &self,
+        from: &GraphSnapshot,
+        to: &GraphSnapshot,
+    ) -> Result<GraphSnapshotDiff, DbError> {
+        let mut before = self.snapshot_items(from.recorded_at)?;
+        let after = self.snapshot_items(to.recorded_at)?;
+
+        let mut diff = GraphSnapshotDiff::default();
+        for (id, item) in after {
+            match before.remove(&id) {
+                None => diff.added.push(item),
+                Some(old)
+                    if old.tracking_hash != item.tracking_hash || old.vis_kind != item.vis_kind =>
+                {
+                    diff.changed.push(item)
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = before.into_values().collect();
+        Ok(diff)
+    }
+
     pub fn collect_namespace_inventory(
         &self,
         namespace: Uuid,
@@ -2375,19 +3161,71 @@ desc[id] := parent_of[id, parent], desc[parent]
         })
     }
 
+    /// Removes every row belonging to `namespace`.
+    ///
+    /// Returns [`DbError::ReadOnlyNamespace`] for the namespace of an indexed dependency, which
+    /// is only removed through [`Self::remove_dependency_namespace`].
     pub async fn remove_namespace(
         &self,
         namespace: Uuid,
     ) -> Result<NamespaceRemovalResult, DbError> {
-        let inventory = self.collect_namespace_inventory(namespace)?;
-        let removed_root_path = inventory.crate_context.root_path.clone();
-        let removed_crate_name = inventory.crate_context.name.clone();
+        if self.is_dependency_namespace(namespace)? {
+            return Err(DbError::ReadOnlyNamespace(namespace));
+        }
+        self.remove_namespace_rows(namespace).await
+    }
 
-        self.retract_syntax_edges_for_ids(&inventory.descendant_ids)?;
+    /// Removes an indexed dependency namespace, along with its read-only marker.
+    pub async fn remove_dependency_namespace(
+        &self,
+        namespace: Uuid,
+    ) -> Result<NamespaceRemovalResult, DbError> {
+        if !self.is_dependency_namespace(namespace)? {
+            return Err(DbError::NotFound);
+        }
+        let result = self.remove_namespace_rows(namespace).await?;
+        let script = format!(
+            r#"
+?[id, name, version, source, origin, root_path, at] :=
+    *dependency_namespace {{ id, name, version, source, origin, root_path }},
+    id = to_uuid("{namespace}"),
+    at = 'RETRACT'
+
+:put dependency_namespace {{ id, at => name, version, source, origin, root_path }}
+"#
+        );
+        self.run_script(&script, BTreeMap::new(), cozo::ScriptMutability::Mutable)
+            .map_err(DbError::from)?;
+        Ok(result)
+    }
+
+    /// Retracts the code-graph rows of `namespace` as of now: its nodes with their edges and
+    /// parse details, its file modules and its crate context. Embedding and BM25 rows are left
+    /// to the caller.
+    fn retract_namespace_graph_rows(
+        &self,
+        namespace: Uuid,
+        inventory: &NamespaceInventory,
+    ) -> Result<(), DbError> {
+        let batch = WriteBatch::default();
+        self.push_namespace_graph_rows_retraction(&batch, namespace, inventory)?;
+        batch.run(&self.db).map_err(DbError::from)
+    }
 
+    /// Adds the retraction of the code-graph rows of `namespace` to `batch`, see
+    /// [`Self::retract_namespace_graph_rows`].
+    fn push_namespace_graph_rows_retraction(
+        &self,
+        batch: &WriteBatch,
+        namespace: Uuid,
+        inventory: &NamespaceInventory,
+    ) -> Result<(), DbError> {
+        let callable_ids = self.with_owned_method_ids(&inventory.descendant_ids)?;
+        Self::push_syntax_edges_retraction(batch, &callable_ids);
+        self.push_parse_detail_rows_retraction(batch, &callable_ids)?;
         let mut descendant_relations = NodeType::all_variants()
             .into_iter()
-            .filter(|ty| *ty != NodeType::SyntaxEdge)
+            .filter(|ty| *ty != NodeType::SyntaxEdge && !ty.is_parse_detail())
             .map(|ty| ty.relation_str().to_string())
             .collect::<Vec<_>>();
         descendant_relations.push("method".to_string());
@@ -2398,16 +3236,17 @@ desc[id] := parent_of[id, parent], desc[parent]
             let node_type = NodeType::all_variants()
                 .into_iter()
                 .find(|ty| ty.relation_str() == relation)
-                .filter(|ty| *ty != NodeType::SyntaxEdge);
+                .filter(|ty| *ty != NodeType::SyntaxEdge && !ty.is_parse_detail());
             if let Some(node_type) = node_type {
                 let key_fields = node_type.keys().collect::<Vec<_>>();
                 let val_fields = node_type.vals().collect::<Vec<_>>();
-                self.retract_relation_rows_by_id(
+                Self::push_relation_rows_retraction(
+                    batch,
                     &relation,
                     &key_fields,
                     &val_fields,
                     &inventory.descendant_ids,
-                )?;
+                );
             } else if relation == "method" {
                 let key_fields = MethodNodeSchema::SCHEMA
                     .keys()
@@ -2417,21 +3256,16 @@ desc[id] := parent_of[id, parent], desc[parent]
                     .vals()
                     .map(|field| *field)
                     .collect::<Vec<_>>();
-                self.retract_relation_rows_by_id(
+                Self::push_relation_rows_retraction(
+                    batch,
                     &relation,
                     &key_fields,
                     &val_fields,
                     &inventory.descendant_ids,
-                )?;
+                );
             }
         }
 
-        for relation in self.list_embedding_vector_relations()? {
-            self.retract_vector_rows_for_ids(&relation, &inventory.descendant_ids)?;
-        }
-
-        self.retract_bm25_doc_meta_for_ids(&inventory.descendant_ids)?;
-
         let namespace_lit = namespace.to_string();
         let file_mod_script = format!(
             r#"
@@ -2443,12 +3277,7 @@ desc[id] := parent_of[id, parent], desc[parent]
 :put file_mod {{ owner_id, at => file_path, file_docs, items, namespace }}
 "#
         );
-        self.run_script(
-            &file_mod_script,
-            BTreeMap::new(),
-            cozo::ScriptMutability::Mutable,
-        )
-        .map_err(DbError::from)?;
+        batch.push(&file_mod_script, BTreeMap::new());
 
         let crate_context_script = format!(
             r#"
@@ -2460,12 +3289,109 @@ desc[id] := parent_of[id, parent], desc[parent]
 :put crate_context {{ id, at => name, version, namespace, root_path, files }}
 "#
         );
-        self.run_script(
-            &crate_context_script,
-            BTreeMap::new(),
-            cozo::ScriptMutability::Mutable,
-        )
-        .map_err(DbError::from)?;
+        batch.push(&crate_context_script, BTreeMap::new());
+        Ok(())
+    }
+
+    /// Replaces the code-graph rows of the indexed `namespaces` with the rows written to
+    /// `batch`, and returns the tracking hash of each node the namespaces held before.
+    ///
+    /// The old rows are retracted and the new ones written in one transaction, so a failing write
+    /// leaves the namespaces as they were. Rows are retracted rather than removed, so the graph as
+    /// of earlier snapshots stays readable, and embeddings are kept for the nodes that come back
+    /// unchanged. Pass the returned hashes to [`Self::retract_stale_search_rows`] afterwards.
+    pub fn reindex_namespaces(
+        &self,
+        namespaces: &[Uuid],
+        batch: WriteBatch,
+    ) -> Result<Vec<(Uuid, BTreeMap<Uuid, Option<Uuid>>)>, DbError> {
+        let writes = WriteBatch::default();
+        let mut previous = Vec::with_capacity(namespaces.len());
+        for &namespace in namespaces {
+            if self.is_dependency_namespace(namespace)? {
+                return Err(DbError::ReadOnlyNamespace(namespace));
+            }
+            let inventory = self.collect_namespace_inventory(namespace)?;
+            self.push_namespace_graph_rows_retraction(&writes, namespace, &inventory)?;
+            previous.push((
+                namespace,
+                self.node_tracking_hashes(&inventory.descendant_ids)?,
+            ));
+        }
+        writes.append(batch);
+        writes.run(&self.db).map_err(DbError::from)?;
+        Ok(previous)
+    }
+
+    /// Retracts the embedding and BM25 rows of the nodes in `previous` that the re-indexed
+    /// `namespace` no longer contains or whose tracking hash changed, and returns their ids.
+    pub fn retract_stale_search_rows(
+        &self,
+        namespace: Uuid,
+        previous: &BTreeMap<Uuid, Option<Uuid>>,
+    ) -> Result<BTreeSet<Uuid>, DbError> {
+        let current = match self.collect_namespace_inventory(namespace) {
+            Ok(inventory) => self.node_tracking_hashes(&inventory.descendant_ids)?,
+            Err(DbError::NotFound) => BTreeMap::new(),
+            Err(err) => return Err(err),
+        };
+        let stale_ids = previous
+            .iter()
+            .filter(|(id, hash)| current.get(id) != Some(hash))
+            .map(|(id, _)| *id)
+            .collect::<BTreeSet<_>>();
+        for relation in self.list_embedding_vector_relations()? {
+            self.retract_vector_rows_for_ids(&relation, &stale_ids)?;
+        }
+        self.retract_bm25_rows_for_ids(&stale_ids)?;
+        Ok(stale_ids)
+    }
+
+    /// Returns the current tracking hash of each node in `ids`, or `None` for the nodes without
+    /// one.
+    fn node_tracking_hashes(
+        &self,
+        ids: &BTreeSet<Uuid>,
+    ) -> Result<BTreeMap<Uuid, Option<Uuid>>, DbError> {
+        let mut hashes = ids.iter().map(|id| (*id, None)).collect::<BTreeMap<_, _>>();
+        if ids.is_empty() {
+            return Ok(hashes);
+        }
+        let input_rows = Self::uuid_input_rows(ids);
+        for (relation, key_fields, val_fields) in namespace_node_relations() {
+            if !key_fields.contains(&"id") || !val_fields.contains(&"tracking_hash") {
+                continue;
+            }
+            let rows = self.raw_query(&format!(
+                r#"
+input[id_str] <- [{input_rows}]
+?[id, tracking_hash] :=
+    input[id_str],
+    id = to_uuid(id_str),
+    *{relation} {{ id, tracking_hash @ 'NOW' }}
+"#
+            ))?;
+            for row in &rows.rows {
+                hashes.insert(to_uuid(&row[0])?, Some(to_uuid(&row[1])?));
+            }
+        }
+        Ok(hashes)
+    }
+
+    async fn remove_namespace_rows(
+        &self,
+        namespace: Uuid,
+    ) -> Result<NamespaceRemovalResult, DbError> {
+        let inventory = self.collect_namespace_inventory(namespace)?;
+        let removed_root_path = inventory.crate_context.root_path.clone();
+        let removed_crate_name = inventory.crate_context.name.clone();
+
+        self.retract_namespace_graph_rows(namespace, &inventory)?;
+        for relation in self.list_embedding_vector_relations()? {
+            self.retract_vector_rows_for_ids(&relation, &inventory.descendant_ids)?;
+        }
+        self.retract_bm25_rows_for_ids(&inventory.descendant_ids)?;
+        self.retract_bm25_index_stats_for_namespace(namespace)?;
 
         self.retract_active_embedding_set_meta_for_crate_name(&removed_crate_name)?;
         let removed_workspace_member =
@@ -2487,16 +3413,21 @@ desc[id] := parent_of[id, parent], desc[parent]
 
     pub fn export_namespace(&self, namespace: Uuid) -> Result<NamespaceExportArtifact, DbError> {
         let inventory = self.collect_namespace_inventory(namespace)?;
+        let callable_ids = self.with_owned_method_ids(&inventory.descendant_ids)?;
         let mut relation_exports = vec![
             self.collect_crate_context_rows_for_namespace(namespace)?,
             self.collect_file_mod_rows_for_namespace(namespace)?,
-            self.collect_syntax_edge_rows_for_ids(&inventory.descendant_ids)?,
+            self.collect_syntax_edge_rows_for_ids(&callable_ids)?,
             self.collect_bm25_doc_meta_for_ids(&inventory.descendant_ids)?,
         ];
+        relation_exports.extend(self.collect_parse_detail_rows_for_ids(&callable_ids)?);
+        if self.has_dependency_namespace_relation()? {
+            relation_exports.push(self.collect_dependency_namespace_rows_for_namespace(namespace)?);
+        }
 
         let mut descendant_relations = NodeType::all_variants()
             .into_iter()
-            .filter(|ty| *ty != NodeType::SyntaxEdge)
+            .filter(|ty| *ty != NodeType::SyntaxEdge && !ty.is_parse_detail())
             .map(|ty| ty.relation_str().to_string())
             .collect::<Vec<_>>();
         descendant_relations.push("method".to_string());
@@ -2507,7 +3438,7 @@ desc[id] := parent_of[id, parent], desc[parent]
             let node_type = NodeType::all_variants()
                 .into_iter()
                 .find(|ty| ty.relation_str() == relation)
-                .filter(|ty| *ty != NodeType::SyntaxEdge);
+                .filter(|ty| *ty != NodeType::SyntaxEdge && !ty.is_parse_detail());
             if let Some(node_type) = node_type {
                 let key_fields = node_type.keys().collect::<Vec<_>>();
                 let val_fields = node_type.vals().collect::<Vec<_>>();
@@ -2556,6 +3487,19 @@ desc[id] := parent_of[id, parent], desc[parent]
             self.collect_workspace_metadata_rows_for_root(&inventory.crate_context.root_path)?;
         relation_exports.push(workspace_export);
 
+        let file_timestamps = self
+            .raw_query_params(
+                "?[file_path, written_at] := *file_mod { file_path, namespace, at @ 'NOW' }, namespace = $namespace, written_at = to_int(at)",
+                BTreeMap::from([(
+                    "namespace".to_string(),
+                    DataValue::Uuid(UuidWrapper(namespace)),
+                )]),
+            )?
+            .rows
+            .iter()
+            .map(|row| Ok((to_string(&row[0])?, to_i64(&row[1])?)))
+            .collect::<Result<BTreeMap<_, _>, DbError>>()?;
+
         Ok(NamespaceExportArtifact {
             namespace: inventory.crate_context.namespace,
             crate_context: inventory.crate_context,
@@ -2563,6 +3507,7 @@ desc[id] := parent_of[id, parent], desc[parent]
             descendant_ids: inventory.descendant_ids,
             workspace_members,
             relation_exports,
+            file_timestamps,
         })
     }
 
@@ -2570,37 +3515,13 @@ desc[id] := parent_of[id, parent], desc[parent]
         &self,
         artifact: &NamespaceExportArtifact,
     ) -> Result<NamespaceImportResult, NamespaceImportError> {
-        self.validate_namespace_import_conflicts(artifact)?;
-
-        let embedding_set_export = Self::relation_export(artifact, EmbeddingSet::RELATION_NAME)
-            .map_err(NamespaceImportError::from)?;
-        self.put_relation_export_rows(embedding_set_export)
-            .map_err(NamespaceImportError::from)?;
-
-        for row in &embedding_set_export.rows {
-            let provider = to_string(&row[1]).map_err(NamespaceImportError::from)?;
-            let model = to_string(&row[2]).map_err(NamespaceImportError::from)?;
-            let dims = to_u64(&row[3]).map_err(NamespaceImportError::from)?;
-            let dtype = Self::parse_embedding_dtype(
-                &to_string(&row[4]).map_err(NamespaceImportError::from)?,
-            )
-            .map_err(NamespaceImportError::from)?;
-            let set = EmbeddingSet::new(
-                EmbeddingProviderSlug::new_from_str(&provider),
-                EmbeddingModelId::new_from_str(&model),
-                EmbeddingShape::new(dims as u32, dtype),
-            );
-            self.ensure_embedding_relation(&set)
-                .map_err(NamespaceImportError::from)?;
-            self.ensure_vector_embedding_relation(&set)
-                .map_err(NamespaceImportError::from)?;
+        if self.is_dependency_namespace(artifact.namespace)? {
+            return Err(DbError::ReadOnlyNamespace(artifact.namespace).into());
         }
+        self.validate_namespace_import_conflicts(artifact, false)?;
 
-        let active_embedding_export = Self::relation_export(artifact, ACTIVE_EMBEDDING_SET_REL)
-            .map_err(NamespaceImportError::from)?;
-        self.ensure_active_embedding_relation()
-            .map_err(NamespaceImportError::from)?;
-        self.put_relation_export_rows(active_embedding_export)
+        let embedding_set_relations = self
+            .put_embedding_sets_from_export(artifact)
             .map_err(NamespaceImportError::from)?;
 
         for relation in [
@@ -2621,9 +3542,28 @@ desc[id] := parent_of[id, parent], desc[parent]
             }
         }
 
+        // Exports taken before a parse detail relation was added have no snapshot of it.
+        ploke_transform::schema::ensure_parse_detail_relations(&self.db)
+            .map_err(|e| NamespaceImportError::from(DbError::Cozo(e.to_string())))?;
+        for relation in PARSE_DETAIL_RELS {
+            if let Ok(export) = Self::relation_export(artifact, relation) {
+                self.put_relation_export_rows(export)
+                    .map_err(NamespaceImportError::from)?;
+            }
+        }
+        // Keeps an imported dependency namespace read-only.
+        if let Ok(export) = Self::relation_export(artifact, DEPENDENCY_NAMESPACE_REL) {
+            if !export.rows.is_empty() {
+                ploke_transform::schema::ensure_dependency_namespace_relation(&self.db)
+                    .map_err(|e| NamespaceImportError::from(DbError::Cozo(e.to_string())))?;
+                self.put_relation_export_rows(export)
+                    .map_err(NamespaceImportError::from)?;
+            }
+        }
+
         let mut descendant_relations = NodeType::all_variants()
             .into_iter()
-            .filter(|ty| *ty != NodeType::SyntaxEdge)
+            .filter(|ty| *ty != NodeType::SyntaxEdge && !ty.is_parse_detail())
             .map(|ty| ty.relation_str().to_string())
             .collect::<Vec<_>>();
         descendant_relations.push("method".to_string());
@@ -2636,15 +3576,9 @@ desc[id] := parent_of[id, parent], desc[parent]
                 .map_err(NamespaceImportError::from)?;
         }
 
-        let embedding_set_relations = embedding_set_export
-            .rows
-            .iter()
-            .filter_map(|row| row.get(5))
-            .filter_map(|value| value.get_str())
-            .collect::<BTreeSet<_>>();
         for relation in embedding_set_relations {
             let export =
-                Self::relation_export(artifact, relation).map_err(NamespaceImportError::from)?;
+                Self::relation_export(artifact, &relation).map_err(NamespaceImportError::from)?;
             self.put_relation_export_rows(export)
                 .map_err(NamespaceImportError::from)?;
         }
@@ -2667,6 +3601,271 @@ desc[id] := parent_of[id, parent], desc[parent]
                 .is_empty(),
         })
     }
+
+    /// Updates the loaded graph of a crate with the result of an incremental re-parse, see
+    /// [`ploke_transform::transform::apply_graph_diff`].
+    ///
+    /// The embedding vectors and the BM25 metadata and persisted postings of removed and modified
+    /// nodes are retracted as well, so modified nodes are picked up again by the next embedding
+    /// and BM25 pass, and a restored BM25 index does not bring removed ones back. Everything is
+    /// applied in one transaction.
+    pub fn apply_graph_diff(
+        &self,
+        parsed_graph: syn_parser::ParsedCodeGraph,
+        tree: &syn_parser::ModuleTree,
+        diff: &syn_parser::parser::graph::GraphDiff,
+    ) -> Result<(), DbError> {
+        let namespace = parsed_graph.crate_namespace;
+        if self.is_dependency_namespace(namespace)? {
+            return Err(DbError::ReadOnlyNamespace(namespace));
+        }
+        let stale_ids = diff
+            .stale_ids()
+            .map(AnyNodeId::uuid)
+            .collect::<BTreeSet<_>>();
+        let batch = WriteBatch::default();
+        for relation in self.list_embedding_vector_relations()? {
+            Self::push_vector_rows_retraction(&batch, &relation, &stale_ids);
+        }
+        Self::push_bm25_rows_retraction(&batch, &stale_ids);
+        ploke_transform::transform::push_graph_diff(&self.db, &batch, parsed_graph, tree, diff)
+            .map_err(|e| DbError::Cozo(e.to_string()))?;
+        batch.run(&self.db).map_err(DbError::from)
+    }
+
+    /// Imports `artifact` over the loaded version of its namespace, file by file.
+    ///
+    /// Without a `base`, a file of the artifact whose module tracking hash differs from the loaded
+    /// file with the same path replaces it unless the loaded version was written later, see
+    /// [`NamespaceExportArtifact::file_timestamps`], and loaded files missing from the artifact
+    /// are kept. With the `base` both sides were exported from, a file only changed on one side
+    /// keeps that side's version, and a file changed on both sides keeps the newer one and is
+    /// reported as a conflict.
+    ///
+    /// Replaced nodes whose tracking hash did not change keep their embeddings. Imported syntax
+    /// edges are re-linked to the kept nodes, and dropped when one of their ends left the
+    /// namespace. All rows are retracted and imported in one transaction. A namespace that is not
+    /// loaded yet is imported as by [`Self::import_namespace`].
+    pub async fn merge_namespace(
+        &self,
+        artifact: &NamespaceExportArtifact,
+        base: Option<&NamespaceExportArtifact>,
+    ) -> Result<NamespaceMergeResult, NamespaceImportError> {
+        let current = match self.export_namespace(artifact.namespace) {
+            Ok(current) => current,
+            Err(DbError::NotFound) => {
+                let imported = self.import_namespace(artifact).await?;
+                let incoming = NamespaceArtifactIndex::new(artifact);
+                return Ok(NamespaceMergeResult {
+                    merged_namespace: imported.imported_namespace,
+                    added_files: incoming.file_states().into_keys().collect(),
+                    imported_node_ids: incoming.node_hashes.into_keys().collect(),
+                    hnsw_invalidated: imported.hnsw_invalidated,
+                    bm25_invalidated: imported.bm25_invalidated,
+                    ..Default::default()
+                });
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if self.is_dependency_namespace(artifact.namespace)? {
+            return Err(DbError::ReadOnlyNamespace(artifact.namespace).into());
+        }
+        self.validate_namespace_import_conflicts(artifact, true)?;
+
+        let ours = NamespaceArtifactIndex::new(&current);
+        let theirs = NamespaceArtifactIndex::new(artifact);
+        let base_files = base.map(|base| NamespaceArtifactIndex::new(base).file_states());
+        let our_files = ours.file_states();
+        let their_files = theirs.file_states();
+
+        let mut result = NamespaceMergeResult {
+            merged_namespace: artifact.namespace,
+            ..Default::default()
+        };
+        let mut replaced_files = BTreeSet::new();
+        let mut imported_files = BTreeSet::new();
+        let paths = our_files
+            .keys()
+            .chain(their_files.keys())
+            .collect::<BTreeSet<_>>();
+        for path in paths {
+            let our_file = our_files.get(path);
+            let their_file = their_files.get(path);
+            let our_hash = our_file.map(|(_, hash)| hash);
+            let their_hash = their_file.map(|(_, hash)| hash);
+            if our_hash == their_hash {
+                continue;
+            }
+            // Without a timestamp on either side, e.g. for a hand-built artifact, theirs wins.
+            let theirs_newer = match (
+                artifact.file_timestamps.get(path),
+                current.file_timestamps.get(path),
+            ) {
+                (Some(theirs), Some(ours)) => theirs >= ours,
+                _ => true,
+            };
+            let take_theirs = match &base_files {
+                Some(base_files) => {
+                    let base_hash = base_files.get(path).map(|(_, hash)| hash);
+                    if base_hash == their_hash {
+                        false
+                    } else if base_hash == our_hash {
+                        true
+                    } else {
+                        result.conflicted_files.push(path.clone());
+                        theirs_newer
+                    }
+                }
+                None => their_file.is_some() && theirs_newer,
+            };
+            if !take_theirs {
+                result.kept_files.push(path.clone());
+                continue;
+            }
+            match (our_file, their_file) {
+                (None, Some(_)) => result.added_files.push(path.clone()),
+                (Some(_), Some(_)) => result.updated_files.push(path.clone()),
+                (Some(_), None) => result.removed_files.push(path.clone()),
+                (None, None) => unreachable!("path comes from one of the two sides"),
+            }
+            replaced_files.extend(our_file.map(|(owner, _)| *owner));
+            imported_files.extend(their_file.map(|(owner, _)| *owner));
+        }
+        if replaced_files.is_empty() && imported_files.is_empty() {
+            return Ok(result);
+        }
+
+        let retracted_ids = ours.ids_owned_by(&replaced_files);
+        let imported_ids = theirs.ids_owned_by(&imported_files);
+        let preserved_embedding_ids = retracted_ids
+            .iter()
+            .filter(|id| imported_ids.contains(id))
+            .filter(|id| {
+                let hash = ours.node_hashes.get(id).copied().flatten();
+                hash.is_some() && hash == theirs.node_hashes.get(id).copied().flatten()
+            })
+            .copied()
+            .collect::<BTreeSet<_>>();
+        let stale_ids = retracted_ids
+            .difference(&preserved_embedding_ids)
+            .copied()
+            .collect::<BTreeSet<_>>();
+
+        // Relations are created up front; every row change below is applied as one transaction, so
+        // a failing merge leaves the loaded namespace as it was.
+        ploke_transform::schema::ensure_parse_detail_relations(&self.db)
+            .map_err(|e| DbError::Cozo(e.to_string()))?;
+        let batch = WriteBatch::default();
+        Self::push_syntax_edges_retraction(&batch, &retracted_ids);
+        self.push_parse_detail_rows_retraction(&batch, &retracted_ids)?;
+        for (relation, key_fields, val_fields) in namespace_node_relations() {
+            Self::push_relation_rows_retraction(
+                &batch,
+                &relation,
+                &key_fields,
+                &val_fields,
+                &retracted_ids,
+            );
+        }
+        Self::push_relation_rows_retraction(
+            &batch,
+            "file_mod",
+            &["owner_id"],
+            &["file_path", "file_docs", "items", "namespace"],
+            &replaced_files,
+        );
+        for relation in self.list_embedding_vector_relations()? {
+            Self::push_vector_rows_retraction(&batch, &relation, &stale_ids);
+        }
+        Self::push_bm25_rows_retraction(&batch, &stale_ids);
+        Self::push_relation_rows_retraction(
+            &batch,
+            "crate_context",
+            &["id"],
+            &["name", "version", "namespace", "root_path", "files"],
+            &BTreeSet::from([current.crate_context.id]),
+        );
+        self.push_embedding_sets_from_export(&batch, artifact)?;
+
+        // An edge end resolves when the merge keeps it, or when it lies outside the namespace.
+        let namespace_ids = ours
+            .owners
+            .keys()
+            .chain(theirs.owners.keys())
+            .copied()
+            .collect::<BTreeSet<_>>();
+        let resolves = |id: &Uuid| {
+            imported_ids.contains(id)
+                || (ours.owners.contains_key(id) && !retracted_ids.contains(id))
+                || !namespace_ids.contains(id)
+        };
+        for export in &artifact.relation_exports {
+            let relation = export.relation.as_str();
+            if matches!(relation, "crate_context" | "workspace_metadata")
+                || relation == DEPENDENCY_NAMESPACE_REL
+                || relation == EmbeddingSet::RELATION_NAME
+                || relation == ACTIVE_EMBEDDING_SET_REL
+            {
+                continue;
+            }
+            let mut rows = Vec::new();
+            for row in &export.rows {
+                if relation == "syntax_edge" {
+                    let (Some(source), Some(target)) = (
+                        export.field_uuid(row, "source_id"),
+                        export.field_uuid(row, "target_id"),
+                    ) else {
+                        continue;
+                    };
+                    let (source_in, target_in) = (
+                        imported_ids.contains(&source),
+                        imported_ids.contains(&target),
+                    );
+                    if !source_in && !target_in {
+                        continue;
+                    }
+                    if !resolves(&source) || !resolves(&target) {
+                        result.dropped_edges += 1;
+                        continue;
+                    }
+                    if !source_in || !target_in {
+                        result.relinked_edges += 1;
+                    }
+                } else if !theirs
+                    .row_owner(export, row)
+                    .is_some_and(|owner| imported_files.contains(&owner))
+                {
+                    continue;
+                }
+                rows.push(row.clone());
+            }
+            Self::push_relation_export_rows(
+                &batch,
+                &RelationExportRows {
+                    relation: export.relation.clone(),
+                    key_fields: export.key_fields.clone(),
+                    val_fields: export.val_fields.clone(),
+                    rows,
+                },
+            );
+        }
+        Self::push_relation_export_rows(&batch, Self::relation_export(artifact, "crate_context")?);
+        batch.run(&self.db).map_err(DbError::from)?;
+
+        result.hnsw_invalidated = self.invalidate_active_hnsw_index()?;
+        result.bm25_invalidated = true;
+        result.imported_node_ids = imported_ids
+            .into_iter()
+            .filter(|id| theirs.node_hashes.contains_key(id))
+            .collect();
+        result.retracted_node_ids = retracted_ids
+            .into_iter()
+            .filter(|id| ours.node_hashes.contains_key(id))
+            .collect();
+        result.preserved_embedding_ids = preserved_embedding_ids;
+        Ok(result)
+    }
+
     pub fn get_path_info(&self, path: &str) -> Result<QueryResult, PlokeError> {
         let ty = NodeType::Module;
         let rel = ty.relation_str();
@@ -3092,17 +4291,6 @@ batch[id, name, target_file, file_hash, hash, span, namespace, string_id] :=
         inner_db.count_pending_embeddings(&embedding_set)
     }
 
-    pub fn into_usize(named_rows: NamedRows) -> Result<usize, DbError> {
-        named_rows
-            .rows
-            .first()
-            .and_then(|row| row.first())
-            .and_then(|v| v.get_int())
-            .inspect(|v| trace!("the value in first row, first cell is: {:?}", v))
-            .map(|n| n as usize)
-            .ok_or(DbError::NotFound)
-    }
-
     pub fn get_pending_test(&self) -> Result<NamedRows, DbError> {
         use crate::multi_embedding::db_ext::EmbeddingExt;
         let mut rows = Vec::new();
@@ -3179,6 +4367,218 @@ batch[id, name, target_file, file_hash, hash, span, namespace, string_id] :=
         Ok(())
     }
 
+    /// Upserts the sparse BM25 vectors of `postings`, embedded with `avgdl`, and the index
+    /// parameters of their namespaces.
+    pub fn upsert_bm25_postings_batch(
+        &self,
+        avgdl: f32,
+        postings: impl IntoIterator<Item = Bm25Posting>,
+    ) -> Result<(), DbError> {
+        let mut namespaces = BTreeSet::new();
+        let postings_data: Vec<DataValue> = postings
+            .into_iter()
+            .map(|posting| {
+                namespaces.insert(posting.namespace);
+                DataValue::List(vec![
+                    DataValue::Uuid(UuidWrapper(posting.id)),
+                    DataValue::Uuid(UuidWrapper(posting.namespace)),
+                    DataValue::List(
+                        posting
+                            .token_ids
+                            .iter()
+                            .map(|token| DataValue::from(*token as i64))
+                            .collect(),
+                    ),
+                    DataValue::List(
+                        posting
+                            .token_weights
+                            .iter()
+                            .map(|weight| DataValue::from(*weight as f64))
+                            .collect(),
+                    ),
+                ])
+            })
+            .collect();
+        if postings_data.is_empty() {
+            return Ok(());
+        }
+
+        let params = BTreeMap::from([("postings".to_string(), DataValue::List(postings_data))]);
+        let script = r#"
+            postings[id, namespace, token_ids, token_weights] <- $postings
+            ?[id, at, namespace, token_ids, token_weights] :=
+                postings[id, namespace, token_ids, token_weights],
+                at = 'ASSERT'
+
+            :put bm25_doc_postings { id, at => namespace, token_ids, token_weights }
+        "#;
+        self.run_script(script, params, cozo::ScriptMutability::Mutable)
+            .map_err(|e| DbError::Cozo(e.to_string()))?;
+
+        let params = BTreeMap::from([
+            (
+                "namespaces".to_string(),
+                DataValue::List(
+                    namespaces
+                        .into_iter()
+                        .map(|ns| DataValue::List(vec![DataValue::Uuid(UuidWrapper(ns))]))
+                        .collect(),
+                ),
+            ),
+            (
+                "tokenizer_version".to_string(),
+                DataValue::from(TOKENIZER_VERSION),
+            ),
+            ("avgdl".to_string(), DataValue::from(avgdl as f64)),
+        ]);
+        let script = r#"
+            namespaces[namespace] <- $namespaces
+            docs[namespace, count(doc), sum(token_length)] :=
+                namespaces[namespace],
+                *bm25_doc_postings { id: doc, namespace @ 'NOW' },
+                *bm25_doc_meta { id: doc, token_length @ 'NOW' }
+            ?[id, at, tokenizer_version, avgdl, doc_count, total_len] :=
+                docs[id, doc_count, total_len],
+                at = 'ASSERT',
+                tokenizer_version = $tokenizer_version,
+                avgdl = $avgdl
+
+            :put bm25_index_stats { id, at => tokenizer_version, avgdl, doc_count, total_len }
+        "#;
+        self.run_script(script, params, cozo::ScriptMutability::Mutable)
+            .map_err(|e| DbError::Cozo(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Retracts the BM25 vectors and document metadata of `ids`.
+    pub fn retract_bm25_docs(&self, ids: &[Uuid]) -> Result<(), DbError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let ids_data = DataValue::List(
+            ids.iter()
+                .map(|id| DataValue::List(vec![DataValue::Uuid(UuidWrapper(*id))]))
+                .collect(),
+        );
+        let postings = r#"
+            ids[id] <- $ids
+            ?[id, at, namespace, token_ids, token_weights] :=
+                ids[id],
+                *bm25_doc_postings { id, namespace, token_ids, token_weights @ 'NOW' },
+                at = 'RETRACT'
+
+            :put bm25_doc_postings { id, at => namespace, token_ids, token_weights }
+        "#;
+        let meta = r#"
+            ids[id] <- $ids
+            ?[id, at, tracking_hash, tokenizer_version, token_length] :=
+                ids[id],
+                *bm25_doc_meta { id, tracking_hash, tokenizer_version, token_length @ 'NOW' },
+                at = 'RETRACT'
+
+            :put bm25_doc_meta { id, at => tracking_hash, tokenizer_version, token_length }
+        "#;
+        for script in [postings, meta] {
+            let params = BTreeMap::from([("ids".to_string(), ids_data.clone())]);
+            self.run_script(script, params, cozo::ScriptMutability::Mutable)
+                .map_err(|e| DbError::Cozo(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Retracts every persisted BM25 vector and index parameter, e.g. before persisting a
+    /// rebuilt index.
+    pub fn retract_bm25_index(&self) -> Result<(), DbError> {
+        let postings = r#"
+            ?[id, at, namespace, token_ids, token_weights] :=
+                *bm25_doc_postings { id, namespace, token_ids, token_weights @ 'NOW' },
+                at = 'RETRACT'
+
+            :put bm25_doc_postings { id, at => namespace, token_ids, token_weights }
+        "#;
+        let stats = r#"
+            ?[id, at, tokenizer_version, avgdl, doc_count, total_len] :=
+                *bm25_index_stats { id, tokenizer_version, avgdl, doc_count, total_len @ 'NOW' },
+                at = 'RETRACT'
+
+            :put bm25_index_stats { id, at => tokenizer_version, avgdl, doc_count, total_len }
+        "#;
+        for script in [postings, stats] {
+            self.raw_query_mut(script)?;
+        }
+        Ok(())
+    }
+
+    /// The parameters of the persisted BM25 index, per namespace.
+    pub fn bm25_index_stats(&self) -> Result<Vec<Bm25IndexStats>, DbError> {
+        let rows = self.raw_query(
+            "?[id, tokenizer_version, avgdl, doc_count, total_len] := *bm25_index_stats { id, tokenizer_version, avgdl, doc_count, total_len @ 'NOW' }",
+        )?;
+        let float = |value: &DataValue| {
+            value
+                .get_float()
+                .ok_or_else(|| DbError::Cozo(format!("Expected Float, found {value:?}")))
+        };
+        rows.rows
+            .iter()
+            .map(|row| {
+                Ok(Bm25IndexStats {
+                    namespace: to_uuid(&row[0])?,
+                    tokenizer_version: to_string(&row[1])?,
+                    avgdl: float(&row[2])? as f32,
+                    doc_count: to_usize(&row[3])?,
+                    total_len: float(&row[4])?,
+                })
+            })
+            .collect()
+    }
+
+    /// The persisted BM25 vectors with the metadata of their documents.
+    pub fn bm25_postings(&self) -> Result<Vec<(Bm25Posting, DocMeta)>, DbError> {
+        let rows = self.raw_query(
+            r#"?[id, namespace, token_ids, token_weights, tracking_hash, token_length] :=
+                *bm25_doc_postings { id, namespace, token_ids, token_weights @ 'NOW' },
+                *bm25_doc_meta { id, tracking_hash, token_length @ 'NOW' }"#,
+        )?;
+        rows.rows
+            .iter()
+            .map(|row| {
+                let list = |idx: usize| match &row[idx] {
+                    DataValue::List(items) => Ok(items),
+                    other => Err(DbError::Cozo(format!("Expected List, found {other:?}"))),
+                };
+                let token_ids = list(2)?
+                    .iter()
+                    .map(|token| to_u64(token).map(|token| token as u32))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let token_weights = list(3)?
+                    .iter()
+                    .map(|weight| {
+                        weight.get_float().map(|w| w as f32).ok_or_else(|| {
+                            DbError::Cozo(format!("Expected Float, found {weight:?}"))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let id = to_uuid(&row[0])?;
+                let namespace = to_uuid(&row[1])?;
+                let meta = DocMeta {
+                    token_length: to_usize(&row[5])?,
+                    tracking_hash: TrackingHash(to_uuid(&row[4])?),
+                    namespace,
+                };
+                Ok((
+                    Bm25Posting {
+                        id,
+                        namespace,
+                        token_ids,
+                        token_weights,
+                    },
+                    meta,
+                ))
+            })
+            .collect()
+    }
+
     /// Print counts of various information relevent to a given embedding set.
     ///
     /// The printed information shows the debug print, and will not error if the relation is not
@@ -3206,16 +4606,126 @@ batch[id, name, target_file, file_hash, hash, span, namespace, string_id] :=
         );
     }
 
-    /// Helper function `debug_print_counts_active` to for convencience to instead print the
-    /// database's currently active embedding set.
+    /// Helper function `debug_print_counts_active` to for convencience to instead print the
+    /// database's currently active embedding set.
+    ///
+    /// See `debug_print_counts` for the more general version that accepts a given embedding set.
+    pub fn debug_print_counts_active(&self) {
+        // TODO:active-embedding-set 2025-12-15
+        // update the active embedding set functions to correctly use Arc<RwLock<>> within these
+        // functions.
+        let active_embedding_set = self.with_active_set(|set| set.clone()).unwrap();
+        self.debug_print_counts(&active_embedding_set);
+    }
+}
+
+impl Database {
+    pub fn new_init() -> Result<Self, PlokeError> {
+        let db = Db::new(MemStorage::default()).map_err(|e| DbError::Cozo(e.to_string()))?;
+        db.initialize().map_err(|e| DbError::Cozo(e.to_string()))?;
+        Ok(Self::new_with_active_set(
+            db,
+            Arc::new(RwLock::new(DEFAULT_EMBEDDING_SET.clone())),
+        ))
+    }
+
+    pub fn init_with_schema() -> Result<Self, PlokeError> {
+        let db = Db::new(MemStorage::default()).map_err(|e| DbError::Cozo(e.to_string()))?;
+        db.initialize().map_err(|e| DbError::Cozo(e.to_string()))?;
+
+        // Create the schema
+        ploke_transform::schema::create_schema_all(&db)?;
+
+        let database =
+            Self::new_with_active_set(db, Arc::new(RwLock::new(DEFAULT_EMBEDDING_SET.clone())));
+        database.stamp_current_schema_version()?;
+        Ok(database)
+    }
+
+    /// Restores `backup` into a scratch in-memory database and migrates it to the current schema.
+    fn restore_migrated(backup: &Path) -> Result<Self, DbError> {
+        let scratch = cozo::new_cozo_mem().map_err(DbError::from)?;
+        scratch.restore_backup(backup).map_err(DbError::from)?;
+        let source = Self::new(scratch);
+        source.migrate()?;
+        Ok(source)
+    }
+
+    /// Restores the backup at `path` into a new in-memory database, migrated to the current
+    /// schema.
+    pub async fn create_new_backup_default(path: impl AsRef<Path>) -> Result<Database, PlokeError> {
+        Self::create_new_backup(DEFAULT_EMBEDDING_SET.clone(), path).await
+    }
+    pub async fn create_new_backup(
+        active_embedding_set: EmbeddingSet,
+        path: impl AsRef<Path>,
+    ) -> Result<Database, PlokeError> {
+        let database = Self::restore_migrated(path.as_ref())?;
+        database.set_active_set(active_embedding_set)?;
+        Ok(database)
+    }
+
+    pub fn into_usize(named_rows: NamedRows) -> Result<usize, DbError> {
+        named_rows
+            .rows
+            .first()
+            .and_then(|row| row.first())
+            .and_then(|v| v.get_int())
+            .inspect(|v| trace!("the value in first row, first cell is: {:?}", v))
+            .map(|n| n as usize)
+            .ok_or(DbError::NotFound)
+    }
+}
+
+/// Where [`Database::import_backup_with_embeddings`] reads the rows of a backup from.
+enum BackupSource<'a> {
+    /// A backup at the current schema version, read from the file.
+    Current(&'a Path),
+    /// An older backup, restored into a scratch database and migrated.
+    Migrated(Database<MemStorage>),
+}
+
+/// Whether `err` reports a stored relation that does not exist, e.g. one missing from a backup.
+pub(crate) fn is_missing_relation(err: &cozo::Error) -> bool {
+    err.to_string()
+        .contains("Cannot find requested stored relation")
+}
+
+/// How [`PersistentDatabase::open_persistent`] treats a store that already exists at the path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExistingStore {
+    /// Open the store without changing its schema, e.g. to read a saved snapshot. Relations added
+    /// to the schema since the store was written stay missing.
+    AsIs,
+    /// Migrate the store to the current schema, in place.
+    Upgrade,
+}
+
+impl PersistentDatabase {
+    /// Opens the SQLite store at `path`, creating it with the full schema if it does not exist.
     ///
-    /// See `debug_print_counts` for the more general version that accepts a given embedding set.
-    pub fn debug_print_counts_active(&self) {
-        // TODO:active-embedding-set 2025-12-15
-        // update the active embedding set functions to correctly use Arc<RwLock<>> within these
-        // functions.
-        let active_embedding_set = self.with_active_set(|set| set.clone()).unwrap();
-        self.debug_print_counts(&active_embedding_set);
+    /// Existing stores, including Cozo backups, are used in place rather than imported into
+    /// memory. `existing` decides whether such a store may be changed to match the current
+    /// schema: pass [`ExistingStore::AsIs`] for files this process does not own. The active
+    /// embedding set starts as the default one; call [`Database::restore_embedding_set`] to pick
+    /// up the set recorded for a crate.
+    pub fn open_persistent(
+        path: impl AsRef<Path>,
+        existing: ExistingStore,
+    ) -> Result<Self, PlokeError> {
+        let path = path.as_ref();
+        let is_new = !path.exists();
+        let db = cozo::new_cozo_sqlite(path).map_err(DbError::from)?;
+        if is_new {
+            ploke_transform::schema::create_schema_all(&db)?;
+        }
+        let database = Self::new(db);
+        if is_new {
+            database.stamp_current_schema_version()?;
+        } else if existing == ExistingStore::Upgrade {
+            database.migrate()?;
+        }
+        Ok(database)
     }
 }
 
@@ -3226,7 +4736,9 @@ mod tests {
     use super::*;
     use crate::Database;
     use crate::DbError;
+    use crate::PersistentDatabase;
     use crate::RestoredEmbeddingSet;
+    use crate::migration::SCHEMA_MIGRATION_REL;
     use crate::multi_embedding::db_ext::EmbeddingExt;
     use crate::multi_embedding::hnsw_ext::HnswExt;
     use crate::multi_embedding::schema::CozoEmbeddingSetExt;
@@ -3262,7 +4774,9 @@ mod tests {
     use tracing_subscriber::util::SubscriberInitExt;
     use uuid::Uuid;
 
-    use ploke_test_utils::{WS_FIXTURE_01_CANONICAL, fresh_backup_fixture_db, workspace_root};
+    use ploke_test_utils::{
+        FIXTURE_NODES_CANONICAL, WS_FIXTURE_01_CANONICAL, fresh_backup_fixture_db, workspace_root,
+    };
 
     fn setup_db() -> Database {
         let db = Db::new(MemStorage::default()).unwrap();
@@ -3979,6 +5493,153 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn persistent_database_opens_backup_in_place() -> Result<(), PlokeError> {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("graph.sqlite");
+
+        let db = Database::init_with_schema()?;
+        db.setup_multi_embedding()?;
+        let set = EmbeddingSet::new(
+            EmbeddingProviderSlug::new_from_str("openrouter"),
+            EmbeddingModelId::new_from_str("test-model-512"),
+            EmbeddingShape::new_dims_default(512),
+        );
+        db.put_embedding_set(&set)?;
+        db.ensure_vector_embedding_relation(&set)?;
+        db.set_active_set(set.clone())?;
+        db.update_embeddings_batch(vec![(Uuid::new_v4(), vec![0.0_f32; 512])])?;
+        db.write_backup_to_path(&path)?;
+
+        let persisted = PersistentDatabase::open_persistent(&path, ExistingStore::AsIs)?;
+        assert_eq!(persisted.relations_vec()?, db.relations_vec()?);
+        let (restored, _) = persisted
+            .restore_embedding_set("fixture_nodes")?
+            .expect("populated set in the store");
+        assert_eq!(restored.hash_id().into_inner(), set.hash_id().into_inner());
+
+        // Writes go to the store itself, so they are there when it is opened again.
+        persisted.update_embeddings_batch(vec![(Uuid::new_v4(), vec![0.0_f32; 512])])?;
+        drop(persisted);
+        let reopened = PersistentDatabase::open_persistent(&path, ExistingStore::AsIs)?;
+        assert_eq!(reopened.count_embeddings_for_set(&set)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn persistent_database_upgrades_existing_store_only_when_asked() -> Result<(), PlokeError> {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("old.sqlite");
+
+        // A store written before the `compilation_unit` relation and schema versions existed.
+        let db = Database::init_with_schema()?;
+        db.raw_query_mut("::remove compilation_unit")?;
+        db.raw_query_mut(&format!("::remove {SCHEMA_MIGRATION_REL}"))?;
+        db.write_backup_to_path(&path)?;
+        let has_compilation_unit = |db: &PersistentDatabase| -> Result<bool, PlokeError> {
+            Ok(db
+                .relations_vec()?
+                .iter()
+                .any(|rel| rel == "compilation_unit"))
+        };
+
+        let as_is = PersistentDatabase::open_persistent(&path, ExistingStore::AsIs)?;
+        assert!(!has_compilation_unit(&as_is)?);
+        drop(as_is);
+        let reopened = PersistentDatabase::open_persistent(&path, ExistingStore::AsIs)?;
+        assert!(
+            !has_compilation_unit(&reopened)?,
+            "AsIs must not change the store"
+        );
+        drop(reopened);
+
+        let upgraded = PersistentDatabase::open_persistent(&path, ExistingStore::Upgrade)?;
+        assert!(has_compilation_unit(&upgraded)?);
+        assert_eq!(
+            upgraded.schema_version()?,
+            crate::migration::CURRENT_SCHEMA_VERSION
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn persistent_database_creates_schema_for_new_store() -> Result<(), PlokeError> {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let persisted = PersistentDatabase::open_persistent(
+            dir.path().join("new.sqlite"),
+            ExistingStore::AsIs,
+        )?;
+        let in_memory = Database::init_with_schema()?;
+        assert_eq!(persisted.relations_vec()?, in_memory.relations_vec()?);
+        assert!(persisted.list_crate_context_rows()?.is_empty());
+        Ok(())
+    }
+
+    fn put_test_function(db: &Database, id: Uuid, name: &str, vis_kind: &str, hash: Uuid) {
+        db.raw_query_mut(&format!(
+            r#"?[id, at, name, vis_kind, span, tracking_hash, cfgs, module_id] <- [[to_uuid("{id}"), 'ASSERT', "{name}", "{vis_kind}", [0, 1], to_uuid("{hash}"), [], to_uuid("{id}")]]
+:put function {{ id, at => name, vis_kind, span, tracking_hash, cfgs, module_id }}"#
+        ))
+        .expect("put function");
+        std::thread::sleep(std::time::Duration::from_millis(2));
+    }
+
+    #[test]
+    fn graph_snapshots_query_and_diff_the_graph_as_of_a_commit() -> Result<(), PlokeError> {
+        let db = Database::init_with_schema()?;
+        let (kept, changed, removed, added) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+
+        let first = SnapshotRun::begin(SnapshotTag::Commit("a1b2c3".to_string()));
+        put_test_function(&db, kept, "kept", "public", Uuid::nil());
+        put_test_function(&db, changed, "changed", "public", Uuid::nil());
+        put_test_function(&db, removed, "removed", "public", Uuid::nil());
+        let first = db.record_graph_snapshot(first)?;
+        std::thread::sleep(std::time::Duration::from_millis(2));
+
+        let second = SnapshotRun::begin(SnapshotTag::Commit("d4e5f6".to_string()));
+        put_test_function(&db, changed, "changed", "crate", Uuid::nil());
+        put_test_function(&db, added, "added", "public", Uuid::nil());
+        db.raw_query_mut(&format!(
+            r#"?[id, at, name, vis_kind, span, tracking_hash, cfgs, module_id] := *function {{ id, name, vis_kind, span, tracking_hash, cfgs, module_id @ 'NOW' }}, id = to_uuid("{removed}"), at = 'RETRACT'
+:put function {{ id, at => name, vis_kind, span, tracking_hash, cfgs, module_id }}"#
+        ))?;
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = db.record_graph_snapshot(second)?;
+
+        assert_eq!(
+            db.list_graph_snapshots()?,
+            vec![first.clone(), second.clone()]
+        );
+        assert_eq!(
+            db.graph_snapshot(&SnapshotTag::Commit("a1b2c3".to_string()))?,
+            Some(first.clone())
+        );
+
+        let names = |snapshot: &GraphSnapshot| -> Result<Vec<String>, DbError> {
+            let rows = db.raw_query_as_of(
+                "?[name] := *function { name @ $as_of } :order name",
+                snapshot,
+            )?;
+            rows.rows.iter().map(|row| to_string(&row[0])).collect()
+        };
+        assert_eq!(names(&first)?, vec!["changed", "kept", "removed"]);
+        assert_eq!(names(&second)?, vec!["added", "changed", "kept"]);
+
+        let diff = db.diff_graph_snapshots(&first, &second)?;
+        let ids = |items: &[SnapshotItem]| items.iter().map(|item| item.id).collect::<Vec<_>>();
+        assert_eq!(ids(&diff.added), vec![added]);
+        assert_eq!(ids(&diff.removed), vec![removed]);
+        assert_eq!(ids(&diff.changed), vec![changed]);
+        assert_eq!(diff.changed[0].vis_kind, "crate");
+        assert!(db.diff_graph_snapshots(&second, &second)?.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn workspace_fixture_namespace_inventory_matches_crate_context_membership()
     -> Result<(), PlokeError> {
@@ -4152,6 +5813,15 @@ id = to_uuid("{seeded_node}")"#
             .rows
             .len();
         assert_eq!(bm25_before, 1, "fixture setup should seed bm25 metadata");
+        db.upsert_bm25_postings_batch(
+            1.0,
+            [crate::bm25_index::Bm25Posting {
+                id: seeded_node,
+                namespace: removed.namespace,
+                token_ids: vec![1],
+                token_weights: vec![1.0],
+            }],
+        )?;
 
         let result = db.remove_namespace(removed.namespace).await?;
 
@@ -4222,7 +5892,7 @@ id = to_uuid("{seeded_node}")"#
             .join(" or ");
         let mut descendant_relations = NodeType::all_variants()
             .into_iter()
-            .filter(|ty| *ty != NodeType::SyntaxEdge)
+            .filter(|ty| *ty != NodeType::SyntaxEdge && !ty.is_parse_detail())
             .map(|ty| ty.relation_str().to_string())
             .collect::<Vec<_>>();
         descendant_relations.push("method".to_string());
@@ -4232,7 +5902,7 @@ id = to_uuid("{seeded_node}")"#
             let key_fields = NodeType::all_variants()
                 .into_iter()
                 .find(|ty| ty.relation_str() == relation)
-                .filter(|ty| *ty != NodeType::SyntaxEdge)
+                .filter(|ty| *ty != NodeType::SyntaxEdge && !ty.is_parse_detail())
                 .map(|ty| ty.keys().collect::<Vec<_>>())
                 .unwrap_or_else(|| {
                     if relation == "method" {
@@ -4292,6 +5962,19 @@ id = to_uuid("{seeded_node}")"#
             bm25_after.rows.is_empty(),
             "removed namespace should retract bm25 metadata for removed nodes"
         );
+        let bm25_postings_after = db.raw_query(&format!(
+            r#"?[id] := *bm25_doc_postings {{ id, namespace @ 'NOW' }}, id = to_uuid("{seeded_node}")"#
+        ))?;
+        assert!(
+            bm25_postings_after.rows.is_empty(),
+            "removed namespace should retract persisted bm25 postings"
+        );
+        assert!(
+            db.bm25_index_stats()?
+                .iter()
+                .all(|stats| stats.namespace != removed.namespace),
+            "removed namespace should retract its bm25 index stats"
+        );
 
         Ok(())
     }
@@ -4639,4 +6322,577 @@ id = to_uuid("{exported_seeded_node}")"#
 
         Ok(())
     }
+
+    /// Sets the tracking hash of `id` in every relation export of `artifact`.
+    fn set_exported_tracking_hash(artifact: &mut NamespaceExportArtifact, id: Uuid, hash: Uuid) {
+        for export in &mut artifact.relation_exports {
+            let fields = export
+                .key_fields
+                .iter()
+                .chain(export.val_fields.iter())
+                .collect::<Vec<_>>();
+            let (Some(id_idx), Some(hash_idx)) = (
+                fields.iter().position(|field| *field == "id"),
+                fields.iter().position(|field| *field == "tracking_hash"),
+            ) else {
+                continue;
+            };
+            for row in &mut export.rows {
+                if to_uuid(&row[id_idx]).ok() == Some(id) {
+                    row[hash_idx] = DataValue::Uuid(UuidWrapper(hash));
+                }
+            }
+        }
+    }
+
+    /// Drops every row owned by the file module `owner`, as if the file was deleted.
+    fn drop_exported_file(artifact: &mut NamespaceExportArtifact, owner: Uuid) {
+        let ids = NamespaceArtifactIndex::new(artifact).ids_owned_by(&BTreeSet::from([owner]));
+        for export in &mut artifact.relation_exports {
+            let key_len = export.key_fields.len();
+            export.rows.retain(|row| {
+                !row[..key_len]
+                    .iter()
+                    .filter_map(|value| to_uuid(value).ok())
+                    .any(|id| ids.contains(&id))
+            });
+        }
+    }
+
+    /// Paths and file module ids of the files of an export, except the crate root.
+    fn exported_non_root_files(artifact: &NamespaceExportArtifact) -> Vec<(String, Uuid)> {
+        NamespaceArtifactIndex::new(artifact)
+            .file_states()
+            .into_iter()
+            .filter(|(path, _)| !path.ends_with("lib.rs"))
+            .map(|(path, (owner, _))| (path, owner))
+            .collect()
+    }
+
+    fn loaded_tracking_hash(db: &Database, relation: &str, id: Uuid) -> Result<Uuid, DbError> {
+        let rows = db.raw_query(&format!(
+            r#"?[tracking_hash] := *{relation} {{ id, tracking_hash @ 'NOW' }}, id = to_uuid("{id}")"#
+        ))?;
+        rows.rows
+            .first()
+            .and_then(|row| row.first())
+            .ok_or(DbError::NotFound)
+            .and_then(to_uuid)
+    }
+
+    #[test]
+    fn namespace_artifact_diff_reports_changed_and_removed_files() -> Result<(), PlokeError> {
+        let db = fresh_local_backup_fixture_db(&FIXTURE_NODES_CANONICAL)?;
+        let contexts = db.list_crate_context_rows()?;
+        let [context] = contexts.as_slice() else {
+            panic!("expected exactly one crate context for fixture_nodes");
+        };
+        let original = db.export_namespace(context.namespace)?;
+        assert!(original.diff(&original).is_empty());
+
+        let files = exported_non_root_files(&original);
+        let [(modified_path, modified), (removed_path, removed), ..] = files.as_slice() else {
+            panic!("fixture_nodes should have at least two non-root files");
+        };
+        let index = NamespaceArtifactIndex::new(&original);
+        let removed_ids = index.ids_owned_by(&BTreeSet::from([*removed]));
+
+        let mut newer = original.clone();
+        set_exported_tracking_hash(&mut newer, *modified, Uuid::new_v4());
+        drop_exported_file(&mut newer, *removed);
+        let diff = original.diff(&newer);
+
+        assert!(!diff.crate_context_changed);
+        assert_eq!(
+            diff.files,
+            BTreeMap::from([
+                (modified_path.clone(), NamespaceFileChange::Modified),
+                (removed_path.clone(), NamespaceFileChange::Removed),
+            ])
+        );
+        assert_eq!(diff.changed_nodes, BTreeSet::from([*modified]));
+        assert!(diff.added_nodes.is_empty());
+        assert!(diff.removed_nodes.contains(removed));
+        assert!(diff.removed_nodes.iter().all(|id| removed_ids.contains(id)));
+        assert!(diff.added_edges.is_empty());
+        assert!(
+            diff.removed_edges
+                .iter()
+                .any(|(_, target, kind)| target == removed && kind == "Contains"),
+            "the module declaring the removed file should lose its Contains edge"
+        );
+
+        let reverse = newer.diff(&original);
+        assert_eq!(
+            reverse.files.get(removed_path),
+            Some(&NamespaceFileChange::Added)
+        );
+        assert_eq!(reverse.added_nodes, diff.removed_nodes);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_namespace_replaces_changed_files_and_keeps_embeddings_of_unchanged_nodes()
+    -> Result<(), PlokeError> {
+        let db = fresh_local_backup_fixture_db(&FIXTURE_NODES_CANONICAL)?;
+        let contexts = db.list_crate_context_rows()?;
+        let [context] = contexts.as_slice() else {
+            panic!("expected exactly one crate context for fixture_nodes");
+        };
+        let original = db.export_namespace(context.namespace)?;
+        let index = NamespaceArtifactIndex::new(&original);
+        let (path, owner, nodes) = index
+            .file_states()
+            .into_iter()
+            .filter(|(path, _)| !path.ends_with("lib.rs"))
+            .find_map(|(path, (owner, _))| {
+                let nodes = index
+                    .ids_owned_by(&BTreeSet::from([owner]))
+                    .into_iter()
+                    .filter(|id| *id != owner)
+                    .filter(|id| index.node_hashes.get(id).copied().flatten().is_some())
+                    .collect::<Vec<_>>();
+                (nodes.len() >= 2).then_some((path, owner, nodes))
+            })
+            .expect("fixture_nodes should have a file with two hashed nodes");
+        let (changed, unchanged) = (nodes[0], nodes[1]);
+
+        let mut incoming = original.clone();
+        let changed_hash = Uuid::new_v4();
+        set_exported_tracking_hash(&mut incoming, owner, Uuid::new_v4());
+        set_exported_tracking_hash(&mut incoming, changed, changed_hash);
+
+        db.setup_multi_embedding()?;
+        let active_set = db.with_active_set(|set| set.clone())?;
+        let dims = active_set.dims() as usize;
+        db.update_embeddings_batch(vec![
+            (changed, vec![0.0; dims]),
+            (unchanged, vec![0.0; dims]),
+        ])?;
+
+        let result = db
+            .merge_namespace(&incoming, None)
+            .await
+            .expect("merging over the loaded namespace should succeed");
+
+        assert_eq!(result.merged_namespace, context.namespace);
+        assert_eq!(result.updated_files, vec![path]);
+        assert!(result.added_files.is_empty());
+        assert!(result.removed_files.is_empty());
+        assert!(result.kept_files.is_empty());
+        assert!(result.conflicted_files.is_empty());
+        assert!(result.imported_node_ids.contains(&changed));
+        assert_eq!(result.imported_node_ids, result.retracted_node_ids);
+        assert!(result.preserved_embedding_ids.contains(&unchanged));
+        assert!(!result.preserved_embedding_ids.contains(&changed));
+        assert!(!result.preserved_embedding_ids.contains(&owner));
+        assert!(
+            result.relinked_edges > 0,
+            "the file module is declared in lib.rs"
+        );
+        assert_eq!(result.dropped_edges, 0);
+        assert!(result.bm25_invalidated);
+
+        let vector_rows = |id: Uuid| {
+            db.raw_query(&format!(
+                r#"?[node_id] := *{} {{ node_id, embedding_set_id, vector @ 'NOW' }}, node_id = to_uuid("{id}")"#,
+                active_set.rel_name()
+            ))
+            .map(|rows| rows.rows.len())
+        };
+        assert_eq!(vector_rows(unchanged)?, 1);
+        assert_eq!(
+            vector_rows(changed)?,
+            0,
+            "embeddings of changed nodes are stale after the merge"
+        );
+
+        let merged = db.export_namespace(context.namespace)?;
+        let diff = incoming.diff(&merged);
+        assert!(
+            diff.is_empty(),
+            "merged namespace should match the incoming artifact: {diff:?}"
+        );
+        assert_eq!(
+            db.collect_namespace_inventory(context.namespace)?
+                .descendant_ids,
+            original.descendant_ids
+        );
+
+        let again = db
+            .merge_namespace(&merged, None)
+            .await
+            .expect("merging an identical artifact should succeed");
+        assert!(again.updated_files.is_empty());
+        assert!(again.retracted_node_ids.is_empty());
+        assert!(!again.bm25_invalidated);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_namespace_with_base_keeps_local_changes_and_reports_conflicts()
+    -> Result<(), PlokeError> {
+        let db = fresh_local_backup_fixture_db(&FIXTURE_NODES_CANONICAL)?;
+        let contexts = db.list_crate_context_rows()?;
+        let [context] = contexts.as_slice() else {
+            panic!("expected exactly one crate context for fixture_nodes");
+        };
+        let mut base = db.export_namespace(context.namespace)?;
+        let files = exported_non_root_files(&base);
+        let [
+            (upstream_path, upstream),
+            (both_path, both),
+            (local_path, local),
+            (deleted_path, deleted),
+            ..,
+        ] = files.as_slice()
+        else {
+            panic!("fixture_nodes should have at least four non-root files");
+        };
+        let local_hash = loaded_tracking_hash(&db, "module", *local)?;
+
+        // The loaded namespace holds the base except for `local`, changed since the base.
+        let mut incoming = base.clone();
+        let upstream_hash = Uuid::new_v4();
+        set_exported_tracking_hash(&mut incoming, *upstream, upstream_hash);
+        let both_hash = Uuid::new_v4();
+        set_exported_tracking_hash(&mut incoming, *both, both_hash);
+        set_exported_tracking_hash(&mut base, *both, Uuid::new_v4());
+        let local_base_hash = Uuid::new_v4();
+        set_exported_tracking_hash(&mut incoming, *local, local_base_hash);
+        set_exported_tracking_hash(&mut base, *local, local_base_hash);
+        drop_exported_file(&mut incoming, *deleted);
+
+        let result = db
+            .merge_namespace(&incoming, Some(&base))
+            .await
+            .expect("three-way merge should succeed");
+
+        let mut updated = vec![upstream_path.clone(), both_path.clone()];
+        updated.sort();
+        assert_eq!(result.updated_files, updated);
+        assert_eq!(result.conflicted_files, vec![both_path.clone()]);
+        assert_eq!(result.kept_files, vec![local_path.clone()]);
+        assert_eq!(result.removed_files, vec![deleted_path.clone()]);
+        assert!(result.added_files.is_empty());
+        assert!(result.retracted_node_ids.contains(deleted));
+        assert!(!result.imported_node_ids.contains(deleted));
+
+        assert_eq!(
+            loaded_tracking_hash(&db, "module", *upstream)?,
+            upstream_hash
+        );
+        assert_eq!(loaded_tracking_hash(&db, "module", *both)?, both_hash);
+        assert_eq!(loaded_tracking_hash(&db, "module", *local)?, local_hash);
+        assert!(matches!(
+            loaded_tracking_hash(&db, "module", *deleted),
+            Err(DbError::NotFound)
+        ));
+
+        let inventory = db.collect_namespace_inventory(context.namespace)?;
+        assert!(!inventory.file_module_owner_ids.contains(deleted));
+        assert!(!inventory.descendant_ids.contains(deleted));
+        for owner in [upstream, both, local] {
+            assert!(inventory.file_module_owner_ids.contains(owner));
+            assert!(inventory.descendant_ids.contains(owner));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_namespace_keeps_the_newer_version_of_a_conflicting_file()
+    -> Result<(), PlokeError> {
+        let db = fresh_local_backup_fixture_db(&FIXTURE_NODES_CANONICAL)?;
+        let contexts = db.list_crate_context_rows()?;
+        let [context] = contexts.as_slice() else {
+            panic!("expected exactly one crate context for fixture_nodes");
+        };
+        let mut base = db.export_namespace(context.namespace)?;
+        let files = exported_non_root_files(&base);
+        let [(older_path, older), (newer_path, newer), ..] = files.as_slice() else {
+            panic!("fixture_nodes should have at least two non-root files");
+        };
+        let loaded_hash = loaded_tracking_hash(&db, "module", *older)?;
+
+        // Both files changed on both sides since the base; only `newer` was written after the
+        // loaded version.
+        let mut incoming = base.clone();
+        for owner in [older, newer] {
+            set_exported_tracking_hash(&mut incoming, *owner, Uuid::new_v4());
+            set_exported_tracking_hash(&mut base, *owner, Uuid::new_v4());
+        }
+        let newer_hash = Uuid::new_v4();
+        set_exported_tracking_hash(&mut incoming, *newer, newer_hash);
+        incoming.file_timestamps.insert(older_path.clone(), 0);
+        incoming
+            .file_timestamps
+            .insert(newer_path.clone(), i64::MAX);
+
+        let result = db
+            .merge_namespace(&incoming, Some(&base))
+            .await
+            .expect("three-way merge should succeed");
+
+        let mut conflicted = vec![older_path.clone(), newer_path.clone()];
+        conflicted.sort();
+        assert_eq!(result.conflicted_files, conflicted);
+        assert_eq!(result.kept_files, vec![older_path.clone()]);
+        assert_eq!(result.updated_files, vec![newer_path.clone()]);
+        assert_eq!(loaded_tracking_hash(&db, "module", *older)?, loaded_hash);
+        assert_eq!(loaded_tracking_hash(&db, "module", *newer)?, newer_hash);
+        Ok(())
+    }
+
+    #[test]
+    fn apply_graph_diff_retracts_embeddings_and_bm25_meta_of_stale_nodes() -> Result<(), PlokeError>
+    {
+        use syn_parser::incremental::IncrementalParser;
+
+        let dir = tempfile::tempdir().expect("temp dir");
+        let crate_root = dir.path();
+        std::fs::create_dir_all(crate_root.join("src")).expect("create src");
+        std::fs::write(
+            crate_root.join("Cargo.toml"),
+            "[package]\nname = \"diff_fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .expect("write manifest");
+        std::fs::write(
+            crate_root.join("src/lib.rs"),
+            "pub fn keep() {}\n\npub fn edited() {}\n",
+        )
+        .expect("write lib.rs");
+
+        let db = Database::init_with_schema()?;
+        let mut parser = IncrementalParser::new(crate_root)
+            .map_err(|e| DbError::QueryExecution(e.to_string()))?;
+        ploke_transform::transform::transform_parsed_graph(
+            &db,
+            parser.merged_graph().clone(),
+            parser.module_tree(),
+        )?;
+        let function_id = |name: &str| -> Result<Uuid, DbError> {
+            let rows = db.raw_query(&format!(
+                r#"?[id] := *function {{ id, name @ 'NOW' }}, name = "{name}""#
+            ))?;
+            rows.rows
+                .first()
+                .and_then(|row| row.first())
+                .ok_or(DbError::NotFound)
+                .and_then(to_uuid)
+        };
+        let (keep, edited) = (function_id("keep")?, function_id("edited")?);
+
+        db.setup_multi_embedding()?;
+        let active_set = db.with_active_set(|set| set.clone())?;
+        let dims = active_set.dims() as usize;
+        db.update_embeddings_batch(vec![(keep, vec![0.0; dims]), (edited, vec![0.0; dims])])?;
+        db.upsert_bm25_doc_meta_batch([keep, edited].into_iter().map(|id| {
+            (
+                id,
+                DocMeta {
+                    token_length: 1,
+                    tracking_hash: TrackingHash(Uuid::new_v4()),
+                    namespace: Uuid::new_v4(),
+                },
+            )
+        }))?;
+
+        std::fs::write(
+            crate_root.join("src/lib.rs"),
+            "pub fn keep() {}\n\npub fn edited() -> i32 {\n    1\n}\n",
+        )
+        .expect("rewrite lib.rs");
+        let diff = parser
+            .update()
+            .map_err(|e| DbError::QueryExecution(e.to_string()))?;
+        db.apply_graph_diff(parser.merged_graph().clone(), parser.module_tree(), &diff)?;
+
+        let vector_rows = |id: Uuid| {
+            db.raw_query(&format!(
+                r#"?[node_id] := *{} {{ node_id, embedding_set_id, vector @ 'NOW' }}, node_id = to_uuid("{id}")"#,
+                active_set.rel_name()
+            ))
+            .map(|rows| rows.rows.len())
+        };
+        let meta_rows = |id: Uuid| {
+            db.raw_query(&format!(
+                r#"?[id] := *bm25_doc_meta {{ id, tracking_hash @ 'NOW' }}, id = to_uuid("{id}")"#
+            ))
+            .map(|rows| rows.rows.len())
+        };
+        assert_eq!(vector_rows(keep)?, 1);
+        assert_eq!(meta_rows(keep)?, 1);
+        assert_eq!(
+            vector_rows(edited)?,
+            0,
+            "the edited function must be embedded again"
+        );
+        assert_eq!(meta_rows(edited)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn reindexing_a_namespace_retracts_deleted_nodes_but_keeps_their_history()
+    -> Result<(), PlokeError> {
+        use syn_parser::incremental::IncrementalParser;
+
+        let dir = tempfile::tempdir().expect("temp dir");
+        let crate_root = dir.path();
+        std::fs::create_dir_all(crate_root.join("src")).expect("create src");
+        std::fs::write(
+            crate_root.join("Cargo.toml"),
+            "[package]\nname = \"reindex_fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .expect("write manifest");
+        std::fs::write(
+            crate_root.join("src/lib.rs"),
+            "pub fn keep() {}\n\npub fn edited() -> u8 {\n    1\n}\n\npub fn gone() {}\n",
+        )
+        .expect("write lib.rs");
+
+        let db = Database::init_with_schema()?;
+        let parse = |db: &Database| -> Result<WriteBatch, PlokeError> {
+            let parser = IncrementalParser::new(crate_root)
+                .map_err(|e| DbError::QueryExecution(e.to_string()))?;
+            let batch = WriteBatch::default();
+            ploke_transform::transform::push_parsed_graph(
+                db,
+                &batch,
+                parser.merged_graph().clone(),
+                parser.module_tree(),
+            )?;
+            Ok(batch)
+        };
+        let first = SnapshotRun::begin(SnapshotTag::new_run());
+        parse(&db)?.run(&db).map_err(DbError::from)?;
+        let first = db.record_graph_snapshot(first)?;
+        let function_ids = |script: &str, params| -> Result<BTreeMap<String, Uuid>, DbError> {
+            db.raw_query_params(script, params)?
+                .rows
+                .iter()
+                .map(|row| Ok((to_string(&row[0])?, to_uuid(&row[1])?)))
+                .collect()
+        };
+        let current = "?[name, id] := *function { name, id @ 'NOW' }";
+        let before = function_ids(current, BTreeMap::new())?;
+        let (keep, edited, gone) = (before["keep"], before["edited"], before["gone"]);
+
+        db.setup_multi_embedding()?;
+        let active_set = db.with_active_set(|set| set.clone())?;
+        let dims = active_set.dims() as usize;
+        db.update_embeddings_batch(
+            [keep, edited, gone]
+                .into_iter()
+                .map(|id| (id, vec![0.0; dims]))
+                .collect(),
+        )?;
+
+        let namespace = db.list_crate_context_rows()?[0].namespace;
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let failing = WriteBatch::default();
+        failing.push(
+            "?[id] := *missing { id }\n:rm function { id }",
+            BTreeMap::new(),
+        );
+        assert!(db.reindex_namespaces(&[namespace], failing).is_err());
+        assert_eq!(
+            function_ids(current, BTreeMap::new())?,
+            before,
+            "a failing re-index leaves the namespace as it was"
+        );
+
+        std::fs::write(
+            crate_root.join("src/lib.rs"),
+            "pub fn keep() {}\n\npub fn edited() -> u8 {\n    2\n}\n",
+        )
+        .expect("rewrite");
+        let previous = db.reindex_namespaces(&[namespace], parse(&db)?)?;
+        assert_eq!(previous.len(), 1);
+        let stale_ids = db.retract_stale_search_rows(namespace, &previous[0].1)?;
+
+        assert_eq!(
+            stale_ids,
+            BTreeSet::from([edited, gone]),
+            "deleted and edited functions must be embedded again"
+        );
+        assert_eq!(
+            function_ids(current, BTreeMap::new())?
+                .into_keys()
+                .collect::<Vec<_>>(),
+            vec!["edited", "keep"]
+        );
+        let as_of_first = db.raw_query_as_of("?[name] := *function { name @ $as_of }", &first)?;
+        assert_eq!(
+            as_of_first.rows.len(),
+            3,
+            "the first snapshot still sees every function"
+        );
+        assert_eq!(db.count_embeddings_for_set(&active_set)?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dependency_namespace_is_read_only_until_removed_as_dependency()
+    -> Result<(), PlokeError> {
+        use ploke_common::fixtures_crates_dir;
+        use syn_parser::discovery::DependencyOrigin;
+        use syn_parser::{DependencySource, ParsedCrate, ParsedDependency};
+
+        let db = Database::init_with_schema()?;
+        assert!(db.list_dependency_namespaces()?.is_empty());
+
+        let root_path = fixtures_crates_dir().join("fixture_nodes");
+        let mut output = syn_parser::try_run_phases_and_merge(&root_path)
+            .map_err(|e| DbError::QueryExecution(e.to_string()))?;
+        output
+            .merged_graph
+            .as_mut()
+            .expect("merged graph")
+            .retain_public_api();
+        let parsed =
+            ParsedCrate::try_from(output).map_err(|e| DbError::QueryExecution(e.to_string()))?;
+        let namespace = parsed.crate_context.namespace;
+        let source = DependencySource {
+            name: parsed.crate_context.name.clone(),
+            version: parsed.crate_context.version.clone(),
+            source: "registry+https://github.com/rust-lang/crates.io-index".to_string(),
+            origin: DependencyOrigin::Registry,
+            root_path,
+        };
+        ploke_transform::transform::transform_parsed_dependency(
+            &db,
+            ParsedDependency { source, parsed },
+        )?;
+
+        let dependencies = db.list_dependency_namespaces()?;
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(dependencies[0].namespace, namespace);
+        assert_eq!(dependencies[0].name, "fixture_nodes");
+        assert_eq!(dependencies[0].origin, "registry");
+        assert!(db.is_dependency_namespace(namespace)?);
+
+        let err = db
+            .remove_namespace(namespace)
+            .await
+            .expect_err("dependency namespaces are read-only");
+        assert_eq!(err, DbError::ReadOnlyNamespace(namespace));
+        assert_eq!(db.list_crate_context_rows()?.len(), 1);
+
+        let artifact = db.export_namespace(namespace)?;
+        let err = db
+            .import_namespace(&artifact)
+            .await
+            .expect_err("dependency namespaces are read-only");
+        assert!(matches!(
+            err,
+            NamespaceImportError::Db(DbError::ReadOnlyNamespace(id)) if id == namespace
+        ));
+
+        let removed = db.remove_dependency_namespace(namespace).await?;
+        assert_eq!(removed.removed_namespace, namespace);
+        assert!(db.list_dependency_namespaces()?.is_empty());
+        assert!(db.list_crate_context_rows()?.is_empty());
+
+        Ok(())
+    }
 }
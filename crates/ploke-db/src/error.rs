@@ -38,6 +38,9 @@ pub enum DbError {
     #[error("Item not found")]
     NotFound,
 
+    #[error("Namespace {0} holds an indexed dependency and is read-only")]
+    ReadOnlyNamespace(uuid::Uuid),
+
     #[error("Error encountered for callback construction")]
     CallbackErr,
 
@@ -1,7 +1,10 @@
-use cozo::NamedRows;
+use std::collections::{BTreeMap, HashMap};
+
+use cozo::{DataValue, NamedRows, UuidWrapper};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use ploke_core::{TrackingHash, rag_types::ContextPart};
+use ploke_transform::schema::CozoStorage;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -148,6 +151,47 @@ pub trait GetNodeInfo: ImmutQuery {
         );
         self.raw_query(&query)
     }
+
+    /// The node type of each of `ids` that is a primary or associated node; other ids are left
+    /// out of the map.
+    fn node_types_of(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, NodeType>, DbError> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let types = NodeType::primary_and_assoc_nodes();
+        let rules = types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                format!(
+                    "?[id, ty] := ids[id], *{rel}{{ id @ 'NOW' }}, ty = {i}",
+                    rel = ty.relation_str()
+                )
+            })
+            .join("\n");
+        let query = format!("ids[id] := id in $ids\n{rules}");
+        let params = BTreeMap::from([(
+            "ids".to_string(),
+            DataValue::List(
+                ids.iter()
+                    .map(|id| DataValue::Uuid(UuidWrapper(*id)))
+                    .collect(),
+            ),
+        )]);
+
+        self.raw_query_with_params(&query, params)?
+            .rows
+            .iter()
+            .map(|row| {
+                let id = to_uuid(&row[0])?;
+                let ty = row[1]
+                    .get_int()
+                    .and_then(|i| types.get(i as usize))
+                    .ok_or_else(|| DbError::QueryExecution("invalid node type index".into()))?;
+                Ok((id, *ty))
+            })
+            .collect()
+    }
 }
 
-impl GetNodeInfo for Database {}
+impl<S: CozoStorage> GetNodeInfo for Database<S> {}
@@ -1,6 +1,6 @@
 use crate::{
     Database, DbError, NodeType,
-    database::to_string,
+    database::{to_string, to_string_list, to_uuid},
     get_by_id::COMMON_FIELDS_EMBEDDED,
     result::{get_pos, typed_rows::ResolvedEdgeData},
 };
@@ -300,6 +300,121 @@ pub fn count_edges_by_kind(db: &Database, relation_kind: &str) -> Result<usize,
     Database::into_usize(qr)
 }
 
+/// A test covering a node, see [`tests_covering`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoveringTestRow {
+    pub id: uuid::Uuid,
+    /// Name of the test function, or `<item> (doctest <n>)` for doc-tests.
+    pub name: String,
+    /// `Unit`, `Integration`, `Doc` or `Bench`.
+    pub kind: String,
+    pub file_path: PathBuf,
+    /// Why the test covers the node: `Call`, `EnclosingModule` or `Documented`.
+    pub coverage: String,
+    pub ignored: bool,
+}
+
+/// Lists the tests that exercise the node `node_id` at NOW: the tests calling it or documenting
+/// it, and the tests of `#[cfg(test)]` modules whose module under test contains it.
+pub fn tests_covering(db: &Database, node_id: uuid::Uuid) -> Result<Vec<CoveringTestRow>, DbError> {
+    let script = format!(
+        r#"
+parent_of[child, parent] := *syntax_edge{{source_id: parent, target_id: child, relation_kind: "Contains" @ 'NOW' }}
+
+ancestor[desc, desc] := desc = to_uuid("{node_id}")
+ancestor[desc, asc] := ancestor[desc, intermediate], parent_of[intermediate, asc]
+
+covering[test_id, coverage] :=
+    *test_target{{ source_id: test_id, target_id, coverage @ 'NOW' }},
+    target_id = to_uuid("{node_id}")
+covering[test_id, coverage] :=
+    *test_target{{ source_id: test_id, target_id: module_id, coverage @ 'NOW' }},
+    coverage == "EnclosingModule",
+    ancestor[_, module_id]
+
+?[id, name, kind, file_path, coverage, ignored] :=
+    covering[id, coverage],
+    *test{{ id, name, kind, file_path, ignored @ 'NOW' }}
+"#
+    );
+    let qr = db.raw_query(&script)?;
+    let id_idx = get_pos(&qr.headers, "id")?;
+    let name_idx = get_pos(&qr.headers, "name")?;
+    let kind_idx = get_pos(&qr.headers, "kind")?;
+    let file_idx = get_pos(&qr.headers, "file_path")?;
+    let coverage_idx = get_pos(&qr.headers, "coverage")?;
+    let ignored_idx = get_pos(&qr.headers, "ignored")?;
+
+    qr.rows
+        .into_iter()
+        .map(|row| {
+            Ok(CoveringTestRow {
+                id: to_uuid(&row[id_idx])?,
+                name: to_string(&row[name_idx])?,
+                kind: to_string(&row[kind_idx])?,
+                file_path: PathBuf::from(to_string(&row[file_idx])?),
+                coverage: to_string(&row[coverage_idx])?,
+                ignored: row[ignored_idx].get_bool().unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+/// A trait implemented by a type, see [`implemented_traits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImplementedTraitRow {
+    pub impl_id: uuid::Uuid,
+    /// Path of the trait: from the derive table for derived impls, e.g. `["core", "hash",
+    /// "Hash"]`, and as written in the impl header otherwise.
+    pub trait_path: Vec<String>,
+    /// Whether the impl was synthesized from a `#[derive(...)]` attribute.
+    pub derived: bool,
+}
+
+/// Lists the traits implemented at NOW by the type definition `type_id`, through written impls
+/// and derives.
+pub fn implemented_traits(
+    db: &Database,
+    type_id: uuid::Uuid,
+) -> Result<Vec<ImplementedTraitRow>, DbError> {
+    let script = format!(
+        r#"
+impls[impl_id, trait_type] :=
+    *syntax_edge{{source_id: impl_id, target_id, relation_kind: "ImplFor" @ 'NOW' }},
+    target_id = to_uuid("{type_id}"),
+    *impl{{ id: impl_id, trait_type @ 'NOW' }},
+    !is_null(trait_type)
+derived_ids[impl_id] := impls[impl_id, _], *derived_impl{{ id: impl_id @ 'NOW' }}
+
+?[impl_id, trait_path, derived] :=
+    impls[impl_id, _],
+    *derived_impl{{ id: impl_id, trait_path @ 'NOW' }},
+    !is_null(trait_path),
+    derived = true
+?[impl_id, trait_path, derived] :=
+    impls[impl_id, trait_type],
+    not derived_ids[impl_id],
+    *named_type{{ type_id: trait_type, path: trait_path @ 'NOW' }},
+    derived = false
+"#
+    );
+    let qr = db.raw_query(&script)?;
+    let id_idx = get_pos(&qr.headers, "impl_id")?;
+    let path_idx = get_pos(&qr.headers, "trait_path")?;
+    let derived_idx = get_pos(&qr.headers, "derived")?;
+
+    qr.rows
+        .into_iter()
+        .map(|row| {
+            Ok(ImplementedTraitRow {
+                impl_id: to_uuid(&row[id_idx])?,
+                trait_path: to_string_list(&row[path_idx])?,
+                derived: row[derived_idx].get_bool().unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
 /// Resolve nodes by canonical module path and item name (no file_path equality).
 /// This relaxed resolver is intended for diagnostics and as a fallback when
 /// absolute file paths differ across environments. It mirrors the projection of
@@ -370,6 +485,54 @@ mod tests {
         run_script,
     };
 
+    #[test]
+    fn tests_covering_finds_unit_tests_and_doctests() -> Result<(), DbError> {
+        let cozo_db = ploke_test_utils::setup_db_full("fixture_tests")
+            .expect("database must be set up correctly");
+        let db = Database::new(cozo_db);
+
+        let qr = db.raw_query("?[id] := *function { id, name @ 'NOW' }, name == \"add\"")?;
+        let add_id = crate::database::to_uuid(&qr.rows[0][0])?;
+        let covering = super::tests_covering(&db, add_id)?;
+
+        let covers = |name: &str, coverage: &str| {
+            covering
+                .iter()
+                .any(|row| row.name == name && row.coverage == coverage)
+        };
+        assert!(covers("adds", "Call"), "got {covering:?}");
+        assert!(covers("doubles", "EnclosingModule"), "got {covering:?}");
+        assert!(covers("add (doctest 1)", "Documented"), "got {covering:?}");
+        assert!(
+            !covering
+                .iter()
+                .any(|row| row.name == "answer_outside_test_module"),
+            "got {covering:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn implemented_traits_lists_derived_and_written_impls() -> Result<(), DbError> {
+        let cozo_db = ploke_test_utils::setup_db_full("fixture_derives")
+            .expect("database must be set up correctly");
+        let db = Database::new(cozo_db);
+
+        let qr = db.raw_query("?[id] := *struct { id, name @ 'NOW' }, name == \"ModelId\"")?;
+        let model_id = crate::database::to_uuid(&qr.rows[0][0])?;
+        let traits = super::implemented_traits(&db, model_id)?;
+
+        let implements = |path: &str, derived: bool| {
+            traits
+                .iter()
+                .any(|row| row.trait_path.join("::") == path && row.derived == derived)
+        };
+        assert!(implements("core::hash::Hash", true), "got {traits:?}");
+        assert!(implements("serde::Serialize", true), "got {traits:?}");
+        assert!(implements("Describe", false), "got {traits:?}");
+        Ok(())
+    }
+
     #[test]
     fn resolve_exact_basic() -> Result<(), DbError> {
         let cozo_db = ploke_test_utils::setup_db_full_multi_embedding("fixture_nodes")
@@ -636,6 +799,14 @@ mod tests {
             "EnumVariant",
             "ImplAssociatedItem",
             "TraitAssociatedItem",
+            "ImplementsTrait",
+            "ImplFor",
+            "ImplementsTraitMethod",
+            "Calls",
+            "ExpandsTo",
+            "UsesType",
+            "RequiresTrait",
+            "SuperTrait",
         ];
         let mut all_counts: VecDeque<(&'static str, usize)> = VecDeque::new();
         for kind in all_edge_kinds {
@@ -15,14 +15,20 @@ mod result;
 mod span;
 pub(crate) mod utils;
 
+pub mod migration;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod tool_query;
+pub mod traversal;
 
 pub mod multi_embedding;
 
 pub use database::RestoredEmbeddingSet;
 pub use database::{
-    CrateContextRow, Database, NamespaceExportArtifact, NamespaceImportConflictReport,
-    NamespaceImportError, NamespaceImportResult, NamespaceRemovalResult, QueryContext,
+    CrateContextRow, Database, DependencyNamespaceRow, ExistingStore, GraphSnapshotDiff,
+    NamespaceArtifactDiff, NamespaceExportArtifact, NamespaceFileChange,
+    NamespaceImportConflictReport, NamespaceImportError, NamespaceImportResult,
+    NamespaceMergeResult, NamespaceRemovalResult, PersistentDatabase, QueryContext, SnapshotItem,
     TypedEmbedData, to_usize, to_uuid,
 };
 pub use error::DbError;
@@ -36,14 +42,18 @@ pub use observability::{
     Validity,
 };
 pub use ploke_error::PrettyDebug;
+pub use ploke_transform::schema::CozoStorage;
+pub use ploke_transform::transform::{GraphSnapshot, SnapshotRun, SnapshotTag};
 pub use query::{
     QueryBuilder,
     builder::FieldValue,
     builder::NodeType,
     callbacks::{Callback, CallbackManager},
+    graph::{Direction, EdgeKind, GraphQuery, NodeFilter},
 };
 
 pub use result::typed_rows;
+pub use result::typed_rows::{FromRow, NodeRow, ReachedNodeRow};
 
 pub use result::{CodeSnippet, QueryResult, ResultFormatter};
 pub use span::{CodeLocation, SpanChange, SpanTracker};
@@ -0,0 +1,439 @@
+//! Versioned migrations of the Cozo relations.
+//!
+//! Relations are created by `define_schema!` with the current layout, but a backup keeps the layout
+//! of the ploke version that wrote it. Each [`Migration`] upgrades a database by one schema
+//! version, and the migrations applied to a database are recorded in the `schema_migration`
+//! relation, so [`Database::migrate`] only applies the pending ones.
+//!
+//! Databases created by this version of ploke start at [`CURRENT_SCHEMA_VERSION`]; databases
+//! without the relation (backups and stores predating it) are at version 0. Older backups are
+//! migrated before their rows are imported, see [`Database::import_backup_with_embeddings`]. Steps
+//! look at the layout they upgrade before changing it, so applying them to a database that already
+//! has the newer layout is harmless.
+
+use std::collections::BTreeMap;
+use std::ops::Deref;
+use std::path::Path;
+
+use cozo::{DataValue, ScriptMutability, UuidWrapper};
+use ploke_core::PROJECT_NAMESPACE_UUID;
+use ploke_transform::transform::snapshot::validity_now;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::database::{is_missing_relation, to_i64, to_string};
+use crate::multi_embedding::hnsw_ext::HnswExt;
+use crate::multi_embedding::schema::EmbeddingSetExt as _;
+use crate::{CozoStorage, Database, DbError};
+
+pub const SCHEMA_MIGRATION_REL: &str = "schema_migration";
+
+/// A change of the layout of the relations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationStep {
+    /// Creates the `compilation_unit*`, parse detail and BM25 index relations, see
+    /// [`Database::ensure_compilation_unit_relations`].
+    CreateMissingRelations,
+    /// Adds the value column `column` of type `ty` to `relation`, set to `default` (a Cozo
+    /// expression) in the existing rows. The relation must not have an HNSW index.
+    AddColumn {
+        relation: &'static str,
+        column: &'static str,
+        ty: &'static str,
+        default: &'static str,
+    },
+    /// Renames the relation `from`, if present, to `to`.
+    RenameRelation {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// Drops and recreates the HNSW index of every embedding set that has one.
+    RebuildHnswIndexes,
+}
+
+/// Steps upgrading the database to schema `version`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub steps: &'static [MigrationStep],
+}
+
+/// The migrations of the current schema, in version order.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "compilation_unit_parse_detail_and_bm25_relations",
+        steps: &[MigrationStep::CreateMissingRelations],
+    },
+    Migration {
+        version: 2,
+        name: "bm25_index_stats_document_counts",
+        steps: &[
+            MigrationStep::AddColumn {
+                relation: "bm25_index_stats",
+                column: "doc_count",
+                ty: "Int",
+                default: "0",
+            },
+            MigrationStep::AddColumn {
+                relation: "bm25_index_stats",
+                column: "total_len",
+                ty: "Float",
+                default: "0.0",
+            },
+        ],
+    },
+];
+
+/// Schema version of databases created or migrated by this version of ploke.
+pub const CURRENT_SCHEMA_VERSION: i64 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// A migration recorded in the `schema_migration` relation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    /// Microseconds since the Unix epoch.
+    pub applied_at: i64,
+}
+
+impl<S: CozoStorage> Database<S> {
+    fn has_relation(&self, relation: &str) -> Result<bool, DbError> {
+        Ok(self
+            .relations_vec()
+            .map_err(|e| DbError::Cozo(e.to_string()))?
+            .iter()
+            .any(|r| r == relation))
+    }
+
+    /// The migrations applied to the database, oldest first.
+    pub fn applied_migrations(&self) -> Result<Vec<AppliedMigration>, DbError> {
+        if !self.has_relation(SCHEMA_MIGRATION_REL)? {
+            return Ok(Vec::new());
+        }
+        let rows = self.raw_query(&format!(
+            "?[version, name, applied_at] := *{SCHEMA_MIGRATION_REL} {{ version, name, applied_at @ 'NOW' }} :order version"
+        ))?;
+        rows.rows
+            .iter()
+            .map(|row| {
+                Ok(AppliedMigration {
+                    version: to_i64(&row[0])?,
+                    name: to_string(&row[1])?,
+                    applied_at: to_i64(&row[2])?,
+                })
+            })
+            .collect()
+    }
+
+    /// The schema version of the database, 0 if no migration was applied to it.
+    pub fn schema_version(&self) -> Result<i64, DbError> {
+        Ok(self
+            .applied_migrations()?
+            .iter()
+            .map(|migration| migration.version)
+            .max()
+            .unwrap_or(0))
+    }
+
+    /// Upgrades the database to [`CURRENT_SCHEMA_VERSION`], e.g. after loading a backup. Returns
+    /// the migrations it applied.
+    pub fn migrate(&self) -> Result<Vec<AppliedMigration>, DbError> {
+        self.apply_migrations(MIGRATIONS)
+    }
+
+    /// Applies the `migrations` newer than the schema version of the database, in version order,
+    /// and records each of them once its steps are done.
+    pub fn apply_migrations(
+        &self,
+        migrations: &[Migration],
+    ) -> Result<Vec<AppliedMigration>, DbError> {
+        ploke_transform::schema::ensure_schema_migration_relation(self.deref())
+            .map_err(|e| DbError::Cozo(e.to_string()))?;
+        let version = self.schema_version()?;
+
+        let mut pending: Vec<&Migration> = migrations
+            .iter()
+            .filter(|migration| migration.version > version)
+            .collect();
+        pending.sort_by_key(|migration| migration.version);
+
+        let mut applied = Vec::with_capacity(pending.len());
+        for migration in pending {
+            info!(
+                "Applying schema migration {} ({})",
+                migration.version, migration.name
+            );
+            for step in migration.steps {
+                self.apply_migration_step(step)?;
+            }
+            applied.push(self.record_migration(migration)?);
+        }
+        Ok(applied)
+    }
+
+    /// Records every migration in [`MIGRATIONS`] as applied, for a database whose relations were
+    /// just created with the current layout.
+    pub(crate) fn stamp_current_schema_version(&self) -> Result<(), DbError> {
+        ploke_transform::schema::ensure_schema_migration_relation(self.deref())
+            .map_err(|e| DbError::Cozo(e.to_string()))?;
+        for migration in MIGRATIONS {
+            self.record_migration(migration)?;
+        }
+        Ok(())
+    }
+
+    fn record_migration(&self, migration: &Migration) -> Result<AppliedMigration, DbError> {
+        let applied_at = validity_now();
+        let id = Uuid::new_v5(&PROJECT_NAMESPACE_UUID, migration.name.as_bytes());
+        let params = BTreeMap::from([
+            ("id".to_string(), DataValue::Uuid(UuidWrapper(id))),
+            ("version".to_string(), DataValue::from(migration.version)),
+            ("name".to_string(), DataValue::from(migration.name)),
+            ("applied_at".to_string(), DataValue::from(applied_at)),
+        ]);
+        let script = format!(
+            r#"
+?[id, version, name, applied_at, at] := id = $id, version = $version, name = $name,
+    applied_at = $applied_at, at = 'ASSERT'
+:put {SCHEMA_MIGRATION_REL} {{ id, at => version, name, applied_at }}
+"#
+        );
+        self.run_script(&script, params, ScriptMutability::Mutable)
+            .map_err(DbError::from)?;
+        Ok(AppliedMigration {
+            version: migration.version,
+            name: migration.name.to_string(),
+            applied_at,
+        })
+    }
+
+    fn apply_migration_step(&self, step: &MigrationStep) -> Result<(), DbError> {
+        match *step {
+            MigrationStep::CreateMissingRelations => self
+                .ensure_compilation_unit_relations()
+                .map_err(|e| DbError::Cozo(e.to_string())),
+            MigrationStep::AddColumn {
+                relation,
+                column,
+                ty,
+                default,
+            } => self.add_column(relation, column, ty, default),
+            MigrationStep::RenameRelation { from, to } => {
+                if !self.has_relation(from)? {
+                    return Ok(());
+                }
+                self.run_script(
+                    &format!("::rename {from} -> {to}"),
+                    BTreeMap::new(),
+                    ScriptMutability::Mutable,
+                )
+                .map(|_| ())
+                .map_err(DbError::from)
+            }
+            MigrationStep::RebuildHnswIndexes => self.rebuild_hnsw_indexes(),
+        }
+    }
+
+    /// Rewrites `relation` with the extra value column, keeping every row (and its history).
+    fn add_column(
+        &self,
+        relation: &str,
+        column: &str,
+        ty: &str,
+        default: &str,
+    ) -> Result<(), DbError> {
+        if !self.has_relation(relation)? {
+            return Ok(());
+        }
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+        for row in self.raw_query(&format!("::columns {relation}"))?.rows {
+            let name = to_string(&row[0])?;
+            if name == column {
+                return Ok(());
+            }
+            let spec = format!("{name}: {}", to_string(&row[3])?);
+            if row[1].get_bool().unwrap_or(false) {
+                keys.push((name, spec));
+            } else {
+                values.push((name, spec));
+            }
+        }
+
+        let names = keys
+            .iter()
+            .chain(&values)
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let key_specs = keys
+            .iter()
+            .map(|(_, spec)| spec.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let value_specs = values
+            .iter()
+            .map(|(_, spec)| spec.clone())
+            .chain([format!("{column}: {ty} default {default}")])
+            .collect::<Vec<_>>()
+            .join(", ");
+        let script = format!(
+            r#"
+?[{names}, {column}] := *{relation} {{ {names} }}, {column} = {default}
+:replace {relation} {{ {key_specs} => {value_specs} }}
+"#
+        );
+        self.run_script(&script, BTreeMap::new(), ScriptMutability::Mutable)
+            .map(|_| ())
+            .map_err(DbError::from)
+    }
+
+    fn rebuild_hnsw_indexes(&self) -> Result<(), DbError> {
+        for set in self.list_embedding_sets()? {
+            if !self.is_hnsw_index_registered(&set)? {
+                continue;
+            }
+            self.run_script(
+                &format!("::hnsw drop {}", set.hnsw_rel_name()),
+                BTreeMap::new(),
+                ScriptMutability::Mutable,
+            )
+            .map_err(DbError::from)?;
+            self.create_embedding_index(&set)?;
+        }
+        Ok(())
+    }
+}
+
+impl Database {
+    /// The schema version of the database `backup` was written from, read without restoring the
+    /// rest of the backup. 0 for backups without the `schema_migration` relation.
+    pub fn backup_schema_version(backup: &Path) -> Result<i64, DbError> {
+        let scratch = Self::new(cozo::new_cozo_mem().map_err(DbError::from)?);
+        ploke_transform::schema::ensure_schema_migration_relation(scratch.deref())
+            .map_err(|e| DbError::Cozo(e.to_string()))?;
+        match scratch.import_from_backup(backup, &[SCHEMA_MIGRATION_REL.to_string()]) {
+            Ok(()) => scratch.schema_version(),
+            Err(err) if is_missing_relation(&err) => Ok(0),
+            Err(err) => Err(DbError::from(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_databases_start_at_the_current_schema_version() -> Result<(), ploke_error::Error> {
+        let db = Database::init_with_schema()?;
+        assert_eq!(db.schema_version()?, CURRENT_SCHEMA_VERSION);
+        assert!(db.migrate()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_applies_pending_migrations_once() -> Result<(), ploke_error::Error> {
+        let db = Database::init_with_schema()?;
+        db.raw_query_mut(&format!("::remove {SCHEMA_MIGRATION_REL}"))?;
+        assert_eq!(db.schema_version()?, 0);
+
+        let applied = db.migrate()?;
+        assert_eq!(
+            applied.iter().map(|m| m.version).collect::<Vec<_>>(),
+            MIGRATIONS.iter().map(|m| m.version).collect::<Vec<_>>()
+        );
+        assert_eq!(db.schema_version()?, CURRENT_SCHEMA_VERSION);
+        assert!(db.migrate()?.is_empty());
+        assert_eq!(db.applied_migrations()?, applied);
+        Ok(())
+    }
+
+    #[test]
+    fn backup_schema_version_reads_only_the_migration_relation() -> Result<(), ploke_error::Error> {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let current = dir.path().join("current.sqlite");
+        let legacy = dir.path().join("legacy.sqlite");
+
+        let db = Database::init_with_schema()?;
+        db.raw_query_mut(
+            r#"
+?[id, at, tokenizer_version, avgdl, doc_count, total_len] <-
+    [[to_uuid("5d9a1a3e-4a3b-4f8e-9a7d-0c6f2d3b1e11"), 'ASSERT', "v1", 2.0, 3, 6.0]]
+:put bm25_index_stats { id, at => tokenizer_version, avgdl, doc_count, total_len }
+"#,
+        )?;
+        db.write_backup_to_path(&current)?;
+        db.raw_query_mut(&format!("::remove {SCHEMA_MIGRATION_REL}"))?;
+        db.write_backup_to_path(&legacy)?;
+
+        assert_eq!(
+            Database::backup_schema_version(&current)?,
+            CURRENT_SCHEMA_VERSION
+        );
+        assert_eq!(Database::backup_schema_version(&legacy)?, 0);
+
+        // Both are imported, the legacy one through a migrated scratch database.
+        for backup in [&current, &legacy] {
+            let restored = Database::init_with_schema()?;
+            restored.import_backup_with_embeddings(backup)?;
+            assert_eq!(restored.bm25_index_stats()?.len(), 1);
+            assert_eq!(restored.schema_version()?, CURRENT_SCHEMA_VERSION);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn migrations_rename_relations_and_add_columns_in_place() -> Result<(), ploke_error::Error> {
+        const UPGRADES: &[Migration] = &[
+            Migration {
+                version: 2,
+                name: "add_item_kind",
+                steps: &[MigrationStep::AddColumn {
+                    relation: "item",
+                    column: "kind",
+                    ty: "String",
+                    default: r#""fn""#,
+                }],
+            },
+            Migration {
+                version: 1,
+                name: "rename_legacy_item",
+                steps: &[MigrationStep::RenameRelation {
+                    from: "legacy_item",
+                    to: "item",
+                }],
+            },
+        ];
+
+        let db = Database::init_with_schema()?;
+        db.run_script(
+            r#"
+{:create legacy_item { id: Uuid, at: Validity => name: String }}
+{?[id, at, name] <- [[to_uuid("5d9a1a3e-4a3b-4f8e-9a7d-0c6f2d3b1e11"), 'ASSERT', "parse"]]
+ :put legacy_item { id, at => name }}
+"#,
+            BTreeMap::new(),
+            ScriptMutability::Mutable,
+        )
+        .map_err(DbError::from)?;
+
+        let applied = db.apply_migrations(UPGRADES)?;
+        assert_eq!(
+            applied.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(),
+            vec!["rename_legacy_item", "add_item_kind"]
+        );
+        assert_eq!(db.schema_version()?, 2);
+        assert!(!db.has_relation("legacy_item")?);
+
+        let rows = db.raw_query("?[name, kind] := *item { name, kind @ 'NOW' }")?;
+        assert_eq!(
+            rows.rows,
+            vec![vec![DataValue::from("parse"), DataValue::from("fn")]]
+        );
+        assert!(db.apply_migrations(UPGRADES)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+//! Content-addressed cache of embedding vectors, keyed by embedding set and snippet hash.
+//!
+//! The vector relations of an embedding set are keyed by node id, so a re-parse that changes ids
+//! (or a branch switch back and forth) leaves every node unembedded. The cache lets the indexer
+//! look the vector of an identical snippet up instead of sending it to the provider again.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
+
+use cozo::{DataValue, ScriptMutability, UuidWrapper};
+use ploke_core::PROJECT_NAMESPACE_UUID;
+use ploke_core::embeddings::EmbeddingSet;
+use uuid::Uuid;
+
+use crate::database::to_uuid;
+use crate::{CozoStorage, Database, DbError};
+
+pub const EMBEDDING_CACHE_REL: &str = "embedding_cache";
+
+/// Content hash of a snippet, as used to key the embedding cache.
+pub fn snippet_hash(snippet: &str) -> Uuid {
+    Uuid::new_v5(&PROJECT_NAMESPACE_UUID, snippet.as_bytes())
+}
+
+/// Id of the cache row holding the vector of `snippet_hash` for `embedding_set`.
+fn cache_id(embedding_set: &EmbeddingSet, snippet_hash: Uuid) -> Uuid {
+    let mut bytes = embedding_set.hash_id.into_inner().to_le_bytes().to_vec();
+    bytes.extend_from_slice(snippet_hash.as_bytes());
+    Uuid::new_v5(&PROJECT_NAMESPACE_UUID, &bytes)
+}
+
+impl<S: CozoStorage> Database<S> {
+    /// Creates the embedding cache relation if missing.
+    pub fn ensure_embedding_cache_relation(&self) -> Result<(), DbError> {
+        ploke_transform::schema::ensure_embedding_cache_relation(self.deref())
+            .map_err(|e| DbError::Cozo(e.to_string()))
+    }
+
+    /// Cached vectors of `snippet_hashes` for `embedding_set`, keyed by snippet hash. Hashes
+    /// without a cached vector are missing from the map.
+    pub fn cached_embeddings(
+        &self,
+        embedding_set: &EmbeddingSet,
+        snippet_hashes: &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<f32>>, DbError> {
+        if snippet_hashes.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let ids = snippet_hashes
+            .iter()
+            .map(|hash| {
+                DataValue::List(vec![DataValue::Uuid(UuidWrapper(cache_id(
+                    embedding_set,
+                    *hash,
+                )))])
+            })
+            .collect();
+        let script = format!(
+            r#"
+ids[id] <- $ids
+?[snippet_hash, vector] := ids[id], *{EMBEDDING_CACHE_REL} {{ id, snippet_hash, vector @ 'NOW' }}
+"#
+        );
+        let rows = self
+            .raw_query_params(
+                &script,
+                BTreeMap::from([("ids".to_string(), DataValue::List(ids))]),
+            )?
+            .rows;
+
+        rows.iter()
+            .map(|row| {
+                let hash = to_uuid(&row[0])?;
+                let vector = row[1]
+                    .get_slice()
+                    .ok_or_else(|| {
+                        DbError::QueryExecution("embedding_cache.vector is not a list".into())
+                    })?
+                    .iter()
+                    .map(|value| {
+                        value.get_float().map(|f| f as f32).ok_or_else(|| {
+                            DbError::QueryExecution("non-float in embedding_cache.vector".into())
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((hash, vector))
+            })
+            .collect()
+    }
+
+    /// Caches the vectors of snippets embedded with `embedding_set`, replacing older entries.
+    pub fn put_cached_embeddings(
+        &self,
+        embedding_set: &EmbeddingSet,
+        entries: Vec<(Uuid, Vec<f32>)>,
+    ) -> Result<(), DbError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let set_id = DataValue::from(embedding_set.hash_id.into_inner() as i64);
+        let rows = entries
+            .into_iter()
+            .map(|(hash, vector)| {
+                DataValue::List(vec![
+                    DataValue::Uuid(UuidWrapper(cache_id(embedding_set, hash))),
+                    set_id.clone(),
+                    DataValue::Uuid(UuidWrapper(hash)),
+                    DataValue::List(
+                        vector
+                            .into_iter()
+                            .map(|f| DataValue::from(f as f64))
+                            .collect(),
+                    ),
+                ])
+            })
+            .collect();
+        let script = format!(
+            r#"
+input[id, embedding_set_id, snippet_hash, vector] <- $rows
+?[id, embedding_set_id, snippet_hash, vector, at] :=
+    input[id, embedding_set_id, snippet_hash, vector],
+    at = 'ASSERT'
+
+:put {EMBEDDING_CACHE_REL} {{ id, at => embedding_set_id, snippet_hash, vector }}
+"#
+        );
+        self.run_script(
+            &script,
+            BTreeMap::from([("rows".to_string(), DataValue::List(rows))]),
+            ScriptMutability::Mutable,
+        )
+        .map(|_| ())
+        .map_err(DbError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ploke_core::embeddings::{EmbeddingModelId, EmbeddingProviderSlug, EmbeddingShape};
+
+    use super::*;
+
+    fn embedding_set(model: &str) -> EmbeddingSet {
+        EmbeddingSet::new(
+            EmbeddingProviderSlug::new_from_str("local"),
+            EmbeddingModelId::new_from_str(model),
+            EmbeddingShape::new_dims_default(3),
+        )
+    }
+
+    #[test]
+    fn cached_embeddings_are_keyed_by_embedding_set_and_snippet() -> Result<(), ploke_error::Error>
+    {
+        let db = Database::init_with_schema()?;
+        db.ensure_embedding_cache_relation()?;
+        let (minilm, other) = (embedding_set("minilm"), embedding_set("other"));
+        let (parse, lex) = (snippet_hash("fn parse() {}"), snippet_hash("fn lex() {}"));
+        assert_eq!(parse, snippet_hash("fn parse() {}"));
+        assert_ne!(parse, lex);
+
+        db.put_cached_embeddings(&minilm, vec![(parse, vec![0.5, 0.25, 1.0])])?;
+        db.put_cached_embeddings(&other, vec![(lex, vec![1.0, 0.0, 0.0])])?;
+
+        let cached = db.cached_embeddings(&minilm, &[parse, lex])?;
+        assert_eq!(cached, HashMap::from([(parse, vec![0.5, 0.25, 1.0])]));
+        assert!(db.cached_embeddings(&other, &[parse])?.is_empty());
+
+        db.put_cached_embeddings(&minilm, vec![(parse, vec![0.0, 0.0, 1.0])])?;
+        let replaced = db.cached_embeddings(&minilm, &[parse])?;
+        assert_eq!(replaced[&parse], vec![0.0, 0.0, 1.0]);
+        Ok(())
+    }
+}
@@ -10,6 +10,7 @@ use ploke_core::embeddings::{
     EmbeddingShape, HnswRelName,
 };
 use ploke_error::Error as PlokeError;
+use ploke_transform::schema::CozoStorage;
 use syn_parser::utils::LogStyle as _;
 use tokio::fs;
 use tracing::{debug, error, info, instrument};
@@ -232,7 +233,7 @@ pub trait EmbeddingExt {
     // fn get_node_info<T: From<NamedRows>>(&self, rel_name: &str) -> Result<T, DbError>;
 }
 
-impl EmbeddingExt for cozo::Db<cozo::MemStorage> {
+impl<S: CozoStorage> EmbeddingExt for cozo::Db<S> {
     fn count_pending_embeddings(&self, embedding_set: &EmbeddingSet) -> Result<usize, DbError> {
         let pending_nodes_rhs = self.script_pending_nodes_rhs(embedding_set);
 
@@ -1101,7 +1102,7 @@ batch[id, name, file_path, file_hash, hash, span, namespace, ordering] :=
 }
 
 /// Trait used to extend the database with embeddings-aware methods
-impl EmbeddingExt for Database {
+impl<S: CozoStorage> EmbeddingExt for Database<S> {
     fn count_complete_embeddings(&self, embedding_set: &EmbeddingSet) -> Result<usize, DbError> {
         self.deref().count_complete_embeddings(embedding_set)
     }
@@ -1,4 +1,5 @@
 use ploke_core::embeddings::EmbeddingSet;
+use ploke_transform::schema::CozoStorage;
 use tracing::Level;
 
 use crate::{
@@ -8,7 +9,7 @@ use crate::{
 
 use super::*;
 
-impl DebugAll for Database {}
+impl<S: CozoStorage> DebugAll for Database<S> {}
 
 pub trait DebugAll: HnswExt + EmbeddingExt {
     fn is_embedding_info_all(
@@ -4,6 +4,7 @@ use cozo::{DataValue, Num, ScriptMutability, UuidWrapper};
 use itertools::Itertools;
 use ploke_core::RetrievalScope;
 use ploke_core::embeddings::EmbeddingSet;
+use ploke_transform::schema::CozoStorage;
 use syn_parser::utils::LogStyle;
 use tracing::{debug, info, instrument};
 use uuid::Uuid;
@@ -73,7 +74,7 @@ pub trait HnswExt {
     fn create_index_warn(&self, enbedding_set: &EmbeddingSet) -> Result<(), ploke_error::Error>;
 }
 
-impl HnswExt for cozo::Db<cozo::MemStorage> {
+impl<S: CozoStorage> HnswExt for cozo::Db<S> {
     fn ensure_embedding_relation(&self, embedding_set: &EmbeddingSet) -> Result<(), DbError> {
         if self.is_relation_registered(&embedding_set.rel_name)? {
             return Ok(());
@@ -495,7 +496,7 @@ batch[id, name, file_path, file_hash, hash, span, namespace, distance] :=
     }
 }
 
-impl HnswExt for Database {
+impl<S: CozoStorage> HnswExt for Database<S> {
     fn ensure_embedding_relation(&self, embedding_set: &EmbeddingSet) -> Result<(), DbError> {
         self.deref().ensure_embedding_relation(embedding_set)
     }
@@ -1,3 +1,4 @@
+pub mod cache;
 pub mod db_ext;
 pub mod debug;
 pub mod hnsw_ext;
@@ -6,6 +6,7 @@ use std::{collections::BTreeMap, sync::Arc};
 
 use cozo::{DataValue, ScriptMutability, UuidWrapper};
 use ploke_core::ArcStr;
+use ploke_transform::schema::CozoStorage;
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -137,7 +138,7 @@ pub trait ObservabilityStore {
     fn get_edit_proposal(&self, req_id: uuid::Uuid) -> Result<Option<CodeEditProposal>, DbError>;
 }
 
-impl Database {
+impl<S: CozoStorage> Database<S> {
     fn ensure_observability_schema(&self) -> Result<(), DbError> {
         // conversation_turn
         let create_conversation = r#"
@@ -211,7 +212,7 @@ impl Database {
     }
 }
 
-impl ObservabilityStore for Database {
+impl<S: CozoStorage> ObservabilityStore for Database<S> {
     // TODO: Needs to be tested.
     fn upsert_conversation_turn(&self, turn: ConversationTurn) -> Result<(), DbError> {
         self.ensure_observability_schema()?;
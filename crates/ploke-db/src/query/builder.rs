@@ -3,7 +3,7 @@
 
 use itertools::Itertools;
 use ploke_error::Error;
-use ploke_transform::schema::edges::SyntacticRelationSchema;
+use ploke_transform::schema::edges::{SyntacticRelationSchema, TestSchema, TestTargetSchema};
 
 use ploke_transform::schema::assoc_nodes::MethodNodeSchema;
 use ploke_transform::schema::primary_nodes::{
@@ -51,6 +51,9 @@ fn compilation_unit_membership_clause(node_type: Option<NodeType>, cu_id: Uuid)
 }
 
 /// Main query builder struct
+///
+/// Builds CozoScript fragments as strings. For queries that are executed with their values passed
+/// as parameters and decoded into typed rows, see [`GraphQuery`](super::graph::GraphQuery).
 #[derive(Debug, Clone)]
 pub struct QueryBuilder {
     pub selected_node: Option<NodeType>,
@@ -111,10 +114,12 @@ pub enum NodeType {
     MacroType,
     UnknownType,
     SyntaxEdge,
+    Test,
+    TestTarget,
 }
 
 impl NodeType {
-    pub fn all_variants() -> [Self; 35] {
+    pub fn all_variants() -> [Self; 37] {
         use NodeType::*;
         [
             Function,
@@ -152,9 +157,17 @@ impl NodeType {
             MacroType,
             UnknownType,
             SyntaxEdge,
+            Test,
+            TestTarget,
         ]
     }
 
+    /// Relations holding parse details rather than nodes. They are not reached from the node ids
+    /// of a namespace, so namespace export, import and removal handle them separately.
+    pub fn is_parse_detail(self) -> bool {
+        matches!(self, NodeType::Test | NodeType::TestTarget)
+    }
+
     pub fn primary_nodes() -> [Self; 10] {
         use NodeType::*;
         [
@@ -286,6 +299,18 @@ impl QueryBuilder {
         self
     }
 
+    /// Select tests (test functions and doc-tests) to query
+    pub fn tests(mut self) -> Self {
+        self.selected_node = Some(NodeType::Test);
+        self
+    }
+
+    /// Select the links from tests to the items they exercise to query
+    pub fn test_targets(mut self) -> Self {
+        self.selected_node = Some(NodeType::TestTarget);
+        self
+    }
+
     /// Filter by name (exact match)
     pub fn with_name(mut self, name: &str) -> Self {
         self.filters.push(format!("name = '{}'", name));
@@ -492,51 +517,6 @@ impl QueryBuilder {
 
         self.rhs_rels.insert(new_rhs, Vec::new());
     }
-
-    // /// Execute the constructed query
-    // pub fn execute(self) -> Result<QueryResult, Error> {
-    //     let relation = match self.selected_node {
-    //         Some(NodeType::Function) => FunctionNodeSchema::SCHEMA.relation,
-    //         Some(NodeType::Struct) => StructNodeSchema::SCHEMA.relation,
-    //         None => {
-    //             return Err(Error::from(DbError::QueryConstruction(
-    //                 "No node type selected".into(),
-    //             )))
-    //         }
-    //         _ => {
-    //             return Err(Error::from(DbError::QueryConstruction(
-    //                 "Warning! Node type not yet supported".into(),
-    //             )))
-    //         }
-    //     };
-    //
-    //     let mut query = match relation {
-    //         "functions" => format!(
-    //             "?[id, name, visibility, return_type_id, docstring, body] := *{}[id, name, visibility, return_type_id, docstring, body]",
-    //             relation
-    //         ),
-    //         "structs" => format!(
-    //             "?[id, name, visibility, docstring] := *{}[id, name, visibility, docstring]",
-    //             relation
-    //         ),
-    //         _ => return Err(Error::from(DbError::QueryConstruction(format!("Unsupported relation: {}", relation)))),
-    //     };
-    //
-    //     if !self.filters.is_empty() {
-    //         query.push_str(", ");
-    //         query.push_str(&self.filters.join(", "));
-    //     }
-    //
-    //     if let Some(limit) = self.limit {
-    //         query.push_str(&format!(" :limit {}", limit));
-    //     }
-    //
-    //     self.db
-    //         .run_script(&query, BTreeMap::new(), cozo::ScriptMutability::Immutable)
-    //         .map(QueryResult::from)
-    //         .map_err(|e| DbError::Cozo(e.to_string()))
-    //         .map_err(Error::from)
-    // }
 }
 
 impl Default for QueryBuilder {
@@ -644,7 +624,9 @@ define_static_fields!(
     (PAREN_TYPE_FIELDS, ParenTypeSchema, ParenType),
     (MACRO_TYPE_FIELDS, MacroTypeSchema, MacroType),
     (UNKNOWN_TYPE_FIELDS, UnknownTypeSchema, UnknownType),
-    (SYNTAX_EDGE_FIELDS, SyntacticRelationSchema, SyntaxEdge)
+    (SYNTAX_EDGE_FIELDS, SyntacticRelationSchema, SyntaxEdge),
+    (TEST_FIELDS, TestSchema, Test),
+    (TEST_TARGET_FIELDS, TestTargetSchema, TestTarget)
 );
 
 // impl NodeType {
@@ -0,0 +1,458 @@
+//! Typed graph queries over [`NodeType`]s and edge kinds.
+//!
+//! A [`GraphQuery`] selects the nodes of one relation, narrows them down with [`NodeFilter`]s
+//! and either projects fields of the selected nodes or follows `syntax_edge` rows from them for
+//! a bounded number of hops. Every value taken from the caller is passed to Cozo as a named
+//! parameter, so names and paths never end up in the script itself.
+//!
+//! ```rust,ignore
+//! use ploke_db::{Direction, EdgeKind, GraphQuery, NodeType, ReachedNodeRow};
+//!
+//! // Everything calling `parse`, directly or through one other function.
+//! let callers: Vec<ReachedNodeRow> = GraphQuery::new(NodeType::Function)
+//!     .name("parse")
+//!     .follow(&[EdgeKind::Calls], Direction::Incoming, 2)
+//!     .fetch(&db)?;
+//! ```
+
+use std::collections::BTreeMap;
+
+use cozo::{DataValue, UuidWrapper};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::builder::NodeType;
+use crate::result::typed_rows::FromRow;
+use crate::{CozoStorage, Database, DbError, QueryResult};
+
+/// The kind of a `syntax_edge` row, as written by `ploke-transform`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum EdgeKind {
+    Contains,
+    ResolvesToDefinition,
+    CustomPath,
+    Sibling,
+    ModuleImports,
+    ReExports,
+    ImportedBy,
+    StructField,
+    UnionField,
+    VariantField,
+    EnumVariant,
+    ImplAssociatedItem,
+    TraitAssociatedItem,
+    ImplementsTrait,
+    ImplFor,
+    ImplementsTraitMethod,
+    Calls,
+    ExpandsTo,
+    UsesType,
+    RequiresTrait,
+    SuperTrait,
+}
+
+impl EdgeKind {
+    /// The `relation_kind` column of the edges of this kind.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EdgeKind::Contains => "Contains",
+            EdgeKind::ResolvesToDefinition => "ResolvesToDefinition",
+            EdgeKind::CustomPath => "CustomPath",
+            EdgeKind::Sibling => "Sibling",
+            EdgeKind::ModuleImports => "ModuleImports",
+            EdgeKind::ReExports => "ReExports",
+            EdgeKind::ImportedBy => "ImportedBy",
+            EdgeKind::StructField => "StructField",
+            EdgeKind::UnionField => "UnionField",
+            EdgeKind::VariantField => "VariantField",
+            EdgeKind::EnumVariant => "EnumVariant",
+            EdgeKind::ImplAssociatedItem => "ImplAssociatedItem",
+            EdgeKind::TraitAssociatedItem => "TraitAssociatedItem",
+            EdgeKind::ImplementsTrait => "ImplementsTrait",
+            EdgeKind::ImplFor => "ImplFor",
+            EdgeKind::ImplementsTraitMethod => "ImplementsTraitMethod",
+            EdgeKind::Calls => "Calls",
+            EdgeKind::ExpandsTo => "ExpandsTo",
+            EdgeKind::UsesType => "UsesType",
+            EdgeKind::RequiresTrait => "RequiresTrait",
+            EdgeKind::SuperTrait => "SuperTrait",
+        }
+    }
+}
+
+/// Which way edges are followed from the selected nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// From `source_id` to `target_id`, e.g. to the callees of a function.
+    Outgoing,
+    /// From `target_id` to `source_id`, e.g. to the callers of a function.
+    Incoming,
+    Both,
+}
+
+/// A condition on the selected nodes of a [`GraphQuery`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeFilter {
+    /// The name is exactly this one.
+    Name(String),
+    /// The name contains this substring.
+    NameContains(String),
+    /// The node is directly contained in the module at this path, e.g.
+    /// `["crate", "parser"]`. For modules, their own path is this one.
+    ModulePath(Vec<String>),
+    /// The `vis_kind` column is this one, e.g. `"public"` or `"crate"`.
+    Visibility(String),
+    /// The node is enabled for any of these compilation units.
+    CompilationUnits(Vec<Uuid>),
+}
+
+/// The edges a query follows, shared with the traversals of [`crate::traversal`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Traversal {
+    pub(crate) kinds: Vec<EdgeKind>,
+    pub(crate) direction: Direction,
+    pub(crate) max_hops: usize,
+    /// Node types the traversal may reach and go through. Empty allows any node.
+    pub(crate) through: Vec<NodeType>,
+}
+
+impl Traversal {
+    /// The rules deriving `reach[id, depth]` for each node reached from a `start[id]` node after
+    /// `depth` hops, up to `max_hops`.
+    pub(crate) fn reach_rules(&self, params: &mut BTreeMap<String, DataValue>) -> String {
+        params.insert(
+            "max_hops".to_string(),
+            DataValue::from(self.max_hops as i64),
+        );
+        let kind_filter = self.kind_filter(params);
+        // Looking the reached node up by key in each allowed relation avoids scanning them.
+        let node_filters: Vec<String> = if self.through.is_empty() {
+            vec![String::new()]
+        } else {
+            self.through
+                .iter()
+                .map(|node_type| format!(", *{} {{ id: next @ 'NOW' }}", node_type.relation_str()))
+                .collect()
+        };
+        let mut rules = vec!["reach[id, depth] := start[id], depth = 0".to_string()];
+        for (source, target) in edge_ends(self.direction, "prev", "next") {
+            let edge = edge_atom(source, target);
+            for node_filter in &node_filters {
+                rules.push(format!(
+                    "reach[next, depth] := reach[prev, d], d < $max_hops, {edge}{kind_filter}{node_filter}, depth = d + 1"
+                ));
+            }
+        }
+        rules.join("\n")
+    }
+
+    /// The condition restricting `relation_kind` to the followed kinds, if any.
+    pub(crate) fn kind_filter(&self, params: &mut BTreeMap<String, DataValue>) -> String {
+        if self.kinds.is_empty() {
+            return String::new();
+        }
+        params.insert(
+            "edge_kinds".to_string(),
+            DataValue::List(
+                self.kinds
+                    .iter()
+                    .map(|kind| DataValue::from(kind.as_str()))
+                    .collect(),
+            ),
+        );
+        ", is_in(relation_kind, $edge_kinds)".to_string()
+    }
+}
+
+/// The ends of the edges leading from `from` to `to` in `direction`, as
+/// `(source_id, target_id)`.
+pub(crate) fn edge_ends<'a>(
+    direction: Direction,
+    from: &'a str,
+    to: &'a str,
+) -> Vec<(&'a str, &'a str)> {
+    match direction {
+        Direction::Outgoing => vec![(from, to)],
+        Direction::Incoming => vec![(to, from)],
+        Direction::Both => vec![(from, to), (to, from)],
+    }
+}
+
+/// The `syntax_edge` atom binding the edges from `source` to `target` and their `relation_kind`.
+pub(crate) fn edge_atom(source: &str, target: &str) -> String {
+    format!("*syntax_edge {{ source_id: {source}, target_id: {target}, relation_kind @ 'NOW' }}")
+}
+
+/// One copy of `rule` per relation of `node_types` with an `id` and a `name`, each looking up the
+/// `name` of the node bound to `id` by key and binding `relation` to that relation.
+pub(crate) fn named_rules(rule: &str, node_types: &[NodeType]) -> String {
+    node_types
+        .iter()
+        .filter(|node_type| {
+            let fields = node_type.fields();
+            fields.contains(&"id") && fields.contains(&"name")
+        })
+        .map(|node_type| {
+            let relation = node_type.relation_str();
+            format!("{rule}, *{relation} {{ id, name @ 'NOW' }}, relation = \"{relation}\"")
+        })
+        .join("\n")
+}
+
+/// A typed query over the code graph, see the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphQuery {
+    node_type: NodeType,
+    filters: Vec<NodeFilter>,
+    fields: Vec<&'static str>,
+    traversal: Option<Traversal>,
+    limit: Option<usize>,
+}
+
+impl GraphQuery {
+    /// Selects the nodes of `node_type`. Without a projection, the `id` and `name` of each node
+    /// are returned.
+    pub fn new(node_type: NodeType) -> Self {
+        Self {
+            node_type,
+            filters: Vec::new(),
+            fields: Vec::new(),
+            traversal: None,
+            limit: None,
+        }
+    }
+
+    pub fn node_type(&self) -> NodeType {
+        self.node_type
+    }
+
+    pub fn filter(mut self, filter: NodeFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Shorthand for [`NodeFilter::Name`].
+    pub fn name(self, name: impl Into<String>) -> Self {
+        self.filter(NodeFilter::Name(name.into()))
+    }
+
+    /// Shorthand for [`NodeFilter::NameContains`].
+    pub fn name_contains(self, needle: impl Into<String>) -> Self {
+        self.filter(NodeFilter::NameContains(needle.into()))
+    }
+
+    /// Shorthand for [`NodeFilter::ModulePath`].
+    pub fn in_module<S: Into<String>>(self, path: impl IntoIterator<Item = S>) -> Self {
+        self.filter(NodeFilter::ModulePath(
+            path.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    /// Shorthand for [`NodeFilter::Visibility`].
+    pub fn visibility(self, vis_kind: impl Into<String>) -> Self {
+        self.filter(NodeFilter::Visibility(vis_kind.into()))
+    }
+
+    /// Shorthand for [`NodeFilter::CompilationUnits`]. Adds no filter when `cu_ids` is empty.
+    pub fn in_compilation_units(self, cu_ids: &[Uuid]) -> Self {
+        if cu_ids.is_empty() {
+            return self;
+        }
+        self.filter(NodeFilter::CompilationUnits(cu_ids.to_vec()))
+    }
+
+    /// Returns `field` of the selected nodes, after their `id`. Ignored once the query follows
+    /// edges, whose rows are always [`ReachedNodeRow`]s.
+    pub fn select(mut self, field: &'static str) -> Self {
+        if !self.fields.contains(&field) {
+            self.fields.push(field);
+        }
+        self
+    }
+
+    /// Returns the nodes reached from the selected nodes by following edges of `kinds` in
+    /// `direction`, at most `max_hops` times, instead of the selected nodes themselves. An empty
+    /// `kinds` follows edges of any kind.
+    pub fn follow(mut self, kinds: &[EdgeKind], direction: Direction, max_hops: usize) -> Self {
+        self.traversal = Some(Traversal {
+            kinds: kinds.to_vec(),
+            direction,
+            max_hops,
+            through: Vec::new(),
+        });
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// The CozoScript of the query and its parameters.
+    pub fn to_script(&self) -> Result<(String, BTreeMap<String, DataValue>), DbError> {
+        let fields = self.node_type.fields();
+        for required in ["id", "name"] {
+            if !fields.contains(&required) {
+                return Err(DbError::QueryConstruction(format!(
+                    "{:?} has no `{required}` field",
+                    self.node_type
+                )));
+            }
+        }
+        if let Some(field) = self.fields.iter().find(|f| !fields.contains(f)) {
+            return Err(DbError::QueryConstruction(format!(
+                "{:?} has no `{field}` field",
+                self.node_type
+            )));
+        }
+
+        let mut params = BTreeMap::new();
+        let selection = self.selection(&mut params);
+        let mut script = match &self.traversal {
+            None => {
+                let head = std::iter::once("id").chain(self.output_fields()).join(", ");
+                format!("?[{head}] := {selection}")
+            }
+            Some(traversal) => traversal_script(&selection, traversal, &mut params),
+        };
+        if let Some(limit) = self.limit {
+            script.push_str(&format!("\n:limit {limit}"));
+        }
+        Ok((script, params))
+    }
+
+    /// Runs the query against `db`.
+    pub fn execute<S: CozoStorage>(&self, db: &Database<S>) -> Result<QueryResult, DbError> {
+        let (script, params) = self.to_script()?;
+        db.raw_query_params(&script, params)
+    }
+
+    /// Runs the query against `db` and decodes each row into a `T`.
+    pub fn fetch<T: FromRow, S: CozoStorage>(&self, db: &Database<S>) -> Result<Vec<T>, DbError> {
+        self.execute(db)?.into_typed()
+    }
+
+    /// The projected fields, or `name` without a projection.
+    fn output_fields(&self) -> impl Iterator<Item = &'static str> + '_ {
+        let default: &[&'static str] = if self.fields.is_empty() {
+            &["name"]
+        } else {
+            &[]
+        };
+        self.fields
+            .iter()
+            .copied()
+            .chain(default.iter().copied())
+            .filter(|f| *f != "id")
+    }
+
+    /// The body binding `id` to each selected node, with the filters applied.
+    fn selection(&self, params: &mut BTreeMap<String, DataValue>) -> String {
+        let mut bound = vec!["id", "name"];
+        if self
+            .filters
+            .iter()
+            .any(|f| matches!(f, NodeFilter::Visibility(_)))
+        {
+            bound.push("vis_kind");
+        }
+        let module_path_filter = self.node_type == NodeType::Module
+            && self
+                .filters
+                .iter()
+                .any(|f| matches!(f, NodeFilter::ModulePath(_)));
+        if module_path_filter {
+            bound.push("path");
+        }
+        for field in self.output_fields() {
+            if !bound.contains(&field) {
+                bound.push(field);
+            }
+        }
+
+        let mut clauses = vec![format!(
+            "*{} {{ {} @ 'NOW' }}",
+            self.node_type.relation_str(),
+            bound.join(", ")
+        )];
+        for (idx, filter) in self.filters.iter().enumerate() {
+            let param = format!("filter_{idx}");
+            let clause = match filter {
+                NodeFilter::Name(name) => {
+                    params.insert(param.clone(), DataValue::from(name.as_str()));
+                    format!("name == ${param}")
+                }
+                NodeFilter::NameContains(needle) => {
+                    params.insert(param.clone(), DataValue::from(needle.as_str()));
+                    format!("str_includes(name, ${param})")
+                }
+                NodeFilter::ModulePath(path) => {
+                    params.insert(param.clone(), string_list(path));
+                    if self.node_type == NodeType::Module {
+                        format!("path == ${param}")
+                    } else {
+                        format!(
+                            "*syntax_edge {{ source_id: parent_{idx}, target_id: id, relation_kind: \"Contains\" @ 'NOW' }}, \
+                             *module {{ id: parent_{idx}, path: parent_path_{idx} @ 'NOW' }}, \
+                             parent_path_{idx} == ${param}"
+                        )
+                    }
+                }
+                NodeFilter::Visibility(vis_kind) => {
+                    params.insert(param.clone(), DataValue::from(vis_kind.as_str()));
+                    format!("vis_kind == ${param}")
+                }
+                NodeFilter::CompilationUnits(cu_ids) => {
+                    params.insert(
+                        param.clone(),
+                        DataValue::List(
+                            cu_ids
+                                .iter()
+                                .map(|id| DataValue::Uuid(UuidWrapper(*id)))
+                                .collect(),
+                        ),
+                    );
+                    // Same join as `QueryBuilder::filter_by_compilation_unit`.
+                    format!(
+                        "*compilation_unit_enabled_node {{ cu_id: cu_{idx}, node_id: id }}, is_in(cu_{idx}, ${param})"
+                    )
+                }
+            };
+            clauses.push(clause);
+        }
+        clauses.join(",\n    ")
+    }
+}
+
+fn string_list(items: &[String]) -> DataValue {
+    DataValue::List(
+        items
+            .iter()
+            .map(|item| DataValue::from(item.as_str()))
+            .collect(),
+    )
+}
+
+/// Follows the edges of `traversal` from the nodes bound by `selection`, keeping the fewest hops
+/// at which each node is reached.
+fn traversal_script(
+    selection: &str,
+    traversal: &Traversal,
+    params: &mut BTreeMap<String, DataValue>,
+) -> String {
+    let reach = traversal.reach_rules(params);
+    let named = named_rules(
+        "?[id, name, relation, depth] := reached[id, depth]",
+        &NodeType::primary_and_assoc_nodes(),
+    );
+
+    format!(
+        r#"start[id] := {selection}
+{reach}
+reached[id, min(depth)] := reach[id, depth], depth > 0
+{named}
+:order depth, name"#
+    )
+}
@@ -1,4 +1,5 @@
 pub mod builder;
+pub mod graph;
 pub mod semantic;
 
 // Re-exporting with same module path for consumers, to keep changes in feature flag isolated from
@@ -11,3 +12,4 @@ mod callbacks_single;
 
 pub use builder::FieldValue;
 pub use builder::QueryBuilder;
+pub use graph::{Direction, EdgeKind, GraphQuery, NodeFilter};
@@ -45,6 +45,11 @@ impl QueryResult {
             .map(|row| Row::new(self.headers.as_slice(), row.as_slice()))
     }
 
+    /// Decodes each row into a `T`, see [`typed_rows::FromRow`].
+    pub fn into_typed<T: typed_rows::FromRow>(self) -> Result<Vec<T>, DbError> {
+        self.row_refs().map(T::from_row).collect()
+    }
+
     pub fn try_into_file_data(self) -> Result<Vec<FileData>, ploke_error::Error> {
         let map_err = |e: DbError| {
             ploke_error::Error::Internal(ploke_error::InternalError::CompilerError(e.to_string()))
@@ -4,6 +4,14 @@ use ploke_core::rag_types::CanonPath;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::Row;
+use crate::DbError;
+
+/// A struct decoded from a single row of a [`QueryResult`](crate::QueryResult), by column name.
+pub trait FromRow: Sized {
+    fn from_row(row: Row<'_>) -> Result<Self, DbError>;
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResolvedEdgeData {
     pub source_id: Uuid,
@@ -14,3 +22,41 @@ pub struct ResolvedEdgeData {
     pub relation_kind: String,
     pub file_path: PathBuf,
 }
+
+/// A node selected by a [`GraphQuery`](crate::GraphQuery) without a projection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeRow {
+    pub id: Uuid,
+    pub name: String,
+}
+
+impl FromRow for NodeRow {
+    fn from_row(row: Row<'_>) -> Result<Self, DbError> {
+        Ok(Self {
+            id: row.get("id")?,
+            name: row.get("name")?,
+        })
+    }
+}
+
+/// A node reached by a [`GraphQuery`](crate::GraphQuery) following edges.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReachedNodeRow {
+    pub id: Uuid,
+    pub name: String,
+    /// The relation holding the node, e.g. `"function"`.
+    pub relation: String,
+    /// The fewest hops from a selected node to this one.
+    pub depth: usize,
+}
+
+impl FromRow for ReachedNodeRow {
+    fn from_row(row: Row<'_>) -> Result<Self, DbError> {
+        Ok(Self {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            relation: row.get("relation")?,
+            depth: row.get("depth")?,
+        })
+    }
+}
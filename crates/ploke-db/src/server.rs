@@ -0,0 +1,302 @@
+//! Read-only HTTP/JSON server over a loaded database, behind the `server` feature.
+//!
+//! Every endpoint takes a JSON body with `POST`:
+//! - `/query`: a Cozo script with named parameters, run immutably. Returns the `headers` and
+//!   `rows` of the result.
+//! - `/search/dense`: nearest neighbours of an embedding vector across node types, see
+//!   [`search_similar_args`].
+//! - `/search/bm25`: a query in the [`Bm25Query`](crate::bm25_index::Bm25Query) syntax.
+//! - `/nodes/by-path`: nodes of one type by module path and name, see [`resolve_nodes_by_canon`].
+//! - `/traverse`: the [`neighborhood`] of a node.
+//!
+//! Errors are returned as `{ "error": message }`, with status 400 for requests the database
+//! rejects and 500 for everything else.
+//!
+//! Cozo queries and BM25 searches block, so handlers run them on the blocking thread pool rather
+//! than on the runtime's workers.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::Router;
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use cozo::{DataValue, ScriptMutability};
+use ploke_core::{EmbeddingData, RetrievalScope};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use crate::bm25_index::Bm25Indexer;
+use crate::helpers::resolve_nodes_by_canon;
+use crate::traversal::{Neighborhood, TraversalOptions, neighborhood};
+use crate::{Database, DbError, Direction, EdgeKind, NodeType, SimilarArgs, search_similar_args};
+
+/// The database and BM25 index served by [`router`].
+#[derive(Clone)]
+pub struct ServerState {
+    pub db: Arc<Database>,
+    pub bm25: Arc<Bm25Indexer>,
+}
+
+/// An error response of the server.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl ToString) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.to_string(),
+        }
+    }
+
+    fn internal(message: impl ToString) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<DbError> for ApiError {
+    fn from(e: DbError) -> Self {
+        let status = match e {
+            DbError::NotFound => StatusCode::NOT_FOUND,
+            DbError::Cozo(_)
+            | DbError::CozoQuery { .. }
+            | DbError::QueryExecution(_)
+            | DbError::QueryConstruction(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self {
+            status,
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<ploke_error::Error> for ApiError {
+    fn from(e: ploke_error::Error) -> Self {
+        Self::internal(e)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueryRequest {
+    pub script: String,
+    /// Named parameters of the script, as JSON values.
+    #[serde(default)]
+    pub params: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DenseSearchRequest {
+    /// Query embedding, of the dimension of the active embedding set.
+    pub vector: Vec<f32>,
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+    /// Node types to search; primary and associated nodes when empty.
+    #[serde(default)]
+    pub node_types: Vec<NodeType>,
+    #[serde(default = "default_ef")]
+    pub ef: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DenseHit {
+    pub node_type: NodeType,
+    pub node: EmbeddingData,
+    /// `1.0 - distance`, as scored by dense retrieval in `ploke-rag`.
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Bm25SearchRequest {
+    pub query: String,
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bm25Hit {
+    pub id: Uuid,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NodeByPathRequest {
+    pub node_type: NodeType,
+    /// Path of the module holding the node, e.g. `["crate", "parser"]`.
+    pub module_path: Vec<String>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TraverseRequest {
+    pub center: Uuid,
+    /// Edge kinds to follow; any kind when empty.
+    #[serde(default)]
+    pub edge_kinds: Vec<EdgeKind>,
+    /// Node types to go through; primary and associated nodes when empty.
+    #[serde(default)]
+    pub node_types: Vec<NodeType>,
+    #[serde(default = "default_direction")]
+    pub direction: Direction,
+    #[serde(default = "default_max_hops")]
+    pub max_hops: usize,
+}
+
+fn default_top_k() -> usize {
+    10
+}
+
+fn default_ef() -> usize {
+    10
+}
+
+fn default_direction() -> Direction {
+    Direction::Both
+}
+
+fn default_max_hops() -> usize {
+    2
+}
+
+/// Runs `f` on the blocking thread pool.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, ApiError> + Send + 'static,
+) -> Result<T, ApiError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(ApiError::internal)?
+}
+
+/// The routes of the server, see the [module docs](self).
+pub fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/query", post(query))
+        .route("/search/dense", post(dense_search))
+        .route("/search/bm25", post(bm25_search))
+        .route("/nodes/by-path", post(node_by_path))
+        .route("/traverse", post(traverse))
+        .with_state(state)
+}
+
+async fn query(
+    State(state): State<ServerState>,
+    Json(req): Json<QueryRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let params = req
+        .params
+        .into_iter()
+        .map(|(name, value)| (name, DataValue::from(value)))
+        .collect();
+    let rows = blocking(move || {
+        state
+            .db
+            .run_script(&req.script, params, ScriptMutability::Immutable)
+            .map_err(ApiError::bad_request)
+    })
+    .await?;
+    Ok(Json(rows.into_json()))
+}
+
+async fn dense_search(
+    State(state): State<ServerState>,
+    Json(req): Json<DenseSearchRequest>,
+) -> Result<Json<Vec<DenseHit>>, ApiError> {
+    let node_types = if req.node_types.is_empty() {
+        NodeType::primary_and_assoc_nodes().to_vec()
+    } else {
+        req.node_types
+    };
+    let hits = blocking(move || {
+        let mut hits = Vec::new();
+        for node_type in node_types {
+            let result = search_similar_args(SimilarArgs {
+                db: &state.db,
+                vector_query: &req.vector,
+                scope: RetrievalScope::LoadedWorkspace,
+                k: req.top_k,
+                ef: req.ef,
+                ty: node_type,
+                max_hits: req.top_k,
+                radius: 100.0,
+            })?;
+            hits.extend(result.typed_data.v.into_iter().zip(result.dist).map(
+                |(node, distance)| DenseHit {
+                    node_type,
+                    node,
+                    score: 1.0 - distance as f32,
+                },
+            ));
+        }
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(req.top_k);
+        Ok(hits)
+    })
+    .await?;
+    Ok(Json(hits))
+}
+
+async fn bm25_search(
+    State(state): State<ServerState>,
+    Json(req): Json<Bm25SearchRequest>,
+) -> Result<Json<Vec<Bm25Hit>>, ApiError> {
+    let hits = blocking(move || {
+        Ok(state
+            .bm25
+            .search(&req.query, req.top_k, RetrievalScope::LoadedWorkspace)
+            .into_iter()
+            .map(|doc| Bm25Hit {
+                id: doc.id,
+                score: doc.score,
+            })
+            .collect())
+    })
+    .await?;
+    Ok(Json(hits))
+}
+
+async fn node_by_path(
+    State(state): State<ServerState>,
+    Json(req): Json<NodeByPathRequest>,
+) -> Result<Json<Vec<EmbeddingData>>, ApiError> {
+    let nodes = blocking(move || {
+        Ok(resolve_nodes_by_canon(
+            &state.db,
+            req.node_type.relation_str(),
+            &req.module_path,
+            &req.name,
+        )?)
+    })
+    .await?;
+    Ok(Json(nodes))
+}
+
+async fn traverse(
+    State(state): State<ServerState>,
+    Json(req): Json<TraverseRequest>,
+) -> Result<Json<Neighborhood>, ApiError> {
+    let mut options = TraversalOptions::default()
+        .edge_kinds(&req.edge_kinds)
+        .direction(req.direction)
+        .max_hops(req.max_hops);
+    if !req.node_types.is_empty() {
+        options = options.node_types(&req.node_types);
+    }
+    let reached = blocking(move || Ok(neighborhood(&*state.db, req.center, &options)?)).await?;
+    Ok(Json(reached))
+}
@@ -1,6 +1,7 @@
 use std::path::Path;
 
 use itertools::Itertools;
+use ploke_transform::schema::CozoStorage;
 
 use crate::{Database, DbError, result::get_pos};
 
@@ -10,7 +11,7 @@ pub trait Validator {
     fn validate_path_module(&self, module_path: &[&str]) -> Result<(), DbError>;
 }
 
-impl Validator for Database {
+impl<S: CozoStorage> Validator for Database<S> {
     fn validate_path_file(&self, file_path: &Path) -> Result<(), DbError> {
         let fp = file_path.display().to_string();
         // let query = "?[count(files)] := *module {{id, name, module_kind, path @ 'NOW'";
@@ -0,0 +1,464 @@
+//! Traversals of the code graph: k-hop neighborhoods, shortest paths, fixed edge paths, and the
+//! strongly connected components of the module import graph.
+//!
+//! Neighborhoods and shortest paths run in one query each, following edges with the same
+//! recursive rule as [`GraphQuery::follow`](crate::GraphQuery::follow). `syntax_edge` is keyed by
+//! `source_id`, so an outgoing hop only looks up the edges of the nodes it starts from, while an
+//! incoming hop scans the whole relation.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use cozo::{DataValue, UuidWrapper};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::database::{to_string, to_string_list, to_usize, to_uuid};
+use crate::query::graph::{Direction, EdgeKind, Traversal, edge_atom, edge_ends, named_rules};
+use crate::typed_rows::ReachedNodeRow;
+use crate::{CozoStorage, Database, DbError, NodeType};
+
+/// Which edges and nodes a traversal may go through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraversalOptions {
+    /// Edge kinds to follow. Empty follows edges of any kind.
+    pub edge_kinds: Vec<EdgeKind>,
+    /// Node types the traversal may reach and go through.
+    pub node_types: Vec<NodeType>,
+    pub direction: Direction,
+    pub max_hops: usize,
+}
+
+impl Default for TraversalOptions {
+    /// Any edge, in both directions, through primary and associated nodes, for up to two hops.
+    fn default() -> Self {
+        Self {
+            edge_kinds: Vec::new(),
+            node_types: NodeType::primary_and_assoc_nodes().to_vec(),
+            direction: Direction::Both,
+            max_hops: 2,
+        }
+    }
+}
+
+impl TraversalOptions {
+    pub fn edge_kinds(mut self, edge_kinds: &[EdgeKind]) -> Self {
+        self.edge_kinds = edge_kinds.to_vec();
+        self
+    }
+
+    pub fn node_types(mut self, node_types: &[NodeType]) -> Self {
+        self.node_types = node_types.to_vec();
+        self
+    }
+
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn max_hops(mut self, max_hops: usize) -> Self {
+        self.max_hops = max_hops;
+        self
+    }
+
+    /// The node types to go through that have a name to report.
+    fn named_node_types(&self) -> Vec<NodeType> {
+        self.node_types
+            .iter()
+            .copied()
+            .filter(|node_type| {
+                let fields = node_type.fields();
+                fields.contains(&"id") && fields.contains(&"name")
+            })
+            .collect()
+    }
+}
+
+/// A `syntax_edge` row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source_id: Uuid,
+    pub target_id: Uuid,
+    pub relation_kind: String,
+}
+
+/// The nodes reached from a center node and the edges followed to reach them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Neighborhood {
+    /// The center at depth 0, when it is of one of the allowed node types, then the reached nodes
+    /// by depth.
+    pub nodes: Vec<ReachedNodeRow>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// A shortest path between two nodes: `nodes` from the start to the goal, and the edge followed
+/// between each consecutive pair.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphPath {
+    pub nodes: Vec<ReachedNodeRow>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// A module of the import graph, see [`module_import_cycles`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleRow {
+    pub id: Uuid,
+    pub path: Vec<String>,
+}
+
+fn uuid_list(ids: impl IntoIterator<Item = Uuid>) -> DataValue {
+    DataValue::List(
+        ids.into_iter()
+            .map(|id| DataValue::Uuid(UuidWrapper(id)))
+            .collect(),
+    )
+}
+
+/// The name and relation of each of `ids` held by one of `node_types`.
+fn describe_nodes(
+    db: &Database<impl CozoStorage>,
+    ids: &[Uuid],
+    node_types: &[NodeType],
+) -> Result<HashMap<Uuid, (String, String)>, DbError> {
+    let rules = named_rules("?[id, name, relation] := ids[id]", node_types);
+    if rules.is_empty() || ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let params = BTreeMap::from([(
+        "ids".to_string(),
+        DataValue::List(
+            ids.iter()
+                .map(|id| DataValue::List(vec![DataValue::Uuid(UuidWrapper(*id))]))
+                .collect(),
+        ),
+    )]);
+
+    db.raw_query_params(&format!("ids[id] <- $ids\n{rules}"), params)?
+        .rows
+        .iter()
+        .map(|row| {
+            Ok((
+                to_uuid(&row[0])?,
+                (to_string(&row[1])?, to_string(&row[2])?),
+            ))
+        })
+        .collect()
+}
+
+/// The nodes within `options.max_hops` of `start`, going only through edges and nodes allowed by
+/// `options`, each paired with every edge reaching it from a node one hop closer to `start`.
+/// Sorted by depth, then name.
+fn reach_steps(
+    db: &Database<impl CozoStorage>,
+    start: Uuid,
+    options: &TraversalOptions,
+) -> Result<Vec<(ReachedNodeRow, GraphEdge)>, DbError> {
+    let node_types = options.named_node_types();
+    if node_types.is_empty() || options.max_hops == 0 {
+        return Ok(Vec::new());
+    }
+    let traversal = Traversal {
+        kinds: options.edge_kinds.clone(),
+        direction: options.direction,
+        max_hops: options.max_hops,
+        through: node_types.clone(),
+    };
+    let mut params = BTreeMap::from([("start".to_string(), DataValue::Uuid(UuidWrapper(start)))]);
+    let reach = traversal.reach_rules(&mut params);
+    let kind_filter = traversal.kind_filter(&mut params);
+    let steps = edge_ends(options.direction, "prev", "id")
+        .into_iter()
+        .map(|(source, target)| {
+            format!(
+                "step[source_id, target_id, relation_kind, id, depth] := level[prev, d], {}{kind_filter}, level[id, depth], depth == d + 1, source_id = {source}, target_id = {target}",
+                edge_atom(source, target)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let named = named_rules(
+        "?[id, name, relation, depth, source_id, target_id, relation_kind] := step[source_id, target_id, relation_kind, id, depth]",
+        &node_types,
+    );
+    let script = format!(
+        r#"start[id] <- [[$start]]
+{reach}
+level[id, min(depth)] := reach[id, depth]
+{steps}
+{named}
+:order depth, name"#
+    );
+
+    db.raw_query_params(&script, params)?
+        .rows
+        .iter()
+        .map(|row| {
+            let node = ReachedNodeRow {
+                id: to_uuid(&row[0])?,
+                name: to_string(&row[1])?,
+                relation: to_string(&row[2])?,
+                depth: to_usize(&row[3])?,
+            };
+            let edge = GraphEdge {
+                source_id: to_uuid(&row[4])?,
+                target_id: to_uuid(&row[5])?,
+                relation_kind: to_string(&row[6])?,
+            };
+            Ok((node, edge))
+        })
+        .collect()
+}
+
+fn reached(id: Uuid, depth: usize, (name, relation): &(String, String)) -> ReachedNodeRow {
+    ReachedNodeRow {
+        id,
+        name: name.clone(),
+        relation: relation.clone(),
+        depth,
+    }
+}
+
+/// The nodes within `options.max_hops` of `center`, going only through edges and nodes allowed by
+/// `options`. Each node is reported once, at the fewest hops it is reached at, along with one of
+/// the edges reaching it from a node one hop closer.
+pub fn neighborhood(
+    db: &Database<impl CozoStorage>,
+    center: Uuid,
+    options: &TraversalOptions,
+) -> Result<Neighborhood, DbError> {
+    let mut neighborhood = Neighborhood::default();
+    if let Some(node) = describe_nodes(db, &[center], &options.node_types)?.get(&center) {
+        neighborhood.nodes.push(reached(center, 0, node));
+    }
+    let mut seen = HashSet::new();
+    for (node, edge) in reach_steps(db, center, options)? {
+        if seen.insert(node.id) {
+            neighborhood.nodes.push(node);
+            neighborhood.edges.push(edge);
+        }
+    }
+    Ok(neighborhood)
+}
+
+/// A shortest path from `start` to `goal` within `options.max_hops`, going only through edges and
+/// nodes allowed by `options`, or `None` if there is none.
+pub fn shortest_path(
+    db: &Database<impl CozoStorage>,
+    start: Uuid,
+    goal: Uuid,
+    options: &TraversalOptions,
+) -> Result<Option<GraphPath>, DbError> {
+    let start_node = describe_nodes(db, &[start], &NodeType::all_variants())?
+        .remove(&start)
+        .unwrap_or_default();
+    if start == goal {
+        return Ok(Some(GraphPath {
+            nodes: vec![reached(start, 0, &start_node)],
+            edges: Vec::new(),
+        }));
+    }
+
+    // Each reached node keeps one edge from a node one hop closer to `start`, so following these
+    // edges back from `goal` ends at `start`.
+    let mut parents: HashMap<Uuid, (ReachedNodeRow, GraphEdge)> = HashMap::new();
+    for (node, edge) in reach_steps(db, start, options)? {
+        parents.entry(node.id).or_insert((node, edge));
+    }
+    if !parents.contains_key(&goal) {
+        return Ok(None);
+    }
+
+    let mut nodes = VecDeque::new();
+    let mut edges = VecDeque::new();
+    let mut current = goal;
+    while let Some((node, edge)) = parents.remove(&current) {
+        current = if edge.target_id == current {
+            edge.source_id
+        } else {
+            edge.target_id
+        };
+        nodes.push_front(node);
+        edges.push_front(edge);
+    }
+    nodes.push_front(reached(start, 0, &start_node));
+    Ok(Some(GraphPath {
+        nodes: nodes.into(),
+        edges: edges.into(),
+    }))
+}
+
+/// The nodes reached from each of `starts` by following exactly one edge of each of `steps`, in
+/// order, as `(start, reached)` pairs. Unlike [`neighborhood`], intermediate nodes may be of any
+/// type, e.g. the impl block between a method and the type it is implemented for.
+pub fn follow_edge_path(
+    db: &Database<impl CozoStorage>,
+    starts: &[Uuid],
+    steps: &[(EdgeKind, Direction)],
+) -> Result<Vec<(Uuid, Uuid)>, DbError> {
+    if starts.is_empty() || steps.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut params = BTreeMap::from([(
+        "starts".to_string(),
+        DataValue::List(
+            starts
+                .iter()
+                .map(|id| DataValue::List(vec![DataValue::Uuid(UuidWrapper(*id))]))
+                .collect(),
+        ),
+    )]);
+    let mut rules = vec!["hop_0[start, node] := starts[start], node = start".to_string()];
+    for (i, (kind, direction)) in steps.iter().enumerate() {
+        let (prev, hop) = (format!("hop_{i}"), format!("hop_{}", i + 1));
+        params.insert(format!("kind_{i}"), DataValue::from(kind.as_str()));
+        let outgoing = format!(
+            "{hop}[start, next] := {prev}[start, node], *syntax_edge {{ source_id: node, target_id: next, relation_kind @ 'NOW' }}, relation_kind = $kind_{i}"
+        );
+        let incoming = format!(
+            "{hop}[start, next] := {prev}[start, node], *syntax_edge {{ source_id: next, target_id: node, relation_kind @ 'NOW' }}, relation_kind = $kind_{i}"
+        );
+        match direction {
+            Direction::Outgoing => rules.push(outgoing),
+            Direction::Incoming => rules.push(incoming),
+            Direction::Both => rules.extend([outgoing, incoming]),
+        }
+    }
+    let script = format!(
+        "starts[start] <- $starts\n{}\n?[start, node] := hop_{}[start, node]",
+        rules.join("\n"),
+        steps.len()
+    );
+
+    db.raw_query_params(&script, params)?
+        .rows
+        .iter()
+        .map(|row| Ok((to_uuid(&row[0])?, to_uuid(&row[1])?)))
+        .collect()
+}
+
+/// The edges of the module import graph: a module imports another when one of its `use` items
+/// brings that module, or an item it directly contains, into scope.
+pub fn module_import_edges(db: &Database<impl CozoStorage>) -> Result<Vec<(Uuid, Uuid)>, DbError> {
+    let script = r#"
+imported_module[from_mod, def] :=
+    *syntax_edge{source_id: from_mod, target_id: import_id, relation_kind: "ModuleImports" @ 'NOW' },
+    *syntax_edge{source_id: def, target_id: import_id, relation_kind: "ImportedBy" @ 'NOW' }
+
+imports[from_mod, to_mod] := imported_module[from_mod, to_mod], *module{ id: to_mod @ 'NOW' }
+imports[from_mod, to_mod] :=
+    imported_module[from_mod, def],
+    not *module{ id: def @ 'NOW' },
+    *syntax_edge{source_id: to_mod, target_id: def, relation_kind: "Contains" @ 'NOW' }
+
+?[from_mod, to_mod] := imports[from_mod, to_mod], from_mod != to_mod
+"#;
+    db.raw_query(script)?
+        .rows
+        .iter()
+        .map(|row| Ok((to_uuid(&row[0])?, to_uuid(&row[1])?)))
+        .collect()
+}
+
+/// The strongly connected components of the module import graph with more than one module,
+/// i.e. the groups of modules importing each other in a cycle. Modules are sorted by path within
+/// each component.
+pub fn module_import_cycles(
+    db: &Database<impl CozoStorage>,
+) -> Result<Vec<Vec<ModuleRow>>, DbError> {
+    let edges = module_import_edges(db)?;
+    let cycles: Vec<Vec<Uuid>> = strongly_connected_components(&edges)
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .collect();
+    if cycles.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let params = BTreeMap::from([(
+        "ids".to_string(),
+        uuid_list(cycles.iter().flatten().copied()),
+    )]);
+    let paths: HashMap<Uuid, Vec<String>> = db
+        .raw_query_params(
+            "?[id, path] := *module { id, path @ 'NOW' }, is_in(id, $ids)",
+            params,
+        )?
+        .rows
+        .iter()
+        .map(|row| Ok((to_uuid(&row[0])?, to_string_list(&row[1])?)))
+        .collect::<Result<_, DbError>>()?;
+
+    Ok(cycles
+        .into_iter()
+        .map(|component| {
+            let mut modules: Vec<ModuleRow> = component
+                .into_iter()
+                .map(|id| ModuleRow {
+                    id,
+                    path: paths.get(&id).cloned().unwrap_or_default(),
+                })
+                .collect();
+            modules.sort_by(|a, b| a.path.cmp(&b.path));
+            modules
+        })
+        .collect())
+}
+
+/// Tarjan's algorithm over the directed graph of `edges`, without recursion.
+pub fn strongly_connected_components(edges: &[(Uuid, Uuid)]) -> Vec<Vec<Uuid>> {
+    let mut successors: BTreeMap<Uuid, Vec<Uuid>> = BTreeMap::new();
+    for (from, to) in edges {
+        successors.entry(*from).or_default().push(*to);
+        successors.entry(*to).or_default();
+    }
+
+    let mut index: HashMap<Uuid, usize> = HashMap::new();
+    let mut low_link: HashMap<Uuid, usize> = HashMap::new();
+    let mut on_stack: HashSet<Uuid> = HashSet::new();
+    let mut stack: Vec<Uuid> = Vec::new();
+    let mut components = Vec::new();
+
+    for &root in successors.keys() {
+        if index.contains_key(&root) {
+            continue;
+        }
+        // (node, position of the next successor to visit)
+        let mut work = vec![(root, 0usize)];
+        while let Some((node, next)) = work.pop() {
+            if next == 0 {
+                let order = index.len();
+                index.insert(node, order);
+                low_link.insert(node, order);
+                stack.push(node);
+                on_stack.insert(node);
+            }
+            if let Some(&succ) = successors[&node].get(next) {
+                work.push((node, next + 1));
+                if !index.contains_key(&succ) {
+                    work.push((succ, 0));
+                } else if on_stack.contains(&succ) {
+                    let low = low_link[&node].min(index[&succ]);
+                    low_link.insert(node, low);
+                }
+                continue;
+            }
+
+            if low_link[&node] == index[&node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack.remove(&member);
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+            if let Some(&(parent, _)) = work.last() {
+                let low = low_link[&parent].min(low_link[&node]);
+                low_link.insert(parent, low);
+            }
+        }
+    }
+    components
+}
@@ -2,8 +2,9 @@
 
 use cozo::Db;
 use cozo::MemStorage;
-use ploke_db::Database;
+use ploke_db::{Database, DbError};
 use ploke_transform::schema::create_schema_all;
+use uuid::Uuid;
 
 /// Creates a new in-memory database with the schema initialized
 pub fn setup_test_db() -> Db<MemStorage> {
@@ -21,3 +22,38 @@ pub fn setup_test_db() -> Db<MemStorage> {
 pub fn setup_test_db_with_index() -> Database {
     Database::init_with_schema().expect("Failed to initialize database")
 }
+
+/// Inserts an inline module at `path`, named after its last segment.
+pub fn insert_module(db: &Database, id: Uuid, path: &[&str]) -> Result<(), DbError> {
+    let name = path.last().expect("module name");
+    let path = serde_json::to_string(path).expect("path literal");
+    db.raw_query_mut(&format!(
+        r#"?[id, at, name, path, vis_kind, span, tracking_hash, module_kind, cfgs] <- [[to_uuid("{id}"), 'ASSERT', "{name}", {path}, "public", [0, 1], to_uuid("{id}"), "inline", []]]
+:put module {{ id, at => name, path, vis_kind, span, tracking_hash, module_kind, cfgs }}"#
+    ))?;
+    Ok(())
+}
+
+/// Inserts a function contained in `module_id`.
+pub fn insert_function(
+    db: &Database,
+    id: Uuid,
+    name: &str,
+    vis_kind: &str,
+    module_id: Uuid,
+) -> Result<(), DbError> {
+    db.raw_query_mut(&format!(
+        r#"?[id, at, name, vis_kind, span, tracking_hash, cfgs, module_id] <- [[to_uuid("{id}"), 'ASSERT', "{name}", "{vis_kind}", [0, 1], to_uuid("{id}"), [], to_uuid("{module_id}")]]
+:put function {{ id, at => name, vis_kind, span, tracking_hash, cfgs, module_id }}"#
+    ))?;
+    insert_edge(db, module_id, id, "Contains")
+}
+
+/// Inserts a `syntax_edge` row of `kind`.
+pub fn insert_edge(db: &Database, source: Uuid, target: Uuid, kind: &str) -> Result<(), DbError> {
+    db.raw_query_mut(&format!(
+        r#"?[source_id, target_id, at, relation_kind, source_kind, target_kind] <- [[to_uuid("{source}"), to_uuid("{target}"), 'ASSERT', "{kind}", "", ""]]
+:put syntax_edge {{ source_id, target_id, at => relation_kind, source_kind, target_kind }}"#
+    ))?;
+    Ok(())
+}
@@ -2,5 +2,9 @@ pub mod database_tests;
 pub mod debug_cozo;
 pub mod index_test;
 pub mod method_embeddable;
+pub mod test_graph_query;
 #[cfg(feature = "type_bearing_ids")]
 pub mod test_queries;
+#[cfg(feature = "server")]
+pub mod test_server;
+pub mod test_traversal;
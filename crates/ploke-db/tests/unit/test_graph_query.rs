@@ -0,0 +1,125 @@
+//! Tests for typed graph queries.
+
+use ploke_db::{
+    Database, DbError, Direction, EdgeKind, GraphQuery, NodeRow, NodeType, ReachedNodeRow,
+};
+use uuid::Uuid;
+
+use crate::common::test_helpers::{insert_edge, insert_function, insert_module};
+
+/// `crate::parser` holds `parse`, which calls `lex`, which calls `next_token`. `crate::util`
+/// holds a private `parse`.
+struct Graph {
+    db: Database,
+    parse: Uuid,
+    lex: Uuid,
+    next_token: Uuid,
+    util_parse: Uuid,
+}
+
+fn sample_graph() -> Result<Graph, ploke_error::Error> {
+    let db = Database::init_with_schema()?;
+    let (parser, util) = (Uuid::new_v4(), Uuid::new_v4());
+    insert_module(&db, parser, &["crate", "parser"])?;
+    insert_module(&db, util, &["crate", "util"])?;
+
+    let graph = Graph {
+        parse: Uuid::new_v4(),
+        lex: Uuid::new_v4(),
+        next_token: Uuid::new_v4(),
+        util_parse: Uuid::new_v4(),
+        db,
+    };
+    insert_function(&graph.db, graph.parse, "parse", "public", parser)?;
+    insert_function(&graph.db, graph.lex, "lex", "crate", parser)?;
+    insert_function(&graph.db, graph.next_token, "next_token", "crate", parser)?;
+    insert_function(&graph.db, graph.util_parse, "parse", "inherited", util)?;
+    insert_edge(&graph.db, graph.parse, graph.lex, "Calls")?;
+    insert_edge(&graph.db, graph.lex, graph.next_token, "Calls")?;
+    Ok(graph)
+}
+
+#[test]
+fn test_graph_query_filters_by_name_path_and_visibility() -> Result<(), ploke_error::Error> {
+    let graph = sample_graph()?;
+
+    let mut parses: Vec<Uuid> = GraphQuery::new(NodeType::Function)
+        .name("parse")
+        .fetch::<NodeRow, _>(&graph.db)?
+        .into_iter()
+        .map(|row| row.id)
+        .collect();
+    parses.sort();
+    let mut expected = vec![graph.parse, graph.util_parse];
+    expected.sort();
+    assert_eq!(parses, expected);
+
+    let in_parser: Vec<NodeRow> = GraphQuery::new(NodeType::Function)
+        .name("parse")
+        .in_module(["crate", "parser"])
+        .fetch(&graph.db)?;
+    assert_eq!(in_parser.len(), 1);
+    assert_eq!(in_parser[0].id, graph.parse);
+
+    let crate_visible: Vec<NodeRow> = GraphQuery::new(NodeType::Function)
+        .visibility("crate")
+        .name_contains("ex")
+        .fetch(&graph.db)?;
+    let names: Vec<&str> = crate_visible.iter().map(|row| row.name.as_str()).collect();
+    assert_eq!(names, vec!["lex"]);
+
+    let projected = GraphQuery::new(NodeType::Function)
+        .name("lex")
+        .select("vis_kind")
+        .execute(&graph.db)?;
+    assert_eq!(projected.headers, vec!["id", "vis_kind"]);
+    Ok(())
+}
+
+#[test]
+fn test_graph_query_follows_edges_up_to_the_hop_limit() -> Result<(), ploke_error::Error> {
+    let graph = sample_graph()?;
+    let callees = |max_hops| {
+        GraphQuery::new(NodeType::Function)
+            .name("parse")
+            .in_module(["crate", "parser"])
+            .follow(&[EdgeKind::Calls], Direction::Outgoing, max_hops)
+            .fetch::<ReachedNodeRow, _>(&graph.db)
+    };
+
+    let one_hop = callees(1)?;
+    assert_eq!(one_hop.len(), 1);
+    assert_eq!((one_hop[0].id, one_hop[0].depth), (graph.lex, 1));
+
+    let two_hops = callees(2)?;
+    let reached: Vec<(Uuid, usize)> = two_hops.iter().map(|row| (row.id, row.depth)).collect();
+    assert_eq!(reached, vec![(graph.lex, 1), (graph.next_token, 2)]);
+    assert!(two_hops.iter().all(|row| row.relation == "function"));
+
+    let callers: Vec<ReachedNodeRow> = GraphQuery::new(NodeType::Function)
+        .name("next_token")
+        .follow(&[EdgeKind::Calls], Direction::Incoming, 5)
+        .fetch(&graph.db)?;
+    let names: Vec<&str> = callers.iter().map(|row| row.name.as_str()).collect();
+    assert_eq!(names, vec!["lex", "parse"]);
+    Ok(())
+}
+
+#[test]
+fn test_graph_query_passes_values_as_parameters() -> Result<(), ploke_error::Error> {
+    let graph = sample_graph()?;
+    let injected = r#"parse" or name == "lex"#;
+    let query = GraphQuery::new(NodeType::Function).name(injected);
+
+    let (script, params) = query.to_script()?;
+    assert!(!script.contains(injected));
+    assert_eq!(params.len(), 1);
+    assert!(query.fetch::<NodeRow, _>(&graph.db)?.is_empty());
+
+    let err = GraphQuery::new(NodeType::Function)
+        .select("not_a_field")
+        .to_script()
+        .expect_err("unknown field");
+    assert!(matches!(err, DbError::QueryConstruction(_)));
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//! Tests for the read-only query server.
+
+use std::sync::Arc;
+
+use axum::Router;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use ploke_db::Database;
+use ploke_db::bm25_index::Bm25Indexer;
+use ploke_db::server::{ServerState, router};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::common::test_helpers::{insert_edge, insert_function, insert_module};
+
+/// `crate::parser` holds `parse`, which calls `lex`.
+fn sample_server() -> Result<(Router, Uuid, Uuid), ploke_error::Error> {
+    let db = Database::init_with_schema()?;
+    let (parser, parse, lex) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    insert_module(&db, parser, &["crate", "parser"])?;
+    insert_function(&db, parse, "parse", "public", parser)?;
+    insert_function(&db, lex, "lex", "crate", parser)?;
+    insert_edge(&db, parse, lex, "Calls")?;
+
+    let bm25 = Bm25Indexer::new_from_corpus(vec![
+        (parse, "fn parse(input: &str) { lex(input) }".to_string()),
+        (lex, "fn lex(input: &str) {}".to_string()),
+    ]);
+    let state = ServerState {
+        db: Arc::new(db),
+        bm25: Arc::new(bm25),
+    };
+    Ok((router(state), parse, lex))
+}
+
+async fn post(router: &Router, path: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::post(path)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("request");
+    let response = router.clone().oneshot(request).await.expect("response");
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("body");
+    (status, serde_json::from_slice(&bytes).expect("json body"))
+}
+
+#[tokio::test]
+async fn test_server_runs_parameterized_queries_read_only() -> Result<(), ploke_error::Error> {
+    let (router, parse, _) = sample_server()?;
+
+    let (status, body) = post(
+        &router,
+        "/query",
+        json!({
+            "script": "?[id] := *function { id, name @ 'NOW' }, name = $name",
+            "params": { "name": "parse" },
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["headers"], json!(["id"]));
+    assert_eq!(body["rows"], json!([[parse.to_string()]]));
+
+    let (status, body) = post(
+        &router,
+        "/query",
+        json!({ "script": ":create scratch { x: Int }" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].is_string());
+    let (status, _) = post(
+        &router,
+        "/query",
+        json!({ "script": "?[x] := *scratch { x }" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_traverses_and_searches() -> Result<(), ploke_error::Error> {
+    let (router, parse, lex) = sample_server()?;
+
+    let (status, body) = post(
+        &router,
+        "/traverse",
+        json!({
+            "center": parse,
+            "edge_kinds": ["Calls"],
+            "direction": "outgoing",
+            "max_hops": 1,
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let reached: Vec<&str> = body["nodes"]
+        .as_array()
+        .expect("nodes")
+        .iter()
+        .filter_map(|node| node["name"].as_str())
+        .collect();
+    assert_eq!(reached, vec!["parse", "lex"]);
+    assert_eq!(body["edges"][0]["relation_kind"], json!("Calls"));
+
+    let (status, body) = post(
+        &router,
+        "/search/bm25",
+        json!({ "query": "lex", "top_k": 1 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body[0]["id"], json!(lex));
+    Ok(())
+}
@@ -0,0 +1,198 @@
+//! Tests for neighborhood, shortest-path and import-cycle traversals.
+
+use ploke_db::traversal::{
+    TraversalOptions, follow_edge_path, module_import_cycles, neighborhood, shortest_path,
+    strongly_connected_components,
+};
+use ploke_db::{Database, Direction, EdgeKind, NodeType};
+use uuid::Uuid;
+
+use crate::common::test_helpers::{insert_edge, insert_function, insert_module};
+
+/// `crate::a` holds `entry`, `crate::b` holds `helper` and `crate::c` holds `leaf`.
+/// `entry -> helper -> leaf` through `Calls`, and `entry -> leaf` through `UsesType`.
+/// `a` imports `helper`, `b` imports the module `c`, and `c` imports `entry`, so the three modules
+/// form an import cycle. `crate::d` imports `entry` without being imported back.
+struct Graph {
+    db: Database,
+    modules: [Uuid; 4],
+    entry: Uuid,
+    helper: Uuid,
+    leaf: Uuid,
+}
+
+fn import(db: &Database, module: Uuid, def: Uuid) -> Result<(), ploke_error::Error> {
+    let import_id = Uuid::new_v4();
+    insert_edge(db, module, import_id, "ModuleImports")?;
+    insert_edge(db, def, import_id, "ImportedBy")?;
+    Ok(())
+}
+
+fn sample_graph() -> Result<Graph, ploke_error::Error> {
+    let db = Database::init_with_schema()?;
+    let modules = [(); 4].map(|_| Uuid::new_v4());
+    for (id, name) in modules.iter().zip(["a", "b", "c", "d"]) {
+        insert_module(&db, *id, &["crate", name])?;
+    }
+    let [a, b, c, d] = modules;
+    let graph = Graph {
+        entry: Uuid::new_v4(),
+        helper: Uuid::new_v4(),
+        leaf: Uuid::new_v4(),
+        modules,
+        db,
+    };
+    insert_function(&graph.db, graph.entry, "entry", "public", a)?;
+    insert_function(&graph.db, graph.helper, "helper", "public", b)?;
+    insert_function(&graph.db, graph.leaf, "leaf", "public", c)?;
+    insert_edge(&graph.db, graph.entry, graph.helper, "Calls")?;
+    insert_edge(&graph.db, graph.helper, graph.leaf, "Calls")?;
+    insert_edge(&graph.db, graph.entry, graph.leaf, "UsesType")?;
+
+    import(&graph.db, a, graph.helper)?;
+    import(&graph.db, b, c)?;
+    import(&graph.db, c, graph.entry)?;
+    import(&graph.db, d, graph.entry)?;
+    Ok(graph)
+}
+
+#[test]
+fn test_neighborhood_filters_edge_and_node_kinds() -> Result<(), ploke_error::Error> {
+    let graph = sample_graph()?;
+    let calls = TraversalOptions::default()
+        .edge_kinds(&[EdgeKind::Calls])
+        .direction(Direction::Outgoing);
+
+    let one_hop = neighborhood(&graph.db, graph.entry, &calls.clone().max_hops(1))?;
+    let reached: Vec<(Uuid, usize)> = one_hop.nodes.iter().map(|n| (n.id, n.depth)).collect();
+    assert_eq!(reached, vec![(graph.entry, 0), (graph.helper, 1)]);
+    assert_eq!(one_hop.edges.len(), 1);
+
+    let two_hops = neighborhood(&graph.db, graph.entry, &calls)?;
+    let reached: Vec<(Uuid, usize)> = two_hops.nodes.iter().map(|n| (n.id, n.depth)).collect();
+    assert_eq!(
+        reached,
+        vec![(graph.entry, 0), (graph.helper, 1), (graph.leaf, 2)]
+    );
+
+    // Following any edge in both directions reaches the containing module, unless only
+    // functions may be reached.
+    let any_edge = neighborhood(
+        &graph.db,
+        graph.helper,
+        &TraversalOptions::default().max_hops(1),
+    )?;
+    assert!(any_edge.nodes.iter().any(|n| n.id == graph.modules[1]));
+    let functions_only = neighborhood(
+        &graph.db,
+        graph.helper,
+        &TraversalOptions::default()
+            .max_hops(1)
+            .node_types(&[NodeType::Function]),
+    )?;
+    assert!(
+        functions_only
+            .nodes
+            .iter()
+            .all(|n| n.relation == "function")
+    );
+    assert_eq!(functions_only.nodes.len(), 3);
+    Ok(())
+}
+
+#[test]
+fn test_shortest_path_prefers_fewest_hops() -> Result<(), ploke_error::Error> {
+    let graph = sample_graph()?;
+    let outgoing = TraversalOptions::default()
+        .direction(Direction::Outgoing)
+        .max_hops(3);
+
+    let path = shortest_path(&graph.db, graph.entry, graph.leaf, &outgoing)?.expect("path");
+    let ids: Vec<Uuid> = path.nodes.iter().map(|n| n.id).collect();
+    assert_eq!(ids, vec![graph.entry, graph.leaf]);
+    assert_eq!(path.edges[0].relation_kind, "UsesType");
+
+    let calls_only = outgoing.clone().edge_kinds(&[EdgeKind::Calls]);
+    let path = shortest_path(&graph.db, graph.entry, graph.leaf, &calls_only)?.expect("path");
+    let ids: Vec<Uuid> = path.nodes.iter().map(|n| n.id).collect();
+    assert_eq!(ids, vec![graph.entry, graph.helper, graph.leaf]);
+    assert_eq!(path.edges.len(), 2);
+
+    assert!(shortest_path(&graph.db, graph.leaf, graph.entry, &calls_only)?.is_none());
+    assert!(shortest_path(&graph.db, graph.entry, graph.leaf, &calls_only.max_hops(1))?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_follow_edge_path_goes_through_untyped_nodes() -> Result<(), ploke_error::Error> {
+    let graph = sample_graph()?;
+    let [a, b, _, d] = graph.modules;
+
+    let callees = follow_edge_path(
+        &graph.db,
+        &[graph.entry],
+        &[(EdgeKind::Calls, Direction::Outgoing); 2],
+    )?;
+    assert_eq!(callees, vec![(graph.entry, graph.leaf)]);
+
+    // The import between a module and the item it imports has no node row of its own.
+    let mut imported = follow_edge_path(
+        &graph.db,
+        &[a, d],
+        &[
+            (EdgeKind::ModuleImports, Direction::Outgoing),
+            (EdgeKind::ImportedBy, Direction::Incoming),
+        ],
+    )?;
+    imported.sort();
+    let mut expected = vec![(a, graph.helper), (d, graph.entry)];
+    expected.sort();
+    assert_eq!(imported, expected);
+
+    let parents = follow_edge_path(
+        &graph.db,
+        &[graph.helper],
+        &[(EdgeKind::Contains, Direction::Incoming)],
+    )?;
+    assert_eq!(parents, vec![(graph.helper, b)]);
+    assert!(follow_edge_path(&graph.db, &[graph.helper], &[])?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_module_import_cycles() -> Result<(), ploke_error::Error> {
+    let graph = sample_graph()?;
+    let cycles = module_import_cycles(&graph.db)?;
+    assert_eq!(cycles.len(), 1);
+    let paths: Vec<Vec<String>> = cycles[0].iter().map(|m| m.path.clone()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            vec!["crate".to_string(), "a".to_string()],
+            vec!["crate".to_string(), "b".to_string()],
+            vec!["crate".to_string(), "c".to_string()],
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_strongly_connected_components_of_nested_cycles() {
+    let [a, b, c, d, e] = [(); 5].map(|_| Uuid::new_v4());
+    let edges = [(a, b), (b, a), (b, c), (c, d), (d, c), (d, e)];
+    let mut components: Vec<Vec<Uuid>> = strongly_connected_components(&edges)
+        .into_iter()
+        .map(|mut component| {
+            component.sort();
+            component
+        })
+        .collect();
+    components.sort();
+
+    let mut expected = vec![vec![a, b], vec![c, d], vec![e]];
+    for component in &mut expected {
+        component.sort();
+    }
+    expected.sort();
+    assert_eq!(components, expected);
+}
@@ -16,8 +16,8 @@ use itertools::Itertools;
 use ploke_core::{
     EmbeddingData,
     rag_types::{
-        AssembledContext, CanonPath, ContextPart, ContextPartKind, ContextStats, Modality,
-        NodeFilepath,
+        AssembledContext, CanonPath, ContextPart, ContextPartKind, ContextStats, InclusionReason,
+        Modality, NodeFilepath, PartInclusion,
     },
 };
 use ploke_db::{
@@ -67,9 +67,11 @@ pub enum Ordering {
 pub struct AssemblyPolicy {
     pub ordering: Ordering,
     pub include_kinds: HashSet<ContextPartKind>,
-    /// Optional per-node-type caps for fairness; not yet enforced in this initial version.
+    /// Optional per-node-type caps for fairness: at most this many parts of each listed type are
+    /// admitted, counting parts folded into others. Parts of unlisted types are not capped.
     pub per_type_caps: Option<HashMap<NodeType, usize>>,
-    /// Allow overlapping snippet ranges; range handling is a no-op in this initial version.
+    /// Allow overlapping snippet ranges. When false, a part whose byte range overlaps, touches or
+    /// is nested in the range of an admitted part of the same file is folded into that part.
     pub allow_overlap: bool,
     /// If true, IO errors during snippet retrieval are treated as fatal.
     pub strict_io: bool,
@@ -93,6 +95,10 @@ impl Default for AssemblyPolicy {
 /// Trait for counting tokens. Implementations can be provided by consumers.
 pub trait TokenCounter: Send + Sync + std::fmt::Debug {
     fn count(&self, text: &str) -> usize;
+
+    /// Switches to the tokenizer of `model`; a no-op for counters that do not depend on the
+    /// model. See [`ModelTokenCounter`](crate::tokenizer::ModelTokenCounter).
+    fn select_model(&self, _model: &ploke_llm::ModelId) {}
 }
 
 /// A simple, deterministic tokenizer suitable for tests:
@@ -197,16 +203,26 @@ pub async fn assemble_context(
         .map(|ed| ed.file_path.to_string_lossy().to_string())
         .collect_vec();
 
+    // Ids and byte ranges in batch order; nodes without an embedding are not fetched at all.
+    let node_spans: Vec<(Uuid, (usize, usize))> = nodes
+        .iter()
+        .map(|ed| (ed.id, (ed.start_byte, ed.end_byte)))
+        .collect();
+    let node_types: HashMap<Uuid, NodeType> = match &policy.per_type_caps {
+        Some(_) => db.node_types_of(&dedup_ids).map_err(RagError::Db)?,
+        None => HashMap::new(),
+    };
+
     // Batch fetch snippets.
     let batch = io
         .get_snippets_batch(nodes)
         .await
         .map_err(|e| RagError::Search(format!("get_snippets_batch failed: {:?}", e)))?;
 
-    // Build preliminary parts (with placeholder file path and no ranges for now).
+    // Build preliminary parts.
     let mut prelim_parts: Vec<ContextPart> = Vec::with_capacity(batch.len());
     for (i, (res, node_paths)) in batch.into_iter().zip(node_paths.into_iter()).enumerate() {
-        let id = dedup_ids
+        let (id, range) = node_spans
             .get(i)
             .copied()
             .ok_or_else(|| RagError::Search(format!("mismatched batch index {}", i)))?;
@@ -220,7 +236,7 @@ pub async fn assemble_context(
                     id,
                     file_path: NodeFilepath::new(file),
                     canon_path: CanonPath::new(canon),
-                    ranges: Vec::new(),
+                    ranges: vec![range],
                     kind: ContextPartKind::Code,
                     text,
                     score: *score_map.get(&id).unwrap_or(&0.0),
@@ -284,7 +300,10 @@ pub async fn assemble_context(
     };
 
     let mut per_file_used: HashMap<NodeFilepath, usize> = HashMap::new();
+    let mut per_type_used: HashMap<NodeType, usize> = HashMap::new();
     let mut admitted: Vec<ContextPart> = Vec::with_capacity(parts.len());
+    let mut admitted_tokens: Vec<usize> = Vec::with_capacity(parts.len());
+    let mut folded: Vec<PartInclusion> = Vec::new();
 
     for mut part in parts {
         // Enforce per-part cap via trimming.
@@ -296,6 +315,54 @@ pub async fn assemble_context(
                 (part.text.clone(), false)
             };
 
+        // Enforce per-type quota, before folding so a capped part cannot enter merged into
+        // another one.
+        let node_type = node_types.get(&part.id).copied();
+        if let (Some(caps), Some(ty)) = (&policy.per_type_caps, node_type)
+            && let Some(cap) = caps.get(&ty)
+            && per_type_used.get(&ty).copied().unwrap_or(0) >= *cap
+        {
+            stats.capped_parts += 1;
+            continue;
+        }
+
+        // Fold into an admitted part of the same file when the ranges overlap, touch or nest;
+        // a nested part adds no text at all.
+        if !policy.allow_overlap && !truncated {
+            let folded_into = admitted.iter().enumerate().find_map(|(idx, other)| {
+                if other.file_path != part.file_path {
+                    return None;
+                }
+                let (range, text) = merge_snippets(other, &part)?;
+                let tokens = tokenizer.count(&text);
+                let extra = tokens.saturating_sub(admitted_tokens[idx]);
+                let file_used = per_file_used.get(&part.file_path).copied().unwrap_or(0);
+                let fits = (budget.per_part_max == 0 || tokens <= budget.per_part_max)
+                    && (budget.per_file_max == 0
+                        || file_used.saturating_add(extra) <= budget.per_file_max)
+                    && stats.total_tokens.saturating_add(extra) <= budget.max_total;
+                fits.then_some((idx, range, text, tokens, extra))
+            });
+            if let Some((idx, range, text, tokens, extra)) = folded_into {
+                let other = &mut admitted[idx];
+                folded.push(PartInclusion {
+                    id: part.id,
+                    reason: InclusionReason::Folded { into: other.id },
+                });
+                other.ranges = vec![range];
+                other.text = text;
+                other.score = other.score.max(part.score);
+                admitted_tokens[idx] = tokens;
+                *per_file_used.entry(part.file_path.clone()).or_insert(0) += extra;
+                stats.total_tokens = stats.total_tokens.saturating_add(extra);
+                stats.merged_parts += 1;
+                if let Some(ty) = node_type {
+                    *per_type_used.entry(ty).or_insert(0) += 1;
+                }
+                continue;
+            }
+        }
+
         let part_tokens = tokenizer.count(&maybe_trimmed);
         if truncated {
             stats.truncated_parts += 1;
@@ -308,7 +375,7 @@ pub async fn assemble_context(
         }
 
         // Enforce total cap
-        if budget.max_total < part_tokens {
+        if stats.total_tokens.saturating_add(part_tokens) > budget.max_total {
             // No more room.
             break;
         }
@@ -319,20 +386,57 @@ pub async fn assemble_context(
             .entry(part.file_path.clone())
             .and_modify(|t| *t += part_tokens)
             .or_insert(part_tokens);
-        let remaining_total = budget.max_total.saturating_sub(part_tokens);
+        if let Some(ty) = node_type {
+            *per_type_used.entry(ty).or_insert(0) += 1;
+        }
         stats.total_tokens = stats.total_tokens.saturating_add(part_tokens);
         admitted.push(part);
+        admitted_tokens.push(part_tokens);
     }
 
     stats.parts = admitted.len();
     stats.files = per_file_used.len();
 
+    let inclusions = admitted
+        .iter()
+        .map(|part| PartInclusion {
+            id: part.id,
+            reason: InclusionReason::Retrieved,
+        })
+        .chain(folded)
+        .collect();
     Ok(AssembledContext {
         parts: admitted,
         stats,
+        inclusions,
     })
 }
 
+/// The text and range covering both `a` and `b` when their ranges overlap, touch or nest.
+/// Snippets are the exact bytes of their range, so parts whose text does not match the length of
+/// their range (e.g. trimmed ones) are never merged.
+fn merge_snippets(a: &ContextPart, b: &ContextPart) -> Option<((usize, usize), String)> {
+    let (&[a_range], &[b_range]) = (a.ranges.as_slice(), b.ranges.as_slice()) else {
+        return None;
+    };
+    let (first, first_text, second, second_text) = if a_range.0 <= b_range.0 {
+        (a_range, &a.text, b_range, &b.text)
+    } else {
+        (b_range, &b.text, a_range, &a.text)
+    };
+    if second.0 > first.1
+        || first.1.checked_sub(first.0) != Some(first_text.len())
+        || second.1.checked_sub(second.0) != Some(second_text.len())
+    {
+        return None;
+    }
+    if second.1 <= first.1 {
+        return Some((first, first_text.clone()));
+    }
+    let tail = second_text.get(first.1 - second.0..)?;
+    Some(((first.0, second.1), format!("{first_text}{tail}")))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -376,4 +480,38 @@ mod tests {
         );
         assert_eq!(removed, 2);
     }
+
+    fn part(range: (usize, usize), text: &str) -> ContextPart {
+        ContextPart {
+            id: Uuid::from_u128(range.0 as u128),
+            file_path: NodeFilepath::new("src/lib.rs".to_string()),
+            canon_path: CanonPath::new("crate::lib".to_string()),
+            ranges: vec![range],
+            kind: ContextPartKind::Code,
+            text: text.to_string(),
+            score: 0.0,
+            modality: Modality::HybridFused,
+        }
+    }
+
+    #[test]
+    fn merge_snippets_joins_overlapping_and_nested_ranges() {
+        let src = "impl A { fn a() {} }\nfn b() {}";
+        let slice = |start: usize, end: usize| part((start, end), &src[start..end]);
+
+        // A method nested in its impl adds nothing.
+        let merged = merge_snippets(&slice(9, 18), &slice(0, 20));
+        assert_eq!(merged, Some(((0, 20), src[0..20].to_string())));
+        // Overlapping and adjacent ranges are joined in file order.
+        let merged = merge_snippets(&slice(15, 30), &slice(0, 20));
+        assert_eq!(merged, Some(((0, 30), src.to_string())));
+        let merged = merge_snippets(&slice(0, 21), &slice(21, 30));
+        assert_eq!(merged, Some(((0, 30), src.to_string())));
+        // Disjoint ranges and trimmed texts are kept apart.
+        assert_eq!(merge_snippets(&slice(0, 20), &slice(21, 30)), None);
+        assert_eq!(
+            merge_snippets(&part((0, 20), "impl A"), &slice(9, 18)),
+            None
+        );
+    }
 }
@@ -9,7 +9,7 @@
 //! context assembly policy. For diversity or learning-to-rank experiments, plug in a custom [`Reranker`].
 mod unit_tests;
 use super::*;
-use ploke_core::rag_types::AssembledContext;
+use ploke_core::rag_types::{AssembledContext, InclusionReason};
 use ploke_embed::indexer::EmbeddingProcessor;
 use ploke_embed::runtime::EmbeddingRuntime;
 use ploke_io::IoManagerHandle;
@@ -17,7 +17,7 @@ use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
-use tracing::trace;
+use tracing::{trace, warn};
 
 #[derive(Debug, Clone, Copy)]
 pub enum RetrievalStrategy {
@@ -59,8 +59,13 @@ impl Default for SearchParams {
 #[derive(Debug, Clone)]
 pub struct RagConfig {
     pub bm25_timeout_ms: u64,
+    /// Timeout of [`RagService::bm25_restore`], which may have to rebuild the whole index and so
+    /// takes far longer than a search.
+    pub bm25_restore_timeout_ms: u64,
     pub bm25_retry_backoff_ms: Vec<u64>,
     pub strict_bm25_by_default: bool,
+    /// Field boosts applied to BM25 queries.
+    pub bm25_boosts: FieldBoosts,
     pub rrf_default: RrfConfig,
     pub mmr_default: Option<MmrConfig>,
     pub score_norm: ScoreNorm,
@@ -68,14 +73,20 @@ pub struct RagConfig {
     pub assembly_policy: AssemblyPolicy,
     pub token_counter: Arc<dyn TokenCounter>,
     pub reranker: Option<Arc<dyn Reranker>>,
+    /// Graph expansion of the hits in [`RagService::get_context`]; disabled when `None`.
+    pub graph_expansion: Option<GraphExpansion>,
+    /// Query rewriting in [`RagService::get_context_for_turn`]; disabled when `None`.
+    pub query_rewrite: Option<QueryRewrite>,
 }
 
 impl Default for RagConfig {
     fn default() -> Self {
         Self {
             bm25_timeout_ms: crate::BM25_TIMEOUT_MS,
+            bm25_restore_timeout_ms: crate::BM25_RESTORE_TIMEOUT_MS,
             bm25_retry_backoff_ms: crate::BM25_RETRY_BACKOFF_MS.to_vec(),
             strict_bm25_by_default: false,
+            bm25_boosts: FieldBoosts::default(),
             rrf_default: RrfConfig::default(),
             mmr_default: None,
             score_norm: ScoreNorm::default(),
@@ -83,6 +94,8 @@ impl Default for RagConfig {
             assembly_policy: AssemblyPolicy::default(),
             token_counter: Arc::new(crate::context::ApproxCharTokenizer),
             reranker: None,
+            graph_expansion: None,
+            query_rewrite: None,
         }
     }
 }
@@ -252,8 +265,8 @@ impl RagService {
 
             let (tx, rx) = oneshot::channel();
             self.bm_embedder
-                .send(Bm25Cmd::Search {
-                    query: query.to_string(),
+                .send(Bm25Cmd::SearchQuery {
+                    query: Bm25Query::parse(query).with_boosts(self.cfg.bm25_boosts),
                     top_k,
                     scope,
                     resp: tx,
@@ -420,6 +433,31 @@ impl RagService {
         }
     }
 
+    /// Restore the BM25 index persisted in the database via actor with timeout, rebuilding it
+    /// when none is usable.
+    #[instrument(skip(self), fields(timeout_ms = self.cfg.bm25_restore_timeout_ms))]
+    pub async fn bm25_restore(&self) -> Result<(), RagError> {
+        let (tx, rx) = oneshot::channel();
+        self.bm_embedder
+            .send(Bm25Cmd::Restore { resp: tx })
+            .await
+            .map_err(|e| {
+                RagError::Channel(format!("failed to send BM25 restore command: {}", e))
+            })?;
+        match timeout(Duration::from_millis(self.cfg.bm25_restore_timeout_ms), rx).await {
+            Ok(Ok(Ok(()))) => Ok(()),
+            Ok(Ok(Err(db_err))) => Err(RagError::Db(db_err)),
+            Ok(Err(recv_err)) => Err(RagError::Channel(format!(
+                "BM25 restore response channel closed: {}",
+                recv_err
+            ))),
+            Err(_) => Err(RagError::Channel(format!(
+                "timeout waiting for BM25 restore ({} ms)",
+                self.cfg.bm25_restore_timeout_ms
+            ))),
+        }
+    }
+
     /// Execute a BM25 search in strict mode: no dense fallback.
     /// Returns error if the index is Uninitialized/Building/Empty when results are empty.
     #[instrument(skip(self, query), fields(query_len = %query.len(), top_k = top_k, strict = true, timeout_ms = BM25_TIMEOUT_MS))]
@@ -433,8 +471,8 @@ impl RagService {
 
         let (tx, rx) = oneshot::channel();
         self.bm_embedder
-            .send(Bm25Cmd::Search {
-                query: query.to_string(),
+            .send(Bm25Cmd::SearchQuery {
+                query: Bm25Query::parse(query).with_boosts(self.cfg.bm25_boosts),
                 top_k,
                 scope,
                 resp: tx,
@@ -550,6 +588,12 @@ impl RagService {
         Ok(all_results)
     }
 
+    /// Points the configured [`TokenCounter`] at the tokenizer of `model`, e.g. after the user
+    /// switches models. Loading a tokenizer may block on IO.
+    pub fn select_tokenizer_model(&self, model: &ploke_llm::ModelId) {
+        self.cfg.token_counter.select_model(model);
+    }
+
     /// High-level API: retrieve and assemble a context using the chosen strategy and budget.
     /// Uses configured defaults (policy, tokenizer, strict bm25) unless overridden by the strategy.
     #[instrument(skip(self, query, budget, strategy), fields(query_len = %query.len(), top_k = top_k))]
@@ -561,7 +605,97 @@ impl RagService {
         strategy: &RetrievalStrategy,
         scope: RetrievalScope,
     ) -> Result<AssembledContext, RagError> {
-        // 1) Retrieve hits according to strategy
+        let hits = self.retrieve(query, top_k, strategy, scope).await?;
+        self.assemble_hits(query, hits, budget).await
+    }
+
+    /// Like [`get_context`](Self::get_context), for a message of a conversation: with
+    /// [`RagConfig::query_rewrite`] set, `message` and the preceding turns in `history` (oldest
+    /// first) are rewritten into sub-queries whose results are fused, see
+    /// [`multi_query_search`](Self::multi_query_search). Otherwise the message is searched as is.
+    #[instrument(skip(self, message, history, budget, strategy), fields(message_len = %message.len(), turns = history.len(), top_k = top_k))]
+    pub async fn get_context_for_turn(
+        &self,
+        message: &str,
+        history: &[String],
+        top_k: usize,
+        budget: &TokenBudget,
+        strategy: &RetrievalStrategy,
+        scope: RetrievalScope,
+    ) -> Result<AssembledContext, RagError> {
+        let Some(rewrite) = &self.cfg.query_rewrite else {
+            return self
+                .get_context(message, top_k, budget, strategy, scope)
+                .await;
+        };
+        let sub_queries = rewrite.sub_queries(message, history).await;
+        let hits = self
+            .multi_query_search(&sub_queries, top_k, strategy, scope)
+            .await?;
+        self.assemble_hits(message, hits, budget).await
+    }
+
+    /// Retrieves each of `sub_queries` with `strategy` and fuses the ranked lists with weighted
+    /// RRF (see [`SubQueryKind::weight`]), returning the top_k results by fused score.
+    ///
+    /// HyDE snippets are code, not search terms, and are only searched densely. A failing
+    /// sub-query is logged and left out of the fusion; the error is returned only when every
+    /// sub-query fails.
+    #[instrument(skip(self, sub_queries, strategy), fields(sub_queries = sub_queries.len(), top_k = top_k))]
+    pub async fn multi_query_search(
+        &self,
+        sub_queries: &[SubQuery],
+        top_k: usize,
+        strategy: &RetrievalStrategy,
+        scope: RetrievalScope,
+    ) -> Result<Vec<(Uuid, f32)>, RagError> {
+        let mut lists: Vec<(Vec<(Uuid, f32)>, f32)> = Vec::with_capacity(sub_queries.len());
+        let mut last_err = None;
+        for sub_query in sub_queries {
+            let sub_strategy = match sub_query.kind {
+                SubQueryKind::Hyde => &RetrievalStrategy::Dense,
+                _ => strategy,
+            };
+            match self
+                .retrieve(&sub_query.text, top_k, sub_strategy, scope)
+                .await
+            {
+                Ok(hits) => {
+                    debug!(kind = ?sub_query.kind, hits = hits.len(), "sub-query retrieved");
+                    lists.push((hits, sub_query.kind.weight()));
+                }
+                Err(e) => {
+                    warn!(kind = ?sub_query.kind, "sub-query failed, fusing without it: {e}");
+                    last_err = Some(e);
+                }
+            }
+        }
+        if lists.is_empty()
+            && let Some(e) = last_err
+        {
+            return Err(e);
+        }
+        let weighted: Vec<(&[(Uuid, f32)], f32)> = lists
+            .iter()
+            .map(|(hits, weight)| (hits.as_slice(), *weight))
+            .collect();
+        let k = match strategy {
+            RetrievalStrategy::Hybrid { rrf, .. } => rrf.k,
+            _ => self.cfg.rrf_default.k,
+        };
+        let mut fused = rrf_fuse_many(&weighted, k);
+        fused.truncate(top_k);
+        Ok(fused)
+    }
+
+    /// Retrieves the hits for `query` according to `strategy`.
+    async fn retrieve(
+        &self,
+        query: &str,
+        top_k: usize,
+        strategy: &RetrievalStrategy,
+        scope: RetrievalScope,
+    ) -> Result<Vec<(Uuid, f32)>, RagError> {
         let hits: Vec<(Uuid, f32)> = match strategy {
             RetrievalStrategy::Dense => self
                 .search(query, top_k, scope)
@@ -594,7 +728,16 @@ impl RagService {
                 }
             }
         };
+        Ok(hits)
+    }
 
+    /// Reranks and expands `hits` as configured, then assembles them into a context.
+    async fn assemble_hits(
+        &self,
+        query: &str,
+        hits: Vec<(Uuid, f32)>,
+        budget: &TokenBudget,
+    ) -> Result<AssembledContext, RagError> {
         // Optional reranker: requires IoManager to fetch texts
         let final_hits: Vec<(Uuid, f32)> = if let Some(rr) = &self.cfg.reranker {
             let io = self
@@ -635,14 +778,24 @@ impl RagService {
             hits
         };
 
-        // 2) Assemble context
+        // Optional graph expansion: related items follow the hits with decayed scores
+        let mut final_hits = final_hits;
+        let mut reasons: HashMap<Uuid, InclusionReason> = HashMap::new();
+        if let Some(expansion) = &self.cfg.graph_expansion {
+            for (id, score, reason) in expand_hits(&self.db, &final_hits, expansion)? {
+                final_hits.push((id, score));
+                reasons.insert(id, reason);
+            }
+        }
+
+        // Assemble context
         let io = self
             .io
             .as_ref()
             .ok_or_else(|| RagError::Search("IoManagerHandle not configured".to_string()))?
             .clone();
 
-        assemble_context(
+        let mut ctx = assemble_context(
             query,
             &final_hits,
             budget,
@@ -651,7 +804,16 @@ impl RagService {
             &self.db,
             &io,
         )
-        .await
+        .await?;
+        for inclusion in &mut ctx.inclusions {
+            if matches!(inclusion.reason, InclusionReason::Folded { .. }) {
+                continue;
+            }
+            if let Some(reason) = reasons.remove(&inclusion.id) {
+                inclusion.reason = reason;
+            }
+        }
+        Ok(ctx)
     }
 
     /// Perform a hybrid search (BM25 + dense).
@@ -1,13 +1,24 @@
 #[cfg(test)]
 mod tests {
-    use std::{collections::BTreeMap, default, ops::Deref, sync::Arc};
+    use std::{
+        collections::{BTreeMap, HashMap},
+        default,
+        ops::Deref,
+        sync::Arc,
+    };
 
-    use crate::{RetrievalStrategy, TokenBudget};
+    use crate::{
+        ApproxCharTokenizer, AssemblyPolicy, ExpansionRule, GraphExpansion, RagConfig,
+        RetrievalStrategy, SubQuery, SubQueryKind, TokenBudget, assemble_context,
+    };
     use itertools::Itertools;
     use lazy_static::lazy_static;
-    use ploke_core::{CrateId, EmbeddingData, RetrievalScope};
+    use ploke_core::{
+        CrateId, EmbeddingData, RetrievalScope,
+        rag_types::{InclusionReason, PartInclusion},
+    };
     use ploke_db::{
-        Database, create_index_primary_with_index,
+        Database, Direction, EdgeKind, NodeType, create_index_primary_with_index,
         multi_embedding::{db_ext::EmbeddingExt, debug::DebugAll, hnsw_ext::HnswExt},
     };
     use ploke_embed::{
@@ -341,6 +352,33 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn multi_query_search_searches_hyde_densely_and_drops_failed_sub_queries()
+    -> Result<(), Error> {
+        init_tracing_once();
+        let rag = &DEFAULT_TEST_RAG;
+
+        // The BM25 index of this service is never built, so the strict sparse sub-query fails or
+        // finds nothing; the HyDE snippet is searched densely whatever the strategy.
+        let sub_queries = [
+            SubQuery {
+                kind: SubQueryKind::Original,
+                text: "use_all_const_static".to_string(),
+            },
+            SubQuery {
+                kind: SubQueryKind::Hyde,
+                text: "pub fn use_all_const_static() { let _ = \"path:nowhere kind:\"; }"
+                    .to_string(),
+            },
+        ];
+        let strategy = RetrievalStrategy::Sparse { strict: Some(true) };
+        let hits = rag
+            .multi_query_search(&sub_queries, 15, &strategy, LOADED_WORKSPACE_SCOPE)
+            .await?;
+        assert!(!hits.is_empty(), "HyDE sub-query must be searched densely");
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_bm25_rebuild() -> Result<(), Error> {
         init_tracing_once();
@@ -744,6 +782,209 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn get_context_expands_a_retrieved_method_to_its_self_type() -> Result<(), Error> {
+        init_tracing_once();
+        let db = Arc::new(fresh_backup_fixture_db(&FIXTURE_NODES_LOCAL_EMBEDDINGS)?);
+        let self_type = ExpansionRule::new(
+            "self_type",
+            &[
+                (EdgeKind::ImplAssociatedItem, Direction::Incoming),
+                (EdgeKind::ImplFor, Direction::Outgoing),
+            ],
+        );
+        let cfg = RagConfig {
+            graph_expansion: Some(GraphExpansion {
+                rules: vec![self_type],
+                ..GraphExpansion::default()
+            }),
+            ..RagConfig::default()
+        };
+        let rag = RagService::new_full(
+            Arc::clone(&db),
+            runtime_for(&db, EmbeddingProcessor::new_mock()),
+            IoManagerHandle::new(),
+            cfg,
+        )?;
+        rag.bm25_rebuild().await?;
+
+        let context = rag
+            .get_context(
+                "public_method",
+                1,
+                &TokenBudget::default(),
+                &RetrievalStrategy::Sparse { strict: Some(true) },
+                LOADED_WORKSPACE_SCOPE,
+            )
+            .await?;
+
+        let ids_named = |relation: &str, name: &str| -> Result<Vec<Uuid>, Error> {
+            let rows = db
+                .raw_query(&format!(
+                    r#"?[id] := *{relation} {{ id, name @ 'NOW' }}, name == "{name}""#
+                ))
+                .map_err(ploke_error::Error::from)?;
+            Ok(rows
+                .rows
+                .iter()
+                .filter_map(|row| row.first().and_then(|val| ploke_db::to_uuid(val).ok()))
+                .collect())
+        };
+        let method_ids = ids_named("method", "public_method")?;
+        let struct_ids = ids_named("struct", "SimpleStruct")?;
+
+        let retrieved = context
+            .inclusions
+            .iter()
+            .find(|inclusion| method_ids.contains(&inclusion.id))
+            .expect("the method is retrieved");
+        assert_eq!(retrieved.reason, InclusionReason::Retrieved);
+        let expanded = context
+            .inclusions
+            .iter()
+            .find(|inclusion| struct_ids.contains(&inclusion.id))
+            .expect("the struct the method is implemented for is pulled in");
+        assert_eq!(
+            expanded.reason,
+            InclusionReason::GraphExpansion {
+                from: retrieved.id,
+                rule: "self_type".to_string(),
+                hops: 2,
+            }
+        );
+        assert!(context.parts.iter().any(|part| part.id == expanded.id));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn assemble_context_caps_parts_per_node_type() -> Result<(), Error> {
+        init_tracing_once();
+        let db = default_test_db_setup()?;
+        let fetchable = |relation: &str| -> Result<Vec<EmbeddingData>, Error> {
+            let rows = db
+                .raw_query(&format!("?[id] := *{relation} {{ id @ 'NOW' }}"))
+                .map_err(ploke_error::Error::from)?;
+            let ids = rows
+                .rows
+                .iter()
+                .filter_map(|row| row.first().and_then(|val| ploke_db::to_uuid(val).ok()))
+                .collect();
+            db.get_nodes_ordered(ids)
+        };
+        let functions: Vec<EmbeddingData> = fetchable("function")?.into_iter().take(3).collect();
+        assert_eq!(functions.len(), 3, "fixture has at least three functions");
+        // Structs outside the file of the admitted function, so none is folded into it.
+        let structs: Vec<EmbeddingData> = fetchable("struct")?
+            .into_iter()
+            .filter(|node| node.file_path != functions[0].file_path)
+            .take(2)
+            .collect();
+        assert_eq!(structs.len(), 2, "fixture has structs in other files");
+
+        // Functions rank first; only the best one fits the cap of one.
+        let hits: Vec<(Uuid, f32)> = functions
+            .iter()
+            .chain(&structs)
+            .zip([1.0, 0.9, 0.8, 0.7, 0.6])
+            .map(|(node, score)| (node.id, score))
+            .collect();
+        let policy = AssemblyPolicy {
+            per_type_caps: Some(HashMap::from([(NodeType::Function, 1)])),
+            ..AssemblyPolicy::default()
+        };
+        let budget = TokenBudget {
+            max_total: 100_000,
+            per_file_max: 0,
+            per_part_max: 0,
+        };
+        let context = assemble_context(
+            "capped",
+            &hits,
+            &budget,
+            &policy,
+            &ApproxCharTokenizer,
+            &db,
+            &IoManagerHandle::new(),
+        )
+        .await?;
+
+        assert_eq!(context.stats.capped_parts, 2);
+        let admitted: Vec<Uuid> = context.parts.iter().map(|part| part.id).collect();
+        let mut expected = vec![functions[0].id, structs[0].id, structs[1].id];
+        expected.sort();
+        let mut admitted_sorted = admitted.clone();
+        admitted_sorted.sort();
+        assert_eq!(admitted_sorted, expected);
+        assert!(!admitted.contains(&functions[1].id) && !admitted.contains(&functions[2].id));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn assemble_context_records_parts_folded_into_others() -> Result<(), Error> {
+        init_tracing_once();
+        let db = default_test_db_setup()?;
+        let fetchable = |relation: &str| -> Result<Vec<EmbeddingData>, Error> {
+            let rows = db
+                .raw_query(&format!("?[id] := *{relation} {{ id @ 'NOW' }}"))
+                .map_err(ploke_error::Error::from)?;
+            let ids = rows
+                .rows
+                .iter()
+                .filter_map(|row| row.first().and_then(|val| ploke_db::to_uuid(val).ok()))
+                .collect();
+            db.get_nodes_ordered(ids)
+        };
+        let methods = fetchable("method")?;
+        let (outer, inner) = fetchable("impl")?
+            .into_iter()
+            .find_map(|outer| {
+                let inner = methods
+                    .iter()
+                    .find(|inner| {
+                        inner.file_path == outer.file_path
+                            && outer.start_byte <= inner.start_byte
+                            && inner.end_byte <= outer.end_byte
+                    })?
+                    .clone();
+                Some((outer, inner))
+            })
+            .expect("fixture has a method inside an impl");
+
+        let budget = TokenBudget {
+            max_total: 100_000,
+            per_file_max: 0,
+            per_part_max: 0,
+        };
+        let context = assemble_context(
+            "folded",
+            &[(outer.id, 1.0), (inner.id, 0.9)],
+            &budget,
+            &AssemblyPolicy::default(),
+            &ApproxCharTokenizer,
+            &db,
+            &IoManagerHandle::new(),
+        )
+        .await?;
+
+        assert_eq!(context.stats.merged_parts, 1);
+        let parts: Vec<Uuid> = context.parts.iter().map(|part| part.id).collect();
+        assert_eq!(parts, vec![outer.id]);
+        assert_eq!(
+            context.inclusions,
+            vec![
+                PartInclusion {
+                    id: outer.id,
+                    reason: InclusionReason::Retrieved,
+                },
+                PartInclusion {
+                    id: inner.id,
+                    reason: InclusionReason::Folded { into: outer.id },
+                },
+            ]
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_bm25_search_complex_enum() -> Result<(), Error> {
         init_tracing_once();
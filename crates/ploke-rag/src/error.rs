@@ -1,9 +1,9 @@
 #![allow(missing_docs)]
 //! Error types for ploke-rag.
 //!
-//! [`RagError`] captures channel failures, database/actor errors, embedding failures, and
-//! search state violations. A conversion into the workspace-wide error type is provided so
-//! higher layers can uniformly handle failures.
+//! [`RagError`] captures channel failures, database/actor errors, embedding, reranker and
+//! tokenizer failures, and search state violations. A conversion into the workspace-wide error
+//! type is provided so higher layers can uniformly handle failures.
 use ploke_db::DbError;
 use thiserror::Error;
 
@@ -20,6 +20,12 @@ pub enum RagError {
 
     #[error("Search error: {0}")]
     Search(String),
+
+    #[error("Rerank error: {0}")]
+    Rerank(String),
+
+    #[error("Tokenizer error: {0}")]
+    Tokenizer(String),
 }
 
 impl From<RagError> for ploke_error::Error {
@@ -45,6 +51,16 @@ impl From<RagError> for ploke_error::Error {
                     format!("Search error: {}", msg),
                 ))
             }
+            RagError::Rerank(msg) => {
+                ploke_error::Error::Internal(ploke_error::internal::InternalError::NotImplemented(
+                    format!("Rerank error: {}", msg),
+                ))
+            }
+            RagError::Tokenizer(msg) => {
+                ploke_error::Error::Internal(ploke_error::internal::InternalError::NotImplemented(
+                    format!("Tokenizer error: {}", msg),
+                ))
+            }
         }
     }
 }
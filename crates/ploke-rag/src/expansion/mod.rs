@@ -0,0 +1,256 @@
+#![allow(missing_docs)]
+//! Graph expansion: pulling structurally related items into the retrieved hits.
+//!
+//! Retrieval finds the items whose text matches the query, but the code around them is often
+//! needed to make sense of them: the type a method is implemented for, the trait it belongs to,
+//! the types of a struct's fields, the functions it calls and the module it lives in. Each
+//! [`ExpansionRule`] is a path of syntax edges from a retrieved item to such a related item, and
+//! [`expand_hits`] follows them from the best hits with scores decayed per hop, recording the rule
+//! that reached each item as an [`InclusionReason`].
+use std::collections::{HashMap, HashSet};
+
+use ploke_core::rag_types::InclusionReason;
+use ploke_db::{Database, DbError, Direction, EdgeKind, traversal::follow_edge_path};
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+use crate::error::RagError;
+
+/// A named path of edges from a retrieved item to a related one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionRule {
+    pub name: String,
+    pub steps: Vec<(EdgeKind, Direction)>,
+}
+
+impl ExpansionRule {
+    pub fn new(name: impl Into<String>, steps: &[(EdgeKind, Direction)]) -> Self {
+        Self {
+            name: name.into(),
+            steps: steps.to_vec(),
+        }
+    }
+
+    /// Number of edges followed by the rule.
+    pub fn hops(&self) -> usize {
+        self.steps.len()
+    }
+}
+
+/// Configuration of the graph expansion stage of
+/// [`RagService::get_context`](crate::RagService::get_context).
+#[derive(Debug, Clone)]
+pub struct GraphExpansion {
+    pub rules: Vec<ExpansionRule>,
+    /// Score factor applied per hop: a related item scores `seed_score * decay^hops`.
+    pub decay: f32,
+    /// Number of top hits expanded.
+    pub max_seeds: usize,
+    /// Maximum number of related items added per expanded hit.
+    pub per_seed_max: usize,
+}
+
+impl Default for GraphExpansion {
+    fn default() -> Self {
+        use Direction::{Incoming, Outgoing};
+        let rules = vec![
+            ExpansionRule::new(
+                "self_type",
+                &[
+                    (EdgeKind::ImplAssociatedItem, Incoming),
+                    (EdgeKind::ImplFor, Outgoing),
+                ],
+            ),
+            ExpansionRule::new(
+                "implemented_trait",
+                &[
+                    (EdgeKind::ImplAssociatedItem, Incoming),
+                    (EdgeKind::ImplementsTrait, Outgoing),
+                ],
+            ),
+            ExpansionRule::new(
+                "containing_trait",
+                &[(EdgeKind::TraitAssociatedItem, Incoming)],
+            ),
+            ExpansionRule::new(
+                "field_type",
+                &[
+                    (EdgeKind::StructField, Outgoing),
+                    (EdgeKind::UsesType, Outgoing),
+                ],
+            ),
+            ExpansionRule::new("uses_type", &[(EdgeKind::UsesType, Outgoing)]),
+            ExpansionRule::new("callee", &[(EdgeKind::Calls, Outgoing)]),
+            ExpansionRule::new("parent_module", &[(EdgeKind::Contains, Incoming)]),
+        ];
+        Self {
+            rules,
+            decay: 0.5,
+            max_seeds: 5,
+            per_seed_max: 4,
+        }
+    }
+}
+
+/// Items related to the top `cfg.max_seeds` of `hits` (ordered by score, desc), with their decayed
+/// scores and the reason they were reached. Items already in `hits` and items without a fetchable
+/// snippet are left out. The result is ordered by score (desc), tie-broken by UUID asc.
+#[instrument(skip(db, hits, cfg), fields(hits = hits.len(), rules = cfg.rules.len()))]
+pub fn expand_hits(
+    db: &Database,
+    hits: &[(Uuid, f32)],
+    cfg: &GraphExpansion,
+) -> Result<Vec<(Uuid, f32, InclusionReason)>, RagError> {
+    let seeds: Vec<Uuid> = hits.iter().take(cfg.max_seeds).map(|(id, _)| *id).collect();
+    if seeds.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut reached: Vec<(usize, Uuid, Uuid)> = Vec::new();
+    for (rule_idx, rule) in cfg.rules.iter().enumerate() {
+        let pairs = follow_edge_path(db, &seeds, &rule.steps)?;
+        reached.extend(pairs.into_iter().map(|(from, to)| (rule_idx, from, to)));
+    }
+
+    let mut expanded = rank_expansions(hits, &reached, cfg);
+    if expanded.is_empty() {
+        return Ok(expanded);
+    }
+
+    // Only keep items whose snippet can be fetched, so assembly sees the ids it is given.
+    let fetchable: HashSet<Uuid> = db
+        .get_nodes_ordered(expanded.iter().map(|(id, _, _)| *id).collect())
+        .map_err(|e| RagError::Db(DbError::QueryExecution(e.to_string())))?
+        .into_iter()
+        .map(|node| node.id)
+        .collect();
+    expanded.retain(|(id, _, _)| fetchable.contains(id));
+    debug!(expanded = expanded.len(), "graph expansion done");
+    Ok(expanded)
+}
+
+/// Scores the items `reached` as `(rule index, seed, item)` from the seeds in `hits`, keeping the
+/// best reason per item and at most `cfg.per_seed_max` items per seed.
+fn rank_expansions(
+    hits: &[(Uuid, f32)],
+    reached: &[(usize, Uuid, Uuid)],
+    cfg: &GraphExpansion,
+) -> Vec<(Uuid, f32, InclusionReason)> {
+    let mut seed_scores: HashMap<Uuid, f32> = HashMap::with_capacity(hits.len());
+    for (id, score) in hits {
+        seed_scores.entry(*id).or_insert(*score);
+    }
+
+    // Best candidate per (seed, item), then the best `per_seed_max` per seed.
+    let mut per_seed: HashMap<Uuid, HashMap<Uuid, (f32, usize)>> = HashMap::new();
+    for &(rule_idx, from, to) in reached {
+        if seed_scores.contains_key(&to) {
+            continue;
+        }
+        let Some(seed_score) = seed_scores.get(&from) else {
+            continue;
+        };
+        let hops = cfg.rules[rule_idx].hops();
+        let score = seed_score * cfg.decay.powi(hops as i32);
+        let entry = per_seed
+            .entry(from)
+            .or_default()
+            .entry(to)
+            .or_insert((score, rule_idx));
+        if score > entry.0 || (score == entry.0 && rule_idx < entry.1) {
+            *entry = (score, rule_idx);
+        }
+    }
+
+    let mut best: HashMap<Uuid, (f32, usize, Uuid)> = HashMap::new();
+    for (from, items) in per_seed {
+        let mut items: Vec<(Uuid, (f32, usize))> = items.into_iter().collect();
+        items.sort_by(|a, b| b.1.0.total_cmp(&a.1.0).then_with(|| a.0.cmp(&b.0)));
+        for (to, (score, rule_idx)) in items.into_iter().take(cfg.per_seed_max) {
+            let candidate = (score, rule_idx, from);
+            best.entry(to)
+                .and_modify(|current| {
+                    if score > current.0
+                        || (score == current.0 && (rule_idx, from) < (current.1, current.2))
+                    {
+                        *current = candidate;
+                    }
+                })
+                .or_insert(candidate);
+        }
+    }
+
+    let mut out: Vec<(Uuid, f32, InclusionReason)> = best
+        .into_iter()
+        .map(|(to, (score, rule_idx, from))| {
+            let rule = &cfg.rules[rule_idx];
+            (
+                to,
+                score,
+                InclusionReason::GraphExpansion {
+                    from,
+                    rule: rule.name.clone(),
+                    hops: rule.hops(),
+                },
+            )
+        })
+        .collect();
+    out.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: u128) -> Uuid {
+        Uuid::from_u128(n)
+    }
+
+    #[test]
+    fn rank_expansions_decays_scores_and_keeps_best_reason() {
+        let cfg = GraphExpansion {
+            per_seed_max: 2,
+            ..GraphExpansion::default()
+        };
+        let self_type = cfg
+            .rules
+            .iter()
+            .position(|r| r.name == "self_type")
+            .unwrap();
+        let callee = cfg.rules.iter().position(|r| r.name == "callee").unwrap();
+        let hits = vec![(id(1), 1.0), (id(2), 0.5)];
+        let reached = vec![
+            // The other hit is not added again.
+            (callee, id(1), id(2)),
+            // Reached from both seeds: the one-hop path from the best seed wins.
+            (self_type, id(1), id(10)),
+            (callee, id(1), id(10)),
+            (callee, id(2), id(10)),
+            (callee, id(1), id(11)),
+            // Over the per-seed cap of the first seed.
+            (self_type, id(1), id(12)),
+            (self_type, id(2), id(13)),
+        ];
+
+        let expanded = rank_expansions(&hits, &reached, &cfg);
+        let summary: Vec<(Uuid, f32)> = expanded.iter().map(|(id, s, _)| (*id, *s)).collect();
+        assert_eq!(summary, vec![(id(10), 0.5), (id(11), 0.5), (id(13), 0.125)]);
+        assert_eq!(
+            expanded[0].2,
+            InclusionReason::GraphExpansion {
+                from: id(1),
+                rule: "callee".to_string(),
+                hops: 1,
+            }
+        );
+        assert_eq!(
+            expanded[2].2,
+            InclusionReason::GraphExpansion {
+                from: id(2),
+                rule: "self_type".to_string(),
+                hops: 2,
+            }
+        );
+    }
+}
@@ -5,6 +5,7 @@
 //! deterministic ranking. The core entry points are:
 //! - [`normalize_scores`]: bring scores from different modalities onto a comparable scale.
 //! - [`rrf_fuse`]: weighted reciprocal rank fusion with stable UUID tie-breaking.
+//! - [`rrf_fuse_many`]: the same over any number of ranked lists (e.g. one per sub-query).
 //! - [`mmr_select`]: diversity-aware selection using cosine similarity on normalized vectors.
 //!
 //! The algorithms are intentionally small, well-documented, and pure (no I/O) to aid testing and reuse.
@@ -164,17 +165,20 @@ impl Default for RrfConfig {
 /// - Missing ranks contribute 0 to the fused score.
 /// - Stable tie-breaking by UUID ascending if fused scores are equal.
 pub fn rrf_fuse(bm25: &[(Uuid, f32)], dense: &[(Uuid, f32)], cfg: &RrfConfig) -> Vec<(Uuid, f32)> {
+    rrf_fuse_many(&[(bm25, cfg.weight_bm25), (dense, cfg.weight_dense)], cfg.k)
+}
+
+/// Weighted RRF over any number of `(ranked list, weight)` pairs, with smoothing parameter `k`.
+/// Same ranking and tie-breaking rules as [`rrf_fuse`].
+pub fn rrf_fuse_many(lists: &[(&[(Uuid, f32)], f32)], k: f32) -> Vec<(Uuid, f32)> {
     let mut fused: HashMap<Uuid, f32> = HashMap::new();
 
-    for (i, (id, _)) in bm25.iter().enumerate() {
-        let rank = (i as f32) + 1.0;
-        let add = cfg.weight_bm25 / (cfg.k + rank);
-        *fused.entry(*id).or_insert(0.0) += add;
-    }
-    for (i, (id, _)) in dense.iter().enumerate() {
-        let rank = (i as f32) + 1.0;
-        let add = cfg.weight_dense / (cfg.k + rank);
-        *fused.entry(*id).or_insert(0.0) += add;
+    for (list, weight) in lists {
+        for (i, (id, _)) in list.iter().enumerate() {
+            let rank = (i as f32) + 1.0;
+            let add = weight / (k + rank);
+            *fused.entry(*id).or_insert(0.0) += add;
+        }
     }
 
     let mut out: Vec<(Uuid, f32)> = fused.into_iter().collect();
@@ -405,4 +409,22 @@ mod tests {
         assert!(approx_eq(out[1].1, 0.5, 1e-6));
         assert!(approx_eq(out[2].1, 0.993_307, 1e-3));
     }
+
+    #[test]
+    fn test_rrf_fuse_many_matches_pairwise_rrf() {
+        let (a, b, c) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+        let first = vec![(a, 9.0), (b, 5.0)];
+        let second = vec![(b, 0.9), (c, 0.8)];
+        let third = vec![(c, 1.0)];
+        let cfg = RrfConfig::default();
+        assert_eq!(
+            rrf_fuse_many(&[(&first, 1.0), (&second, 1.0)], cfg.k),
+            rrf_fuse(&first, &second, &cfg)
+        );
+
+        let out = rrf_fuse_many(&[(&first, 1.0), (&second, 1.0), (&third, 2.0)], cfg.k);
+        let ids: Vec<Uuid> = out.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![c, b, a]);
+        assert!(approx_eq(out[0].1, 1.0 / 62.0 + 2.0 / 61.0, 1e-6));
+    }
 }
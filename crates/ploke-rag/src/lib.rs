@@ -3,7 +3,10 @@
 //!
 //! This crate provides the "R" and "A" of RAG for the PLOKE workspace:
 //! - Retrieval: sparse (BM25) via an in-memory actor and dense (HNSW) via `ploke-db`.
+//! - Query rewriting: expanding a conversational message into sub-queries (identifiers, keywords,
+//!   an optional HyDE snippet) whose results are fused.
 //! - Fusion: score normalization, weighted Reciprocal Rank Fusion (RRF), optional Maximal Marginal Relevance (MMR).
+//! - Graph expansion: following syntax edges from the hits to the items around them.
 //! - Context assembly: selecting, trimming, ordering and packaging snippets under a token budget.
 //!
 //! The design emphasizes deterministic behavior, strong observability, and strict error handling,
@@ -75,7 +78,8 @@
 //!   from the DB in `ploke-db`. Keep API usage stable to benefit from future serialization improvements.
 //!
 //! Algorithms
-//! - RRF: [`rrf_fuse`] implements weighted reciprocal rank fusion with stable UUID tie-breaking.
+//! - RRF: [`rrf_fuse`] implements weighted reciprocal rank fusion with stable UUID tie-breaking;
+//!   [`rrf_fuse_many`] generalizes it to any number of lists.
 //! - Score normalization: [`normalize_scores`] provides min-max, z-score, and logistic transforms.
 //! - MMR: [`mmr_select`] performs diversity-aware selection using cosine similarity on normalized vectors.
 //!
@@ -123,7 +127,10 @@ fn ensure_tracer_initialized() {
 use ploke_core::{EmbeddingData, RetrievalScope};
 use ploke_db::{
     Database, DbError, NodeType, SimilarArgs, TypedEmbedData,
-    bm25_index::bm25_service::{self, Bm25Cmd},
+    bm25_index::{
+        Bm25Query, FieldBoosts,
+        bm25_service::{self, Bm25Cmd},
+    },
     search_similar_args,
 };
 use ploke_embed::indexer::{EmbeddingProcessor, IndexerTask};
@@ -138,14 +145,27 @@ pub mod fusion;
 pub use error::RagError;
 pub use fusion::{
     MmrConfig, RrfConfig, ScoreNorm, Similarity, mmr_select, normalize_scores, rrf_fuse,
+    rrf_fuse_many,
 };
 pub mod context;
 pub use context::{
     ApproxCharTokenizer, AssemblyPolicy, Ordering, TokenBudget, TokenCounter, assemble_context,
 };
+pub mod expansion;
+pub use expansion::{ExpansionRule, GraphExpansion, expand_hits};
 pub mod core;
 pub use core::{NoopReranker, RagConfig, RagService, Reranker, RetrievalStrategy};
+pub mod rerank;
 pub use ploke_db::bm25_index::bm25_service::Bm25Status;
+pub use rerank::{CrossEncoderReranker, LlmReranker};
+pub mod tokenizer;
+pub use tokenizer::{HfTokenCounter, ModelTokenCounter, TokenizerFamily};
+pub mod rewrite;
+pub use rewrite::{
+    HydeGenerator, LlmHydeGenerator, QueryRewrite, SubQuery, SubQueryKind, extract_identifiers,
+    keyword_query,
+};
 
 const BM25_TIMEOUT_MS: u64 = 250;
+const BM25_RESTORE_TIMEOUT_MS: u64 = 120_000;
 const BM25_RETRY_BACKOFF_MS: [u64; 2] = [50, 100];
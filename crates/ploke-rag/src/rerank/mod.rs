@@ -0,0 +1,239 @@
+#![allow(missing_docs)]
+//! Rerankers: rescoring the retrieved candidates against the query using their snippet texts.
+//!
+//! Both implement [`Reranker`] and are plugged in through [`RagConfig::reranker`]:
+//! - [`CrossEncoderReranker`] scores each (query, snippet) pair with a local cross-encoder model
+//!   (see [`LocalCrossEncoder`]), on a blocking thread. The model is loaded, and downloaded if
+//!   needed, on the first rerank rather than when the reranker is built.
+//! - [`LlmReranker`] asks a chat model routed through `ploke-llm` to grade the candidates in a
+//!   single request.
+//!
+//! Rerankers return the candidates ordered by score (desc), tie-broken by UUID asc.
+//!
+//! [`RagConfig::reranker`]: crate::RagConfig::reranker
+use std::fmt;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use ploke_embed::local::cross_encoder::{CrossEncoderConfig, LocalCrossEncoder};
+use ploke_llm::router_only::Router;
+use ploke_llm::{ChatHttpConfig, ChatStepOutcome, ModelId, RequestMessage, chat_step};
+use tokio::sync::OnceCell;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::core::Reranker;
+use crate::error::RagError;
+
+fn sort_by_score(mut scored: Vec<(Uuid, f32)>) -> Vec<(Uuid, f32)> {
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    scored
+}
+
+/// Reranker backed by a local cross-encoder model, run on the CPU.
+#[derive(Debug)]
+pub struct CrossEncoderReranker {
+    config: CrossEncoderConfig,
+    encoder: OnceCell<Arc<LocalCrossEncoder>>,
+}
+
+impl CrossEncoderReranker {
+    /// A reranker loading the model described by `config` on its first use.
+    pub fn new(config: CrossEncoderConfig) -> Self {
+        Self {
+            config,
+            encoder: OnceCell::new(),
+        }
+    }
+
+    /// A reranker using an already loaded model.
+    pub fn from_encoder(encoder: Arc<LocalCrossEncoder>) -> Self {
+        Self {
+            config: CrossEncoderConfig::default(),
+            encoder: OnceCell::new_with(Some(encoder)),
+        }
+    }
+
+    /// The model, loaded on a blocking thread on first use. A failed load is retried on the next
+    /// call.
+    async fn encoder(&self) -> Result<Arc<LocalCrossEncoder>, RagError> {
+        self.encoder
+            .get_or_try_init(|| async {
+                let config = self.config.clone();
+                let encoder = tokio::task::spawn_blocking(move || LocalCrossEncoder::new(config))
+                    .await
+                    .map_err(|e| RagError::Rerank(format!("cross-encoder load task failed: {e}")))?
+                    .map_err(|e| RagError::Rerank(format!("failed to load cross-encoder: {e}")))?;
+                Ok(Arc::new(encoder))
+            })
+            .await
+            .cloned()
+    }
+}
+
+impl Reranker for CrossEncoderReranker {
+    fn rerank<'a>(
+        &'a self,
+        query: &'a str,
+        candidates: Vec<(Uuid, String)>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<(Uuid, f32)>, RagError>> + Send + 'a>> {
+        Box::pin(async move {
+            if candidates.is_empty() {
+                return Ok(Vec::new());
+            }
+            let encoder = self.encoder().await?;
+            let query = query.to_string();
+            let (ids, texts): (Vec<Uuid>, Vec<String>) = candidates.into_iter().unzip();
+            let scores = tokio::task::spawn_blocking(move || {
+                let passages: Vec<&str> = texts.iter().map(String::as_str).collect();
+                encoder.score(&query, &passages)
+            })
+            .await
+            .map_err(|e| RagError::Rerank(format!("cross-encoder task failed: {e}")))?
+            .map_err(|e| RagError::Rerank(format!("cross-encoder scoring failed: {e}")))?;
+            Ok(sort_by_score(ids.into_iter().zip(scores).collect()))
+        })
+    }
+}
+
+const LLM_RERANK_PROMPT: &str = "You grade how relevant code snippets are to a search query. \
+Reply with a JSON object {\"scores\": [...]} holding one number from 0 (irrelevant) to 10 \
+(exactly what the query asks for) per snippet, in the order of the snippets.";
+
+/// Reranker grading the candidates with a chat model through the router `R`.
+pub struct LlmReranker<R: Router> {
+    client: reqwest::Client,
+    model: ModelId,
+    http: ChatHttpConfig,
+    /// Snippets are cut to this many characters in the prompt.
+    pub max_snippet_chars: usize,
+    router: PhantomData<R>,
+}
+
+impl<R: Router> fmt::Debug for LlmReranker<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LlmReranker")
+            .field("model", &self.model)
+            .field("max_snippet_chars", &self.max_snippet_chars)
+            .finish()
+    }
+}
+
+impl<R: Router> LlmReranker<R> {
+    pub fn new(model: ModelId) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            model,
+            http: ChatHttpConfig::default(),
+            max_snippet_chars: 1500,
+            router: PhantomData,
+        }
+    }
+
+    fn prompt(&self, query: &str, candidates: &[(Uuid, String)]) -> String {
+        let mut prompt = format!("Query: {query}\n");
+        for (i, (_, text)) in candidates.iter().enumerate() {
+            let snippet: String = text.chars().take(self.max_snippet_chars).collect();
+            prompt.push_str(&format!("\nSnippet {i}:\n```rust\n{snippet}\n```\n"));
+        }
+        prompt
+    }
+}
+
+impl<R> Reranker for LlmReranker<R>
+where
+    R: Router + Send + Sync + 'static,
+    R::CompletionFields: Send + Sync,
+{
+    fn rerank<'a>(
+        &'a self,
+        query: &'a str,
+        candidates: Vec<(Uuid, String)>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<(Uuid, f32)>, RagError>> + Send + 'a>> {
+        Box::pin(async move {
+            if candidates.is_empty() {
+                return Ok(Vec::new());
+            }
+            let req = R::default_chat_completion()
+                .with_model(self.model.clone())
+                .with_messages(vec![
+                    RequestMessage::new_system(LLM_RERANK_PROMPT.to_string()),
+                    RequestMessage::new_user(self.prompt(query, &candidates)),
+                ])
+                .with_json_response()
+                .with_temperature(0.0);
+            let data = chat_step(&self.client, &req, &self.http)
+                .await
+                .map_err(|e| RagError::Rerank(format!("llm rerank request failed: {e}")))?;
+            let content = match data.outcome {
+                ChatStepOutcome::Content {
+                    content: Some(content),
+                    ..
+                } => content,
+                other => {
+                    return Err(RagError::Rerank(format!(
+                        "llm rerank returned no content: {other:?}"
+                    )));
+                }
+            };
+            let scores = parse_llm_scores(&content, candidates.len())?;
+            debug!(?scores, "llm rerank scores");
+            Ok(sort_by_score(
+                candidates
+                    .into_iter()
+                    .map(|(id, _)| id)
+                    .zip(scores)
+                    .collect(),
+            ))
+        })
+    }
+}
+
+/// Parses the `{"scores": [...]}` reply of the model into `n` scores in `[0, 1]`.
+fn parse_llm_scores(content: &str, n: usize) -> Result<Vec<f32>, RagError> {
+    #[derive(serde::Deserialize)]
+    struct Reply {
+        scores: Vec<f32>,
+    }
+    // Some models wrap the object in a code fence despite the JSON response format.
+    let json = content
+        .trim()
+        .trim_start_matches("```json")
+        .trim_matches('`')
+        .trim();
+    let reply: Reply = serde_json::from_str(json)
+        .map_err(|e| RagError::Rerank(format!("invalid llm rerank reply: {e}: {content}")))?;
+    if reply.scores.len() != n {
+        return Err(RagError::Rerank(format!(
+            "llm rerank graded {} snippets, expected {n}",
+            reply.scores.len()
+        )));
+    }
+    Ok(reply
+        .scores
+        .into_iter()
+        .map(|score| (score / 10.0).clamp(0.0, 1.0))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_llm_scores_normalizes_and_checks_count() {
+        let scores = parse_llm_scores("```json\n{\"scores\": [10, 2.5, 12]}\n```", 3).unwrap();
+        assert_eq!(scores, vec![1.0, 0.25, 1.0]);
+        assert!(parse_llm_scores("{\"scores\": [1]}", 2).is_err());
+        assert!(parse_llm_scores("the first one", 1).is_err());
+    }
+
+    #[test]
+    fn sort_by_score_breaks_ties_by_id() {
+        let (a, b, c) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+        let sorted = sort_by_score(vec![(c, 0.5), (b, 0.9), (a, 0.5)]);
+        assert_eq!(sorted, vec![(b, 0.9), (a, 0.5), (c, 0.5)]);
+    }
+}
@@ -0,0 +1,399 @@
+#![allow(missing_docs)]
+//! Query rewriting: expanding a conversational message into sub-queries for retrieval.
+//!
+//! A chat message is often a poor search query. In "why does the thing from before break when
+//! loading?" the content words are vague and the item it is about was named in an earlier turn.
+//! [`QueryRewrite::sub_queries`] derives several [`SubQuery`]s from the message and the recent
+//! turns of the conversation:
+//! - the message itself,
+//! - the code identifiers it names (see [`extract_identifiers`]), or those named in the recent
+//!   turns when the message names none,
+//! - its keywords, without stopwords and conversational filler (see [`keyword_query`]),
+//! - optionally a HyDE-style synthetic snippet: the code a [`HydeGenerator`] expects the answer to
+//!   be about, which lands close to the real code in embedding space.
+//!
+//! [`RagService::multi_query_search`] retrieves each sub-query and fuses the ranked lists with
+//! weighted RRF ([`rrf_fuse_many`]).
+//!
+//! [`RagService::multi_query_search`]: crate::RagService::multi_query_search
+//! [`rrf_fuse_many`]: crate::rrf_fuse_many
+use std::fmt;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use ploke_llm::router_only::Router;
+use ploke_llm::{ChatHttpConfig, ChatStepOutcome, ModelId, RequestMessage, chat_step};
+use tracing::{debug, warn};
+
+use crate::error::RagError;
+
+/// Where a [`SubQuery`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubQueryKind {
+    Original,
+    Identifiers,
+    Keywords,
+    Hyde,
+}
+
+impl SubQueryKind {
+    /// RRF weight of the results of sub-queries of this kind. Named identifiers are the most
+    /// precise signal; keywords stripped of their context the least.
+    pub fn weight(self) -> f32 {
+        match self {
+            Self::Original => 1.0,
+            Self::Identifiers => 1.5,
+            Self::Keywords => 0.75,
+            Self::Hyde => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubQuery {
+    pub kind: SubQueryKind,
+    pub text: String,
+}
+
+/// Produces a synthetic code snippet answering a message, to be used as a search query.
+#[allow(clippy::type_complexity)]
+pub trait HydeGenerator: Send + Sync + fmt::Debug {
+    fn generate<'a>(
+        &'a self,
+        message: &'a str,
+        history: &'a [String],
+    ) -> Pin<Box<dyn Future<Output = Result<String, RagError>> + Send + 'a>>;
+}
+
+/// Configuration of the query rewriting in
+/// [`RagService::get_context_for_turn`](crate::RagService::get_context_for_turn).
+#[derive(Debug, Clone)]
+pub struct QueryRewrite {
+    /// Number of most recent conversation turns looked at.
+    pub history_turns: usize,
+    /// Maximum number of identifiers in the identifier sub-query.
+    pub max_identifiers: usize,
+    /// HyDE generator; no synthetic snippet is searched when `None`.
+    pub hyde: Option<Arc<dyn HydeGenerator>>,
+}
+
+impl Default for QueryRewrite {
+    fn default() -> Self {
+        Self {
+            history_turns: 4,
+            max_identifiers: 8,
+            hyde: None,
+        }
+    }
+}
+
+impl QueryRewrite {
+    /// The sub-queries for `message`, given the preceding turns of the conversation in order
+    /// (oldest first). A failing HyDE generator is logged and skipped.
+    pub async fn sub_queries(&self, message: &str, history: &[String]) -> Vec<SubQuery> {
+        let mut sub_queries = self.lexical_sub_queries(message, history);
+        if let Some(hyde) = &self.hyde {
+            match hyde.generate(message, self.recent(history)).await {
+                Ok(snippet) => push_distinct(&mut sub_queries, SubQueryKind::Hyde, snippet),
+                Err(e) => warn!("HyDE generation failed, searching without it: {e}"),
+            }
+        }
+        debug!(?sub_queries, "rewrote query");
+        sub_queries
+    }
+
+    /// The sub-queries not needing a model: the message, its identifiers and its keywords.
+    pub fn lexical_sub_queries(&self, message: &str, history: &[String]) -> Vec<SubQuery> {
+        let mut sub_queries = Vec::new();
+        push_distinct(&mut sub_queries, SubQueryKind::Original, message.trim());
+
+        let mut identifiers = extract_identifiers(message);
+        if identifiers.is_empty() {
+            // Most likely a follow-up: look for what the recent turns were about, newest first.
+            for turn in self.recent(history).iter().rev() {
+                for ident in extract_identifiers(turn) {
+                    if !identifiers.contains(&ident) {
+                        identifiers.push(ident);
+                    }
+                }
+            }
+        }
+        identifiers.truncate(self.max_identifiers);
+        push_distinct(
+            &mut sub_queries,
+            SubQueryKind::Identifiers,
+            identifiers.join(" "),
+        );
+        push_distinct(
+            &mut sub_queries,
+            SubQueryKind::Keywords,
+            keyword_query(message),
+        );
+        sub_queries
+    }
+
+    fn recent<'h>(&self, history: &'h [String]) -> &'h [String] {
+        &history[history.len().saturating_sub(self.history_turns)..]
+    }
+}
+
+/// Adds a sub-query unless it is empty or repeats an earlier one.
+fn push_distinct(sub_queries: &mut Vec<SubQuery>, kind: SubQueryKind, text: impl Into<String>) {
+    let text = text.into();
+    if text.trim().is_empty() || sub_queries.iter().any(|q| q.text == text) {
+        return;
+    }
+    sub_queries.push(SubQuery { kind, text });
+}
+
+/// Code identifiers named in `text`, in order of appearance and without duplicates.
+///
+/// A word counts as an identifier when it is written in backticks, is a `::` path, is followed by
+/// `(`, or is shaped like one: `snake_case` or `CamelCase` with a lowercase letter.
+pub fn extract_identifiers(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out: Vec<String> = Vec::new();
+    let mut in_backticks = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '`' {
+            in_backticks = !in_backticks;
+            i += 1;
+            continue;
+        }
+        if !is_word_char(c) && c != ':' {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && (is_word_char(chars[i]) || chars[i] == ':') {
+            i += 1;
+        }
+        let word: String = chars[start..i].iter().collect();
+        let word = word.trim_matches(':');
+        let called = chars.get(i) == Some(&'(');
+        if is_identifier(word, in_backticks || called) && !out.iter().any(|w| w == word) {
+            out.push(word.to_string());
+        }
+    }
+    out
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_identifier(word: &str, in_code: bool) -> bool {
+    let segments: Vec<&str> = word.split("::").collect();
+    let well_formed = segments.iter().all(|s| {
+        s.chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+            && s.chars().all(is_word_char)
+    });
+    if !well_formed {
+        return false;
+    }
+    if in_code || segments.len() > 1 {
+        return true;
+    }
+    let snake = word.contains('_') && word.chars().any(char::is_alphanumeric);
+    let camel =
+        word.chars().skip(1).any(char::is_uppercase) && word.chars().any(char::is_lowercase);
+    snake || camel
+}
+
+/// Words that carry no meaning for code search: common English stopwords and conversational
+/// filler ("the thing from before").
+const STOPWORDS: &str = "\
+about above after again all also and any are aren because been before being below both but can \
+code could did didn does doesn doing don done each earlier for from get gets had has have \
+having here how into isn its just like look make mentioned more most need not now one only \
+other our out please should some still stuff such thanks than that the their them then there \
+these they thing things this those through too use used very want was wasn way were what when \
+where which while who why will with won would you your";
+
+/// Keyword query of `text`: its lowercased words of three or more characters, without
+/// stopwords, numbers and duplicates.
+pub fn keyword_query(text: &str) -> String {
+    let mut keywords: Vec<String> = Vec::new();
+    for word in text.split(|c: char| !is_word_char(c)) {
+        let word = word.to_lowercase();
+        if word.chars().count() < 3
+            || word.chars().all(|c| c.is_ascii_digit())
+            || STOPWORDS
+                .split_whitespace()
+                .any(|stopword| stopword == word)
+            || keywords.contains(&word)
+        {
+            continue;
+        }
+        keywords.push(word);
+    }
+    keywords.join(" ")
+}
+
+const HYDE_PROMPT: &str = "You help search a Rust codebase. Given a question from a \
+conversation about the code, write the Rust code the question is most likely about: a short, \
+plausible snippet with the item signatures, types and a few lines of body, using the names \
+mentioned in the conversation. Reply with the code only.";
+
+/// HyDE generator asking a chat model routed through the router `R`.
+pub struct LlmHydeGenerator<R: Router> {
+    client: reqwest::Client,
+    model: ModelId,
+    http: ChatHttpConfig,
+    /// Turns of the conversation are cut to this many characters in the prompt.
+    pub max_turn_chars: usize,
+    /// Upper bound on the length of the generated snippet.
+    pub max_tokens: u32,
+    router: PhantomData<R>,
+}
+
+impl<R: Router> fmt::Debug for LlmHydeGenerator<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LlmHydeGenerator")
+            .field("model", &self.model)
+            .field("max_turn_chars", &self.max_turn_chars)
+            .field("max_tokens", &self.max_tokens)
+            .finish()
+    }
+}
+
+impl<R: Router> LlmHydeGenerator<R> {
+    pub fn new(model: ModelId) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            model,
+            http: ChatHttpConfig::default(),
+            max_turn_chars: 1000,
+            max_tokens: 300,
+            router: PhantomData,
+        }
+    }
+
+    fn prompt(&self, message: &str, history: &[String]) -> String {
+        let mut prompt = String::new();
+        if !history.is_empty() {
+            prompt.push_str("Conversation so far:\n");
+            for turn in history {
+                let turn: String = turn.chars().take(self.max_turn_chars).collect();
+                prompt.push_str(&format!("---\n{turn}\n"));
+            }
+            prompt.push_str("---\n\n");
+        }
+        prompt.push_str(&format!("Question: {message}"));
+        prompt
+    }
+}
+
+impl<R> HydeGenerator for LlmHydeGenerator<R>
+where
+    R: Router + Send + Sync + 'static,
+    R::CompletionFields: Send + Sync,
+{
+    fn generate<'a>(
+        &'a self,
+        message: &'a str,
+        history: &'a [String],
+    ) -> Pin<Box<dyn Future<Output = Result<String, RagError>> + Send + 'a>> {
+        Box::pin(async move {
+            let req = R::default_chat_completion()
+                .with_model(self.model.clone())
+                .with_messages(vec![
+                    RequestMessage::new_system(HYDE_PROMPT.to_string()),
+                    RequestMessage::new_user(self.prompt(message, history)),
+                ])
+                .with_max_tokens(self.max_tokens)
+                .with_temperature(0.0);
+            let data = chat_step(&self.client, &req, &self.http)
+                .await
+                .map_err(|e| RagError::Search(format!("HyDE request failed: {e}")))?;
+            match data.outcome {
+                ChatStepOutcome::Content {
+                    content: Some(content),
+                    ..
+                } => Ok(strip_code_fence(&content).to_string()),
+                other => Err(RagError::Search(format!(
+                    "HyDE returned no content: {other:?}"
+                ))),
+            }
+        })
+    }
+}
+
+/// The body of a reply wrapped in a Markdown code fence, or the trimmed reply otherwise.
+fn strip_code_fence(reply: &str) -> &str {
+    let reply = reply.trim();
+    let Some(fenced) = reply.strip_prefix("```") else {
+        return reply;
+    };
+    // Drop the language tag line, then the closing fence.
+    let body = fenced.split_once('\n').map_or("", |(_, body)| body);
+    body.trim_end().trim_end_matches("```").trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifiers_are_picked_by_shape_and_markup() {
+        let ids = extract_identifiers(
+            "Does `load` call IndexerTask::run or parse_workspace() when the Database \
+             (a HashMap? JSON?) is empty: see `load` again.",
+        );
+        assert_eq!(
+            ids,
+            vec!["load", "IndexerTask::run", "parse_workspace", "HashMap"]
+        );
+    }
+
+    #[test]
+    fn follow_ups_take_identifiers_from_recent_turns() {
+        let rewrite = QueryRewrite {
+            history_turns: 2,
+            ..QueryRewrite::default()
+        };
+        let history = vec![
+            "Where is `old_turn` used?".to_string(),
+            "The crash is in IndexerTask::run.".to_string(),
+            "It calls load_crate_state on startup.".to_string(),
+        ];
+        let sub_queries = rewrite.lexical_sub_queries(
+            "why does the thing from before break when loading?",
+            &history,
+        );
+        assert_eq!(
+            sub_queries,
+            vec![
+                SubQuery {
+                    kind: SubQueryKind::Original,
+                    text: "why does the thing from before break when loading?".to_string(),
+                },
+                SubQuery {
+                    kind: SubQueryKind::Identifiers,
+                    text: "load_crate_state IndexerTask::run".to_string(),
+                },
+                SubQuery {
+                    kind: SubQueryKind::Keywords,
+                    text: "break loading".to_string(),
+                },
+            ]
+        );
+
+        // Identifiers named in the message itself take precedence over the history.
+        let sub_queries = rewrite.lexical_sub_queries("and `parse_crate`?", &history);
+        assert_eq!(sub_queries[1].text, "parse_crate");
+        assert_eq!(sub_queries.len(), 2, "keywords repeat the identifier query");
+    }
+
+    #[test]
+    fn strip_code_fence_keeps_the_code() {
+        assert_eq!(strip_code_fence("```rust\nfn a() {}\n```\n"), "fn a() {}");
+        assert_eq!(strip_code_fence("  fn a() {}  "), "fn a() {}");
+    }
+}
@@ -0,0 +1,349 @@
+#![allow(missing_docs)]
+//! Tokenizer-backed [`TokenCounter`]s, chosen from the model the context is assembled for.
+//!
+//! [`ApproxCharTokenizer`] (chars / 4) underestimates Rust code, which is dense in punctuation and
+//! long identifiers. [`HfTokenCounter`] counts with the tokenizer of a model family, loaded from
+//! a `tokenizer.json` in the local Hugging Face cache (downloaded into it in the background when
+//! allowed), and [`ModelTokenCounter`] picks the family from a [`ModelId`], falling back to the
+//! approximation for unknown families or while the tokenizer is not available.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
+
+use hf_hub::{Cache, Repo, api::sync::ApiBuilder};
+use ploke_llm::ModelId;
+use tokenizers::Tokenizer;
+use tracing::{debug, warn};
+
+use crate::context::{ApproxCharTokenizer, TokenCounter};
+use crate::error::RagError;
+
+/// Model families sharing a tokenizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenizerFamily {
+    OpenAi,
+    Anthropic,
+    Llama,
+    Qwen,
+    DeepSeek,
+    /// No known tokenizer; counted with [`ApproxCharTokenizer`].
+    Unknown,
+}
+
+impl TokenizerFamily {
+    /// The family of `model`, from its author and, for hosted open-weight models, its name.
+    pub fn from_model(model: &ModelId) -> Self {
+        let author = model.key.author.as_str().to_ascii_lowercase();
+        let slug = model.key.slug.as_str().to_ascii_lowercase();
+        match author.as_str() {
+            "openai" => Self::OpenAi,
+            "anthropic" => Self::Anthropic,
+            "meta-llama" => Self::Llama,
+            "qwen" => Self::Qwen,
+            "deepseek" => Self::DeepSeek,
+            _ if slug.contains("llama") => Self::Llama,
+            _ if slug.contains("qwen") => Self::Qwen,
+            _ if slug.contains("deepseek") => Self::DeepSeek,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Hugging Face repository holding the `tokenizer.json` of the family.
+    pub fn hf_repo(self) -> Option<&'static str> {
+        match self {
+            Self::OpenAi => Some("Xenova/gpt-4o"),
+            Self::Anthropic => Some("Xenova/claude-tokenizer"),
+            Self::Llama => Some("NousResearch/Meta-Llama-3-8B"),
+            Self::Qwen => Some("Qwen/Qwen2.5-7B-Instruct"),
+            Self::DeepSeek => Some("deepseek-ai/DeepSeek-V3"),
+            Self::Unknown => None,
+        }
+    }
+}
+
+/// Counts tokens with a Hugging Face tokenizer.
+pub struct HfTokenCounter {
+    repo: String,
+    tokenizer: Tokenizer,
+}
+
+impl fmt::Debug for HfTokenCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HfTokenCounter")
+            .field("repo", &self.repo)
+            .finish()
+    }
+}
+
+impl HfTokenCounter {
+    pub fn new(repo: impl Into<String>, tokenizer: Tokenizer) -> Self {
+        Self {
+            repo: repo.into(),
+            tokenizer,
+        }
+    }
+
+    /// Loads the `tokenizer.json` of `repo` from the local Hugging Face cache, downloading it
+    /// into the cache first if it is missing and `allow_download` is set.
+    pub fn load(repo: &str, allow_download: bool) -> Result<Self, RagError> {
+        Self::load_from(&Cache::default(), repo, allow_download)
+    }
+
+    /// Like [`HfTokenCounter::load`], with `cache` in place of the default Hugging Face cache.
+    pub fn load_from(cache: &Cache, repo: &str, allow_download: bool) -> Result<Self, RagError> {
+        let cached = cache.model(repo.to_string()).get("tokenizer.json");
+        let path = match cached {
+            Some(path) => path,
+            None if allow_download => ApiBuilder::from_cache(cache.clone())
+                .build()
+                .and_then(|api| {
+                    api.repo(Repo::model(repo.to_string()))
+                        .get("tokenizer.json")
+                })
+                .map_err(|e| {
+                    RagError::Tokenizer(format!("downloading tokenizer of {repo}: {e}"))
+                })?,
+            None => {
+                return Err(RagError::Tokenizer(format!(
+                    "tokenizer of {repo} is not in the local cache"
+                )));
+            }
+        };
+        let tokenizer = Tokenizer::from_file(&path).map_err(|e| {
+            RagError::Tokenizer(format!("loading tokenizer {}: {e}", path.display()))
+        })?;
+        Ok(Self::new(repo, tokenizer))
+    }
+}
+
+impl TokenCounter for HfTokenCounter {
+    fn count(&self, text: &str) -> usize {
+        match self.tokenizer.encode(text, false) {
+            Ok(encoding) => encoding.len(),
+            Err(e) => {
+                warn!("tokenizer {} failed, approximating: {e}", self.repo);
+                ApproxCharTokenizer.count(text)
+            }
+        }
+    }
+}
+
+/// Token counter following the selected model, see [`TokenCounter::select_model`].
+///
+/// Tokenizers are loaded once per family and kept for later switches back. Selecting a model only
+/// reads the local cache: a tokenizer missing from it is counted approximately, and downloaded on
+/// a background thread when allowed, after which the counter switches to it. Failed loads are not
+/// remembered, so the next selection of the family tries again.
+#[derive(Debug)]
+pub struct ModelTokenCounter {
+    allow_download: bool,
+    cache: Cache,
+    current: Arc<RwLock<(TokenizerFamily, Arc<dyn TokenCounter>)>>,
+    loaded: Arc<RwLock<HashMap<TokenizerFamily, Arc<dyn TokenCounter>>>>,
+    downloading: Arc<Mutex<HashSet<TokenizerFamily>>>,
+}
+
+impl ModelTokenCounter {
+    /// A counter for `model`. With `allow_download`, tokenizers missing from the local cache are
+    /// fetched from the Hugging Face hub in the background.
+    pub fn new(model: &ModelId, allow_download: bool) -> Self {
+        Self::with_cache(model, allow_download, Cache::default())
+    }
+
+    fn with_cache(model: &ModelId, allow_download: bool, cache: Cache) -> Self {
+        let counter = Self {
+            allow_download,
+            cache,
+            current: Arc::new(RwLock::new((
+                TokenizerFamily::Unknown,
+                Arc::new(ApproxCharTokenizer),
+            ))),
+            loaded: Arc::new(RwLock::new(HashMap::new())),
+            downloading: Arc::new(Mutex::new(HashSet::new())),
+        };
+        counter.select_model(model);
+        counter
+    }
+
+    fn counter_for(&self, family: TokenizerFamily) -> Arc<dyn TokenCounter> {
+        if let Some(counter) = self
+            .loaded
+            .read()
+            .ok()
+            .and_then(|l| l.get(&family).cloned())
+        {
+            return counter;
+        }
+        let Some(repo) = family.hf_repo() else {
+            return Arc::new(ApproxCharTokenizer);
+        };
+        match HfTokenCounter::load_from(&self.cache, repo, false) {
+            Ok(counter) => {
+                let counter: Arc<dyn TokenCounter> = Arc::new(counter);
+                if let Ok(mut loaded) = self.loaded.write() {
+                    loaded.insert(family, Arc::clone(&counter));
+                }
+                counter
+            }
+            Err(e) => {
+                if self.allow_download {
+                    self.download_in_background(family, repo);
+                } else {
+                    warn!("Falling back to approximate token counts: {e}");
+                }
+                Arc::new(ApproxCharTokenizer)
+            }
+        }
+    }
+
+    /// Downloads the tokenizer of `family` on a new thread, unless a download is under way, and
+    /// switches to it if the family is still selected.
+    fn download_in_background(&self, family: TokenizerFamily, repo: &'static str) {
+        match self.downloading.lock() {
+            Ok(mut downloading) if downloading.insert(family) => {}
+            _ => return,
+        }
+        let cache = self.cache.clone();
+        let current = Arc::clone(&self.current);
+        let loaded = Arc::clone(&self.loaded);
+        let downloading = Arc::clone(&self.downloading);
+        std::thread::spawn(move || {
+            match HfTokenCounter::load_from(&cache, repo, true) {
+                Ok(counter) => {
+                    let counter: Arc<dyn TokenCounter> = Arc::new(counter);
+                    if let Ok(mut loaded) = loaded.write() {
+                        loaded.insert(family, Arc::clone(&counter));
+                    }
+                    if let Ok(mut current) = current.write()
+                        && current.0 == family
+                    {
+                        current.1 = counter;
+                    }
+                    debug!(?family, "tokenizer downloaded");
+                }
+                Err(e) => warn!("Falling back to approximate token counts: {e}"),
+            }
+            if let Ok(mut downloading) = downloading.lock() {
+                downloading.remove(&family);
+            }
+        });
+    }
+}
+
+impl TokenCounter for ModelTokenCounter {
+    fn count(&self, text: &str) -> usize {
+        match self.current.read() {
+            Ok(current) => current.1.count(text),
+            Err(_) => ApproxCharTokenizer.count(text),
+        }
+    }
+
+    fn select_model(&self, model: &ModelId) {
+        let family = TokenizerFamily::from_model(model);
+        debug!(?family, %model, "selecting tokenizer");
+        // Mark the family first so a download finishing meanwhile switches to it. The counter
+        // found below only replaces this placeholder, never a tokenizer the download installed.
+        let placeholder: Arc<dyn TokenCounter> = Arc::new(ApproxCharTokenizer);
+        if let Ok(mut current) = self.current.write() {
+            *current = (family, Arc::clone(&placeholder));
+        }
+        let counter = self.counter_for(family);
+        if let Ok(mut current) = self.current.write()
+            && Arc::ptr_eq(&current.1, &placeholder)
+        {
+            current.1 = counter;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn family(model: &str) -> TokenizerFamily {
+        TokenizerFamily::from_model(&ModelId::from_str(model).unwrap())
+    }
+
+    #[test]
+    fn families_are_picked_from_author_then_name() {
+        assert_eq!(family("openai/gpt-4o"), TokenizerFamily::OpenAi);
+        assert_eq!(
+            family("anthropic/claude-sonnet-4"),
+            TokenizerFamily::Anthropic
+        );
+        assert_eq!(
+            family("meta-llama/llama-3.1-8b-instruct"),
+            TokenizerFamily::Llama
+        );
+        assert_eq!(
+            family("nousresearch/hermes-3-llama-3.1-70b"),
+            TokenizerFamily::Llama
+        );
+        assert_eq!(
+            family("deepseek/deepseek-chat-v3.1:free"),
+            TokenizerFamily::DeepSeek
+        );
+        assert_eq!(family("moonshotai/kimi-k2"), TokenizerFamily::Unknown);
+        assert_eq!(TokenizerFamily::Unknown.hf_repo(), None);
+    }
+
+    /// A word-level tokenizer splitting on whitespace and punctuation runs.
+    const WORD_LEVEL_TOKENIZER: &str = r#"{
+        "version": "1.0",
+        "truncation": null,
+        "padding": null,
+        "added_tokens": [],
+        "normalizer": null,
+        "pre_tokenizer": { "type": "Whitespace" },
+        "post_processor": null,
+        "decoder": null,
+        "model": {
+            "type": "WordLevel",
+            "vocab": { "[UNK]": 0, "fn": 1, "parse": 2 },
+            "unk_token": "[UNK]"
+        }
+    }"#;
+
+    #[test]
+    fn hf_counter_counts_tokenizer_tokens() {
+        let tokenizer = Tokenizer::from_str(WORD_LEVEL_TOKENIZER).unwrap();
+        let counter = HfTokenCounter::new("test/word-level", tokenizer);
+        // fn, parse, (, s, :, &, str, ), ->, Vec, <, u8, >
+        let text = "fn parse(s: &str) -> Vec<u8>";
+        assert_eq!(counter.count(text), 13);
+        assert_ne!(counter.count(text), ApproxCharTokenizer.count(text));
+        assert_eq!(counter.count(""), 0);
+    }
+
+    #[test]
+    fn failed_loads_are_retried() {
+        let family = TokenizerFamily::Anthropic;
+        // Nothing is written to the cache without downloads, so it stays empty.
+        let cache = Cache::new(std::env::temp_dir().join("ploke-rag-empty-hf-cache"));
+        let counter = ModelTokenCounter::with_cache(
+            &ModelId::from_str("anthropic/claude-sonnet-4").unwrap(),
+            false,
+            cache,
+        );
+        assert_eq!(
+            counter.count("abcdefgh"),
+            ApproxCharTokenizer.count("abcdefgh")
+        );
+        assert!(
+            !counter.loaded.read().unwrap().contains_key(&family),
+            "the fallback must not be kept in place of the tokenizer"
+        );
+    }
+
+    #[test]
+    fn unknown_families_use_the_approximation() {
+        let counter =
+            ModelTokenCounter::new(&ModelId::from_str("moonshotai/kimi-k2").unwrap(), false);
+        assert_eq!(
+            counter.count("abcdefgh"),
+            ApproxCharTokenizer.count("abcdefgh")
+        );
+    }
+}
@@ -1071,6 +1071,7 @@ impl TestRuntime<NotSpawned, NotSpawned, NotSpawned, NotSpawned, NotSpawned> {
             indexing_state: RwLock::new(None),
             indexer_task: Some(Arc::clone(&indexer_task)),
             indexing_control: Arc::new(Mutex::new(None)),
+            parsers: Default::default(),
             db: db_handle,
             embedder: Arc::clone(&embedding_runtime),
             io_handle,
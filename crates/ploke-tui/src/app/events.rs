@@ -47,7 +47,7 @@ pub(crate) async fn handle_event(app: &mut App, app_event: AppEvent) {
                     );
                     return;
                 }
-                let AssembledContext { parts, stats } = assembled_context;
+                let AssembledContext { parts, stats, .. } = assembled_context;
                 info!(
                     "ContextSearch event completed with search results.
                     AssembledContext with stats:
@@ -128,6 +128,10 @@ pub(crate) async fn handle_event(app: &mut App, app_event: AppEvent) {
                     app.active_model_indicator =
                         Some((new_model.clone().to_string(), Instant::now()));
                     app.active_model_id = new_model.to_string();
+                    // Count context tokens with the new model's tokenizer; loading may hit disk.
+                    if let Some(rag) = app.state.rag.clone() {
+                        tokio::task::spawn_blocking(move || rag.select_tokenizer_model(&new_model));
+                    }
                 }
                 SystemEvent::ReadQuery {
                     file_name,
@@ -11,6 +11,7 @@ use uuid::Uuid;
 use crate::llm::LLMParameters;
 use crate::llm::registry::user_prefs::RegistryPrefs;
 use crate::llm::{ModelId, ModelKey};
+use crate::parser::IncrementalParsers;
 use crate::user_config::{
     ChatPolicy, CommandStyle, CtxPrefs, EmbeddingConfig, LocalEmbeddingTuning,
     MessageVerbosityProfile, MessageVerbosityProfiles, RagUserConfig, UserConfig,
@@ -36,6 +37,8 @@ pub struct AppState {
     pub indexing_state: RwLock<Option<IndexingStatus>>,
     pub indexer_task: Option<Arc<IndexerTask>>,
     pub indexing_control: Arc<Mutex<Option<mpsc::Sender<IndexerCommand>>>>,
+    /// Parsers of the crates indexed in this session, reused to re-index only changed files.
+    pub parsers: IncrementalParsers,
 
     pub db: Arc<Database>,
     pub embedder: Arc<EmbeddingRuntime>,
@@ -195,7 +198,7 @@ impl Default for EditingConfig {
 
 use super::*;
 use crate::tools::ToolVerbosity;
-use crate::user_config::ToolingConfig;
+use crate::user_config::{IndexingConfig, ToolingConfig};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuntimeConfig {
@@ -214,6 +217,7 @@ pub struct RuntimeConfig {
     pub embedding_local: LocalEmbeddingTuning,
     pub ploke_editor: Option<String>,
     pub tooling: ToolingConfig,
+    pub indexing: IndexingConfig,
     pub chat_policy: ChatPolicy,
     pub rag: RagUserConfig,
     pub token_limit: u32,
@@ -270,6 +274,7 @@ impl From<UserConfig> for RuntimeConfig {
             embedding_local,
             ploke_editor: uc.ploke_editor,
             tooling: uc.tooling,
+            indexing: uc.indexing,
             chat_policy,
             rag,
             token_limit: uc.token_limit,
@@ -307,6 +312,7 @@ impl RuntimeConfig {
             ploke_editor: self.ploke_editor.clone(),
             context_management: self.context_management.clone(),
             tooling: self.tooling.clone(),
+            indexing: self.indexing.clone(),
             chat_policy: self.chat_policy.clone(),
             rag: self.rag.clone(),
             token_limit: self.token_limit,
@@ -397,6 +403,7 @@ impl AppState {
             indexing_state: RwLock::new(None),
             indexer_task: None,
             indexing_control: Arc::new(Mutex::new(None)),
+            parsers: Default::default(),
             db,
             embedder,
             io_handle,
@@ -10,8 +10,9 @@ use ploke_core::{
     CrateId, CrateInfo, EmbeddingData, FileData, NodeId, RetrievalScope, WorkspaceInfo,
 };
 use ploke_db::{
-    CrateContextRow, EmbedDataVerbose, NamespaceImportConflictReport, NamespaceImportError,
-    NamespaceImportResult, NamespaceRemovalResult, NodeType, RestoredEmbeddingSet, SimilarArgs,
+    CozoStorage, CrateContextRow, EmbedDataVerbose, ExistingStore, NamespaceImportConflictReport,
+    NamespaceImportError, NamespaceImportResult, NamespaceRemovalResult, NodeType,
+    PersistentDatabase, RestoredEmbeddingSet, SimilarArgs,
     multi_embedding::schema::EmbeddingSetExt, search_similar_args,
 };
 use ploke_embed::config::OpenRouterConfig;
@@ -40,7 +41,7 @@ use crate::{
         core::WorkspaceFreshness,
         helpers::{print_module_set, printable_nodes},
     },
-    parser::run_parse_no_transform,
+    parser::{reindex_crate, run_parse_no_transform},
     tracing_setup::SCAN_CHANGE,
     user_config::{WorkspaceRegistry, WorkspaceRegistryEntry},
     utils::parse_errors::format_parse_failure,
@@ -147,7 +148,7 @@ impl From<&str> for IndexTargetDir {
 }
 
 fn restored_workspace_members_from_db(
-    db: &ploke_db::Database,
+    db: &ploke_db::Database<impl CozoStorage>,
 ) -> Result<Option<(PathBuf, Vec<PathBuf>)>, ploke_error::Error> {
     let db_res = db
         .raw_query("?[root_path, members] := *workspace_metadata { root_path, members @ 'NOW' }")?;
@@ -190,7 +191,7 @@ fn restored_workspace_members_from_db(
 }
 
 fn restored_crate_context_rows(
-    db: &ploke_db::Database,
+    db: &ploke_db::Database<impl CozoStorage>,
 ) -> Result<Vec<(String, PathBuf)>, ploke_error::Error> {
     let db_res =
         db.raw_query("?[name, root_path] := *crate_context { name, root_path @ 'NOW' }")?;
@@ -222,7 +223,7 @@ fn restored_crate_context_rows(
 }
 
 fn restored_workspace_snapshot_from_db(
-    db: &ploke_db::Database,
+    db: &ploke_db::Database<impl CozoStorage>,
     preferred_focus: Option<&std::path::Path>,
 ) -> Result<RestoredWorkspaceSnapshot, ploke_error::Error> {
     let crate_rows = restored_crate_context_rows(db)?;
@@ -725,6 +726,9 @@ async fn persist_current_workspace_snapshot(
                 ),
             })
         })?;
+    // Reopen the written snapshot the way `/load` will, so a snapshot that cannot be restored
+    // never makes it into the registry.
+    load_staging_snapshot_from_registry_entry(&registry_entry)?;
 
     registry.upsert(registry_entry.clone());
     save_workspace_registry(&registry)?;
@@ -837,9 +841,13 @@ fn namespace_import_error_to_ui(
     }
 }
 
+/// Opens the snapshot an entry points at as a persistent store and checks it against the entry.
+///
+/// The snapshot is read where it is on disk instead of being imported into a staging in-memory
+/// database, and is opened with [`ExistingStore::AsIs`] so validating it never rewrites the file.
 fn load_staging_snapshot_from_registry_entry(
     entry: &WorkspaceRegistryEntry,
-) -> Result<(ploke_db::Database, RestoredWorkspaceSnapshot), ploke_error::Error> {
+) -> Result<(PersistentDatabase, RestoredWorkspaceSnapshot), ploke_error::Error> {
     let valid_file = entry.snapshot_file.clone();
     if !valid_file.exists() {
         return Err(ploke_error::Error::Domain(DomainError::Ui {
@@ -851,10 +859,7 @@ fn load_staging_snapshot_from_registry_entry(
         }));
     }
 
-    let staging_db = ploke_db::Database::init_with_schema().map_err(ploke_error::Error::from)?;
-    staging_db
-        .import_backup_with_embeddings(&valid_file)
-        .map_err(ploke_error::Error::from)?;
+    let staging_db = PersistentDatabase::open_persistent(&valid_file, ExistingStore::AsIs)?;
     let restored_snapshot =
         restored_workspace_snapshot_from_db(&staging_db, entry.focused_root.as_deref())?;
     workspace_registry_entry_matches_snapshot(entry, &restored_snapshot)?;
@@ -998,6 +1003,7 @@ pub(super) async fn load_db(
 
     state.db.clear_hnsw_idx().await?;
     state.db.clear_relations().await?;
+    state.parsers.clear();
     state
         .db
         .import_backup_with_embeddings(&valid_file)
@@ -1118,14 +1124,31 @@ pub(super) async fn load_db(
         .await;
     }
 
-    handlers::chat::add_msg_immediate(
-        state,
-        event_bus,
-        Uuid::new_v4(),
-        "BM25 search availability was not restored from the workspace snapshot; rebuild BM25 before relying on sparse or hybrid search.".to_string(),
-        chat_history::MessageKind::SysInfo,
-    )
-    .await;
+    let bm25_msg = match &state.rag {
+        Some(rag) => match rag.bm25_restore().await {
+            Ok(()) => None,
+            Err(e) => {
+                warn!("Failed to restore BM25 index: {e}");
+                Some(format!(
+                    "BM25 index could not be restored from the workspace snapshot: {e}. Rebuild BM25 before relying on sparse or hybrid search."
+                ))
+            }
+        },
+        None => Some(
+            "RAG service unavailable; BM25 search was not restored from the workspace snapshot."
+                .to_string(),
+        ),
+    };
+    if let Some(msg) = bm25_msg {
+        handlers::chat::add_msg_immediate(
+            state,
+            event_bus,
+            Uuid::new_v4(),
+            msg,
+            chat_history::MessageKind::SysInfo,
+        )
+        .await;
+    }
 
     // get count for sanity and user feedback
     match state.db.count_relations().await {
@@ -1264,7 +1287,6 @@ async fn scan_for_change_target(
     event_bus: &Arc<EventBus>,
     target: &LoadedCrateScanTarget,
     scan_tx: oneshot::Sender<Option<Vec<std::path::PathBuf>>>,
-    emit_reindex: bool,
 ) -> Result<(), ploke_error::Error> {
     let crate_path = target.root_path.clone();
     let crate_name = target.crate_name.clone();
@@ -1313,31 +1335,36 @@ async fn scan_for_change_target(
 
         // Extract pwd from SystemState before calling sync function
         let pwd = state.with_system_read(|sys| sys.pwd().to_path_buf()).await;
-
-        let mut parser_output =
-            match run_parse_no_transform(Arc::clone(&state.db), Some(crate_path.clone()), &pwd) {
-                Ok(output) => {
-                    state
-                        .with_system_txn(|txn| {
-                            txn.record_parse_success();
-                        })
-                        .await;
-                    output
-                }
-                Err(err) => {
-                    let msg = format_parse_failure(&crate_path, &err);
-                    state
-                        .with_system_txn(|txn| {
-                            txn.record_parse_failure(crate_path.clone(), msg.clone());
-                        })
-                        .await;
-                    event_bus.send(AppEvent::Error(crate::event_bus::ErrorEvent {
-                        message: msg.clone(),
-                        severity: crate::error::ErrorSeverity::Error,
-                    }));
-                    return Err(ploke_error::Error::Domain(DomainError::Ui { message: msg }));
-                }
-            };
+        let derives = state.config.read().await.indexing.derive_table();
+
+        let mut parser_output = match run_parse_no_transform(
+            Arc::clone(&state.db),
+            Some(crate_path.clone()),
+            &pwd,
+            &derives,
+        ) {
+            Ok(output) => {
+                state
+                    .with_system_txn(|txn| {
+                        txn.record_parse_success();
+                    })
+                    .await;
+                output
+            }
+            Err(err) => {
+                let msg = format_parse_failure(&crate_path, &err);
+                state
+                    .with_system_txn(|txn| {
+                        txn.record_parse_failure(crate_path.clone(), msg.clone());
+                    })
+                    .await;
+                event_bus.send(AppEvent::Error(crate::event_bus::ErrorEvent {
+                    message: msg.clone(),
+                    severity: crate::error::ErrorSeverity::Error,
+                }));
+                return Err(ploke_error::Error::Domain(DomainError::Ui { message: msg }));
+            }
+        };
         let mut merged = match parser_output
             .extract_merged_graph()
             .ok_or(SynParserError::MergeError)
@@ -1640,12 +1667,10 @@ module tree process or run_parse_no_transform"
             }
         }
 
-        if emit_reindex {
-            trace!("Finishing scanning, sending message to reindex workspace");
-            event_bus.send(AppEvent::System(SystemEvent::ReIndex {
-                workspace: crate_name.to_string(),
-            }));
-        }
+        trace!("Finishing scanning, sending message to reindex workspace");
+        event_bus.send(AppEvent::System(SystemEvent::ReIndex {
+            workspace: crate_name.to_string(),
+        }));
         let _ = scan_tx.send(Some(changed_filenames));
         // TODO: Add validation step here.
     }
@@ -1660,7 +1685,7 @@ pub(super) async fn scan_for_change(
     scan_tx: oneshot::Sender<Option<Vec<std::path::PathBuf>>>,
 ) -> Result<(), ploke_error::Error> {
     let target = primary_scan_target(state).await?;
-    scan_for_change_target(state, event_bus, &target, scan_tx, true).await
+    scan_for_change_target(state, event_bus, &target, scan_tx).await
 }
 
 pub(super) async fn workspace_status(
@@ -1722,11 +1747,27 @@ pub(super) async fn workspace_update(
         return Ok(());
     }
 
+    let derives = state.config.read().await.indexing.derive_table();
     for target in &stale_targets {
-        let (scan_tx, scan_rx) = oneshot::channel();
-        scan_for_change_target(state, event_bus, target, scan_tx, false).await?;
-        let _ = scan_rx.await;
+        if let Err(err) = reindex_crate(&state.db, &state.parsers, &derives, &target.root_path) {
+            let msg = format_parse_failure(&target.root_path, &err);
+            state
+                .with_system_txn(|txn| {
+                    txn.record_parse_failure(target.root_path.clone(), msg.clone());
+                })
+                .await;
+            event_bus.send(AppEvent::Error(crate::event_bus::ErrorEvent {
+                message: msg.clone(),
+                severity: crate::error::ErrorSeverity::Error,
+            }));
+            return Err(ploke_error::Error::Domain(DomainError::Ui { message: msg }));
+        }
     }
+    state
+        .with_system_txn(|txn| {
+            txn.record_parse_success();
+        })
+        .await;
 
     let workspace_target = state
         .with_system_read(|sys| {
@@ -1816,6 +1857,7 @@ pub(super) async fn load_workspace_crates(
         .import_namespace(&artifact)
         .await
         .map_err(|err| namespace_import_error_to_ui(&workspace_ref, &crate_ref, err))?;
+    state.parsers.clear();
 
     let restored_snapshot =
         restored_workspace_snapshot_from_db(&state.db, preferred_focus.as_deref())?;
@@ -1913,6 +1955,7 @@ pub(super) async fn workspace_remove(
         .remove_namespace(target.namespace)
         .await
         .map_err(ploke_error::Error::from)?;
+    state.parsers.clear();
 
     let restored_snapshot =
         restored_workspace_snapshot_from_db(&state.db, preferred_focus.as_deref())?;
@@ -2058,6 +2101,7 @@ mod tests {
             indexing_state: tokio::sync::RwLock::new(None),
             indexer_task: None,
             indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+            parsers: Default::default(),
             db,
             embedder,
             io_handle: ploke_io::IoManagerHandle::new(),
@@ -2858,6 +2902,7 @@ mod test {
             indexing_state: RwLock::new(None),
             indexer_task: Some(Arc::new(indexer_task)),
             indexing_control: Arc::new(Mutex::new(None)),
+            parsers: Default::default(),
             db: db_handle.clone(),
             embedder: Arc::clone(&embedding_runtime),
             io_handle: io_handle.clone(),
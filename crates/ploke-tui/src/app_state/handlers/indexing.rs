@@ -87,7 +87,8 @@ pub async fn index_workspace(
     };
 
     if needs_parse {
-        match run_parse_resolved(Arc::clone(&state.db), &resolved) {
+        let derives = state.config.read().await.indexing.derive_table();
+        match run_parse_resolved(Arc::clone(&state.db), &state.parsers, &derives, &resolved) {
             Ok(_) => {
                 tracing::info!(
                     "Parse of target {} successful",
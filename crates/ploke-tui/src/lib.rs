@@ -116,13 +116,36 @@ pub async fn emit_app_event(event: AppEvent) {
     }
 }
 
-fn build_rag_config(rag: &crate::user_config::RagUserConfig) -> RagConfig {
+fn build_rag_config(
+    rag: &crate::user_config::RagUserConfig,
+    active_model: &crate::llm::ModelId,
+) -> RagConfig {
     let mut cfg = RagConfig::default();
     cfg.bm25_timeout_ms = rag.bm25_timeout_ms;
     cfg.bm25_retry_backoff_ms = rag.bm25_retry_backoff_ms.clone();
     cfg.strict_bm25_by_default = rag.strict_bm25_by_default;
+    cfg.bm25_boosts = rag.bm25_boosts;
     cfg.rrf_default = rag.rrf;
     cfg.mmr_default = rag.mmr;
+    cfg.token_counter = Arc::new(ploke_rag::ModelTokenCounter::new(
+        active_model,
+        !rag.tokenizer_cache_only,
+    ));
+    match rag.reranker.build() {
+        Ok(reranker) => cfg.reranker = reranker,
+        Err(e) => tracing::warn!(
+            "Failed to initialize reranker, continuing without it: {}",
+            e
+        ),
+    }
+    cfg.graph_expansion = rag.graph_expansion.build();
+    match rag.query_rewrite.build() {
+        Ok(rewrite) => cfg.query_rewrite = rewrite,
+        Err(e) => tracing::warn!(
+            "Failed to initialize query rewriting, searching messages as is: {}",
+            e
+        ),
+    }
     cfg
 }
 pub async fn try_main() -> color_eyre::Result<()> {
@@ -201,7 +224,7 @@ pub async fn try_main() -> color_eyre::Result<()> {
     let indexer_task = Arc::new(indexer_task);
 
     // Initialize RAG orchestration service with full capabilities (BM25 + dense + IoManager)
-    let rag_config = build_rag_config(&runtime_cfg.rag);
+    let rag_config = build_rag_config(&runtime_cfg.rag, &runtime_cfg.active_model);
     let rag = match ploke_rag::RagService::new_full(
         db_handle.clone(),
         Arc::clone(&embedding_runtime),
@@ -230,6 +253,7 @@ pub async fn try_main() -> color_eyre::Result<()> {
         indexing_state: RwLock::new(None), // Initialize as None
         indexer_task: Some(Arc::clone(&indexer_task)),
         indexing_control: Arc::new(Mutex::new(None)),
+        parsers: Default::default(),
         db: db_handle,
         embedder: Arc::clone(&embedding_runtime),
         io_handle: io_handle.clone(),
@@ -1,21 +1,47 @@
-use std::{env, fmt, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    env, fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
 
-use ploke_db::Database;
+use ploke_db::{Database, SnapshotRun, SnapshotTag};
 use ploke_io::path_policy::{PathPolicy, normalize_target_path};
 use ploke_transform::transform::{
-    transform_parsed_graph, transform_parsed_workspace, transform_union_crate_and_structural_masks,
+    WriteBatch, push_parsed_graph, push_parsed_workspace, push_union_crate_and_structural_masks,
+    transform_parsed_dependency,
 };
 use syn_parser::{
-    ManifestKind, ModuleTree, ParsedCodeGraph, ParserOutput,
+    DependencySourceConfig, ManifestKind, ModuleTree, ParseWorkspaceConfig, ParsedCodeGraph,
+    ParserOutput,
     compilation_unit::CompilationUnitDimensionRequest,
     discovery::run_discovery_phase,
     discovery::workspace::{locate_workspace_manifest, try_parse_manifest},
     error::SynParserError,
-    parse_workspace,
-    parser::analyze_files_parallel,
-    try_run_phases_and_merge, try_run_phases_union_for_crate_with_dimensions,
+    incremental::IncrementalParser,
+    parse_dependency_sources, parse_workspace_with_config,
+    parser::{analyze_files_parallel, derives::DeriveTable},
+    try_run_phases_union_for_crate_with_dimensions,
 };
 use tracing::instrument;
+use uuid::Uuid;
+
+/// The incremental parsers of the crates indexed in this session, by crate root, so indexing a
+/// crate again only parses the files that changed since.
+#[derive(Debug, Default)]
+pub struct IncrementalParsers(Mutex<HashMap<PathBuf, IncrementalParser>>);
+
+impl IncrementalParsers {
+    /// Drops every cached parser. Call this when the loaded graph is replaced other than by
+    /// indexing, so the parsers cannot diff against a state the database no longer holds.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<PathBuf, IncrementalParser>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IndexTargetKind {
@@ -178,12 +204,22 @@ pub fn run_parse(
 ) -> Result<(), SynParserError> {
     let resolved = resolve_index_target(target_dir, pwd)
         .map_err(|err| SynParserError::InternalState(err.to_string()))?;
-    run_parse_resolved(db, &resolved)
+    run_parse_resolved(
+        db,
+        &IncrementalParsers::default(),
+        &DeriveTable::from_env_or_default(),
+        &resolved,
+    )
 }
 
-#[instrument(skip(db), fields(target = %resolved.requested_path.display(), kind = ?resolved.kind))]
+#[instrument(
+    skip(db, parsers, derives),
+    fields(target = %resolved.requested_path.display(), kind = ?resolved.kind)
+)]
 pub fn run_parse_resolved(
     db: Arc<Database>,
+    parsers: &IncrementalParsers,
+    derives: &DeriveTable,
     resolved: &ResolvedIndexTarget,
 ) -> Result<(), SynParserError> {
     use syn_parser::utils::LogStyle;
@@ -195,51 +231,61 @@ pub fn run_parse_resolved(
         resolved.kind
     );
 
+    let run = SnapshotRun::begin(index_snapshot_tag(&resolved.workspace_root));
     match resolved.kind {
+        IndexTargetKind::Crate if !compilation_union_ingest_enabled() => {
+            reindex_crate(&db, parsers, derives, &resolved.focused_root)?;
+        }
         IndexTargetKind::Crate => {
-            if compilation_union_ingest_enabled() {
-                let mut parser_output = try_run_phases_union_for_crate_with_dimensions(
-                    &resolved.focused_root,
-                    &CompilationUnitDimensionRequest::from_env_or_default(),
-                )?;
-                let graphs = parser_output
-                    .extract_parsed_graphs_for_masks()
-                    .ok_or_else(|| {
-                        SynParserError::InternalState(
-                            "Missing parsed graphs for CU masks".to_string(),
-                        )
-                    })?;
-                let compilation_units = parser_output.extract_compilation_units();
-                transform_union_crate_and_structural_masks(&db, graphs, compilation_units)
-                    .map_err(|err| {
-                        SynParserError::InternalState(format!(
-                            "Failed union crate transform and CU masks: {err}"
-                        ))
-                    })?;
-            } else {
-                let mut parser_output = try_run_phases_and_merge(&resolved.focused_root)?;
-                let merged = parser_output.extract_merged_graph().ok_or_else(|| {
-                    SynParserError::InternalState("Missing parsed code graph".to_string())
+            let mut parser_output = try_run_phases_union_for_crate_with_dimensions(
+                &resolved.focused_root,
+                &CompilationUnitDimensionRequest::from_env_or_default(),
+            )?;
+            let graphs = parser_output
+                .extract_parsed_graphs_for_masks()
+                .ok_or_else(|| {
+                    SynParserError::InternalState("Missing parsed graphs for CU masks".to_string())
                 })?;
-                let tree = parser_output.extract_module_tree().ok_or_else(|| {
-                    SynParserError::InternalState("Missing module tree".to_string())
-                })?;
-                transform_parsed_graph(&db, merged, &tree).map_err(|err| {
+            let compilation_units = parser_output.extract_compilation_units();
+            let batch = WriteBatch::default();
+            push_union_crate_and_structural_masks(&db, &batch, graphs, compilation_units).map_err(
+                |err| {
                     SynParserError::InternalState(format!(
-                        "Failed to transform parsed graph: {err}"
+                        "Failed union crate transform and CU masks: {err}"
                     ))
-                })?;
-            }
+                },
+            )?;
+            replace_namespaces(&db, &reindexed_namespaces(&db, resolved)?, batch)?;
+            parsers.lock().remove(&resolved.focused_root);
         }
         IndexTargetKind::Workspace => {
-            let parsed_workspace = parse_workspace(&resolved.workspace_root, None)?;
-            transform_parsed_workspace(&db, parsed_workspace).map_err(|err| {
+            let parsed_workspace = parse_workspace_with_config(
+                &resolved.workspace_root,
+                &ParseWorkspaceConfig {
+                    derives: Some(derives),
+                    ..Default::default()
+                },
+            )?;
+            let batch = WriteBatch::default();
+            push_parsed_workspace(&db, &batch, parsed_workspace).map_err(|err| {
                 SynParserError::InternalState(format!(
                     "Failed to transform parsed workspace: {err}"
                 ))
             })?;
+            replace_namespaces(&db, &reindexed_namespaces(&db, resolved)?, batch)?;
+            let mut parsers = parsers.lock();
+            for root in &resolved.member_roots {
+                parsers.remove(root);
+            }
         }
     }
+    if dependency_ingest_enabled() {
+        index_dependency_sources(&db, &resolved.workspace_root)?;
+    }
+    let snapshot = db.record_graph_snapshot(run).map_err(|err| {
+        SynParserError::InternalState(format!("Failed to record graph snapshot: {err}"))
+    })?;
+    tracing::debug!(tag = %snapshot.tag, "recorded graph snapshot");
 
     tracing::info!(
         "{}: Parsing and Database Transform Complete",
@@ -248,17 +294,175 @@ pub fn run_parse_resolved(
     Ok(())
 }
 
+/// Tags the snapshot of an index run with the commit checked out at `workspace_root`, or with a
+/// fresh run id outside a git checkout.
+fn index_snapshot_tag(workspace_root: &std::path::Path) -> SnapshotTag {
+    std::process::Command::new("git")
+        .arg("-C")
+        .arg(workspace_root)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .filter(|commit| !commit.is_empty())
+        .map_or_else(SnapshotTag::new_run, SnapshotTag::Commit)
+}
+
+/// Returns the namespaces of the already indexed crates that are about to be written again, so
+/// their rows can be replaced and nodes deleted since the last run do not linger.
+fn reindexed_namespaces(
+    db: &Database,
+    resolved: &ResolvedIndexTarget,
+) -> Result<Vec<Uuid>, SynParserError> {
+    let roots = match resolved.kind {
+        IndexTargetKind::Crate => vec![resolved.focused_root.clone()],
+        IndexTargetKind::Workspace => resolved.member_roots.clone(),
+    };
+    let rows = db.list_crate_context_rows().map_err(|err| {
+        SynParserError::InternalState(format!("Failed to list indexed crates: {err}"))
+    })?;
+    Ok(rows
+        .into_iter()
+        .filter(|row| {
+            roots
+                .iter()
+                .any(|root| PathBuf::from(&row.root_path) == *root)
+        })
+        .map(|row| row.namespace)
+        .collect())
+}
+
+/// Replaces the rows of the indexed `namespaces` with the writes in `batch`, then retracts the
+/// search rows of the nodes that are gone.
+fn replace_namespaces(
+    db: &Database,
+    namespaces: &[Uuid],
+    batch: WriteBatch,
+) -> Result<(), SynParserError> {
+    let reindexed = db.reindex_namespaces(namespaces, batch).map_err(|err| {
+        SynParserError::InternalState(format!("Failed to write the parsed graph: {err}"))
+    })?;
+    for (namespace, previous_ids) in &reindexed {
+        db.retract_stale_search_rows(*namespace, previous_ids)
+            .map_err(|err| {
+                SynParserError::InternalState(format!("Failed to retract stale nodes: {err}"))
+            })?;
+    }
+    Ok(())
+}
+
+/// Indexes the crate at `crate_root` again.
+///
+/// When the crate was parsed before in this session and is still loaded, only the files changed
+/// since are parsed, and the resulting diff is applied with [`Database::apply_graph_diff`].
+/// Otherwise, or when the cached parser used another derive table, the crate is parsed in full
+/// and its rows are replaced. Either way the new graph is written in one transaction, and the
+/// parser is kept for the next run.
+pub fn reindex_crate(
+    db: &Database,
+    parsers: &IncrementalParsers,
+    derives: &DeriveTable,
+    crate_root: &Path,
+) -> Result<(), SynParserError> {
+    let rows = db.list_crate_context_rows().map_err(|err| {
+        SynParserError::InternalState(format!("Failed to list indexed crates: {err}"))
+    })?;
+    let loaded = rows
+        .iter()
+        .filter(|row| Path::new(&row.root_path) == crate_root)
+        .map(|row| row.namespace)
+        .collect::<Vec<_>>();
+
+    let cached = parsers.lock().remove(crate_root);
+    if let Some(mut parser) = cached
+        && loaded == [parser.merged_graph().crate_namespace]
+        && parser.derives() == derives
+    {
+        let diff = match parser.update() {
+            Ok(diff) => diff,
+            Err(err) => {
+                // The parser keeps its previous state on error, which still matches the database.
+                parsers.lock().insert(crate_root.to_path_buf(), parser);
+                return Err(err);
+            }
+        };
+        if !diff.is_empty() {
+            db.apply_graph_diff(parser.merged_graph().clone(), parser.module_tree(), &diff)
+                .map_err(|err| {
+                    SynParserError::InternalState(format!("Failed to apply the graph diff: {err}"))
+                })?;
+        }
+        parsers.lock().insert(crate_root.to_path_buf(), parser);
+        return Ok(());
+    }
+
+    let parser = IncrementalParser::with_derives(crate_root, None, derives.clone())?;
+    let batch = WriteBatch::default();
+    push_parsed_graph(
+        db,
+        &batch,
+        parser.merged_graph().clone(),
+        parser.module_tree(),
+    )
+    .map_err(|err| {
+        SynParserError::InternalState(format!("Failed to transform parsed graph: {err}"))
+    })?;
+    replace_namespaces(db, &loaded, batch)?;
+    parsers.lock().insert(crate_root.to_path_buf(), parser);
+    Ok(())
+}
+
 fn compilation_union_ingest_enabled() -> bool {
     std::env::var("PLOKE_COMPILATION_UNION")
         .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
         .unwrap_or(false)
 }
 
-#[instrument(err, fields(target_dir), skip(db))]
+fn dependency_ingest_enabled() -> bool {
+    std::env::var("PLOKE_INDEX_DEPENDENCIES")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Parses the registry dependencies of the workspace and stores each one that is not indexed yet
+/// as a read-only namespace. Already indexed dependencies are skipped, since their namespaces
+/// cannot be written again.
+fn index_dependency_sources(
+    db: &Database,
+    workspace_root: &std::path::Path,
+) -> Result<(), SynParserError> {
+    use syn_parser::utils::LogStyle;
+
+    let parsed = parse_dependency_sources(workspace_root, &DependencySourceConfig::default())?;
+    for dependency in parsed.crates {
+        let namespace = dependency.parsed.crate_context.namespace;
+        let indexed = db.is_dependency_namespace(namespace).map_err(|err| {
+            SynParserError::InternalState(format!("Failed to check dependency namespace: {err}"))
+        })?;
+        if indexed {
+            continue;
+        }
+        transform_parsed_dependency(db, dependency).map_err(|err| {
+            SynParserError::InternalState(format!("Failed to transform dependency: {err}"))
+        })?;
+    }
+    tracing::info!(
+        "{}: Indexed dependency sources ({} not found locally, {} failed to parse)",
+        "Parse".log_step(),
+        parsed.missing.len(),
+        parsed.failures.len()
+    );
+    Ok(())
+}
+
+#[instrument(err, fields(target_dir), skip(db, derives))]
 pub fn run_parse_no_transform(
     db: Arc<Database>,
     target_dir: Option<PathBuf>,
     pwd: &std::path::Path,
+    derives: &DeriveTable,
 ) -> Result<ParserOutput, SynParserError> {
     use syn_parser::utils::LogStyle;
 
@@ -278,9 +482,11 @@ pub fn run_parse_no_transform(
     let graphs: Vec<_> = results.into_iter().collect::<Result<_, _>>()?;
 
     let mut merged = ParsedCodeGraph::merge_new(graphs)?;
-    let tree = merged.build_tree_and_prune().map_err(|err| {
-        SynParserError::InternalState(format!("Failed to build module tree: {err}"))
-    })?;
+    let tree = merged
+        .build_tree_and_prune_with_derives(derives)
+        .map_err(|err| {
+            SynParserError::InternalState(format!("Failed to build module tree: {err}"))
+        })?;
     Ok(ParserOutput {
         merged_graph: Some(merged),
         module_tree: Some(tree),
@@ -373,4 +579,47 @@ mod tests {
             other => panic!("unexpected error: {other}"),
         }
     }
+
+    #[test]
+    fn reindexing_a_crate_reuses_its_parser() {
+        let dir = tempdir().unwrap();
+        let crate_root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir_all(crate_root.join("src")).unwrap();
+        std::fs::write(
+            crate_root.join("Cargo.toml"),
+            "[package]\nname = \"reindex_tui\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            crate_root.join("src/lib.rs"),
+            "pub mod a;\n\npub fn keep() {}\n",
+        )
+        .unwrap();
+        std::fs::write(crate_root.join("src/a.rs"), "pub fn old() {}\n").unwrap();
+
+        let db = Arc::new(Database::init_with_schema().unwrap());
+        let parsers = IncrementalParsers::default();
+        let derives = DeriveTable::default();
+        let resolved = resolve_index_target(Some(crate_root.clone()), &crate_root).unwrap();
+        let function_names = || {
+            let mut names = db
+                .raw_query("?[name] := *function { name @ 'NOW' }")
+                .unwrap()
+                .rows
+                .into_iter()
+                .map(|row| row[0].get_str().unwrap().to_string())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+
+        run_parse_resolved(Arc::clone(&db), &parsers, &derives, &resolved).unwrap();
+        assert_eq!(function_names(), vec!["keep", "old"]);
+        assert!(parsers.lock().contains_key(&crate_root));
+
+        std::fs::write(crate_root.join("src/a.rs"), "pub fn renamed() {}\n").unwrap();
+        run_parse_resolved(Arc::clone(&db), &parsers, &derives, &resolved).unwrap();
+        assert_eq!(function_names(), vec!["keep", "renamed"]);
+        assert_eq!(db.list_crate_context_rows().unwrap().len(), 1);
+    }
 }
@@ -106,9 +106,19 @@ pub async fn process_with_rag(
         if let Some(profile) = ctx_profile {
             let mut budget = state.budget.clone();
             budget.per_part_max = profile.per_part_max_tokens;
+            // Earlier turns let follow-up messages be resolved to what they refer to.
+            let mut history: Vec<String> = messages
+                .iter()
+                .filter(|m| matches!(m.role, Role::User | Role::Assistant))
+                .map(|m| m.content.clone())
+                .collect();
+            if history.last() == Some(&user_msg) {
+                history.pop();
+            }
             match rag
-                .get_context(
+                .get_context_for_turn(
                     &user_msg,
+                    &history,
                     profile.top_k,
                     &budget,
                     &retrieval_strategy,
@@ -396,7 +406,10 @@ mod tests {
                 parts: 1,
                 truncated_parts: 0,
                 dedup_removed: 0,
+                merged_parts: 0,
+                capped_parts: 0,
             },
+            inclusions: Vec::new(),
         };
 
         let plan_a = build_context_plan(plan_id, parent_id, &plan_messages, &[], Some(&ctx));
@@ -612,7 +625,10 @@ mod tests {
                 parts: 2,
                 truncated_parts: 0,
                 dedup_removed: 0,
+                merged_parts: 0,
+                capped_parts: 0,
             },
+            inclusions: Vec::new(),
         };
 
         let plan = build_context_plan(
@@ -97,6 +97,7 @@ lazy_static! {
             indexing_state: RwLock::new(None),
             indexer_task: Some(Arc::clone(&indexer_task)),
             indexing_control: Arc::new(Mutex::new(None)),
+            parsers: Default::default(),
             db: db_handle,
             embedder: Arc::clone(&embedding_runtime),
             io_handle: io_handle.clone(),
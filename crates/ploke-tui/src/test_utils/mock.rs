@@ -40,6 +40,7 @@ pub fn create_mock_app_state() -> AppState {
         indexing_state: tokio::sync::RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
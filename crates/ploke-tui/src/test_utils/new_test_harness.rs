@@ -113,6 +113,7 @@ impl AppHarness {
             indexing_state: RwLock::new(None),
             indexer_task: Some(Arc::clone(&indexer_task)),
             indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+            parsers: Default::default(),
             db: db_handle,
             embedder: Arc::clone(&embedding_runtime),
             io_handle,
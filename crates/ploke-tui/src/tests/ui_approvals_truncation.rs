@@ -49,6 +49,7 @@ async fn test_truncation_controls_no_panic() {
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
@@ -111,6 +112,7 @@ async fn test_help_display_truncation() {
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
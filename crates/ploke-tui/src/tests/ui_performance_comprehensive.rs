@@ -91,6 +91,7 @@ async fn create_mock_app_state_with_proposals(
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
@@ -347,6 +348,7 @@ mod performance_tests {
             indexing_state: RwLock::new(None),
             indexer_task: None,
             indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+            parsers: Default::default(),
             db,
             embedder,
             io_handle,
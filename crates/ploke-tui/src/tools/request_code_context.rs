@@ -24,7 +24,7 @@ lazy_static::lazy_static! {
             "properties": {
                 "search_term": {
                     "type": "string",
-                    "description": "Search term guide which code guide hybrid semantic search and bm25."
+                    "description": "Search term guide which code guide hybrid semantic search and bm25. BM25 also understands `kind:fn` and `path:crate::module` filters, \"exact phrases\" and `prefix*` terms."
                 },
                 "token_budget": {
                     "type": "integer",
@@ -146,7 +146,11 @@ impl super::Tool for RequestCodeContextGat {
         };
         let strategy = cfg.rag.strategy.to_runtime();
         drop(cfg);
-        let AssembledContext { parts, stats } = rag
+        let AssembledContext {
+            parts,
+            stats,
+            inclusions,
+        } = rag
             .get_context(
                 &search_term,
                 top_k,
@@ -160,6 +164,7 @@ impl super::Tool for RequestCodeContextGat {
             search_term,
             top_k,
             kind: ContextPartKind::Code,
+            inclusions,
         };
         tracing::debug!(?parts, ?stats);
         let result = RequestCodeContextResult::from_assembled(parts, assembled_meta);
@@ -228,7 +233,7 @@ mod gat_tests {
                     "properties": {
                         "search_term": {
                             "type": "string",
-                            "description": "Search term guide which code guide hybrid semantic search and bm25."
+                            "description": "Search term guide which code guide hybrid semantic search and bm25. BM25 also understands `kind:fn` and `path:crate::module` filters, \"exact phrases\" and `prefix*` terms."
                         },
                         "token_budget": {
                             "type": "integer",
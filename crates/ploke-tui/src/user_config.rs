@@ -6,11 +6,17 @@ use lazy_static::lazy_static;
 use ploke_embed::{
     config::{CozoConfig, HuggingFaceConfig, LocalModelConfig, OpenAIConfig},
     indexer::{CozoBackend, EmbeddingProcessor, EmbeddingSource},
-    local::{DevicePreference, EmbeddingConfig as LocalEmbeddingConfig, LocalEmbedder},
+    local::{
+        DevicePreference, EmbeddingConfig as LocalEmbeddingConfig, LocalEmbedder,
+        cross_encoder::CrossEncoderConfig,
+    },
     providers::{hugging_face::HuggingFaceBackend, openai::OpenAIBackend},
 };
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr as _;
+use std::sync::Arc;
 use std::time::Duration;
 
 // llm types and router defaults
@@ -20,7 +26,12 @@ use crate::llm::router_only::default_model;
 use crate::llm::router_only::openrouter::OpenRouter;
 use crate::llm::{Router as _, registry::user_prefs::RegistryPrefs};
 use crate::tools::ToolVerbosity;
-use ploke_rag::{MmrConfig, RetrievalStrategy, RrfConfig};
+use ploke_db::bm25_index::FieldBoosts;
+use ploke_rag::{
+    CrossEncoderReranker, GraphExpansion, HydeGenerator, LlmHydeGenerator, LlmReranker, MmrConfig,
+    QueryRewrite, Reranker, RetrievalStrategy, RrfConfig,
+};
+use syn_parser::parser::derives::DeriveTable;
 
 lazy_static! {
     // Parsed from llm OpenRouter BASE_URL
@@ -156,6 +167,9 @@ pub struct UserConfig {
     /// Retrieval/fusion preferences (advanced).
     #[serde(default)]
     pub rag: RagUserConfig,
+    /// Parser settings used when indexing crates.
+    #[serde(default)]
+    pub indexing: IndexingConfig,
     /// Default token limit for tool-initiated LLM requests.
     #[serde(default = "default_token_limit")]
     pub token_limit: u32,
@@ -202,6 +216,42 @@ impl Default for ToolingConfig {
     }
 }
 
+/// Parser settings used when indexing crates.
+///
+/// ```rust
+/// use ploke_tui::user_config::IndexingConfig;
+///
+/// let cfg: IndexingConfig = toml::from_str(
+///     r#"
+///     [derives]
+///     "my_macros::Model" = "my_crate::Model"
+///     "my_macros::Builder" = ""
+///     "#,
+/// )
+/// .unwrap();
+/// assert_eq!(cfg.derives.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct IndexingConfig {
+    /// Derive macros mapped to the trait they implement, by resolved `::`-separated paths. An
+    /// empty trait path marks a derive that implements no trait. These entries are added to the
+    /// built-in table and to the entries of the `PLOKE_DERIVE_TABLE` variable. The compilation-union
+    /// ingest still uses the built-in table.
+    #[serde(default)]
+    pub derives: BTreeMap<String, String>,
+}
+
+impl IndexingConfig {
+    /// The table derive macros are resolved against when indexing.
+    pub fn derive_table(&self) -> DeriveTable {
+        let mut table = DeriveTable::from_env_or_default();
+        for (macro_path, trait_path) in &self.derives {
+            table.insert_str(macro_path, trait_path);
+        }
+        table
+    }
+}
+
 fn default_cargo_check_timeout_secs() -> u64 {
     60
 }
@@ -655,9 +705,22 @@ pub struct RagUserConfig {
     #[serde(default)]
     pub strict_bm25_by_default: bool,
     #[serde(default)]
+    pub bm25_boosts: FieldBoosts,
+    #[serde(default)]
     pub rrf: RrfConfig,
     #[serde(default)]
     pub mmr: Option<MmrConfig>,
+    #[serde(default)]
+    pub reranker: RerankerUserConfig,
+    /// Only count tokens with tokenizers already in the local Hugging Face cache, never
+    /// downloading them; models without one are counted approximately. When disabled, missing
+    /// tokenizers are downloaded in the background.
+    #[serde(default = "default_tokenizer_cache_only")]
+    pub tokenizer_cache_only: bool,
+    #[serde(default)]
+    pub query_rewrite: QueryRewriteUserConfig,
+    #[serde(default)]
+    pub graph_expansion: GraphExpansionUserConfig,
 }
 
 impl Default for RagUserConfig {
@@ -669,8 +732,13 @@ impl Default for RagUserConfig {
             bm25_timeout_ms: default_bm25_timeout_ms(),
             bm25_retry_backoff_ms: default_bm25_retry_backoff_ms(),
             strict_bm25_by_default: false,
+            bm25_boosts: FieldBoosts::default(),
             rrf: RrfConfig::default(),
             mmr: None,
+            reranker: RerankerUserConfig::default(),
+            tokenizer_cache_only: default_tokenizer_cache_only(),
+            query_rewrite: QueryRewriteUserConfig::default(),
+            graph_expansion: GraphExpansionUserConfig::default(),
         }
     }
 }
@@ -690,6 +758,15 @@ impl RagUserConfig {
             weight_bm25: self.rrf.weight_bm25.clamp(0.0, 10.0),
             weight_dense: self.rrf.weight_dense.clamp(0.0, 10.0),
         };
+        let boost = |b: f32| b.clamp(0.0, 10.0);
+        let bm25_boosts = FieldBoosts {
+            name: boost(self.bm25_boosts.name),
+            path: boost(self.bm25_boosts.path),
+            docs: boost(self.bm25_boosts.docs),
+            signature: boost(self.bm25_boosts.signature),
+            body: boost(self.bm25_boosts.body),
+            prefix: boost(self.bm25_boosts.prefix),
+        };
         let mmr = self.mmr.map(|m| MmrConfig {
             lambda: m.lambda.clamp(0.0, 1.0),
             sim_metric: m.sim_metric,
@@ -702,12 +779,177 @@ impl RagUserConfig {
             bm25_timeout_ms,
             bm25_retry_backoff_ms,
             strict_bm25_by_default: self.strict_bm25_by_default,
+            bm25_boosts,
             rrf,
             mmr,
+            reranker: self.reranker.validated(),
+            tokenizer_cache_only: self.tokenizer_cache_only,
+            query_rewrite: self.query_rewrite.validated(),
+            graph_expansion: self.graph_expansion.validated(),
+        }
+    }
+}
+
+/// Reranking of the retrieved candidates before context assembly.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub enum RerankerUserConfig {
+    #[default]
+    None,
+    /// Local cross-encoder model (Hugging Face id), run on the CPU.
+    CrossEncoder {
+        #[serde(default = "default_cross_encoder_model")]
+        model_id: String,
+        #[serde(default = "default_cross_encoder_max_length")]
+        max_length: usize,
+    },
+    /// Chat model graded through OpenRouter, e.g. `openai/gpt-4o-mini`.
+    Llm { model: String },
+}
+
+impl RerankerUserConfig {
+    fn validated(self) -> Self {
+        match self {
+            RerankerUserConfig::CrossEncoder {
+                model_id,
+                max_length,
+            } => RerankerUserConfig::CrossEncoder {
+                model_id,
+                max_length: max_length.clamp(32, 512),
+            },
+            other => other,
+        }
+    }
+
+    /// Builds the configured reranker. The cross-encoder model is loaded, and downloaded if
+    /// needed, on the first rerank.
+    pub fn build(&self) -> Result<Option<Arc<dyn Reranker>>, color_eyre::eyre::Error> {
+        let reranker: Arc<dyn Reranker> = match self {
+            RerankerUserConfig::None => return Ok(None),
+            RerankerUserConfig::CrossEncoder {
+                model_id,
+                max_length,
+            } => Arc::new(CrossEncoderReranker::new(CrossEncoderConfig {
+                model_id: model_id.clone(),
+                max_length: *max_length,
+                ..CrossEncoderConfig::default()
+            })),
+            RerankerUserConfig::Llm { model } => {
+                let model = crate::llm::ModelId::from_str(model)?;
+                Arc::new(LlmReranker::<OpenRouter>::new(model))
+            }
+        };
+        Ok(Some(reranker))
+    }
+}
+
+/// Rewriting of chat messages into several sub-queries (identifiers, keywords, optionally a
+/// HyDE snippet) whose results are fused.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QueryRewriteUserConfig {
+    /// Off by default: each sub-query is a separate retrieval, so a message costs up to three
+    /// searches (four with HyDE) instead of one.
+    #[serde(default = "default_query_rewrite_enabled")]
+    pub enabled: bool,
+    /// Recent conversation turns searched for the identifiers a follow-up refers to.
+    #[serde(default = "default_query_rewrite_history_turns")]
+    pub history_turns: usize,
+    #[serde(default = "default_query_rewrite_max_identifiers")]
+    pub max_identifiers: usize,
+    /// Chat model writing a HyDE snippet through OpenRouter, e.g. `openai/gpt-4o-mini`.
+    /// Costs one request per message; disabled when unset.
+    #[serde(default)]
+    pub hyde_model: Option<String>,
+}
+
+impl Default for QueryRewriteUserConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_query_rewrite_enabled(),
+            history_turns: default_query_rewrite_history_turns(),
+            max_identifiers: default_query_rewrite_max_identifiers(),
+            hyde_model: None,
         }
     }
 }
 
+impl QueryRewriteUserConfig {
+    fn validated(self) -> Self {
+        Self {
+            history_turns: self.history_turns.min(20),
+            max_identifiers: self.max_identifiers.clamp(1, 32),
+            ..self
+        }
+    }
+
+    /// Builds the configured query rewriting, if enabled.
+    pub fn build(&self) -> Result<Option<QueryRewrite>, color_eyre::eyre::Error> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let hyde: Option<Arc<dyn HydeGenerator>> = match &self.hyde_model {
+            Some(model) => {
+                let model = crate::llm::ModelId::from_str(model)?;
+                Some(Arc::new(LlmHydeGenerator::<OpenRouter>::new(model)))
+            }
+            None => None,
+        };
+        Ok(Some(QueryRewrite {
+            history_turns: self.history_turns,
+            max_identifiers: self.max_identifiers,
+            hyde,
+        }))
+    }
+}
+
+/// Expansion of the best hits to the items around them (the type a method is implemented for,
+/// the trait it belongs to, its callees, ...). Runs a few local graph queries per search.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GraphExpansionUserConfig {
+    #[serde(default = "default_graph_expansion_enabled")]
+    pub enabled: bool,
+    /// Score factor applied per hop from the hit.
+    #[serde(default = "default_graph_expansion_decay")]
+    pub decay: f32,
+    /// Number of top hits expanded.
+    #[serde(default = "default_graph_expansion_max_seeds")]
+    pub max_seeds: usize,
+    /// Maximum number of related items added per expanded hit.
+    #[serde(default = "default_graph_expansion_per_seed_max")]
+    pub per_seed_max: usize,
+}
+
+impl Default for GraphExpansionUserConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_graph_expansion_enabled(),
+            decay: default_graph_expansion_decay(),
+            max_seeds: default_graph_expansion_max_seeds(),
+            per_seed_max: default_graph_expansion_per_seed_max(),
+        }
+    }
+}
+
+impl GraphExpansionUserConfig {
+    fn validated(self) -> Self {
+        Self {
+            decay: self.decay.clamp(0.0, 1.0),
+            max_seeds: self.max_seeds.min(50),
+            per_seed_max: self.per_seed_max.min(20),
+            ..self
+        }
+    }
+
+    /// Builds the configured graph expansion, with the default rules, if enabled.
+    pub fn build(&self) -> Option<GraphExpansion> {
+        self.enabled.then(|| GraphExpansion {
+            decay: self.decay,
+            max_seeds: self.max_seeds,
+            per_seed_max: self.per_seed_max,
+            ..GraphExpansion::default()
+        })
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum RetrievalStrategyUser {
     Dense,
@@ -876,6 +1118,46 @@ fn default_rag_per_part_max_tokens() -> usize {
     256
 }
 
+fn default_cross_encoder_model() -> String {
+    CrossEncoderConfig::default().model_id
+}
+
+fn default_cross_encoder_max_length() -> usize {
+    CrossEncoderConfig::default().max_length
+}
+
+fn default_tokenizer_cache_only() -> bool {
+    true
+}
+
+fn default_query_rewrite_enabled() -> bool {
+    false
+}
+
+fn default_query_rewrite_history_turns() -> usize {
+    QueryRewrite::default().history_turns
+}
+
+fn default_query_rewrite_max_identifiers() -> usize {
+    QueryRewrite::default().max_identifiers
+}
+
+fn default_graph_expansion_enabled() -> bool {
+    true
+}
+
+fn default_graph_expansion_decay() -> f32 {
+    GraphExpansion::default().decay
+}
+
+fn default_graph_expansion_max_seeds() -> usize {
+    GraphExpansion::default().max_seeds
+}
+
+fn default_graph_expansion_per_seed_max() -> usize {
+    GraphExpansion::default().per_seed_max
+}
+
 fn default_bm25_timeout_ms() -> u64 {
     250
 }
@@ -45,6 +45,7 @@ async fn make_app_with_proposals() -> (
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
@@ -119,6 +120,7 @@ async fn make_app_with_proposals_and_editor(
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
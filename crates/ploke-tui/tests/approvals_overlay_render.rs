@@ -136,6 +136,7 @@ async fn make_state_with_ids(
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
@@ -209,6 +210,7 @@ fn approvals_overlay_renders_empty_list() {
             indexing_state: RwLock::new(None),
             indexer_task: None,
             indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+            parsers: Default::default(),
             db,
             embedder,
             io_handle,
@@ -436,6 +438,7 @@ fn approvals_overlay_filters_and_orders_by_status_and_recency() {
             indexing_state: RwLock::new(None),
             indexer_task: None,
             indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+            parsers: Default::default(),
             db,
             embedder,
             io_handle,
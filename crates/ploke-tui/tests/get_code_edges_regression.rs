@@ -46,6 +46,7 @@ async fn code_item_edges_handles_trailing_module_separators() {
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(Mutex::new(None)),
+        parsers: Default::default(),
         db: db.clone(),
         embedder,
         io_handle: IoManagerHandle::new(),
@@ -164,6 +165,7 @@ async fn code_item_edges_returns_edges_for_ploke_db_primary_node() {
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(Mutex::new(None)),
+        parsers: Default::default(),
         db: db.clone(),
         embedder,
         io_handle: IoManagerHandle::new(),
@@ -280,6 +282,7 @@ async fn code_item_edges_returns_edges_for_database_struct_in_ploke_db() {
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(Mutex::new(None)),
+        parsers: Default::default(),
         db: db.clone(),
         embedder,
         io_handle: IoManagerHandle::new(),
@@ -123,6 +123,7 @@ async fn index_start_keeps_ui_responsive_in_app_loop() {
             indexing_state: RwLock::new(None),
             indexer_task: Some(indexer_task),
             indexing_control: Arc::new(Mutex::new(None)),
+            parsers: Default::default(),
             db,
             embedder,
             io_handle,
@@ -255,6 +256,7 @@ async fn indexing_completed_event_does_not_block_input_when_system_read_held() {
             indexing_state: RwLock::new(None),
             indexer_task: Some(indexer_task),
             indexing_control: Arc::new(Mutex::new(None)),
+            parsers: Default::default(),
             db,
             embedder,
             io_handle,
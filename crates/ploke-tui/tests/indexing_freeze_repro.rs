@@ -84,6 +84,7 @@ async fn index_start_fixture_nodes_does_not_freeze_ui() {
             indexing_state: RwLock::new(None),
             indexer_task: Some(indexer_task),
             indexing_control: Arc::new(Mutex::new(None)),
+            parsers: Default::default(),
             db,
             embedder,
             io_handle,
@@ -46,6 +46,7 @@ async fn index_start_does_not_block_state_manager() {
         indexing_state: RwLock::new(None),
         indexer_task: Some(indexer_task),
         indexing_control: Arc::new(Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
@@ -25,6 +25,7 @@ async fn crate_focus_assigns_absolute_root_from_db() {
         indexing_state: tokio::sync::RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db: db.clone(),
         embedder,
         io_handle,
@@ -90,6 +91,7 @@ async fn workspace_restore_assigns_loaded_workspace_membership_from_db() {
         indexing_state: tokio::sync::RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db: db.clone(),
         embedder,
         io_handle,
@@ -37,6 +37,7 @@ async fn build_state(profile: MessageVerbosityProfile) -> (Arc<AppState>, Arc<Ev
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
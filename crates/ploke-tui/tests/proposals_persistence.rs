@@ -46,6 +46,7 @@ async fn proposals_save_and_load_roundtrip() {
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
@@ -123,6 +124,7 @@ async fn proposals_load_missing_file_is_graceful() {
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
@@ -162,6 +164,7 @@ async fn proposals_load_corrupted_file_is_graceful() {
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(tokio::sync::Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
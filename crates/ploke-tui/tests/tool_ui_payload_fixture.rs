@@ -43,6 +43,7 @@ async fn ns_read_tool_payload_renders_with_fixture_verbosity() {
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(Mutex::new(None)),
+        parsers: Default::default(),
         db: db.clone(),
         embedder,
         io_handle: IoManagerHandle::new(),
@@ -40,6 +40,7 @@ async fn workspace_fixture_app_state() -> Arc<AppState> {
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle: IoManagerHandle::new(),
@@ -69,6 +69,7 @@ fn build_state() -> Arc<AppState> {
         indexing_state: RwLock::new(None),
         indexer_task: Some(Arc::new(indexer_task)),
         indexing_control: Arc::new(Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle,
@@ -78,6 +78,7 @@ fn build_state() -> Arc<AppState> {
         indexing_state: RwLock::new(None),
         indexer_task: None,
         indexing_control: Arc::new(Mutex::new(None)),
+        parsers: Default::default(),
         db,
         embedder,
         io_handle: IoManagerHandle::new(),
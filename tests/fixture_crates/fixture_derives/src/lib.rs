@@ -0,0 +1,5 @@
+//! Types deriving standard and third-party traits, next to a hand-written trait impl.
+
+pub mod model;
+
+pub use model::{Describe, Model, ModelError, ModelId};
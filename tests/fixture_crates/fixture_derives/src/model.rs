@@ -0,0 +1,29 @@
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+
+/// Identifies a [`Model`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ModelId(pub u64);
+
+/// `Builder` generates a `ModelBuilder` struct rather than a trait impl.
+#[derive(Clone, Builder)]
+pub struct Model<T: Clone> {
+    pub id: ModelId,
+    pub payload: T,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ModelError {
+    #[error("model {0:?} not found")]
+    NotFound(ModelId),
+}
+
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+impl Describe for ModelId {
+    fn describe(&self) -> String {
+        format!("model #{}", self.0)
+    }
+}
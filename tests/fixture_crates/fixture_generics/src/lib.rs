@@ -72,3 +72,26 @@ impl<T> GenericStruct<T> {
         Self { field }
     }
 }
+
+// Trait used as a bound by the items below
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+// Trait with a supertrait declared in a where clause
+pub trait DescribeTwice
+where
+    Self: Describe,
+{
+    fn describe_twice(&self) -> String {
+        format!("{}{}", self.describe(), self.describe())
+    }
+}
+
+// Function bound by crate-local traits, inline and in a where clause
+pub fn describe_all<D: Describe, E>(first: D, second: E) -> String
+where
+    E: DescribeTwice,
+{
+    first.describe() + &second.describe_twice()
+}
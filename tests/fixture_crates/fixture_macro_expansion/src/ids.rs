@@ -0,0 +1,11 @@
+//! Invokes a macro defined in the crate root, directly and from another macro's expansion.
+
+make_newtype!(pub(crate) SessionId(String));
+
+macro_rules! nested_newtype {
+    ($name:ident) => {
+        make_newtype!(pub $name(u32));
+    };
+}
+
+nested_newtype!(RetryCount);
@@ -0,0 +1,37 @@
+//! Items generated by crate-local `macro_rules!` macros invoked at item position.
+#![allow(dead_code)]
+
+macro_rules! make_newtype {
+    ($(#[$meta:meta])* $vis:vis $name:ident($inner:ty)) => {
+        $(#[$meta])*
+        $vis struct $name(pub $inner);
+
+        impl $name {
+            pub fn new(value: $inner) -> Self {
+                Self(value)
+            }
+        }
+    };
+}
+
+make_newtype!(pub UserId(u64));
+
+make_newtype!(
+    /// Identifies an order.
+    pub OrderId(u64)
+);
+
+macro_rules! make_consts {
+    ($($name:ident = $value:expr),* $(,)?) => {
+        $(pub const $name: u32 = $value;)*
+    };
+}
+
+make_consts!(ONE = 1, TWO = 1 + 1,);
+
+// Not defined in this crate, so it is left unexpanded.
+thread_local! {
+    static COUNTER: u32 = 0;
+}
+
+pub mod ids;
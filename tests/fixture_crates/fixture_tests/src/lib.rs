@@ -0,0 +1,25 @@
+//! Test functions and doc-tests, for test indexing.
+//!
+//! ```
+//! assert_eq!(fixture_tests::math::add(1, 2), 3);
+//! ```
+
+pub mod math;
+
+/// Returns the answer.
+///
+/// ```
+/// assert_eq!(fixture_tests::answer(), 42);
+/// ```
+///
+/// ```text
+/// Not a doc-test.
+/// ```
+pub fn answer() -> u32 {
+    42
+}
+
+#[test]
+fn answer_outside_test_module() {
+    assert_eq!(answer(), 42);
+}
@@ -0,0 +1,50 @@
+/// Adds two numbers.
+///
+/// ```ignore
+/// let three = add(1, 2);
+/// ```
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+pub fn double(a: u32) -> u32 {
+    add(a, a)
+}
+
+pub struct Counter {
+    pub count: u32,
+}
+
+impl Counter {
+    /// Increments the counter.
+    ///
+    /// ```
+    /// let mut counter = fixture_tests::math::Counter { count: 0 };
+    /// counter.increment();
+    /// # assert_eq!(counter.count, 1);
+    /// ```
+    pub fn increment(&mut self) {
+        self.count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn adds() {
+        assert_eq!(super::add(1, 2), 3);
+    }
+
+    #[test]
+    #[ignore]
+    fn doubles() {
+        assert_eq!(super::double(2), 4);
+    }
+
+    mod nested {
+        #[test]
+        fn adds_zero() {
+            assert_eq!(crate::math::add(0, 0), 0);
+        }
+    }
+}